@@ -0,0 +1,123 @@
+//! Login by importing gcloud's Application Default Credentials: the client
+//! and refresh token from `gcloud auth application-default login` replace a
+//! consent flow of our own. The file records no scopes, so the import only
+//! sticks once a refresh shows they cover what the config asks for.
+
+use crate::{
+  auth::{self, AuthStatus},
+  error::Error,
+  oauth_client, read_config_from_dir, refresh, refresh_and_persist, scopes, setup, write_config_to_dir, write_tokens_to_dir, AppConfig,
+  Tokens,
+};
+use serde::Deserialize;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+const ADC_FILE: &str = "application_default_credentials.json";
+
+#[derive(Deserialize)]
+struct AdcFile {
+  #[serde(rename = "type", default)]
+  kind: String,
+  #[serde(default)]
+  client_id: String,
+  #[serde(default)]
+  client_secret: String,
+  #[serde(default)]
+  refresh_token: String,
+}
+
+/// Where gcloud keeps the ADC file: `GOOGLE_APPLICATION_CREDENTIALS` when
+/// set, else `application_default_credentials.json` in the gcloud config
+/// dir (`CLOUDSDK_CONFIG`, `%APPDATA%\gcloud`, or `~/.config/gcloud`).
+pub fn locate() -> Option<PathBuf> {
+  if let Some(path) = std::env::var_os("GOOGLE_APPLICATION_CREDENTIALS").filter(|p| !p.is_empty()) {
+    return Some(PathBuf::from(path));
+  }
+  if let Some(dir) = std::env::var_os("CLOUDSDK_CONFIG").filter(|p| !p.is_empty()) {
+    return Some(PathBuf::from(dir).join(ADC_FILE));
+  }
+  #[cfg(windows)]
+  let base = std::env::var_os("APPDATA").map(|d| PathBuf::from(d).join("gcloud"));
+  #[cfg(not(windows))]
+  let base = std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config").join("gcloud"));
+  base.map(|d| d.join(ADC_FILE))
+}
+
+fn read_adc(path: &Path) -> Result<AdcFile, Error> {
+  let text = fs::read_to_string(path).map_err(|e| {
+    Error::Config(format!("Nie można odczytać {} ({}) — uruchom `gcloud auth application-default login`", path.display(), e))
+  })?;
+  let adc: AdcFile = serde_json::from_str(&text).map_err(|e| Error::Config(format!("Nieprawidłowy plik {}: {}", path.display(), e)))?;
+  if adc.kind != "authorized_user" {
+    return Err(Error::Config(format!(
+      "{} zawiera poświadczenia typu \"{}\", a potrzebne są poświadczenia użytkownika (authorized_user) — konto usługi nie ma dostępu do kanału YouTube",
+      path.display(),
+      adc.kind
+    )));
+  }
+  if adc.client_id.is_empty() || adc.client_secret.is_empty() || adc.refresh_token.is_empty() {
+    return Err(Error::Config(format!("{} nie zawiera client_id, client_secret i refresh_token", path.display())));
+  }
+  Ok(adc)
+}
+
+/// The files an import replaces, to put back when it's refused.
+struct Backup(Vec<(PathBuf, Option<Vec<u8>>)>);
+
+impl Backup {
+  fn take(dir: &Path) -> Self {
+    Backup(["oauth_config.json", "tokens.json"].iter().map(|f| dir.join(f)).map(|p| (p.clone(), fs::read(&p).ok())).collect())
+  }
+
+  fn restore(self) {
+    for (path, contents) in self.0 {
+      let _ = match contents {
+        Some(bytes) => fs::write(&path, bytes),
+        None => fs::remove_file(&path),
+      };
+    }
+  }
+}
+
+/// Imports the ADC file at `path` (or [`locate`]'s) into `dir`: the client
+/// goes into the config, the refresh token into tokens.json, and a refresh
+/// gets a working access token. When the granted scopes don't cover the
+/// configured ones (read-only YouTube by default), or the refresh fails, the
+/// previous config and tokens are put back.
+pub async fn import(dir: &Path, path: Option<&Path>) -> Result<AuthStatus, Error> {
+  let path = path.map(Path::to_path_buf).or_else(locate).ok_or_else(|| {
+    Error::Config("Nie znaleziono pliku ADC — ustaw GOOGLE_APPLICATION_CREDENTIALS albo podaj ścieżkę".into())
+  })?;
+  let adc = read_adc(&path)?;
+  let backup = Backup::take(dir);
+  let result = install(dir, &adc).await;
+  if result.is_err() {
+    backup.restore();
+    setup::notify(dir);
+  }
+  result
+}
+
+async fn install(dir: &Path, adc: &AdcFile) -> Result<AuthStatus, Error> {
+  let cfg = AppConfig { client_id: adc.client_id.clone(), client_secret: adc.client_secret.clone(), ..read_config_from_dir(dir).unwrap_or_default() };
+  write_config_to_dir(dir, &cfg)?;
+  let tokens = Tokens { refresh_token: adc.refresh_token.clone(), client_id: Some(adc.client_id.clone()), ..Tokens::default() };
+  write_tokens_to_dir(dir, &tokens)?;
+  refresh::reset(dir);
+  let t = refresh_and_persist(dir).await?;
+  let requested = scopes::configured(&cfg);
+  let missing = scopes::missing(&t, &requested.iter().map(String::as_str).collect::<Vec<_>>());
+  if !missing.is_empty() {
+    return Err(Error::Validation(format!(
+      "Poświadczenia ADC nie obejmują zakresów: {} — zaloguj się ponownie przez `gcloud auth application-default login --scopes={}`",
+      missing.join(", "),
+      requested.join(",")
+    )));
+  }
+  oauth_client::record_login(dir, &adc.client_id, None)?;
+  setup::notify(dir);
+  Ok(auth::status(dir))
+}
@@ -0,0 +1,633 @@
+use crate::{
+  connectivity,
+  cursor::{self, PageRequest},
+  error::Error,
+  forbidden::{self, ForbiddenKind},
+  http_cache, http_headers, notifications, now_secs,
+  observe::{self, Exchange},
+  publish_time::PublishTime,
+  quota, read_config_from_dir, read_tokens_from_dir, reconsent, refresh, runtime, scopes,
+  timeouts::{self, OperationClass},
+  tls, token_age,
+  video_links::VideoLink,
+  Tokens,
+};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::{
+  future::Future,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+pub(crate) fn api_base() -> String {
+  std::env::var("YOUTUBE_API_URL").unwrap_or_else(|_| "https://www.googleapis.com".to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Privacy {
+  Public,
+  Unlisted,
+  Private,
+}
+
+impl Privacy {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Privacy::Public => "public",
+      Privacy::Unlisted => "unlisted",
+      Privacy::Private => "private",
+    }
+  }
+}
+
+/// Content type of every JSON body, metadata parts included.
+const JSON_CONTENT_TYPE: &str = "application/json; charset=UTF-8";
+
+#[derive(Clone)]
+pub(crate) enum Body {
+  Json(serde_json::Value),
+  Bytes { content_type: String, data: Vec<u8> },
+}
+
+/// One Data API request. Every endpoint wrapper describes its call with this
+/// and hands it to [`execute`], so auth, scope checks and error mapping live
+/// in one place.
+#[derive(Clone)]
+pub(crate) struct ApiCall {
+  method: Method,
+  /// Absolute URL used instead of the API base, e.g. an upload session.
+  base: Option<String>,
+  path: String,
+  query: Vec<(String, String)>,
+  headers: Vec<(String, String)>,
+  body: Option<Body>,
+  scopes: &'static [&'static str],
+  cost: u32,
+  class: OperationClass,
+  publish_at: Option<PublishTime>,
+  video_links: Vec<VideoLink>,
+  /// Sent as `X-Client-Trace-Id`; a fresh one per call unless given.
+  trace_id: Option<String>,
+}
+
+impl ApiCall {
+  pub fn new(method: Method, path: &str) -> Self {
+    // Data API list calls cost 1 unit and most writes 50; endpoints that
+    // differ say so with `cost`.
+    let cost = if method == Method::GET { 1 } else { 50 };
+    let class = if path.starts_with("/upload/") { OperationClass::UploadChunk } else { OperationClass::Api };
+    ApiCall {
+      method,
+      base: None,
+      path: path.to_string(),
+      query: Vec::new(),
+      headers: Vec::new(),
+      body: None,
+      scopes: &[scopes::YOUTUBE_READONLY],
+      cost,
+      class,
+      publish_at: None,
+      video_links: Vec::new(),
+      trace_id: None,
+    }
+  }
+
+  /// A request to a resumable upload session URL: media bytes, no quota.
+  pub fn upload_session(method: Method, url: &str) -> Self {
+    ApiCall { base: Some(url.to_string()), cost: 0, class: OperationClass::UploadChunk, ..Self::new(method, "") }
+  }
+
+  /// A request to `path` on another googleapis host than the API base.
+  pub fn on_host(method: Method, base: &str, path: &str) -> Self {
+    ApiCall { base: Some(base.to_string()), ..Self::new(method, path) }
+  }
+
+  pub fn get(path: &str) -> Self { Self::new(Method::GET, path) }
+  pub fn post(path: &str) -> Self { Self::new(Method::POST, path) }
+  pub fn put(path: &str) -> Self { Self::new(Method::PUT, path) }
+  pub fn delete(path: &str) -> Self { Self::new(Method::DELETE, path) }
+
+  pub fn query(mut self, key: &str, value: impl Into<String>) -> Self {
+    self.query.push((key.to_string(), value.into()));
+    self
+  }
+
+  /// An extra request header. Values are ASCII only (a non-ASCII one is
+  /// refused before sending); titles and descriptions go in the JSON body.
+  pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+    self.headers.push((name.to_string(), value.into()));
+    self
+  }
+
+  pub fn json(mut self, body: serde_json::Value) -> Self {
+    self.body = Some(Body::Json(body));
+    self
+  }
+
+  /// Raw media body, as used by `uploadType=media` endpoints.
+  pub fn bytes(mut self, content_type: &str, data: Vec<u8>) -> Self {
+    self.body = Some(Body::Bytes { content_type: content_type.to_string(), data });
+    self
+  }
+
+  /// Quota units charged for this call.
+  pub fn cost(mut self, units: u32) -> Self {
+    self.cost = units;
+    self
+  }
+
+  /// The `publishAt` the body sets, shown in a dry-run plan.
+  pub fn publish_at(mut self, at: Option<PublishTime>) -> Self {
+    self.publish_at = at;
+    self
+  }
+
+  /// The video links checked in the description the body sets, shown in a
+  /// dry-run plan.
+  pub fn video_links(mut self, links: Vec<VideoLink>) -> Self {
+    self.video_links = links;
+    self
+  }
+
+  /// The trace id of the operation this call is part of.
+  pub fn trace_id(mut self, id: &str) -> Self {
+    self.trace_id = Some(id.to_string());
+    self
+  }
+
+  /// Scopes the stored tokens must cover before the request is sent.
+  pub fn scopes(mut self, scopes: &'static [&'static str]) -> Self {
+    self.scopes = scopes;
+    self
+  }
+}
+
+/// What a mutating call would have done, returned instead of sending it while
+/// dry-run mode is on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DryRunPlan {
+  pub method: String,
+  pub url: String,
+  pub body_summary: String,
+  pub estimated_quota: u32,
+  /// When the video would go public, for calls that set `publishAt`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub publish_at: Option<Box<PublishTime>>,
+  /// Links to other videos in the description and what was done with them.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub video_links: Vec<VideoLink>,
+}
+
+const BODY_SUMMARY_MAX: usize = 500;
+
+impl ApiCall {
+  fn is_mutating(&self) -> bool { self.method != Method::GET }
+
+  /// Identifies a GET for the response cache; the query is sorted so the
+  /// order the builder was called in doesn't matter.
+  fn cache_key(&self) -> String {
+    let mut query: Vec<String> = self.query.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    query.sort();
+    format!("{}?{}", self.path, query.join("&"))
+  }
+
+  fn endpoint(&self) -> String {
+    match &self.base {
+      Some(base) => format!("{}{}", base, self.path),
+      None => format!("{}{}", api_base(), self.path),
+    }
+  }
+
+  fn url(&self) -> String {
+    let mut url = self.endpoint();
+    if !self.query.is_empty() {
+      let query: Vec<String> = self.query.iter().map(|(k, v)| format!("{}={}", k, urlencoding::encode(v))).collect();
+      url = format!("{}?{}", url, query.join("&"));
+    }
+    url
+  }
+
+  fn plan(&self) -> DryRunPlan {
+    let url = self.url();
+    let body_summary = match &self.body {
+      Some(Body::Json(v)) => {
+        let s = v.to_string();
+        if s.chars().count() > BODY_SUMMARY_MAX { format!("{}…", s.chars().take(BODY_SUMMARY_MAX).collect::<String>()) } else { s }
+      }
+      Some(Body::Bytes { content_type, data }) => format!("<{} B, {}>", data.len(), content_type),
+      None => String::new(),
+    };
+    DryRunPlan {
+      method: self.method.to_string(),
+      url,
+      body_summary,
+      estimated_quota: self.cost,
+      publish_at: self.publish_at.clone().map(Box::new),
+      video_links: self.video_links.clone(),
+    }
+  }
+}
+
+const CONTENT_OWNER_SCOPES: &[&str] = &[scopes::YOUTUBE_PARTNER];
+
+/// `call` as a CMS partner sends it: with `onBehalfOfContentOwner` (and the
+/// owner's channel when configured) and the youtubepartner scope. `None` for
+/// reads, upload session URLs and configs without `content_owner_id`, whose
+/// requests go out exactly as built.
+fn on_behalf(dir: &Path, call: &ApiCall) -> Option<ApiCall> {
+  if !call.is_mutating() || call.base.is_some() {
+    return None;
+  }
+  let cfg = read_config_from_dir(dir)?;
+  let mut call = call.clone().query("onBehalfOfContentOwner", cfg.content_owner_id?).scopes(CONTENT_OWNER_SCOPES);
+  if let Some(channel) = cfg.content_owner_channel_id {
+    call = call.query("onBehalfOfContentOwnerChannel", channel);
+  }
+  Some(call)
+}
+
+/// Turns the API's content-owner reasons into what to fix in the config.
+pub(crate) fn content_owner_error(dir: &Path, err: Error) -> Error {
+  let Some(cfg) = read_config_from_dir(dir).filter(|c| c.content_owner_id.is_some()) else {
+    return err;
+  };
+  let owner = cfg.content_owner_id.unwrap_or_default();
+  let message = |reason: &str| match reason {
+    "contentOwnerAccountNotFound" => Some(format!(
+      "YouTube nie zna właściciela treści {} albo zalogowane konto nie ma do niego dostępu — sprawdź content_owner_id i zaloguj się kontem z CMS",
+      owner
+    )),
+    "channelNotFound" => Some(match &cfg.content_owner_channel_id {
+      Some(channel) => format!("Kanał {} nie jest powiązany z właścicielem treści {} — sprawdź content_owner_channel_id", channel, owner),
+      None => format!("Nie znaleziono kanału właściciela treści {} — ustaw content_owner_channel_id na kanał z CMS", owner),
+    }),
+    _ => None,
+  };
+  match err {
+    Error::Api { status, reason, message: original } => {
+      let message = message(&reason).unwrap_or(original);
+      Error::Api { status, reason, message }
+    }
+    Error::Forbidden { reason, message: original } => {
+      let message = message(&reason).unwrap_or(original);
+      Error::Forbidden { reason, message }
+    }
+    other => other,
+  }
+}
+
+/// Dry-run is on when `set_dry_run` said so, otherwise when the config says so.
+pub fn dry_run_enabled(dir: &Path) -> bool {
+  runtime::for_dir(dir)
+    .dry_run_override()
+    .unwrap_or_else(|| read_config_from_dir(dir).map(|c| c.dry_run).unwrap_or(false))
+}
+
+/// Read-only mode is on when `set_read_only_mode` said so, otherwise when
+/// the config says so.
+pub fn read_only_enabled(dir: &Path) -> bool {
+  runtime::for_dir(dir)
+    .read_only_override()
+    .unwrap_or_else(|| read_config_from_dir(dir).map(|c| c.read_only_mode).unwrap_or(false))
+}
+
+/// Refuses with `ReadOnlyMode` while the mode is on. Mutating entry points
+/// call it first so nothing, not even a read they'd do first, goes out.
+pub(crate) fn ensure_writable(dir: &Path) -> Result<(), Error> {
+  if read_only_enabled(dir) {
+    return Err(Error::ReadOnlyMode);
+  }
+  Ok(())
+}
+
+/// Builds a `multipart/related` body (JSON metadata part followed by the
+/// media part) as expected by `uploadType=multipart` endpoints. Returns the
+/// content type carrying the boundary, and the body.
+pub(crate) fn multipart_related(metadata: &serde_json::Value, media_type: &str, media: &[u8]) -> (String, Vec<u8>) {
+  let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+  let boundary = format!("ytlite-{:x}-{:x}", nanos, media.len());
+  let mut body = Vec::with_capacity(media.len() + 512);
+  body.extend_from_slice(format!("--{}\r\nContent-Type: {}\r\n\r\n", boundary, JSON_CONTENT_TYPE).as_bytes());
+  body.extend_from_slice(metadata.to_string().as_bytes());
+  body.extend_from_slice(format!("\r\n--{}\r\nContent-Type: {}\r\n\r\n", boundary, media_type).as_bytes());
+  body.extend_from_slice(media);
+  body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+  (format!("multipart/related; boundary={}", boundary), body)
+}
+
+/// Stored tokens, refreshed first when they are about to expire (60s buffer)
+/// or there is no access token (`persist_access_token: false` after a
+/// restart). A failed refresh falls through to the old token while it is
+/// still valid; once it has expired the refresh error is returned instead.
+pub(crate) async fn fresh_tokens(dir: &Path) -> Result<Tokens, Error> {
+  let t = read_tokens_from_dir(dir).ok_or_else(|| Error::Auth("Brak tokenów — zaloguj się".into()))?;
+  token_age::check(dir, &t);
+  reconsent::check(dir, &t);
+  if t.access_token.is_empty() {
+    return refresh::refresh(dir).await;
+  }
+  let age = now_secs().saturating_sub(t.created_at);
+  if t.expires_in > 0 && age + 60 > t.expires_in {
+    match refresh::refresh(dir).await {
+      Ok(refreshed) => return Ok(refreshed),
+      Err(e) if age >= t.expires_in => return Err(e),
+      Err(_) => {}
+    }
+  }
+  Ok(t)
+}
+
+pub(crate) async fn execute(dir: &Path, call: ApiCall) -> Result<serde_json::Value, Error> {
+  let owned = on_behalf(dir, &call);
+  let for_owner = owned.is_some();
+  let call = owned.unwrap_or(call);
+  let cache_key = (!call.is_mutating()).then(|| call.cache_key());
+  let cached = cache_key.as_deref().and_then(|k| http_cache::get(dir, k));
+  // Offline, reads fall back to their last response and writes fail fast
+  // instead of each waiting out a network timeout.
+  if !connectivity::online(dir).await {
+    return cached.map(http_cache::tagged).ok_or(Error::Offline);
+  }
+  let t = prepare(dir, &call).await?;
+  let if_none_match = cached.as_ref().and_then(|c| c.etag.as_deref());
+  let mut attempt = 0;
+  let Reply { status, headers, text } = loop {
+    let reply = match transmit(dir, &call, t.clone(), if_none_match).await {
+      Ok(reply) => reply,
+      Err(err) => {
+        if matches!(err, Error::Network(_)) {
+          connectivity::record(dir, false);
+          if let Some(c) = cached {
+            return Ok(http_cache::tagged(c));
+          }
+        }
+        return Err(err);
+      }
+    };
+    // A rate-limited call was not carried out, so replaying a write is safe.
+    let retry_after = reply.headers.get(reqwest::header::RETRY_AFTER).and_then(|v| v.to_str().ok());
+    match forbidden::retry_delay(reply.status.as_u16(), retry_after, &reply.text, attempt) {
+      Some(wait) => {
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+      }
+      None => break reply,
+    }
+  };
+  if status == reqwest::StatusCode::NOT_MODIFIED {
+    if let (Some(key), Some(c)) = (&cache_key, cached) {
+      return Ok(http_cache::touch(dir, key, c).body);
+    }
+  }
+  if !status.is_success() {
+    let err = error_from_response(status.as_u16(), &text, call.scopes, call.cost);
+    let err = forbidden::record(dir, err);
+    return Err(if for_owner { content_owner_error(dir, err) } else { err });
+  }
+  if text.trim().is_empty() {
+    return Ok(serde_json::Value::Null);
+  }
+  let body: serde_json::Value =
+    serde_json::from_str(&text).map_err(|e| Error::Api { status: status.as_u16(), reason: "invalidJson".into(), message: e.to_string() })?;
+  if let Some(key) = &cache_key {
+    let etag = headers.get(reqwest::header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string);
+    // Best effort: a full disk shouldn't fail a successful read.
+    let _ = http_cache::put(dir, key, http_cache::Entry { etag, fetched_at: now_secs(), body: body.clone() });
+  }
+  Ok(body)
+}
+
+/// Sends `call` with token, scope, dry-run and quota handling and returns the
+/// reply whatever its status. For protocols where non-2xx replies carry
+/// meaning (resumable uploads); JSON endpoints use [`execute`].
+pub(crate) async fn send(dir: &Path, call: &ApiCall) -> Result<Reply, Error> {
+  let owned = on_behalf(dir, call);
+  let call = owned.as_ref().unwrap_or(call);
+  let t = prepare(dir, call).await?;
+  transmit(dir, call, t, None).await
+}
+
+/// Everything before the request goes out: read-only mode, tokens, scopes,
+/// dry-run, quota.
+async fn prepare(dir: &Path, call: &ApiCall) -> Result<Tokens, Error> {
+  // HTTP sends header bytes as they are and servers read them as Latin-1,
+  // so a Polish title in one would arrive garbled.
+  if let Some((name, _)) = call.headers.iter().find(|(_, v)| !v.is_ascii()) {
+    return Err(Error::Validation(format!("Nagłówek {} może zawierać tylko znaki ASCII", name)));
+  }
+  if call.is_mutating() {
+    ensure_writable(dir)?;
+  }
+  let t = fresh_tokens(dir).await?;
+  scopes::require(&t, call.scopes).map_err(|e| reconsent::annotate(dir, e))?;
+  if call.is_mutating() && dry_run_enabled(dir) {
+    let plan = call.plan();
+    runtime::for_dir(dir).emit("dry-run-plan", &plan);
+    return Err(Error::DryRun(plan));
+  }
+  quota::charge(dir, call.cost)?;
+  Ok(t)
+}
+
+/// The request itself, observed and retried once on 401. Errors here are
+/// transport failures or a failed forced refresh.
+async fn transmit(dir: &Path, call: &ApiCall, t: Tokens, if_none_match: Option<&str>) -> Result<Reply, Error> {
+  let class = call.class;
+  let timeout = timeouts::for_class(dir, class);
+  let op = format!("{} {}", call.method, if call.path.is_empty() { "upload session" } else { &call.path });
+  let (method, url) = (call.method.to_string(), call.url());
+  let bytes_sent = match &call.body {
+    Some(Body::Bytes { data, .. }) => data.len() as u64,
+    Some(Body::Json(body)) => body.to_string().len() as u64,
+    None => 0,
+  };
+  // The replay after a 401 is the same operation.
+  let trace_id = call.trace_id.clone().unwrap_or_else(http_headers::trace_id);
+  with_auth_retry(dir, t, |access_token, attempt| {
+    let (method, url, op, trace_id) = (&method, &url, &op, &trace_id);
+    async move {
+      let started = Instant::now();
+      let observe = |status: Option<u16>, body: &str, error: Option<&Error>| {
+        observe::exchange(dir, Exchange { class, method, url, started, status, body, error, bytes_sent, attempt, trace_id })
+      };
+      #[cfg(feature = "chaos")]
+      if let Some(injected) = crate::chaos::intercept(dir, url, op, timeout).await {
+        let (status, headers, text) = injected.inspect_err(|err| observe(None, "", Some(err)))?;
+        observe(Some(status.as_u16()), &text, None);
+        return Ok(Reply { status, headers, text });
+      }
+      let req = tls::request(dir, call.method.clone(), &call.endpoint(), trace_id)?;
+      let mut req = req.query(&call.query).bearer_auth(access_token).timeout(timeout);
+      if let Some(etag) = if_none_match {
+        req = req.header(reqwest::header::IF_NONE_MATCH, etag);
+      }
+      for (name, value) in &call.headers {
+        req = req.header(name, value);
+      }
+      match &call.body {
+        // Said explicitly rather than left to the JSON default, so no proxy
+        // or server guesses another charset for the title and description.
+        Some(Body::Json(body)) => req = req.header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE).body(body.to_string()),
+        Some(Body::Bytes { content_type, data }) => req = req.header(reqwest::header::CONTENT_TYPE, content_type).body(data.clone()),
+        None => {}
+      }
+      let resp = req.send().await.map_err(|e| {
+        let err = timeouts::transport_error(e, op, timeout);
+        observe(None, "", Some(&err));
+        err
+      })?;
+      let status = resp.status();
+      let headers = resp.headers().clone();
+      let text = resp.text().await.map_err(|e| {
+        let err = timeouts::transport_error(e, op, timeout);
+        observe(Some(status.as_u16()), "", Some(&err));
+        err
+      })?;
+      observe(Some(status.as_u16()), &text, None);
+      Ok(Reply { status, headers, text })
+    }
+  })
+  .await
+}
+
+/// A response read in full, as [`send`] hands it back.
+pub(crate) struct Reply {
+  pub status: reqwest::StatusCode,
+  pub headers: reqwest::header::HeaderMap,
+  pub text: String,
+}
+
+/// Sends per request: the original and one replay after a forced refresh.
+const MAX_AUTH_ATTEMPTS: u32 = 2;
+
+/// Runs `send` with the access token and, when the API answers 401 although
+/// the token looked valid locally (revoked, session invalidated), forces one
+/// refresh and replays with the new token. A second 401 means the grant is
+/// gone: `Auth` (REAUTH_REQUIRED) plus a `reauth-required` event. Every
+/// bearer-authenticated request goes through here.
+pub(crate) async fn with_auth_retry<F, Fut>(dir: &Path, tokens: Tokens, mut send: F) -> Result<Reply, Error>
+where
+  F: FnMut(String, u32) -> Fut,
+  Fut: Future<Output = Result<Reply, Error>>,
+{
+  let mut access_token = tokens.access_token;
+  let mut attempt = 1;
+  loop {
+    let reply = send(access_token, attempt).await?;
+    if reply.status != reqwest::StatusCode::UNAUTHORIZED {
+      return Ok(reply);
+    }
+    if attempt >= MAX_AUTH_ATTEMPTS {
+      let err = Error::Auth("YouTube odrzuca token także po odświeżeniu — zaloguj się ponownie".into());
+      runtime::for_dir(dir).emit("reauth-required", err.to_string());
+      notifications::reauth_required(dir);
+      return Err(err);
+    }
+    access_token = refresh::refresh(dir).await?.access_token;
+    attempt += 1;
+  }
+}
+
+/// Follows `nextPageToken` until `limit` items were collected or the listing
+/// ends, asking each page for no more than is still needed. The whole walk
+/// fails with `TimedOut` once `deadline` passes.
+pub(crate) async fn paginate(dir: &Path, call: ApiCall, limit: usize, deadline: Duration) -> Result<Vec<serde_json::Value>, Error> {
+  paginate_with(dir, call, limit, deadline, |_, _| {}).await
+}
+
+/// [`paginate`] reporting after each page the items fetched so far and the
+/// total from `pageInfo`, when the API gives one.
+pub(crate) async fn paginate_with(
+  dir: &Path,
+  call: ApiCall,
+  limit: usize,
+  deadline: Duration,
+  on_page: impl FnMut(usize, Option<u64>) + Send,
+) -> Result<Vec<serde_json::Value>, Error> {
+  Ok(paginate_page(dir, call, &PageRequest::default(), limit, deadline, on_page).await?.items)
+}
+
+/// One stretch of a listing walked by [`paginate_page`].
+pub(crate) struct Page {
+  pub items: Vec<serde_json::Value>,
+  /// Where the next call continues; `None` once the listing ended.
+  pub next_cursor: Option<String>,
+  pub total: Option<u64>,
+}
+
+/// [`paginate_with`] from `paging.cursor` for at most `paging.page_budget`
+/// pages. `limit` and the reported counts cover the whole listing, not just
+/// this stretch. A cursor only continues the listing it was made for.
+pub(crate) async fn paginate_page(
+  dir: &Path,
+  call: ApiCall,
+  paging: &PageRequest,
+  limit: usize,
+  deadline: Duration,
+  mut on_page: impl FnMut(usize, Option<u64>) + Send,
+) -> Result<Page, Error> {
+  let listing = call.cache_key();
+  let start = paging.cursor.as_deref().map(|c| cursor::decode(dir, &listing, c)).transpose()?;
+  let op = format!("paginate {}", call.path);
+  let budget = paging.page_budget.map(|b| b.max(1));
+  let (collected, page_token) = start.unzip();
+  let walk = paginate_inner(dir, call, limit, collected.unwrap_or(0), page_token, budget, &mut on_page);
+  let (items, total, next) = timeouts::with_deadline(&op, deadline, walk).await?;
+  let next_cursor = next.map(|(collected, token)| cursor::encode(dir, &listing, collected, &token));
+  Ok(Page { items, next_cursor, total })
+}
+
+/// The pages of one stretch; returns the items, the last `totalResults`, and
+/// the count and token to continue from when the budget ran out first.
+async fn paginate_inner(
+  dir: &Path,
+  mut call: ApiCall,
+  limit: usize,
+  already: usize,
+  mut page_token: Option<String>,
+  budget: Option<u32>,
+  on_page: &mut (dyn FnMut(usize, Option<u64>) + Send),
+) -> Result<(Vec<serde_json::Value>, Option<u64>, Option<(usize, String)>), Error> {
+  // Every page of the listing carries the same trace id.
+  call.trace_id.get_or_insert_with(http_headers::trace_id);
+  let mut items = Vec::new();
+  let mut total = None;
+  let mut pages = 0;
+  while already + items.len() < limit {
+    let collected = already + items.len();
+    if budget.is_some_and(|b| pages >= b) {
+      return Ok((items, total, page_token.map(|t| (collected, t))));
+    }
+    let per_page = (limit - collected).min(50);
+    let mut page = call.clone().query("maxResults", per_page.to_string());
+    if let Some(token) = &page_token {
+      page = page.query("pageToken", token.clone());
+    }
+    let resp = execute(dir, page).await?;
+    pages += 1;
+    if let Some(batch) = resp["items"].as_array() {
+      items.extend(batch.iter().take(limit - collected).cloned());
+    }
+    total = resp["pageInfo"]["totalResults"].as_u64().or(total);
+    on_page(already + items.len(), total);
+    match resp["nextPageToken"].as_str() {
+      Some(token) if !token.is_empty() => page_token = Some(token.to_string()),
+      _ => break,
+    }
+  }
+  Ok((items, total, None))
+}
+
+/// Maps a Google error body (`{"error": {"message", "errors": [{"reason"}]}}`)
+/// to a typed error, replacing opaque reasons with guidance where we have it.
+/// 403s are told apart by reason (see [`forbidden`]); `cost` is what the
+/// refused call was charged.
+pub(crate) fn error_from_response(status: u16, body: &str, required: &[&str], cost: u32) -> Error {
+  let refusal = forbidden::parse(body);
+  if status == 403 || refusal.kind == ForbiddenKind::Scope {
+    return refusal.into_error(status, required, cost);
+  }
+  Error::Api { status, reason: refusal.reason, message: refusal.message }
+}
@@ -0,0 +1,343 @@
+//! A local copy of the channel's own videos, kept before they are deleted
+//! from YouTube: the full `videos.list` resource, every thumbnail size and
+//! the caption tracks, one folder per video, all listed in `index.json`.
+//! The index is written after each video and a video whose metadata hasn't
+//! changed since is skipped (its caption downloads cost 200 units each), so
+//! an interrupted [`archive_channel`] continues where it stopped when run
+//! again on the same directory.
+
+use crate::{
+  api::{self, ApiCall, Privacy},
+  channel,
+  error::Error,
+  events, forbidden, history, http_headers, now_secs, read_tokens_from_dir, scopes, snapshots, storage,
+  timeouts::{self, OperationClass},
+  tls, videos,
+};
+use chrono::DateTime;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+pub const INDEX_FILE: &str = "index.json";
+pub const METADATA_FILE: &str = "metadata.json";
+/// Every part a channel owner can read.
+const PARTS: &str = "snippet,status,contentDetails,statistics,localizations,recordingDetails,topicDetails";
+const READ_SCOPES: &[&str] = &[scopes::YOUTUBE_READONLY];
+/// `captions.list` and `captions.download` accept nothing narrower.
+pub(crate) const CAPTION_SCOPES: &[&str] = &[scopes::YOUTUBE_FORCE_SSL];
+const CAPTIONS_LIST_COST: u32 = 50;
+const CAPTIONS_DOWNLOAD_COST: u32 = 200;
+/// `videos.list` takes at most this many ids per call.
+const BATCH: usize = 50;
+/// Overall bound on walking the uploads playlist.
+const LIST_DEADLINE: Duration = Duration::from_secs(30 * 60);
+/// One archive runs at a time, so its progress needs no finer key.
+const PROGRESS_KEY: &str = "archive";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedCaption {
+  pub track_id: String,
+  pub language: String,
+  /// The track's name; empty for most.
+  pub name: String,
+  /// SubRip file, relative to the video's folder; absent when refused.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub file: Option<String>,
+  /// Why YouTube refused the download (e.g. a track the channel doesn't own).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+/// One entry of `index.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedVideo {
+  pub video_id: String,
+  pub title: String,
+  pub published_at: Option<String>,
+  pub privacy: Option<String>,
+  /// SHA-256 of the resource without `statistics` and `etag`, which change
+  /// on their own; the same checksum means there is nothing new to keep.
+  pub checksum: String,
+  pub archived_at: u64,
+  /// Files by thumbnail size (`default`, `high`, `maxres`…), relative to the
+  /// video's folder.
+  pub thumbnails: BTreeMap<String, String>,
+  pub captions: Vec<ArchivedCaption>,
+  /// Why no captions were fetched at all, e.g. a missing scope.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub captions_skipped: Option<String>,
+  /// Thumbnails that couldn't be downloaded.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub problems: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ArchiveIndex {
+  pub videos: BTreeMap<String, ArchivedVideo>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VideoArchive {
+  /// Already archived with the same metadata; nothing was downloaded.
+  pub skipped: bool,
+  /// The video's folder.
+  pub path: String,
+  #[serde(flatten)]
+  pub video: ArchivedVideo,
+}
+
+/// Which uploads [`archive_channel`] keeps; empty means all of them.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ArchiveFilter {
+  /// Published at or after: RFC3339 timestamp or `YYYY-MM-DD` (UTC).
+  #[serde(default)]
+  pub from: Option<String>,
+  /// Published at or before: RFC3339 timestamp or `YYYY-MM-DD` (end of that day, UTC).
+  #[serde(default)]
+  pub to: Option<String>,
+  #[serde(default)]
+  pub privacy: Option<Privacy>,
+  /// Case-insensitive substring of the title.
+  #[serde(default)]
+  pub text: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ChannelArchive {
+  pub index_path: String,
+  /// Uploads the filter matched.
+  pub matched: usize,
+  pub archived: usize,
+  /// Matched uploads already archived with the same metadata.
+  pub skipped: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchivePhase {
+  Listing,
+  Archiving,
+}
+
+/// Payload of `archive-progress`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ArchiveProgress {
+  pub phase: ArchivePhase,
+  pub done: usize,
+  pub total: Option<u64>,
+}
+
+/// The index of the archive in `out`; empty when there is none yet.
+pub fn read_index(out: &Path) -> Result<ArchiveIndex, Error> {
+  let path = out.join(INDEX_FILE);
+  match fs::read_to_string(&path) {
+    Ok(s) => serde_json::from_str(&s).map_err(|e| Error::Storage(format!("Nieczytelny indeks archiwum {}: {}", path.display(), e))),
+    Err(_) => Ok(ArchiveIndex::default()),
+  }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), Error> {
+  let json = serde_json::to_string_pretty(value).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_atomic(path, json).map_err(|e| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e)))
+}
+
+fn checksum(resource: &Value) -> String {
+  let mut stable = resource.clone();
+  if let Some(fields) = stable.as_object_mut() {
+    fields.remove("statistics");
+    fields.remove("etag");
+  }
+  Sha256::digest(stable.to_string()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Track ids and language tags as file name parts.
+fn file_part(s: &str) -> String {
+  s.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect()
+}
+
+async fn download(dir: &Path, url: &str) -> Result<Vec<u8>, Error> {
+  let timeout = timeouts::for_class(dir, OperationClass::Api);
+  let op = "thumbnail download";
+  let req = tls::request(dir, Method::GET, url, &http_headers::trace_id())?.timeout(timeout);
+  let resp = req.send().await.map_err(|e| timeouts::transport_error(e, op, timeout))?;
+  let status = resp.status().as_u16();
+  if !resp.status().is_success() {
+    return Err(Error::Api { status, reason: "thumbnailUnavailable".into(), message: url.to_string() });
+  }
+  Ok(resp.bytes().await.map_err(|e| timeouts::transport_error(e, op, timeout))?.to_vec())
+}
+
+/// Every size the snippet lists, into `thumbnails/`.
+async fn archive_thumbnails(dir: &Path, folder: &Path, snippet: &Value) -> Result<(BTreeMap<String, String>, Vec<String>), Error> {
+  let (mut saved, mut problems) = (BTreeMap::new(), Vec::new());
+  let Some(sizes) = snippet["thumbnails"].as_object() else {
+    return Ok((saved, problems));
+  };
+  fs::create_dir_all(folder.join("thumbnails")).map_err(|e| Error::Storage(e.to_string()))?;
+  for (size, thumb) in sizes {
+    let Some(url) = thumb["url"].as_str() else { continue };
+    let ext = url::Url::parse(url)
+      .ok()
+      .and_then(|u| Path::new(u.path()).extension().and_then(|e| e.to_str()).map(file_part))
+      .unwrap_or_else(|| "jpg".into());
+    let file = format!("thumbnails/{}.{}", file_part(size), ext);
+    match download(dir, url).await {
+      Ok(bytes) => {
+        fs::write(folder.join(&file), bytes).map_err(|e| Error::Storage(e.to_string()))?;
+        saved.insert(size.clone(), file);
+      }
+      Err(e) => problems.push(format!("Miniatura {}: {}", size, e)),
+    }
+  }
+  Ok((saved, problems))
+}
+
+/// The caption tracks as SubRip into `captions/`. Without the scope they are
+/// skipped rather than failing the archive; a track YouTube won't hand out
+/// keeps its error. An empty quota fails it, so the video is redone later.
+async fn archive_captions(dir: &Path, folder: &Path, video_id: &str) -> Result<(Vec<ArchivedCaption>, Option<String>), Error> {
+  let missing = read_tokens_from_dir(dir).map(|t| scopes::missing(&t, CAPTION_SCOPES)).unwrap_or_default();
+  if !missing.is_empty() {
+    return Ok((Vec::new(), Some(format!("Napisy pominięte — brak uprawnienia {}", missing.join(" ")))));
+  }
+  let call = ApiCall::get("/youtube/v3/captions").query("part", "snippet").query("videoId", video_id).cost(CAPTIONS_LIST_COST).scopes(CAPTION_SCOPES);
+  let tracks = match api::execute(dir, call).await {
+    Ok(resp) => resp["items"].as_array().cloned().unwrap_or_default(),
+    Err(Error::Forbidden { message, .. }) => return Ok((Vec::new(), Some(message))),
+    Err(e) => return Err(e),
+  };
+  let mut captions = Vec::new();
+  for track in tracks {
+    let Some(track_id) = track["id"].as_str() else { continue };
+    let language = track["snippet"]["language"].as_str().unwrap_or_default().to_string();
+    let mut caption =
+      ArchivedCaption { track_id: track_id.to_string(), language, name: track["snippet"]["name"].as_str().unwrap_or_default().to_string(), file: None, error: None };
+    let call = ApiCall::get(&format!("/youtube/v3/captions/{}", urlencoding::encode(track_id)))
+      .query("tfmt", "srt")
+      .cost(CAPTIONS_DOWNLOAD_COST)
+      .scopes(CAPTION_SCOPES);
+    let reply = api::send(dir, &call).await?;
+    if reply.status.is_success() {
+      fs::create_dir_all(folder.join("captions")).map_err(|e| Error::Storage(e.to_string()))?;
+      let file = format!("captions/{}_{}.srt", file_part(&caption.language), file_part(track_id));
+      fs::write(folder.join(&file), reply.text).map_err(|e| Error::Storage(e.to_string()))?;
+      caption.file = Some(file);
+    } else {
+      match forbidden::record(dir, api::error_from_response(reply.status.as_u16(), &reply.text, CAPTION_SCOPES, CAPTIONS_DOWNLOAD_COST)) {
+        Error::Forbidden { message, .. } => caption.error = Some(message),
+        e => return Err(e),
+      }
+    }
+    captions.push(caption);
+  }
+  Ok((captions, None))
+}
+
+/// Archives `resource` into `out/<video_id>/` unless the index already has
+/// it with the same checksum, then records it in the index.
+async fn archive_resource(dir: &Path, out: &Path, resource: &Value, index: &mut ArchiveIndex) -> Result<VideoArchive, Error> {
+  let video_id = resource["id"].as_str().unwrap_or_default();
+  snapshots::check_video_id(video_id)?;
+  let folder = out.join(video_id);
+  let path = folder.to_string_lossy().into_owned();
+  let checksum = checksum(resource);
+  if let Some(done) = index.videos.get(video_id).filter(|v| v.checksum == checksum && folder.join(METADATA_FILE).is_file()) {
+    return Ok(VideoArchive { skipped: true, path, video: done.clone() });
+  }
+  fs::create_dir_all(&folder).map_err(|e| Error::Storage(format!("Nie można utworzyć {}: {}", folder.display(), e)))?;
+  write_json(&folder.join(METADATA_FILE), resource)?;
+  let snippet = &resource["snippet"];
+  let (thumbnails, problems) = archive_thumbnails(dir, &folder, snippet).await?;
+  let (captions, captions_skipped) = archive_captions(dir, &folder, video_id).await?;
+  let text = |v: &Value| v.as_str().map(str::to_string);
+  let video = ArchivedVideo {
+    video_id: video_id.to_string(),
+    title: text(&snippet["title"]).unwrap_or_default(),
+    published_at: text(&snippet["publishedAt"]),
+    privacy: text(&resource["status"]["privacyStatus"]),
+    checksum,
+    archived_at: now_secs(),
+    thumbnails,
+    captions,
+    captions_skipped,
+    problems,
+  };
+  index.videos.insert(video.video_id.clone(), video.clone());
+  write_json(&out.join(INDEX_FILE), index)?;
+  Ok(VideoArchive { skipped: false, path, video })
+}
+
+/// Archives one of the channel's videos into `out/<video_id>/`.
+pub async fn archive_video(dir: &Path, video_id: &str, out: &Path) -> Result<VideoArchive, Error> {
+  snapshots::check_video_id(video_id)?;
+  let mut index = read_index(out)?;
+  let found = videos::fetch_as(dir, &[video_id.to_string()], PARTS, READ_SCOPES).await?;
+  let resource = found.get(video_id).ok_or_else(|| Error::Validation(format!("Nie znaleziono wideo {}", video_id)))?;
+  archive_resource(dir, out, resource, &mut index).await
+}
+
+/// Whether a `playlistItems` entry of the uploads playlist passes `filter`.
+fn matches(item: &Value, from: Option<u64>, to: Option<u64>, privacy: Option<Privacy>, text: Option<&str>) -> bool {
+  let published = item["contentDetails"]["videoPublishedAt"]
+    .as_str()
+    .or_else(|| item["snippet"]["publishedAt"].as_str())
+    .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+    .map(|at| at.timestamp().max(0) as u64);
+  let in_range = |bound: Option<u64>, ok: fn(u64, u64) -> bool| bound.is_none_or(|b| published.is_some_and(|p| ok(p, b)));
+  in_range(from, |p, b| p >= b)
+    && in_range(to, |p, b| p <= b)
+    && privacy.is_none_or(|p| item["status"]["privacyStatus"].as_str() == Some(p.as_str()))
+    && text.is_none_or(|t| item["snippet"]["title"].as_str().unwrap_or_default().to_lowercase().contains(t))
+}
+
+/// Archives every upload `filter` matches into `out`, one folder each,
+/// reporting `archive-progress` while listing and after each video.
+pub async fn archive_channel(dir: &Path, out: &Path, filter: &ArchiveFilter) -> Result<ChannelArchive, Error> {
+  let from = filter.from.as_deref().map(|v| history::parse_bound(v, false)).transpose()?;
+  let to = filter.to.as_deref().map(|v| history::parse_bound(v, true)).transpose()?;
+  let text = filter.text.as_deref().map(str::to_lowercase).filter(|t| !t.is_empty());
+  let mut index = read_index(out)?;
+  let channel = channel::list_channels(dir).await?.into_iter().next().ok_or_else(|| Error::Validation("Brak kanału".into()))?;
+  let uploads = channel.uploads_playlist_id.ok_or_else(|| Error::Validation("Kanał nie ma playlisty przesłanych filmów".into()))?;
+
+  let call = ApiCall::get("/youtube/v3/playlistItems").query("part", "snippet,contentDetails,status").query("playlistId", uploads);
+  let on_page =
+    |done, total| events::progress(dir, PROGRESS_KEY, "archive-progress", ArchiveProgress { phase: ArchivePhase::Listing, done, total });
+  let listed = api::paginate_with(dir, call, usize::MAX, LIST_DEADLINE, on_page).await?;
+  let mut seen = HashSet::new();
+  let ids: Vec<String> = listed
+    .iter()
+    .filter(|i| matches(i, from, to, filter.privacy, text.as_deref()))
+    .filter_map(|i| i["contentDetails"]["videoId"].as_str())
+    .filter(|id| seen.insert(id.to_string()))
+    .map(str::to_string)
+    .collect();
+
+  let (mut archived, mut skipped) = (0, 0);
+  for batch in ids.chunks(BATCH) {
+    let found = videos::fetch_as(dir, batch, PARTS, READ_SCOPES).await?;
+    // Ids the API no longer knows (deleted since the listing) drop out.
+    for resource in batch.iter().filter_map(|id| found.get(id)) {
+      if archive_resource(dir, out, resource, &mut index).await?.skipped {
+        skipped += 1;
+      } else {
+        archived += 1;
+      }
+      let progress = ArchiveProgress { phase: ArchivePhase::Archiving, done: archived + skipped, total: Some(ids.len() as u64) };
+      events::progress(dir, PROGRESS_KEY, "archive-progress", progress);
+    }
+  }
+  // Also when nothing new was archived, so the index is always there.
+  let index_path: PathBuf = out.join(INDEX_FILE);
+  fs::create_dir_all(out).map_err(|e| Error::Storage(format!("Nie można utworzyć {}: {}", out.display(), e)))?;
+  write_json(&index_path, &index)?;
+  Ok(ChannelArchive { index_path: index_path.to_string_lossy().into_owned(), matched: ids.len(), archived, skipped })
+}
@@ -0,0 +1,41 @@
+//! Append-only record of actions that hand credentials out of the app, and
+//! of uploads forced past warnings, in `audit.jsonl` under the config dir.
+//! Entries describe what was done, never the secret itself.
+
+use crate::{error::Error, now_secs, profiles};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+  fs::{self, OpenOptions},
+  io::Write,
+  path::Path,
+  sync::Mutex,
+};
+
+const AUDIT_FILE: &str = "audit.jsonl";
+
+static AUDIT_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+  /// Unix seconds.
+  pub timestamp: u64,
+  pub profile: String,
+  pub action: String,
+  #[serde(default)]
+  pub details: Value,
+}
+
+pub fn record(dir: &Path, action: &str, details: Value) -> Result<(), Error> {
+  let entry = AuditEntry { timestamp: now_secs(), profile: profiles::name_of(dir), action: action.to_string(), details };
+  let mut line = serde_json::to_string(&entry).map_err(|e| Error::Storage(e.to_string()))?;
+  line.push('\n');
+  let _guard = AUDIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut f = OpenOptions::new().create(true).append(true).open(dir.join(AUDIT_FILE)).map_err(|e| Error::Storage(e.to_string()))?;
+  f.write_all(line.as_bytes()).map_err(|e| Error::Storage(format!("Nie można zapisać dziennika audytu: {}", e)))
+}
+
+/// Entries in file order; unreadable lines are skipped.
+pub fn entries(dir: &Path) -> Vec<AuditEntry> {
+  fs::read_to_string(dir.join(AUDIT_FILE)).map(|s| s.lines().filter_map(|l| serde_json::from_str(l).ok()).collect()).unwrap_or_default()
+}
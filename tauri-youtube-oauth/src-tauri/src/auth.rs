@@ -0,0 +1,152 @@
+//! Authentication status and waiting for it, for automation that starts the
+//! app and must not begin API work before a login finished.
+
+use crate::{
+  api,
+  auth_health::{self, AuthHealth},
+  capabilities,
+  error::Error,
+  notifications, now_secs, read_config_from_dir, read_tokens_from_dir, reconsent, runtime, scopes, setup, AppConfig, SetupStep, Tokens,
+};
+use serde::Serialize;
+use std::{
+  path::Path,
+  time::{Duration, Instant},
+};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AuthStatus {
+  /// Tokens exist and are either unexpired or refreshable.
+  pub authenticated: bool,
+  /// Unix seconds the access token expires at, when known.
+  pub expires_at: Option<u64>,
+  pub refreshable: bool,
+  /// Granted with the stored tokens.
+  pub scopes: Vec<String>,
+  /// What a login asks for with the current config.
+  pub requested_scopes: Vec<String>,
+  /// Requested but not covered by the granted scopes; non-empty means consent
+  /// should be redone. Empty without tokens.
+  pub missing_scopes: Vec<String>,
+  /// The config gained scopes after these tokens were issued
+  /// (`reconsent-required`); `reconsent` asks for them.
+  pub needs_reconsent: bool,
+  /// Granted but no longer configured; nothing to do about them.
+  pub unused_scopes: Vec<String>,
+  /// Unix seconds of the login behind the refresh token, when known.
+  pub issued_at: Option<u64>,
+  /// Mutating calls are refused (`set_read_only_mode`).
+  pub read_only_mode: bool,
+  /// The last background check of the login; absent before the first one.
+  pub health: Option<AuthHealth>,
+}
+
+/// What `exchange_code` returns: the saved tokens, plus the requested scopes
+/// the user unchecked on the consent screen.
+#[derive(Serialize, Debug, Clone)]
+pub struct LoginResult {
+  #[serde(flatten)]
+  pub tokens: Tokens,
+  pub missing_scopes: Vec<String>,
+}
+
+/// Payload of `scopes-not-granted`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ScopesNotGranted {
+  pub missing: Vec<String>,
+}
+
+fn not_granted(cfg: Option<&AppConfig>, t: &Tokens) -> Vec<String> {
+  let Some(cfg) = cfg else {
+    return Vec::new();
+  };
+  let requested = scopes::configured(cfg);
+  scopes::missing(t, &requested.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+/// Compares a fresh login's grant with what the flow asked for, and emits
+/// `scopes-not-granted` when the user left something out.
+pub(crate) fn check_login(dir: &Path, cfg: &AppConfig, tokens: Tokens) -> LoginResult {
+  let missing_scopes = not_granted(Some(cfg), &tokens);
+  if !missing_scopes.is_empty() {
+    runtime::for_dir(dir).emit("scopes-not-granted", ScopesNotGranted { missing: missing_scopes.clone() });
+  }
+  LoginResult { tokens, missing_scopes }
+}
+
+pub fn status(dir: &Path) -> AuthStatus {
+  let cfg = read_config_from_dir(dir);
+  let requested_scopes = cfg.as_ref().map(scopes::configured).unwrap_or_default();
+  let Some(t) = read_tokens_from_dir(dir) else {
+    return AuthStatus {
+      authenticated: false,
+      expires_at: None,
+      refreshable: false,
+      scopes: Vec::new(),
+      requested_scopes,
+      missing_scopes: Vec::new(),
+      needs_reconsent: false,
+      unused_scopes: Vec::new(),
+      issued_at: None,
+      read_only_mode: api::read_only_enabled(dir),
+      health: None,
+    };
+  };
+  let matches_client = match (&t.client_id, &cfg) {
+    (Some(issued_for), Some(cfg)) => *issued_for == cfg.client_id,
+    _ => true,
+  };
+  let expires_at = (t.expires_in > 0).then(|| t.created_at + t.expires_in);
+  let unexpired = expires_at.is_none_or(|at| at > now_secs()) && !t.access_token.is_empty();
+  let refreshable = !t.refresh_token.is_empty() && cfg.is_some();
+  AuthStatus {
+    authenticated: matches_client && (unexpired || refreshable),
+    expires_at,
+    refreshable,
+    scopes: t.scope.split_whitespace().map(str::to_string).collect(),
+    requested_scopes,
+    missing_scopes: not_granted(cfg.as_ref(), &t),
+    needs_reconsent: reconsent::pending(dir).is_some(),
+    unused_scopes: cfg.as_ref().map(|c| reconsent::unused(c, &t)).unwrap_or_default(),
+    issued_at: t.issued_at,
+    read_only_mode: api::read_only_enabled(dir),
+    health: auth_health::current(dir),
+  }
+}
+
+/// Wakes [`wait_until_authenticated`] callers; called whenever tokens are written.
+pub(crate) fn notify_changed(dir: &Path) {
+  if let Some(t) = read_tokens_from_dir(dir) {
+    reconsent::check(dir, &t);
+    auth_health::tokens_written(dir);
+  }
+  notifications::auth_restored(dir);
+  capabilities::notify(dir);
+  runtime::for_dir(dir).auth_changed().notify_waiters();
+}
+
+/// Resolves once usable tokens exist: checks now, then on every token write
+/// in this process. Fails at once without client credentials, since no login
+/// can complete, and with `TimedOut` after `timeout`.
+pub async fn wait_until_authenticated(dir: &Path, timeout: Duration) -> Result<AuthStatus, Error> {
+  let rt = runtime::for_dir(dir);
+  let deadline = Instant::now() + timeout;
+  loop {
+    // Registered before checking, so a write in between isn't missed.
+    let changed = rt.auth_changed().notified();
+    tokio::pin!(changed);
+    changed.as_mut().enable();
+    let setup = setup::current(dir);
+    if setup.step == SetupStep::NeedsClientCredentials {
+      let detail = setup.problem.map(|p| format!("{}: {}", p.file, p.message)).unwrap_or_else(|| setup.missing.join(", "));
+      return Err(Error::Config(format!("Brak konfiguracji klienta — logowanie niemożliwe ({})", detail)));
+    }
+    let status = status(dir);
+    if status.authenticated {
+      return Ok(status);
+    }
+    if tokio::time::timeout_at(deadline.into(), changed).await.is_err() {
+      return Err(Error::TimedOut { operation: "wait_until_authenticated".into(), after_secs: timeout.as_secs() });
+    }
+  }
+}
@@ -0,0 +1,192 @@
+//! Daily background check of the saved login, so a refresh token that died
+//! (password change, security event) shows up the same day rather than as
+//! an upload failing at 3am. A still-fresh access token is checked with
+//! tokeninfo, sparing a refresh; otherwise the token is refreshed through
+//! [`refresh::refresh`] and its backoff. The result is kept in
+//! `auth_health.json`, so a restart doesn't check again right away. On
+//! unless `auth_health_check` is `false`.
+
+use crate::{
+  channel, connectivity,
+  error::Error,
+  format, http_headers, now_secs, read_config_from_dir, read_tokens_from_dir, redact, refresh, runtime, shutdown, storage,
+  timeouts::{self, OperationClass},
+  tls,
+};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, time::Duration};
+
+const STATE_FILE: &str = "auth_health.json";
+const DAY_SECS: u64 = 24 * 60 * 60;
+/// A degraded result (network, Google's 5xx) is re-checked sooner.
+const DEGRADED_RETRY_SECS: u64 = 60 * 60;
+/// An access token valid for less than this is refreshed instead.
+const FRESH_MARGIN_SECS: u64 = 5 * 60;
+/// How often the background task checks whether a check is due.
+const AUTO_CHECK: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthState {
+  Healthy,
+  /// The check couldn't reach Google or got an error that may pass; the
+  /// login itself may be fine.
+  Degraded,
+  /// Google refused the refresh token; only a new login helps.
+  Dead,
+}
+
+/// What the last check found, as kept in `auth_health.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Record {
+  state: HealthState,
+  checked_at: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  code: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
+}
+
+/// The login's health as of the last check, in `get_auth_status` and the
+/// payload of `auth-health-changed`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuthHealth {
+  pub state: HealthState,
+  /// Unix seconds of the last check; a token refresh counts as one.
+  pub checked_at: u64,
+  /// When the background check runs next; absent while dead or turned off.
+  pub next_check_at: Option<u64>,
+  /// Error code of a degraded or dead check.
+  pub code: Option<String>,
+  pub error: Option<String>,
+  /// One line for a tray tooltip, in the app language.
+  pub tooltip: String,
+}
+
+const TOOLTIPS: [[&str; 2]; 3] = [
+  ["ytlite: logowanie działa", "ytlite: signed in"],
+  ["ytlite: nie udało się sprawdzić logowania", "ytlite: couldn't check the login"],
+  ["ytlite: zaloguj się ponownie", "ytlite: sign in again"],
+];
+
+fn enabled(dir: &Path) -> bool { read_config_from_dir(dir).is_none_or(|c| c.auth_health_check != Some(false)) }
+
+fn load(dir: &Path) -> Option<Record> { fs::read_to_string(dir.join(STATE_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()) }
+
+fn next_check_at(dir: &Path, r: &Record) -> Option<u64> {
+  match r.state {
+    _ if !enabled(dir) => None,
+    HealthState::Healthy => Some(r.checked_at + DAY_SECS),
+    HealthState::Degraded => Some(r.checked_at + DEGRADED_RETRY_SECS),
+    HealthState::Dead => None,
+  }
+}
+
+fn describe(dir: &Path, r: Record) -> AuthHealth {
+  let lang = match format::language(dir) {
+    format::Language::Pl => 0,
+    format::Language::En => 1,
+  };
+  let tooltip = TOOLTIPS[r.state as usize][lang].to_string();
+  AuthHealth { state: r.state, checked_at: r.checked_at, next_check_at: next_check_at(dir, &r), code: r.code, error: r.error, tooltip }
+}
+
+/// The last check's result; `None` before the first one or without tokens.
+pub fn current(dir: &Path) -> Option<AuthHealth> {
+  read_tokens_from_dir(dir)?;
+  load(dir).map(|r| describe(dir, r))
+}
+
+/// Stores `r`; a change of state is emitted as `auth-health-changed`.
+fn store(dir: &Path, r: Record) -> AuthHealth {
+  let before = load(dir).map(|r| r.state);
+  if let Ok(s) = serde_json::to_string_pretty(&r) {
+    // Best effort: a failed write means checking again sooner.
+    let _ = storage::write_atomic(&dir.join(STATE_FILE), s);
+  }
+  let health = describe(dir, r);
+  if before != Some(health.state) {
+    runtime::for_dir(dir).emit("auth-health-changed", &health);
+  }
+  health
+}
+
+/// Tokens were written by a login or a refresh, or tokeninfo accepted them:
+/// as good as a healthy check.
+pub(crate) fn tokens_written(dir: &Path) -> AuthHealth {
+  store(dir, Record { state: HealthState::Healthy, checked_at: now_secs(), code: None, error: None })
+}
+
+fn failed(state: HealthState, e: &Error) -> Record {
+  Record { state, checked_at: now_secs(), code: Some(e.code().to_string()), error: Some(redact::text(&e.to_string())) }
+}
+
+/// Whether tokeninfo accepts the access token. `Ok(false)` means Google
+/// no longer knows it, which a refresh settles.
+async fn tokeninfo(dir: &Path, access_token: &str) -> Result<bool, Error> {
+  let url = channel::tokeninfo_endpoint();
+  let timeout = timeouts::for_class(dir, OperationClass::Auth);
+  let req = tls::request(dir, Method::GET, &url, &http_headers::trace_id())?.query(&[("access_token", access_token)]).timeout(timeout);
+  let resp = match req.send().await {
+    Ok(resp) => resp,
+    Err(e) => return Err(tls::transport_error(dir, &url, e, "tokeninfo", timeout).await),
+  };
+  match resp.status().as_u16() {
+    200..=299 => Ok(true),
+    400 | 401 => Ok(false),
+    status => Err(Error::Network(format!("tokeninfo: HTTP {}", status))),
+  }
+}
+
+/// Checks the login now and records the result. `None` when there was
+/// nothing to check or it can't be checked yet: no tokens, offline, or the
+/// refresh is in backoff (the next due check tries again).
+pub async fn check(dir: &Path) -> Option<AuthHealth> {
+  let t = read_tokens_from_dir(dir)?;
+  if !connectivity::online(dir).await {
+    return None;
+  }
+  let fresh = !t.access_token.is_empty() && (t.expires_in == 0 || t.created_at + t.expires_in > now_secs() + FRESH_MARGIN_SECS);
+  if fresh {
+    match tokeninfo(dir, &t.access_token).await {
+      Ok(true) => return Some(tokens_written(dir)),
+      Ok(false) => {}
+      Err(e) => return Some(store(dir, failed(HealthState::Degraded, &e))),
+    }
+  }
+  let record = match refresh::refresh(dir).await {
+    // The write already recorded it as healthy.
+    Ok(_) => return current(dir),
+    Err(Error::RefreshBackoff { .. }) => return None,
+    // `refresh` has already sent `reauth-required` and its notification.
+    Err(e @ (Error::Auth(_) | Error::GrantRevoked { .. } | Error::TokenEndpoint(_))) => failed(HealthState::Dead, &e),
+    Err(e) => failed(HealthState::Degraded, &e),
+  };
+  Some(store(dir, record))
+}
+
+/// Runs [`check`] when the last result is old enough. `None` when not due.
+pub async fn check_if_due(dir: &Path) -> Option<AuthHealth> {
+  if !enabled(dir) {
+    return None;
+  }
+  let due = match load(dir) {
+    Some(r) => next_check_at(dir, &r).is_some_and(|at| at <= now_secs()),
+    None => true,
+  };
+  if !due {
+    return None;
+  }
+  check(dir).await
+}
+
+/// Background loop behind the daily check; ends when the app shuts down or
+/// switches to another profile.
+pub async fn run_periodically(dir: &Path) {
+  let generation = shutdown::task_generation(dir);
+  while !shutdown::tasks_ended(dir, generation) {
+    check_if_due(dir).await;
+    shutdown::idle(dir, generation, AUTO_CHECK).await;
+  }
+}
@@ -0,0 +1,51 @@
+//! Writes the frontend's `src/bindings.d.ts` from the Rust sources.
+//!
+//! `--check` compares instead and exits with 1 when the committed file is out
+//! of date; `--out <path>` writes elsewhere.
+
+use std::{fs, path::PathBuf, process::ExitCode};
+use tauri_youtube_oauth::bindings;
+
+fn main() -> ExitCode {
+  let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  let mut out = manifest_dir.join("../src/bindings.d.ts");
+  let mut check = false;
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--check" => check = true,
+      "--out" => match args.next() {
+        Some(path) => out = PathBuf::from(path),
+        None => {
+          eprintln!("--out wymaga ścieżki");
+          return ExitCode::from(2);
+        }
+      },
+      _ => {
+        eprintln!("Nieznany argument: {} (dostępne: --check, --out <ścieżka>)", arg);
+        return ExitCode::from(2);
+      }
+    }
+  }
+
+  let generated = match bindings::generate(&manifest_dir.join("src")) {
+    Ok(text) => text,
+    Err(e) => {
+      eprintln!("Nie udało się wygenerować typów: {}", e);
+      return ExitCode::FAILURE;
+    }
+  };
+  if check {
+    if fs::read_to_string(&out).ok().as_deref() == Some(generated.as_str()) {
+      return ExitCode::SUCCESS;
+    }
+    eprintln!("{} jest nieaktualny — uruchom `cargo run --features bindings --bin generate-bindings`", out.display());
+    return ExitCode::FAILURE;
+  }
+  if let Err(e) = fs::write(&out, generated) {
+    eprintln!("Nie można zapisać {}: {}", out.display(), e);
+    return ExitCode::FAILURE;
+  }
+  println!("Zapisano {}", out.display());
+  ExitCode::SUCCESS
+}
@@ -0,0 +1,12 @@
+//! `ytlite-oauth`: the app's login, status, refresh, env and upload without
+//! the window. See [`tauri_youtube_oauth::cli`] for the commands.
+
+use std::process::ExitCode;
+use tauri_youtube_oauth::cli;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let code = cli::run(&args, &mut std::io::stdout(), &mut std::io::stderr()).await;
+  ExitCode::from(code)
+}
@@ -0,0 +1,569 @@
+//! TypeScript declarations for everything that crosses the Tauri boundary,
+//! generated from the Rust sources so the frontend can't drift from them.
+//!
+//! The walk starts at every `#[tauri::command]` in `commands.rs` (argument
+//! and result types) and at the payloads in [`EVENTS`], and follows field
+//! types through the serde structs and enums of the crate, honouring the
+//! serde attributes this crate uses. A new command is covered as soon as it
+//! exists; an emitted event missing from [`EVENTS`] fails the generation.
+//!
+//! Behind the `bindings` feature; `cargo run --features bindings --bin
+//! generate-bindings` writes `src/bindings.d.ts` of the frontend, `-- --check`
+//! only compares.
+
+use crate::error::{self, Error};
+use regex::Regex;
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+  fs,
+  path::Path,
+};
+use syn::{Attribute, Fields, FnArg, GenericArgument, Item, Pat, PathArguments, ReturnType, Type};
+
+/// Payload of every event the core emits. An emit call names no type, so
+/// these are kept by hand; [`generate`] checks them against the sources.
+pub const EVENTS: &[(&str, &str)] = &[
+  ("app-closing", "AppClosing"),
+  ("archive-progress", "ArchiveProgress"),
+  ("auth-health-changed", "AuthHealth"),
+  ("background-task-crashed", "TaskCrashed"),
+  ("capabilities-changed", "Capabilities"),
+  ("config-changed", "ConfigChange"),
+  ("connectivity-changed", "Connectivity"),
+  ("dry-run-plan", "DryRunPlan"),
+  ("env-drift", "EnvDrift"),
+  ("first-comment-posted", "FirstCommentPosted"),
+  ("hash-progress", "HashProgress"),
+  ("inventory-progress", "InventoryProgress"),
+  ("library-progress", "LibraryProgress"),
+  ("metadata-lint", "MetadataLint"),
+  ("metadata-plan-progress", "PlanProgress"),
+  ("migration-available", "MigrationAvailable"),
+  ("oauth-callback", "CallbackOutcome"),
+  ("oauth-client-mismatch", "OAuthClientDescription"),
+  ("oauth-open-browser-failed", "BrowserLaunch"),
+  ("playlist-progress", "PlaylistProgress"),
+  ("post-upload-hook", "HookOutcome"),
+  ("privacy-progress", "PrivacyProgress"),
+  ("processing-progress", "ProcessingProgress"),
+  ("profile-switched", "ProfileSwitched"),
+  ("queue-changed", "Vec<QueueEntry>"),
+  ("queue-entry-blocked", "EntryBlocked"),
+  ("queue-entry-enriched", "EntryEnriched"),
+  ("queue-entry-failed", "EntryFailed"),
+  ("queue-entry-gave-up", "EntryFailed"),
+  ("queue-entry-planned", "EntryPlanned"),
+  ("queue-entry-retried", "EntryRetried"),
+  ("read-only-mode-changed", "bool"),
+  ("reauth-required", "String"),
+  ("reconsent-required", "ReconsentRequired"),
+  ("remote-state-verified", "RemoteStateSummary"),
+  ("scopes-not-granted", "ScopesNotGranted"),
+  ("setup-state-changed", "SetupState"),
+  ("storage-cleaned", "CleanupReport"),
+  ("thumbnail-retry", "ThumbnailRetry"),
+  ("token-aging-warning", "TokenAgingWarning"),
+  ("upload-processed", "UploadProcessed"),
+  ("upload-progress", "UploadProgress"),
+  ("upload-suspect", "UploadSuspect"),
+  ("warning", "Warning"),
+  ("workspace-restricted", "WorkspaceRestriction"),
+];
+
+/// Fields whose TS type is narrower than their Rust one.
+const FIELD_OVERRIDES: &[(&str, &str, &str)] = &[("CommandError", "code", "CommandErrorCode")];
+
+/// Declared even though no command returns them: what every command rejects with.
+const EXTRA_ROOTS: [&str; 1] = ["CommandError"];
+
+/// Argument types Tauri injects rather than reading from JS.
+const INJECTED: [&str; 3] = ["AppHandle", "State", "Window"];
+
+const HEADER: &str = "// Generated by `cargo run --features bindings --bin generate-bindings` from the Rust sources; do not edit.\n";
+
+fn invalid(message: String) -> Error {
+  Error::Validation(message)
+}
+
+/// Named fields of a struct or variant, as [`Walker::members`] renders them.
+#[derive(Default)]
+struct Members {
+  fields: Vec<(Vec<String>, String)>,
+  flattened: Vec<String>,
+}
+
+/// A serde type found in the sources.
+struct Def {
+  item: Item,
+  serialize: bool,
+}
+
+#[derive(Default)]
+struct SerdeAttrs {
+  rename: Option<String>,
+  rename_all: Option<String>,
+  tag: Option<String>,
+  content: Option<String>,
+  untagged: bool,
+  skip: bool,
+  flatten: bool,
+  default: bool,
+  skip_serializing_if: bool,
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> Result<SerdeAttrs, Error> {
+  let mut out = SerdeAttrs::default();
+  for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+    attr
+      .parse_nested_meta(|meta| {
+        let key = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+        let value = |meta: &syn::meta::ParseNestedMeta| -> syn::Result<Option<String>> {
+          if meta.input.peek(syn::Token![=]) {
+            match meta.value()?.parse::<syn::Expr>()? {
+              syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => Ok(Some(s.value())),
+              _ => Ok(None),
+            }
+          } else if meta.input.peek(syn::token::Paren) {
+            let _nested;
+            syn::parenthesized!(_nested in meta.input);
+            Ok(None)
+          } else {
+            Ok(None)
+          }
+        };
+        let v = value(&meta)?;
+        match key.as_str() {
+          "rename" => out.rename = v,
+          "rename_all" => out.rename_all = v,
+          "tag" => out.tag = v,
+          "content" => out.content = v,
+          "untagged" => out.untagged = true,
+          "skip" => out.skip = true,
+          "flatten" => out.flatten = true,
+          "default" => out.default = true,
+          "skip_serializing_if" => out.skip_serializing_if = true,
+          _ => {}
+        }
+        Ok(())
+      })
+      .map_err(|e| invalid(format!("Nieczytelny atrybut serde: {}", e)))?;
+  }
+  Ok(out)
+}
+
+fn derives(attrs: &[Attribute]) -> BTreeSet<String> {
+  let mut found = BTreeSet::new();
+  for attr in attrs.iter().filter(|a| a.path().is_ident("derive")) {
+    let _ = attr.parse_nested_meta(|meta| {
+      if let Some(last) = meta.path.segments.last() {
+        found.insert(last.ident.to_string());
+      }
+      Ok(())
+    });
+  }
+  found
+}
+
+fn docs(attrs: &[Attribute]) -> Vec<String> {
+  attrs
+    .iter()
+    .filter(|a| a.path().is_ident("doc"))
+    .filter_map(|a| match &a.meta {
+      syn::Meta::NameValue(nv) => match &nv.value {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => Some(s.value().trim().replace("*/", "* /")),
+        _ => None,
+      },
+      _ => None,
+    })
+    .collect()
+}
+
+fn doc_comment(lines: &[String], indent: &str) -> String {
+  match lines {
+    [] => String::new(),
+    [line] => format!("{}/** {} */\n", indent, line),
+    lines => {
+      let body: String = lines.iter().map(|l| format!("{} *{}{}\n", indent, if l.is_empty() { "" } else { " " }, l)).collect();
+      format!("{}/**\n{}{} */\n", indent, body, indent)
+    }
+  }
+}
+
+fn snake(variant: &str) -> String {
+  let mut out = String::new();
+  for (i, c) in variant.chars().enumerate() {
+    if c.is_uppercase() && i > 0 {
+      out.push('_');
+    }
+    out.push(c.to_ascii_lowercase());
+  }
+  out
+}
+
+fn rename_variant(name: &str, rule: Option<&str>) -> String {
+  match rule {
+    Some("lowercase") => name.to_ascii_lowercase(),
+    Some("UPPERCASE") => name.to_ascii_uppercase(),
+    Some("camelCase") => name[..1].to_ascii_lowercase() + &name[1..],
+    Some("snake_case") => snake(name),
+    Some("SCREAMING_SNAKE_CASE") => snake(name).to_ascii_uppercase(),
+    Some("kebab-case") => snake(name).replace('_', "-"),
+    Some("SCREAMING-KEBAB-CASE") => snake(name).to_ascii_uppercase().replace('_', "-"),
+    _ => name.to_string(),
+  }
+}
+
+fn rename_field(name: &str, rule: Option<&str>) -> String {
+  let pascal = || name.split('_').map(|w| w[..1.min(w.len())].to_ascii_uppercase() + &w[1.min(w.len())..]).collect::<String>();
+  match rule {
+    Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE") => name.to_ascii_uppercase(),
+    Some("PascalCase") => pascal(),
+    Some("camelCase") => {
+      let p = pascal();
+      p[..1.min(p.len())].to_ascii_lowercase() + &p[1.min(p.len())..]
+    }
+    Some("kebab-case") => name.replace('_', "-"),
+    Some("SCREAMING-KEBAB-CASE") => name.to_ascii_uppercase().replace('_', "-"),
+    _ => name.to_string(),
+  }
+}
+
+fn quote_key(key: &str) -> String {
+  if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && !key.starts_with(|c: char| c.is_ascii_digit()) {
+    key.to_string()
+  } else {
+    format!("{:?}", key)
+  }
+}
+
+fn type_args(args: &PathArguments) -> Vec<&Type> {
+  match args {
+    PathArguments::AngleBracketed(a) => a
+      .args
+      .iter()
+      .filter_map(|a| match a {
+        GenericArgument::Type(t) => Some(t),
+        _ => None,
+      })
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn last_ident(ty: &Type) -> Option<String> {
+  match ty {
+    Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+    _ => None,
+  }
+}
+
+fn is_option(ty: &Type) -> bool {
+  last_ident(ty).as_deref() == Some("Option")
+}
+
+fn array_of(inner: String) -> String {
+  if inner.contains(" | ") {
+    format!("({})[]", inner)
+  } else {
+    format!("{}[]", inner)
+  }
+}
+
+struct Walker {
+  defs: HashMap<String, Def>,
+  queue: VecDeque<String>,
+  seen: BTreeSet<String>,
+}
+
+impl Walker {
+  /// The TS type of `ty`, queueing the crate types it names.
+  fn ts(&mut self, ty: &Type, context: &str) -> Result<String, Error> {
+    match ty {
+      Type::Reference(r) => self.ts(&r.elem, context),
+      Type::Paren(p) => self.ts(&p.elem, context),
+      Type::Group(g) => self.ts(&g.elem, context),
+      Type::Slice(s) => Ok(array_of(self.ts(&s.elem, context)?)),
+      Type::Array(a) => Ok(array_of(self.ts(&a.elem, context)?)),
+      Type::Tuple(t) if t.elems.is_empty() => Ok("null".into()),
+      Type::Tuple(t) => {
+        let elems = t.elems.iter().map(|e| self.ts(e, context)).collect::<Result<Vec<_>, _>>()?;
+        Ok(format!("[{}]", elems.join(", ")))
+      }
+      Type::Path(p) => {
+        let last = p.path.segments.last().ok_or_else(|| invalid(format!("Pusty typ w {}", context)))?;
+        let name = last.ident.to_string();
+        let args = type_args(&last.arguments);
+        let arg = |i: usize| args.get(i).copied().ok_or_else(|| invalid(format!("{} bez parametru w {}", name, context)));
+        Ok(match name.as_str() {
+          "String" | "str" | "PathBuf" | "Path" | "char" | "NaiveDate" | "NaiveDateTime" | "DateTime" | "Url" | "IpAddr" => "string".into(),
+          "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "f32" | "f64" => {
+            "number".into()
+          }
+          "bool" => "boolean".into(),
+          "Value" => "unknown".into(),
+          "Option" => format!("{} | null", self.ts(arg(0)?, context)?),
+          "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => array_of(self.ts(arg(0)?, context)?),
+          "HashMap" | "BTreeMap" => format!("Record<string, {}>", self.ts(arg(1)?, context)?),
+          "Box" | "Arc" | "Rc" | "Cow" => self.ts(arg(0)?, context)?,
+          _ if self.defs.contains_key(&name) => {
+            if self.seen.insert(name.clone()) {
+              self.queue.push_back(name.clone());
+            }
+            name
+          }
+          _ => return Err(invalid(format!("Brak odpowiednika TypeScript dla typu {} (w {})", name, context))),
+        })
+      }
+      _ => Err(invalid(format!("Nieobsługiwany rodzaj typu w {}", context))),
+    }
+  }
+
+  /// `b?: U | null` members for named fields, each with its doc lines, and
+  /// the types of `#[serde(flatten)]` fields, which the caller extends.
+  fn members(&mut self, owner: &str, fields: &syn::FieldsNamed, rule: Option<&str>, serialize: bool) -> Result<Members, Error> {
+    let mut out = Members::default();
+    for field in &fields.named {
+      let attrs = serde_attrs(&field.attrs)?;
+      if attrs.skip {
+        continue;
+      }
+      let ident = field.ident.as_ref().map(|i| i.to_string()).unwrap_or_default();
+      let context = format!("{}.{}", owner, ident);
+      if attrs.flatten {
+        let ts = self.ts(&field.ty, &context)?;
+        if !self.defs.contains_key(&ts) {
+          return Err(invalid(format!("#[serde(flatten)] w {} wymaga struktury z tego crate'a", context)));
+        }
+        out.flattened.push(ts);
+        continue;
+      }
+      let key = attrs.rename.clone().unwrap_or_else(|| rename_field(&ident, rule));
+      let ts = match FIELD_OVERRIDES.iter().find(|(o, f, _)| *o == owner && *f == ident) {
+        Some((_, _, ts)) => ts.to_string(),
+        None => self.ts(&field.ty, &context)?,
+      };
+      let optional = is_option(&field.ty) || attrs.skip_serializing_if || (attrs.default && !serialize);
+      out.fields.push((docs(&field.attrs), format!("{}{}: {}", quote_key(&key), if optional { "?" } else { "" }, ts)));
+    }
+    Ok(out)
+  }
+
+  fn declaration(&mut self, name: &str) -> Result<String, Error> {
+    let def = &self.defs[name];
+    let (item, serialize) = (def.item.clone(), def.serialize);
+    match item {
+      Item::Struct(s) => {
+        let attrs = serde_attrs(&s.attrs)?;
+        let doc = doc_comment(&docs(&s.attrs), "");
+        match &s.fields {
+          Fields::Named(fields) => {
+            let members = self.members(name, fields, attrs.rename_all.as_deref(), serialize)?;
+            let body: String = members.fields.iter().map(|(docs, m)| format!("{}  {};\n", doc_comment(docs, "  "), m)).collect();
+            let extends = if members.flattened.is_empty() { String::new() } else { format!(" extends {}", members.flattened.join(", ")) };
+            Ok(format!("{}export interface {}{} {{\n{}}}\n", doc, name, extends, body))
+          }
+          Fields::Unnamed(fields) => {
+            let elems = fields.unnamed.iter().map(|f| self.ts(&f.ty, name)).collect::<Result<Vec<_>, _>>()?;
+            let ts = if elems.len() == 1 { elems[0].clone() } else { format!("[{}]", elems.join(", ")) };
+            Ok(format!("{}export type {} = {};\n", doc, name, ts))
+          }
+          Fields::Unit => Ok(format!("{}export type {} = null;\n", doc, name)),
+        }
+      }
+      Item::Enum(e) => {
+        let attrs = serde_attrs(&e.attrs)?;
+        if attrs.content.is_some() {
+          return Err(invalid(format!("#[serde(content)] w {} nie jest obsługiwane", name)));
+        }
+        let mut alternatives = Vec::new();
+        for variant in &e.variants {
+          let vattrs = serde_attrs(&variant.attrs)?;
+          if vattrs.skip {
+            continue;
+          }
+          let wire = vattrs.rename.clone().unwrap_or_else(|| rename_variant(&variant.ident.to_string(), attrs.rename_all.as_deref()));
+          let label = format!("{:?}", wire);
+          let context = format!("{}::{}", name, variant.ident);
+          let ts = match (&variant.fields, &attrs.tag, attrs.untagged) {
+            (Fields::Unit, _, true) => "null".to_string(),
+            (Fields::Unit, Some(tag), _) => format!("{{ {}: {} }}", quote_key(tag), label),
+            (Fields::Unit, None, _) => label,
+            (Fields::Unnamed(f), tag, untagged) if f.unnamed.len() == 1 => {
+              let inner = self.ts(&f.unnamed[0].ty, &context)?;
+              match (tag, untagged) {
+                (_, true) => inner,
+                (Some(tag), _) => format!("{{ {}: {} }} & {}", quote_key(tag), label, inner),
+                (None, _) => format!("{{ {}: {} }}", label, inner),
+              }
+            }
+            (Fields::Unnamed(f), None, untagged) => {
+              let elems = f.unnamed.iter().map(|f| self.ts(&f.ty, &context)).collect::<Result<Vec<_>, _>>()?;
+              let tuple = format!("[{}]", elems.join(", "));
+              if untagged {
+                tuple
+              } else {
+                format!("{{ {}: {} }}", label, tuple)
+              }
+            }
+            (Fields::Unnamed(_), Some(_), _) => return Err(invalid(format!("Krotka w enumie z tagiem: {}", context))),
+            (Fields::Named(fields), tag, untagged) => {
+              // Inline, so without the field docs.
+              let members = self.members(&context, fields, vattrs.rename_all.as_deref(), serialize)?;
+              if !members.flattened.is_empty() {
+                return Err(invalid(format!("#[serde(flatten)] w wariancie {} nie jest obsługiwane", context)));
+              }
+              let body = members.fields.into_iter().map(|(_, m)| m).collect::<Vec<_>>().join("; ");
+              match (tag, untagged) {
+                (_, true) => format!("{{ {} }}", body),
+                (Some(tag), _) => format!("{{ {}: {}; {} }}", quote_key(tag), label, body),
+                (None, _) => format!("{{ {}: {{ {} }} }}", label, body),
+              }
+            }
+          };
+          alternatives.push(ts);
+        }
+        let doc = doc_comment(&docs(&e.attrs), "");
+        let body: Vec<String> = alternatives.iter().map(|a| format!("  | {}", a)).collect();
+        Ok(format!("{}export type {} =\n{};\n", doc, name, body.join("\n")))
+      }
+      _ => unreachable!("only structs and enums are indexed"),
+    }
+  }
+}
+
+fn parse(path: &Path) -> Result<syn::File, Error> {
+  let source = fs::read_to_string(path).map_err(|e| Error::Storage(format!("Nie można odczytać {}: {}", path.display(), e)))?;
+  syn::parse_file(&source).map_err(|e| invalid(format!("Nie można sparsować {}: {}", path.display(), e)))
+}
+
+fn index(items: &[Item], defs: &mut HashMap<String, Def>) {
+  for item in items {
+    let (ident, attrs) = match item {
+      Item::Struct(s) => (&s.ident, &s.attrs),
+      Item::Enum(e) => (&e.ident, &e.attrs),
+      Item::Mod(m) => {
+        if let Some((_, items)) = &m.content {
+          index(items, defs);
+        }
+        continue;
+      }
+      _ => continue,
+    };
+    let derived = derives(attrs);
+    let serialize = derived.contains("Serialize");
+    if serialize || derived.contains("Deserialize") {
+      defs.insert(ident.to_string(), Def { item: item.clone(), serialize });
+    }
+  }
+}
+
+/// Event names passed as literals to `emit` or the `events` dispatcher.
+fn emitted_events(src_dir: &Path) -> Result<BTreeSet<String>, Error> {
+  let re = Regex::new(r#"(?:\.emit|events::(?:progress|transition|broadcast))\(\s*(?:[^"(),]+,\s*)*"([a-z0-9-]+)""#).expect("valid regex");
+  let mut found = BTreeSet::new();
+  for path in rust_files(src_dir)? {
+    let source = fs::read_to_string(&path).map_err(|e| Error::Storage(e.to_string()))?;
+    found.extend(re.captures_iter(&source).map(|c| c[1].to_string()));
+  }
+  Ok(found)
+}
+
+fn rust_files(src_dir: &Path) -> Result<Vec<std::path::PathBuf>, Error> {
+  let entries = fs::read_dir(src_dir).map_err(|e| Error::Storage(format!("Nie można odczytać {}: {}", src_dir.display(), e)))?;
+  let mut files: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.extension().is_some_and(|x| x == "rs")).collect();
+  files.sort();
+  Ok(files)
+}
+
+fn is_command(attrs: &[Attribute]) -> bool {
+  attrs.iter().any(|a| a.path().segments.last().is_some_and(|s| s.ident == "command"))
+}
+
+/// The `T` of `Result<T, CommandError>`, or the type itself.
+fn ok_type(ty: &Type) -> &Type {
+  if let Type::Path(p) = ty {
+    if let Some(last) = p.path.segments.last().filter(|s| s.ident == "Result") {
+      if let Some(first) = type_args(&last.arguments).first() {
+        return first;
+      }
+    }
+  }
+  ty
+}
+
+/// Renders the declarations for the crate whose sources are in `src_dir`.
+pub fn generate(src_dir: &Path) -> Result<String, Error> {
+  let mut defs = HashMap::new();
+  for path in rust_files(src_dir)? {
+    index(&parse(&path)?.items, &mut defs);
+  }
+  let mut walker = Walker { defs, queue: VecDeque::new(), seen: BTreeSet::new() };
+  for root in EXTRA_ROOTS {
+    walker.ts(&syn::parse_str(root).expect("valid type"), root)?;
+  }
+
+  let mut commands = Vec::new();
+  for item in parse(&src_dir.join("commands.rs"))?.items {
+    let Item::Fn(f) = item else { continue };
+    if !is_command(&f.attrs) {
+      continue;
+    }
+    let name = f.sig.ident.to_string();
+    let mut args = Vec::new();
+    for input in &f.sig.inputs {
+      let FnArg::Typed(arg) = input else { continue };
+      let Pat::Ident(ident) = &*arg.pat else { continue };
+      if last_ident(&arg.ty).is_some_and(|i| INJECTED.contains(&i.as_str())) {
+        continue;
+      }
+      let context = format!("{}({})", name, ident.ident);
+      let optional = is_option(&arg.ty);
+      // Tauri takes command arguments in camelCase.
+      let key = rename_field(&ident.ident.to_string(), Some("camelCase"));
+      args.push(format!("{}{}: {}", key, if optional { "?" } else { "" }, walker.ts(&arg.ty, &context)?));
+    }
+    let result = match &f.sig.output {
+      ReturnType::Default => "null".to_string(),
+      ReturnType::Type(_, ty) => walker.ts(ok_type(ty), &name)?,
+    };
+    let args = if args.is_empty() { "{}".to_string() } else { format!("{{ {} }}", args.join("; ")) };
+    commands.push((name, format!("{{ args: {}; result: {} }}", args, result)));
+  }
+  commands.sort();
+
+  let emitted = emitted_events(src_dir)?;
+  let registered: BTreeSet<String> = EVENTS.iter().map(|(e, _)| e.to_string()).collect();
+  if let Some(missing) = emitted.difference(&registered).next() {
+    return Err(invalid(format!("Zdarzenie {} nie ma typu w bindings::EVENTS", missing)));
+  }
+  if let Some(stale) = registered.difference(&emitted).next() {
+    return Err(invalid(format!("Zdarzenie {} z bindings::EVENTS nie jest nigdzie wysyłane", stale)));
+  }
+  let mut events = Vec::new();
+  for (event, ty) in EVENTS {
+    let parsed: Type = syn::parse_str(ty).map_err(|e| invalid(format!("Nieprawidłowy typ {} zdarzenia {}: {}", ty, event, e)))?;
+    events.push(format!("  {:?}: {};\n", event, walker.ts(&parsed, event)?));
+  }
+
+  let mut declarations = BTreeMap::new();
+  while let Some(name) = walker.queue.pop_front() {
+    let declaration = walker.declaration(&name)?;
+    declarations.insert(name, declaration);
+  }
+
+  let codes: Vec<String> = error::error_codes().iter().map(|c| format!("  | {:?}", c.code)).collect();
+  let mut out = String::from(HEADER);
+  out.push_str(&format!("\n/** Values of `CommandError.code`. */\nexport type CommandErrorCode =\n{};\n", codes.join("\n")));
+  for declaration in declarations.values() {
+    out.push('\n');
+    out.push_str(declaration);
+  }
+  out.push_str("\n/** Arguments and result of every command, by name. */\nexport interface Commands {\n");
+  for (name, signature) in &commands {
+    out.push_str(&format!("  {}: {};\n", name, signature));
+  }
+  out.push_str("}\n\n/** Payload of every event, by name. */\nexport interface Events {\n");
+  out.push_str(&events.concat());
+  out.push_str("}\n");
+  Ok(out)
+}
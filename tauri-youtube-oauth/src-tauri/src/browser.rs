@@ -0,0 +1,131 @@
+//! Opening the consent screen. `preferred_browser` names the program, so the
+//! login happens in the profile that is signed in to the right Google
+//! account; without it the platform opener decides. Inside a Flatpak or
+//! Snap sandbox host binaries can't be started, so a preferred browser isn't
+//! tried there. Only https links are opened. Whatever keeps the browser
+//! closed goes out as `oauth-open-browser-failed` with the link for the user
+//! to copy.
+
+use crate::{error::Error, runtime, AppConfig};
+use serde::Serialize;
+use std::{path::Path, process::Command};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sandbox {
+  Flatpak,
+  Snap,
+}
+
+/// How the consent screen was opened; what `start_oauth` returns and the
+/// payload of `oauth-open-browser-failed`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BrowserLaunch {
+  pub url: String,
+  /// The program started, or the one that would have been.
+  pub program: String,
+  pub opened: bool,
+  /// Why the browser didn't open.
+  pub error: Option<String>,
+  pub sandbox: Option<Sandbox>,
+}
+
+/// The sandbox this process runs in, from the variables and the marker
+/// file Flatpak and snapd set up.
+pub fn sandbox() -> Option<Sandbox> {
+  if std::env::var_os("FLATPAK_ID").is_some() || Path::new("/.flatpak-info").exists() {
+    Some(Sandbox::Flatpak)
+  } else if std::env::var_os("SNAP").is_some() {
+    Some(Sandbox::Snap)
+  } else {
+    None
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+  Windows,
+  Macos,
+  Linux,
+}
+
+impl Platform {
+  pub fn current() -> Self {
+    if cfg!(target_os = "windows") {
+      Platform::Windows
+    } else if cfg!(target_os = "macos") {
+      Platform::Macos
+    } else {
+      Platform::Linux
+    }
+  }
+
+  /// What opens URLs and directories there. Inside a sandbox `xdg-open` is
+  /// the wrapper that asks the host through a portal.
+  pub fn opener(self) -> &'static str {
+    match self {
+      // Not `cmd /C start`: cmd ends the command at the first `&` of the
+      // query (dropping `scope`) and expands `%…%` in percent-escapes.
+      Platform::Windows => "explorer",
+      Platform::Macos => "open",
+      Platform::Linux => "xdg-open",
+    }
+  }
+}
+
+/// The program and arguments that open `target` on `platform`: `program`,
+/// or the platform opener, with `target` as the only argument.
+pub fn open_command(platform: Platform, program: Option<&str>, target: &str) -> (String, Vec<String>) {
+  (program.unwrap_or(platform.opener()).to_string(), vec![target.to_string()])
+}
+
+/// Starts `program` with `args`, never through a shell. Only a failed spawn
+/// is detected.
+pub(crate) fn spawn(program: &str, args: &[String]) -> std::io::Result<()> {
+  Command::new(program).args(args).spawn().map(|_| ())
+}
+
+/// Opens the https `url` with `program` or the opener of `platform`, started
+/// by `run`. Anything else is refused before `run` is called.
+pub fn open_url_with(
+  url: &str,
+  program: Option<&str>,
+  platform: Platform,
+  run: impl FnOnce(&str, &[String]) -> std::io::Result<()>,
+) -> Result<(), Error> {
+  match url::Url::parse(url) {
+    Ok(u) if u.scheme() == "https" && u.host_str().is_some_and(|h| !h.is_empty()) => {}
+    _ => return Err(Error::Validation(format!("Nie otwieram {} — to nie jest adres https", url))),
+  }
+  let (program, args) = open_command(platform, program, url);
+  run(&program, &args).map_err(|e| Error::Open { target: program, message: e.to_string() })
+}
+
+/// Opens the https `url` with the platform opener.
+pub fn open_url(url: &str) -> Result<(), Error> {
+  open_url_with(url, None, Platform::current(), spawn)
+}
+
+/// Opens `url` with `preferred_browser` from `cfg` or the platform opener.
+pub fn open(dir: &Path, cfg: &AppConfig, url: &str) -> BrowserLaunch {
+  let sandbox = sandbox();
+  let preferred = cfg.preferred_browser.as_deref().map(str::trim).filter(|p| !p.is_empty());
+  let platform = Platform::current();
+  let program = preferred.unwrap_or(platform.opener());
+  let result = match (preferred, sandbox) {
+    (Some(_), Some(s)) => {
+      let name = match s {
+        Sandbox::Flatpak => "Flatpak",
+        Sandbox::Snap => "Snap",
+      };
+      Err(format!("Aplikacja działa w piaskownicy {}, więc nie uruchomi {}; skopiuj link do właściwej przeglądarki", name, program))
+    }
+    _ => open_url_with(url, preferred, platform, spawn).map_err(|e| e.to_string()),
+  };
+  let launch =
+    BrowserLaunch { url: url.to_string(), program: program.to_string(), opened: result.is_ok(), error: result.err(), sandbox };
+  if !launch.opened {
+    runtime::for_dir(dir).emit("oauth-open-browser-failed", launch.clone());
+  }
+  launch
+}
@@ -0,0 +1,286 @@
+//! Bulk metadata edits from a plan file, the counterpart of the inventory
+//! export: one row per video with only the fields to change. Every row is
+//! checked before the first write, so a bad file changes nothing.
+
+use crate::{
+  api::{self, DryRunPlan},
+  csv,
+  error::Error,
+  runtime,
+  video_links::{self, LinkCache, VideoLink, VideoLinksConfig},
+  videos,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashSet, fs, path::Path, time::Duration};
+
+const DEFAULT_MIN_INTERVAL_MS: u64 = 1000;
+const PRIVACY_VALUES: [&str; 3] = ["public", "unlisted", "private"];
+
+/// One plan entry; absent fields stay as they are on YouTube.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PlanRow {
+  pub video_id: String,
+  #[serde(default)]
+  pub title: Option<String>,
+  #[serde(default)]
+  pub description: Option<String>,
+  #[serde(default)]
+  pub tags: Option<Vec<String>>,
+  #[serde(default)]
+  pub privacy: Option<String>,
+  #[serde(default)]
+  pub publish_at: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PlanOptions {
+  /// Skip rows whose values already match YouTube, saving the 50-unit write.
+  #[serde(default)]
+  pub only_diff: bool,
+  /// Pause between writes; 1000 ms when absent.
+  #[serde(default)]
+  pub min_interval_ms: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PlanProblem {
+  /// 1-based position of the row in the plan.
+  pub row: usize,
+  pub video_id: String,
+  pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RowStatus {
+  Succeeded,
+  Failed,
+  /// Already matched YouTube (`only_diff`).
+  Unchanged,
+  /// Dry-run mode: the write was planned, not sent.
+  Planned,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RowOutcome {
+  pub row: usize,
+  pub video_id: String,
+  pub status: RowStatus,
+  pub error: Option<String>,
+  pub plan: Option<DryRunPlan>,
+  /// Links to other videos in the description written, with `video_links` on.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub video_links: Vec<VideoLink>,
+}
+
+/// Payload of `metadata-plan-progress`, sent after each row.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlanProgress {
+  pub row: usize,
+  pub total: usize,
+  pub video_id: String,
+  pub status: RowStatus,
+}
+
+/// With `problems` set nothing was applied.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PlanReport {
+  pub problems: Vec<PlanProblem>,
+  pub rows: Vec<RowOutcome>,
+  pub succeeded: usize,
+  pub failed: usize,
+  pub unchanged: usize,
+  pub planned: usize,
+}
+
+fn cell(record: &[String], i: Option<usize>) -> Option<String> {
+  i.and_then(|i| record.get(i)).map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+/// Rows from a CSV (by extension) or JSON array plan. In CSV an empty cell
+/// leaves the field alone and tags are comma-separated.
+pub fn read_plan(path: &Path) -> Result<Vec<PlanRow>, Error> {
+  let text = fs::read_to_string(path).map_err(|e| Error::Validation(format!("Nie można odczytać planu {}: {}", path.display(), e)))?;
+  let is_csv = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+  if !is_csv {
+    return serde_json::from_str(&text).map_err(|e| Error::Validation(format!("Nieprawidłowy plan JSON: {}", e)));
+  }
+  let mut records = csv::parse(&text).map_err(|e| Error::Validation(format!("Nieprawidłowy plan CSV: {}", e)))?.into_iter();
+  let header: Vec<String> = records.next().unwrap_or_default().iter().map(|h| h.trim().to_lowercase()).collect();
+  let col = |name: &str| header.iter().position(|h| h == name);
+  let Some(id_col) = col("video_id") else {
+    return Err(Error::Validation("Plan CSV nie ma kolumny video_id".into()));
+  };
+  let (title, description, tags, privacy, publish_at) = (col("title"), col("description"), col("tags"), col("privacy"), col("publish_at"));
+  Ok(
+    records
+      .map(|r| PlanRow {
+        video_id: cell(&r, Some(id_col)).unwrap_or_default(),
+        // Descriptions keep their own whitespace.
+        title: cell(&r, title),
+        description: description.and_then(|i| r.get(i)).filter(|s| !s.trim().is_empty()).cloned(),
+        tags: cell(&r, tags).map(|t| t.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect()),
+        privacy: cell(&r, privacy),
+        publish_at: cell(&r, publish_at),
+      })
+      .collect(),
+  )
+}
+
+/// Everything wrong with a row given the video's current `status`.
+fn row_problems(row: &PlanRow, current: &Value, now: DateTime<Utc>) -> Vec<String> {
+  let mut problems = videos::text_problems(row.title.as_deref(), row.description.as_deref(), row.tags.as_deref());
+  if let Some(p) = row.privacy.as_deref().filter(|p| !PRIVACY_VALUES.contains(p)) {
+    problems.push(format!("Nieznana prywatność {:?} (public, unlisted, private)", p));
+  }
+  if let Some(at) = &row.publish_at {
+    match DateTime::parse_from_rfc3339(at) {
+      Err(e) => problems.push(format!("publish_at {:?} nie jest datą RFC3339: {}", at, e)),
+      Ok(t) if t <= now => problems.push(format!("publish_at {} jest w przeszłości", at)),
+      Ok(_) => {
+        let privacy = row.privacy.as_deref().or_else(|| current["status"]["privacyStatus"].as_str());
+        if privacy != Some("private") {
+          problems.push("publish_at wymaga prywatności private".to_string());
+        }
+      }
+    }
+  }
+  problems
+}
+
+/// Validates every row against the file's own rules and the remote videos.
+pub async fn validate_plan(dir: &Path, rows: &[PlanRow]) -> Result<Vec<PlanProblem>, Error> {
+  let mut problems = Vec::new();
+  let mut push = |i: usize, row: &PlanRow, message: String| problems.push(PlanProblem { row: i + 1, video_id: row.video_id.clone(), message });
+  let mut seen = HashSet::new();
+  let ids: Vec<String> = rows.iter().map(|r| r.video_id.clone()).filter(|id| !id.is_empty() && seen.insert(id.clone())).collect();
+  let remote = videos::fetch(dir, &ids, "status").await?;
+  let now = Utc::now();
+  let mut seen = HashSet::new();
+  for (i, row) in rows.iter().enumerate() {
+    if row.video_id.is_empty() {
+      push(i, row, "Brak video_id".into());
+      continue;
+    }
+    if !seen.insert(&row.video_id) {
+      push(i, row, "video_id powtarza się w planie".into());
+    }
+    let Some(current) = remote.get(&row.video_id) else {
+      push(i, row, "Nie znaleziono wideo".into());
+      continue;
+    };
+    for message in row_problems(row, current, now) {
+      push(i, row, message);
+    }
+  }
+  Ok(problems)
+}
+
+/// `current` (a fresh `snippet,status` resource) with the row's changes
+/// applied, as the body of `videos.update`.
+fn updated_resource(row: &PlanRow, current: &Value) -> Value {
+  let mut snippet = videos::snippet_for_update(&current["snippet"]);
+  let mut status = videos::status_for_update(&current["status"]);
+  if let Some(t) = &row.title {
+    snippet["title"] = json!(t);
+  }
+  if let Some(d) = &row.description {
+    snippet["description"] = json!(d);
+  }
+  if let Some(tags) = &row.tags {
+    snippet["tags"] = json!(tags);
+  }
+  if let Some(p) = &row.privacy {
+    status["privacyStatus"] = json!(p);
+  }
+  if let Some(at) = &row.publish_at {
+    status["publishAt"] = json!(at);
+  }
+  json!({ "id": row.video_id, "snippet": snippet, "status": status })
+}
+
+/// Whether the row asks for anything the video doesn't already have.
+fn differs(row: &PlanRow, current: &Value) -> bool {
+  let same_time = |a: &str, b: Option<&str>| {
+    let parse = |s: &str| DateTime::parse_from_rfc3339(s).ok();
+    b.is_some_and(|b| parse(a).is_some() && parse(a) == parse(b))
+  };
+  let snippet = &current["snippet"];
+  let tags: Vec<&str> = snippet["tags"].as_array().map(|t| t.iter().filter_map(Value::as_str).collect()).unwrap_or_default();
+  row.title.as_deref().is_some_and(|t| snippet["title"].as_str() != Some(t))
+    || row.description.as_deref().is_some_and(|d| snippet["description"].as_str().unwrap_or_default() != d)
+    || row.tags.as_ref().is_some_and(|t| t.iter().map(String::as_str).ne(tags.iter().copied()))
+    || row.privacy.as_deref().is_some_and(|p| current["status"]["privacyStatus"].as_str() != Some(p))
+    || row.publish_at.as_deref().is_some_and(|at| !same_time(at, current["status"]["publishAt"].as_str()))
+}
+
+/// When the video goes public with the row applied; now for one already
+/// public or without a schedule.
+fn public_at(row: &PlanRow, current: &Value) -> DateTime<Utc> {
+  let at = row.publish_at.as_deref().or(current["status"]["publishAt"].as_str());
+  at.and_then(|at| DateTime::parse_from_rfc3339(at).ok()).map_or_else(Utc::now, |at| at.to_utc())
+}
+
+async fn apply_row(
+  dir: &Path,
+  row: &PlanRow,
+  only_diff: bool,
+  link_cfg: Option<&VideoLinksConfig>,
+  cache: &mut LinkCache,
+) -> Result<(RowStatus, Vec<VideoLink>), Error> {
+  // Read right before writing: the plan may be older than the video's state.
+  let current = videos::fetch(dir, std::slice::from_ref(&row.video_id), "snippet,status")
+    .await?
+    .remove(&row.video_id)
+    .ok_or_else(|| Error::Validation(format!("Nie znaleziono wideo {}", row.video_id)))?;
+  let mut row = row.clone();
+  let mut links = Vec::new();
+  if let (Some(cfg), Some(description)) = (link_cfg, &row.description) {
+    let report = video_links::resolve_with(dir, description, cfg, public_at(&row, &current), cache).await?;
+    row.description = Some(report.description);
+    links = report.links;
+  }
+  if only_diff && !differs(&row, &current) {
+    return Ok((RowStatus::Unchanged, links));
+  }
+  videos::update_with_links(dir, "snippet,status", updated_resource(&row, &current), links.clone()).await?;
+  Ok((RowStatus::Succeeded, links))
+}
+
+/// Validates the whole plan at `path` and, when nothing is wrong, applies it
+/// row by row. In dry-run mode each write is reported as `planned`.
+pub async fn apply_metadata_plan(dir: &Path, path: &Path, options: &PlanOptions) -> Result<PlanReport, Error> {
+  api::ensure_writable(dir)?;
+  let rows = read_plan(path)?;
+  let problems = validate_plan(dir, &rows).await?;
+  if !problems.is_empty() {
+    return Ok(PlanReport { problems, ..Default::default() });
+  }
+  let interval = Duration::from_millis(options.min_interval_ms.unwrap_or(DEFAULT_MIN_INTERVAL_MS));
+  let rt = runtime::for_dir(dir);
+  let link_cfg = video_links::configured(dir);
+  let mut cache = LinkCache::default();
+  let mut report = PlanReport::default();
+  for (i, row) in rows.iter().enumerate() {
+    let (status, error, plan, video_links) = match apply_row(dir, row, options.only_diff, link_cfg.as_ref(), &mut cache).await {
+      Ok((status, links)) => (status, None, None, links),
+      Err(Error::DryRun(plan)) => (RowStatus::Planned, None, Some(plan), Vec::new()),
+      Err(e) => (RowStatus::Failed, Some(e.to_string()), None, Vec::new()),
+    };
+    match status {
+      RowStatus::Succeeded => report.succeeded += 1,
+      RowStatus::Failed => report.failed += 1,
+      RowStatus::Unchanged => report.unchanged += 1,
+      RowStatus::Planned => report.planned += 1,
+    }
+    rt.emit("metadata-plan-progress", PlanProgress { row: i + 1, total: rows.len(), video_id: row.video_id.clone(), status });
+    report.rows.push(RowOutcome { row: i + 1, video_id: row.video_id.clone(), status, error, plan, video_links });
+    let wrote = matches!(status, RowStatus::Succeeded | RowStatus::Failed);
+    if wrote && i + 1 < rows.len() {
+      tokio::time::sleep(interval).await;
+    }
+  }
+  Ok(report)
+}
@@ -0,0 +1,349 @@
+//! Loopback server receiving the OAuth redirect. It only answers
+//! `GET /callback` from a loopback peer carrying the flow's `state`, handles
+//! one login per start, and never reflects input unescaped. A flow keeps the port it was started on
+//! until it completes or is cancelled; a changed `redirect_port` applies to
+//! the next one.
+
+use crate::{
+  error::Error,
+  exchange_and_persist,
+  format::{self, Language},
+  guidance::{self, ConsentGuidance},
+  now_secs, profiles, read_config_from_dir, runtime, setup, update_config_in_dir, AppConfig, CALLBACK_PORT,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  convert::Infallible,
+  net::{Ipv4Addr, SocketAddr, TcpListener},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+};
+use tokio::sync::oneshot;
+use warp::{
+  http::{header, HeaderValue, Method, StatusCode},
+  path::FullPath,
+  reply::Response,
+  Filter, Reply,
+};
+
+/// Google's redirect carries a code, scope and maybe state; anything far
+/// longer is not from Google.
+const MAX_QUERY_LEN: usize = 4096;
+
+/// Per-login guard: set once a callback with a code was accepted.
+pub struct CallbackState {
+  handled: AtomicBool,
+  /// Google refused the login or the code exchange failed.
+  failed: AtomicBool,
+  /// The `state` the consent URL carried. Any page can send the browser to
+  /// the loopback port, so a callback without it is someone else's.
+  oauth_state: Mutex<String>,
+}
+
+impl CallbackState {
+  pub fn new(oauth_state: String) -> Self {
+    CallbackState { handled: AtomicBool::new(false), failed: AtomicBool::new(false), oauth_state: Mutex::new(oauth_state) }
+  }
+
+  fn oauth_state(&self) -> String {
+    self.oauth_state.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+}
+
+/// A fresh state per flow.
+impl Default for CallbackState {
+  fn default() -> Self {
+    CallbackState::new(new_oauth_state())
+  }
+}
+
+/// 32 random bytes, hex.
+fn new_oauth_state() -> String {
+  use rand_core::RngCore;
+  let mut bytes = [0u8; 32];
+  rand_core::OsRng.fill_bytes(&mut bytes);
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) struct CallbackServer {
+  state: Arc<CallbackState>,
+  shutdown: oneshot::Sender<()>,
+  port: u16,
+  started_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowState {
+  /// Waiting for the browser to come back.
+  Waiting,
+  /// The code arrived and is being exchanged for tokens.
+  Exchanging,
+  /// The login was refused or the exchange failed; `start_oauth` re-arms it.
+  Failed,
+}
+
+/// The login in progress, from `get_active_flow`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ActiveFlow {
+  pub redirect_uri: String,
+  pub port: u16,
+  pub age_secs: u64,
+  pub state: FlowState,
+}
+
+/// The port the next flow listens on: `redirect_port`, or 14321.
+pub(crate) fn configured_port(cfg: &AppConfig) -> u16 {
+  cfg.redirect_port.unwrap_or(CALLBACK_PORT)
+}
+
+pub(crate) fn redirect_uri(port: u16) -> String {
+  format!("http://127.0.0.1:{}/callback", port)
+}
+
+/// The port of the flow in progress, else the configured one: what a
+/// consent URL and the code exchange must name.
+pub(crate) fn flow_port(dir: &Path) -> u16 {
+  match runtime::for_dir(dir).callback_server().as_ref() {
+    Some(s) => s.port,
+    None => read_config_from_dir(dir).map_or(CALLBACK_PORT, |c| configured_port(&c)),
+  }
+}
+
+/// The `state` of the flow in progress, for its consent URL.
+pub fn flow_state(dir: &Path) -> Option<String> {
+  runtime::for_dir(dir).callback_server().as_ref().map(|s| s.state.oauth_state())
+}
+
+pub fn active_flow(dir: &Path) -> Option<ActiveFlow> {
+  let rt = runtime::for_dir(dir);
+  let server = rt.callback_server();
+  let s = server.as_ref()?;
+  let state = if s.state.failed.load(Ordering::SeqCst) {
+    FlowState::Failed
+  } else if s.state.handled.load(Ordering::SeqCst) {
+    FlowState::Exchanging
+  } else {
+    FlowState::Waiting
+  };
+  Some(ActiveFlow { redirect_uri: redirect_uri(s.port), port: s.port, age_secs: now_secs().saturating_sub(s.started_at), state })
+}
+
+/// Fails when nothing could listen on `port` now, found by binding it and
+/// letting go at once. The port of the flow in progress is ours and passes.
+pub fn check_port(dir: &Path, port: u16) -> Result<(), Error> {
+  if port == 0 {
+    return Err(Error::Validation("redirect_port musi być od 1 do 65535".into()));
+  }
+  if runtime::for_dir(dir).callback_server().as_ref().is_some_and(|s| s.port == port) {
+    return Ok(());
+  }
+  TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+    .map(drop)
+    .map_err(|e| Error::Validation(format!("Port {} jest niedostępny ({}) — wybierz inny redirect_port", port, e)))
+}
+
+/// Saves `redirect_port` (`None` for 14321) once [`check_port`] passes.
+/// A flow in progress keeps its port. Returns the port of the next flow.
+pub fn set_port(dir: &Path, port: Option<u16>) -> Result<u16, Error> {
+  let next = port.unwrap_or(CALLBACK_PORT);
+  check_port(dir, next)?;
+  update_config_in_dir(dir, |cfg| cfg.redirect_port = port)?;
+  setup::notify(dir);
+  Ok(next)
+}
+
+/// Payload of the `oauth-callback` event.
+#[derive(Serialize, Debug, Clone)]
+pub struct CallbackOutcome {
+  pub ok: bool,
+  pub error: Option<String>,
+  /// What to tell the user when Google refused the login.
+  pub guidance: Option<ConsentGuidance>,
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '"' => out.push_str("&quot;"),
+      '\'' => out.push_str("&#x27;"),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+/// `title` and `message` are escaped here; callers pass raw text.
+fn page(status: StatusCode, title: &str, message: &str) -> Response {
+  let html = format!(
+    "<!doctype html><html><head><meta charset=\"utf-8\"><title>{0}</title></head><body><h1>{0}</h1><p>{1}</p></body></html>",
+    escape_html(title),
+    escape_html(message)
+  );
+  let mut resp = warp::reply::html(html).into_response();
+  *resp.status_mut() = status;
+  resp.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+  resp
+}
+
+/// 302 to the configured success page, tagged with the profile so setups
+/// with several profiles can tell the logins apart.
+fn success_redirect(dir: &Path) -> Option<Response> {
+  let target = read_config_from_dir(dir)?.callback_success_redirect?;
+  let mut url = url::Url::parse(&target).ok().filter(|u| matches!(u.scheme(), "http" | "https"))?;
+  url.query_pairs_mut().append_pair("profile", &profiles::name_of(dir));
+  let mut resp = StatusCode::FOUND.into_response();
+  resp.headers_mut().insert(header::LOCATION, HeaderValue::from_str(url.as_str()).ok()?);
+  resp.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+  Some(resp)
+}
+
+/// Help page for a refused login: the entry's explanation and action, a
+/// link to the fix, and the raw values for support. Escaped like [`page`].
+fn guidance_page(status: StatusCode, g: &ConsentGuidance, lang: Language) -> Response {
+  let (lang_tag, link_label, details_label) = match lang {
+    Language::Pl => ("pl", "Otwórz pomoc", "Szczegóły"),
+    Language::En => ("en", "Open help", "Details"),
+  };
+  let raw = [Some(g.error.as_str()), g.error_subtype.as_deref(), g.description.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" — ");
+  let action = g.action.as_deref().map(|a| format!("<p>{}</p>", escape_html(a))).unwrap_or_default();
+  let html = format!(
+    "<!doctype html><html lang=\"{0}\"><head><meta charset=\"utf-8\"><title>{1}</title></head><body><main><h1>{1}</h1>\
+     <p role=\"alert\">{2}</p>{3}<p><a href=\"{4}\">{5}</a></p><p><small>{6}: {7}</small></p></main></body></html>",
+    lang_tag,
+    escape_html(&g.title),
+    escape_html(&g.explanation),
+    action,
+    escape_html(&g.help_url),
+    link_label,
+    details_label,
+    escape_html(&raw),
+  );
+  let mut resp = warp::reply::html(html).into_response();
+  *resp.status_mut() = status;
+  resp.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+  resp
+}
+
+fn not_found() -> Response {
+  page(StatusCode::NOT_FOUND, "Nie znaleziono", "")
+}
+
+async fn handle(dir: &Path, state: &CallbackState, method: Method, path: FullPath, peer: Option<SocketAddr>, query: String) -> Response {
+  if !peer.is_some_and(|p| p.ip().is_loopback()) {
+    return page(StatusCode::FORBIDDEN, "Odmowa dostępu", "Dozwolone tylko połączenia z tego komputera");
+  }
+  if method != Method::GET || path.as_str() != "/callback" {
+    return not_found();
+  }
+  if query.len() > MAX_QUERY_LEN {
+    return page(StatusCode::URI_TOO_LONG, "Nieprawidłowe żądanie", "Zbyt długie parametry");
+  }
+  let param = |name: &str| url::form_urlencoded::parse(query.as_bytes()).find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+  // Checked before anything else, so a forged callback can neither log in
+  // with another account's code nor fail the real flow with an `error`.
+  if param("state") != Some(state.oauth_state()) {
+    return page(
+      StatusCode::BAD_REQUEST,
+      "Nieprawidłowe żądanie",
+      "Parametr state nie pasuje do logowania w toku — zacznij logowanie w aplikacji",
+    );
+  }
+  if let Some(error) = param("error") {
+    let lang = format::language(dir);
+    let g = guidance::for_callback(&error, param("error_subtype").as_deref(), param("error_description").as_deref(), lang);
+    let g = guidance::on_port(g, flow_port(dir));
+    let resp = guidance_page(StatusCode::BAD_REQUEST, &g, lang);
+    guidance::report_restriction(dir, &g);
+    state.failed.store(true, Ordering::SeqCst);
+    runtime::for_dir(dir).emit("oauth-callback", CallbackOutcome { ok: false, error: Some(error), guidance: Some(g) });
+    return resp;
+  }
+  let Some(code) = param("code").filter(|c| !c.is_empty()) else {
+    return page(StatusCode::BAD_REQUEST, "Nieprawidłowe żądanie", "Brak parametru code");
+  };
+  if state.handled.swap(true, Ordering::SeqCst) {
+    // A reload after a failed exchange: the code is spent, but this login didn't happen.
+    if state.failed.load(Ordering::SeqCst) {
+      return page(StatusCode::BAD_GATEWAY, "Logowanie nieudane", "To logowanie się nie powiodło — zacznij je ponownie w aplikacji");
+    }
+    return page(StatusCode::CONFLICT, "Już zalogowano", "To logowanie zostało już obsłużone — zamknij tę kartę");
+  }
+  match exchange_and_persist(dir, &code).await {
+    Ok(_) => {
+      runtime::for_dir(dir).emit("oauth-callback", CallbackOutcome { ok: true, error: None, guidance: None });
+      stop(dir);
+      success_redirect(dir).unwrap_or_else(|| page(StatusCode::OK, "Zalogowano", "Możesz zamknąć tę kartę i wrócić do aplikacji"))
+    }
+    // Never redirected: the error text shouldn't end up in a third party's logs.
+    Err(Error::ConsentFailed(g) | Error::WorkspaceRestricted { guidance: g, .. }) => {
+      state.failed.store(true, Ordering::SeqCst);
+      let resp = guidance_page(StatusCode::BAD_GATEWAY, &g, format::language(dir));
+      runtime::for_dir(dir).emit("oauth-callback", CallbackOutcome { ok: false, error: Some(g.error.clone()), guidance: Some(*g) });
+      resp
+    }
+    Err(e) => {
+      state.failed.store(true, Ordering::SeqCst);
+      runtime::for_dir(dir).emit("oauth-callback", CallbackOutcome { ok: false, error: Some(e.code().to_string()), guidance: None });
+      page(StatusCode::BAD_GATEWAY, "Logowanie nieudane", &e.to_string())
+    }
+  }
+}
+
+/// The whole server as one infallible filter: every request is answered by
+/// [`handle`], so no warp rejection (405 and friends) leaks out.
+pub fn routes(dir: PathBuf, state: Arc<CallbackState>) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone {
+  let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+  warp::method().and(warp::path::full()).and(warp::addr::remote()).and(query).then(
+    move |method: Method, path: FullPath, peer: Option<SocketAddr>, query: String| {
+      let (dir, state) = (dir.clone(), state.clone());
+      async move { handle(&dir, &state, method, path, peer, query).await }
+    },
+  )
+}
+
+/// Starts listening for the redirect of a new login on the configured port,
+/// with a new `state`. When already running there, re-arms the one-login
+/// guard instead of binding again; a flow on a port the config no longer
+/// names is replaced.
+pub fn start(dir: &Path) -> Result<(), Error> {
+  let port = read_config_from_dir(dir).map_or(CALLBACK_PORT, |c| configured_port(&c));
+  let rt = runtime::for_dir(dir);
+  let mut server = rt.callback_server();
+  if let Some(s) = server.as_ref().filter(|s| s.port == port) {
+    s.state.handled.store(false, Ordering::SeqCst);
+    s.state.failed.store(false, Ordering::SeqCst);
+    *s.state.oauth_state.lock().unwrap_or_else(|e| e.into_inner()) = new_oauth_state();
+    return Ok(());
+  }
+  if let Some(old) = server.take() {
+    let _ = old.shutdown.send(());
+  }
+  let state = Arc::new(CallbackState::default());
+  let (tx, rx) = oneshot::channel::<()>();
+  let addr = (Ipv4Addr::LOCALHOST, port);
+  let (_, serving) = warp::serve(routes(dir.to_path_buf(), state.clone()))
+    .try_bind_with_graceful_shutdown(addr, async {
+      let _ = rx.await;
+    })
+    .map_err(|e| Error::Network(format!("Nie można nasłuchiwać na porcie {}: {}", port, e)))?;
+  tokio::spawn(serving);
+  *server = Some(CallbackServer { state, shutdown: tx, port, started_at: now_secs() });
+  Ok(())
+}
+
+/// Stops the flow in progress, if any; returns whether there was one.
+pub fn stop(dir: &Path) -> bool {
+  let Some(s) = runtime::for_dir(dir).callback_server().take() else {
+    return false;
+  };
+  let _ = s.shutdown.send(());
+  true
+}
@@ -0,0 +1,146 @@
+//! What the current auth can do, per feature, so the UI can grey out a
+//! button with the reason instead of letting the call fail. [`compute`] is
+//! pure over [`CapabilityInputs`]; [`notify`] re-sends the result as
+//! `capabilities-changed` whenever a token, config, quota or read-only
+//! change alters it. The quota day rolling over changes nothing on disk, so
+//! that one shows up on the next `get_capabilities` or change.
+
+use crate::{
+  api, auth, comments, live, playlists, quota, read_config_from_dir, read_tokens_from_dir, reconsent, runtime, scopes, thumbnails, upload,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const LIST_VIDEOS_SCOPES: &[&str] = &[scopes::YOUTUBE_READONLY];
+const ANALYTICS_SCOPES: &[&str] = &[scopes::YT_ANALYTICS_READONLY];
+
+/// Why a feature isn't available; the values match `CommandError.code`
+/// where the failing call would report the same.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReasonCode {
+  ReauthRequired,
+  InsufficientScope,
+  /// No channel picked yet (`select_channel`).
+  ChannelNotSelected,
+  ReadOnlyMode,
+  QuotaExceeded,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Capability {
+  pub available: bool,
+  /// The first thing in the way, checked in declaration order of [`ReasonCode`].
+  pub reason_code: Option<ReasonCode>,
+  /// Scopes to ask for with `request_scopes`; set with `INSUFFICIENT_SCOPE`.
+  pub missing_scopes: Vec<String>,
+}
+
+/// Payload of `get_capabilities` and the `capabilities-changed` event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+  pub list_videos: Capability,
+  pub upload: Capability,
+  pub set_thumbnail: Capability,
+  pub manage_playlists: Capability,
+  pub comments: Capability,
+  pub live: Capability,
+  pub analytics: Capability,
+  /// `AuthStatus.needs_reconsent`: the missing scopes above come from a
+  /// config change and `reconsent` restores them.
+  pub needs_reconsent: bool,
+}
+
+/// Everything [`compute`] looks at.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapabilityInputs {
+  /// Tokens exist and are unexpired or refreshable (`AuthStatus.authenticated`).
+  pub authenticated: bool,
+  pub granted_scopes: Vec<String>,
+  pub channel_selected: bool,
+  /// `content_owner_id` is set: writes also need youtubepartner.
+  pub content_owner: bool,
+  pub read_only_mode: bool,
+  pub quota_remaining: u32,
+  pub needs_reconsent: bool,
+}
+
+/// What a feature's cheapest meaningful call needs.
+struct Needs {
+  scopes: &'static [&'static str],
+  /// Acts on the selected channel rather than on ids the user passes in.
+  channel: bool,
+  writes: bool,
+  /// Data API units; analytics has its own quota.
+  cost: u32,
+}
+
+fn check(inputs: &CapabilityInputs, needs: Needs) -> Capability {
+  let mut required = needs.scopes.to_vec();
+  if needs.writes && inputs.content_owner {
+    required.push(scopes::YOUTUBE_PARTNER);
+  }
+  let missing_scopes: Vec<String> = required
+    .iter()
+    .filter(|r| !inputs.granted_scopes.iter().any(|h| scopes::covers(h, r)))
+    .map(|r| r.to_string())
+    .collect();
+  let reason_code = if !inputs.authenticated {
+    Some(ReasonCode::ReauthRequired)
+  } else if !missing_scopes.is_empty() {
+    Some(ReasonCode::InsufficientScope)
+  } else if needs.channel && !inputs.channel_selected {
+    Some(ReasonCode::ChannelNotSelected)
+  } else if needs.writes && inputs.read_only_mode {
+    Some(ReasonCode::ReadOnlyMode)
+  } else if needs.cost > inputs.quota_remaining {
+    Some(ReasonCode::QuotaExceeded)
+  } else {
+    None
+  };
+  let missing_scopes = if reason_code == Some(ReasonCode::InsufficientScope) { missing_scopes } else { Vec::new() };
+  Capability { available: reason_code.is_none(), reason_code, missing_scopes }
+}
+
+/// The capability of every feature given `inputs`; no I/O.
+pub fn compute(inputs: &CapabilityInputs) -> Capabilities {
+  let needs = |scopes, channel, writes, cost| check(inputs, Needs { scopes, channel, writes, cost });
+  Capabilities {
+    list_videos: needs(LIST_VIDEOS_SCOPES, true, false, 1),
+    upload: needs(upload::UPLOAD_SCOPES, false, true, upload::INSERT_COST),
+    set_thumbnail: needs(thumbnails::THUMBNAIL_SCOPES, false, true, 50),
+    manage_playlists: needs(playlists::PLAYLIST_WRITE_SCOPES, true, true, 50),
+    comments: needs(comments::COMMENT_SCOPES, true, true, 50),
+    live: needs(live::LIVE_SCOPES, false, true, 50),
+    analytics: needs(ANALYTICS_SCOPES, true, false, 0),
+    needs_reconsent: inputs.needs_reconsent,
+  }
+}
+
+/// The inputs as they are for the config in `dir`.
+pub fn inputs(dir: &Path) -> CapabilityInputs {
+  let cfg = read_config_from_dir(dir);
+  CapabilityInputs {
+    authenticated: auth::status(dir).authenticated,
+    granted_scopes: read_tokens_from_dir(dir).map(|t| scopes::granted(&t)).unwrap_or_default(),
+    channel_selected: cfg.as_ref().and_then(|c| c.channel_id.as_deref()).is_some_and(|id| !id.is_empty()),
+    content_owner: cfg.is_some_and(|c| c.content_owner_id.is_some()),
+    read_only_mode: api::read_only_enabled(dir),
+    quota_remaining: quota::status(dir).remaining,
+    needs_reconsent: reconsent::pending(dir).is_some(),
+  }
+}
+
+pub fn current(dir: &Path) -> Capabilities {
+  compute(&inputs(dir))
+}
+
+/// Emits `capabilities-changed` when the result differs from the last one
+/// sent (or is the first).
+pub(crate) fn notify(dir: &Path) {
+  let now = current(dir);
+  let rt = runtime::for_dir(dir);
+  if rt.replace_capabilities(now.clone()).as_ref() != Some(&now) {
+    rt.emit("capabilities-changed", &now);
+  }
+}
@@ -0,0 +1,116 @@
+use crate::{error::Error, now_secs, observe::Exchange, redact, runtime};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::VecDeque,
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+const RING_SIZE: usize = 100;
+/// Capture switches itself off after this long.
+const AUTO_DISABLE: Duration = Duration::from_secs(30 * 60);
+const BODY_MAX_CHARS: usize = 2000;
+
+/// One request/response pair. Request headers and bodies are never kept:
+/// they carry the bearer token, client_secret and refresh tokens.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HttpExchange {
+  pub at: u64,
+  pub method: String,
+  pub url: String,
+  pub status: Option<u16>,
+  pub latency_ms: u64,
+  pub response_body: String,
+  pub error: Option<String>,
+  /// Above 1 when the request was replayed after a 401 and a token refresh.
+  #[serde(default = "first_attempt")]
+  pub attempt: u32,
+  /// The `X-Client-Trace-Id` sent, to find the request in proxy logs.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub trace_id: String,
+}
+
+fn first_attempt() -> u32 {
+  1
+}
+
+#[derive(Default)]
+pub(crate) struct CaptureBuffer {
+  until: Option<Instant>,
+  ring: VecDeque<HttpExchange>,
+}
+
+impl CaptureBuffer {
+  fn active(&mut self) -> bool {
+    if self.until.is_some_and(|u| Instant::now() >= u) {
+      self.until = None;
+    }
+    self.until.is_some()
+  }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CaptureStatus {
+  pub enabled: bool,
+  /// Seconds until capture turns itself off.
+  pub expires_in_secs: Option<u64>,
+  pub entries: usize,
+}
+
+/// Appends an exchange when capture is on. The body is redacted before it is
+/// truncated so a cut can't leave part of a secret behind.
+pub(crate) fn record(dir: &Path, e: &Exchange) {
+  let rt = runtime::for_dir(dir);
+  let mut buf = rt.capture();
+  if !buf.active() {
+    return;
+  }
+  let redacted = redact::text(e.body);
+  let response_body = if redacted.chars().count() > BODY_MAX_CHARS {
+    format!("{}…", redacted.chars().take(BODY_MAX_CHARS).collect::<String>())
+  } else {
+    redacted
+  };
+  if buf.ring.len() == RING_SIZE {
+    buf.ring.pop_front();
+  }
+  buf.ring.push_back(HttpExchange {
+    at: now_secs(),
+    method: e.method.to_string(),
+    url: redact::url(e.url),
+    status: e.status,
+    latency_ms: e.started.elapsed().as_millis() as u64,
+    response_body,
+    error: e.error.map(|err| redact::text(&err.to_string())),
+    attempt: e.attempt,
+    trace_id: e.trace_id.to_string(),
+  });
+}
+
+pub fn status(dir: &Path) -> CaptureStatus {
+  let rt = runtime::for_dir(dir);
+  let mut buf = rt.capture();
+  let enabled = buf.active();
+  let expires_in_secs = buf.until.map(|u| u.saturating_duration_since(Instant::now()).as_secs());
+  CaptureStatus { enabled, expires_in_secs, entries: buf.ring.len() }
+}
+
+/// Turning capture on (re)starts the 30 minute window; turning it off keeps
+/// what was recorded so it can still be exported.
+pub fn set_enabled(dir: &Path, enabled: bool) -> CaptureStatus {
+  runtime::for_dir(dir).capture().until = enabled.then(|| Instant::now() + AUTO_DISABLE);
+  status(dir)
+}
+
+/// Recorded exchanges, oldest first.
+pub fn entries(dir: &Path) -> Vec<HttpExchange> {
+  runtime::for_dir(dir).capture().ring.iter().cloned().collect()
+}
+
+pub fn export(dir: &Path, path: &Path) -> Result<usize, Error> {
+  let entries = entries(dir);
+  let s = serde_json::to_string_pretty(&entries).map_err(|e| Error::Storage(e.to_string()))?;
+  fs::write(path, s).map_err(|e| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e)))?;
+  Ok(entries.len())
+}
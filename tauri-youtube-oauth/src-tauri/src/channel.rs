@@ -0,0 +1,392 @@
+use crate::{
+  api::{self, ApiCall},
+  error::Error,
+  http_headers, read_config_from_dir, read_tokens_from_dir, scopes, setup,
+  timeouts::{self, OperationClass},
+  tls, update_config_in_dir,
+};
+use imagesize::ImageType;
+use reqwest::Method;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fs, path::Path};
+
+pub(crate) const BRANDING_SCOPES: &[&str] = &[scopes::YOUTUBE];
+
+const BANNER_MIN_WIDTH: usize = 2048;
+const BANNER_MIN_HEIGHT: usize = 1152;
+const BANNER_MAX_BYTES: u64 = 6 * 1024 * 1024;
+const DESCRIPTION_MAX_CHARS: usize = 1000;
+
+/// A local image that passed the pre-upload checks, ready to send.
+pub(crate) struct ProbedImage {
+  pub content_type: &'static str,
+  pub width: usize,
+  pub height: usize,
+  pub data: Vec<u8>,
+}
+
+/// Reads `path` and checks its size and format without trusting the file
+/// extension, so bad images are rejected before spending quota.
+pub(crate) fn probe_image(path: &Path, max_bytes: u64, allowed: &[ImageType]) -> Result<ProbedImage, Error> {
+  let meta = fs::metadata(path).map_err(|e| Error::Validation(format!("Nie można odczytać {}: {}", path.display(), e)))?;
+  if meta.len() > max_bytes {
+    return Err(Error::Validation(format!(
+      "Plik {} ma {} B, limit to {} B",
+      path.display(),
+      meta.len(),
+      max_bytes
+    )));
+  }
+  let data = fs::read(path).map_err(|e| Error::Storage(e.to_string()))?;
+  let kind = imagesize::image_type(&data).map_err(|_| Error::Validation(format!("{} nie jest rozpoznawalnym obrazem", path.display())))?;
+  let content_type = match kind {
+    ImageType::Png => "image/png",
+    ImageType::Jpeg => "image/jpeg",
+    ImageType::Gif => "image/gif",
+    ImageType::Bmp => "image/bmp",
+    _ => "application/octet-stream",
+  };
+  if !allowed.contains(&kind) {
+    return Err(Error::Validation(format!("Nieobsługiwany format obrazu {} ({})", path.display(), content_type)));
+  }
+  let size = imagesize::blob_size(&data).map_err(|e| Error::Validation(format!("Nie można odczytać wymiarów obrazu: {}", e)))?;
+  Ok(ProbedImage { content_type, width: size.width, height: size.height, data })
+}
+
+pub(crate) fn tokeninfo_endpoint() -> String {
+  std::env::var("OAUTH_TOKENINFO_URL").unwrap_or_else(|_| "https://oauth2.googleapis.com/tokeninfo".to_string())
+}
+
+/// Email of the signed-in Google account. Only known when the token carries
+/// an email scope, so this is best effort.
+async fn account_email(dir: &Path) -> Option<String> {
+  let t = read_tokens_from_dir(dir)?;
+  let url = tokeninfo_endpoint();
+  let req = tls::request(dir, Method::GET, &url, &http_headers::trace_id())
+    .ok()?
+    .query(&[("access_token", &t.access_token)])
+    .timeout(timeouts::for_class(dir, OperationClass::Auth));
+  let resp = req.send().await.ok()?;
+  let json: Value = resp.json().await.ok()?;
+  json["email"].as_str().map(str::to_string)
+}
+
+/// `channels.list?mine=true`, failing with `NoChannelForAccount` when the
+/// Google account has never created a channel (an empty list, not an error).
+pub async fn list_mine(dir: &Path, part: &str) -> Result<Value, Error> {
+  let call = ApiCall::get("/youtube/v3/channels").query("part", part).query("mine", "true");
+  let resp = api::execute(dir, call).await?;
+  let empty = resp.pointer("/pageInfo/totalResults").and_then(Value::as_u64) == Some(0)
+    || resp["items"].as_array().is_none_or(|a| a.is_empty());
+  if empty {
+    return Err(Error::NoChannelForAccount { email: account_email(dir).await });
+  }
+  Ok(resp)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+  pub url: String,
+  #[serde(default)]
+  pub width: Option<u32>,
+  #[serde(default)]
+  pub height: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelStatistics {
+  pub view_count: Option<u64>,
+  /// Absent when the owner hides it (`hidden_subscriber_count`).
+  pub subscriber_count: Option<u64>,
+  pub hidden_subscriber_count: bool,
+  pub video_count: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Channel {
+  pub id: String,
+  pub title: String,
+  pub description: String,
+  pub custom_url: Option<String>,
+  /// Keyed by size name: `default`, `medium`, `high`.
+  pub thumbnails: BTreeMap<String, Thumbnail>,
+  pub statistics: Option<ChannelStatistics>,
+  pub uploads_playlist_id: Option<String>,
+}
+
+/// `channels` resource as the API sends it; fields we don't use are ignored.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChannelResource {
+  id: String,
+  #[serde(default)]
+  snippet: ChannelSnippet,
+  #[serde(default)]
+  content_details: Option<ChannelContentDetails>,
+  #[serde(default)]
+  statistics: Option<StatisticsResource>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ChannelSnippet {
+  #[serde(default)]
+  title: String,
+  #[serde(default)]
+  description: String,
+  #[serde(default)]
+  custom_url: Option<String>,
+  #[serde(default)]
+  thumbnails: BTreeMap<String, Thumbnail>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChannelContentDetails {
+  #[serde(default)]
+  related_playlists: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatisticsResource {
+  #[serde(default, deserialize_with = "count")]
+  view_count: Option<u64>,
+  #[serde(default, deserialize_with = "count")]
+  subscriber_count: Option<u64>,
+  #[serde(default)]
+  hidden_subscriber_count: bool,
+  #[serde(default, deserialize_with = "count")]
+  video_count: Option<u64>,
+}
+
+/// The API sends counts as decimal strings (they can exceed 2^53).
+fn count<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Count {
+    Text(String),
+    Number(u64),
+  }
+  Ok(match Option::<Count>::deserialize(d)? {
+    Some(Count::Text(s)) => s.parse().ok(),
+    Some(Count::Number(n)) => Some(n),
+    None => None,
+  })
+}
+
+impl From<ChannelResource> for Channel {
+  fn from(r: ChannelResource) -> Self {
+    Channel {
+      id: r.id,
+      title: r.snippet.title,
+      description: r.snippet.description,
+      custom_url: r.snippet.custom_url.filter(|u| !u.is_empty()),
+      thumbnails: r.snippet.thumbnails,
+      statistics: r.statistics.map(|s| ChannelStatistics {
+        view_count: s.view_count,
+        subscriber_count: s.subscriber_count.filter(|_| !s.hidden_subscriber_count),
+        hidden_subscriber_count: s.hidden_subscriber_count,
+        video_count: s.video_count,
+      }),
+      uploads_playlist_id: r.content_details.and_then(|mut c| c.related_playlists.remove("uploads")),
+    }
+  }
+}
+
+/// Parses a `channels.list` response body into [`Channel`]s.
+pub fn parse_channels(resp: &Value) -> Result<Vec<Channel>, Error> {
+  let items = resp.get("items").cloned().unwrap_or_else(|| json!([]));
+  let resources: Vec<ChannelResource> = serde_json::from_value(items)
+    .map_err(|e| Error::Api { status: 200, reason: "unexpectedResponse".into(), message: format!("Nieoczekiwana odpowiedź channels.list: {}", e) })?;
+  Ok(resources.into_iter().map(Channel::from).collect())
+}
+
+/// The authenticated user's channels with snippet, content details and
+/// statistics, fetched in one `channels.list` call.
+pub async fn list_channels(dir: &Path) -> Result<Vec<Channel>, Error> {
+  parse_channels(&list_mine(dir, "snippet,contentDetails,statistics").await?)
+}
+
+/// The authenticated user's channel id and its full `brandingSettings`.
+async fn mine_branding(dir: &Path) -> Result<(String, Value), Error> {
+  let resp = list_mine(dir, "brandingSettings").await?;
+  let item = &resp["items"][0];
+  let id = item["id"].as_str().unwrap_or_default().to_string();
+  Ok((id, item.get("brandingSettings").cloned().unwrap_or_else(|| json!({}))))
+}
+
+/// `channels.update` replaces the whole part, so callers pass the settings
+/// they read earlier with only their own keys changed.
+async fn update_branding(dir: &Path, channel_id: &str, branding: Value) -> Result<Value, Error> {
+  let call = ApiCall::put("/youtube/v3/channels")
+    .query("part", "brandingSettings")
+    .json(json!({ "id": channel_id, "brandingSettings": branding }))
+    .scopes(BRANDING_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  Ok(resp.get("brandingSettings").cloned().unwrap_or(Value::Null))
+}
+
+fn set_key(branding: &mut Value, section: &str, key: &str, value: Value) {
+  if !branding.is_object() {
+    *branding = json!({});
+  }
+  let section = branding.as_object_mut().unwrap().entry(section).or_insert_with(|| json!({}));
+  if !section.is_object() {
+    *section = json!({});
+  }
+  section.as_object_mut().unwrap().insert(key.to_string(), value);
+}
+
+pub async fn set_channel_description(dir: &Path, text: &str) -> Result<Value, Error> {
+  api::ensure_writable(dir)?;
+  if text.chars().count() > DESCRIPTION_MAX_CHARS {
+    return Err(Error::Validation(format!("Opis kanału może mieć najwyżej {} znaków", DESCRIPTION_MAX_CHARS)));
+  }
+  if text.contains('<') || text.contains('>') {
+    return Err(Error::Validation("Opis kanału nie może zawierać znaków < ani >".into()));
+  }
+  let (id, mut branding) = mine_branding(dir).await?;
+  set_key(&mut branding, "channel", "description", json!(text));
+  update_branding(dir, &id, branding).await
+}
+
+/// Uploads a banner image and applies it to the channel. The two API calls
+/// aren't atomic: if applying fails, the uploaded image stays unused on
+/// Google's side and the channel keeps its previous banner.
+pub async fn upload_banner(dir: &Path, image_path: &Path) -> Result<String, Error> {
+  api::ensure_writable(dir)?;
+  let img = probe_image(image_path, BANNER_MAX_BYTES, &[ImageType::Jpeg, ImageType::Png, ImageType::Gif, ImageType::Bmp])?;
+  if img.width < BANNER_MIN_WIDTH || img.height < BANNER_MIN_HEIGHT {
+    return Err(Error::Validation(format!(
+      "Baner ma {}×{} px, wymagane minimum to {}×{} px",
+      img.width, img.height, BANNER_MIN_WIDTH, BANNER_MIN_HEIGHT
+    )));
+  }
+  let (id, mut branding) = mine_branding(dir).await?;
+  let call = ApiCall::post("/upload/youtube/v3/channelBanners/insert")
+    .query("uploadType", "media")
+    .bytes(img.content_type, img.data)
+    .scopes(BRANDING_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  let url = resp["url"]
+    .as_str()
+    .ok_or_else(|| Error::Api { status: 200, reason: "missingUrl".into(), message: "channelBanners.insert nie zwrócił adresu baneru".into() })?
+    .to_string();
+  set_key(&mut branding, "image", "bannerExternalUrl", json!(url));
+  update_branding(dir, &id, branding)
+    .await
+    .map_err(|e| e.context("Baner został przesłany, ale nie ustawiono go na kanale (kanał zachował poprzedni baner) — ponów próbę"))?;
+  Ok(url)
+}
+
+/// Id of the channel channel-level commands act on: the one picked with
+/// [`select_channel`], or the account's own channel, remembered on first use.
+pub async fn selected_channel_id(dir: &Path) -> Result<String, Error> {
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  if let Some(id) = cfg.channel_id.filter(|id| !id.is_empty()) {
+    return Ok(id);
+  }
+  let resp = list_mine(dir, "id").await?;
+  let id = resp["items"][0]["id"].as_str().unwrap_or_default().to_string();
+  update_config_in_dir(dir, |cfg| cfg.channel_id = Some(id.clone()))?;
+  setup::notify(dir);
+  Ok(id)
+}
+
+pub fn select_channel(dir: &Path, channel_id: &str) -> Result<(), Error> {
+  update_config_in_dir(dir, |cfg| cfg.channel_id = Some(channel_id.to_string()).filter(|id| !id.is_empty()))?;
+  setup::notify(dir);
+  Ok(())
+}
+
+const WATERMARK_MAX_BYTES: u64 = 1024 * 1024;
+/// Longest video YouTube accepts; no offset or duration can exceed it.
+const MAX_VIDEO_MS: u64 = 12 * 60 * 60 * 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OffsetType {
+  OffsetFromStart,
+  OffsetFromEnd,
+}
+
+/// When the watermark shows: `offset_ms` from the start or end of each video,
+/// for `duration_ms` (until the end of the video when absent).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WatermarkTiming {
+  pub offset_type: OffsetType,
+  #[serde(default)]
+  pub offset_ms: u64,
+  #[serde(default)]
+  pub duration_ms: Option<u64>,
+}
+
+impl WatermarkTiming {
+  fn validate(&self) -> Result<(), Error> {
+    if self.offset_ms > MAX_VIDEO_MS {
+      return Err(Error::Validation("Przesunięcie znaku wodnego przekracza maksymalną długość filmu (12 h)".into()));
+    }
+    match self.duration_ms {
+      Some(0) => Err(Error::Validation("Czas wyświetlania znaku wodnego musi być dodatni".into())),
+      Some(d) if d > MAX_VIDEO_MS => Err(Error::Validation("Czas wyświetlania znaku wodnego przekracza 12 h".into())),
+      // Counting back from the end, the watermark can't outlast the remaining time.
+      Some(d) if self.offset_type == OffsetType::OffsetFromEnd && d > self.offset_ms => Err(Error::Validation(
+        "Przy offsetFromEnd czas wyświetlania nie może być dłuższy niż przesunięcie od końca".into(),
+      )),
+      None if self.offset_type == OffsetType::OffsetFromEnd && self.offset_ms == 0 => Err(Error::Validation(
+        "Przy offsetFromEnd przesunięcie musi być dodatnie, inaczej znak wodny nigdy się nie pojawi".into(),
+      )),
+      _ => Ok(()),
+    }
+  }
+
+  fn to_resource(&self) -> Value {
+    let offset_type = match self.offset_type {
+      OffsetType::OffsetFromStart => "offsetFromStart",
+      OffsetType::OffsetFromEnd => "offsetFromEnd",
+    };
+    let mut timing = json!({ "type": offset_type, "offsetMs": self.offset_ms.to_string() });
+    if let Some(d) = self.duration_ms {
+      timing["durationMs"] = json!(d.to_string());
+    }
+    timing
+  }
+}
+
+/// A 403 on watermark calls means the token's account doesn't manage the channel.
+fn watermark_error(e: Error, channel_id: &str) -> Error {
+  match e {
+    Error::Api { status: 401, reason, .. } | Error::Forbidden { reason, .. } if reason != "quotaExceeded" => Error::Forbidden {
+      reason,
+      message: format!("Brak uprawnień do kanału {} — zaloguj się kontem, które nim zarządza, lub wybierz inny kanał", channel_id),
+    },
+    e => e,
+  }
+}
+
+pub async fn set_watermark(dir: &Path, image_path: &Path, timing: &WatermarkTiming) -> Result<(), Error> {
+  api::ensure_writable(dir)?;
+  timing.validate()?;
+  let img = probe_image(image_path, WATERMARK_MAX_BYTES, &[ImageType::Png, ImageType::Gif])?;
+  let channel_id = selected_channel_id(dir).await?;
+  let metadata = json!({
+    "timing": timing.to_resource(),
+    "position": { "type": "corner", "cornerPosition": "topRight" },
+  });
+  let (content_type, body) = api::multipart_related(&metadata, img.content_type, &img.data);
+  let call = ApiCall::post("/upload/youtube/v3/watermarks/set")
+    .query("channelId", channel_id.clone())
+    .query("uploadType", "multipart")
+    .bytes(&content_type, body)
+    .scopes(BRANDING_SCOPES);
+  api::execute(dir, call).await.map(|_| ()).map_err(|e| watermark_error(e, &channel_id))
+}
+
+pub async fn unset_watermark(dir: &Path) -> Result<(), Error> {
+  api::ensure_writable(dir)?;
+  let channel_id = selected_channel_id(dir).await?;
+  let call = ApiCall::post("/youtube/v3/watermarks/unset").query("channelId", channel_id.clone()).scopes(BRANDING_SCOPES);
+  api::execute(dir, call).await.map(|_| ()).map_err(|e| watermark_error(e, &channel_id))
+}
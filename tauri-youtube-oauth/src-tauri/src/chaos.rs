@@ -0,0 +1,100 @@
+//! Failure injection for checking the retry, backoff and queue-resume paths
+//! without a real outage. Only with the `chaos` feature, which release
+//! builds refuse. A fault is armed per config dir for the next requests
+//! whose URL (with query) matches a pattern, and is applied where requests
+//! leave the app: the API transport and the token endpoint. Injected
+//! failures are observed like real ones.
+
+use crate::{error::Error, runtime};
+use regex::Regex;
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{path::Path, time::Duration};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Fault {
+  /// Fails as a request that ran out its timeout does, at once.
+  Timeout,
+  /// The connection drops before any response.
+  ConnectionReset,
+  /// Answered with `status` without reaching the server. `body` defaults to
+  /// a Google-style error without a reason.
+  Status {
+    status: u16,
+    #[serde(default)]
+    retry_after_secs: Option<u64>,
+    #[serde(default)]
+    body: Option<String>,
+  },
+  /// The real request goes out `ms` late.
+  Delay { ms: u64 },
+}
+
+/// An armed fault and how many more requests it hits.
+pub(crate) struct Armed {
+  pattern: Regex,
+  fault: Fault,
+  remaining: u32,
+}
+
+/// Arms `fault` for the next `times` requests matching `url_pattern` (a
+/// regex). Faults armed earlier are tried first.
+pub fn inject(dir: &Path, url_pattern: &str, fault: Fault, times: u32) -> Result<(), Error> {
+  let pattern = Regex::new(url_pattern).map_err(|e| Error::Validation(format!("Nieprawidłowy wzorzec URL {}: {}", url_pattern, e)))?;
+  if let Fault::Status { status, .. } = fault {
+    if !(100..600).contains(&status) {
+      return Err(Error::Validation(format!("Nieprawidłowy status HTTP {}", status)));
+    }
+  }
+  if times > 0 {
+    runtime::for_dir(dir).chaos().push(Armed { pattern, fault, remaining: times });
+  }
+  Ok(())
+}
+
+/// Disarms every fault.
+pub fn clear(dir: &Path) {
+  runtime::for_dir(dir).chaos().clear();
+}
+
+/// Requests the armed faults will still hit.
+pub fn pending(dir: &Path) -> u32 {
+  runtime::for_dir(dir).chaos().iter().map(|a| a.remaining).sum()
+}
+
+fn take(dir: &Path, url: &str) -> Option<Fault> {
+  let rt = runtime::for_dir(dir);
+  let mut armed = rt.chaos();
+  let i = armed.iter().position(|a| a.pattern.is_match(url))?;
+  let fault = armed[i].fault.clone();
+  armed[i].remaining -= 1;
+  if armed[i].remaining == 0 {
+    armed.remove(i);
+  }
+  Some(fault)
+}
+
+/// The fault armed for `url`, if any: an error or a response to use instead
+/// of sending. `None` sends as usual, after the delay of a `Delay` fault.
+pub(crate) async fn intercept(dir: &Path, url: &str, operation: &str, timeout: Duration) -> Option<Result<(StatusCode, HeaderMap, String), Error>> {
+  match take(dir, url)? {
+    Fault::Timeout => Some(Err(Error::TimedOut { operation: operation.to_string(), after_secs: timeout.as_secs() })),
+    Fault::ConnectionReset => Some(Err(Error::Network(format!("{}: connection reset by peer (chaos)", operation)))),
+    Fault::Status { status, retry_after_secs, body } => {
+      let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+      let mut headers = HeaderMap::new();
+      if let Some(secs) = retry_after_secs {
+        headers.insert(reqwest::header::RETRY_AFTER, secs.into());
+      }
+      let text = body.unwrap_or_else(|| {
+        serde_json::json!({ "error": { "code": status.as_u16(), "message": "Injected fault", "errors": [] } }).to_string()
+      });
+      Some(Ok((status, headers, text)))
+    }
+    Fault::Delay { ms } => {
+      tokio::time::sleep(Duration::from_millis(ms)).await;
+      None
+    }
+  }
+}
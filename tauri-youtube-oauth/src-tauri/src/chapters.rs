@@ -0,0 +1,147 @@
+//! YouTube chapters: a `MM:SS Title` list in the description, starting at
+//! 00:00. The block sits under a marker line so it can be replaced in place
+//! without touching the rest of the description.
+
+use crate::{api, error::Error, read_config_from_dir, sidecar, videos};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{fs, path::Path};
+
+/// First line of the generated block; everything up to the first line that
+/// isn't a timestamp belongs to it.
+pub const MARKER: &str = "⏱ Rozdziały";
+/// YouTube ignores chapters when there are fewer or any is shorter.
+const MIN_CHAPTERS: usize = 3;
+const MIN_CHAPTER_SECS: u64 = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+  pub start_secs: u64,
+  pub title: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChapterPlacement {
+  #[default]
+  Append,
+  Prepend,
+}
+
+/// The formatted block and what YouTube would object to; warnings don't stop
+/// the block from being written.
+#[derive(Serialize, Debug, Clone)]
+pub struct ChapterBlock {
+  pub text: String,
+  pub warnings: Vec<String>,
+}
+
+fn timestamp(secs: u64, with_hours: bool) -> String {
+  if with_hours {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+  } else {
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+  }
+}
+
+/// `MM:SS`, `M:SS` or `H:MM:SS` followed by a space.
+fn is_timestamp_line(line: &str) -> bool {
+  let Some((stamp, _)) = line.trim_start().split_once(' ') else {
+    return false;
+  };
+  let parts: Vec<&str> = stamp.split(':').collect();
+  (2..=3).contains(&parts.len())
+    && parts.iter().all(|p| !p.is_empty() && p.len() <= 2 && p.bytes().all(|b| b.is_ascii_digit()))
+    && parts[1..].iter().all(|p| p.len() == 2)
+}
+
+/// Sorts the chapters and formats them. The first one is moved to 00:00;
+/// every timestamp gets hours once any chapter starts past the first hour.
+pub fn format(chapters: &[Chapter]) -> Result<ChapterBlock, Error> {
+  if chapters.is_empty() {
+    return Err(Error::Validation("Lista rozdziałów jest pusta".into()));
+  }
+  if let Some(i) = chapters.iter().position(|c| c.title.trim().is_empty()) {
+    return Err(Error::Validation(format!("Rozdział {} nie ma tytułu", i + 1)));
+  }
+  let mut sorted = chapters.to_vec();
+  sorted.sort_by_key(|c| c.start_secs);
+  let mut warnings = Vec::new();
+  if sorted[0].start_secs != 0 {
+    warnings.push(format!("Pierwszy rozdział zaczynał się od {} s, przesunięto go na 00:00", sorted[0].start_secs));
+    sorted[0].start_secs = 0;
+  }
+  if sorted.len() < MIN_CHAPTERS {
+    warnings.push(format!("YouTube wymaga co najmniej {} rozdziałów (jest {})", MIN_CHAPTERS, sorted.len()));
+  }
+  for pair in sorted.windows(2) {
+    let length = pair[1].start_secs - pair[0].start_secs;
+    if length < MIN_CHAPTER_SECS {
+      warnings.push(format!("Rozdział \"{}\" trwa {} s, YouTube wymaga co najmniej {} s", pair[0].title, length, MIN_CHAPTER_SECS));
+    }
+  }
+  let with_hours = sorted.last().is_some_and(|c| c.start_secs >= 3600);
+  let mut text = MARKER.to_string();
+  for c in &sorted {
+    text.push('\n');
+    text.push_str(&format!("{} {}", timestamp(c.start_secs, with_hours), c.title.trim()));
+  }
+  Ok(ChapterBlock { text, warnings })
+}
+
+/// Replaces the existing block in place, or adds `block` at `placement`.
+pub fn apply_to_description(description: &str, block: &str, placement: ChapterPlacement) -> String {
+  let lines: Vec<&str> = description.lines().collect();
+  if let Some(start) = lines.iter().position(|l| l.trim() == MARKER) {
+    let end = start + 1 + lines[start + 1..].iter().take_while(|l| is_timestamp_line(l)).count();
+    let mut out: Vec<&str> = lines[..start].to_vec();
+    out.extend(block.lines());
+    out.extend(&lines[end..]);
+    return out.join("\n");
+  }
+  match (description.trim().is_empty(), placement) {
+    (true, _) => block.to_string(),
+    (false, ChapterPlacement::Append) => format!("{}\n\n{}", description.trim_end(), block),
+    (false, ChapterPlacement::Prepend) => format!("{}\n\n{}", block, description.trim_start()),
+  }
+}
+
+/// `<stem>.chapters.json` next to the video, else the render's
+/// `chapters.json` in the same directory.
+pub fn read_sidecar(video: &Path) -> Result<Option<Vec<Chapter>>, Error> {
+  let own = video.with_extension("chapters.json");
+  let shared = video.with_file_name("chapters.json");
+  let Some(path) = [own, shared].into_iter().find(|p| p.is_file()) else {
+    return Ok(None);
+  };
+  let bytes = fs::read(&path).map_err(|e| Error::Storage(format!("{}: {}", path.display(), e)))?;
+  serde_json::from_str(&sidecar::decode(&path, bytes)?)
+    .map(Some)
+    .map_err(|e| Error::Validation(format!("Nieprawidłowy plik {}: {}", path.display(), e)))
+}
+
+fn placement(dir: &Path) -> ChapterPlacement {
+  read_config_from_dir(dir).and_then(|c| c.chapters_placement).unwrap_or_default()
+}
+
+/// Writes the chapter block into the video's description (read-modify-write
+/// of the snippet); text outside the block is kept as is.
+pub async fn set_chapters(dir: &Path, video_id: &str, chapters: &[Chapter]) -> Result<ChapterBlock, Error> {
+  api::ensure_writable(dir)?;
+  let block = format(chapters)?;
+  let video = videos::fetch(dir, &[video_id.to_string()], "snippet").await?.remove(video_id);
+  let Some(snippet) = video.and_then(|v| v.get("snippet").cloned()) else {
+    return Err(Error::Validation(format!("Nie znaleziono wideo {}", video_id)));
+  };
+  let current = snippet["description"].as_str().unwrap_or_default();
+  let description = apply_to_description(current, &block.text, placement(dir));
+  if description == current {
+    return Ok(block);
+  }
+  if description.chars().count() > videos::DESCRIPTION_MAX_CHARS {
+    return Err(Error::Validation(format!("Opis z rozdziałami przekracza {} znaków", videos::DESCRIPTION_MAX_CHARS)));
+  }
+  let mut updated = videos::snippet_for_update(&snippet);
+  updated["description"] = json!(description);
+  videos::update(dir, "snippet", json!({ "id": video_id, "snippet": updated })).await.map(|_| block)
+}
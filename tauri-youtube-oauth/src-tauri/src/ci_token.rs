@@ -0,0 +1,48 @@
+//! Short-lived access tokens for CI: a freshly refreshed access token with
+//! its expiry and scopes, and nothing that outlives it — the refresh token
+//! and client secret never leave the app this way. Every mint is audited.
+
+use crate::{audit, error::Error, refresh, scopes};
+use serde::Serialize;
+use serde_json::json;
+use std::{fs, io::Write, path::Path};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CiToken {
+  pub access_token: String,
+  /// Unix seconds after which the token stops working.
+  pub expires_at: u64,
+  pub scopes: Vec<String>,
+}
+
+/// Forces a refresh so the token has its full lifetime (about an hour) ahead.
+pub async fn mint(dir: &Path) -> Result<CiToken, Error> {
+  let t = refresh::refresh(dir).await?;
+  let token = CiToken { access_token: t.access_token.clone(), expires_at: t.created_at + t.expires_in, scopes: scopes::granted(&t) };
+  audit::record(dir, "mint_ci_token", json!({ "expires_at": token.expires_at, "scopes": token.scopes }))?;
+  Ok(token)
+}
+
+pub fn env_text(token: &CiToken) -> String {
+  format!("YOUTUBE_ACCESS_TOKEN={}\nYOUTUBE_TOKEN_EXPIRES_AT={}\n", token.access_token, token.expires_at)
+}
+
+/// Mints a token and writes it to `path` as two env lines, readable only by
+/// the owner (0600 on Unix, also for a file that already existed).
+pub async fn write_env(dir: &Path, path: &Path) -> Result<CiToken, Error> {
+  let token = mint(dir).await?;
+  let fail = |e: std::io::Error| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e));
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    options.mode(0o600);
+    if path.exists() {
+      fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(fail)?;
+    }
+  }
+  options.open(path).and_then(|mut f| f.write_all(env_text(&token).as_bytes())).map_err(fail)?;
+  audit::record(dir, "write_ci_env", json!({ "path": path.to_string_lossy(), "expires_at": token.expires_at }))?;
+  Ok(token)
+}
@@ -0,0 +1,174 @@
+//! Removes what piles up in the config dir over months: stale response cache
+//! entries, upload sessions Google has long closed, old crash reports and
+//! logs, old metadata snapshots, and `.tmp` files left by interrupted atomic
+//! writes. Credentials, the config, profiles and the upload history are
+//! never candidates.
+//!
+//! Runs on demand (`cleanup_storage`) and weekly from a background task.
+
+use crate::{crash, error::Error, http_cache, now_secs, runtime, shutdown, snapshots, storage, upload};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, SystemTime},
+};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_CACHE_MAX_AGE_SECS: u64 = 30 * DAY_SECS;
+const DEFAULT_LOG_RETENTION_DAYS: u64 = 30;
+/// An undo this old is unlikely to be wanted; the newest per video are
+/// capped separately.
+const SNAPSHOT_MAX_AGE_SECS: u64 = 90 * DAY_SECS;
+/// Atomic writes finish in milliseconds; a day-old temp file is orphaned.
+const TMP_MAX_AGE_SECS: u64 = DAY_SECS;
+const STATE_FILE: &str = "cleanup_state.json";
+const AUTO_INTERVAL_SECS: u64 = 7 * DAY_SECS;
+/// How often the background task checks whether a weekly run is due.
+const AUTO_CHECK: Duration = Duration::from_secs(60 * 60);
+/// Never deleted, whatever their name or age.
+const PROTECTED: [&str; 4] = ["tokens.json", "oauth_config.json", "profiles", "upload_history.jsonl"];
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CleanupOptions {
+  /// List candidates without deleting anything.
+  #[serde(default)]
+  pub dry_run: bool,
+  /// Cache entries fetched longer ago go; 30 days when absent.
+  #[serde(default)]
+  pub cache_max_age_secs: Option<u64>,
+  /// Crash reports and logs older than this go; 30 days when absent.
+  #[serde(default)]
+  pub log_retention_days: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanupCategory {
+  Cache,
+  UploadSessions,
+  Logs,
+  Snapshots,
+  TempFiles,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CategoryReport {
+  pub category: CleanupCategory,
+  /// Cache keys, session file paths or file paths relative to the config dir.
+  pub items: Vec<String>,
+  pub bytes: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CleanupReport {
+  pub dry_run: bool,
+  pub categories: Vec<CategoryReport>,
+  pub total_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct CleanupState {
+  last_run: u64,
+}
+
+fn age_secs(meta: &fs::Metadata) -> u64 {
+  meta.modified().ok().and_then(|m| SystemTime::now().duration_since(m).ok()).map_or(0, |d| d.as_secs())
+}
+
+fn is_log(name: &str) -> bool {
+  // `app.log` and its rotations `app.log.1`, `app.log.2.gz`.
+  name.ends_with(".log") || name.split_once(".log.").is_some_and(|(stem, _)| !stem.is_empty())
+}
+
+fn is_tmp(name: &str) -> bool {
+  name.starts_with('.') && name.ends_with(".tmp")
+}
+
+/// Files directly in `dir` (prefixed with `prefix` in the report) matching
+/// `wanted` and older than `max_age_secs`.
+fn old_files(dir: &Path, prefix: &str, max_age_secs: u64, wanted: impl Fn(&str) -> bool) -> Vec<(String, u64)> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let mut found: Vec<(String, u64)> = entries
+    .filter_map(|e| e.ok())
+    .filter_map(|e| {
+      let name = e.file_name().to_string_lossy().into_owned();
+      let meta = e.metadata().ok()?;
+      let keep = PROTECTED.contains(&name.as_str()) || !meta.is_file() || !wanted(&name) || age_secs(&meta) < max_age_secs;
+      (!keep).then(|| (format!("{}{}", prefix, name), meta.len()))
+    })
+    .collect();
+  found.sort();
+  found
+}
+
+fn remove_files(dir: &Path, category: CleanupCategory, files: Vec<(String, u64)>, dry_run: bool) -> Result<CategoryReport, Error> {
+  let mut report = CategoryReport { category, items: Vec::new(), bytes: 0 };
+  for (name, size) in files {
+    if !dry_run {
+      match fs::remove_file(dir.join(&name)) {
+        Ok(()) => {}
+        // Gone in the meantime: nothing reclaimed.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+        Err(e) => return Err(Error::Storage(format!("Nie można usunąć {}: {}", name, e))),
+      }
+    }
+    report.items.push(name);
+    report.bytes += size;
+  }
+  Ok(report)
+}
+
+pub fn cleanup(dir: &Path, options: &CleanupOptions) -> Result<CleanupReport, Error> {
+  let now = now_secs();
+  let dry_run = options.dry_run;
+  let cache_before = now.saturating_sub(options.cache_max_age_secs.unwrap_or(DEFAULT_CACHE_MAX_AGE_SECS));
+  let (items, bytes) = http_cache::prune(dir, cache_before, dry_run)?;
+  let cache = CategoryReport { category: CleanupCategory::Cache, items, bytes };
+  let (items, bytes) = upload::prune_sessions(dir, now.saturating_sub(upload::SESSION_VALIDITY_SECS), dry_run)?;
+  let sessions = CategoryReport { category: CleanupCategory::UploadSessions, items, bytes };
+
+  let retention = options.log_retention_days.unwrap_or(DEFAULT_LOG_RETENTION_DAYS) * DAY_SECS;
+  let crash_prefix = format!("{}/", crash::CRASH_DIR);
+  let mut log_files = old_files(&dir.join(crash::CRASH_DIR), &crash_prefix, retention, |n| n.ends_with(".txt"));
+  log_files.extend(old_files(dir, "", retention, is_log));
+  let logs = remove_files(dir, CleanupCategory::Logs, log_files, dry_run)?;
+  let (items, bytes) = snapshots::prune(dir, now.saturating_sub(SNAPSHOT_MAX_AGE_SECS) * 1000, dry_run)?;
+  let snapshots = CategoryReport { category: CleanupCategory::Snapshots, items, bytes };
+  let temp = remove_files(dir, CleanupCategory::TempFiles, old_files(dir, "", TMP_MAX_AGE_SECS, is_tmp), dry_run)?;
+
+  let categories = vec![cache, sessions, logs, snapshots, temp];
+  let total_bytes = categories.iter().map(|c| c.bytes).sum();
+  Ok(CleanupReport { dry_run, categories, total_bytes })
+}
+
+fn read_state(dir: &Path) -> CleanupState {
+  fs::read_to_string(dir.join(STATE_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Runs [`cleanup`] with the defaults when the last run is a week old,
+/// emitting `storage-cleaned` with the report. `None` when not due yet.
+pub fn run_if_due(dir: &Path) -> Result<Option<CleanupReport>, Error> {
+  if now_secs().saturating_sub(read_state(dir).last_run) < AUTO_INTERVAL_SECS {
+    return Ok(None);
+  }
+  let report = cleanup(dir, &CleanupOptions::default())?;
+  let state = serde_json::to_string(&CleanupState { last_run: now_secs() }).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_atomic(&dir.join(STATE_FILE), state).map_err(|e| Error::Storage(e.to_string()))?;
+  runtime::for_dir(dir).emit("storage-cleaned", &report);
+  Ok(Some(report))
+}
+
+/// Background loop behind the weekly run; ends when the app shuts down or
+/// switches to another profile.
+pub async fn run_periodically(dir: &Path) {
+  let generation = shutdown::task_generation(dir);
+  while !shutdown::tasks_ended(dir, generation) {
+    if let Err(e) = run_if_due(dir) {
+      runtime::for_dir(dir).warn("cleanup", format!("Automatyczne porządkowanie katalogu konfiguracyjnego nie powiodło się: {}", e));
+    }
+    shutdown::idle(dir, generation, AUTO_CHECK).await;
+  }
+}
@@ -0,0 +1,287 @@
+//! `ytlite-oauth`, the headless companion of the app: login, status, refresh,
+//! env files and uploads for one config dir, through the same functions the
+//! Tauri commands call. Human-readable output by default, `--json` for
+//! scripts; secrets are printed only with `--include-secrets`.
+//!
+//! Exit codes: 0 ok, 1 other errors, 2 authentication, 3 network, 64 usage.
+
+use crate::{
+  auth, callback,
+  error::{CommandError, Error},
+  env_file::{self, EnvFormat},
+  metadata, overrides, queue, refresh, runtime, start_login, storage, upload, AuthStatus,
+};
+use serde_json::json;
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+pub const EXIT_OK: u8 = 0;
+pub const EXIT_FAILURE: u8 = 1;
+pub const EXIT_AUTH: u8 = 2;
+pub const EXIT_NETWORK: u8 = 3;
+/// `EX_USAGE` from sysexits.h.
+pub const EXIT_USAGE: u8 = 64;
+/// Used when neither `--config-dir` nor this variable is given.
+pub const CONFIG_DIR_ENV: &str = "YTLITE_CONFIG_DIR";
+const DEFAULT_LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+const USAGE: &str = "Użycie: ytlite-oauth [--config-dir <katalog>] [--json] [--include-secrets] <polecenie>
+
+Polecenia:
+  login [--timeout <s>]                 logowanie przez przeglądarkę (port redirect_port, domyślnie 14321)
+  status                                stan logowania
+  refresh                               odświeża access token
+  env [--out <plik>] [--format <f>] [--no-tokens] [--passphrase <hasło>]
+                                        plik .env (formaty: dotEnv, dotEnvNoComments, json, shellExports)
+  upload <plik> [--meta <plik.json>] [--force <kod,...>]
+                                        przesyła film; metadane domyślnie z <plik>.json; --force pomija
+                                        wymienione ostrzeżenia (recentlyModified, missingDescription, ...)
+  queue                                 wysyła kolejkę według planu, aż zostanie pusta; z funkcją
+                                        metrics-endpoint serwuje w tym czasie /metrics (metrics_endpoint)";
+
+/// The exit code `e` ends the process with.
+pub fn exit_code(e: &Error) -> u8 {
+  match e {
+    Error::Auth(_)
+    | Error::GrantRevoked { .. }
+    | Error::TokenEndpoint(_)
+    | Error::InsufficientScope { .. }
+    | Error::RefreshBackoff { .. }
+    | Error::PassphraseRejected
+    | Error::ConsentFailed(_)
+    | Error::WorkspaceRestricted { .. } => EXIT_AUTH,
+    Error::Api { status: 401, .. } => EXIT_AUTH,
+    Error::Network(_) | Error::Offline | Error::TimedOut { .. } => EXIT_NETWORK,
+    _ => EXIT_FAILURE,
+  }
+}
+
+struct Usage(String);
+
+#[derive(Default)]
+struct Args {
+  config_dir: Option<PathBuf>,
+  json: bool,
+  include_secrets: bool,
+  command: String,
+  positional: Vec<String>,
+  out: Option<PathBuf>,
+  format: Option<EnvFormat>,
+  no_tokens: bool,
+  passphrase: Option<String>,
+  meta: Option<PathBuf>,
+  force: Vec<String>,
+  timeout: Option<Duration>,
+}
+
+fn parse(args: &[String]) -> Result<Args, Usage> {
+  let mut parsed = Args::default();
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    let mut value = |name: &str| args.next().cloned().ok_or_else(|| Usage(format!("{} wymaga wartości", name)));
+    match arg.as_str() {
+      "--config-dir" => parsed.config_dir = Some(PathBuf::from(value(arg)?)),
+      "--json" => parsed.json = true,
+      "--include-secrets" => parsed.include_secrets = true,
+      "--out" => parsed.out = Some(PathBuf::from(value(arg)?)),
+      "--format" => {
+        let f = value(arg)?;
+        let format = serde_json::from_value(json!(f)).map_err(|_| Usage(format!("Nieznany format {}", f)))?;
+        parsed.format = Some(format);
+      }
+      "--no-tokens" => parsed.no_tokens = true,
+      "--passphrase" => parsed.passphrase = Some(value(arg)?),
+      "--meta" => parsed.meta = Some(PathBuf::from(value(arg)?)),
+      "--force" => parsed.force.extend(value(arg)?.split(',').map(str::to_string)),
+      "--timeout" => {
+        let secs = value(arg)?;
+        parsed.timeout = Some(Duration::from_secs(secs.parse().map_err(|_| Usage(format!("Nieprawidłowy --timeout {}", secs)))?));
+      }
+      "-h" | "--help" => return Err(Usage(String::new())),
+      flag if flag.starts_with("--") => return Err(Usage(format!("Nieznana opcja {}", flag))),
+      _ if parsed.command.is_empty() => parsed.command = arg.clone(),
+      _ => parsed.positional.push(arg.clone()),
+    }
+  }
+  if parsed.command.is_empty() {
+    return Err(Usage("Brak polecenia".into()));
+  }
+  Ok(parsed)
+}
+
+fn config_dir(args: &Args) -> Result<PathBuf, Usage> {
+  args
+    .config_dir
+    .clone()
+    .or_else(|| std::env::var_os(CONFIG_DIR_ENV).map(PathBuf::from))
+    .ok_or_else(|| Usage(format!("Podaj --config-dir albo ustaw {}", CONFIG_DIR_ENV)))
+}
+
+fn status_text(s: &AuthStatus) -> String {
+  let mut lines = vec![format!("Zalogowano: {}", if s.authenticated { "tak" } else { "nie" })];
+  if let Some(at) = s.expires_at.and_then(|at| chrono::DateTime::from_timestamp(at as i64, 0)) {
+    lines.push(format!("Access token ważny do: {}", at.to_rfc3339()));
+  }
+  lines.push(format!("Odświeżalny: {}", if s.refreshable { "tak" } else { "nie" }));
+  if !s.scopes.is_empty() {
+    lines.push(format!("Zakresy: {}", s.scopes.join(" ")));
+  }
+  if !s.missing_scopes.is_empty() {
+    lines.push(format!("Brakujące zakresy: {}", s.missing_scopes.join(" ")));
+  }
+  if s.read_only_mode {
+    lines.push("Tryb tylko do odczytu: włączony".into());
+  }
+  lines.join("\n")
+}
+
+/// Runs the same login as `start_oauth` and waits for its `oauth-callback`.
+/// Without a browser to open, the consent URL goes to `err` for the user to
+/// open by hand on this machine.
+async fn login(dir: &Path, timeout: Duration, err: &mut dyn Write) -> Result<AuthStatus, Error> {
+  let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+  runtime::for_dir(dir).attach_emitter(move |event, payload| {
+    if event == "oauth-callback" {
+      let _ = tx.send(payload);
+    }
+  });
+  let launch = start_login(dir).await?;
+  if !launch.opened {
+    let _ = writeln!(err, "Otwórz w przeglądarce: {}", launch.url);
+  }
+  let outcome = match tokio::time::timeout(timeout, rx.recv()).await {
+    Ok(Some(outcome)) => outcome,
+    _ => {
+      callback::stop(dir);
+      return Err(Error::TimedOut { operation: "login".into(), after_secs: timeout.as_secs() });
+    }
+  };
+  if outcome["ok"] != true {
+    let g = &outcome["guidance"];
+    let reason = match (g["title"].as_str(), g["action"].as_str()) {
+      (Some(title), Some(action)) => format!("{} — {}", title, action),
+      (Some(title), None) => title.to_string(),
+      _ => outcome["error"].as_str().unwrap_or("nieznany błąd").to_string(),
+    };
+    return Err(Error::Auth(format!("Logowanie nieudane: {}", reason)));
+  }
+  Ok(auth::status(dir))
+}
+
+/// What a finished command prints: the `--json` value and the text.
+struct Output {
+  json: serde_json::Value,
+  text: String,
+}
+
+async fn execute(dir: &Path, args: &Args, err: &mut dyn Write) -> Result<Output, Error> {
+  match args.command.as_str() {
+    "login" => {
+      let status = login(dir, args.timeout.unwrap_or(DEFAULT_LOGIN_TIMEOUT), err).await?;
+      Ok(Output { text: status_text(&status), json: json!(status) })
+    }
+    "status" => {
+      let status = auth::status(dir);
+      Ok(Output { text: status_text(&status), json: json!(status) })
+    }
+    "refresh" => {
+      let t = refresh::refresh(dir).await?;
+      let mut out = json!({ "expires_in": t.expires_in, "scope": t.scope });
+      let mut text = format!("Odświeżono; access token ważny {} s", t.expires_in);
+      if args.include_secrets {
+        out["access_token"] = json!(t.access_token);
+        text = format!("{}\n{}", text, t.access_token);
+      }
+      Ok(Output { json: out, text })
+    }
+    "env" => {
+      let env = env_file::generate(dir, !args.no_tokens, args.passphrase.as_deref(), args.format.unwrap_or_default()).await?;
+      match &args.out {
+        Some(path) => {
+          storage::write_secret(path, env).map_err(|e| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e)))?;
+          Ok(Output { json: json!({ "path": path }), text: format!("Zapisano {}", path.display()) })
+        }
+        None => Ok(Output { json: json!({ "env": env }), text: env.trim_end().to_string() }),
+      }
+    }
+    "upload" => {
+      let file = PathBuf::from(&args.positional[0]);
+      let force = overrides::parse(&args.force)?;
+      let meta = match &args.meta {
+        Some(sidecar) => metadata::load_with_sidecar(dir, &file, sidecar).await?,
+        None => metadata::load_video_metadata(dir, &file).await?,
+      };
+      let uploaded = upload::upload_video(dir, &file, &meta, &force).await?;
+      Ok(Output { text: format!("Przesłano {}: {}", uploaded.video_id, uploaded.url), json: json!(uploaded) })
+    }
+    "queue" => {
+      #[cfg(feature = "metrics-endpoint")]
+      if let Some(addr) = crate::metrics_endpoint::start(dir)? {
+        let _ = writeln!(err, "Metryki: http://{}/metrics", addr);
+      }
+      let result = queue::run(dir).await;
+      #[cfg(feature = "metrics-endpoint")]
+      crate::metrics_endpoint::stop(dir);
+      result?;
+      let remaining = queue::depth(dir);
+      Ok(Output { json: json!({ "remaining": remaining }), text: format!("Kolejka przetworzona; pozostało wpisów: {}", remaining) })
+    }
+    _ => unreachable!("checked by validate"),
+  }
+}
+
+/// Usage problems `parse` can't see on its own.
+fn validate(args: &Args) -> Result<(), Usage> {
+  let positional = match args.command.as_str() {
+    "login" | "status" | "refresh" | "env" | "queue" => 0,
+    "upload" => 1,
+    other => return Err(Usage(format!("Nieznane polecenie {}", other))),
+  };
+  if args.positional.len() != positional {
+    return Err(Usage(format!("Nieprawidłowa liczba argumentów polecenia {}", args.command)));
+  }
+  // The env text has the client secret, and the tokens unless left out.
+  if args.command == "env" && args.out.is_none() && !args.include_secrets {
+    return Err(Usage("env bez --out wypisuje sekrety — podaj --out <plik> albo --include-secrets".into()));
+  }
+  Ok(())
+}
+
+/// Runs the command line `args` (without the program name), writing results
+/// to `out` and errors and usage to `err`; returns the exit code.
+pub async fn run(args: &[String], out: &mut dyn Write, err: &mut dyn Write) -> u8 {
+  let parsed = parse(args).and_then(|a| validate(&a).map(|_| a));
+  let (args, dir) = match parsed.and_then(|a| config_dir(&a).map(|d| (a, d))) {
+    Ok(v) => v,
+    Err(Usage(message)) if message.is_empty() => {
+      let _ = writeln!(out, "{}", USAGE);
+      return EXIT_OK;
+    }
+    Err(Usage(message)) => {
+      let _ = writeln!(err, "{}\n\n{}", message, USAGE);
+      return EXIT_USAGE;
+    }
+  };
+  match execute(&dir, &args, err).await {
+    Ok(output) if args.json => {
+      let _ = writeln!(out, "{}", output.json);
+      EXIT_OK
+    }
+    Ok(output) => {
+      let _ = writeln!(out, "{}", output.text);
+      EXIT_OK
+    }
+    Err(e) => {
+      let code = exit_code(&e);
+      if args.json {
+        let _ = writeln!(out, "{}", json!({ "error": CommandError::from(e) }));
+      } else {
+        let _ = writeln!(err, "Błąd [{}]: {}", e.code(), e);
+      }
+      code
+    }
+  }
+}
@@ -0,0 +1,987 @@
+//! Tauri commands. Each one resolves the config dir and delegates to the
+//! tauri-free core; errors reach JS as [`CommandError`].
+
+use crate::{
+  adc, api, app_config_dir, archive, audit, auth, auth_health, bulk, callback, capabilities, capture, channel, chapters, ci_token,
+  cleanup, comments, config, connectivity, consent_probe, crash, diagnostics, env_drift, env_file,
+  error::{self, CommandError, Error, ErrorCode}, exchange_and_persist, exit_after_shutdown, finish_profile_switch, format, hashing,
+  history, hooks, inventory, library, lint, live, localizations, metadata, metrics, migration, notifications, oauth_client,
+  open_consent, open_path, overrides, passphrase, playlists, privacy, profiles, publish_time, queue, quota, raw_api, read_config,
+  read_tokens, refresh, remote_state, rotation, runtime, scopes, search, sections, setup, shutdown, sidecar, snapshots,
+  spawn_queue_worker, start_login, storage_location, thumbnails, token_age, update_config_in_dir, upload, video_links, videos, ActiveFlow,
+  AppConfig, ArchiveFilter, AuditEntry, AuthHealth, AuthStatus, Broadcast, BroadcastOptions, BroadcastStatus, BrowserLaunch,
+  BulkPrivacyResult, Capabilities, CaptureStatus, Channel, ChannelArchive, ChannelSection, Chapter, ChapterBlock,
+  CiToken, CleanupOptions, CleanupReport, ConfigChange, Connectivity, CrashReport, DedupReport, DiagnosticsReport,
+  EntryPriority, EnvDrift, EnvFileResult, EnvFormat, EnvSyncResult, ExportFormat, FileHash, HistoryFilter, HistoryPage,
+  HookOutcome, HttpExchange, InventoryResult, LibraryMatch, LinkReport, LintWarning, LiveStream, Localization,
+  LocalizationSet, LoginResult, MetadataPreview, MetricsSnapshot, MigrationReport, NotificationTest,
+  OAuthClientDescription, PageRequest, PlanOptions, PlanReport, PlaylistInsert, PlaylistItemsPage, PostedComment,
+  Privacy, PrivacyOptions, PublishTime, QueueEntry, QuotaStatus, RawResponse, ReconcileReport, ScheduleOptions,
+  SearchOptions, SearchResult, SectionInput, SectionType, SetupState, SidecarDiagnostic, SnapshotInfo, StorageLocation,
+  StreamResolution, Thumbnail, TokenAge, Tokens, UploadPlan, UploadRecord, UploadedVideo, VerifyOptions, VerifyReport, VideoArchive,
+  VideoMetadata, VideoPage, WatermarkTiming,
+};
+use std::{collections::BTreeMap, fs, path::Path, time::Duration};
+use tauri::AppHandle;
+
+#[tauri::command]
+pub async fn start_oauth(app: AppHandle) -> Result<BrowserLaunch, CommandError> {
+  Ok(start_login(&app_config_dir(&app)?).await?)
+}
+
+/// The login waiting for its redirect, if any: its redirect URI (on the
+/// port it started with), age and state.
+#[tauri::command]
+pub async fn get_active_flow(app: AppHandle) -> Result<Option<ActiveFlow>, CommandError> {
+  Ok(callback::active_flow(&app_config_dir(&app)?))
+}
+
+/// Stops listening for the redirect; returns whether a login was waiting.
+#[tauri::command]
+pub async fn cancel_oauth(app: AppHandle) -> Result<bool, CommandError> {
+  Ok(callback::stop(&app_config_dir(&app)?))
+}
+
+/// Saves `redirect_port` (`null` for 14321) after checking the port can be
+/// bound; the next login uses it. Returns that port.
+#[tauri::command]
+pub async fn set_redirect_port(app: AppHandle, port: Option<u16>) -> Result<u16, CommandError> {
+  Ok(callback::set_port(&app_config_dir(&app)?, port)?)
+}
+
+/// Incremental authorization: adds `extra` to the configured scopes (so later
+/// logins keep asking for them) and opens consent for just the missing ones.
+#[tauri::command]
+pub async fn request_scopes(app: AppHandle, extra: Vec<String>) -> Result<(), CommandError> {
+  let dir = app_config_dir(&app)?;
+  let (cfg, requested) = update_config_in_dir(&dir, |cfg| {
+    let mut requested = scopes::configured(cfg);
+    for s in extra {
+      if !requested.contains(&s) {
+        requested.push(s);
+      }
+    }
+    cfg.scopes = requested.clone();
+    (cfg.clone(), requested)
+  })?;
+  setup::notify(&dir);
+  let missing = match read_tokens(&app) {
+    Some(t) => {
+      let required: Vec<&str> = requested.iter().map(String::as_str).collect();
+      scopes::missing(&t, &required)
+    }
+    None => requested,
+  };
+  if !missing.is_empty() {
+    consent_probe::check(&dir, &cfg, &missing).await?;
+    callback::start(&dir)?;
+    // A browser that didn't open is reported through oauth-open-browser-failed.
+    open_consent(&dir, &cfg, &missing, true);
+  }
+  Ok(())
+}
+
+/// Incremental authorization for the scopes the config gained since the
+/// tokens were issued (`AuthStatus.needs_reconsent`); returns them, empty
+/// when nothing was missing.
+#[tauri::command]
+pub async fn reconsent(app: AppHandle) -> Result<Vec<String>, CommandError> {
+  Ok(crate::reconsent::start(&app_config_dir(&app)?).await?)
+}
+
+#[tauri::command]
+pub async fn exchange_code(app: AppHandle, code: String) -> Result<LoginResult, CommandError> {
+  let dir = app_config_dir(&app)?;
+  Ok(exchange_and_persist(&dir, &code).await?)
+}
+
+/// Logs in with gcloud's Application Default Credentials instead of a
+/// consent flow; `path` defaults to where gcloud keeps them.
+#[tauri::command]
+pub async fn import_adc(app: AppHandle, path: Option<String>) -> Result<AuthStatus, CommandError> {
+  Ok(adc::import(&app_config_dir(&app)?, path.as_deref().map(Path::new)).await?)
+}
+
+/// Replaces the client secret after a refresh proves Google accepts it; the
+/// session survives.
+#[tauri::command]
+pub async fn rotate_client_secret(app: AppHandle, new_secret: String) -> Result<AuthStatus, CommandError> {
+  Ok(rotation::rotate_client_secret(&app_config_dir(&app)?, &new_secret).await?)
+}
+
+/// Switches to another OAuth client, deleting the stored tokens; refused
+/// with an explanation unless `confirm`.
+#[tauri::command]
+pub async fn rotate_client_id(
+  app: AppHandle,
+  client_id: String,
+  client_secret: String,
+  confirm: Option<bool>,
+) -> Result<AuthStatus, CommandError> {
+  Ok(rotation::rotate_client_id(&app_config_dir(&app)?, &client_id, &client_secret, confirm.unwrap_or(false))?)
+}
+
+#[tauri::command]
+pub async fn refresh_tokens(app: AppHandle) -> Result<Tokens, CommandError> {
+  Ok(refresh::refresh(&app_config_dir(&app)?).await?)
+}
+
+/// Refreshes immediately, clearing any backoff from earlier network failures.
+#[tauri::command]
+pub async fn refresh_tokens_now(app: AppHandle) -> Result<Tokens, CommandError> {
+  let dir = app_config_dir(&app)?;
+  refresh::reset(&dir);
+  Ok(refresh::refresh(&dir).await?)
+}
+
+/// Raw `channels.list` JSON (snippet only), kept for existing frontends.
+#[deprecated(note = "use youtube_get_channels, which returns typed channels")]
+#[tauri::command]
+pub async fn youtube_list_channels(app: AppHandle) -> Result<serde_json::Value, CommandError> {
+  Ok(channel::list_mine(&app_config_dir(&app)?, "snippet").await?)
+}
+
+#[tauri::command]
+pub async fn youtube_get_channels(app: AppHandle) -> Result<Vec<Channel>, CommandError> {
+  Ok(channel::list_channels(&app_config_dir(&app)?).await?)
+}
+
+/// The channel's uploads, newest first, `page_budget` pages per call;
+/// `next_cursor` continues the listing.
+#[tauri::command]
+pub async fn youtube_list_my_videos(app: AppHandle, cursor: Option<String>, page_budget: Option<u32>) -> Result<VideoPage, CommandError> {
+  Ok(videos::list_my_videos(&app_config_dir(&app)?, &PageRequest { cursor, page_budget }).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_create_broadcast(
+  app: AppHandle,
+  title: String,
+  scheduled_start: String,
+  privacy: Privacy,
+  options: BroadcastOptions,
+) -> Result<Broadcast, CommandError> {
+  Ok(live::create_broadcast(&app_config_dir(&app)?, &title, &scheduled_start, privacy, &options).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_bind_stream(app: AppHandle, broadcast_id: String, stream_id: String) -> Result<Broadcast, CommandError> {
+  Ok(live::bind_stream(&app_config_dir(&app)?, &broadcast_id, &stream_id).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_transition_broadcast(app: AppHandle, id: String, status: BroadcastStatus) -> Result<Broadcast, CommandError> {
+  Ok(live::transition_broadcast(&app_config_dir(&app)?, &id, status).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_list_streams(app: AppHandle) -> Result<Vec<LiveStream>, CommandError> {
+  Ok(live::list_streams(&app_config_dir(&app)?).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_create_stream(app: AppHandle, title: String, resolution: StreamResolution) -> Result<LiveStream, CommandError> {
+  Ok(live::create_stream(&app_config_dir(&app)?, &title, resolution).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_set_channel_description(app: AppHandle, text: String) -> Result<serde_json::Value, CommandError> {
+  Ok(channel::set_channel_description(&app_config_dir(&app)?, &text).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_list_playlist_localizations(app: AppHandle, playlist_id: String) -> Result<LocalizationSet, CommandError> {
+  Ok(localizations::playlist_localizations(&app_config_dir(&app)?, &playlist_id).await?)
+}
+
+/// Sets the given languages of the playlist and keeps the rest; a `null`
+/// value removes that language.
+#[tauri::command]
+pub async fn youtube_set_playlist_localizations(
+  app: AppHandle,
+  playlist_id: String,
+  changes: BTreeMap<String, Option<Localization>>,
+) -> Result<LocalizationSet, CommandError> {
+  Ok(localizations::set_playlist_localizations(&app_config_dir(&app)?, &playlist_id, &changes).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_list_channel_localizations(app: AppHandle) -> Result<LocalizationSet, CommandError> {
+  Ok(localizations::channel_localizations(&app_config_dir(&app)?).await?)
+}
+
+/// [`youtube_set_playlist_localizations`] for the authenticated user's channel.
+#[tauri::command]
+pub async fn youtube_set_channel_localizations(
+  app: AppHandle,
+  changes: BTreeMap<String, Option<Localization>>,
+) -> Result<LocalizationSet, CommandError> {
+  Ok(localizations::set_channel_localizations(&app_config_dir(&app)?, &changes).await?)
+}
+
+/// Sends a request the crate has no command for (needs `allow_raw_api`).
+/// `path` is a path on the Data API or an https URL on a YouTube googleapis
+/// host; non-2xx answers come back as a response, not an error.
+#[tauri::command]
+pub async fn youtube_raw_request(
+  app: AppHandle,
+  method: String,
+  path: String,
+  query: Option<BTreeMap<String, String>>,
+  body: Option<serde_json::Value>,
+) -> Result<RawResponse, CommandError> {
+  Ok(raw_api::request(&app_config_dir(&app)?, &method, &path, &query.unwrap_or_default(), body).await?)
+}
+
+/// Uploads a video in resumable chunks; calling again for the same file
+/// after an interruption continues the earlier session. `force` lists the
+/// warnings to upload despite, by code: `recentlyModified` for a file
+/// modified moments ago, a lint rule (`missingDescription`, ...) for
+/// metadata failing it in block mode. Unknown codes are refused.
+#[tauri::command]
+pub async fn youtube_upload_video(
+  app: AppHandle,
+  file_path: String,
+  metadata: Option<VideoMetadata>,
+  force: Option<Vec<String>>,
+) -> Result<UploadedVideo, CommandError> {
+  let dir = app_config_dir(&app)?;
+  let force = overrides::parse(&force.unwrap_or_default())?;
+  let path = Path::new(&file_path);
+  let metadata = match metadata {
+    Some(m) => m,
+    None => metadata::load_video_metadata(&dir, path).await?,
+  };
+  Ok(upload::upload_video(&dir, path, &metadata, &force).await?)
+}
+
+/// The video `path` was uploaded as, found by content so renamed and moved
+/// files still match.
+#[tauri::command]
+pub async fn locate_video_for_file(app: AppHandle, path: String) -> Result<Option<LibraryMatch>, CommandError> {
+  let dir = app_config_dir(&app)?;
+  Ok(hashing::off_runtime(move || library::locate_video_for_file(&dir, Path::new(&path))).await?)
+}
+
+/// Where the file uploaded as `video_id` was last seen.
+#[tauri::command]
+pub async fn find_local_file_for_video(app: AppHandle, video_id: String) -> Result<Option<LibraryMatch>, CommandError> {
+  Ok(library::find_local_file_for_video(&app_config_dir(&app)?, &video_id))
+}
+
+/// The content hash of `path` with the configured algorithm; progress in
+/// `hash-progress`.
+#[tauri::command]
+pub async fn hash_file(app: AppHandle, path: String) -> Result<FileHash, CommandError> {
+  let dir = app_config_dir(&app)?;
+  Ok(hashing::off_runtime(move || hashing::hash(&dir, Path::new(&path))).await?)
+}
+
+/// Hashes the videos under `dir` against the library, recording moved ones.
+#[tauri::command]
+pub async fn reconcile_library(app: AppHandle, dir: String) -> Result<ReconcileReport, CommandError> {
+  let config_dir = app_config_dir(&app)?;
+  Ok(hashing::off_runtime(move || library::reconcile(&config_dir, Path::new(&dir))).await?)
+}
+
+/// Warnings the pre-upload lint gives `metadata`, with the rules and banned
+/// words of `metadata_lint`.
+#[tauri::command]
+pub async fn lint_metadata(app: AppHandle, metadata: VideoMetadata) -> Result<Vec<LintWarning>, CommandError> {
+  let cfg = read_config(&app).unwrap_or_default();
+  Ok(lint::lint_metadata(&metadata, &cfg.metadata_lint.unwrap_or_default()))
+}
+
+/// Links to other videos in `description` with their privacy and what
+/// `video_links` (warn when unset) does to them.
+#[tauri::command]
+pub async fn resolve_video_links(app: AppHandle, description: String) -> Result<LinkReport, CommandError> {
+  Ok(video_links::resolve_video_links(&app_config_dir(&app)?, &description).await?)
+}
+
+/// Metadata an upload of `file_path` would use: its sidecar, completed from
+/// `metadata_template`.
+#[tauri::command]
+pub async fn load_video_metadata(app: AppHandle, file_path: String) -> Result<VideoMetadata, CommandError> {
+  Ok(metadata::load_video_metadata(&app_config_dir(&app)?, Path::new(&file_path)).await?)
+}
+
+/// The instant `value` stands for as a `publish_at`, in UTC and in
+/// `publish_timezone`, or why it can't be used.
+#[tauri::command]
+pub async fn resolve_publish_time(app: AppHandle, value: String) -> Result<PublishTime, CommandError> {
+  Ok(publish_time::resolve(&app_config_dir(&app)?, &value)?)
+}
+
+/// Everything wrong with the sidecar JSON at `path`, errors first. The same
+/// check runs when a file enters the queue and before its upload.
+#[tauri::command]
+pub async fn validate_sidecar(path: String) -> Result<Vec<SidecarDiagnostic>, CommandError> {
+  Ok(sidecar::validate(Path::new(&path))?)
+}
+
+/// What `metadata_template` makes of `filename`, without uploading anything.
+#[tauri::command]
+pub async fn preview_metadata(app: AppHandle, filename: String) -> Result<MetadataPreview, CommandError> {
+  Ok(metadata::preview(&app_config_dir(&app)?, &filename).await?)
+}
+
+/// Writes `chapters` into the video's description, replacing an earlier block.
+#[tauri::command]
+pub async fn youtube_set_chapters(app: AppHandle, video_id: String, chapters: Vec<Chapter>) -> Result<ChapterBlock, CommandError> {
+  Ok(chapters::set_chapters(&app_config_dir(&app)?, &video_id, &chapters).await?)
+}
+
+/// Posts a top-level comment on the channel's own video.
+#[tauri::command]
+pub async fn youtube_post_comment(app: AppHandle, video_id: String, text: String) -> Result<PostedComment, CommandError> {
+  Ok(comments::post_comment(&app_config_dir(&app)?, &video_id, &text).await?)
+}
+
+/// Publishes the comment if it's held, then rejects with `NOT_SUPPORTED_BY_API`:
+/// the pin itself is only possible in YouTube Studio.
+#[tauri::command]
+pub async fn youtube_pin_comment(app: AppHandle, comment_id: String) -> Result<(), CommandError> {
+  Ok(comments::pin_comment(&app_config_dir(&app)?, &comment_id).await?)
+}
+
+/// Rejects with `NOT_SUPPORTED_BY_API`: hearting has no endpoint.
+#[tauri::command]
+pub async fn youtube_heart_comment(app: AppHandle, comment_id: String) -> Result<(), CommandError> {
+  Ok(comments::heart_comment(&app_config_dir(&app)?, &comment_id).await?)
+}
+
+/// Metadata kept before the app's edits and deletions of `video_id`, newest first.
+#[tauri::command]
+pub async fn list_snapshots(app: AppHandle, video_id: String) -> Result<Vec<SnapshotInfo>, CommandError> {
+  Ok(snapshots::list(&app_config_dir(&app)?, &video_id)?)
+}
+
+/// Writes snapshot `timestamp` back to the video; returns the updated resource.
+#[tauri::command]
+pub async fn restore_snapshot(app: AppHandle, video_id: String, timestamp: u64) -> Result<serde_json::Value, CommandError> {
+  Ok(snapshots::restore(&app_config_dir(&app)?, &video_id, timestamp).await?)
+}
+
+/// Sets a custom thumbnail; returns the sizes by name (`default`, `medium`, `high`, ...).
+#[tauri::command]
+pub async fn youtube_set_thumbnail(
+  app: AppHandle,
+  video_id: String,
+  image_path: String,
+) -> Result<BTreeMap<String, Thumbnail>, CommandError> {
+  Ok(thumbnails::set_thumbnail(&app_config_dir(&app)?, &video_id, Path::new(&image_path)).await?)
+}
+
+/// `force` as for [`youtube_upload_video`], applied when the entry uploads.
+#[tauri::command]
+pub async fn queue_add(
+  app: AppHandle,
+  file_path: String,
+  metadata: Option<VideoMetadata>,
+  force: Option<Vec<String>>,
+) -> Result<QueueEntry, CommandError> {
+  Ok(queue::add_forced(&app_config_dir(&app)?, &file_path, metadata, &force.unwrap_or_default())?)
+}
+
+#[tauri::command]
+pub async fn queue_remove(app: AppHandle, id: String) -> Result<(), CommandError> {
+  Ok(queue::remove(&app_config_dir(&app)?, &id)?)
+}
+
+#[tauri::command]
+pub async fn set_queue_priority(app: AppHandle, id: String, priority: EntryPriority) -> Result<QueueEntry, CommandError> {
+  Ok(queue::set_priority(&app_config_dir(&app)?, &id, priority)?)
+}
+
+/// Moves an entry to `new_index` of `queue_list`; returns the new order.
+#[tauri::command]
+pub async fn move_queue_entry(app: AppHandle, id: String, new_index: usize) -> Result<Vec<QueueEntry>, CommandError> {
+  Ok(queue::move_entry(&app_config_dir(&app)?, &id, new_index)?)
+}
+
+#[tauri::command]
+pub async fn queue_list(app: AppHandle) -> Result<Vec<QueueEntry>, CommandError> {
+  Ok(queue::list(&app_config_dir(&app)?))
+}
+
+/// Starts the queue worker in the background; a second call while it runs
+/// does nothing. A panic in the worker restarts it with backoff.
+#[tauri::command]
+pub async fn start_queue(app: AppHandle) -> Result<(), CommandError> {
+  spawn_queue_worker(app_config_dir(&app)?);
+  Ok(())
+}
+
+/// Checks blocked entries again and returns the ones put back in the queue.
+#[tauri::command]
+pub async fn revalidate_blocked(app: AppHandle) -> Result<Vec<QueueEntry>, CommandError> {
+  Ok(queue::revalidate_blocked(&app_config_dir(&app)?).await?)
+}
+
+/// Puts a failed or given-up entry back in the queue.
+#[tauri::command]
+pub async fn retry_queue_entry(app: AppHandle, id: String) -> Result<QueueEntry, CommandError> {
+  Ok(queue::retry(&app_config_dir(&app)?, &id)?)
+}
+
+/// Retries failed entries, with `codes` only those that failed with one of
+/// them; returns the entries put back.
+#[tauri::command]
+pub async fn retry_all_failed(app: AppHandle, codes: Option<Vec<String>>) -> Result<Vec<QueueEntry>, CommandError> {
+  Ok(queue::retry_all_failed(&app_config_dir(&app)?, codes.as_deref())?)
+}
+
+/// Spreads the queue over days; `None` uploads everything right away.
+#[tauri::command]
+pub async fn set_upload_schedule(app: AppHandle, options: Option<ScheduleOptions>) -> Result<UploadPlan, CommandError> {
+  Ok(queue::set_schedule(&app_config_dir(&app)?, options)?)
+}
+
+#[tauri::command]
+pub async fn get_upload_plan(app: AppHandle) -> Result<UploadPlan, CommandError> {
+  Ok(queue::plan(&app_config_dir(&app)?)?)
+}
+
+/// Fires post-upload hook `index` with sample data and reports how it went.
+#[tauri::command]
+pub async fn test_hook(app: AppHandle, index: usize) -> Result<HookOutcome, CommandError> {
+  Ok(hooks::test(&app_config_dir(&app)?, index).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_upload_banner(app: AppHandle, image_path: String) -> Result<String, CommandError> {
+  Ok(channel::upload_banner(&app_config_dir(&app)?, Path::new(&image_path)).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_select_channel(app: AppHandle, channel_id: String) -> Result<(), CommandError> {
+  Ok(channel::select_channel(&app_config_dir(&app)?, &channel_id)?)
+}
+
+#[tauri::command]
+pub async fn youtube_set_watermark(app: AppHandle, image_path: String, timing: WatermarkTiming) -> Result<(), CommandError> {
+  Ok(channel::set_watermark(&app_config_dir(&app)?, Path::new(&image_path), &timing).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_unset_watermark(app: AppHandle) -> Result<(), CommandError> {
+  Ok(channel::unset_watermark(&app_config_dir(&app)?).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_search(app: AppHandle, query: String, options: SearchOptions) -> Result<Vec<SearchResult>, CommandError> {
+  Ok(search::search(&app_config_dir(&app)?, &query, &options).await?)
+}
+
+#[tauri::command]
+pub async fn get_quota_usage(app: AppHandle) -> Result<QuotaStatus, CommandError> {
+  Ok(quota::status(&app_config_dir(&app)?))
+}
+
+#[tauri::command]
+pub async fn youtube_list_channel_sections(app: AppHandle) -> Result<Vec<ChannelSection>, CommandError> {
+  Ok(sections::list_sections(&app_config_dir(&app)?).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_create_channel_section(
+  app: AppHandle,
+  section_type: SectionType,
+  title: Option<String>,
+  playlist_ids: Vec<String>,
+  position: Option<u32>,
+) -> Result<Vec<ChannelSection>, CommandError> {
+  let input = SectionInput { section_type, title, playlist_ids, position };
+  Ok(sections::create_section(&app_config_dir(&app)?, &input).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_update_channel_section(
+  app: AppHandle,
+  id: String,
+  section_type: SectionType,
+  title: Option<String>,
+  playlist_ids: Vec<String>,
+  position: Option<u32>,
+) -> Result<Vec<ChannelSection>, CommandError> {
+  let input = SectionInput { section_type, title, playlist_ids, position };
+  Ok(sections::update_section(&app_config_dir(&app)?, &id, &input).await?)
+}
+
+#[tauri::command]
+pub async fn youtube_delete_channel_section(app: AppHandle, id: String) -> Result<Vec<ChannelSection>, CommandError> {
+  Ok(sections::delete_section(&app_config_dir(&app)?, &id).await?)
+}
+
+/// Entries of a playlist, `page_budget` pages per call; `next_cursor`
+/// continues the listing.
+#[tauri::command]
+pub async fn youtube_list_playlist_items(
+  app: AppHandle,
+  playlist_id: String,
+  cursor: Option<String>,
+  page_budget: Option<u32>,
+) -> Result<PlaylistItemsPage, CommandError> {
+  Ok(playlists::list_playlist_items(&app_config_dir(&app)?, &playlist_id, &PageRequest { cursor, page_budget }).await?)
+}
+
+/// Adds a video to a playlist; a video already there is reported as
+/// `alreadyInPlaylist` instead of being added again.
+#[tauri::command]
+pub async fn youtube_add_to_playlist(app: AppHandle, playlist_id: String, video_id: String) -> Result<PlaylistInsert, CommandError> {
+  Ok(playlists::add_to_playlist(&app_config_dir(&app)?, &playlist_id, &video_id).await?)
+}
+
+/// Removes repeated videos from a playlist, keeping each one's earliest
+/// position; with `dry_run` only lists what would be removed.
+#[tauri::command]
+pub async fn deduplicate_playlist(app: AppHandle, playlist_id: String, dry_run: Option<bool>) -> Result<DedupReport, CommandError> {
+  Ok(playlists::deduplicate_playlist(&app_config_dir(&app)?, &playlist_id, dry_run.unwrap_or(false)).await?)
+}
+
+/// Turns dry-run mode on or off for this session, overriding `dry_run` from
+/// the config. Returns the new state.
+#[tauri::command]
+pub async fn set_dry_run(app: AppHandle, enabled: bool) -> Result<bool, CommandError> {
+  let dir = app_config_dir(&app)?;
+  runtime::for_dir(&dir).set_dry_run(enabled);
+  Ok(api::dry_run_enabled(&dir))
+}
+
+/// Turns read-only mode on or off for this session, overriding
+/// `read_only_mode` from the config. While on, every call that would change
+/// the channel fails with `READ_ONLY_MODE` and the upload queue waits.
+/// Returns the new state, also sent as `read-only-mode-changed`.
+#[tauri::command]
+pub async fn set_read_only_mode(app: AppHandle, enabled: bool) -> Result<bool, CommandError> {
+  let dir = app_config_dir(&app)?;
+  let rt = runtime::for_dir(&dir);
+  rt.set_read_only(enabled);
+  let enabled = api::read_only_enabled(&dir);
+  rt.emit("read-only-mode-changed", enabled);
+  capabilities::notify(&dir);
+  Ok(enabled)
+}
+
+/// Shows a sample desktop notification, whether or not they're turned on,
+/// so the user sees if the system lets them through.
+#[tauri::command]
+pub async fn test_notification(app: AppHandle) -> Result<NotificationTest, CommandError> {
+  Ok(notifications::test(&app_config_dir(&app)?))
+}
+
+#[tauri::command]
+pub async fn get_connectivity(app: AppHandle) -> Result<Connectivity, CommandError> {
+  Ok(connectivity::status(&app_config_dir(&app)?).await)
+}
+
+/// With `enabled`, behaves as offline until switched off again: reads are
+/// served from the response cache, writes fail with `OFFLINE`.
+#[tauri::command]
+pub async fn set_offline_mode(app: AppHandle, enabled: bool) -> Result<Connectivity, CommandError> {
+  Ok(connectivity::set_offline_mode(&app_config_dir(&app)?, enabled).await)
+}
+
+/// Records the last 100 HTTP exchanges (redacted) for bug reports; switches
+/// itself off after 30 minutes.
+#[tauri::command]
+pub async fn set_http_capture(app: AppHandle, enabled: bool) -> Result<CaptureStatus, CommandError> {
+  Ok(capture::set_enabled(&app_config_dir(&app)?, enabled))
+}
+
+#[tauri::command]
+pub async fn get_http_capture(app: AppHandle) -> Result<Vec<HttpExchange>, CommandError> {
+  Ok(capture::entries(&app_config_dir(&app)?))
+}
+
+#[tauri::command]
+pub async fn export_http_capture(app: AppHandle, path: String) -> Result<usize, CommandError> {
+  Ok(capture::export(&app_config_dir(&app)?, Path::new(&path))?)
+}
+
+/// Request counts, errors by reason, p50/p95 latency over the last 15
+/// minutes and uploaded bytes per endpoint class. With `reset` the counters
+/// start over after this read.
+#[tauri::command]
+pub async fn get_metrics(app: AppHandle, reset: Option<bool>) -> Result<MetricsSnapshot, CommandError> {
+  Ok(metrics::snapshot(&app_config_dir(&app)?, reset.unwrap_or(false)))
+}
+
+/// Where first-run setup stands and what the current step still needs.
+#[tauri::command]
+pub async fn get_setup_state(app: AppHandle) -> Result<SetupState, CommandError> {
+  Ok(setup::current(&app_config_dir(&app)?))
+}
+
+#[tauri::command]
+pub async fn get_auth_status(app: AppHandle) -> Result<AuthStatus, CommandError> {
+  Ok(auth::status(&app_config_dir(&app)?))
+}
+
+/// Checks the saved login now instead of waiting for the daily check; `None`
+/// without tokens, offline or while refreshing is in backoff.
+#[tauri::command]
+pub async fn check_auth_health(app: AppHandle) -> Result<Option<AuthHealth>, CommandError> {
+  Ok(auth_health::check(&app_config_dir(&app)?).await)
+}
+
+/// Per feature whether the current auth can use it, and why not; updates
+/// come as `capabilities-changed`.
+#[tauri::command]
+pub async fn get_capabilities(app: AppHandle) -> Result<Capabilities, CommandError> {
+  Ok(capabilities::current(&app_config_dir(&app)?))
+}
+
+/// How old the refresh token is, counted from the login, with the 7-day
+/// Testing-mode expiry.
+#[tauri::command]
+pub async fn get_token_age(app: AppHandle) -> Result<TokenAge, CommandError> {
+  Ok(token_age::token_age(&app_config_dir(&app)?))
+}
+
+/// Panic reports from `crashes/`, newest first, to attach to a bug report.
+#[tauri::command]
+pub async fn list_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, CommandError> {
+  Ok(crash::list(&app_config_dir(&app)?)?)
+}
+
+/// Resolves once usable tokens exist, for scripts that start the app and
+/// wait for the login before doing API work.
+#[tauri::command]
+pub async fn wait_until_authenticated(app: AppHandle, timeout_secs: u64) -> Result<AuthStatus, CommandError> {
+  Ok(auth::wait_until_authenticated(&app_config_dir(&app)?, Duration::from_secs(timeout_secs)).await?)
+}
+
+/// Runs the connectivity, config and token checklist; nothing secret ends
+/// up in the report.
+#[tauri::command]
+pub async fn run_diagnostics(app: AppHandle) -> Result<DiagnosticsReport, CommandError> {
+  Ok(diagnostics::run(&app_config_dir(&app)?).await)
+}
+
+/// The configured OAuth client's project next to the last login's, with
+/// anything that suggests credentials mixed up between projects or profiles.
+/// Client ids are masked.
+#[tauri::command]
+pub async fn describe_oauth_client(app: AppHandle) -> Result<OAuthClientDescription, CommandError> {
+  Ok(oauth_client::describe(&app_config_dir(&app)?).await)
+}
+
+/// Writes the report and the redacted HTTP capture as a zip for an issue.
+#[tauri::command]
+pub async fn export_diagnostics(app: AppHandle, path: String) -> Result<DiagnosticsReport, CommandError> {
+  Ok(diagnostics::export(&app_config_dir(&app)?, Path::new(&path)).await?)
+}
+
+/// Picks up hand edits to oauth_config.json without a restart.
+#[tauri::command]
+pub async fn reload_config(app: AppHandle) -> Result<ConfigChange, CommandError> {
+  Ok(config::reload(&app_config_dir(&app)?)?)
+}
+
+#[tauri::command]
+pub async fn query_upload_history(app: AppHandle, filter: HistoryFilter) -> Result<HistoryPage, CommandError> {
+  Ok(history::query(&app_config_dir(&app)?, &filter)?)
+}
+
+/// Uploads forced past warnings, newest first, for review; `since` is an
+/// RFC3339 timestamp or `YYYY-MM-DD`.
+#[tauri::command]
+pub async fn list_overridden_uploads(app: AppHandle, since: Option<String>) -> Result<Vec<UploadRecord>, CommandError> {
+  Ok(history::overridden(&app_config_dir(&app)?, since.as_deref())?)
+}
+
+#[tauri::command]
+pub async fn export_upload_history(app: AppHandle, path: String, format: ExportFormat) -> Result<usize, CommandError> {
+  Ok(history::export(&app_config_dir(&app)?, Path::new(&path), format)?)
+}
+
+/// Checks every video in the history, library and queue against YouTube and
+/// records the ones rejected or removed since.
+#[tauri::command]
+pub async fn verify_remote_state(app: AppHandle, options: VerifyOptions) -> Result<VerifyReport, CommandError> {
+  Ok(remote_state::verify(&app_config_dir(&app)?, &options).await?)
+}
+
+/// Writes every upload's metadata to `path`. Without `confirm` returns the
+/// quota estimate only; re-running after an interruption resumes. With
+/// `page_budget` stops early with a `next_cursor` to pass back.
+#[tauri::command]
+pub async fn export_channel_inventory(
+  app: AppHandle,
+  path: String,
+  format: ExportFormat,
+  confirm: Option<bool>,
+  cursor: Option<String>,
+  page_budget: Option<u32>,
+) -> Result<InventoryResult, CommandError> {
+  let paging = PageRequest { cursor, page_budget };
+  Ok(inventory::export_channel_inventory(&app_config_dir(&app)?, Path::new(&path), format, confirm.unwrap_or(false), &paging).await?)
+}
+
+/// Saves the video's metadata, thumbnails and caption tracks into
+/// `dir/<video_id>/`; unchanged videos are skipped.
+#[tauri::command]
+pub async fn archive_video(app: AppHandle, video_id: String, dir: String) -> Result<VideoArchive, CommandError> {
+  Ok(archive::archive_video(&app_config_dir(&app)?, &video_id, Path::new(&dir)).await?)
+}
+
+/// [`archive_video`] for every upload `filter` matches, with `archive-progress`
+/// events; running it again on the same `dir` resumes.
+#[tauri::command]
+pub async fn archive_channel(app: AppHandle, dir: String, filter: Option<ArchiveFilter>) -> Result<ChannelArchive, CommandError> {
+  Ok(archive::archive_channel(&app_config_dir(&app)?, Path::new(&dir), &filter.unwrap_or_default()).await?)
+}
+
+/// Checks every row of the plan at `path` and applies it only when all are
+/// valid; the report lists the problems or the per-row results.
+#[tauri::command]
+pub async fn apply_metadata_plan(app: AppHandle, path: String, options: Option<PlanOptions>) -> Result<PlanReport, CommandError> {
+  Ok(bulk::apply_metadata_plan(&app_config_dir(&app)?, Path::new(&path), &options.unwrap_or_default()).await?)
+}
+
+/// Two-step privacy change: without `confirm_token` returns the plan and a
+/// token, with it applies that plan. `options.cancel_schedules` allows
+/// changes that cancel a scheduled publication.
+#[tauri::command]
+pub async fn bulk_set_privacy(
+  app: AppHandle,
+  video_ids: Vec<String>,
+  privacy: Privacy,
+  confirm_token: Option<String>,
+  options: Option<PrivacyOptions>,
+) -> Result<BulkPrivacyResult, CommandError> {
+  let dir = app_config_dir(&app)?;
+  Ok(privacy::bulk_set_privacy(&dir, &video_ids, privacy, confirm_token.as_deref(), &options.unwrap_or_default()).await?)
+}
+
+/// Opens the config dir (tokens.json, oauth_config.json) in the file manager,
+/// creating it first. Returns the path; on failure the `OPEN_FAILED` error
+/// carries it in `details.target`.
+#[tauri::command]
+pub async fn open_config_dir(app: AppHandle) -> Result<String, CommandError> {
+  let dir = app_config_dir(&app)?;
+  fs::create_dir_all(&dir).map_err(|e| Error::Storage(e.to_string()))?;
+  let path = dir.to_string_lossy().into_owned();
+  open_path(&path)?;
+  Ok(path)
+}
+
+/// Whether portable or standard storage is active, and where.
+#[tauri::command]
+pub async fn get_storage_location(app: AppHandle) -> Result<StorageLocation, CommandError> {
+  Ok(storage_location(&app)?)
+}
+
+/// Copies the credentials and state from `from_path` (a source offered by
+/// `migration-available`, or any dir) into the config dir in use, backing
+/// up what it overwrites.
+#[tauri::command]
+pub async fn migrate_storage(app: AppHandle, from_path: String) -> Result<MigrationReport, CommandError> {
+  Ok(migration::migrate(&app_config_dir(&app)?, Path::new(&from_path))?)
+}
+
+/// Exits after a clean shutdown even with uploads running; the UI calls this
+/// once the user confirmed the `app-closing` prompt.
+#[tauri::command]
+pub async fn confirm_exit(app: AppHandle) -> Result<(), CommandError> {
+  let dir = app_config_dir(&app)?;
+  if !shutdown::stopping(&dir) {
+    exit_after_shutdown(app, dir);
+  }
+  Ok(())
+}
+
+/// Machine-readable codes `CommandError` can carry, for the UI to map.
+#[tauri::command]
+pub fn error_codes() -> Vec<ErrorCode> {
+  error::error_codes()
+}
+
+/// With a passphrase set, `include_tokens` (default true) needs it.
+#[tauri::command]
+pub async fn generate_env(
+  app: AppHandle,
+  include_tokens: Option<bool>,
+  passphrase: Option<String>,
+  format: Option<EnvFormat>,
+) -> Result<String, CommandError> {
+  let dir = app_config_dir(&app)?;
+  Ok(env_file::generate(&dir, include_tokens.unwrap_or(true), passphrase.as_deref(), format.unwrap_or_default()).await?)
+}
+
+/// The client config; the secret only with `include_secret`, which needs the
+/// app passphrase when one is set.
+#[tauri::command]
+pub async fn get_config(
+  app: AppHandle,
+  include_secret: Option<bool>,
+  passphrase: Option<String>,
+) -> Result<AppConfig, CommandError> {
+  let dir = app_config_dir(&app)?;
+  let include_secret = include_secret.unwrap_or(false);
+  if include_secret {
+    passphrase::require(&dir, passphrase.as_deref())?;
+  }
+  Ok(config::visible(&dir, include_secret)?)
+}
+
+/// Config and tokens in one JSON file for another machine; metadata
+/// snapshots only with `include_snapshots`.
+#[tauri::command]
+pub async fn export_bundle(
+  app: AppHandle,
+  path: String,
+  passphrase: Option<String>,
+  include_snapshots: Option<bool>,
+) -> Result<(), CommandError> {
+  let dir = app_config_dir(&app)?;
+  passphrase::require(&dir, passphrase.as_deref())?;
+  Ok(config::export_bundle(&dir, Path::new(&path), include_snapshots.unwrap_or(false))?)
+}
+
+/// `generate_env` for one profile; tokens need the app passphrase when set.
+#[tauri::command]
+pub async fn generate_env_for_profile(
+  app: AppHandle,
+  profile: String,
+  include_tokens: Option<bool>,
+  passphrase: Option<String>,
+  format: Option<EnvFormat>,
+) -> Result<String, CommandError> {
+  let root = profiles::root_of(&app_config_dir(&app)?);
+  let include_tokens = include_tokens.unwrap_or(true);
+  if include_tokens {
+    passphrase::require(&root, passphrase.as_deref())?;
+  }
+  let (text, _) = env_file::generate_for_profile(&root, &profile, include_tokens, format.unwrap_or_default()).await?;
+  Ok(text)
+}
+
+/// Writes `<profile>.env` for every profile into `dir`; profiles without
+/// valid tokens get a file with a warning instead of failing the batch.
+#[tauri::command]
+pub async fn generate_all_envs(app: AppHandle, dir: String, passphrase: Option<String>) -> Result<Vec<EnvFileResult>, CommandError> {
+  let root = profiles::root_of(&app_config_dir(&app)?);
+  passphrase::require(&root, passphrase.as_deref())?;
+  Ok(env_file::generate_all(&root, Path::new(&dir)).await?)
+}
+
+/// A fresh access token for CI, without the refresh token or client secret.
+/// Needs the app passphrase when one is set.
+#[tauri::command]
+pub async fn mint_ci_token(app: AppHandle, passphrase: Option<String>) -> Result<CiToken, CommandError> {
+  let dir = app_config_dir(&app)?;
+  passphrase::require(&dir, passphrase.as_deref())?;
+  Ok(ci_token::mint(&dir).await?)
+}
+
+fn env_path(app: &AppHandle, path: Option<String>) -> Result<String, Error> {
+  path
+    .or_else(|| read_config(app).and_then(|c| c.watched_env_file))
+    .ok_or_else(|| Error::Validation("Podaj ścieżkę pliku .env albo ustaw watched_env_file".into()))
+}
+
+/// Which managed keys of the `.env` at `path` (default `watched_env_file`)
+/// match the app; names only, no values.
+#[tauri::command]
+pub async fn check_env_file(app: AppHandle, path: Option<String>) -> Result<EnvDrift, CommandError> {
+  let path = env_path(&app, path)?;
+  Ok(env_drift::check(&app_config_dir(&app)?, Path::new(&path))?)
+}
+
+/// Writes the app's values into the `.env` at `path`, keeping its other
+/// lines. Needs the app passphrase when one is set.
+#[tauri::command]
+pub async fn sync_env_file(app: AppHandle, path: Option<String>, passphrase: Option<String>) -> Result<EnvSyncResult, CommandError> {
+  let dir = app_config_dir(&app)?;
+  passphrase::require(&dir, passphrase.as_deref())?;
+  let path = env_path(&app, path)?;
+  Ok(env_drift::sync(&dir, Path::new(&path))?)
+}
+
+/// Mints a CI token into `path` (`YOUTUBE_ACCESS_TOKEN`, `YOUTUBE_TOKEN_EXPIRES_AT`, mode 0600).
+#[tauri::command]
+pub async fn write_ci_env(app: AppHandle, path: String, passphrase: Option<String>) -> Result<CiToken, CommandError> {
+  let dir = app_config_dir(&app)?;
+  passphrase::require(&dir, passphrase.as_deref())?;
+  Ok(ci_token::write_env(&dir, Path::new(&path)).await?)
+}
+
+#[tauri::command]
+pub async fn get_audit_log(app: AppHandle) -> Result<Vec<AuditEntry>, CommandError> {
+  Ok(audit::entries(&app_config_dir(&app)?))
+}
+
+/// Deletes stale cache entries, expired upload sessions, old logs and
+/// orphaned temp files; `dry_run` only lists them.
+#[tauri::command]
+pub async fn cleanup_storage(app: AppHandle, options: Option<CleanupOptions>) -> Result<CleanupReport, CommandError> {
+  Ok(cleanup::cleanup(&app_config_dir(&app)?, &options.unwrap_or_default())?)
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: AppHandle) -> Result<Vec<String>, CommandError> {
+  Ok(profiles::list(&profiles::root_of(&app_config_dir(&app)?)))
+}
+
+#[tauri::command]
+pub async fn get_active_profile(app: AppHandle) -> Result<String, CommandError> {
+  Ok(profiles::name_of(&app_config_dir(&app)?))
+}
+
+/// Makes `name` the profile every other command works in. Refused while the
+/// current profile uploads; ends with `profile-switched`.
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, name: String) -> Result<String, CommandError> {
+  let root = profiles::root_of(&app_config_dir(&app)?);
+  if let Some(switched) = profiles::switch(&root, &name)? {
+    finish_profile_switch(&app, &switched)?;
+  }
+  Ok(name)
+}
+
+/// Deletes profile `name` with everything in its dir and returns the
+/// profiles left. Deleting the active one switches to `default`.
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, name: String) -> Result<Vec<String>, CommandError> {
+  let root = profiles::root_of(&app_config_dir(&app)?);
+  if let Some(switched) = profiles::delete(&root, &name)? {
+    finish_profile_switch(&app, &switched)?;
+  }
+  Ok(profiles::list(&root))
+}
+
+/// Sets the app passphrase; replacing one needs `current`.
+#[tauri::command]
+pub async fn set_app_passphrase(app: AppHandle, passphrase: String, current: Option<String>) -> Result<(), CommandError> {
+  Ok(passphrase::set(&app_config_dir(&app)?, &passphrase, current.as_deref())?)
+}
+
+#[tauri::command]
+pub async fn clear_app_passphrase(app: AppHandle, current: String) -> Result<(), CommandError> {
+  Ok(passphrase::clear(&app_config_dir(&app)?, &current)?)
+}
+
+/// Byte count in binary units in the app language, e.g. `1,5 MiB`.
+#[tauri::command]
+pub async fn format_bytes(app: AppHandle, bytes: u64) -> Result<String, CommandError> {
+  Ok(format::bytes(bytes, format::language(&app_config_dir(&app)?)))
+}
+
+#[tauri::command]
+pub async fn format_duration(app: AppHandle, secs: f64) -> Result<String, CommandError> {
+  Ok(format::duration(secs, format::language(&app_config_dir(&app)?)))
+}
+
+/// Remaining-time text; `null` (estimate not ready) has its own wording.
+#[tauri::command]
+pub async fn format_eta(app: AppHandle, secs: Option<u64>) -> Result<String, CommandError> {
+  Ok(format::eta(secs, format::language(&app_config_dir(&app)?)))
+}
+
+#[tauri::command]
+pub async fn format_timestamp(app: AppHandle, value: String) -> Result<String, CommandError> {
+  Ok(format::timestamp(&value, format::language(&app_config_dir(&app)?))?)
+}
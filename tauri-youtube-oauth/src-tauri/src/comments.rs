@@ -0,0 +1,136 @@
+//! Comments on the channel's own videos. The Data API can post a top-level
+//! comment and moderate one (`comments.setModerationStatus`), but has no
+//! endpoint for pinning or hearting, nor for liking a comment: those exist
+//! only in YouTube Studio. Such a step fails with `NotSupportedByApi` after
+//! whatever part the API does cover, rather than reporting it done.
+
+use crate::{
+  api::{self, ApiCall},
+  error::Error,
+  scopes,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Comment endpoints accept force-ssl only.
+pub(crate) const COMMENT_SCOPES: &[&str] = &[scopes::YOUTUBE_FORCE_SSL];
+/// Longest comment YouTube takes, in characters.
+const MAX_COMMENT_CHARS: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostedComment {
+  /// Id of the comment itself, as `pin_comment` takes it.
+  pub comment_id: String,
+  pub thread_id: String,
+  pub video_id: String,
+  /// `published` for the owner's comments; `heldForReview` when the
+  /// channel holds every comment.
+  pub moderation_status: Option<String>,
+}
+
+/// Payload of `first-comment-posted`, after the sidecar's `first_comment`
+/// went up.
+#[derive(Serialize, Debug, Clone)]
+pub struct FirstCommentPosted {
+  pub video_id: String,
+  pub comment_id: String,
+  /// Always false while the API can't pin; `pin_hint` says what to do instead.
+  pub pinned: bool,
+  pub pin_hint: Option<String>,
+}
+
+fn studio_comments_url(video_id: &str) -> String {
+  format!("https://studio.youtube.com/video/{}/comments", video_id)
+}
+
+fn check_comment_id(comment_id: &str) -> Result<(), Error> {
+  if comment_id.trim().is_empty() {
+    return Err(Error::Validation("Brak identyfikatora komentarza".into()));
+  }
+  Ok(())
+}
+
+/// Posts `text` as a top-level comment of `video_id` from the channel.
+pub async fn post_comment(dir: &Path, video_id: &str, text: &str) -> Result<PostedComment, Error> {
+  api::ensure_writable(dir)?;
+  let text = text.trim();
+  if text.is_empty() {
+    return Err(Error::Validation("Treść komentarza nie może być pusta".into()));
+  }
+  if text.chars().count() > MAX_COMMENT_CHARS {
+    return Err(Error::Validation(format!("Komentarz może mieć najwyżej {} znaków", MAX_COMMENT_CHARS)));
+  }
+  let body = json!({ "snippet": { "videoId": video_id, "topLevelComment": { "snippet": { "textOriginal": text } } } });
+  let call = ApiCall::post("/youtube/v3/commentThreads").query("part", "snippet").json(body).scopes(COMMENT_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  let top = &resp["snippet"]["topLevelComment"];
+  let comment_id = top["id"]
+    .as_str()
+    .ok_or_else(|| Error::Api { status: 200, reason: "invalidJson".into(), message: "commentThreads.insert: brak id komentarza".into() })?;
+  Ok(PostedComment {
+    comment_id: comment_id.to_string(),
+    thread_id: resp["id"].as_str().unwrap_or_default().to_string(),
+    video_id: resp["snippet"]["videoId"].as_str().unwrap_or(video_id).to_string(),
+    moderation_status: top["snippet"]["moderationStatus"].as_str().map(str::to_string),
+  })
+}
+
+/// The comment's snippet; only the video's owner sees `moderationStatus`.
+async fn snippet(dir: &Path, comment_id: &str) -> Result<Value, Error> {
+  let call = ApiCall::get("/youtube/v3/comments").query("part", "snippet").query("id", comment_id).scopes(COMMENT_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  match &resp["items"][0]["snippet"] {
+    Value::Object(_) => Ok(resp["items"][0]["snippet"].clone()),
+    _ => Err(Error::Validation(format!("Nie znaleziono komentarza {}", comment_id))),
+  }
+}
+
+/// Gets `comment_id` as far towards pinned as the API allows: a comment held
+/// for review or marked as spam is published, since only a visible comment
+/// can be pinned. The pin itself has no endpoint, so this always ends in
+/// `NotSupportedByApi` naming the Studio page where it's one click.
+pub async fn pin_comment(dir: &Path, comment_id: &str) -> Result<(), Error> {
+  api::ensure_writable(dir)?;
+  check_comment_id(comment_id)?;
+  let snippet = snippet(dir, comment_id).await?;
+  if snippet["moderationStatus"].as_str().is_some_and(|s| s != "published") {
+    let call = ApiCall::post("/youtube/v3/comments/setModerationStatus")
+      .query("id", comment_id)
+      .query("moderationStatus", "published")
+      .scopes(COMMENT_SCOPES);
+    api::execute(dir, call).await?;
+  }
+  let video_id = snippet["videoId"].as_str().unwrap_or_default();
+  Err(Error::NotSupportedByApi {
+    operation: "pin_comment".into(),
+    message: format!(
+      "Komentarz {} jest opublikowany, ale YouTube API nie pozwala go przypiąć — przypnij go w YouTube Studio: {}",
+      comment_id,
+      studio_comments_url(video_id)
+    ),
+  })
+}
+
+/// Hearting has no endpoint at all; fails with `NotSupportedByApi` without
+/// sending anything.
+pub async fn heart_comment(dir: &Path, comment_id: &str) -> Result<(), Error> {
+  api::ensure_writable(dir)?;
+  check_comment_id(comment_id)?;
+  Err(Error::NotSupportedByApi {
+    operation: "heart_comment".into(),
+    message: "YouTube API nie pozwala serduszkować komentarzy — zrób to w YouTube Studio albo pod filmem".into(),
+  })
+}
+
+/// Posts `text` and takes it as far towards pinned as [`pin_comment`] can.
+/// A pin the API can't make isn't a failure here: the payload says so.
+pub(crate) async fn post_first_comment(dir: &Path, video_id: &str, text: &str) -> Result<FirstCommentPosted, Error> {
+  let posted = post_comment(dir, video_id, text).await?;
+  let pin_hint = match pin_comment(dir, &posted.comment_id).await {
+    Ok(()) => None,
+    Err(Error::NotSupportedByApi { message, .. }) => Some(message),
+    Err(e) => return Err(e),
+  };
+  Ok(FirstCommentPosted { video_id: posted.video_id, comment_id: posted.comment_id, pinned: pin_hint.is_none(), pin_hint })
+}
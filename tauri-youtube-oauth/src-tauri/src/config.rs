@@ -0,0 +1,153 @@
+use crate::{
+  callback, error::Error, hooks, http_headers, localizations, metadata, oauth_client, read_config_from_dir, read_tokens_from_dir, runtime, setup,
+  sinks, snapshots, tls, upload, write_tokens_to_dir, AppConfig,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeSet, fs, path::Path};
+
+/// Payload of the `config-changed` event and result of `reload_config`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ConfigChange {
+  /// Top-level keys that were added, removed or changed. Names only, so
+  /// secrets never leave the backend.
+  pub changed: Vec<String>,
+  /// Stored tokens were issued for a different client_id; the UI should
+  /// suggest logging in again.
+  pub client_id_mismatch: bool,
+}
+
+pub fn validate(cfg: &AppConfig) -> Result<(), Error> {
+  if cfg.client_id.trim().is_empty() || cfg.client_secret.trim().is_empty() {
+    return Err(Error::Config("Konfiguracja wymaga client_id i client_secret".into()));
+  }
+  if cfg.redirect_port == Some(0) {
+    return Err(Error::Config("redirect_port musi być od 1 do 65535".into()));
+  }
+  if cfg.metrics_endpoint.as_ref().is_some_and(|m| m.port == 0) {
+    return Err(Error::Config("metrics_endpoint.port musi być od 1 do 65535".into()));
+  }
+  if cfg.status_file.as_ref().and_then(|s| s.path.as_deref()).is_some_and(|p| p.trim().is_empty()) {
+    return Err(Error::Config("status_file.path nie może być pusty".into()));
+  }
+  if cfg.daily_quota == Some(0) {
+    return Err(Error::Config("daily_quota musi być dodatnie".into()));
+  }
+  if cfg.queue_max_attempts == Some(0) {
+    return Err(Error::Config("queue_max_attempts musi być dodatnie".into()));
+  }
+  if let Some(bytes) = cfg.upload_chunk_bytes {
+    upload::validate_chunk_size(bytes)?;
+  }
+  for (i, hook) in cfg.post_upload_hooks.iter().enumerate() {
+    hooks::validate(hook).map_err(|e| e.context(&format!("post_upload_hooks[{}]", i)))?;
+  }
+  http_headers::validate(&cfg.custom_headers).map_err(|e| e.context("custom_headers"))?;
+  for (i, sink) in cfg.event_sinks.iter().enumerate() {
+    sinks::validate(sink).map_err(|e| e.context(&format!("event_sinks[{}]", i)))?;
+  }
+  if let Some(tpl) = &cfg.metadata_template {
+    metadata::validate(tpl).map_err(|e| e.context("metadata_template"))?;
+  }
+  if let Some(target) = &cfg.callback_success_redirect {
+    let parsed = url::Url::parse(target).map_err(|e| Error::Config(format!("Nieprawidłowy callback_success_redirect {}: {}", target, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+      return Err(Error::Config(format!("callback_success_redirect musi używać http(s): {}", target)));
+    }
+  }
+  if cfg.content_owner_channel_id.is_some() && cfg.content_owner_id.is_none() {
+    return Err(Error::Config("content_owner_channel_id wymaga content_owner_id".into()));
+  }
+  if let Some(domain) = cfg.hosted_domain.as_deref().filter(|d| d.trim().is_empty() || d.contains(['@', '/', ' '])) {
+    return Err(Error::Config(format!("hosted_domain musi być domeną (np. firma.pl), podano \"{}\"", domain)));
+  }
+  if let Some(language) = &cfg.default_metadata_language {
+    localizations::validate_language(language).map_err(|e| e.context("default_metadata_language"))?;
+  }
+  if let Some(bad) = cfg.scopes.iter().find(|s| !s.starts_with("https://www.googleapis.com/auth/")) {
+    return Err(Error::Config(format!("Nieznany zakres OAuth: {}", bad)));
+  }
+  Ok(())
+}
+
+fn changed_keys(old: &Value, new: &Value) -> Vec<String> {
+  let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+    return Vec::new();
+  };
+  let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+  keys.into_iter().filter(|k| old.get(*k) != new.get(*k)).cloned().collect()
+}
+
+/// Records the config as first seen in this process, the baseline for the
+/// next reload's diff.
+pub(crate) fn remember(dir: &Path) {
+  runtime::for_dir(dir).seed_config_snapshot(|| read_config_from_dir(dir).and_then(|c| serde_json::to_value(c).ok()));
+}
+
+/// Re-reads and validates the config, then emits `config-changed` when
+/// anything differs from the last accepted version. An invalid file is
+/// rejected and the previous baseline kept; so is a new `redirect_port`
+/// nothing can listen on (see [`callback::check_port`]).
+pub fn reload(dir: &Path) -> Result<ConfigChange, Error> {
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak lub nieczytelny oauth_config.json".into()))?;
+  validate(&cfg)?;
+  let new = serde_json::to_value(&cfg).map_err(|e| Error::Storage(e.to_string()))?;
+  let rt = runtime::for_dir(dir);
+  let old = rt.config_snapshot().unwrap_or_else(|| Value::Object(Default::default()));
+  if old.get("redirect_port") != new.get("redirect_port") {
+    callback::check_port(dir, callback::configured_port(&cfg))?;
+  }
+  rt.replace_config_snapshot(new.clone());
+  let client_id_mismatch = read_tokens_from_dir(dir)
+    .and_then(|t| t.client_id)
+    .is_some_and(|issued_for| issued_for != cfg.client_id);
+  let change = ConfigChange { changed: changed_keys(&old, &new), client_id_mismatch };
+  // Rewritten so turning persist_access_token off takes the token off disk now.
+  if change.changed.iter().any(|k| k == "persist_access_token") {
+    if let Some(t) = read_tokens_from_dir(dir) {
+      write_tokens_to_dir(dir, &t)?;
+    }
+  }
+  if change.changed.iter().any(|k| tls::CLIENT_KEYS.contains(&k.as_str())) {
+    tls::rebuild_clients(dir);
+  }
+  if !change.changed.is_empty() {
+    rt.emit("config-changed", &change);
+    setup::notify(dir);
+    oauth_client::check(dir);
+  }
+  Ok(change)
+}
+
+/// The config as shown to the UI: never the passphrase hash, and the client
+/// secret only with `include_secret`.
+pub fn visible(dir: &Path, include_secret: bool) -> Result<AppConfig, Error> {
+  let mut cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  cfg.passphrase_hash = None;
+  if !include_secret {
+    cfg.client_secret.clear();
+    for sink in &mut cfg.event_sinks {
+      if let sinks::EventSink::Webhook { secret, .. } = sink {
+        *secret = None;
+      }
+    }
+  }
+  Ok(cfg)
+}
+
+/// Writes config and tokens to one file for moving to another machine. The
+/// passphrase hash stays behind: it guards this machine only. Metadata
+/// snapshots describe unlisted and private videos too, so they go along
+/// only with `include_snapshots`.
+pub fn export_bundle(dir: &Path, path: &Path, include_snapshots: bool) -> Result<(), Error> {
+  let mut bundle = json!({
+    "version": 1,
+    "config": visible(dir, true)?,
+    "tokens": read_tokens_from_dir(dir),
+  });
+  if include_snapshots {
+    bundle["snapshots"] = json!(snapshots::all(dir));
+  }
+  let s = serde_json::to_string_pretty(&bundle).map_err(|e| Error::Storage(e.to_string()))?;
+  fs::write(path, s).map_err(|e| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e)))
+}
@@ -0,0 +1,65 @@
+use crate::{runtime, tls, token_endpoint};
+use serde::Serialize;
+use std::{path::Path, time::Duration};
+
+const CHECK_TTL: Duration = Duration::from_secs(60);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Connectivity {
+  pub online: bool,
+  /// Offline mode was switched on by hand (`set_offline_mode`).
+  pub forced_offline: bool,
+}
+
+/// Any HTTP answer from the token endpoint's host counts as online.
+async fn probe(dir: &Path) -> bool {
+  let Ok(mut url) = reqwest::Url::parse(&token_endpoint()) else {
+    return false;
+  };
+  url.set_path("/");
+  url.set_query(None);
+  let Ok(head) = tls::probe(dir, url.as_str()) else {
+    return false;
+  };
+  head.timeout(PROBE_TIMEOUT).send().await.is_ok()
+}
+
+/// Records the observed state, emitting `connectivity-changed` on a flip.
+pub(crate) fn record(dir: &Path, online: bool) {
+  let rt = runtime::for_dir(dir);
+  if rt.set_connectivity(online) != Some(online) {
+    rt.emit("connectivity-changed", Connectivity { online, forced_offline: rt.forced_offline() });
+  }
+}
+
+/// Whether to attempt network calls; probes at most once per minute.
+pub(crate) async fn online(dir: &Path) -> bool {
+  let rt = runtime::for_dir(dir);
+  if rt.forced_offline() {
+    return false;
+  }
+  if let Some(online) = rt.connectivity(CHECK_TTL) {
+    return online;
+  }
+  let online = probe(dir).await;
+  record(dir, online);
+  online
+}
+
+pub async fn status(dir: &Path) -> Connectivity {
+  let online = online(dir).await;
+  Connectivity { online, forced_offline: runtime::for_dir(dir).forced_offline() }
+}
+
+/// Forces offline mode on, or back off to automatic detection.
+pub async fn set_offline_mode(dir: &Path, enabled: bool) -> Connectivity {
+  let rt = runtime::for_dir(dir);
+  rt.set_forced_offline(enabled);
+  rt.clear_connectivity();
+  let online = online(dir).await;
+  if enabled {
+    rt.emit("connectivity-changed", Connectivity { online, forced_offline: true });
+  }
+  Connectivity { online, forced_offline: enabled }
+}
@@ -0,0 +1,105 @@
+//! Pre-flight of the consent screen. A client whose Cloud Console entry lacks
+//! the loopback redirect, or that was deleted, only shows Google's error page
+//! after the browser opened; asking the auth endpoint for the same URL with
+//! `prompt=none` first gets that verdict without a browser. Whatever the
+//! probe can't tell (no network, an unfamiliar answer) lets the login go
+//! ahead; the callback and the code exchange still translate the error.
+
+use crate::{
+  callback, consent_url,
+  error::Error,
+  format, guidance, http_headers,
+  observe::{self, Exchange},
+  timeouts::{self, OperationClass},
+  tls, AppConfig,
+};
+use reqwest::Method;
+use std::{path::Path, time::Instant};
+
+/// Errors the consent screen reports for a broken client, as the catalog key
+/// each one maps to.
+const PROBED: &[(&str, &str)] =
+  &[("redirect_uri_mismatch", "redirect_uri_mismatch"), ("invalid_client", "invalid_client"), ("deleted_client", "invalid_client")];
+
+/// Lenient base64 (standard or URL-safe, padding optional); `authError` in
+/// Google's error redirects is one.
+pub(crate) fn base64_decode(s: &str) -> Vec<u8> {
+  let value = |c: u8| match c {
+    b'A'..=b'Z' => Some(c - b'A'),
+    b'a'..=b'z' => Some(c - b'a' + 26),
+    b'0'..=b'9' => Some(c - b'0' + 52),
+    b'+' | b'-' => Some(62),
+    b'/' | b'_' => Some(63),
+    _ => None,
+  };
+  let (mut out, mut acc, mut bits) = (Vec::new(), 0u32, 0);
+  for v in s.bytes().map_while(value) {
+    acc = (acc << 6) | v as u32;
+    bits += 6;
+    if bits >= 8 {
+      bits -= 8;
+      out.push((acc >> bits) as u8);
+    }
+  }
+  out
+}
+
+/// The probed error named in a response: in its body, in the redirect
+/// target, or inside that target's `authError`.
+fn probed_error(location: Option<&str>, body: &str) -> Option<&'static str> {
+  let mut texts = vec![body.to_string()];
+  if let Some(location) = location {
+    texts.push(location.to_string());
+    if let Ok(url) = url::Url::parse(location) {
+      texts.extend(url.query_pairs().filter(|(k, _)| k == "authError").map(|(_, v)| String::from_utf8_lossy(&base64_decode(&v)).into_owned()));
+    }
+  }
+  PROBED.iter().find(|(raw, _)| texts.iter().any(|t| t.contains(raw))).map(|(_, key)| *key)
+}
+
+/// Asks the consent screen about the client in `cfg` without a browser.
+/// Fails with `CONSENT_FAILED` when Google already refuses the redirect URI
+/// or the client; any other answer, or none, passes.
+pub async fn check(dir: &Path, cfg: &AppConfig, scopes: &[String]) -> Result<(), Error> {
+  // Probed before the flow starts, so with the port it's going to use.
+  let port = callback::configured_port(cfg);
+  let redirect = callback::redirect_uri(port);
+  // Never followed back to the callback, so no flow state.
+  let url = consent_url(cfg, &redirect, None, scopes, false, "none");
+  let timeout = timeouts::for_class(dir, OperationClass::Auth);
+  let started = Instant::now();
+  let trace_id = http_headers::trace_id();
+  let observed = |status, body, error| Exchange {
+    class: OperationClass::Auth,
+    method: "GET",
+    url: &url,
+    started,
+    status,
+    body,
+    error,
+    bytes_sent: 0,
+    attempt: 1,
+    trace_id: &trace_id,
+  };
+  let req = tls::request_unredirected(dir, Method::GET, &url, &trace_id)?.timeout(timeout);
+  let resp = match req.send().await {
+    Ok(resp) => resp,
+    Err(e) => {
+      let err = timeouts::transport_error(e, "consent probe", timeout);
+      observe::exchange(dir, observed(None, "", Some(&err)));
+      return Ok(());
+    }
+  };
+  let status = resp.status();
+  let location = resp.headers().get(reqwest::header::LOCATION).and_then(|v| v.to_str().ok()).map(str::to_string);
+  let body = resp.text().await.unwrap_or_default();
+  observe::exchange(dir, observed(Some(status.as_u16()), &body, None));
+  // Sent back to our own callback: the redirect URI is registered.
+  if location.as_deref().is_some_and(|l| l.starts_with(&redirect)) {
+    return Ok(());
+  }
+  match probed_error(location.as_deref(), &body) {
+    Some(error) => Err(Error::ConsentFailed(Box::new(guidance::on_port(guidance::for_preflight(error, format::language(dir)), port)))),
+    None => Ok(()),
+  }
+}
@@ -0,0 +1,182 @@
+//! Panic reports in `crashes/` under the config dir, and a supervisor that
+//! restarts background tasks killed by a panic instead of letting the
+//! feature stop silently.
+//!
+//! Reports hold the panic message, location, thread and a backtrace. Nothing
+//! in the app formats tokens into panics; the message still goes through
+//! `redact::text` in case a library does.
+
+use crate::{error::Error, redact, runtime, shutdown};
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+  backtrace::Backtrace,
+  fs,
+  future::Future,
+  panic::PanicHookInfo,
+  path::{Path, PathBuf},
+  sync::{Mutex, OnceLock},
+  time::Duration,
+};
+
+pub(crate) const CRASH_DIR: &str = "crashes";
+/// Restarts after this many panics in a row would only spin.
+const MAX_RESTARTS: u32 = 5;
+const RESTART_BASE: Duration = Duration::from_secs(1);
+const RESTART_MAX: Duration = Duration::from_secs(60);
+/// A task that ran this long before panicking starts the backoff over.
+const HEALTHY_RUN: Duration = Duration::from_secs(10 * 60);
+
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Report written by the most recent panic, or why it couldn't be, for the
+/// crash event.
+static LAST_REPORT: Mutex<Option<Result<PathBuf, String>>> = Mutex::new(None);
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CrashReport {
+  pub file_name: String,
+  pub path: String,
+  pub size_bytes: u64,
+  /// Unix seconds, from the file's modification time.
+  pub created_at: u64,
+  /// First line of the panic message.
+  pub summary: String,
+}
+
+/// Payload of `background-task-crashed`.
+#[derive(Serialize, Debug, Clone)]
+pub struct TaskCrashed {
+  pub task: String,
+  pub message: String,
+  pub report: Option<String>,
+  pub restarts: u32,
+  /// Absent when the task was given up on.
+  pub restart_in_ms: Option<u64>,
+}
+
+fn message(payload: &(dyn std::any::Any + Send)) -> String {
+  let raw = payload.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| payload.downcast_ref::<String>().cloned());
+  redact::text(raw.as_deref().unwrap_or("(panika bez komunikatu)"))
+}
+
+fn write_report(dir: &Path, info: &PanicHookInfo) -> std::io::Result<PathBuf> {
+  let crashes = dir.join(CRASH_DIR);
+  fs::create_dir_all(&crashes)?;
+  let now = Utc::now();
+  let mut path = crashes.join(format!("{}.txt", now.format("%Y%m%dT%H%M%S%.3fZ")));
+  for n in 1.. {
+    if !path.exists() {
+      break;
+    }
+    path = crashes.join(format!("{}-{}.txt", now.format("%Y%m%dT%H%M%S%.3fZ"), n));
+  }
+  let thread = std::thread::current();
+  let report = format!(
+    "{}\n\nwersja: {}\nczas: {}\nwątek: {}\nmiejsce: {}\n\n{}\n",
+    message(info.payload()),
+    env!("CARGO_PKG_VERSION"),
+    now.to_rfc3339(),
+    thread.name().unwrap_or("(bez nazwy)"),
+    info.location().map_or_else(|| "(nieznane)".to_string(), |l| l.to_string()),
+    Backtrace::force_capture(),
+  );
+  fs::write(&path, report)?;
+  Ok(path)
+}
+
+/// Installs the panic hook for `dir`; later calls keep the first dir. The
+/// default hook still runs, so panics show up on stderr as before. The hook
+/// only records the outcome: a panic may hold the locks emitting takes, so
+/// a report that couldn't be written is warned about by [`supervise`].
+pub fn install(dir: &Path) {
+  if CONFIG_DIR.set(dir.to_path_buf()).is_err() {
+    return;
+  }
+  let previous = std::panic::take_hook();
+  std::panic::set_hook(Box::new(move |info| {
+    if let Some(dir) = CONFIG_DIR.get() {
+      *LAST_REPORT.lock().unwrap_or_else(|e| e.into_inner()) = Some(write_report(dir, info).map_err(|e| e.to_string()));
+    }
+    previous(info);
+  }));
+}
+
+/// Reports in `crashes/`, newest first.
+pub fn list(dir: &Path) -> Result<Vec<CrashReport>, Error> {
+  let entries = match fs::read_dir(dir.join(CRASH_DIR)) {
+    Ok(entries) => entries,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => return Err(Error::Storage(format!("Nie można odczytać katalogu {}: {}", CRASH_DIR, e))),
+  };
+  let mut reports: Vec<CrashReport> = entries
+    .filter_map(|e| e.ok())
+    .filter(|e| e.path().extension().is_some_and(|x| x == "txt"))
+    .filter_map(|e| {
+      let meta = e.metadata().ok()?;
+      let created_at = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+      let summary = fs::read_to_string(e.path()).ok()?.lines().next().unwrap_or_default().to_string();
+      Some(CrashReport {
+        file_name: e.file_name().to_string_lossy().into_owned(),
+        path: e.path().to_string_lossy().into_owned(),
+        size_bytes: meta.len(),
+        created_at,
+        summary,
+      })
+    })
+    .collect();
+  // Names are timestamps, so they sort in time order.
+  reports.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+  Ok(reports)
+}
+
+fn backoff(restarts: u32) -> Duration {
+  RESTART_BASE.saturating_mul(1 << restarts.min(6)).min(RESTART_MAX)
+}
+
+/// Runs `task` as a tokio task and starts it again with backoff whenever it
+/// panics, emitting `background-task-crashed` each time. Returns once the
+/// task ends on its own or has panicked `MAX_RESTARTS` times in a row, and
+/// doesn't restart tasks a profile switch ended meanwhile.
+pub async fn supervise<F, Fut>(dir: &Path, name: &str, task: F)
+where
+  F: Fn() -> Fut,
+  Fut: Future<Output = ()> + Send + 'static,
+{
+  let generation = shutdown::task_generation(dir);
+  let mut restarts = 0;
+  loop {
+    let started = std::time::Instant::now();
+    let Err(e) = tokio::spawn(task()).await else {
+      return;
+    };
+    if !e.is_panic() {
+      return;
+    }
+    if started.elapsed() >= HEALTHY_RUN {
+      restarts = 0;
+    }
+    let message = message(&*e.into_panic());
+    let last = LAST_REPORT.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let report = match last {
+      Some(Ok(path)) => Some(path.to_string_lossy().into_owned()),
+      Some(Err(e)) => {
+        runtime::for_dir(dir).warn("crash", format!("Nie udało się zapisać raportu awarii: {}", e));
+        None
+      }
+      None => None,
+    };
+    let delay = (restarts < MAX_RESTARTS).then(|| backoff(restarts));
+    runtime::for_dir(dir).emit(
+      "background-task-crashed",
+      TaskCrashed { task: name.to_string(), message, report, restarts, restart_in_ms: delay.map(|d| d.as_millis() as u64) },
+    );
+    let Some(delay) = delay else {
+      return;
+    };
+    tokio::time::sleep(delay).await;
+    if shutdown::tasks_ended(dir, generation) {
+      return;
+    }
+    restarts += 1;
+  }
+}
@@ -0,0 +1,62 @@
+/// Appends one CSV record (RFC 4180 quoting) terminated by CRLF.
+pub(crate) fn write_row<S: AsRef<str>>(out: &mut String, fields: &[S]) {
+  for (i, field) in fields.iter().enumerate() {
+    if i > 0 {
+      out.push(',');
+    }
+    let f = field.as_ref();
+    if f.contains(['"', ',', '\n', '\r']) {
+      out.push('"');
+      out.push_str(&f.replace('"', "\"\""));
+      out.push('"');
+    } else {
+      out.push_str(f);
+    }
+  }
+  out.push_str("\r\n");
+}
+
+/// Splits CSV text into records (RFC 4180 quoting, CRLF or LF line ends).
+/// Blank lines are skipped and a leading BOM is ignored.
+pub(crate) fn parse(text: &str) -> Result<Vec<Vec<String>>, String> {
+  let mut records = Vec::new();
+  let (mut record, mut field) = (Vec::new(), String::new());
+  let (mut quoted, mut line) = (false, 1);
+  let mut chars = text.strip_prefix('\u{feff}').unwrap_or(text).chars().peekable();
+  while let Some(c) = chars.next() {
+    match (quoted, c) {
+      (true, '"') if chars.peek() == Some(&'"') => {
+        chars.next();
+        field.push('"');
+      }
+      (true, '"') => quoted = false,
+      (true, c) => {
+        if c == '\n' {
+          line += 1;
+        }
+        field.push(c);
+      }
+      (false, '"') if field.is_empty() => quoted = true,
+      (false, '"') => return Err(format!("Cudzysłów w środku pola niecytowanego (wiersz {})", line)),
+      (false, ',') => record.push(std::mem::take(&mut field)),
+      (false, '\r') if chars.peek() == Some(&'\n') => {}
+      (false, '\n') => {
+        record.push(std::mem::take(&mut field));
+        if record.iter().any(|f| !f.is_empty()) || record.len() > 1 {
+          records.push(std::mem::take(&mut record));
+        }
+        record.clear();
+        line += 1;
+      }
+      (false, c) => field.push(c),
+    }
+  }
+  if quoted {
+    return Err(format!("Niezamknięty cudzysłów (wiersz {})", line));
+  }
+  if !field.is_empty() || !record.is_empty() {
+    record.push(field);
+    records.push(record);
+  }
+  Ok(records)
+}
@@ -0,0 +1,66 @@
+//! Resumable listing cursors. A long listing can stop after a few pages and
+//! hand the frontend a cursor to continue from later; the cursor carries the
+//! next `pageToken` and how many items came before it, and is signed with a
+//! key made fresh each run, so a cursor from another listing, an earlier run
+//! or edited by hand is refused instead of turning into odd requests.
+
+use crate::{error::Error, runtime};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::path::Path;
+
+/// Where a list command starts and how far it goes in one call.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PageRequest {
+  /// `next_cursor` of the previous call; absent starts from the beginning.
+  #[serde(default)]
+  pub cursor: Option<String>,
+  /// Pages fetched before returning with a `next_cursor`; absent means all.
+  #[serde(default)]
+  pub page_budget: Option<u32>,
+}
+
+fn mac(dir: &Path, listing: &str, collected: usize, page_token: &str) -> Hmac<Sha256> {
+  let mut mac = Hmac::<Sha256>::new_from_slice(&runtime::for_dir(dir).cursor_key()).expect("HMAC takes keys of any length");
+  mac.update(format!("{}\n{}\n{}", listing, collected, page_token).as_bytes());
+  mac
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+  if text.len() % 2 != 0 || !text.is_ascii() {
+    return None;
+  }
+  (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok()).collect()
+}
+
+fn signature(dir: &Path, listing: &str, collected: usize, page_token: &str) -> String {
+  hex(&mac(dir, listing, collected, page_token).finalize().into_bytes())
+}
+
+/// Cursor continuing `listing` at `page_token`, after `collected` items.
+pub(crate) fn encode(dir: &Path, listing: &str, collected: usize, page_token: &str) -> String {
+  format!("{}.{}.{}", collected, urlencoding::encode(page_token), signature(dir, listing, collected, page_token))
+}
+
+/// The collected count and page token of a cursor [`encode`] made for
+/// `listing` in this run.
+pub(crate) fn decode(dir: &Path, listing: &str, cursor: &str) -> Result<(usize, String), Error> {
+  let invalid = || Error::Validation("Nieprawidłowy lub nieaktualny kursor — zacznij listowanie od początku".into());
+  // The count and the signature never hold a `.`, the page token may.
+  let Some(((collected, token), sig)) = cursor.rsplit_once('.').and_then(|(rest, sig)| Some((rest.split_once('.')?, sig))) else {
+    return Err(invalid());
+  };
+  let collected: usize = collected.parse().map_err(|_| invalid())?;
+  let token = urlencoding::decode(token).map_err(|_| invalid())?.into_owned();
+  // verify_slice compares in constant time, so the time taken says nothing about the key.
+  let same = unhex(sig).is_some_and(|sig| mac(dir, listing, collected, &token).verify_slice(&sig).is_ok());
+  if !same || token.is_empty() {
+    return Err(invalid());
+  }
+  Ok((collected, token))
+}
@@ -0,0 +1,249 @@
+//! `run_diagnostics`: an end-to-end checklist for support threads. Messages
+//! go through [`redact`] so the report is safe to paste into an issue.
+
+use crate::{
+  api, callback, capture, channel, config, error::Error, http_headers, now_secs, quota, read_config_from_dir, read_tokens_from_dir, redact,
+  tls, token_endpoint, CALLBACK_PORT,
+};
+use reqwest::Method;
+use serde::Serialize;
+use std::{
+  fs,
+  io::Write,
+  net::{Ipv4Addr, TcpListener},
+  path::Path,
+  time::Duration,
+};
+
+const NET_TIMEOUT: Duration = Duration::from_secs(5);
+/// Skew past which token expiry checks get unreliable.
+const SKEW_WARN_SECS: i64 = 60;
+const SKEW_FAIL_SECS: i64 = 300;
+/// Below this many units left the quota check warns.
+const QUOTA_WARN: u32 = 1600;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+  Pass,
+  Warn,
+  Fail,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Check {
+  pub id: &'static str,
+  pub status: CheckStatus,
+  /// `OK`, an error code from `error_codes`, or a check-specific code.
+  pub code: String,
+  pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DiagnosticsReport {
+  pub generated_at: u64,
+  pub app_version: &'static str,
+  pub checks: Vec<Check>,
+}
+
+fn check(id: &'static str, status: CheckStatus, code: &str, message: impl Into<String>) -> Check {
+  Check { id, status, code: code.to_string(), message: redact::text(&message.into()) }
+}
+
+fn pass(id: &'static str, message: impl Into<String>) -> Check {
+  check(id, CheckStatus::Pass, "OK", message)
+}
+
+fn from_error(id: &'static str, status: CheckStatus, e: &Error) -> Check {
+  check(id, status, e.code(), e.to_string())
+}
+
+fn config_check(dir: &Path) -> Check {
+  let path = dir.join("oauth_config.json");
+  let raw = match fs::read_to_string(&path) {
+    Ok(raw) => raw,
+    Err(_) => return check("config", CheckStatus::Fail, "CONFIG_MISSING", "Brak oauth_config.json"),
+  };
+  match serde_json::from_str(&raw).map_err(|e| Error::Config(format!("Uszkodzony oauth_config.json: {}", e))) {
+    Ok(cfg) => match config::validate(&cfg) {
+      Ok(()) => pass("config", "oauth_config.json poprawny"),
+      Err(e) => from_error("config", CheckStatus::Fail, &e),
+    },
+    Err(e) => from_error("config", CheckStatus::Fail, &e),
+  }
+}
+
+fn dir_writable_check(dir: &Path) -> Check {
+  let probe = dir.join(".diagnostics_write_test");
+  let result = fs::create_dir_all(dir).and_then(|_| fs::write(&probe, b"ok")).and_then(|_| fs::remove_file(&probe));
+  match result {
+    Ok(()) => pass("config_dir_writable", format!("Zapis możliwy: {}", dir.display())),
+    Err(e) => check("config_dir_writable", CheckStatus::Fail, "STORAGE_ERROR", format!("{}: {}", dir.display(), e)),
+  }
+}
+
+fn port_check(dir: &Path) -> Check {
+  if let Some(flow) = callback::active_flow(dir) {
+    return pass("loopback_port", format!("Port {} nasłuchuje na trwające logowanie", flow.port));
+  }
+  let port = read_config_from_dir(dir).map_or(CALLBACK_PORT, |c| callback::configured_port(&c));
+  match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+    Ok(_) => pass("loopback_port", format!("Port {} wolny", port)),
+    Err(e) => check("loopback_port", CheckStatus::Warn, "PORT_IN_USE", format!("Port {} zajęty: {}", port, e)),
+  }
+}
+
+async fn dns_check() -> Check {
+  let lookup = tokio::net::lookup_host("accounts.google.com:443");
+  match tokio::time::timeout(NET_TIMEOUT, lookup).await.map(|r| r.map(|mut addrs| addrs.next().is_some())) {
+    Ok(Ok(true)) => pass("dns", "accounts.google.com rozwiązuje się"),
+    Ok(Ok(false)) => check("dns", CheckStatus::Fail, "DNS_FAILED", "accounts.google.com: brak adresów"),
+    Ok(Err(e)) => check("dns", CheckStatus::Fail, "DNS_FAILED", format!("accounts.google.com: {}", e)),
+    Err(_) => check("dns", CheckStatus::Fail, "TIMED_OUT", "Przekroczono czas rozwiązywania accounts.google.com"),
+  }
+}
+
+/// Reachability of the token endpoint, plus clock skew from its Date header.
+async fn token_endpoint_checks(dir: &Path) -> (Check, Check) {
+  let url = token_endpoint();
+  let unreachable = |e: &Error| {
+    let skew = check("clock_skew", CheckStatus::Warn, "SKIPPED", "Brak odpowiedzi serwera do porównania zegara");
+    (from_error("token_endpoint", CheckStatus::Fail, e), skew)
+  };
+  let req = match tls::request(dir, Method::HEAD, &url, &http_headers::trace_id()) {
+    Ok(req) => req.timeout(NET_TIMEOUT),
+    Err(e) => return unreachable(&e),
+  };
+  let resp = match req.send().await {
+    Ok(resp) => resp,
+    Err(e) => return unreachable(&tls::transport_error(dir, &url, e, "diagnostics token endpoint", NET_TIMEOUT).await),
+  };
+  let reachable = pass("token_endpoint", format!("Serwer tokenów odpowiada ({})", resp.status()));
+  let server_time = resp
+    .headers()
+    .get(reqwest::header::DATE)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok());
+  let skew = match server_time {
+    None => check("clock_skew", CheckStatus::Warn, "SKIPPED", "Odpowiedź bez nagłówka Date"),
+    Some(t) => {
+      let skew = now_secs() as i64 - t.timestamp();
+      let message = format!("Różnica zegara względem Google: {} s", skew);
+      match skew.abs() {
+        s if s >= SKEW_FAIL_SECS => check("clock_skew", CheckStatus::Fail, "CLOCK_SKEW", message),
+        s if s >= SKEW_WARN_SECS => check("clock_skew", CheckStatus::Warn, "CLOCK_SKEW", message),
+        _ => pass("clock_skew", message),
+      }
+    }
+  };
+  (reachable, skew)
+}
+
+/// Whether the signed-in account belongs to the configured `hosted_domain`,
+/// from tokeninfo's `hd` or email. `info` is `None` without a valid token.
+fn hosted_domain_check(configured: Option<&str>, info: Option<&serde_json::Value>) -> Check {
+  let Some(expected) = configured else {
+    return pass("hosted_domain", "hd nie jest ustawione");
+  };
+  let Some(info) = info else {
+    return check("hosted_domain", CheckStatus::Warn, "SKIPPED", "Brak ważnego tokenu do sprawdzenia domeny konta");
+  };
+  let actual = info["hd"].as_str().or_else(|| info["email"].as_str().and_then(|e| e.rsplit_once('@')).map(|(_, d)| d));
+  match actual {
+    Some(domain) if domain.eq_ignore_ascii_case(expected) => pass("hosted_domain", format!("Konto należy do domeny {}", domain)),
+    Some(domain) => check(
+      "hosted_domain",
+      CheckStatus::Fail,
+      "HD_MISMATCH",
+      format!("Zalogowane konto jest z domeny {}, a hosted_domain to {} — zaloguj się kontem z tej domeny albo popraw hosted_domain", domain, expected),
+    ),
+    None => check("hosted_domain", CheckStatus::Warn, "SKIPPED", "tokeninfo nie podaje konta (brak zakresu email) — domeny nie sprawdzono"),
+  }
+}
+
+/// Token presence, validity per tokeninfo, the account's domain against
+/// `hosted_domain`, and a cheap channels.list.
+async fn token_checks(dir: &Path) -> Vec<Check> {
+  let hd = read_config_from_dir(dir).and_then(|c| c.hosted_domain);
+  let Some(t) = read_tokens_from_dir(dir) else {
+    let missing = || check("tokens", CheckStatus::Fail, "REAUTH_REQUIRED", "Brak tokens.json — zaloguj się");
+    return vec![
+      missing(),
+      check("token_valid", CheckStatus::Warn, "SKIPPED", "Brak tokenów"),
+      hosted_domain_check(hd.as_deref(), None),
+      check("channels_list", CheckStatus::Warn, "SKIPPED", "Brak tokenów"),
+    ];
+  };
+  let present = if t.refresh_token.is_empty() {
+    check("tokens", CheckStatus::Warn, "REAUTH_REQUIRED", "Brak refresh_token — sesja wygaśnie po godzinie")
+  } else {
+    pass("tokens", "tokens.json zawiera refresh_token")
+  };
+  // Not kept on disk with persist_access_token: false; tokeninfo needs one.
+  let t = if t.access_token.is_empty() { api::fresh_tokens(dir).await.unwrap_or(t) } else { t };
+  let url = channel::tokeninfo_endpoint();
+  let info = match tls::request(dir, Method::GET, &url, &http_headers::trace_id()) {
+    Ok(req) => match req.query(&[("access_token", &t.access_token)]).timeout(NET_TIMEOUT).send().await {
+      Ok(resp) => Ok(resp),
+      Err(e) => Err(tls::transport_error(dir, &url, e, "tokeninfo", NET_TIMEOUT).await),
+    },
+    Err(e) => Err(e),
+  };
+  let (valid, info) = match info {
+    Ok(resp) if resp.status().is_success() => {
+      let json: serde_json::Value = resp.json().await.unwrap_or_default();
+      let left = json["expires_in"].as_str().and_then(|s| s.parse::<u64>().ok()).or(json["expires_in"].as_u64());
+      (pass("token_valid", format!("Access token ważny jeszcze {} s", left.unwrap_or_default())), Some(json))
+    }
+    // Expired access tokens are routine; the refresh token renews them.
+    Ok(resp) => (check("token_valid", CheckStatus::Warn, "TOKEN_EXPIRED", format!("tokeninfo: {}", resp.status())), None),
+    Err(e) => (from_error("token_valid", CheckStatus::Fail, &e), None),
+  };
+  let domain = hosted_domain_check(hd.as_deref(), info.as_ref());
+  let list = match channel::list_mine(dir, "id").await {
+    Ok(_) => pass("channels_list", "channels.list działa"),
+    Err(e @ Error::NoChannelForAccount { .. }) => from_error("channels_list", CheckStatus::Warn, &e),
+    Err(e) => from_error("channels_list", CheckStatus::Fail, &e),
+  };
+  vec![present, valid, domain, list]
+}
+
+fn quota_check(dir: &Path) -> Check {
+  let s = quota::status(dir);
+  let message = format!("Pozostało {} z {} jednostek quota ({})", s.remaining, s.limit, s.day);
+  if s.remaining < QUOTA_WARN {
+    check("quota", CheckStatus::Warn, "QUOTA_LOW", message)
+  } else {
+    pass("quota", message)
+  }
+}
+
+pub async fn run(dir: &Path) -> DiagnosticsReport {
+  let mut checks = vec![config_check(dir), dir_writable_check(dir), port_check(dir)];
+  let (dns, (endpoint, skew), tokens) = tokio::join!(dns_check(), token_endpoint_checks(dir), token_checks(dir));
+  checks.extend([dns, endpoint]);
+  checks.extend(tokens);
+  checks.extend([skew, quota_check(dir)]);
+  DiagnosticsReport { generated_at: now_secs(), app_version: env!("CARGO_PKG_VERSION"), checks }
+}
+
+/// Writes a zip with a fresh report and the HTTP capture buffer (already
+/// redacted). Returns the report.
+pub async fn export(dir: &Path, path: &Path) -> Result<DiagnosticsReport, Error> {
+  let report = run(dir).await;
+  let storage = |e: &dyn std::fmt::Display| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e));
+  let file = fs::File::create(path).map_err(|e| storage(&e))?;
+  let mut zip = zip::ZipWriter::new(file);
+  let options = zip::write::SimpleFileOptions::default();
+  let files = [
+    ("diagnostics.json", serde_json::to_string_pretty(&report)),
+    ("http_capture.json", serde_json::to_string_pretty(&capture::entries(dir))),
+  ];
+  for (name, body) in files {
+    let body = body.map_err(|e| storage(&e))?;
+    zip.start_file(name, options).map_err(|e| storage(&e))?;
+    zip.write_all(body.as_bytes()).map_err(|e| storage(&e))?;
+  }
+  zip.finish().map_err(|e| storage(&e))?;
+  Ok(report)
+}
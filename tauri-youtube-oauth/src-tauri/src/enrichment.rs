@@ -0,0 +1,157 @@
+//! What the queue view shows next to each entry — the title the upload would
+//! get, lint warnings, the video the same content was already uploaded as,
+//! whether the thumbnail file is there — computed off the command thread.
+//! [`attach`] fills in what is ready and hands the rest to background tasks,
+//! at most [`WORKERS`] at a time, each ending in `queue-entry-enriched`.
+//! Results are kept per entry and stamped with the mtime and size of the
+//! video and its sidecar; a listing that finds either changed redoes it.
+
+use crate::{
+  library, lint, metadata, queue::QueueEntry, read_config_from_dir, runtime,
+  upload::VideoMetadata,
+  LintWarning,
+};
+use serde::Serialize;
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+  sync::Arc,
+  time::SystemTime,
+};
+use tokio::sync::Semaphore;
+
+/// Entries enriched at once; each one reads, hashes and lints a file.
+pub const WORKERS: usize = 4;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Enrichment {
+  /// From the entry's metadata, or the sidecar and `metadata_template`.
+  pub title: Option<String>,
+  /// Why the metadata couldn't be loaded; the upload would fail the same way.
+  pub metadata_error: Option<String>,
+  pub lint: Vec<LintWarning>,
+  /// The video this exact content was uploaded as before, from the library.
+  pub uploaded_as: Option<String>,
+  /// Whether the metadata's thumbnail exists; absent without one.
+  pub thumbnail_exists: Option<bool>,
+  /// The video file can't be read (moved or deleted since it was queued).
+  pub file_error: Option<String>,
+}
+
+/// Payload of `queue-entry-enriched`.
+#[derive(Serialize, Debug, Clone)]
+pub struct EntryEnriched {
+  pub id: String,
+  pub enrichment: Enrichment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+  video: Option<(SystemTime, u64)>,
+  sidecar: Option<(SystemTime, u64)>,
+}
+
+fn stat(path: &Path) -> Option<(SystemTime, u64)> {
+  let m = fs::metadata(path).ok()?;
+  Some((m.modified().ok()?, m.len()))
+}
+
+impl Stamp {
+  fn of(video: &Path) -> Stamp {
+    Stamp { video: stat(video), sidecar: stat(&metadata::sidecar_path(video)) }
+  }
+}
+
+/// Results by entry id, and the entries being worked on with the stamp they
+/// were started for.
+#[derive(Default)]
+pub(crate) struct EnrichmentCache {
+  done: HashMap<String, (Stamp, Enrichment)>,
+  running: HashMap<String, Stamp>,
+  workers: Option<Arc<Semaphore>>,
+}
+
+impl EnrichmentCache {
+  fn workers(&mut self) -> Arc<Semaphore> {
+    self.workers.get_or_insert_with(|| Arc::new(Semaphore::new(WORKERS))).clone()
+  }
+}
+
+/// Sets `enrichment` on the entries whose result is current and schedules
+/// the others; entries gone from the queue are forgotten. Without a tokio
+/// runtime nothing is scheduled.
+pub(crate) fn attach(dir: &Path, entries: &mut [QueueEntry]) {
+  let handle = tokio::runtime::Handle::try_current().ok();
+  let rt = runtime::for_dir(dir);
+  let mut cache = rt.enrichment();
+  cache.done.retain(|id, _| entries.iter().any(|e| e.id == *id));
+  cache.running.retain(|id, _| entries.iter().any(|e| e.id == *id));
+  let mut todo = Vec::new();
+  for e in entries.iter_mut() {
+    let stamp = Stamp::of(Path::new(&e.file_path));
+    match cache.done.get(&e.id) {
+      Some((at, enrichment)) if *at == stamp => e.enrichment = Some(enrichment.clone()),
+      _ if handle.is_some() && cache.running.get(&e.id) != Some(&stamp) => {
+        cache.running.insert(e.id.clone(), stamp);
+        todo.push((e.clone(), stamp));
+      }
+      _ => {}
+    }
+  }
+  let (Some(handle), false) = (handle, todo.is_empty()) else {
+    return;
+  };
+  let workers = cache.workers();
+  drop(cache);
+  for (entry, stamp) in todo {
+    let (dir, workers) = (dir.to_path_buf(), workers.clone());
+    handle.spawn(async move {
+      let Ok(_permit) = workers.acquire_owned().await else {
+        return;
+      };
+      let enrichment = compute(&dir, &entry).await;
+      let rt = runtime::for_dir(&dir);
+      {
+        let mut cache = rt.enrichment();
+        // Removed, or started again for a newer version of the files.
+        if cache.running.get(&entry.id) != Some(&stamp) {
+          return;
+        }
+        cache.running.remove(&entry.id);
+        cache.done.insert(entry.id.clone(), (stamp, enrichment.clone()));
+      }
+      rt.emit("queue-entry-enriched", EntryEnriched { id: entry.id, enrichment });
+    });
+  }
+}
+
+async fn compute(dir: &Path, entry: &QueueEntry) -> Enrichment {
+  let video = PathBuf::from(&entry.file_path);
+  let meta: Result<VideoMetadata, String> = match &entry.metadata {
+    Some(m) => Ok(m.clone()),
+    None => metadata::load_video_metadata(dir, &video).await.map_err(|e| e.to_string()),
+  };
+  let cfg = read_config_from_dir(dir).and_then(|c| c.metadata_lint).unwrap_or_default();
+  let (hash_dir, hash_video) = (dir.to_path_buf(), video.clone());
+  // Hashing reads the whole file; kept off the async workers.
+  let hashed = tokio::task::spawn_blocking(move || {
+    // A rejected or removed upload doesn't make the file a duplicate.
+    library::lookup(&hash_dir, &hash_video).map(|m| m.filter(|m| m.entry.rejection.is_none()).map(|m| m.entry.video_id))
+  })
+  .await;
+  let (uploaded_as, file_error) = match hashed {
+    Ok(Ok(video_id)) => (video_id, None),
+    Ok(Err(e)) => (None, Some(e.to_string())),
+    Err(e) => (None, Some(e.to_string())),
+  };
+  let meta = meta.as_ref();
+  Enrichment {
+    title: meta.ok().map(|m| m.title.clone()),
+    metadata_error: meta.err().cloned(),
+    lint: meta.map(|m| lint::lint_metadata(m, &cfg)).unwrap_or_default(),
+    uploaded_as,
+    thumbnail_exists: meta.ok().and_then(|m| m.thumbnail.as_ref()).map(|t| t.is_file()),
+    file_error,
+  }
+}
@@ -0,0 +1,128 @@
+//! Whether a deployed `.env` still matches the app: rotating the client
+//! secret or logging in again leaves the old values in files written before,
+//! and the uploader reading them fails much later. Values are compared by
+//! hash and only key names are reported, so a check can run without the
+//! passphrase; [`sync`] writes the current values with everything else in
+//! the file left as it was.
+
+use crate::{
+  env_file::{self, merge_dotenv},
+  error::Error,
+  read_config_from_dir, read_tokens_from_dir, runtime, storage, AppConfig, Tokens,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+
+/// Keys compared by value; missing ones are added by [`sync`].
+const REQUIRED: &[&str] = &["YOUTUBE_CLIENT_ID", "YOUTUBE_CLIENT_SECRET", "YOUTUBE_CHANNEL_ID"];
+/// Rotates hourly, so only whether the file has one is checked.
+const ACCESS_TOKEN: &str = "YOUTUBE_ACCESS_TOKEN";
+const REFRESH_TOKEN: &str = "YOUTUBE_REFRESH_TOKEN";
+
+/// Key names only; no value leaves the app this way.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct EnvDrift {
+  pub path: String,
+  pub matching: Vec<String>,
+  /// In the file with another value than the app has.
+  pub stale: Vec<String>,
+  /// Known to the app but not in the file.
+  pub missing: Vec<String>,
+  /// Tokens in the file while the app has none.
+  pub extra: Vec<String>,
+  pub in_sync: bool,
+}
+
+/// What [`sync`] changed in the file.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct EnvSyncResult {
+  pub path: String,
+  /// Written with the app's value.
+  pub updated: Vec<String>,
+  pub removed: Vec<String>,
+}
+
+fn fingerprint(value: &str) -> [u8; 32] {
+  Sha256::digest(value.as_bytes()).into()
+}
+
+/// The app's value for each key it manages; `None` where it has none.
+fn app_values(cfg: &AppConfig, t: Option<&Tokens>) -> Vec<(&'static str, Option<String>)> {
+  let nonempty = |v: &str| (!v.is_empty()).then(|| v.to_string());
+  vec![
+    ("YOUTUBE_CLIENT_ID", nonempty(&cfg.client_id)),
+    ("YOUTUBE_CLIENT_SECRET", nonempty(&cfg.client_secret)),
+    ("YOUTUBE_CHANNEL_ID", cfg.channel_id.clone()),
+    (ACCESS_TOKEN, t.and_then(|t| nonempty(&t.access_token))),
+    (REFRESH_TOKEN, t.and_then(|t| nonempty(&t.refresh_token))),
+  ]
+}
+
+fn read_env(path: &Path) -> Result<Vec<(String, String)>, Error> {
+  let text = fs::read_to_string(path).map_err(|e| Error::Storage(format!("Nie można odczytać {}: {}", path.display(), e)))?;
+  Ok(env_file::parse_dotenv(&text))
+}
+
+fn diff(path: &Path, file: &[(String, String)], app: &[(&'static str, Option<String>)]) -> EnvDrift {
+  let mut drift = EnvDrift { path: path.to_string_lossy().into_owned(), ..Default::default() };
+  for (key, want) in app {
+    // The last assignment wins, as when the file is loaded.
+    let have = file.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v);
+    let bucket = match (have, want) {
+      (None, Some(_)) if REQUIRED.contains(key) => &mut drift.missing,
+      (None, _) => continue,
+      (Some(_), None) if REQUIRED.contains(key) => continue,
+      (Some(_), None) => &mut drift.extra,
+      (Some(_), Some(_)) if *key == ACCESS_TOKEN => &mut drift.matching,
+      (Some(have), Some(want)) if fingerprint(have) == fingerprint(want) => &mut drift.matching,
+      (Some(_), Some(_)) => &mut drift.stale,
+    };
+    bucket.push(key.to_string());
+  }
+  drift.in_sync = drift.stale.is_empty() && drift.missing.is_empty() && drift.extra.is_empty();
+  drift
+}
+
+/// Compares the `.env` at `path` with the current config and tokens.
+pub fn check(dir: &Path, path: &Path) -> Result<EnvDrift, Error> {
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  let t = read_tokens_from_dir(dir);
+  Ok(diff(path, &read_env(path)?, &app_values(&cfg, t.as_ref())))
+}
+
+/// Brings the `.env` at `path` up to date: stale and missing keys get the
+/// app's values, tokens the app no longer has are removed. A fresh access
+/// token is written only where the file already keeps tokens. The file
+/// keeps its permissions.
+pub fn sync(dir: &Path, path: &Path) -> Result<EnvSyncResult, Error> {
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  let t = read_tokens_from_dir(dir);
+  let app = app_values(&cfg, t.as_ref());
+  let text = fs::read_to_string(path).map_err(|e| Error::Storage(format!("Nie można odczytać {}: {}", path.display(), e)))?;
+  let drift = diff(path, &env_file::parse_dotenv(&text), &app);
+  let value = |key: &str| app.iter().find(|(k, _)| *k == key).and_then(|(_, v)| v.clone());
+  let mut updated: Vec<String> = drift.stale.iter().chain(&drift.missing).cloned().collect();
+  if drift.stale.iter().any(|k| k == REFRESH_TOKEN) && drift.matching.iter().any(|k| k == ACCESS_TOKEN) {
+    updated.push(ACCESS_TOKEN.to_string());
+  }
+  let set: Vec<(&str, String)> = updated.iter().filter_map(|k| Some((k.as_str(), value(k)?))).collect();
+  let remove: Vec<&str> = drift.extra.iter().map(String::as_str).collect();
+  if !set.is_empty() || !remove.is_empty() {
+    storage::write_secret(path, merge_dotenv(&text, &set, &remove))
+      .map_err(|e| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e)))?;
+  }
+  Ok(EnvSyncResult { path: drift.path, updated, removed: drift.extra })
+}
+
+/// Emits `env-drift` when the config's `watched_env_file` is out of date.
+pub(crate) fn report(dir: &Path) {
+  let Some(path) = read_config_from_dir(dir).and_then(|c| c.watched_env_file) else {
+    return;
+  };
+  if let Ok(drift) = check(dir, Path::new(&path)) {
+    if !drift.in_sync {
+      runtime::for_dir(dir).emit("env-drift", drift);
+    }
+  }
+}
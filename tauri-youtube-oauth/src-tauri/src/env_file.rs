@@ -0,0 +1,371 @@
+//! Rendering for `generate_env`. Values are quoted per dotenv conventions in
+//! every format: client secrets can contain `#`, `$`, quotes or spaces.
+
+use crate::{
+  channel, error::Error, now_secs, passphrase, profiles, read_config_from_dir, read_tokens_from_dir, refresh, storage, AppConfig,
+  Tokens,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// What `generate_env` knows about the account's channel.
+pub enum EnvChannel {
+  Known(String),
+  /// The account has no channel; the line is replaced by a hint.
+  NoChannel,
+  Unknown,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum EnvFormat {
+  /// `.env` with the banner and hints.
+  #[default]
+  DotEnv,
+  /// `.env` with variables only.
+  DotEnvNoComments,
+  /// A flat JSON object of the variables.
+  Json,
+  /// `export KEY='value'` lines for `eval` / `source`.
+  ShellExports,
+}
+
+enum Line {
+  Comment(String),
+  Blank,
+  Var(&'static str, String),
+  /// A variable `DotEnv` leaves commented out; plain formats include it.
+  Optional(&'static str, String),
+}
+
+/// Token section of the output.
+pub enum EnvTokens<'a> {
+  Omit,
+  Include(&'a Tokens),
+  /// Tokens were wanted but aren't usable: the lines stay commented out
+  /// under a warning.
+  Unusable(String),
+  /// The access token had expired and couldn't be refreshed (`reason`): only
+  /// the refresh token goes out.
+  Stale { tokens: &'a Tokens, reason: String },
+}
+
+const BANNER: &str = "Generated by Tauri YouTube OAuth";
+/// An access token with less left than this is refreshed before it goes
+/// into the env, so the script reading it doesn't fail halfway.
+const MIN_LIFETIME_SECS: u64 = 30 * 60;
+
+fn lines(header: &[String], cfg: &AppConfig, t: EnvTokens, channel: &EnvChannel) -> Vec<Line> {
+  let mut lines: Vec<Line> = header.iter().cloned().map(Line::Comment).collect();
+  lines.push(Line::Var("YOUTUBE_CLIENT_ID", cfg.client_id.clone()));
+  lines.push(Line::Var("YOUTUBE_CLIENT_SECRET", cfg.client_secret.clone()));
+  match channel {
+    EnvChannel::Known(id) => lines.push(Line::Var("YOUTUBE_CHANNEL_ID", id.clone())),
+    EnvChannel::NoChannel => lines.push(Line::Comment(
+      "YOUTUBE_CHANNEL_ID omitted: this Google account has no YouTube channel yet (create one on youtube.com)".into(),
+    )),
+    EnvChannel::Unknown => {}
+  }
+  lines.push(Line::Var("UPLOAD_PRIVACY", "unlisted".into()));
+  lines.push(Line::Var("AUTO_UPLOAD", "false".into()));
+  match t {
+    EnvTokens::Omit => {}
+    EnvTokens::Include(t) => {
+      lines.push(Line::Blank);
+      lines.push(Line::Comment("Optional (not recommended to store in .env)".into()));
+      lines.push(Line::Optional("YOUTUBE_ACCESS_TOKEN", t.access_token.clone()));
+      if t.expires_in > 0 {
+        let expires_at = chrono::DateTime::from_timestamp((t.created_at + t.expires_in) as i64, 0).unwrap_or_default();
+        lines.push(Line::Optional("YOUTUBE_TOKEN_EXPIRES_AT", expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)));
+      }
+      lines.push(Line::Optional("YOUTUBE_REFRESH_TOKEN", t.refresh_token.clone()));
+    }
+    EnvTokens::Stale { tokens, reason } => {
+      lines.push(Line::Blank);
+      lines.push(Line::Comment("Optional (not recommended to store in .env)".into()));
+      lines.push(Line::Comment(format!("YOUTUBE_ACCESS_TOKEN omitted: it had expired and refreshing failed ({})", reason)));
+      lines.push(Line::Optional("YOUTUBE_REFRESH_TOKEN", tokens.refresh_token.clone()));
+    }
+    EnvTokens::Unusable(reason) => {
+      lines.push(Line::Blank);
+      lines.push(Line::Comment(format!("WARNING: no valid tokens ({}) — log in again", reason)));
+      lines.push(Line::Comment("YOUTUBE_ACCESS_TOKEN=".into()));
+      lines.push(Line::Comment("YOUTUBE_REFRESH_TOKEN=".into()));
+    }
+  }
+  lines
+}
+
+fn is_plain(v: &str) -> bool {
+  v.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:@%+,".contains(c))
+}
+
+/// Characters a dotenv parser may take for the end of the line or drop:
+/// control characters other than tab, and the Unicode line separators.
+fn breaks_line(c: char) -> bool {
+  (c.is_control() && c != '\t') || matches!(c, '\u{2028}' | '\u{2029}')
+}
+
+/// Leaves plain values bare, single-quotes (literal) the rest, and falls back
+/// to double quotes with backslash escapes when the value has a `'` or a
+/// character from [`breaks_line`] (as `\n`, `\r` or `\uXXXX`). Other
+/// non-ASCII text (`Zażółć`, emoji) is written as UTF-8, quoted.
+fn dotenv_quote(v: &str) -> String {
+  if is_plain(v) {
+    return v.to_string();
+  }
+  if !v.contains('\'') && !v.chars().any(breaks_line) {
+    return format!("'{}'", v);
+  }
+  let mut out = String::from('"');
+  for c in v.chars() {
+    match c {
+      '\\' => out.push_str("\\\\"),
+      '"' => out.push_str("\\\""),
+      '$' => out.push_str("\\$"),
+      '`' => out.push_str("\\`"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      c if breaks_line(c) => out.push_str(&format!("\\u{:04x}", c as u32)),
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+  out
+}
+
+/// POSIX single quoting; a `'` becomes `'\''`.
+fn shell_quote(v: &str) -> String {
+  if is_plain(v) && !v.is_empty() {
+    return v.to_string();
+  }
+  format!("'{}'", v.replace('\'', "'\\''"))
+}
+
+/// Without `t` the token lines are left out entirely.
+pub fn render(cfg: &AppConfig, t: Option<&Tokens>, channel: &EnvChannel, format: EnvFormat) -> String {
+  let t = t.map(EnvTokens::Include).unwrap_or(EnvTokens::Omit);
+  render_with(&[BANNER.to_string()], cfg, t, channel, format)
+}
+
+/// [`render`] with custom header comments and an explicit token section.
+pub fn render_with(header: &[String], cfg: &AppConfig, t: EnvTokens, channel: &EnvChannel, format: EnvFormat) -> String {
+  let lines = lines(header, cfg, t, channel);
+  let vars = lines.iter().filter_map(|l| match l {
+    Line::Var(k, v) | Line::Optional(k, v) => Some((*k, v.as_str())),
+    _ => None,
+  });
+  match format {
+    EnvFormat::DotEnv => lines
+      .iter()
+      .map(|l| match l {
+        Line::Comment(c) => format!("# {}\n", c),
+        Line::Blank => "\n".to_string(),
+        Line::Var(k, v) => format!("{}={}\n", k, dotenv_quote(v)),
+        Line::Optional(k, v) => format!("# {}={}\n", k, dotenv_quote(v)),
+      })
+      .collect(),
+    EnvFormat::DotEnvNoComments => vars.map(|(k, v)| format!("{}={}\n", k, dotenv_quote(v))).collect(),
+    EnvFormat::ShellExports => vars.map(|(k, v)| format!("export {}={}\n", k, shell_quote(v))).collect(),
+    EnvFormat::Json => {
+      let map: serde_json::Map<String, serde_json::Value> = vars.map(|(k, v)| (k.to_string(), v.into())).collect();
+      serde_json::to_string_pretty(&map).unwrap_or_default()
+    }
+  }
+}
+
+/// Parses `.env` text as [`render`] writes it: comments, blank lines, an
+/// optional `export ` prefix, bare, single-quoted and double-quoted values.
+/// A byte order mark some Windows editors put first is skipped.
+pub fn parse_dotenv(text: &str) -> Vec<(String, String)> {
+  let mut vars = Vec::new();
+  for line in text.trim_start_matches('\u{feff}').lines() {
+    let line = line.trim_start();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let Some((key, raw)) = line.split_once('=') else {
+      continue;
+    };
+    let value = if let Some(inner) = raw.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')) {
+      inner.to_string()
+    } else if let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+      let mut out = String::new();
+      let mut chars = inner.chars();
+      while let Some(c) = chars.next() {
+        if c != '\\' {
+          out.push(c);
+          continue;
+        }
+        match chars.next() {
+          Some('n') => out.push('\n'),
+          Some('r') => out.push('\r'),
+          Some('u') => {
+            let hex: String = chars.by_ref().take(4).collect();
+            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+              Some(c) => out.push(c),
+              None => out.push_str(&format!("\\u{}", hex)),
+            }
+          }
+          Some(c) => out.push(c),
+          None => out.push('\\'),
+        }
+      }
+      out
+    } else {
+      raw.trim_end().to_string()
+    };
+    vars.push((key.trim().to_string(), value));
+  }
+  vars
+}
+
+/// `text` with the values of `set` replaced where the key already is (an
+/// `export ` prefix kept) and appended where it isn't, and the lines of
+/// `remove` dropped. Comments, order and every other line stay as they were.
+pub fn merge_dotenv(text: &str, set: &[(&str, String)], remove: &[&str]) -> String {
+  let mut pending: Vec<&(&str, String)> = set.iter().collect();
+  let mut out = String::new();
+  for line in text.trim_start_matches('\u{feff}').lines() {
+    let body = line.trim_start();
+    let (export, assignment) = match body.strip_prefix("export ") {
+      Some(rest) => ("export ", rest),
+      None => ("", body),
+    };
+    let key = (!body.starts_with('#')).then(|| assignment.split_once('=').map(|(k, _)| k.trim())).flatten();
+    match key {
+      Some(key) if remove.contains(&key) => continue,
+      Some(key) if pending.iter().any(|(k, _)| *k == key) => {
+        let pos = pending.iter().position(|(k, _)| *k == key).unwrap_or_default();
+        let (k, v) = pending.remove(pos);
+        out.push_str(&format!("{}{}={}\n", export, k, dotenv_quote(v)));
+      }
+      _ => {
+        out.push_str(line);
+        out.push('\n');
+      }
+    }
+  }
+  for (k, v) in pending {
+    out.push_str(&format!("{}={}\n", k, dotenv_quote(v)));
+  }
+  out
+}
+
+/// The channel line: from the config, else looked up (and remembered) when
+/// there are tokens to ask with.
+pub(crate) async fn channel_for(dir: &Path, cfg: &AppConfig, has_tokens: bool) -> EnvChannel {
+  match (&cfg.channel_id, has_tokens) {
+    (Some(id), _) => EnvChannel::Known(id.clone()),
+    (None, true) => match channel::selected_channel_id(dir).await {
+      Ok(id) => EnvChannel::Known(id),
+      Err(Error::NoChannelForAccount { .. }) => EnvChannel::NoChannel,
+      Err(_) => EnvChannel::Unknown,
+    },
+    (None, false) => EnvChannel::Unknown,
+  }
+}
+
+/// `t` refreshed first unless its access token has [`MIN_LIFETIME_SECS`]
+/// left (a token only held in memory may be missing); with the refresh
+/// error when that failed.
+async fn fresh_for_env(dir: &Path, t: Tokens) -> (Tokens, Option<String>) {
+  let remaining = (t.created_at + t.expires_in).saturating_sub(now_secs());
+  if t.refresh_token.is_empty() || (!t.access_token.is_empty() && remaining >= MIN_LIFETIME_SECS) {
+    return (t, None);
+  }
+  match refresh::refresh(dir).await {
+    Ok(fresh) => (fresh, None),
+    Err(e) => (t, Some(e.to_string())),
+  }
+}
+
+/// Env text for the config in `dir`; `include_tokens` needs the passphrase
+/// when one is set and refreshes an access token close to expiry first.
+pub async fn generate(dir: &Path, include_tokens: bool, given_passphrase: Option<&str>, format: EnvFormat) -> Result<String, Error> {
+  if include_tokens {
+    passphrase::require(dir, given_passphrase)?;
+  }
+  let cfg = read_config_from_dir(dir).unwrap_or_default();
+  let t = read_tokens_from_dir(dir);
+  let has_tokens = t.is_some();
+  let (t, stale) = match t {
+    Some(t) if include_tokens => fresh_for_env(dir, t).await,
+    t => (t.unwrap_or_default(), None),
+  };
+  let channel = channel_for(dir, &cfg, has_tokens).await;
+  let tokens = match (include_tokens, stale) {
+    (false, _) => EnvTokens::Omit,
+    (true, None) => EnvTokens::Include(&t),
+    (true, Some(reason)) => EnvTokens::Stale { tokens: &t, reason },
+  };
+  Ok(render_with(&[BANNER.to_string()], &cfg, tokens, &channel, format))
+}
+
+/// Why stored tokens can't go into an env file, if they can't.
+fn token_problem(cfg: &AppConfig, t: Option<&Tokens>) -> Option<&'static str> {
+  match t {
+    None => Some("no tokens.json"),
+    Some(t) if t.refresh_token.is_empty() => Some("no refresh_token"),
+    Some(t) if t.client_id.as_deref().is_some_and(|id| id != cfg.client_id) => Some("issued for another client_id"),
+    Some(_) => None,
+  }
+}
+
+fn profile_header(profile: &str) -> Vec<String> {
+  let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+  vec![BANNER.to_string(), format!("Profile: {}", profile), format!("Generated at: {}", now)]
+}
+
+/// Env text for one profile; returns it with a warning when tokens were
+/// wanted but are missing or unusable.
+pub async fn generate_for_profile(
+  root: &Path,
+  profile: &str,
+  include_tokens: bool,
+  format: EnvFormat,
+) -> Result<(String, Option<String>), Error> {
+  let dir = profiles::dir_for(root, profile)?;
+  let cfg = read_config_from_dir(&dir).ok_or_else(|| Error::Config(format!("Profil {}: brak konfiguracji klienta", profile)))?;
+  let mut t = read_tokens_from_dir(&dir);
+  let problem = token_problem(&cfg, t.as_ref());
+  // Nothing to emit for a token that is only kept in memory and not there yet.
+  if let Some(stored) = t.take_if(|t| include_tokens && problem.is_none() && t.access_token.is_empty()) {
+    t = Some(fresh_for_env(&dir, stored).await.0);
+  }
+  let channel = channel_for(&dir, &cfg, problem.is_none()).await;
+  let (tokens, warning) = match (include_tokens, problem, &t) {
+    (false, _, _) => (EnvTokens::Omit, None),
+    (true, None, Some(t)) => (EnvTokens::Include(t), None),
+    (true, problem, _) => {
+      let reason = problem.unwrap_or("no tokens.json");
+      let warning = format!("Profil {}: brak ważnych tokenów ({}) — zaloguj się ponownie", profile, reason);
+      (EnvTokens::Unusable(reason.to_string()), Some(warning))
+    }
+  };
+  Ok((render_with(&profile_header(profile), &cfg, tokens, &channel, format), warning))
+}
+
+/// One file written by [`generate_all`].
+#[derive(Serialize, Debug, Clone)]
+pub struct EnvFileResult {
+  pub profile: String,
+  pub path: String,
+  pub warning: Option<String>,
+}
+
+/// Writes `<profile>.env` into `out` for every configured profile, readable
+/// by the owner only. A profile without usable tokens still gets its file,
+/// with a warning.
+pub async fn generate_all(root: &Path, out: &Path) -> Result<Vec<EnvFileResult>, Error> {
+  std::fs::create_dir_all(out).map_err(|e| Error::Storage(format!("Nie można utworzyć {}: {}", out.display(), e)))?;
+  let mut results = Vec::new();
+  for profile in profiles::list(root) {
+    let (text, warning) = generate_for_profile(root, &profile, true, EnvFormat::DotEnv).await?;
+    let path = out.join(format!("{}.env", profile));
+    storage::write_secret(&path, text).map_err(|e| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e)))?;
+    results.push(EnvFileResult { profile, path: path.to_string_lossy().into_owned(), warning });
+  }
+  Ok(results)
+}
@@ -0,0 +1,327 @@
+use crate::{api::DryRunPlan, forbidden, guidance::{ConsentGuidance, WorkspaceReason}, preflight::PreflightReason, remote_state::Rejection, sidecar::SidecarDiagnostic, token_response::TokenEndpointError};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Error type shared by the YouTube API layer. Commands return it as a
+/// [`CommandError`]: a stable `code` from [`Error::code`], `details` and
+/// `retryable`, and the `Display` text (fit for the UI) as `message`.
+#[derive(Debug, Clone)]
+pub enum Error {
+  /// Missing or invalid client configuration.
+  Config(String),
+  /// Missing tokens or a failed refresh.
+  Auth(String),
+  /// The token endpoint refused a refresh with an OAuth error other than
+  /// `invalid_grant`/`invalid_client`, or answered without a usable grant.
+  TokenEndpoint(Box<TokenEndpointError>),
+  /// The token endpoint answered `invalid_grant`: the refresh token expired
+  /// or was revoked. `hint` names the likely cause when one is known.
+  GrantRevoked { hint: Option<String> },
+  /// The stored tokens were granted without a scope the call needs.
+  /// `reconsent` is set when the config asks for it but the tokens predate
+  /// that (`reconsent.json`), so `reconsent` rather than a new login fixes it.
+  InsufficientScope { missing: Vec<String>, reconsent: bool },
+  /// Transport-level failure (DNS, connection, TLS, body read).
+  Network(String),
+  /// No connectivity (detected or forced) and nothing cached to answer with.
+  Offline,
+  /// A request or a composite operation ran past its timeout.
+  TimedOut { operation: String, after_secs: u64 },
+  /// The API refused the request for policy reasons (channel not eligible,
+  /// not the owner, feature disabled).
+  Forbidden { reason: String, message: String },
+  /// Any other non-success response from the API.
+  Api { status: u16, reason: String, message: String },
+  /// Today's local quota estimate can't cover the call.
+  QuotaExceeded { needed: u32, remaining: u32 },
+  /// Input rejected locally before spending quota.
+  Validation(String),
+  /// Reading or writing files in the config dir failed.
+  Storage(String),
+  /// Dry-run mode stopped a mutating call; carries what would have been sent.
+  DryRun(DryRunPlan),
+  /// The Google account has no YouTube channel yet.
+  NoChannelForAccount { email: Option<String> },
+  /// Token refresh is paused after repeated network failures until `retry_at`
+  /// (Unix seconds).
+  RefreshBackoff { retry_at: u64 },
+  /// Launching the file manager or a hook program for `target` failed; a
+  /// browser that doesn't open is reported by `start_oauth` instead.
+  Open { target: String, message: String },
+  /// The app passphrase was missing, wrong or tried too often. Deliberately
+  /// the same in all three cases.
+  PassphraseRejected,
+  /// A queued file failed the checks before upload (missing, unreadable,
+  /// still being written, over the limits, no room in the config dir).
+  PreflightFailed { reason: PreflightReason, message: String },
+  /// Google refused the login (consent screen or code exchange); carries
+  /// what to tell the user.
+  ConsentFailed(Box<ConsentGuidance>),
+  /// A Google Workspace policy refused the login: the admin hasn't trusted
+  /// the app, or the client is internal to another organization.
+  WorkspaceRestricted { reason: WorkspaceReason, guidance: Box<ConsentGuidance> },
+  /// Read-only mode is on; the mutating call was refused before sending.
+  ReadOnlyMode,
+  /// The sidecar of a video has errors, or warnings while
+  /// `sidecar_warnings_block` is on; `diagnostics` lists them all.
+  SidecarInvalid { path: String, diagnostics: Vec<SidecarDiagnostic> },
+  /// Another process kept the lock of the config dir for `waited_ms`.
+  StorageBusy { path: String, waited_ms: u64 },
+  /// YouTube has no API for `operation` (pinning or hearting a comment);
+  /// `message` says where to do it by hand.
+  NotSupportedByApi { operation: String, message: String },
+  /// The upload went through, but YouTube rejected the video (a duplicate,
+  /// copyright), failed to process it or removed it.
+  VideoRejected { video_id: String, rejection: Rejection },
+  /// With `pin_google_tls`, the TLS handshake with `host` failed while the
+  /// same request without pinning got through: something in between
+  /// presents its own certificate. Nothing secret was sent.
+  TlsInterceptionSuspected { host: String },
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Config(m) | Error::Auth(m) | Error::Network(m) | Error::Validation(m) | Error::Storage(m) => f.write_str(m),
+      Error::InsufficientScope { missing, reconsent: false } => write!(
+        f,
+        "Brak wymaganych uprawnień OAuth: {} — autoryzuj dodatkowy zakres (request_scopes)",
+        missing.join(" ")
+      ),
+      Error::InsufficientScope { missing, reconsent: true } => write!(
+        f,
+        "Brak wymaganych uprawnień OAuth: {} — tokeny wydano przed dodaniem tych zakresów do konfiguracji; \
+         potwierdź je ponownie (reconsent)",
+        missing.join(" ")
+      ),
+      Error::Forbidden { message, .. } | Error::PreflightFailed { message, .. } | Error::NotSupportedByApi { message, .. } => {
+        f.write_str(message)
+      }
+      Error::GrantRevoked { hint } => {
+        f.write_str("Refresh token wygasł lub został cofnięty — zaloguj się ponownie")?;
+        match hint {
+          Some(h) => write!(f, ". {}", h),
+          None => Ok(()),
+        }
+      }
+      Error::TokenEndpoint(e) => write!(f, "{}", e),
+      Error::Offline => f.write_str("Brak połączenia z internetem — ta operacja wymaga sieci"),
+      Error::TimedOut { operation, after_secs } => {
+        write!(f, "Przekroczono limit czasu ({} s) dla operacji {}", after_secs, operation)
+      }
+      Error::QuotaExceeded { needed, remaining } => write!(
+        f,
+        "Dzienny limit quota YouTube API wyczerpany: potrzeba {} jednostek, zostało {} (reset o północy czasu pacyficznego)",
+        needed, remaining
+      ),
+      Error::Api { status, reason, message } => write!(f, "YouTube API error ({} {}): {}", status, reason, message),
+      Error::DryRun(plan) => write!(
+        f,
+        "Tryb próbny: pominięto {} {} (szacowany koszt {} jednostek quota)",
+        plan.method, plan.url, plan.estimated_quota
+      ),
+      Error::NoChannelForAccount { email } => write!(
+        f,
+        "Konto {}nie ma kanału YouTube — utwórz kanał na youtube.com i spróbuj ponownie",
+        email.as_deref().map(|e| format!("{} ", e)).unwrap_or_default()
+      ),
+      Error::RefreshBackoff { retry_at } => write!(
+        f,
+        "Odświeżanie tokenu wstrzymane po powtarzających się błędach sieci — kolejna próba po {} (lub odśwież ręcznie)",
+        chrono::DateTime::<chrono::Utc>::from_timestamp(*retry_at as i64, 0).map(|t| t.to_rfc3339()).unwrap_or_default()
+      ),
+      Error::Open { target, message } => write!(f, "Nie można otworzyć {} ({}) — otwórz ręcznie", target, message),
+      Error::PassphraseRejected => {
+        f.write_str("Nieprawidłowe hasło aplikacji lub zbyt wiele prób — spróbuj ponownie za minutę")
+      }
+      Error::ReadOnlyMode => f.write_str("Tryb tylko do odczytu: zmiany na kanale są zablokowane — wyłącz tryb, aby kontynuować"),
+      Error::SidecarInvalid { path, diagnostics } => {
+        write!(f, "Plik metadanych {} wymaga poprawek", path)?;
+        if let Some(d) = diagnostics.first() {
+          write!(f, ": {}", d.message)?;
+        }
+        match diagnostics.len() {
+          0 | 1 => Ok(()),
+          n => write!(f, " (i {} innych)", n - 1),
+        }
+      }
+      Error::StorageBusy { path, waited_ms } => {
+        write!(f, "Katalog konfiguracji jest zajęty przez inny proces ({}, {} ms) — spróbuj ponownie", path, waited_ms)
+      }
+      Error::VideoRejected { video_id, rejection } => f.write_str(&rejection.describe(video_id)),
+      Error::TlsInterceptionSuspected { host } => write!(
+        f,
+        "Połączenie z {} przechwytuje pośrednik TLS (proxy lub skaner) — dane logowania nie zostały wysłane; \
+         wyłącz pin_google_tls, jeśli to zamierzone",
+        host
+      ),
+      Error::ConsentFailed(g) | Error::WorkspaceRestricted { guidance: g, .. } => match &g.action {
+        Some(action) => write!(f, "{}: {} {}", g.title, g.explanation, action),
+        None => write!(f, "{}: {}", g.title, g.explanation),
+      },
+    }
+  }
+}
+
+impl Error {
+  /// Prefixes the human-readable message, keeping the variant (and thus the
+  /// error's classification) intact.
+  pub fn context(self, prefix: &str) -> Error {
+    let p = |m: String| format!("{}: {}", prefix, m);
+    match self {
+      Error::Config(m) => Error::Config(p(m)),
+      Error::Auth(m) => Error::Auth(p(m)),
+      Error::Network(m) => Error::Network(p(m)),
+      Error::Validation(m) => Error::Validation(p(m)),
+      Error::Storage(m) => Error::Storage(p(m)),
+      Error::Forbidden { reason, message } => Error::Forbidden { reason, message: p(message) },
+      Error::Api { status, reason, message } => Error::Api { status, reason, message: p(message) },
+      Error::Open { target, message } => Error::Open { target, message: p(message) },
+      Error::PreflightFailed { reason, message } => Error::PreflightFailed { reason, message: p(message) },
+      Error::NotSupportedByApi { operation, message } => Error::NotSupportedByApi { operation, message: p(message) },
+      e @ (Error::InsufficientScope { .. } | Error::QuotaExceeded { .. } | Error::DryRun(_) | Error::Offline | Error::TimedOut { .. } | Error::NoChannelForAccount { .. }
+      | Error::RefreshBackoff { .. } | Error::PassphraseRejected | Error::GrantRevoked { .. } | Error::ConsentFailed(_)
+      | Error::WorkspaceRestricted { .. } | Error::ReadOnlyMode | Error::SidecarInvalid { .. }
+      | Error::StorageBusy { .. } | Error::TokenEndpoint(_) | Error::VideoRejected { .. }
+      | Error::TlsInterceptionSuspected { .. }) => e,
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+/// Code table behind [`Error::code`] and the `error_codes` command:
+/// (code, description, retryable by default).
+const CODES: &[(&str, &str, bool)] = &[
+  ("CONFIG_MISSING", "Brak lub nieprawidłowa konfiguracja klienta OAuth", false),
+  ("REAUTH_REQUIRED", "Brak tokenów lub nieudane odświeżenie — wymagane ponowne logowanie", false),
+  ("INSUFFICIENT_SCOPE", "Tokeny nie obejmują wymaganego zakresu — wywołaj request_scopes (details.missing)", false),
+  ("NETWORK_ERROR", "Błąd połączenia z serwerem", true),
+  ("FORBIDDEN", "YouTube odmówił wykonania operacji (details.reason)", false),
+  ("API_ERROR", "Inny błąd YouTube API (details.status, details.reason); ponawialny dla 429, 5xx i limitów częstotliwości", true),
+  ("QUOTA_EXCEEDED", "Dzienny limit quota wyczerpany (details.needed, details.remaining)", false),
+  ("VALIDATION_FAILED", "Dane wejściowe odrzucone lokalnie przed wywołaniem API", false),
+  ("STORAGE_ERROR", "Błąd odczytu lub zapisu plików w katalogu konfiguracyjnym", true),
+  ("DRY_RUN", "Tryb próbny pominął operację; details zawiera plan wywołania", false),
+  ("OFFLINE", "Brak połączenia; odczyty zwracają ostatnią odpowiedź z from_cache: true", true),
+  ("TIMED_OUT", "Przekroczono limit czasu (details.operation, details.after_secs)", true),
+  ("NO_CHANNEL", "Konto Google nie ma jeszcze kanału YouTube (details.email, jeśli znany)", false),
+  ("REFRESH_BACKOFF", "Odświeżanie tokenu wstrzymane po błędach sieci do details.retry_at (sekundy Unix)", true),
+  ("OPEN_FAILED", "Nie udało się uruchomić menedżera plików lub programu (details.target do otwarcia ręcznie)", false),
+  ("PASSPHRASE_REJECTED", "Brak, błędne lub zbyt często podawane hasło aplikacji", false),
+  ("PREFLIGHT_FAILED", "Plik nie przeszedł kontroli przed wysłaniem (details.reason); wpis kolejki jest zablokowany", true),
+  ("CONSENT_FAILED", "Google odrzucił logowanie; details to wskazówka (code, explanation, action, help_url)", false),
+  ("WORKSPACE_RESTRICTED", "Logowanie zablokowane przez zasady Google Workspace (details.reason i wskazówka jak w CONSENT_FAILED)", false),
+  ("READ_ONLY_MODE", "Tryb tylko do odczytu zablokował operację zmieniającą dane (set_read_only_mode)", false),
+  ("SIDECAR_INVALID", "Plik metadanych obok filmu wymaga poprawek (details.path, details.diagnostics z wierszem i kolumną)", false),
+  ("STORAGE_BUSY", "Inny proces (np. CLI) trzyma blokadę katalogu konfiguracyjnego (details.path, details.waited_ms)", true),
+  ("NOT_SUPPORTED_BY_API", "YouTube API nie udostępnia tej operacji (details.operation); wykonaj ją w YouTube Studio", false),
+  ("TOKEN_ENDPOINT_ERROR", "Serwer tokenów odrzucił żądanie lub odpowiedział bez tokenu (details.status, details.error, details.error_description, details.error_uri, details.problem)", false),
+  ("VIDEO_REJECTED", "YouTube odrzucił wideo po wysłaniu, nie przetworzył go albo je usunięto (details.video_id, details.kind, details.reason)", false),
+  ("TLS_INTERCEPTION_SUSPECTED", "Przy pin_google_tls certyfikat serwera logowania nie pochodzi od CA Google, a bez przypinania połączenie działa — pośrednik TLS (details.host)", false),
+];
+
+impl Error {
+  pub fn code(&self) -> &'static str {
+    let i = match self {
+      Error::Config(_) => 0,
+      Error::Auth(_) | Error::GrantRevoked { .. } => 1,
+      Error::InsufficientScope { .. } => 2,
+      Error::Network(_) => 3,
+      Error::Forbidden { .. } => 4,
+      Error::Api { .. } => 5,
+      Error::QuotaExceeded { .. } => 6,
+      Error::Validation(_) => 7,
+      Error::Storage(_) => 8,
+      Error::DryRun(_) => 9,
+      Error::Offline => 10,
+      Error::TimedOut { .. } => 11,
+      Error::NoChannelForAccount { .. } => 12,
+      Error::RefreshBackoff { .. } => 13,
+      Error::Open { .. } => 14,
+      Error::PassphraseRejected => 15,
+      Error::PreflightFailed { .. } => 16,
+      Error::ConsentFailed(_) => 17,
+      Error::WorkspaceRestricted { .. } => 18,
+      Error::ReadOnlyMode => 19,
+      Error::SidecarInvalid { .. } => 20,
+      Error::StorageBusy { .. } => 21,
+      Error::NotSupportedByApi { .. } => 22,
+      Error::TokenEndpoint(_) => 23,
+      Error::VideoRejected { .. } => 24,
+      Error::TlsInterceptionSuspected { .. } => 25,
+    };
+    CODES[i].0
+  }
+
+  /// Whether repeating the same call unchanged may succeed.
+  pub fn retryable(&self) -> bool {
+    match self {
+      Error::Api { status, reason, .. } => *status == 429 || *status >= 500 || forbidden::is_rate_limit(reason),
+      e => CODES.iter().any(|(code, _, retryable)| *code == e.code() && *retryable),
+    }
+  }
+
+  fn details(&self) -> Option<Value> {
+    match self {
+      Error::InsufficientScope { missing, reconsent } => Some(json!({ "missing": missing, "reconsent": reconsent })),
+      Error::Forbidden { reason, .. } => Some(json!({ "reason": reason })),
+      Error::Api { status, reason, .. } => Some(json!({ "status": status, "reason": reason })),
+      Error::QuotaExceeded { needed, remaining } => Some(json!({ "needed": needed, "remaining": remaining })),
+      Error::DryRun(plan) => serde_json::to_value(plan).ok(),
+      Error::TimedOut { operation, after_secs } => Some(json!({ "operation": operation, "after_secs": after_secs })),
+      Error::NoChannelForAccount { email } => Some(json!({ "email": email })),
+      Error::RefreshBackoff { retry_at } => Some(json!({ "retry_at": retry_at })),
+      Error::Open { target, .. } => Some(json!({ "target": target })),
+      Error::GrantRevoked { hint: Some(hint) } => Some(json!({ "hint": hint })),
+      Error::PreflightFailed { reason, .. } => Some(json!({ "reason": reason })),
+      Error::SidecarInvalid { path, diagnostics } => Some(json!({ "path": path, "diagnostics": diagnostics })),
+      Error::StorageBusy { path, waited_ms } => Some(json!({ "path": path, "waited_ms": waited_ms })),
+      Error::NotSupportedByApi { operation, .. } => Some(json!({ "operation": operation })),
+      Error::TokenEndpoint(e) => serde_json::to_value(e).ok(),
+      Error::VideoRejected { video_id, rejection } => Some(json!({ "video_id": video_id, "kind": rejection.kind, "reason": rejection.reason })),
+      Error::TlsInterceptionSuspected { host } => Some(json!({ "host": host })),
+      Error::ConsentFailed(guidance) => serde_json::to_value(guidance).ok(),
+      Error::WorkspaceRestricted { reason, guidance } => {
+        let mut details = serde_json::to_value(guidance).ok()?;
+        details["reason"] = json!(reason);
+        Some(details)
+      }
+      _ => None,
+    }
+  }
+}
+
+/// What commands reject with on the JS side: the `code` is the stable
+/// contract, `message` is for display only.
+#[derive(Serialize, Debug, Clone)]
+pub struct CommandError {
+  pub code: &'static str,
+  pub message: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub details: Option<Value>,
+  pub retryable: bool,
+}
+
+impl From<Error> for CommandError {
+  fn from(e: Error) -> Self {
+    CommandError { code: e.code(), message: e.to_string(), details: e.details(), retryable: e.retryable() }
+  }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorCode {
+  pub code: &'static str,
+  pub description: &'static str,
+  /// Default for the code; `CommandError::retryable` is authoritative per error.
+  pub retryable: bool,
+}
+
+pub fn error_codes() -> Vec<ErrorCode> {
+  CODES.iter().map(|&(code, description, retryable)| ErrorCode { code, description, retryable }).collect()
+}
+
+impl From<Error> for String {
+  fn from(e: Error) -> String { e.to_string() }
+}
@@ -0,0 +1,170 @@
+//! Rate limiting for the events sent to the webview. Concurrent uploads,
+//! listings and processing polls each report progress many times a second;
+//! the UI only needs the latest numbers a few times a second. Progress
+//! events are coalesced per key (the file being uploaded, the playlist being
+//! listed) and state changes go out at once, after any progress they
+//! supersede, so a `failed` is never followed by a stale `45%`.
+
+use crate::runtime;
+use serde::Serialize;
+use std::{
+  collections::HashMap,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+/// Progress events kept per key and second; the rest only update the payload.
+pub const MAX_PROGRESS_PER_SEC: u32 = 5;
+
+#[derive(Default)]
+struct KeyState {
+  last_sent: Option<Instant>,
+  /// Held back progress: event name and the latest payload.
+  pending: Option<(String, serde_json::Value)>,
+}
+
+impl KeyState {
+  fn due_at(&self, interval: Duration) -> Option<Instant> {
+    self.last_sent.map(|at| at + interval)
+  }
+}
+
+/// The coalescing state, separate from any clock or emitter so uploads drive
+/// it with real time and tests with synthetic instants. Every method hands
+/// what should go out now to `send`, in order.
+pub struct Dispatcher {
+  interval: Duration,
+  keys: HashMap<String, KeyState>,
+}
+
+impl Default for Dispatcher {
+  fn default() -> Self {
+    Self::new(MAX_PROGRESS_PER_SEC)
+  }
+}
+
+impl Dispatcher {
+  pub fn new(max_per_sec: u32) -> Self {
+    Self { interval: Duration::from_secs(1) / max_per_sec.max(1), keys: HashMap::new() }
+  }
+
+  /// Sends a progress event for `key` now if the last one went out long
+  /// enough ago; otherwise keeps it as the latest and returns when
+  /// [`flush_due`](Self::flush_due) should be called for it. Returns `None`
+  /// when a flush is already expected for this key.
+  pub fn progress(
+    &mut self,
+    now: Instant,
+    key: &str,
+    event: &str,
+    payload: serde_json::Value,
+    send: &mut dyn FnMut(&str, serde_json::Value),
+  ) -> Option<Instant> {
+    let interval = self.interval;
+    let state = self.keys.entry(key.to_string()).or_default();
+    match state.due_at(interval) {
+      Some(due) if now < due => {
+        let first = state.pending.replace((event.to_string(), payload)).is_none();
+        first.then_some(due)
+      }
+      _ => {
+        // A different progress event for the same key may still be held;
+        // it is older than this one, so it goes first.
+        if let Some((held, held_payload)) = state.pending.take() {
+          if held != event {
+            send(&held, held_payload);
+          }
+        }
+        state.last_sent = Some(now);
+        send(event, payload);
+        None
+      }
+    }
+  }
+
+  /// Sends a state change for `key` immediately, preceded by its held back
+  /// progress. The key starts afresh: the next progress goes out at once.
+  pub fn transition(&mut self, key: &str, event: &str, payload: serde_json::Value, send: &mut dyn FnMut(&str, serde_json::Value)) {
+    if let Some((held, held_payload)) = self.keys.remove(key).and_then(|s| s.pending) {
+      send(&held, held_payload);
+    }
+    send(event, payload);
+  }
+
+  /// Sends a state change that concerns every key, like the whole queue,
+  /// after all held back progress.
+  pub fn broadcast(&mut self, event: &str, payload: serde_json::Value, send: &mut dyn FnMut(&str, serde_json::Value)) {
+    let mut keys: Vec<String> = self.keys.keys().cloned().collect();
+    keys.sort();
+    for key in keys {
+      if let Some((held, held_payload)) = self.keys.get_mut(&key).and_then(|s| s.pending.take()) {
+        send(&held, held_payload);
+      }
+    }
+    self.keys.clear();
+    send(event, payload);
+  }
+
+  /// Sends held back progress whose slot has come. Returns the earliest slot
+  /// still ahead, if any progress is left waiting.
+  pub fn flush_due(&mut self, now: Instant, send: &mut dyn FnMut(&str, serde_json::Value)) -> Option<Instant> {
+    let interval = self.interval;
+    let mut next: Option<Instant> = None;
+    for state in self.keys.values_mut() {
+      let Some(due) = state.due_at(interval).filter(|_| state.pending.is_some()) else { continue };
+      if now < due {
+        next = Some(next.map_or(due, |n| n.min(due)));
+      } else if let Some((event, payload)) = state.pending.take() {
+        state.last_sent = Some(now);
+        send(&event, payload);
+      }
+    }
+    next
+  }
+}
+
+fn with_dispatcher<T>(dir: &Path, f: impl FnOnce(&mut Dispatcher, &mut dyn FnMut(&str, serde_json::Value)) -> T) -> T {
+  let rt = runtime::for_dir(dir);
+  // Emitting under the lock keeps the order across tasks.
+  let mut dispatcher = rt.events();
+  f(&mut dispatcher, &mut |event, payload| rt.emit(event, payload))
+}
+
+fn schedule_flush(dir: &Path, due: Instant) {
+  let Ok(handle) = tokio::runtime::Handle::try_current() else {
+    // No runtime to wait on: send what is held right away.
+    with_dispatcher(dir, |d, send| d.flush_due(due, send));
+    return;
+  };
+  let dir = dir.to_path_buf();
+  handle.spawn(async move {
+    let mut due = due;
+    loop {
+      tokio::time::sleep_until(due.into()).await;
+      match with_dispatcher(&dir, |d, send| d.flush_due(Instant::now(), send)) {
+        Some(next) => due = next,
+        None => break,
+      }
+    }
+  });
+}
+
+/// Progress for `key`, coalesced to [`MAX_PROGRESS_PER_SEC`].
+pub(crate) fn progress(dir: &Path, key: &str, event: &str, payload: impl Serialize) {
+  let payload = serde_json::to_value(payload).unwrap_or_default();
+  if let Some(due) = with_dispatcher(dir, |d, send| d.progress(Instant::now(), key, event, payload, send)) {
+    schedule_flush(dir, due);
+  }
+}
+
+/// A state change for `key` (started, completed, failed), sent now.
+pub(crate) fn transition(dir: &Path, key: &str, event: &str, payload: impl Serialize) {
+  let payload = serde_json::to_value(payload).unwrap_or_default();
+  with_dispatcher(dir, |d, send| d.transition(key, event, payload, send));
+}
+
+/// A state change for everything in flight, sent now.
+pub(crate) fn broadcast(dir: &Path, event: &str, payload: impl Serialize) {
+  let payload = serde_json::to_value(payload).unwrap_or_default();
+  with_dispatcher(dir, |d, send| d.broadcast(event, payload, send));
+}
@@ -0,0 +1,120 @@
+//! YouTube answers 403 for problems that want opposite reactions: an empty
+//! quota waits for the reset, a rate limit for a few seconds, a missing
+//! scope for consent, and an ineligible channel won't change by retrying.
+//! The reason in the body decides; every wrapped endpoint maps its errors
+//! through [`Forbidden::into_error`] and [`record`].
+
+use crate::{auth::ScopesNotGranted, error::Error, quota, reconsent, runtime};
+use serde::Serialize;
+use std::{path::Path, time::Duration};
+
+/// Replays after a rate limit before the error is returned.
+pub const RATE_LIMIT_RETRIES: u32 = 2;
+const RATE_LIMIT_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Longer `Retry-After` values are the caller's to wait out.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForbiddenKind {
+  /// The project's daily quota is gone until midnight Pacific time.
+  Quota,
+  /// Too many requests for now; retried with backoff.
+  RateLimit,
+  /// The token lacks a scope; needs consent through `request_scopes`.
+  Scope,
+  /// The channel, account or video can't do this (custom thumbnails,
+  /// live streaming, not the owner). Permanent until fixed on YouTube.
+  Ineligible,
+}
+
+/// What a refusal said, read from a Google error body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forbidden {
+  pub kind: ForbiddenKind,
+  pub reason: String,
+  pub message: String,
+}
+
+pub fn classify(reason: &str) -> ForbiddenKind {
+  match reason {
+    "quotaExceeded" | "dailyLimitExceeded" | "dailyLimitExceededUnreg" => ForbiddenKind::Quota,
+    "rateLimitExceeded" | "userRateLimitExceeded" => ForbiddenKind::RateLimit,
+    "insufficientPermissions" | "ACCESS_TOKEN_SCOPE_INSUFFICIENT" => ForbiddenKind::Scope,
+    _ => ForbiddenKind::Ineligible,
+  }
+}
+
+/// Reads `{"error": {"message", "errors": [{"reason", "domain"}]}}`; the
+/// newer `details[].reason` form is used when `errors` is missing.
+pub fn parse(body: &str) -> Forbidden {
+  let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+  let err = &json["error"];
+  let first = &err["errors"][0];
+  let reason = first["reason"].as_str().or_else(|| err["details"][0]["reason"].as_str()).unwrap_or_default().to_string();
+  let google = err["message"].as_str().map(str::to_string).unwrap_or_else(|| body.to_string());
+  let message = describe(&reason, first["domain"].as_str().unwrap_or_default()).map(str::to_string).unwrap_or(google);
+  Forbidden { kind: classify(&reason), reason, message }
+}
+
+fn describe(reason: &str, domain: &str) -> Option<&'static str> {
+  Some(match (reason, domain) {
+    ("insufficientLivePermissions" | "liveStreamingNotEnabled", _) => {
+      "Ten kanał nie może prowadzić transmisji na żywo. Włącz transmisje w YouTube Studio (wymagana weryfikacja numeru telefonu, brak ograniczeń transmisji w ostatnich 90 dniach; aktywacja trwa do 24 h)."
+    }
+    ("forbidden", "youtube.thumbnail") => {
+      "Ten kanał nie może ustawiać własnych miniatur. Zweryfikuj konto na youtube.com/verify (funkcję może też blokować ostrzeżenie o naruszeniu zasad)."
+    }
+    ("uploadLimitExceeded", _) => "Kanał osiągnął dzienny limit wysyłanych filmów. Kolejne wysyłki będą możliwe następnego dnia.",
+    _ => return None,
+  })
+}
+
+impl Forbidden {
+  /// The typed error for a refused call that needed `required` scopes and
+  /// was charged `cost` units.
+  pub fn into_error(self, status: u16, required: &[&str], cost: u32) -> Error {
+    match self.kind {
+      ForbiddenKind::Scope => Error::InsufficientScope { missing: required.iter().map(|s| s.to_string()).collect(), reconsent: false },
+      ForbiddenKind::Quota => Error::QuotaExceeded { needed: cost, remaining: 0 },
+      ForbiddenKind::RateLimit => Error::Api { status, reason: self.reason, message: self.message },
+      ForbiddenKind::Ineligible => Error::Forbidden { reason: self.reason, message: self.message },
+    }
+  }
+}
+
+/// Whether `reason` is a rate limit, which passes with time.
+pub(crate) fn is_rate_limit(reason: &str) -> bool {
+  classify(reason) == ForbiddenKind::RateLimit
+}
+
+/// How long to wait before replaying a call that got `status` and `body`;
+/// `None` when it isn't a rate limit or `attempt` replays were spent.
+/// `Retry-After` is honored up to [`MAX_RETRY_AFTER`].
+pub(crate) fn retry_delay(status: u16, retry_after: Option<&str>, body: &str, attempt: u32) -> Option<Duration> {
+  let limited = status == 429 || (status == 403 && parse(body).kind == ForbiddenKind::RateLimit);
+  if !limited || attempt >= RATE_LIMIT_RETRIES {
+    return None;
+  }
+  let backoff = RATE_LIMIT_BASE_DELAY * 2u32.pow(attempt);
+  let asked = retry_after.and_then(|v| v.trim().parse::<u64>().ok()).map(Duration::from_secs);
+  Some(asked.filter(|a| *a <= MAX_RETRY_AFTER).map_or(backoff, |a| a.max(backoff)))
+}
+
+/// What the server's refusal changes locally: an empty quota is recorded so
+/// later calls fail before sending, and a missing scope starts the
+/// incremental consent flow through `scopes-not-granted`. Returns `err`,
+/// marked when it's the scope change [`reconsent`] is pending for.
+pub(crate) fn record(dir: &Path, err: Error) -> Error {
+  match &err {
+    Error::QuotaExceeded { .. } => {
+      // Best effort: the error goes back either way.
+      let _ = quota::exhaust(dir);
+    }
+    Error::InsufficientScope { missing, .. } => {
+      runtime::for_dir(dir).emit("scopes-not-granted", ScopesNotGranted { missing: missing.clone() })
+    }
+    _ => {}
+  }
+  reconsent::annotate(dir, err)
+}
@@ -0,0 +1,127 @@
+//! Display strings for sizes, durations and times in the app language
+//! (`language` in the config), so every screen words them the same way.
+//! Payloads carrying these strings keep their raw numbers next to them.
+
+use crate::{error::Error, progress::UploadProgress, read_config_from_dir};
+use chrono::{DateTime, Datelike};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+const MONTHS_PL: [&str; 12] = [
+  "stycznia",
+  "lutego",
+  "marca",
+  "kwietnia",
+  "maja",
+  "czerwca",
+  "lipca",
+  "sierpnia",
+  "września",
+  "października",
+  "listopada",
+  "grudnia",
+];
+const MONTHS_EN: [&str; 12] =
+  ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+  #[default]
+  Pl,
+  En,
+}
+
+/// Human-readable companions of an `upload-progress` event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProgressLabels {
+  pub sent: String,
+  pub total: String,
+  pub speed: String,
+  pub eta: String,
+}
+
+pub fn language(dir: &Path) -> Language {
+  read_config_from_dir(dir).and_then(|c| c.language).unwrap_or_default()
+}
+
+fn decimal(value: f64, lang: Language) -> String {
+  let s = format!("{:.1}", value);
+  match lang {
+    Language::Pl => s.replace('.', ","),
+    Language::En => s,
+  }
+}
+
+/// Byte count in binary units: `0 B`, `512 B`, `1,5 MiB`.
+pub fn bytes(n: u64, lang: Language) -> String {
+  if n < 1024 {
+    return format!("{} B", n);
+  }
+  let mut value = n as f64;
+  let mut unit = 0;
+  // Compare after rounding, so 1023.97 KiB reads as 1.0 MiB, not 1024.0 KiB.
+  while unit + 1 < UNITS.len() && (value * 10.0).round() >= 10240.0 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  format!("{} {}", decimal(value, lang), UNITS[unit])
+}
+
+pub fn speed(bytes_per_sec: f64, lang: Language) -> String {
+  let n = if bytes_per_sec.is_finite() { bytes_per_sec.max(0.0).round() as u64 } else { 0 };
+  format!("{}/s", bytes(n, lang))
+}
+
+/// Up to the two largest units: `450 ms`, `5 s`, `1 min 5 s`, `2 d 3 h`.
+/// Negative and non-finite values read as zero.
+pub fn duration(secs: f64, lang: Language) -> String {
+  let secs = if secs.is_finite() { secs.max(0.0) } else { 0.0 };
+  if secs < 1.0 {
+    // 0.9996 s would round to "1000 ms".
+    return format!("{} ms", ((secs * 1000.0).round() as u64).min(999));
+  }
+  let total = secs.round() as u64;
+  let hour = match lang {
+    Language::Pl => "godz.",
+    Language::En => "h",
+  };
+  let parts = [(total / 86_400, "d"), (total % 86_400 / 3600, hour), (total % 3600 / 60, "min"), (total % 60, "s")];
+  let first = parts.iter().position(|(v, _)| *v > 0).unwrap_or(3);
+  parts[first..].iter().take(2).filter(|(v, _)| *v > 0).map(|(v, unit)| format!("{} {}", v, unit)).collect::<Vec<_>>().join(" ")
+}
+
+/// Remaining time of a transfer; `None` is an estimate that isn't ready yet.
+pub fn eta(secs: Option<u64>, lang: Language) -> String {
+  match (secs, lang) {
+    (None, Language::Pl) => "obliczanie…".into(),
+    (None, Language::En) => "estimating…".into(),
+    (Some(0), Language::Pl) => "za chwilę".into(),
+    (Some(0), Language::En) => "any moment".into(),
+    (Some(s), Language::Pl) => format!("jeszcze {}", duration(s as f64, lang)),
+    (Some(s), Language::En) => format!("{} left", duration(s as f64, lang)),
+  }
+}
+
+/// RFC3339 timestamp as a date and time in its own offset:
+/// `14 października 2026, 18:05`.
+pub fn timestamp(rfc3339: &str, lang: Language) -> Result<String, Error> {
+  let t = DateTime::parse_from_rfc3339(rfc3339.trim())
+    .map_err(|e| Error::Validation(format!("Nieprawidłowa data RFC3339 {:?}: {}", rfc3339, e)))?;
+  let months = match lang {
+    Language::Pl => &MONTHS_PL,
+    Language::En => &MONTHS_EN,
+  };
+  Ok(format!("{} {} {}, {}", t.day(), months[t.month0() as usize], t.year(), t.format("%H:%M")))
+}
+
+pub fn progress_labels(p: &UploadProgress, lang: Language) -> ProgressLabels {
+  ProgressLabels {
+    sent: bytes(p.bytes_sent, lang),
+    total: bytes(p.total_bytes, lang),
+    speed: speed(p.smoothed_bytes_per_sec.unwrap_or(p.bytes_per_sec), lang),
+    eta: eta(p.eta_secs, lang),
+  }
+}
@@ -0,0 +1,338 @@
+//! What to do about the errors Google's consent screen and token endpoint
+//! send back. The raw `error` values ("access_denied", "admin_policy_enforced")
+//! mean nothing to most users, so each known one maps to an entry with an
+//! explanation and a concrete action, in the app language. The callback page,
+//! the `oauth-callback` event and `CONSENT_FAILED` errors all carry the same
+//! structured entry. Refusals by a Google Workspace policy get their own
+//! error and event: only an admin or another account can fix those.
+
+use crate::{error::Error, format::Language, runtime, CALLBACK_PORT};
+use serde::Serialize;
+use std::path::Path;
+
+/// Where unknown errors send the user for more.
+const GOOGLE_ERRORS_URL: &str = "https://developers.google.com/identity/protocols/oauth2/web-server#authorization-errors";
+const CONSENT_SCREEN_URL: &str = "https://console.cloud.google.com/apis/credentials/consent";
+const CREDENTIALS_URL: &str = "https://console.cloud.google.com/apis/credentials";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GuidanceSource {
+  /// The redirect from the consent screen.
+  Callback,
+  /// The code exchange at the token endpoint.
+  Token,
+  /// The probe of the consent screen before the browser opened.
+  Preflight,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConsentGuidance {
+  /// Stable id of the entry, `UNKNOWN` when the error isn't in the catalog.
+  pub code: String,
+  pub source: GuidanceSource,
+  /// The values as Google sent them.
+  pub error: String,
+  pub error_subtype: Option<String>,
+  pub description: Option<String>,
+  pub known: bool,
+  pub title: String,
+  pub explanation: String,
+  pub action: Option<String>,
+  /// Page to open for the fix, or Google's error reference for unknown codes.
+  pub help_url: String,
+}
+
+/// Which Workspace policy refused the login.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceReason {
+  /// The account's admin hasn't trusted this app.
+  AdminPolicyEnforced,
+  /// The consent screen is Internal to another organization.
+  OrgInternal,
+}
+
+/// Payload of `workspace-restricted`.
+#[derive(Serialize, Debug, Clone)]
+pub struct WorkspaceRestriction {
+  pub reason: WorkspaceReason,
+  pub guidance: ConsentGuidance,
+}
+
+/// One catalog entry; texts are `[pl, en]`.
+struct Entry {
+  code: &'static str,
+  title: [&'static str; 2],
+  explanation: [&'static str; 2],
+  action: [&'static str; 2],
+  help_url: &'static str,
+}
+
+const ACCESS_DENIED: Entry = Entry {
+  code: "ACCESS_DENIED",
+  title: ["Google odmówił dostępu", "Google denied access"],
+  explanation: [
+    "Zgoda nie została udzielona: logowanie anulowano albo konto nie jest użytkownikiem testowym aplikacji, której ekran zgody jest w trybie Testing.",
+    "Consent was not granted: the login was cancelled, or the account is not a test user of an app whose consent screen is in Testing mode.",
+  ],
+  action: [
+    "Jeśli logowanie nie zostało anulowane, dodaj to konto jako użytkownika testowego w Cloud Console (APIs & Services → OAuth consent screen → Test users) i zaloguj się ponownie.",
+    "If you didn't cancel, add this account as a test user in the Cloud Console (APIs & Services → OAuth consent screen → Test users) and log in again.",
+  ],
+  help_url: CONSENT_SCREEN_URL,
+};
+
+const APP_NOT_VERIFIED: Entry = Entry {
+  code: "APP_NOT_VERIFIED",
+  title: ["Aplikacja nie jest zweryfikowana przez Google", "The app isn't verified by Google"],
+  explanation: [
+    "Google blokuje niezweryfikowane aplikacje proszące o zakresy YouTube dla kont spoza listy użytkowników testowych.",
+    "Google blocks unverified apps asking for YouTube scopes for accounts outside the test user list.",
+  ],
+  action: [
+    "Dla własnego projektu dodaj konto jako użytkownika testowego albo na ekranie ostrzeżenia wybierz Zaawansowane → Przejdź do aplikacji; dla aplikacji publicznej zgłoś ją do weryfikacji.",
+    "For your own project add the account as a test user, or choose Advanced → Go to app on the warning screen; for a public app, submit it for verification.",
+  ],
+  help_url: CONSENT_SCREEN_URL,
+};
+
+const CATALOG: &[(&str, Entry)] = &[
+  ("access_denied", ACCESS_DENIED),
+  (
+    "admin_policy_enforced",
+    Entry {
+      code: "ADMIN_POLICY_ENFORCED",
+      title: ["Zablokowane przez administratora Google Workspace", "Blocked by the Google Workspace admin"],
+      explanation: [
+        "Zasady organizacji nie pozwalają temu kontu udzielić dostępu aplikacjom spoza listy zaufanych.",
+        "The organization's policy doesn't let this account grant access to apps outside the trusted list.",
+      ],
+      action: [
+        "Poproś administratora o dodanie Client ID aplikacji do zaufanych (Admin console → Security → API controls) albo zaloguj się kontem prywatnym.",
+        "Ask the admin to trust the app's Client ID (Admin console → Security → API controls), or log in with a personal account.",
+      ],
+      help_url: "https://support.google.com/a/answer/7281227",
+    },
+  ),
+  (
+    "org_internal",
+    Entry {
+      code: "ORG_INTERNAL",
+      title: ["Ten klient OAuth jest ograniczony do innej organizacji", "This OAuth client is restricted to another organization"],
+      explanation: [
+        "Ekran zgody ma typ użytkownika Internal, więc logować mogą się tylko konta z organizacji projektu.",
+        "The consent screen's user type is Internal, so only accounts from the project's organization can log in.",
+      ],
+      action: [
+        "Zaloguj się kontem z tej organizacji albo zmień typ użytkownika na External w ekranie zgody OAuth.",
+        "Log in with an account from that organization, or switch the user type to External on the OAuth consent screen.",
+      ],
+      help_url: CONSENT_SCREEN_URL,
+    },
+  ),
+  (
+    "disallowed_useragent",
+    Entry {
+      code: "DISALLOWED_USERAGENT",
+      title: ["Przeglądarka odrzucona przez Google", "Browser rejected by Google"],
+      explanation: [
+        "Google nie pozwala logować się we wbudowanych oknach przeglądarki.",
+        "Google doesn't allow logging in from embedded browser windows.",
+      ],
+      action: [
+        "Ustaw zwykłą przeglądarkę jako domyślną w systemie i zacznij logowanie od nowa.",
+        "Make a regular browser the system default and start the login again.",
+      ],
+      help_url: GOOGLE_ERRORS_URL,
+    },
+  ),
+  (
+    "redirect_uri_mismatch",
+    Entry {
+      code: "REDIRECT_URI_MISMATCH",
+      title: ["Nieznany adres przekierowania", "Redirect URI not registered"],
+      explanation: [
+        "Klient OAuth w Cloud Console nie ma adresu, na który aplikacja odbiera logowanie.",
+        "The OAuth client in the Cloud Console doesn't list the address the app receives the login on.",
+      ],
+      action: [
+        "Dodaj http://127.0.0.1:{port}/callback w Authorized redirect URIs klienta (Credentials) i spróbuj ponownie.",
+        "Add http://127.0.0.1:{port}/callback to the client's Authorized redirect URIs (Credentials) and try again.",
+      ],
+      help_url: CREDENTIALS_URL,
+    },
+  ),
+  (
+    "invalid_client",
+    Entry {
+      code: "INVALID_CLIENT",
+      title: ["Nieprawidłowy klient OAuth", "Invalid OAuth client"],
+      explanation: [
+        "Google nie rozpoznaje client_id lub client_secret — są błędne, z innego projektu albo klienta usunięto.",
+        "Google doesn't recognize the client_id or client_secret — they are wrong, from another project, or the client was deleted.",
+      ],
+      action: [
+        "Skopiuj Client ID i Client Secret ponownie z Cloud Console (Credentials) do konfiguracji aplikacji.",
+        "Copy the Client ID and Client Secret from the Cloud Console (Credentials) into the app's configuration again.",
+      ],
+      help_url: CREDENTIALS_URL,
+    },
+  ),
+  (
+    "unauthorized_client",
+    Entry {
+      code: "UNAUTHORIZED_CLIENT",
+      title: ["Klient nie może użyć tego logowania", "Client not allowed to use this login"],
+      explanation: [
+        "Typ klienta OAuth nie obsługuje logowania z przekierowaniem na adres lokalny.",
+        "The OAuth client's type doesn't support logging in with a redirect to a local address.",
+      ],
+      action: [
+        "Utwórz klienta typu Web application z adresem http://127.0.0.1:{port}/callback i wpisz jego dane w konfiguracji.",
+        "Create a Web application client with http://127.0.0.1:{port}/callback and put its credentials in the configuration.",
+      ],
+      help_url: CREDENTIALS_URL,
+    },
+  ),
+  (
+    "invalid_grant",
+    Entry {
+      code: "INVALID_GRANT",
+      title: ["Kod logowania nieważny", "Login code no longer valid"],
+      explanation: [
+        "Kod z przekierowania wygasł, został już użyty albo zegar komputera bardzo się rozjechał.",
+        "The code from the redirect expired, was already used, or the computer's clock is far off.",
+      ],
+      action: [
+        "Zacznij logowanie od nowa; jeśli błąd wraca, ustaw automatyczną synchronizację czasu w systemie.",
+        "Start the login again; if it keeps happening, turn on automatic time sync in the system.",
+      ],
+      help_url: GOOGLE_ERRORS_URL,
+    },
+  ),
+  (
+    "invalid_scope",
+    Entry {
+      code: "INVALID_SCOPE",
+      title: ["Nieznany zakres uprawnień", "Unknown permission scope"],
+      explanation: [
+        "Jeden z zakresów w scopes konfiguracji nie istnieje albo nie jest włączony dla projektu.",
+        "One of the scopes in the configuration's scopes doesn't exist or isn't enabled for the project.",
+      ],
+      action: [
+        "Popraw scopes w oauth_config.json i upewnij się, że YouTube Data API v3 jest włączone w projekcie.",
+        "Fix scopes in oauth_config.json and make sure YouTube Data API v3 is enabled in the project.",
+      ],
+      help_url: "https://console.cloud.google.com/apis/library/youtube.googleapis.com",
+    },
+  ),
+];
+
+fn pick(texts: [&'static str; 2], lang: Language) -> String {
+  let text = match lang {
+    Language::Pl => texts[0],
+    Language::En => texts[1],
+  };
+  text.replace("{port}", &CALLBACK_PORT.to_string())
+}
+
+fn lookup(error: &str, subtype: Option<&str>, description: Option<&str>) -> Option<&'static Entry> {
+  let find = |key: &str| CATALOG.iter().find(|(k, _)| *k == key).map(|(_, e)| e);
+  // Google uses one access_denied for a declined consent and for a blocked
+  // unverified app; only the description tells them apart.
+  let unverified = description.is_some_and(|d| d.to_lowercase().contains("verif"));
+  if error == "access_denied" && subtype.is_none() && unverified {
+    return Some(&APP_NOT_VERIFIED);
+  }
+  // An Internal app sometimes refuses outsiders with only a description.
+  let internal = description.is_some_and(|d| d.to_lowercase().contains("within its organization"));
+  if error == "access_denied" && subtype.is_none() && internal {
+    return find("org_internal");
+  }
+  subtype.and_then(find).or_else(|| find(error))
+}
+
+fn guidance(source: GuidanceSource, error: &str, subtype: Option<&str>, description: Option<&str>, lang: Language) -> ConsentGuidance {
+  let base = ConsentGuidance {
+    code: "UNKNOWN".into(),
+    source,
+    error: error.to_string(),
+    error_subtype: subtype.map(str::to_string),
+    description: description.filter(|d| !d.is_empty()).map(str::to_string),
+    known: false,
+    title: pick(["Logowanie nieudane", "Login failed"], lang),
+    explanation: String::new(),
+    action: None,
+    help_url: GOOGLE_ERRORS_URL.into(),
+  };
+  match lookup(error, subtype, description) {
+    Some(entry) => ConsentGuidance {
+      code: entry.code.into(),
+      known: true,
+      title: pick(entry.title, lang),
+      explanation: pick(entry.explanation, lang),
+      action: Some(pick(entry.action, lang)),
+      help_url: entry.help_url.into(),
+      ..base
+    },
+    None => {
+      let raw = subtype.map_or_else(|| error.to_string(), |s| format!("{} ({})", error, s));
+      let explanation = match (lang, &base.description) {
+        (Language::Pl, Some(d)) => format!("Google zwrócił błąd {}: {}", raw, d),
+        (Language::Pl, None) => format!("Google zwrócił błąd {}", raw),
+        (Language::En, Some(d)) => format!("Google returned the error {}: {}", raw, d),
+        (Language::En, None) => format!("Google returned the error {}", raw),
+      };
+      ConsentGuidance { explanation, ..base }
+    }
+  }
+}
+
+/// Entry for the `error`/`error_subtype` of a consent redirect.
+pub fn for_callback(error: &str, subtype: Option<&str>, description: Option<&str>, lang: Language) -> ConsentGuidance {
+  guidance(GuidanceSource::Callback, error, subtype, description, lang)
+}
+
+/// Entry for an `error` from the token endpoint's code exchange.
+pub fn for_token_error(error: &str, description: Option<&str>, lang: Language) -> ConsentGuidance {
+  guidance(GuidanceSource::Token, error, None, description, lang)
+}
+
+/// Entry for an `error` the consent screen showed to the pre-flight probe.
+pub fn for_preflight(error: &str, lang: Language) -> ConsentGuidance {
+  guidance(GuidanceSource::Preflight, error, None, None, lang)
+}
+
+/// `g` with the loopback redirect URI in its texts on `port` instead of the
+/// default one.
+pub(crate) fn on_port(g: ConsentGuidance, port: u16) -> ConsentGuidance {
+  let (default, actual) = (format!("127.0.0.1:{}/", CALLBACK_PORT), format!("127.0.0.1:{}/", port));
+  let swap = |text: String| text.replace(&default, &actual);
+  ConsentGuidance { explanation: swap(g.explanation), action: g.action.map(swap), ..g }
+}
+
+/// The Workspace policy behind a refusal, if any.
+pub fn workspace_reason(g: &ConsentGuidance) -> Option<WorkspaceReason> {
+  match g.code.as_str() {
+    "ADMIN_POLICY_ENFORCED" => Some(WorkspaceReason::AdminPolicyEnforced),
+    "ORG_INTERNAL" => Some(WorkspaceReason::OrgInternal),
+    _ => None,
+  }
+}
+
+/// Emits `workspace-restricted` when a Workspace policy refused the login.
+pub(crate) fn report_restriction(dir: &Path, g: &ConsentGuidance) -> Option<WorkspaceReason> {
+  let reason = workspace_reason(g)?;
+  runtime::for_dir(dir).emit("workspace-restricted", WorkspaceRestriction { reason, guidance: g.clone() });
+  Some(reason)
+}
+
+/// The error a refused login fails with: `WorkspaceRestricted` (reported as
+/// above) or `ConsentFailed`.
+pub(crate) fn refused(dir: &Path, g: ConsentGuidance) -> Error {
+  match report_restriction(dir, &g) {
+    Some(reason) => Error::WorkspaceRestricted { reason, guidance: Box::new(g) },
+    None => Error::ConsentFailed(Box::new(g)),
+  }
+}
@@ -0,0 +1,296 @@
+//! Content hashes of local files, for the library and upload sessions.
+//! BLAKE3 by default; `hash_algorithm: "sha256"` gives the hashes
+//! `sha256sum` and other tools compute.
+//!
+//! A file whose size and modification time match its library entry isn't
+//! read again. A large file being hashed with BLAKE3 is checkpointed to
+//! `hash_progress.json` every [`CHECKPOINT_BYTES`], keyed by path, size and
+//! mtime, so a hash cut short by closing the app resumes instead of
+//! starting over. SHA-256 offers no state to store and always starts over.
+
+use crate::{error::Error, events, library, now_secs, read_config_from_dir, storage};
+use blake3::hazmat::{self, ChainingValue, HasherExt, Mode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+  collections::BTreeMap,
+  fs::{self, File},
+  io::{Read, Seek, SeekFrom},
+  path::Path,
+  time::UNIX_EPOCH,
+};
+
+const PROGRESS_FILE: &str = "hash_progress.json";
+const READ_BLOCK: usize = 1024 * 1024;
+/// Bytes hashed between two checkpoints, a power of two of BLAKE3 chunks;
+/// smaller files are never checkpointed.
+pub const CHECKPOINT_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+  #[default]
+  Blake3,
+  Sha256,
+}
+
+impl HashAlgorithm {
+  pub const ALL: [HashAlgorithm; 2] = [HashAlgorithm::Blake3, HashAlgorithm::Sha256];
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      HashAlgorithm::Blake3 => "blake3",
+      HashAlgorithm::Sha256 => "sha256",
+    }
+  }
+}
+
+/// The algorithm the config asks for.
+pub fn configured(dir: &Path) -> HashAlgorithm {
+  read_config_from_dir(dir).and_then(|c| c.hash_algorithm).unwrap_or_default()
+}
+
+/// What `hash_file` returns.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileHash {
+  pub path: String,
+  pub algorithm: HashAlgorithm,
+  /// Lowercase hex.
+  pub hash: String,
+  pub size: u64,
+  /// Unix nanoseconds; absent where the filesystem has no mtime.
+  pub modified_ns: Option<u64>,
+  /// Taken from the library entry without reading the file.
+  pub from_library: bool,
+  /// Where an interrupted hash was picked up; 0 for a fresh one.
+  pub resumed_from: u64,
+}
+
+/// Payload of `hash-progress`.
+#[derive(Serialize, Debug, Clone)]
+pub struct HashProgress {
+  pub path: String,
+  pub algorithm: HashAlgorithm,
+  pub hashed_bytes: u64,
+  pub total_bytes: u64,
+}
+
+/// BLAKE3 fed in subtrees of [`CHECKPOINT_BYTES`] and merged through the
+/// crate's `hazmat` API the way its own chaining value stack is. At a
+/// subtree boundary the whole state is `stack`, which can be stored.
+#[derive(Clone)]
+struct Blake3Tree {
+  /// Chaining values of the finished subtrees, one per set bit of `subtrees`.
+  stack: Vec<ChainingValue>,
+  subtrees: u64,
+  /// Bytes in `current`, the subtree being hashed.
+  filled: u64,
+  current: blake3::Hasher,
+}
+
+impl Blake3Tree {
+  fn new() -> Self {
+    Blake3Tree { stack: Vec::new(), subtrees: 0, filled: 0, current: blake3::Hasher::new() }
+  }
+
+  /// Continues after `subtrees` whole subtrees whose chaining values are `stack`.
+  fn resume(subtrees: u64, stack: Vec<ChainingValue>) -> Self {
+    let mut current = blake3::Hasher::new();
+    current.set_input_offset(subtrees * CHECKPOINT_BYTES);
+    Blake3Tree { stack, subtrees, filled: 0, current }
+  }
+
+  /// Ends the full subtree in `current`; only once more input follows, as
+  /// the last one is finalized differently.
+  fn close_subtree(&mut self) {
+    let mut cv = self.current.finalize_non_root();
+    self.subtrees += 1;
+    let mut total = self.subtrees;
+    while total & 1 == 0 {
+      let left = self.stack.pop().expect("a chaining value per set bit");
+      cv = hazmat::merge_subtrees_non_root(&left, &cv, Mode::Hash);
+      total >>= 1;
+    }
+    self.stack.push(cv);
+    *self = Blake3Tree::resume(self.subtrees, std::mem::take(&mut self.stack));
+  }
+
+  fn update(&mut self, mut input: &[u8]) {
+    while !input.is_empty() {
+      if self.filled == CHECKPOINT_BYTES {
+        self.close_subtree();
+      }
+      let take = (CHECKPOINT_BYTES - self.filled).min(input.len() as u64) as usize;
+      self.current.update(&input[..take]);
+      self.filled += take as u64;
+      input = &input[take..];
+    }
+  }
+
+  /// The stack to store, at a subtree boundary with more input to come.
+  fn checkpoint(&self) -> Option<Vec<ChainingValue>> {
+    (self.filled == CHECKPOINT_BYTES).then(|| {
+      let mut next = self.clone();
+      next.close_subtree();
+      next.stack
+    })
+  }
+
+  fn finalize(&self) -> blake3::Hash {
+    let Some((first, rest)) = self.stack.split_first() else {
+      return self.current.finalize();
+    };
+    let right = rest.iter().rev().fold(self.current.finalize_non_root(), |cv, left| hazmat::merge_subtrees_non_root(left, &cv, Mode::Hash));
+    hazmat::merge_subtrees_root(first, &right, Mode::Hash)
+  }
+}
+
+/// A hash in progress. `sha2` keeps its state to itself, so only BLAKE3
+/// hashes are checkpointed; an interrupted SHA-256 one starts over.
+enum Hasher {
+  Blake3(Blake3Tree),
+  Sha256(Sha256),
+}
+
+impl Hasher {
+  fn update(&mut self, input: &[u8]) {
+    match self {
+      Hasher::Blake3(tree) => tree.update(input),
+      Hasher::Sha256(h) => h.update(input),
+    }
+  }
+
+  fn hex(self) -> String {
+    match self {
+      Hasher::Blake3(tree) => tree.finalize().to_hex().to_string(),
+      Hasher::Sha256(h) => h.finalize().iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+  }
+}
+
+/// A BLAKE3 hash cut short at a subtree boundary, as checkpointed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Partial {
+  size: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  modified_ns: Option<u64>,
+  offset: u64,
+  /// [`Blake3Tree::stack`] at `offset`, as hex.
+  chaining_values: Vec<String>,
+  updated_at: u64,
+}
+
+impl Partial {
+  /// The hash to continue, if this checkpoint is one of `size` bytes and
+  /// fits the tree.
+  fn resume(&self, size: u64) -> Option<Blake3Tree> {
+    let subtrees = self.offset / CHECKPOINT_BYTES;
+    if self.offset % CHECKPOINT_BYTES != 0 || self.offset >= size || self.chaining_values.len() != subtrees.count_ones() as usize {
+      return None;
+    }
+    let stack = self.chaining_values.iter().map(|cv| blake3::Hash::from_hex(cv).ok().map(|h| *h.as_bytes())).collect::<Option<_>>()?;
+    (subtrees > 0).then(|| Blake3Tree::resume(subtrees, stack))
+  }
+}
+
+fn read_progress(dir: &Path) -> BTreeMap<String, Partial> {
+  fs::read_to_string(dir.join(PROGRESS_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Stores or drops the checkpoint for `path`; files gone since are dropped too.
+fn save_progress(dir: &Path, path: &str, partial: Option<Partial>) -> Result<(), Error> {
+  let _lock = storage::lock(dir)?;
+  let mut progress = read_progress(dir);
+  match partial {
+    Some(p) => {
+      progress.insert(path.to_string(), p);
+    }
+    None => {
+      if progress.remove(path).is_none() {
+        return Ok(());
+      }
+    }
+  }
+  progress.retain(|p, _| Path::new(p).is_file());
+  if progress.is_empty() {
+    return match fs::remove_file(dir.join(PROGRESS_FILE)) {
+      Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::Storage(e.to_string())),
+      _ => Ok(()),
+    };
+  }
+  let s = serde_json::to_string_pretty(&progress).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_atomic(&dir.join(PROGRESS_FILE), s).map_err(|e| Error::Storage(e.to_string()))
+}
+
+fn unreadable(path: &Path, e: std::io::Error) -> Error {
+  Error::Storage(format!("Nie można odczytać {}: {}", path.display(), e))
+}
+
+/// Size and mtime (unix nanoseconds) of `path`.
+pub(crate) fn stamp(path: &Path) -> Result<(u64, Option<u64>), Error> {
+  let meta = fs::metadata(path).map_err(|e| unreadable(path, e))?;
+  let modified = meta.modified().ok().and_then(|m| m.duration_since(UNIX_EPOCH).ok()).and_then(|d| u64::try_from(d.as_nanos()).ok());
+  Ok((meta.len(), modified))
+}
+
+/// Runs `hash`, which reads whole files, on tokio's blocking pool, so the
+/// async workers keep serving everything else meanwhile.
+pub(crate) async fn off_runtime<T: Send + 'static>(hash: impl FnOnce() -> Result<T, Error> + Send + 'static) -> Result<T, Error> {
+  tokio::task::spawn_blocking(hash).await.map_err(|e| Error::Storage(format!("Liczenie skrótu przerwane: {}", e)))?
+}
+
+/// The hash of `path` with the configured algorithm.
+pub fn hash(dir: &Path, path: &Path) -> Result<FileHash, Error> {
+  hash_with(dir, path, configured(dir))
+}
+
+/// The hash of `path` with `algorithm`: from the library when size and mtime
+/// still match, otherwise read, resuming a checkpoint, with `hash-progress`.
+pub fn hash_with(dir: &Path, path: &Path, algorithm: HashAlgorithm) -> Result<FileHash, Error> {
+  let (size, modified_ns) = stamp(path)?;
+  let key = path.to_string_lossy().into_owned();
+  let done = |hash: String, from_library, resumed_from| FileHash { path: key.clone(), algorithm, hash, size, modified_ns, from_library, resumed_from };
+  if let Some(hash) = library::known_hash(dir, &key, algorithm, size, modified_ns) {
+    return Ok(done(hash, true, 0));
+  }
+  let saved = read_progress(dir).remove(&key);
+  let had_checkpoint = saved.is_some();
+  let resumed = saved.filter(|p| algorithm == HashAlgorithm::Blake3 && p.size == size && p.modified_ns == modified_ns);
+  let (mut offset, mut hasher) = match resumed.and_then(|p| Some((p.offset, p.resume(size)?))) {
+    Some((offset, tree)) => (offset, Hasher::Blake3(tree)),
+    None if algorithm == HashAlgorithm::Blake3 => (0, Hasher::Blake3(Blake3Tree::new())),
+    None => (0, Hasher::Sha256(Sha256::new())),
+  };
+  let resumed_from = offset;
+  let mut f = File::open(path).map_err(|e| unreadable(path, e))?;
+  f.seek(SeekFrom::Start(offset)).map_err(|e| unreadable(path, e))?;
+  let mut buf = vec![0; READ_BLOCK];
+  let mut checkpoints = had_checkpoint;
+  loop {
+    // Reads stop at each subtree boundary, where a checkpoint can be made.
+    let want = (CHECKPOINT_BYTES - offset % CHECKPOINT_BYTES).min(READ_BLOCK as u64) as usize;
+    let n = f.read(&mut buf[..want]).map_err(|e| unreadable(path, e))?;
+    if n == 0 {
+      break;
+    }
+    hasher.update(&buf[..n]);
+    offset += n as u64;
+    events::progress(dir, &key, "hash-progress", HashProgress { path: key.clone(), algorithm, hashed_bytes: offset, total_bytes: size });
+    let Hasher::Blake3(tree) = &hasher else {
+      continue;
+    };
+    if let Some(stack) = tree.checkpoint().filter(|_| offset < size) {
+      // Losing a checkpoint only costs a rehash.
+      let chaining_values = stack.into_iter().map(|cv| blake3::Hash::from(cv).to_hex().to_string()).collect();
+      let partial = Partial { size, modified_ns, offset, chaining_values, updated_at: now_secs() };
+      checkpoints |= save_progress(dir, &key, Some(partial)).is_ok();
+    }
+  }
+  if checkpoints {
+    let _ = save_progress(dir, &key, None);
+  }
+  if offset != size {
+    return Err(Error::Storage(format!("{} zmienił się w trakcie liczenia skrótu", path.display())));
+  }
+  Ok(done(hasher.hex(), false, resumed_from))
+}
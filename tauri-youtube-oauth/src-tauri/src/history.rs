@@ -0,0 +1,237 @@
+use crate::{csv, error::Error, overrides::ForcedOverride, remote_state::Rejection, storage};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs::{self, OpenOptions},
+  io::Write,
+  path::Path,
+  sync::Mutex,
+};
+
+const HISTORY_FILE: &str = "upload_history.jsonl";
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Serializes appends from concurrent queue workers within the process.
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadStatus {
+  Completed,
+  Failed,
+  /// Went up, but YouTube rejected, failed to process or removed it; see
+  /// `rejection`.
+  Rejected,
+}
+
+impl UploadStatus {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      UploadStatus::Completed => "completed",
+      UploadStatus::Failed => "failed",
+      UploadStatus::Rejected => "rejected",
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadRecord {
+  /// Unix seconds when the upload finished.
+  pub timestamp: u64,
+  pub profile: String,
+  pub file_path: String,
+  pub file_hash: String,
+  pub video_id: Option<String>,
+  pub title: String,
+  pub privacy: String,
+  pub duration_secs: u64,
+  pub bytes: u64,
+  pub status: UploadStatus,
+  #[serde(default)]
+  pub error: Option<String>,
+  /// The file changed while it was uploading; the video may be truncated.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub suspect: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub rejection: Option<Rejection>,
+  /// Warnings the upload was forced past.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub forced: Option<ForcedOverride>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct HistoryFilter {
+  /// RFC3339 timestamp or `YYYY-MM-DD` (start of that day, UTC).
+  #[serde(default)]
+  pub from: Option<String>,
+  /// RFC3339 timestamp or `YYYY-MM-DD` (end of that day, UTC).
+  #[serde(default)]
+  pub to: Option<String>,
+  #[serde(default)]
+  pub status: Option<UploadStatus>,
+  /// Case-insensitive substring of the title.
+  #[serde(default)]
+  pub text: Option<String>,
+  #[serde(default)]
+  pub offset: usize,
+  #[serde(default)]
+  pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryPage {
+  /// Records matching the filter, before pagination.
+  pub total: usize,
+  pub offset: usize,
+  /// Newest first.
+  pub records: Vec<UploadRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+  Csv,
+  Json,
+}
+
+/// Appends one record. The line goes out in a single `write` on a file
+/// opened with O_APPEND, so concurrent writers never interleave partial lines.
+pub fn append(dir: &Path, record: &UploadRecord) -> Result<(), Error> {
+  let mut line = serde_json::to_string(record).map_err(|e| Error::Storage(e.to_string()))?;
+  line.push('\n');
+  let _lock = storage::lock(dir)?;
+  let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  fs::create_dir_all(dir).map_err(|e| Error::Storage(e.to_string()))?;
+  let mut f = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(dir.join(HISTORY_FILE))
+    .map_err(|e| Error::Storage(e.to_string()))?;
+  f.write_all(line.as_bytes()).map_err(|e| Error::Storage(e.to_string()))
+}
+
+/// Marks the completed uploads of `video_id` as rejected with the reason;
+/// returns how many changed. The file is rewritten in place, unreadable
+/// lines kept as they were.
+pub(crate) fn mark_rejected(dir: &Path, video_id: &str, rejection: &Rejection) -> Result<usize, Error> {
+  let path = dir.join(HISTORY_FILE);
+  let _lock = storage::lock(dir)?;
+  let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let text = match fs::read_to_string(&path) {
+    Ok(text) => text,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+    Err(e) => return Err(Error::Storage(e.to_string())),
+  };
+  let mut changed = 0;
+  let mut out = String::with_capacity(text.len());
+  for line in text.lines() {
+    match serde_json::from_str::<UploadRecord>(line) {
+      Ok(mut r)
+        if r.video_id.as_deref() == Some(video_id)
+          && (r.status == UploadStatus::Completed || r.rejection.as_ref().is_some_and(|was| was.kind != rejection.kind)) =>
+      {
+        r.status = UploadStatus::Rejected;
+        r.error = Some(rejection.describe(video_id));
+        r.rejection = Some(rejection.clone());
+        out.push_str(&serde_json::to_string(&r).map_err(|e| Error::Storage(e.to_string()))?);
+        changed += 1;
+      }
+      _ => out.push_str(line),
+    }
+    out.push('\n');
+  }
+  if changed > 0 {
+    storage::write_atomic(&path, out).map_err(|e| Error::Storage(e.to_string()))?;
+  }
+  Ok(changed)
+}
+
+/// All readable records in file order; a torn or hand-mangled line is
+/// skipped rather than hiding the rest of the history.
+pub fn read_all(dir: &Path) -> Vec<UploadRecord> {
+  fs::read_to_string(dir.join(HISTORY_FILE))
+    .unwrap_or_default()
+    .lines()
+    .filter_map(|l| serde_json::from_str(l).ok())
+    .collect()
+}
+
+/// Unix seconds of a filter bound: RFC3339, or `YYYY-MM-DD` at the start
+/// (or end) of that day, UTC.
+pub(crate) fn parse_bound(value: &str, end_of_day: bool) -> Result<u64, Error> {
+  if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+    return Ok(t.timestamp().max(0) as u64);
+  }
+  let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+    .map_err(|_| Error::Validation(format!("Nieprawidłowa data '{}' (oczekiwano RFC3339 lub RRRR-MM-DD)", value)))?;
+  let time = if end_of_day { date.and_hms_opt(23, 59, 59) } else { date.and_hms_opt(0, 0, 0) };
+  Ok(time.map(|t| t.and_utc().timestamp().max(0) as u64).unwrap_or_default())
+}
+
+pub fn query(dir: &Path, filter: &HistoryFilter) -> Result<HistoryPage, Error> {
+  let from = filter.from.as_deref().map(|v| parse_bound(v, false)).transpose()?;
+  let to = filter.to.as_deref().map(|v| parse_bound(v, true)).transpose()?;
+  let text = filter.text.as_deref().map(str::to_lowercase).filter(|t| !t.is_empty());
+  let mut matching: Vec<UploadRecord> = read_all(dir)
+    .into_iter()
+    .filter(|r| from.is_none_or(|f| r.timestamp >= f))
+    .filter(|r| to.is_none_or(|t| r.timestamp <= t))
+    .filter(|r| filter.status.is_none_or(|s| r.status == s))
+    .filter(|r| text.as_ref().is_none_or(|t| r.title.to_lowercase().contains(t)))
+    .collect();
+  matching.reverse();
+  let total = matching.len();
+  let limit = filter.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+  let records = matching.into_iter().skip(filter.offset).take(limit).collect();
+  Ok(HistoryPage { total, offset: filter.offset, records })
+}
+
+/// Uploads forced past warnings, newest first; with `since` (as a filter's
+/// `from`) only those from then on.
+pub fn overridden(dir: &Path, since: Option<&str>) -> Result<Vec<UploadRecord>, Error> {
+  let since = since.map(|v| parse_bound(v, false)).transpose()?;
+  let mut forced: Vec<UploadRecord> =
+    read_all(dir).into_iter().filter(|r| r.forced.as_ref().is_some_and(|f| since.is_none_or(|s| f.at >= s))).collect();
+  forced.reverse();
+  Ok(forced)
+}
+
+fn rfc3339(ts: u64) -> String {
+  DateTime::<Utc>::from_timestamp(ts as i64, 0).map(|t| t.to_rfc3339()).unwrap_or_default()
+}
+
+/// Writes the whole history to `path`. Returns the number of records.
+pub fn export(dir: &Path, path: &Path, format: ExportFormat) -> Result<usize, Error> {
+  let records = read_all(dir);
+  let out = match format {
+    ExportFormat::Json => serde_json::to_string_pretty(&records).map_err(|e| Error::Storage(e.to_string()))?,
+    ExportFormat::Csv => {
+      let mut out = String::new();
+      csv::write_row(
+        &mut out,
+        &["timestamp", "profile", "file_path", "file_hash", "video_id", "title", "privacy", "duration_secs", "bytes", "status", "error"],
+      );
+      for r in &records {
+        csv::write_row(
+          &mut out,
+          &[
+            rfc3339(r.timestamp),
+            r.profile.clone(),
+            r.file_path.clone(),
+            r.file_hash.clone(),
+            r.video_id.clone().unwrap_or_default(),
+            r.title.clone(),
+            r.privacy.clone(),
+            r.duration_secs.to_string(),
+            r.bytes.to_string(),
+            r.status.as_str().to_string(),
+            r.error.clone().unwrap_or_default(),
+          ],
+        );
+      }
+      out
+    }
+  };
+  fs::write(path, out).map_err(|e| Error::Storage(format!("Nie można zapisać {}: {}", path.display(), e)))?;
+  Ok(records.len())
+}
@@ -0,0 +1,201 @@
+//! Post-upload hooks: a webhook POST or a local command per configured entry,
+//! run once an uploaded video finished processing. Hooks only ever see the
+//! video's public metadata; a failing hook is reported, never fatal.
+
+use crate::{error::Error, read_config_from_dir, runtime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+  path::Path,
+  time::{Duration, Instant},
+};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Environment a command hook inherits; everything else (tokens, secrets
+/// passed via env) is dropped.
+const INHERITED_ENV: &[&str] = &["PATH", "HOME", "USER", "LANG", "TMPDIR", "TEMP", "TMP", "SystemRoot", "USERPROFILE"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Hook {
+  /// POSTs JSON to `url`: `template` with `{{video_id}}`, `{{title}}`,
+  /// `{{url}}` and `{{profile}}` filled in, or all four fields when absent.
+  Webhook {
+    url: String,
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+  },
+  /// Runs `program` with `args` as given (no shell); metadata arrives in
+  /// `YTLITE_VIDEO_ID`, `YTLITE_TITLE`, `YTLITE_URL` and `YTLITE_PROFILE`.
+  Command {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+  },
+}
+
+impl Hook {
+  fn kind(&self) -> &'static str {
+    match self {
+      Hook::Webhook { .. } => "webhook",
+      Hook::Command { .. } => "command",
+    }
+  }
+
+  fn timeout(&self) -> Duration {
+    let (Hook::Webhook { timeout_secs, .. } | Hook::Command { timeout_secs, .. }) = self;
+    Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1))
+  }
+}
+
+/// What a hook learns about the upload.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HookContext {
+  pub video_id: String,
+  pub title: String,
+  pub url: String,
+  pub profile: String,
+}
+
+impl HookContext {
+  /// Stand-in data for `test_hook`.
+  pub fn sample(profile: &str) -> Self {
+    HookContext {
+      video_id: "TEST_VIDEO_ID".into(),
+      title: "Testowe wideo YTLite".into(),
+      url: "https://youtu.be/TEST_VIDEO_ID".into(),
+      profile: profile.to_string(),
+    }
+  }
+}
+
+/// Payload of `post-upload-hook`, one per hook run.
+#[derive(Serialize, Debug, Clone)]
+pub struct HookOutcome {
+  pub index: usize,
+  pub kind: String,
+  pub video_id: String,
+  pub ok: bool,
+  pub error: Option<String>,
+  pub duration_ms: u64,
+}
+
+pub fn validate(hook: &Hook) -> Result<(), Error> {
+  match hook {
+    Hook::Webhook { url, template, .. } => {
+      let parsed = url::Url::parse(url).map_err(|e| Error::Config(format!("Nieprawidłowy adres webhooka {}: {}", url, e)))?;
+      if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(Error::Config(format!("Webhook musi używać http(s): {}", url)));
+      }
+      if let Some(t) = template {
+        render(t, &HookContext::sample("default"))?;
+      }
+    }
+    Hook::Command { program, .. } if program.trim().is_empty() => {
+      return Err(Error::Config("Hook typu command wymaga pola program".into()));
+    }
+    Hook::Command { .. } => {}
+  }
+  Ok(())
+}
+
+/// Fills the template's placeholders with JSON-escaped values (so a quote in
+/// a title can't break out of its string) and checks the result is JSON.
+fn render(template: &str, ctx: &HookContext) -> Result<Value, Error> {
+  let escape = |s: &str| {
+    let quoted = serde_json::to_string(s).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+  };
+  let text = template
+    .replace("{{video_id}}", &escape(&ctx.video_id))
+    .replace("{{title}}", &escape(&ctx.title))
+    .replace("{{url}}", &escape(&ctx.url))
+    .replace("{{profile}}", &escape(&ctx.profile));
+  serde_json::from_str(&text).map_err(|e| Error::Config(format!("Szablon webhooka nie daje poprawnego JSON: {}", e)))
+}
+
+async fn run_webhook(url: &str, template: Option<&str>, ctx: &HookContext, timeout: Duration) -> Result<(), Error> {
+  let body = match template {
+    Some(t) => render(t, ctx)?,
+    None => serde_json::to_value(ctx).map_err(|e| Error::Storage(e.to_string()))?,
+  };
+  let resp = reqwest::Client::new()
+    .post(url)
+    .json(&body)
+    .timeout(timeout)
+    .send()
+    .await
+    .map_err(|e| crate::timeouts::transport_error(e, "post-upload webhook", timeout))?;
+  let status = resp.status();
+  if !status.is_success() {
+    let text = resp.text().await.unwrap_or_default();
+    return Err(Error::Api { status: status.as_u16(), reason: "webhookFailed".into(), message: text.chars().take(200).collect() });
+  }
+  Ok(())
+}
+
+async fn run_command(program: &str, args: &[String], ctx: &HookContext, timeout: Duration) -> Result<(), Error> {
+  let mut cmd = tokio::process::Command::new(program);
+  cmd.args(args).env_clear().kill_on_drop(true).stdin(std::process::Stdio::null());
+  for key in INHERITED_ENV {
+    if let Some(v) = std::env::var_os(key) {
+      cmd.env(key, v);
+    }
+  }
+  cmd
+    .env("YTLITE_VIDEO_ID", &ctx.video_id)
+    .env("YTLITE_TITLE", &ctx.title)
+    .env("YTLITE_URL", &ctx.url)
+    .env("YTLITE_PROFILE", &ctx.profile);
+  let output = tokio::time::timeout(timeout, cmd.output())
+    .await
+    .map_err(|_| Error::TimedOut { operation: format!("hook {}", program), after_secs: timeout.as_secs() })?
+    .map_err(|e| Error::Open { target: program.to_string(), message: e.to_string() })?;
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    return Err(Error::Validation(format!("{} zakończył się kodem {}: {}", program, output.status, stderr.trim())));
+  }
+  Ok(())
+}
+
+async fn run_one(dir: &Path, index: usize, hook: &Hook, ctx: &HookContext) -> HookOutcome {
+  let started = Instant::now();
+  let timeout = hook.timeout();
+  let result = match hook {
+    Hook::Webhook { url, template, .. } => run_webhook(url, template.as_deref(), ctx, timeout).await,
+    Hook::Command { program, args, .. } => run_command(program, args, ctx, timeout).await,
+  };
+  let outcome = HookOutcome {
+    index,
+    kind: hook.kind().to_string(),
+    video_id: ctx.video_id.clone(),
+    ok: result.is_ok(),
+    error: result.err().map(|e| e.to_string()),
+    duration_ms: started.elapsed().as_millis() as u64,
+  };
+  runtime::for_dir(dir).emit("post-upload-hook", &outcome);
+  outcome
+}
+
+/// Runs every configured hook in order.
+pub async fn run_all(dir: &Path, ctx: &HookContext) -> Vec<HookOutcome> {
+  let hooks = read_config_from_dir(dir).map(|c| c.post_upload_hooks).unwrap_or_default();
+  let mut outcomes = Vec::with_capacity(hooks.len());
+  for (i, hook) in hooks.iter().enumerate() {
+    outcomes.push(run_one(dir, i, hook, ctx).await);
+  }
+  outcomes
+}
+
+/// Fires hook `index` with [`HookContext::sample`] data.
+pub async fn test(dir: &Path, index: usize) -> Result<HookOutcome, Error> {
+  let hooks = read_config_from_dir(dir).map(|c| c.post_upload_hooks).unwrap_or_default();
+  let hook = hooks
+    .get(index)
+    .ok_or_else(|| Error::Validation(format!("Brak hooka o indeksie {} (skonfigurowano {})", index, hooks.len())))?;
+  Ok(run_one(dir, index, hook, &HookContext::sample(&crate::profiles::name_of(dir))).await)
+}
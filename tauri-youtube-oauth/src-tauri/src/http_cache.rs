@@ -0,0 +1,79 @@
+use crate::{error::Error, now_secs, storage};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, fs, path::Path, sync::Mutex};
+
+const CACHE_FILE: &str = "http_cache.json";
+const MAX_ENTRIES: usize = 200;
+
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Last good response of one GET, revalidated with `If-None-Match` while
+/// online and served as-is while offline.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Entry {
+  pub etag: Option<String>,
+  pub fetched_at: u64,
+  pub body: Value,
+}
+
+fn load(dir: &Path) -> HashMap<String, Entry> {
+  fs::read_to_string(dir.join(CACHE_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+pub(crate) fn get(dir: &Path, key: &str) -> Option<Entry> {
+  let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  load(dir).remove(key)
+}
+
+pub(crate) fn put(dir: &Path, key: &str, entry: Entry) -> Result<(), Error> {
+  let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut entries = load(dir);
+  entries.insert(key.to_string(), entry);
+  if entries.len() > MAX_ENTRIES {
+    let mut by_age: Vec<(u64, String)> = entries.iter().map(|(k, e)| (e.fetched_at, k.clone())).collect();
+    by_age.sort();
+    for (_, k) in by_age.into_iter().take(entries.len() - MAX_ENTRIES) {
+      entries.remove(&k);
+    }
+  }
+  let s = serde_json::to_string(&entries).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_atomic(&dir.join(CACHE_FILE), s).map_err(|e| Error::Storage(e.to_string()))
+}
+
+/// Drops entries fetched before `before` (Unix seconds); with `dry_run` only
+/// reports them. Returns their keys and the bytes the file shrinks by.
+pub(crate) fn prune(dir: &Path, before: u64, dry_run: bool) -> Result<(Vec<String>, u64), Error> {
+  let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut entries = load(dir);
+  let mut stale: Vec<String> = entries.iter().filter(|(_, e)| e.fetched_at < before).map(|(k, _)| k.clone()).collect();
+  if stale.is_empty() {
+    return Ok((stale, 0));
+  }
+  stale.sort();
+  let size = |e: &HashMap<String, Entry>| serde_json::to_string(e).map(|s| s.len() as u64).map_err(|e| Error::Storage(e.to_string()));
+  let old_size = size(&entries)?;
+  entries.retain(|k, _| !stale.contains(k));
+  let new_size = size(&entries)?;
+  if !dry_run {
+    let s = serde_json::to_string(&entries).map_err(|e| Error::Storage(e.to_string()))?;
+    storage::write_atomic(&dir.join(CACHE_FILE), s).map_err(|e| Error::Storage(e.to_string()))?;
+  }
+  Ok((stale, old_size.saturating_sub(new_size)))
+}
+
+pub(crate) fn touch(dir: &Path, key: &str, mut entry: Entry) -> Entry {
+  entry.fetched_at = now_secs();
+  let _ = put(dir, key, entry.clone());
+  entry
+}
+
+/// The cached body marked with `from_cache` and `fetched_at`, for offline use.
+pub(crate) fn tagged(entry: Entry) -> Value {
+  let mut body = entry.body;
+  if let Some(obj) = body.as_object_mut() {
+    obj.insert("from_cache".into(), json!(true));
+    obj.insert("fetched_at".into(), json!(entry.fetched_at));
+  }
+  body
+}
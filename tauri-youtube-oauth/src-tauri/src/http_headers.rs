@@ -0,0 +1,48 @@
+//! Headers added to every request to Google: the `custom_headers` from the
+//! config (an egress proxy that wants `X-Request-Source`, say) and
+//! `X-Client-Trace-Id`, one per logical operation, so a slow upload can be
+//! found in the proxy's logs. The id is also kept with captured exchanges
+//! and the slowest request of each class in `get_metrics`. Requests get them
+//! from [`crate::tls::request`].
+
+use crate::{error::Error, read_config_from_dir};
+use std::{collections::HashMap, path::Path};
+
+pub const TRACE_HEADER: &str = "X-Client-Trace-Id";
+
+/// Headers `custom_headers` may not set: the request itself depends on them.
+pub const RESERVED: &[&str] = &["authorization", "host", "content-length", "content-range", "x-client-trace-id"];
+
+/// A new trace id: 32 hex digits.
+pub fn trace_id() -> String {
+  use rand_core::RngCore;
+  format!("{:016x}{:016x}", rand_core::OsRng.next_u64(), rand_core::OsRng.next_u64())
+}
+
+pub fn validate(headers: &HashMap<String, String>) -> Result<(), Error> {
+  for (name, value) in headers {
+    if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+      return Err(Error::Config(format!("Nieprawidłowa nazwa nagłówka: {:?}", name)));
+    }
+    if RESERVED.contains(&name.to_ascii_lowercase().as_str()) {
+      return Err(Error::Config(format!("Nagłówka {} nie można nadpisać", name)));
+    }
+    let visible = value.bytes().all(|b| b == b' ' || b.is_ascii_graphic());
+    if value.trim().is_empty() || !visible {
+      return Err(Error::Config(format!("Wartość nagłówka {} musi być niepustym, widocznym tekstem ASCII", name)));
+    }
+  }
+  Ok(())
+}
+
+/// `req` with the configured headers and `trace_id`. Headers that fail
+/// [`validate`] (a config edited by hand) are left out.
+pub(crate) fn apply(dir: &Path, mut req: reqwest::RequestBuilder, trace_id: &str) -> reqwest::RequestBuilder {
+  let headers = read_config_from_dir(dir).map(|c| c.custom_headers).unwrap_or_default();
+  for (name, value) in &headers {
+    if validate(&HashMap::from([(name.clone(), value.clone())])).is_ok() {
+      req = req.header(name.as_str(), value.as_str());
+    }
+  }
+  req.header(TRACE_HEADER, trace_id)
+}
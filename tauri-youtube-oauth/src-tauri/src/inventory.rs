@@ -0,0 +1,253 @@
+//! Snapshot of every upload's metadata to CSV or JSON, as a backup before
+//! batch edits. Rows go to `<path>.partial.jsonl` as batches arrive, so an
+//! interrupted export picks up where it stopped when run again with the same
+//! output path; the final file is written only once everything is fetched.
+//! With a `page_budget` a run stops after that many listing pages and hands
+//! back a cursor, and the next run continues the listing from there.
+
+use crate::{
+  api::{self, ApiCall},
+  channel, csv,
+  cursor::PageRequest,
+  error::Error,
+  events,
+  history::ExportFormat,
+  quota, storage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+  collections::HashSet,
+  fs::{self, OpenOptions},
+  io::Write,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+/// `videos.list` takes at most this many ids per call.
+const BATCH: usize = 50;
+/// Overall bound on walking the uploads playlist.
+const LIST_DEADLINE: Duration = Duration::from_secs(30 * 60);
+/// One export runs at a time, so its progress needs no finer key.
+const PROGRESS_KEY: &str = "inventory";
+const CSV_HEADER: [&str; 12] =
+  ["video_id", "title", "description", "tags", "category_id", "privacy", "publish_at", "published_at", "duration_secs", "views", "likes", "comments"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InventoryRow {
+  pub video_id: String,
+  pub title: String,
+  pub description: String,
+  pub tags: Vec<String>,
+  pub category_id: Option<String>,
+  pub privacy: String,
+  pub publish_at: Option<String>,
+  pub published_at: Option<String>,
+  pub duration_secs: Option<u64>,
+  pub views: Option<u64>,
+  pub likes: Option<u64>,
+  pub comments: Option<u64>,
+}
+
+/// What a run will cost, returned instead of exporting without `confirm`.
+#[derive(Serialize, Debug, Clone)]
+pub struct InventoryEstimate {
+  /// From the channel statistics; may lag behind recent uploads.
+  pub video_count: u64,
+  /// Rows already in the partial file from an interrupted run.
+  pub already_exported: usize,
+  pub quota_units: u32,
+  pub quota_remaining: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum InventoryResult {
+  Estimate(InventoryEstimate),
+  Exported { path: String, rows: usize, resumed_rows: usize },
+  /// The page budget ran out; rows so far are in the partial file.
+  Partial { path: String, rows: usize, next_cursor: String },
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InventoryPhase {
+  Listing,
+  Details,
+}
+
+/// Payload of `inventory-progress`.
+#[derive(Serialize, Debug, Clone)]
+pub struct InventoryProgress {
+  pub phase: InventoryPhase,
+  pub done: usize,
+  pub total: Option<u64>,
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(".partial.jsonl");
+  path.with_file_name(name)
+}
+
+fn read_partial(path: &Path) -> Vec<InventoryRow> {
+  // A line cut off by the interruption is dropped and fetched again.
+  fs::read_to_string(partial_path(path)).map(|s| s.lines().filter_map(|l| serde_json::from_str(l).ok()).collect()).unwrap_or_default()
+}
+
+fn append_partial(path: &Path, rows: &[InventoryRow]) -> Result<(), Error> {
+  let mut out = String::new();
+  for row in rows {
+    out.push_str(&serde_json::to_string(row).map_err(|e| Error::Storage(e.to_string()))?);
+    out.push('\n');
+  }
+  let mut file = OpenOptions::new().create(true).append(true).open(partial_path(path)).map_err(|e| Error::Storage(e.to_string()))?;
+  file.write_all(out.as_bytes()).map_err(|e| Error::Storage(e.to_string()))
+}
+
+/// Seconds in an ISO 8601 duration as `contentDetails.duration` gives it,
+/// e.g. `PT1H2M3S` or `P1DT4M`.
+pub fn iso_duration_secs(s: &str) -> Option<u64> {
+  let rest = s.strip_prefix('P')?;
+  let (mut total, mut number, mut in_time) = (0u64, String::new(), false);
+  for c in rest.chars() {
+    match c {
+      'T' => in_time = true,
+      '0'..='9' => number.push(c),
+      _ => {
+        let n: u64 = std::mem::take(&mut number).parse().ok()?;
+        total += n * match (c, in_time) {
+          ('W', false) => 7 * 86_400,
+          ('D', false) => 86_400,
+          ('H', true) => 3600,
+          ('M', true) => 60,
+          ('S', true) => 1,
+          _ => return None,
+        };
+      }
+    }
+  }
+  number.is_empty().then_some(total)
+}
+
+fn count(v: &Value) -> Option<u64> {
+  v.as_str().and_then(|s| s.parse().ok()).or_else(|| v.as_u64())
+}
+
+fn row_from_resource(v: &Value) -> Option<InventoryRow> {
+  let text = |p: &str| v.pointer(p).and_then(Value::as_str).map(str::to_string);
+  Some(InventoryRow {
+    video_id: v["id"].as_str()?.to_string(),
+    title: text("/snippet/title").unwrap_or_default(),
+    description: text("/snippet/description").unwrap_or_default(),
+    tags: v["snippet"]["tags"].as_array().map(|t| t.iter().filter_map(|t| t.as_str().map(str::to_string)).collect()).unwrap_or_default(),
+    category_id: text("/snippet/categoryId"),
+    privacy: text("/status/privacyStatus").unwrap_or_default(),
+    publish_at: text("/status/publishAt"),
+    published_at: text("/snippet/publishedAt"),
+    duration_secs: text("/contentDetails/duration").and_then(|d| iso_duration_secs(&d)),
+    views: count(&v["statistics"]["viewCount"]),
+    likes: count(&v["statistics"]["likeCount"]),
+    comments: count(&v["statistics"]["commentCount"]),
+  })
+}
+
+pub fn render(rows: &[InventoryRow], format: ExportFormat) -> Result<String, Error> {
+  match format {
+    ExportFormat::Json => serde_json::to_string_pretty(rows).map_err(|e| Error::Storage(e.to_string())),
+    ExportFormat::Csv => {
+      let mut out = String::new();
+      csv::write_row(&mut out, &CSV_HEADER);
+      let opt = |n: Option<u64>| n.map(|n| n.to_string()).unwrap_or_default();
+      for r in rows {
+        csv::write_row(
+          &mut out,
+          &[
+            r.video_id.clone(),
+            r.title.clone(),
+            r.description.clone(),
+            r.tags.join(","),
+            r.category_id.clone().unwrap_or_default(),
+            r.privacy.clone(),
+            r.publish_at.clone().unwrap_or_default(),
+            r.published_at.clone().unwrap_or_default(),
+            opt(r.duration_secs),
+            opt(r.views),
+            opt(r.likes),
+            opt(r.comments),
+          ],
+        );
+      }
+      Ok(out)
+    }
+  }
+}
+
+/// One `playlistItems.list` and one `videos.list` unit per 50 videos, plus
+/// the `channels.list` that found the uploads playlist.
+fn estimate_units(video_count: u64, already_exported: usize) -> u32 {
+  let pages = |n: u64| n.div_ceil(BATCH as u64) as u32;
+  1 + pages(video_count).max(1) + pages(video_count.saturating_sub(already_exported as u64))
+}
+
+/// Exports every upload of the channel to `path`, or the stretch `paging`
+/// allows. Without `confirm` only the quota estimate is returned.
+pub async fn export_channel_inventory(
+  dir: &Path,
+  path: &Path,
+  format: ExportFormat,
+  confirm: bool,
+  paging: &PageRequest,
+) -> Result<InventoryResult, Error> {
+  let channel = channel::list_channels(dir).await?.into_iter().next().ok_or_else(|| Error::Validation("Brak kanału".into()))?;
+  let uploads = channel.uploads_playlist_id.ok_or_else(|| Error::Validation("Kanał nie ma playlisty przesłanych filmów".into()))?;
+  let mut rows = read_partial(path);
+  let resumed_rows = rows.len();
+  if !confirm {
+    let video_count = channel.statistics.and_then(|s| s.video_count).unwrap_or_default();
+    return Ok(InventoryResult::Estimate(InventoryEstimate {
+      video_count,
+      already_exported: resumed_rows,
+      quota_units: estimate_units(video_count, resumed_rows),
+      quota_remaining: quota::status(dir).remaining,
+    }));
+  }
+
+  let call = ApiCall::get("/youtube/v3/playlistItems").query("part", "contentDetails").query("playlistId", uploads.as_str());
+  let on_page = |done, total| {
+    events::progress(dir, PROGRESS_KEY, "inventory-progress", InventoryProgress { phase: InventoryPhase::Listing, done, total })
+  };
+  let page = api::paginate_page(dir, call, paging, usize::MAX, LIST_DEADLINE, on_page).await?;
+  let listed = page.items;
+  let done: HashSet<String> = rows.iter().map(|r| r.video_id.clone()).collect();
+  let mut seen = HashSet::new();
+  let todo: Vec<String> = listed
+    .iter()
+    .filter_map(|i| i["contentDetails"]["videoId"].as_str())
+    .filter(|id| !done.contains(*id) && seen.insert(id.to_string()))
+    .map(str::to_string)
+    .collect();
+
+  let total = match &page.next_cursor {
+    Some(_) => page.total,
+    None => Some((done.len() + todo.len()) as u64),
+  };
+  for batch in todo.chunks(BATCH) {
+    let call = ApiCall::get("/youtube/v3/videos").query("part", "snippet,status,statistics,contentDetails").query("id", batch.join(","));
+    let resp = api::execute(dir, call).await?;
+    // Keep playlist order; ids the API no longer knows (deleted videos) drop out.
+    let items = resp["items"].as_array().cloned().unwrap_or_default();
+    let fetched: Vec<InventoryRow> =
+      batch.iter().filter_map(|id| items.iter().find(|v| v["id"].as_str() == Some(id)).and_then(row_from_resource)).collect();
+    append_partial(path, &fetched)?;
+    rows.extend(fetched);
+    events::progress(dir, PROGRESS_KEY, "inventory-progress", InventoryProgress { phase: InventoryPhase::Details, done: rows.len(), total });
+  }
+
+  if let Some(next_cursor) = page.next_cursor {
+    return Ok(InventoryResult::Partial { path: path.to_string_lossy().into_owned(), rows: rows.len(), next_cursor });
+  }
+  storage::write_atomic(path, render(&rows, format)?).map_err(|e| Error::Storage(e.to_string()))?;
+  let _ = fs::remove_file(partial_path(path));
+  Ok(InventoryResult::Exported { path: path.to_string_lossy().into_owned(), rows: rows.len(), resumed_rows })
+}
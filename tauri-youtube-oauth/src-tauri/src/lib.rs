@@ -1,9 +1,175 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+#[cfg(all(feature = "chaos", not(debug_assertions)))]
+compile_error!("The `chaos` feature injects failures into every request and is for debug builds only");
+
+pub mod adc;
+pub mod api;
+pub mod archive;
+pub mod audit;
+pub mod auth;
+pub mod auth_health;
+#[cfg(feature = "bindings")]
+pub mod bindings;
+pub mod browser;
+pub mod bulk;
+pub mod callback;
+pub mod capabilities;
+pub mod capture;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod channel;
+pub mod chapters;
+pub mod cleanup;
+pub mod ci_token;
+pub mod cli;
+mod commands;
+pub mod comments;
+pub mod config;
+pub mod connectivity;
+pub mod consent_probe;
+pub mod crash;
+mod csv;
+pub mod cursor;
+pub mod diagnostics;
+pub mod env_drift;
+pub mod enrichment;
+pub mod env_file;
+pub mod error;
+pub mod events;
+pub mod forbidden;
+pub mod format;
+pub mod guidance;
+pub mod hashing;
+pub mod history;
+pub mod hooks;
+pub mod http_headers;
+pub mod inventory;
+mod http_cache;
+pub mod library;
+pub mod lint;
+pub mod live;
+pub mod localizations;
+pub mod metadata;
+pub mod metrics;
+pub mod migration;
+#[cfg(feature = "metrics-endpoint")]
+pub mod metrics_endpoint;
+pub mod notifications;
+pub mod oauth_client;
+mod observe;
+pub mod overrides;
+pub mod passphrase;
+pub mod playlists;
+pub mod preflight;
+pub mod privacy;
+pub mod profiles;
+pub mod progress;
+pub mod publish_time;
+pub mod queue;
+pub mod quota;
+pub mod raw_api;
+pub mod reconsent;
+pub mod redact;
+pub mod remote_state;
+mod refresh;
+pub mod rotation;
+mod runtime;
+pub mod scopes;
+pub mod schedule;
+pub mod search;
+pub mod sections;
+pub mod setup;
+pub mod shutdown;
+pub mod sidecar;
+pub mod sinks;
+pub mod snapshots;
+pub mod status_file;
+pub mod storage;
+pub mod thumbnails;
+pub mod timeouts;
+pub mod tls;
+pub mod token_age;
+pub mod token_response;
+pub mod tokens_file;
+pub mod upload;
+pub mod video_links;
+pub mod videos;
+
 use serde::{Deserialize, Serialize};
-use std::{fs, path::{Path, PathBuf}, process::Command, time::{SystemTime, UNIX_EPOCH}};
-use tauri::AppHandle;
-use warp::Filter;
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, time::{Instant, SystemTime, UNIX_EPOCH}};
+use error::Error;
+use observe::Exchange;
+use timeouts::OperationClass;
+use tauri::{AppHandle, Manager};
+
+pub use api::{DryRunPlan, Privacy};
+pub use archive::{ArchiveFilter, ArchiveIndex, ArchivePhase, ArchiveProgress, ArchivedCaption, ArchivedVideo, ChannelArchive, VideoArchive};
+pub use audit::AuditEntry;
+pub use auth::{AuthStatus, LoginResult};
+pub use auth_health::{AuthHealth, HealthState};
+pub use browser::{BrowserLaunch, Platform, Sandbox};
+pub use bulk::{PlanOptions, PlanProblem, PlanReport, PlanRow, RowOutcome, RowStatus};
+pub use callback::{ActiveFlow, FlowState};
+pub use capabilities::{Capabilities, Capability, CapabilityInputs, ReasonCode};
+pub use capture::{CaptureStatus, HttpExchange};
+pub use commands::*;
+pub use comments::{FirstCommentPosted, PostedComment};
+pub use chapters::{Chapter, ChapterBlock, ChapterPlacement};
+pub use ci_token::CiToken;
+pub use cleanup::{CategoryReport, CleanupCategory, CleanupOptions, CleanupReport};
+pub use channel::{Channel, ChannelStatistics, OffsetType, Thumbnail, WatermarkTiming};
+pub use config::ConfigChange;
+pub use connectivity::Connectivity;
+pub use crash::CrashReport;
+pub use cursor::PageRequest;
+pub use diagnostics::{Check, CheckStatus, DiagnosticsReport};
+pub use enrichment::{EntryEnriched, Enrichment};
+pub use env_drift::{EnvDrift, EnvSyncResult};
+pub use env_file::{EnvChannel, EnvFileResult, EnvFormat, EnvTokens};
+pub use format::{Language, ProgressLabels};
+pub use guidance::{ConsentGuidance, GuidanceSource, WorkspaceReason, WorkspaceRestriction};
+pub use hashing::{FileHash, HashAlgorithm, HashProgress};
+pub use hooks::{Hook, HookContext, HookOutcome};
+pub use history::{ExportFormat, HistoryFilter, HistoryPage, UploadRecord, UploadStatus};
+pub use inventory::{InventoryEstimate, InventoryResult, InventoryRow};
+pub use library::{LibraryEntry, LibraryMatch, LibraryProgress, ReconcileReport, ReconciledFile};
+pub use lint::{LintConfig, LintMode, LintRule, LintWarning, MetadataLint};
+pub use live::{Broadcast, BroadcastOptions, BroadcastStatus, LiveStream, StreamResolution};
+pub use localizations::{Localization, LocalizationSet};
+pub use metadata::{MetadataPreview, MetadataTemplate};
+pub use migration::{MigrationAvailable, MigrationReport, MigrationSource};
+pub use metrics::{ClassMetrics, MetricsEndpointConfig, MetricsSnapshot, Outcomes, SlowRequest};
+pub use notifications::{Notice, NoticeKind, NotificationTest};
+pub use oauth_client::{ClientAssociation, ClientMismatch, ClientRef, MismatchKind, OAuthClientDescription};
+pub use overrides::{ForceCode, ForcedOverride};
+pub use playlists::{DedupReport, PlaylistInsert, PlaylistItem, PlaylistItemsPage};
+pub use progress::UploadProgress;
+pub use publish_time::PublishTime;
+pub use preflight::PreflightReason;
+pub use profiles::ProfileSwitched;
+pub use privacy::{BulkPrivacyResult, ChangeAction, PrivacyChange, PrivacyOptions, PrivacyPlan, PrivacyReport};
+pub use queue::{EntryBlocked, EntryFailed, EntryPriority, EntryRetried, EntryStatus, QueueAttempt, QueueEntry, UploadPlan};
+pub use quota::QuotaStatus;
+pub use raw_api::RawResponse;
+pub use reconsent::ReconsentRequired;
+pub use remote_state::{Rejection, RejectionKind, RemoteStateSummary, RemoteVideo, VerifyOptions, VerifyReport};
+pub use schedule::{ScheduleOptions, ScheduledEntry};
+pub use search::{SearchId, SearchOptions, SearchOrder, SearchResult, SearchType};
+pub use sections::{ChannelSection, SectionInput, SectionType};
+pub use setup::{SetupProblem, SetupState, SetupStep};
+pub use sidecar::{Severity, SidecarDiagnostic};
+pub use sinks::EventSink;
+pub use snapshots::{Snapshot, SnapshotInfo, SnapshotReason};
+pub use status_file::{ActiveUpload, LastError, QueueSummary, StatusFileConfig, StatusSnapshot, STATUS_SCHEMA_JSON};
+pub use storage::{StorageLocation, StorageMode};
+pub use thumbnails::ThumbnailRetry;
+pub use token_age::{ConsentStatus, TokenAge};
+pub use token_response::{TokenEndpointError, TokenGrant, TokenReply};
+pub use tokens_file::TOKENS_SCHEMA_JSON;
+pub use upload::{ChunkSizer, UploadedVideo, VideoMetadata};
+pub use video_links::{LinkAction, LinkMode, LinkReport, LinkStatus, VideoLink, VideoLinksConfig, DEFAULT_PLACEHOLDER};
+pub use videos::{MyVideo, VideoPage};
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Tokens {
@@ -13,64 +179,359 @@ pub struct Tokens {
   pub expires_in: u64,
   #[serde(default)]
   pub created_at: u64,
+  /// Space-separated scopes granted with these tokens, as returned by the token endpoint.
+  #[serde(default)]
+  pub scope: String,
+  /// OAuth client the tokens were issued to; absent in files from older versions.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub client_id: Option<String>,
+  /// Unix seconds of the code exchange that issued the refresh token; kept
+  /// across refreshes. Absent in files from older versions.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub issued_at: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct AppConfig {
   pub client_id: String,
   pub client_secret: String,
+  /// Scopes requested on login; empty means read-only access.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub scopes: Vec<String>,
+  /// Google Workspace domain sent as `hd` on login, so the consent screen
+  /// offers that domain's accounts.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub hosted_domain: Option<String>,
+  /// Channel the channel-level commands act on; filled from `mine=true` on first use.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub channel_id: Option<String>,
+  /// Daily Data API quota of the Cloud project, if raised above the default 10 000.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub daily_quota: Option<u32>,
+  /// Plan mutating API calls instead of sending them (see `set_dry_run`).
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub dry_run: bool,
+  /// Refuse every call that would change the channel (see `set_read_only_mode`).
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub read_only_mode: bool,
+  /// Lets the frontend send its own requests to Google APIs with the stored
+  /// tokens (`youtube_raw_request`).
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub allow_raw_api: bool,
+  /// Quota units of raw requests by path, e.g. `"/youtube/v3/captions": 400`;
+  /// other paths cost 1 to read and 50 to write.
+  #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+  pub raw_api_costs: std::collections::BTreeMap<String, u32>,
+  /// Token endpoint request timeout (default 30 s).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub auth_timeout_secs: Option<u64>,
+  /// Data API request timeout (default 60 s).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub api_timeout_secs: Option<u64>,
+  /// Timeout of a single media upload request (default 600 s).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub upload_chunk_timeout_secs: Option<u64>,
+  /// Fixed upload chunk size in bytes, a multiple of 256 KiB; absent means
+  /// adaptive (starts at 8 MiB, grows on fast chunks, shrinks on failures).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub upload_chunk_bytes: Option<u64>,
+  /// Files modified more recently than this are refused unless forced
+  /// (default 30 s), so an upload doesn't race the renderer.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub upload_min_file_age_secs: Option<u64>,
+  /// How long a thumbnail YouTube isn't ready for yet is retried (default 600 s).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub thumbnail_retry_window_secs: Option<u64>,
+  /// Delete the uploaded video when the file changed during the upload.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub strict_integrity: bool,
+  /// Webhooks and local commands run after each upload finished processing.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub post_upload_hooks: Vec<hooks::Hook>,
+  /// Title/description generated from the filename when a sidecar has no title.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub metadata_template: Option<metadata::MetadataTemplate>,
+  /// Title/description checks run before each upload; absent means all rules, warning only.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub metadata_lint: Option<lint::LintConfig>,
+  /// Where chapters from a `chapters.json` sidecar go in the description;
+  /// absent means the sidecar is ignored on upload.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub chapters_placement: Option<chapters::ChapterPlacement>,
+  /// Whether the OAuth consent screen is published; absent means unknown,
+  /// which enables the 7-day Testing-mode warnings.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub consent_screen_published: Option<bool>,
+  /// Argon2 hash of the app passphrase guarding secret-revealing commands;
+  /// only the `default` profile's counts (see [`passphrase`]).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub passphrase_hash: Option<String>,
+  /// Language of the display strings from `format` (`pl` when absent).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub language: Option<format::Language>,
+  /// Page the browser is sent to (302) after a successful login instead of
+  /// the built-in one; gets `?profile=<name>` appended.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub callback_success_redirect: Option<String>,
+  /// CMS content owner the channel is managed by; when set every write is
+  /// sent with `onBehalfOfContentOwner` and needs the youtubepartner scope.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub content_owner_id: Option<String>,
+  /// Channel of that owner the writes act on (`onBehalfOfContentOwnerChannel`).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub content_owner_channel_id: Option<String>,
+  /// A deployed `.env` compared with this config after every setup change;
+  /// `env-drift` reports when it is out of date.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub watched_env_file: Option<String>,
+  /// Program name or path the consent screen is opened with instead of the
+  /// platform opener, e.g. the browser signed in to the channel's account.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub preferred_browser: Option<String>,
+  /// What happens to links to videos viewers can't open yet (private,
+  /// scheduled later) in descriptions sent; absent means they aren't checked.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub video_links: Option<video_links::VideoLinksConfig>,
+  /// Desktop notifications about the upload queue (see `notifications`).
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub desktop_notifications: bool,
+  /// Sidecar warnings (unknown keys) stop the upload like errors do.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub sidecar_warnings_block: bool,
+  /// IANA zone a `publish_at` without an offset is read in, e.g.
+  /// `Europe/Warsaw`; UTC without it.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub publish_timezone: Option<String>,
+  /// Local hour a date-only `publish_at` goes public at (default 9).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub default_publish_hour: Option<u32>,
+  /// `defaultLanguage` given to a playlist or the channel that has none
+  /// when localizations are added, e.g. `pl`; the app `language` when absent.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub default_metadata_language: Option<String>,
+  /// Loopback port of the OAuth redirect, 14321 when absent. Must be in the
+  /// client's Authorized redirect URIs; a login in progress keeps its port.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub redirect_port: Option<u16>,
+  /// Where a build with the `metrics-endpoint` feature serves Prometheus
+  /// `/metrics`; nothing is served when absent.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub metrics_endpoint: Option<metrics::MetricsEndpointConfig>,
+  /// Where every emitted event is also sent (JSONL file, webhook), for runs
+  /// without a window.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub event_sinks: Vec<sinks::EventSink>,
+  /// Headers added to every request to Google, e.g. `X-Request-Source` for
+  /// an egress proxy; see [`http_headers::RESERVED`] for what they can't set.
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub custom_headers: HashMap<String, String>,
+  /// Failed uploads before a queue entry stops and waits for an explicit
+  /// retry (default 3).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub queue_max_attempts: Option<u32>,
+  /// `false` keeps the access token in memory only: tokens.json holds the
+  /// refresh token, scopes and timestamps, and each start refreshes first.
+  /// Absent means `true`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub persist_access_token: Option<bool>,
+  /// What the library and upload sessions hash files with (default blake3).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub hash_algorithm: Option<hashing::HashAlgorithm>,
+  /// Token endpoint and tokeninfo requests trust Google's root CAs from the
+  /// bundled webpki-roots only, never the OS store, so they fail rather
+  /// than go through a TLS-intercepting proxy.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub pin_google_tls: bool,
+  /// A status.json snapshot of auth, queue, uploads, quota and the last
+  /// error for orchestrators that poll; nothing is written when absent.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub status_file: Option<status_file::StatusFileConfig>,
+  /// A daily background check that the saved login still works, shown as
+  /// `health` in the auth status. Absent means `true`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub auth_health_check: Option<bool>,
 }
 
-fn now_secs() -> u64 {
-  SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+/// Loopback port the OAuth redirect lands on unless `redirect_port` says otherwise.
+pub(crate) const CALLBACK_PORT: u16 = 14321;
+
+/// A clock set before 1970 reads as the epoch instead of panicking.
+pub(crate) fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
 }
 
-fn app_config_dir(app: &AppHandle) -> Result<PathBuf, String> {
-  app
-    .path_resolver()
-    .app_config_dir()
-    .ok_or_else(|| "no app config dir".to_string())
+pub(crate) fn storage_location(app: &AppHandle) -> Result<StorageLocation, Error> {
+  let overridden = app.try_state::<storage::ConfigDirOverride>();
+  storage::current(overridden.as_ref().map(|o| o.0.as_path()), || app.path_resolver().app_config_dir())
+}
+
+/// The one place the config dir is decided: the active profile's dir under
+/// the storage location. Every file the app keeps lives under it, so
+/// portable mode can't leak writes into AppData.
+pub(crate) fn app_config_dir(app: &AppHandle) -> Result<PathBuf, Error> {
+  let dir = profiles::active_dir(&storage_location(app)?.path);
+  // Library events for this dir go to the app's windows.
+  let handle = app.clone();
+  runtime::for_dir(&dir).attach_emitter(move |event, payload| {
+    let _ = handle.emit_all(event, payload);
+  });
+  let identifier = app.config().tauri.bundle.identifier.clone();
+  notifications::attach(&dir, move |n| {
+    tauri::api::notification::Notification::new(&identifier).title(&n.title).body(&n.body).show().map_err(|e| e.to_string())
+  });
+  config::remember(&dir);
+  Ok(dir)
 }
 
 fn config_path(app: &AppHandle) -> Result<PathBuf, String> { Ok(app_config_dir(app)?.join("oauth_config.json")) }
 fn tokens_path(app: &AppHandle) -> Result<PathBuf, String> { Ok(app_config_dir(app)?.join("tokens.json")) }
 
-fn read_config_from_dir(dir: &Path) -> Option<AppConfig> {
+pub(crate) fn read_config_from_dir(dir: &Path) -> Option<AppConfig> {
   let p = dir.join("oauth_config.json");
   let s = fs::read_to_string(p).ok()?;
   serde_json::from_str(&s).ok()
 }
 
-fn write_config_to_dir(dir: &Path, cfg: &AppConfig) -> Result<(), String> {
-  fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+/// The one save path for oauth_config.json: a config [`config::validate`]
+/// refuses is never written, whoever is saving it.
+pub(crate) fn write_config_to_dir(dir: &Path, cfg: &AppConfig) -> Result<(), Error> {
+  config::validate(cfg)?;
+  let _lock = storage::lock(dir)?;
   let p = dir.join("oauth_config.json");
-  let s = serde_json::to_string_pretty(cfg).map_err(|e| e.to_string())?;
-  fs::write(p, s).map_err(|e| e.to_string())
+  let s = serde_json::to_string_pretty(cfg).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_secret(&p, s).map_err(|e| Error::Storage(e.to_string()))?;
+  capabilities::notify(dir);
+  Ok(())
+}
+
+/// Read-modify-write of the config under the dir lock, so a change another
+/// process saves in between isn't overwritten.
+pub(crate) fn update_config_in_dir<T>(dir: &Path, f: impl FnOnce(&mut AppConfig) -> T) -> Result<T, Error> {
+  let _lock = storage::lock(dir)?;
+  let mut cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  let out = f(&mut cfg);
+  write_config_to_dir(dir, &cfg)?;
+  Ok(out)
 }
 
-fn read_tokens_from_dir(dir: &Path) -> Option<Tokens> {
+/// tokens.json, with the access token held in memory when the file has
+/// none (`persist_access_token: false`). Without either, `access_token` is
+/// empty: the tokens are still there, they just need a refresh.
+pub fn read_tokens_from_dir(dir: &Path) -> Option<Tokens> {
   let p = dir.join("tokens.json");
   let s = fs::read_to_string(p).ok()?;
-  serde_json::from_str(&s).ok()
+  let mut t: Tokens = serde_json::from_str(&s).ok()?;
+  if t.access_token.is_empty() {
+    if let Some(held) = runtime::for_dir(dir).held_token().as_ref().filter(|h| h.refresh_token == t.refresh_token) {
+      t.access_token = held.access_token.clone();
+      t.created_at = held.created_at;
+      t.expires_in = held.expires_in;
+    }
+  }
+  Some(t)
 }
 
-fn write_tokens_to_dir(dir: &Path, t: &Tokens) -> Result<(), String> {
-  fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+/// Writes tokens.json; with `persist_access_token: false` the access token
+/// and its lifetime stay in memory instead.
+pub fn write_tokens_to_dir(dir: &Path, t: &Tokens) -> Result<(), Error> {
+  let persist = read_config_from_dir(dir).and_then(|c| c.persist_access_token).unwrap_or(true);
+  let lock = storage::lock(dir)?;
   let p = dir.join("tokens.json");
-  let s = serde_json::to_string_pretty(t).map_err(|e| e.to_string())?;
-  fs::write(p, s).map_err(|e| e.to_string())
+  let held = (!persist && !t.access_token.is_empty()).then(|| runtime::HeldToken {
+    refresh_token: t.refresh_token.clone(),
+    access_token: t.access_token.clone(),
+    created_at: t.created_at,
+    expires_in: t.expires_in,
+  });
+  *runtime::for_dir(dir).held_token() = held;
+  let s = if persist { tokens_file::to_json(t) } else { tokens_file::to_json(&Tokens { access_token: String::new(), expires_in: 0, ..t.clone() }) };
+  let s = s.map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_secret(&p, s).map_err(|e| Error::Storage(e.to_string()))?;
+  drop(lock);
+  auth::notify_changed(dir);
+  Ok(())
 }
 
-fn read_config(app: &AppHandle) -> Option<AppConfig> { read_config_from_dir(&app_config_dir(app).ok()?) }
-fn write_config(app: &AppHandle, cfg: &AppConfig) -> Result<(), String> { write_config_to_dir(&app_config_dir(app)?, cfg) }
-fn read_tokens(app: &AppHandle) -> Option<Tokens> { read_tokens_from_dir(&app_config_dir(app).ok()?) }
-pub fn write_tokens(app: &AppHandle, t: &Tokens) -> Result<(), String> { write_tokens_to_dir(&app_config_dir(app)?, t) }
+/// Read-modify-write of tokens.json under the dir lock, so tokens another
+/// process saves in between aren't lost.
+pub fn update_tokens_in_dir(dir: &Path, f: impl FnOnce(&mut Tokens)) -> Result<Tokens, Error> {
+  let lock = storage::lock(dir)?;
+  let mut t = read_tokens_from_dir(dir).ok_or_else(|| Error::Auth("Brak zapisanych tokenów".into()))?;
+  f(&mut t);
+  write_tokens_to_dir(dir, &t)?;
+  drop(lock);
+  Ok(t)
+}
+
+pub(crate) fn read_config(app: &AppHandle) -> Option<AppConfig> { read_config_from_dir(&app_config_dir(app).ok()?) }
+pub(crate) fn read_tokens(app: &AppHandle) -> Option<Tokens> { read_tokens_from_dir(&app_config_dir(app).ok()?) }
+pub fn write_tokens(app: &AppHandle, t: &Tokens) -> Result<(), Error> { write_tokens_to_dir(&app_config_dir(app)?, t) }
 
-fn token_endpoint() -> String {
+pub(crate) fn token_endpoint() -> String {
   std::env::var("OAUTH_TOKEN_URL").unwrap_or_else(|_| "https://oauth2.googleapis.com/token".to_string())
 }
 
-async fn perform_token_exchange(client_id: &str, client_secret: &str, code: &str, redirect: &str) -> Result<Tokens, String> {
+/// POSTs a form to the token endpoint. Captured like API calls, but the
+/// request body never is: it always holds client_secret and a code or token.
+async fn post_token_form(dir: &Path, op: &str, params: &[(&str, &str)]) -> Result<TokenReply, Error> {
+  let timeout = timeouts::for_class(dir, OperationClass::Auth);
+  let url = token_endpoint();
+  let started = Instant::now();
+  let trace_id = http_headers::trace_id();
+  let observed = |status, body, error| Exchange {
+    class: OperationClass::Auth,
+    method: "POST",
+    url: &url,
+    started,
+    status,
+    body,
+    error,
+    bytes_sent: 0,
+    attempt: 1,
+    trace_id: &trace_id,
+  };
+  #[cfg(feature = "chaos")]
+  let injected = chaos::intercept(dir, &url, op, timeout).await.map(|r| r.map(|(status, _, text)| (status, text)));
+  #[cfg(not(feature = "chaos"))]
+  let injected: Option<Result<(reqwest::StatusCode, String), Error>> = None;
+  let (status, text) = match injected {
+    Some(Ok(reply)) => reply,
+    Some(Err(err)) => {
+      observe::exchange(dir, observed(None, "", Some(&err)));
+      return Err(err);
+    }
+    None => {
+      let req = tls::request(dir, reqwest::Method::POST, &url, &trace_id)?.form(params).timeout(timeout);
+      let resp = match req.send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+          let err = tls::transport_error(dir, &url, e, op, timeout).await;
+          observe::exchange(dir, observed(None, "", Some(&err)));
+          return Err(err);
+        }
+      };
+      let status = resp.status();
+      (status, resp.text().await.map_err(|e| timeouts::transport_error(e, op, timeout))?)
+    }
+  };
+  observe::exchange(dir, observed(Some(status.as_u16()), &text, None));
+  let reply = token_response::parse(status.as_u16(), &text)?;
+  if let TokenReply::Granted(grant) = &reply {
+    for warning in &grant.warnings {
+      runtime::for_dir(dir).warn(op, warning);
+    }
+  }
+  Ok(reply)
+}
+
+/// The tokens and, when the grant had one, the id_token.
+async fn perform_token_exchange(
+  dir: &Path,
+  client_id: &str,
+  client_secret: &str,
+  code: &str,
+  redirect: &str,
+) -> Result<(Tokens, Option<String>), Error> {
   let params = [
     ("code", code),
     ("client_id", client_id),
@@ -78,121 +539,316 @@ async fn perform_token_exchange(client_id: &str, client_secret: &str, code: &str
     ("redirect_uri", redirect),
     ("grant_type", "authorization_code"),
   ];
-  let client = reqwest::Client::new();
-  let resp = client.post(&token_endpoint()).form(&params).send().await.map_err(|e| e.to_string())?;
-  let json: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
-  let access = json.get("access_token").and_then(|v| v.as_str()).unwrap_or_default().to_string();
-  let refresh = json.get("refresh_token").and_then(|v| v.as_str()).unwrap_or_default().to_string();
-  let expires_in = json.get("expires_in").and_then(|v| v.as_u64()).unwrap_or(0);
-  if access.is_empty() { return Err(format!("Błąd wymiany tokenów: {}", json)); }
-  Ok(Tokens { access_token: access, refresh_token: refresh, expires_in, created_at: now_secs() })
-}
-
-async fn exchange_and_persist(cfg_dir: &Path, code: &str) -> Result<Tokens, String> {
-  let cfg: AppConfig = read_config_from_dir(cfg_dir).ok_or("Brak konfiguracji klienta".to_string())?;
-  let redirect = "http://127.0.0.1:14321/callback";
-  let t = perform_token_exchange(&cfg.client_id, &cfg.client_secret, code, redirect).await?;
+  let grant = match post_token_form(dir, "oauth_token_exchange", &params).await? {
+    TokenReply::Granted(grant) => grant,
+    TokenReply::Refused(TokenEndpointError { error: Some(error), error_description, .. }) => {
+      let g = guidance::for_token_error(&error, error_description.as_deref(), format::language(dir));
+      return Err(guidance::refused(dir, guidance::on_port(g, callback::flow_port(dir))));
+    }
+    TokenReply::Refused(e) => return Err(Error::TokenEndpoint(Box::new(e))),
+  };
+  let tokens = Tokens {
+    access_token: grant.access_token,
+    refresh_token: grant.refresh_token.unwrap_or_default(),
+    expires_in: grant.expires_in,
+    created_at: now_secs(),
+    scope: grant.scope.unwrap_or_default(),
+    client_id: Some(client_id.to_string()),
+    issued_at: Some(now_secs()),
+  };
+  Ok((tokens, grant.id_token))
+}
+
+pub(crate) async fn exchange_and_persist(cfg_dir: &Path, code: &str) -> Result<LoginResult, Error> {
+  let cfg: AppConfig = read_config_from_dir(cfg_dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  // The URI the flow's consent screen named, even if redirect_port changed since.
+  let redirect = callback::redirect_uri(callback::flow_port(cfg_dir));
+  let (t, id_token) = perform_token_exchange(cfg_dir, &cfg.client_id, &cfg.client_secret, code, &redirect).await?;
   write_tokens_to_dir(cfg_dir, &t)?;
-  Ok(t)
+  if let Err(e) = oauth_client::record_login(cfg_dir, &cfg.client_id, id_token.as_deref()) {
+    runtime::for_dir(cfg_dir).warn("oauth_client", format!("Nie zapisano klienta OAuth logowania: {}", e));
+  }
+  setup::notify(cfg_dir);
+  Ok(auth::check_login(cfg_dir, &cfg, t))
 }
 
-async fn start_oauth(app: AppHandle) -> Result<(), String> {
-  let cfg = read_config(&app).ok_or("Brak konfiguracji klienta (Client ID/Secret)".to_string())?;
-  let redirect = "http://127.0.0.1:14321/callback";
-  let scope = "https://www.googleapis.com/auth/youtube.readonly";
-  let url = format!(
-    "https://accounts.google.com/o/oauth2/v2/auth?client_id={}&response_type=code&redirect_uri={}&access_type=offline&prompt=consent&scope={}",
+pub(crate) fn auth_endpoint() -> String {
+  std::env::var("OAUTH_AUTH_URL").unwrap_or_else(|_| "https://accounts.google.com/o/oauth2/v2/auth".to_string())
+}
+
+/// The consent screen URL for `scopes` with the given `prompt`, redirecting to
+/// `redirect` with `state` (the callback refuses a redirect without it).
+pub(crate) fn consent_url(cfg: &AppConfig, redirect: &str, state: Option<&str>, scopes: &[String], incremental: bool, prompt: &str) -> String {
+  let mut url = format!(
+    "{}?client_id={}&response_type=code&redirect_uri={}&access_type=offline&prompt={}&scope={}",
+    auth_endpoint(),
     urlencoding::encode(&cfg.client_id),
     urlencoding::encode(redirect),
-    urlencoding::encode(scope)
+    prompt,
+    urlencoding::encode(&scopes.join(" "))
   );
+  if let Some(state) = state {
+    url.push_str(&format!("&state={}", urlencoding::encode(state)));
+  }
+  if incremental {
+    url.push_str("&include_granted_scopes=true");
+  }
+  if let Some(domain) = &cfg.hosted_domain {
+    url.push_str(&format!("&hd={}", urlencoding::encode(domain)));
+  }
+  url
+}
 
-  // Open default browser
-  #[cfg(target_os = "windows")]
-  { let _ = Command::new("cmd").args(["/C", "start", &url]).spawn(); }
-  #[cfg(target_os = "macos")]
-  { let _ = Command::new("open").arg(&url).spawn(); }
-  #[cfg(all(unix, not(target_os = "macos")))]
-  { let _ = Command::new("xdg-open").arg(&url).spawn(); }
+/// Opens the consent screen of the flow [`callback::start`] began for
+/// `scopes`. With `incremental`, Google keeps the scopes granted earlier and
+/// only asks for the new ones.
+pub(crate) fn open_consent(dir: &Path, cfg: &AppConfig, scopes: &[String], incremental: bool) -> browser::BrowserLaunch {
+  let redirect = callback::redirect_uri(callback::flow_port(dir));
+  let state = callback::flow_state(dir);
+  browser::open(dir, cfg, &consent_url(cfg, &redirect, state.as_deref(), scopes, incremental, "consent"))
+}
 
-  Ok(())
+/// Probes the consent screen, starts the callback server and opens the
+/// consent screen for the configured scopes; the callback finishes the login.
+pub(crate) async fn start_login(dir: &Path) -> Result<browser::BrowserLaunch, Error> {
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta (Client ID/Secret)".into()))?;
+  let scopes = scopes::configured(&cfg);
+  consent_probe::check(dir, &cfg, &scopes).await?;
+  callback::start(dir)?;
+  Ok(open_consent(dir, &cfg, &scopes, false))
 }
 
-pub async fn exchange_code(app: AppHandle, code: String) -> Result<Tokens, String> {
-  let dir = app_config_dir(&app)?;
-  exchange_and_persist(&dir, &code).await
+/// Opens a directory in the file manager. Only a failed spawn is detected;
+/// the error carries `target` so the UI can show it for the user to open by
+/// hand.
+pub(crate) fn open_path(target: &str) -> Result<(), Error> {
+  let (program, args) = browser::open_command(browser::Platform::current(), None, target);
+  browser::spawn(&program, &args).map_err(|e| Error::Open { target: target.to_string(), message: e.to_string() })
 }
 
-async fn refresh_tokens(app: AppHandle) -> Result<Tokens, String> {
-  let cfg = read_config(&app).ok_or("Brak konfiguracji klienta".to_string())?;
-  let mut t = read_tokens(&app).ok_or("Brak zapisanych tokenów".to_string())?;
+async fn request_refresh(dir: &Path, client_id: &str, client_secret: &str, refresh_token: &str) -> Result<TokenReply, Error> {
+  let params =
+    [("client_id", client_id), ("client_secret", client_secret), ("refresh_token", refresh_token), ("grant_type", "refresh_token")];
+  post_token_form(dir, "oauth_token_refresh", &params).await
+}
+
+pub(crate) async fn refresh_and_persist(cfg_dir: &Path) -> Result<Tokens, Error> {
+  refresh_with_secret(cfg_dir, None).await
+}
+
+/// The refresh behind [`refresh_and_persist`], with `client_secret` instead
+/// of the configured one when given. With the configured one, a secret
+/// `rotate_client_secret` replaced gets one more try on `invalid_client`.
+pub(crate) async fn refresh_with_secret(cfg_dir: &Path, client_secret: Option<&str>) -> Result<Tokens, Error> {
+  let cfg = read_config_from_dir(cfg_dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  let t = read_tokens_from_dir(cfg_dir).ok_or_else(|| Error::Auth("Brak zapisanych tokenów".into()))?;
   if t.refresh_token.is_empty() {
-    return Err("Brak refresh_token — zaloguj się ponownie".into());
+    return Err(Error::Auth("Brak refresh_token — zaloguj się ponownie".into()));
   }
-  let params = [
-    ("client_id", cfg.client_id.as_str()),
-    ("client_secret", cfg.client_secret.as_str()),
-    ("refresh_token", t.refresh_token.as_str()),
-    ("grant_type", "refresh_token"),
-  ];
-  let client = reqwest::Client::new();
-  let resp = client
-    .post(&token_endpoint())
-    .form(&params)
-    .send()
-    .await
-    .map_err(|e| e.to_string())?;
-  let json: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
-  let access = json.get("access_token").and_then(|v| v.as_str()).unwrap_or("").to_string();
-  let expires_in = json.get("expires_in").and_then(|v| v.as_u64()).unwrap_or(0);
-  if access.is_empty() { return Err(format!("Błąd odświeżania: {}", json)); }
-  t.access_token = access;
-  t.expires_in = expires_in;
-  t.created_at = now_secs();
-  write_tokens(&app, &t)?;
-  Ok(t)
+  let secret = client_secret.unwrap_or(&cfg.client_secret);
+  let mut reply = request_refresh(cfg_dir, &cfg.client_id, secret, &t.refresh_token).await?;
+  if reply.error_code() == Some("invalid_client") && client_secret.is_none() {
+    let previous = runtime::for_dir(cfg_dir).previous_secret().take();
+    if let Some(previous) = previous {
+      reply = request_refresh(cfg_dir, &cfg.client_id, &previous, &t.refresh_token).await?;
+    }
+  }
+  let grant = match reply {
+    TokenReply::Granted(grant) => grant,
+    TokenReply::Refused(e) if e.error.as_deref() == Some("invalid_client") => {
+      let g = guidance::for_token_error("invalid_client", e.error_description.as_deref(), format::language(cfg_dir));
+      return Err(guidance::refused(cfg_dir, g));
+    }
+    TokenReply::Refused(e) if e.error.as_deref() == Some("invalid_grant") => {
+      return Err(Error::GrantRevoked { hint: token_age::invalid_grant_hint(cfg_dir, &t) });
+    }
+    TokenReply::Refused(e) => return Err(Error::TokenEndpoint(Box::new(e))),
+  };
+  let (access, expires_in, scope) = (grant.access_token, grant.expires_in, grant.scope);
+  // Rotation: the endpoint may issue a new refresh token and revoke the old one.
+  let rotated = grant.refresh_token;
+  // Applied to the tokens as they are now, not as read before the request.
+  let apply = |t: &mut Tokens| {
+    t.access_token = access.clone();
+    t.expires_in = expires_in;
+    t.created_at = now_secs();
+    if let Some(scope) = &scope {
+      t.scope = scope.clone();
+    }
+    if let Some(r) = &rotated {
+      t.refresh_token = r.clone();
+    }
+  };
+  if rotated.is_none() {
+    return update_tokens_in_dir(cfg_dir, apply);
+  }
+  // The old refresh token may already be dead, so losing the new one would
+  // log the user out; retry before giving up.
+  let mut last_err = String::new();
+  for attempt in 0..3u64 {
+    if attempt > 0 {
+      tokio::time::sleep(std::time::Duration::from_millis(200 * attempt)).await;
+    }
+    match update_tokens_in_dir(cfg_dir, apply) {
+      Ok(t) => return Ok(t),
+      Err(e) => last_err = e.to_string(),
+    }
+  }
+  Err(Error::Auth(format!(
+    "Nie udało się zapisać nowego refresh tokenu ({}) — poprzedni mógł zostać unieważniony, zaloguj się ponownie",
+    last_err
+  )))
+}
+
+// Removed duplicate import to fix build error
+// use tauri::{AppHandle, Manager};
+
+/// Window close: warns via `app-closing` while uploads run, then shuts down
+/// cleanly (at most [`shutdown::GRACE`]) before exiting.
+pub fn on_window_event(event: tauri::GlobalWindowEvent) {
+  let tauri::WindowEvent::CloseRequested { api, .. } = event.event() else {
+    return;
+  };
+  let app = event.window().app_handle();
+  let Ok(dir) = app_config_dir(&app) else {
+    return;
+  };
+  api.prevent_close();
+  if shutdown::request_exit(&dir) {
+    exit_after_shutdown(app, dir);
+  }
+}
+
+/// Writes panics to `crashes/` under the config dir; called once at startup.
+/// Fails when there is no config dir to write them to.
+pub fn install_crash_reporter(app: &AppHandle) -> Result<(), Error> {
+  crash::install(&app_config_dir(app)?);
+  Ok(())
 }
 
-pub async fn youtube_list_channels(app: AppHandle) -> Result<serde_json::Value, String> {
-  let mut t = read_tokens(&app).ok_or("Brak tokenów — zaloguj się".to_string())?;
-  // Auto refresh if expired (buffer 60s)
-  if t.expires_in > 0 && now_secs().saturating_sub(t.created_at) + 60 > t.expires_in {
-    let _ = refresh_tokens(app.clone()).await; // try refresh, ignore error here
-    t = read_tokens(&app).ok_or("Brak tokenów po odświeżeniu".to_string())?;
+/// Finishes profile deletions a crash cut short; called once at startup,
+/// before anything else reads the profiles.
+pub fn recover_profiles(app: &AppHandle) {
+  let Ok(loc) = storage_location(app) else {
+    return;
+  };
+  for name in profiles::recover(&loc.path) {
+    runtime::for_dir(&loc.path).warn("profiles", format!("Dokończono usuwanie profilu {}", name));
   }
-  let client = reqwest::Client::new();
-  let url = "https://www.googleapis.com/youtube/v3/channels?part=snippet&mine=true";
-  let resp = client
-    .get(url)
-    .bearer_auth(&t.access_token)
-    .send()
+}
+
+fn spawn_storage_cleanup(dir: PathBuf) {
+  tauri::async_runtime::spawn(async move {
+    let task_dir = dir.clone();
+    crash::supervise(&dir, "storage-cleanup", move || {
+      let dir = task_dir.clone();
+      async move { cleanup::run_periodically(&dir).await }
+    })
     .await
-    .map_err(|e| e.to_string())?;
-  if !resp.status().is_success() {
-    let text = resp.text().await.unwrap_or_default();
-    return Err(format!("YouTube API error: {}", text));
+  });
+}
+
+fn spawn_auth_health_check(dir: PathBuf) {
+  tauri::async_runtime::spawn(async move {
+    let task_dir = dir.clone();
+    crash::supervise(&dir, "auth-health", move || {
+      let dir = task_dir.clone();
+      async move { auth_health::run_periodically(&dir).await }
+    })
+    .await
+  });
+}
+
+/// Runs the queue worker of `dir` in the background; a second one while it
+/// runs does nothing. A panic in the worker restarts it with backoff.
+pub(crate) fn spawn_queue_worker(dir: PathBuf) {
+  tauri::async_runtime::spawn(async move {
+    let task_dir = dir.clone();
+    crash::supervise(&dir, "queue", move || {
+      let dir = task_dir.clone();
+      async move {
+        if let Err(e) = queue::run(&dir).await {
+          runtime::for_dir(&dir).warn("queue", format!("Kolejka wysyłania zatrzymana: {}", e));
+        }
+      }
+    })
+    .await
+  });
+}
+
+/// Starts the weekly config dir cleanup; called once at startup.
+pub fn start_storage_cleanup(app: &AppHandle) {
+  if let Ok(dir) = app_config_dir(app) {
+    spawn_storage_cleanup(dir);
   }
-  Ok(resp.json().await.map_err(|e| e.to_string())?)
 }
 
-pub async fn generate_env(app: AppHandle) -> Result<String, String> {
-  let cfg = read_config(&app).unwrap_or_default();
-  let t = read_tokens(&app).unwrap_or_default();
-  Ok(format_env_text(&cfg, &t))
+/// Starts the daily check of the saved login; called once at startup.
+pub fn start_auth_health_check(app: &AppHandle) {
+  if let Ok(dir) = app_config_dir(app) {
+    spawn_auth_health_check(dir);
+  }
 }
 
-fn format_env_text(cfg: &AppConfig, t: &Tokens) -> String {
-  format!(
-    "# Generated by Tauri YouTube OAuth\nYOUTUBE_CLIENT_ID={}\nYOUTUBE_CLIENT_SECRET={}\nUPLOAD_PRIVACY=unlisted\nAUTO_UPLOAD=false\n\n# Optional (not recommended to store in .env)\n# YOUTUBE_ACCESS_TOKEN={}\n# YOUTUBE_REFRESH_TOKEN={}\n",
-    cfg.client_id,
-    cfg.client_secret,
-    t.access_token,
-    t.refresh_token
-  )
+/// Second half of a profile switch, after the pointer moved and the old
+/// tasks were ended: starts the new profile's cleanup, login check and
+/// queue worker, then tells the windows with one `profile-switched`.
+pub(crate) fn finish_profile_switch(app: &AppHandle, switched: &profiles::ProfileSwitched) -> Result<(), Error> {
+  let dir = app_config_dir(app)?;
+  spawn_storage_cleanup(dir.clone());
+  spawn_auth_health_check(dir.clone());
+  spawn_queue_worker(dir.clone());
+  runtime::for_dir(&dir).emit("profile-switched", switched);
+  Ok(())
 }
 
-// Removed duplicate import to fix build error
-// use tauri::AppHandle;
+/// Offers `migration-available` when the config dir has no credentials but
+/// an older location does; called once at startup. Copies nothing.
+pub fn check_storage_migration(app: &AppHandle) {
+  let (Ok(loc), Ok(dir)) = (storage_location(app), app_config_dir(app)) else {
+    return;
+  };
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let explicit = migration::from_args(&args);
+  // Portable and --config-dir locations were chosen by hand; only the explicit source applies.
+  let candidates = match loc.mode {
+    StorageMode::Standard => migration::candidates(&dir, tauri::api::path::home_dir().as_deref(), explicit.as_deref()),
+    _ => explicit.into_iter().collect(),
+  };
+  tauri::async_runtime::spawn(async move {
+    migration::probe(&dir, &candidates);
+  });
+}
+
+/// Warns with `oauth-client-mismatch` when the config and the last login's
+/// client no longer belong together, e.g. after editing the config while
+/// the app was closed. Called once at startup.
+pub fn check_oauth_client(app: &AppHandle) {
+  if let Ok(dir) = app_config_dir(app) {
+    oauth_client::check(&dir);
+  }
+}
+
+/// Serves `/metrics` when the config has a `metrics_endpoint`; called once
+/// at startup.
+#[cfg(feature = "metrics-endpoint")]
+pub fn start_metrics_endpoint(app: &AppHandle) {
+  let Ok(dir) = app_config_dir(app) else {
+    return;
+  };
+  tauri::async_runtime::spawn(async move {
+    if let Err(e) = metrics_endpoint::start(&dir) {
+      runtime::for_dir(&dir).warn("metrics_endpoint", e);
+    }
+  });
+}
+
+pub(crate) fn exit_after_shutdown(app: AppHandle, dir: PathBuf) {
+  tauri::async_runtime::spawn(async move {
+    shutdown::shutdown(&dir, shutdown::GRACE).await;
+    app.exit(0);
+  });
+}
 
 pub fn setup() {
     // Placeholder for future functionality
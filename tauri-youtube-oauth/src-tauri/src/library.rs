@@ -0,0 +1,278 @@
+//! Which local file became which video, in `library.json`: content hash →
+//! video, so the link survives moving and renaming the file. Every upload
+//! adds its file; lookups, and [`reconcile`] for a whole directory, notice a
+//! known file at a new path and remember where it was last seen. This is the
+//! one index telling whether a file was already uploaded.
+//!
+//! Each entry records the algorithm of its key ([`hashing`]): entries from
+//! before `hash_algorithm` existed are SHA-256, and when a file's hash with
+//! the configured algorithm isn't known, the algorithms other entries use
+//! are tried too, so switching keeps earlier uploads recognised.
+
+use crate::{
+  error::Error,
+  events,
+  hashing::{self, FileHash, HashAlgorithm},
+  now_secs,
+  remote_state::Rejection,
+  storage,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs,
+  path::{Path, PathBuf},
+  sync::Mutex,
+};
+
+const LIBRARY_FILE: &str = "library.json";
+static LIBRARY_LOCK: Mutex<()> = Mutex::new(());
+/// Files [`reconcile`] hashes; the rest of a render directory is skipped.
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "mkv", "webm", "avi", "wmv", "flv", "mpg", "mpeg", "3gp"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LibraryEntry {
+  pub video_id: String,
+  /// Where the file was uploaded from.
+  pub original_path: String,
+  pub last_seen_path: String,
+  /// Unix seconds.
+  pub uploaded_at: u64,
+  /// What produced the key.
+  #[serde(default = "legacy_algorithm")]
+  pub algorithm: HashAlgorithm,
+  /// Size and mtime (unix nanoseconds) of the file at `last_seen_path` when
+  /// it was last hashed; while they match, it isn't hashed again.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub size: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub modified_ns: Option<u64>,
+  /// YouTube rejected, failed to process or removed the video: the file
+  /// was uploaded, but isn't live.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub rejection: Option<Rejection>,
+}
+
+/// Entries written before the algorithm was recorded.
+fn legacy_algorithm() -> HashAlgorithm {
+  HashAlgorithm::Sha256
+}
+
+/// A library entry with its key, as the lookups return it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LibraryMatch {
+  pub file_hash: String,
+  #[serde(flatten)]
+  pub entry: LibraryEntry,
+  /// Whether a file is still at `last_seen_path`.
+  pub present: bool,
+}
+
+/// A file [`reconcile`] found that was uploaded before.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReconciledFile {
+  pub path: String,
+  pub video_id: String,
+  pub file_hash: String,
+  /// The earlier `last_seen_path`, when the file was found elsewhere.
+  pub moved_from: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ReconcileReport {
+  pub matched: Vec<ReconciledFile>,
+  /// Videos in the directory that were never uploaded.
+  pub new: Vec<String>,
+  /// Uploads last seen under the directory that weren't found in it.
+  pub missing_locally: Vec<LibraryMatch>,
+}
+
+/// Payload of `library-progress`, after each file [`reconcile`] hashed.
+#[derive(Serialize, Debug, Clone)]
+pub struct LibraryProgress {
+  pub dir: String,
+  pub done: usize,
+  pub total: usize,
+}
+
+fn read_library(dir: &Path) -> BTreeMap<String, LibraryEntry> {
+  fs::read_to_string(dir.join(LIBRARY_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+fn update_library<T>(dir: &Path, f: impl FnOnce(&mut BTreeMap<String, LibraryEntry>) -> T) -> Result<T, Error> {
+  let _lock = storage::lock(dir)?;
+  let _guard = LIBRARY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut library = read_library(dir);
+  let before = library.clone();
+  let out = f(&mut library);
+  if library != before {
+    let s = serde_json::to_string_pretty(&library).map_err(|e| Error::Storage(e.to_string()))?;
+    storage::write_atomic(&dir.join(LIBRARY_FILE), s).map_err(|e| Error::Storage(e.to_string()))?;
+  }
+  Ok(out)
+}
+
+fn path_string(path: &Path) -> String {
+  path.to_string_lossy().into_owned()
+}
+
+fn to_match(file_hash: &str, entry: &LibraryEntry) -> LibraryMatch {
+  LibraryMatch { file_hash: file_hash.to_string(), entry: entry.clone(), present: Path::new(&entry.last_seen_path).is_file() }
+}
+
+/// Notes that the hashed file was uploaded as `video_id`. A re-upload of the
+/// same content points the entry at the new video.
+pub(crate) fn record_upload(dir: &Path, file: &FileHash, video_id: &str) -> Result<(), Error> {
+  let entry = LibraryEntry {
+    video_id: video_id.to_string(),
+    original_path: file.path.clone(),
+    last_seen_path: file.path.clone(),
+    uploaded_at: now_secs(),
+    algorithm: file.algorithm,
+    size: Some(file.size),
+    modified_ns: file.modified_ns,
+    rejection: None,
+  };
+  update_library(dir, |l| {
+    l.insert(file.hash.clone(), entry);
+  })
+}
+
+/// Every entry, in key order.
+pub(crate) fn entries(dir: &Path) -> Vec<LibraryEntry> {
+  read_library(dir).into_values().collect()
+}
+
+/// Notes on the entries of `video_id` that it isn't live; returns how many
+/// changed.
+pub(crate) fn mark_rejected(dir: &Path, video_id: &str, rejection: &Rejection) -> Result<usize, Error> {
+  update_library(dir, |l| {
+    let stale = l.values_mut().filter(|e| e.video_id == video_id && e.rejection.as_ref().is_none_or(|r| r.kind != rejection.kind));
+    stale.map(|e| e.rejection = Some(rejection.clone())).count()
+  })
+}
+
+/// The hash recorded for the file at `path` if it hasn't changed since.
+pub(crate) fn known_hash(dir: &Path, path: &str, algorithm: HashAlgorithm, size: u64, modified_ns: Option<u64>) -> Option<String> {
+  // Without an mtime a same-size edit would go unnoticed.
+  modified_ns?;
+  read_library(dir)
+    .into_iter()
+    .find(|(_, e)| e.last_seen_path == path && e.algorithm == algorithm && e.size == Some(size) && e.modified_ns == modified_ns)
+    .map(|(h, _)| h)
+}
+
+/// `path` hashed with the configured algorithm and, when the library doesn't
+/// know that hash, with the other algorithms it has entries for.
+fn hashes(dir: &Path, path: &Path) -> Result<Vec<FileHash>, Error> {
+  let first = hashing::hash(dir, path)?;
+  let library = read_library(dir);
+  if library.contains_key(&first.hash) {
+    return Ok(vec![first]);
+  }
+  let primary = first.algorithm;
+  let mut out = vec![first];
+  for algorithm in HashAlgorithm::ALL.into_iter().filter(|a| *a != primary) {
+    if library.values().any(|e| e.algorithm == algorithm) {
+      out.push(hashing::hash_with(dir, path, algorithm)?);
+    }
+  }
+  Ok(out)
+}
+
+/// Of `hashes`, the one the library knows.
+fn known<'a>(library: &BTreeMap<String, LibraryEntry>, hashes: &'a [FileHash]) -> Option<&'a FileHash> {
+  hashes.iter().find(|h| library.contains_key(&h.hash))
+}
+
+/// Updates the entry for `file` to its path if it was last seen elsewhere,
+/// and to its size and mtime; returns the path it had then.
+fn seen_at(library: &mut BTreeMap<String, LibraryEntry>, file: &FileHash) -> Option<String> {
+  let entry = library.get_mut(&file.hash)?;
+  if entry.algorithm == file.algorithm {
+    (entry.size, entry.modified_ns) = (Some(file.size), file.modified_ns);
+  }
+  (entry.last_seen_path != file.path).then(|| std::mem::replace(&mut entry.last_seen_path, file.path.clone()))
+}
+
+/// The video `path` was uploaded as, whatever the file is called now; hashes
+/// the whole file unless it's unchanged since.
+pub fn locate_video_for_file(dir: &Path, path: &Path) -> Result<Option<LibraryMatch>, Error> {
+  let hashes = hashes(dir, path)?;
+  update_library(dir, |l| {
+    let file = known(l, &hashes)?;
+    seen_at(l, file);
+    l.get(&file.hash).map(|e| to_match(&file.hash, e))
+  })
+}
+
+/// The upload of the file at `path`, without noting where it is now.
+pub(crate) fn lookup(dir: &Path, path: &Path) -> Result<Option<LibraryMatch>, Error> {
+  let hashes = hashes(dir, path)?;
+  let library = read_library(dir);
+  Ok(known(&library, &hashes).and_then(|h| library.get(&h.hash).map(|e| to_match(&h.hash, e))))
+}
+
+/// Where the file of `video_id` was last seen; `present` says whether it's
+/// still there.
+pub fn find_local_file_for_video(dir: &Path, video_id: &str) -> Option<LibraryMatch> {
+  read_library(dir).iter().filter(|(_, e)| e.video_id == video_id).max_by_key(|(_, e)| e.uploaded_at).map(|(h, e)| to_match(h, e))
+}
+
+fn is_video(path: &Path) -> bool {
+  path.extension().and_then(|e| e.to_str()).is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Video files under `root`, sorted; symlinks aren't followed.
+fn video_files(root: &Path) -> Result<Vec<PathBuf>, Error> {
+  let mut out = Vec::new();
+  let mut todo = vec![root.to_path_buf()];
+  while let Some(dir) = todo.pop() {
+    let entries = fs::read_dir(&dir).map_err(|e| Error::Storage(format!("Nie można odczytać {}: {}", dir.display(), e)))?;
+    for entry in entries.flatten() {
+      let Ok(kind) = entry.file_type() else { continue };
+      if kind.is_dir() {
+        todo.push(entry.path());
+      } else if kind.is_file() && is_video(&entry.path()) {
+        out.push(entry.path());
+      }
+    }
+  }
+  out.sort();
+  Ok(out)
+}
+
+/// Hashes every video under `scan_dir`, emitting `library-progress`, and
+/// sorts them into uploaded (moved ones get their new path recorded) and
+/// new, plus the uploads last seen under `scan_dir` that are gone from it.
+pub fn reconcile(dir: &Path, scan_dir: &Path) -> Result<ReconcileReport, Error> {
+  if !scan_dir.is_dir() {
+    return Err(Error::Validation(format!("{} nie jest katalogiem", scan_dir.display())));
+  }
+  let files = video_files(scan_dir)?;
+  let key = path_string(scan_dir);
+  let mut hashed = Vec::with_capacity(files.len());
+  for (i, file) in files.iter().enumerate() {
+    hashed.push((path_string(file), hashes(dir, file)?));
+    events::progress(dir, &key, "library-progress", LibraryProgress { dir: key.clone(), done: i + 1, total: files.len() });
+  }
+  update_library(dir, |l| {
+    let found: HashSet<&String> = hashed.iter().flat_map(|(_, hashes)| hashes.iter().map(|h| &h.hash)).collect();
+    let missing_locally = l
+      .iter()
+      .filter(|(h, e)| !found.contains(h) && Path::new(&e.last_seen_path).starts_with(scan_dir))
+      .map(|(h, e)| to_match(h, e))
+      .collect();
+    let mut report = ReconcileReport { missing_locally, ..Default::default() };
+    for (path, hashes) in &hashed {
+      let Some(file) = known(l, hashes) else {
+        report.new.push(path.clone());
+        continue;
+      };
+      let moved_from = seen_at(l, file);
+      let video_id = l[&file.hash].video_id.clone();
+      report.matched.push(ReconciledFile { path: path.clone(), video_id, file_hash: file.hash.clone(), moved_from });
+    }
+    report
+  })
+}
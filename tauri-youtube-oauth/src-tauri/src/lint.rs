@@ -0,0 +1,173 @@
+//! Checks of a video's title, description and tags before upload: what
+//! YouTube refuses outright (angle brackets, too many tags) and what tends to
+//! get a video limited or flagged (shouting titles, link-stuffed
+//! descriptions, words the channel owner listed). Pure, so the UI can run it
+//! while the metadata is being typed.
+
+use crate::upload::VideoMetadata;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Titles with more upper than this share of cased letters count as shouting.
+const MAX_CAPS_RATIO: f64 = 0.7;
+/// Shorter titles ("NASA", "DIY TV") are left alone.
+const MIN_CAPS_LETTERS: usize = 10;
+const DEFAULT_MAX_LINKS: usize = 2;
+/// YouTube's limit on all tags together, counted as [`tags_length`] does.
+pub const TAGS_MAX_CHARS: usize = 500;
+/// Tags warned about from here on.
+const TAGS_WARN_CHARS: usize = 450;
+/// Links to these don't count as external.
+const OWN_HOSTS: &[&str] = &["youtube.com", "youtu.be"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LintRule {
+  ExcessiveCaps,
+  TooManyLinks,
+  BannedWord,
+  MissingDescription,
+  TagsNearLimit,
+  AngleBrackets,
+  /// A link to a video viewers can't open yet (see `video_links`).
+  UnavailableVideoLink,
+}
+
+/// What a failed check means for the upload.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LintMode {
+  /// Emit `metadata-lint` and upload anyway.
+  #[default]
+  Warn,
+  /// Refuse the upload unless forced.
+  Block,
+}
+
+/// `metadata_lint` in the config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LintConfig {
+  #[serde(default)]
+  pub mode: LintMode,
+  /// Rules not checked.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub disabled: Vec<LintRule>,
+  /// Matched as whole words, ignoring case.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub banned_words: Vec<String>,
+  /// External links allowed in the description (default 2).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_links: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LintWarning {
+  pub rule: LintRule,
+  /// `title`, `description` or `tags`.
+  pub field: String,
+  pub message: String,
+}
+
+/// Payload of `metadata-lint`, sent when an upload starts with warnings.
+#[derive(Serialize, Debug, Clone)]
+pub struct MetadataLint {
+  pub file_path: String,
+  pub warnings: Vec<LintWarning>,
+  /// Whether the upload was refused over them.
+  pub blocked: bool,
+}
+
+fn warning(rule: LintRule, field: &str, message: String) -> LintWarning {
+  LintWarning { rule, field: field.into(), message }
+}
+
+/// Length of the tags the way YouTube counts it: joined with commas, a tag
+/// with a space in quotes.
+pub fn tags_length(tags: &[String]) -> usize {
+  let chars: usize = tags.iter().map(|t| t.chars().count() + if t.contains(' ') { 2 } else { 0 }).sum();
+  chars + tags.len().saturating_sub(1)
+}
+
+fn caps_ratio(text: &str) -> Option<f64> {
+  let cased: Vec<char> = text.chars().filter(|c| c.is_lowercase() || c.is_uppercase()).collect();
+  if cased.len() < MIN_CAPS_LETTERS {
+    return None;
+  }
+  Some(cased.iter().filter(|c| c.is_uppercase()).count() as f64 / cased.len() as f64)
+}
+
+fn external_links(text: &str) -> Vec<String> {
+  let links = Regex::new(r"(?i)\b(?:https?://|www\.)[^\s<>()]+").unwrap();
+  links
+    .find_iter(text)
+    .map(|m| m.as_str().to_string())
+    .filter(|link| {
+      let full = if link.to_lowercase().starts_with("www.") { format!("https://{}", link) } else { link.clone() };
+      let host = url::Url::parse(&full).ok().and_then(|u| u.host_str().map(str::to_lowercase)).unwrap_or_default();
+      !OWN_HOSTS.iter().any(|own| host == *own || host.ends_with(&format!(".{}", own)))
+    })
+    .collect()
+}
+
+fn banned_in(text: &str, words: &[String]) -> Vec<String> {
+  words
+    .iter()
+    .filter(|w| !w.trim().is_empty())
+    .filter(|w| {
+      let pattern = format!(r"\b{}\b", regex::escape(w.trim()));
+      RegexBuilder::new(&pattern).case_insensitive(true).build().map(|re| re.is_match(text)).unwrap_or(false)
+    })
+    .cloned()
+    .collect()
+}
+
+/// Every rule `cfg` leaves enabled, applied to `meta`; empty when it's clean.
+pub fn lint_metadata(meta: &VideoMetadata, cfg: &LintConfig) -> Vec<LintWarning> {
+  let on = |rule: LintRule| !cfg.disabled.contains(&rule);
+  let mut out = Vec::new();
+  if on(LintRule::ExcessiveCaps) {
+    if let Some(ratio) = caps_ratio(&meta.title).filter(|r| *r > MAX_CAPS_RATIO) {
+      out.push(warning(LintRule::ExcessiveCaps, "title", format!("Tytuł jest w {:.0}% pisany wielkimi literami", ratio * 100.0)));
+    }
+  }
+  if on(LintRule::TooManyLinks) {
+    let max = cfg.max_links.unwrap_or(DEFAULT_MAX_LINKS);
+    let links = external_links(&meta.description);
+    if links.len() > max {
+      out.push(warning(
+        LintRule::TooManyLinks,
+        "description",
+        format!("Opis zawiera {} linków zewnętrznych (dozwolone: {})", links.len(), max),
+      ));
+    }
+  }
+  if on(LintRule::BannedWord) {
+    for (field, text) in [("title", &meta.title), ("description", &meta.description)] {
+      for word in banned_in(text, &cfg.banned_words) {
+        out.push(warning(LintRule::BannedWord, field, format!("Niedozwolone słowo: {}", word)));
+      }
+    }
+    for word in banned_in(&meta.tags.join(", "), &cfg.banned_words) {
+      out.push(warning(LintRule::BannedWord, "tags", format!("Niedozwolone słowo: {}", word)));
+    }
+  }
+  if on(LintRule::MissingDescription) && meta.description.trim().is_empty() {
+    out.push(warning(LintRule::MissingDescription, "description", "Brak opisu".into()));
+  }
+  if on(LintRule::TagsNearLimit) {
+    let len = tags_length(&meta.tags);
+    if len > TAGS_MAX_CHARS {
+      out.push(warning(LintRule::TagsNearLimit, "tags", format!("Tagi mają {} znaków, więcej niż limit {}", len, TAGS_MAX_CHARS)));
+    } else if len >= TAGS_WARN_CHARS {
+      out.push(warning(LintRule::TagsNearLimit, "tags", format!("Tagi mają {} z {} dozwolonych znaków", len, TAGS_MAX_CHARS)));
+    }
+  }
+  if on(LintRule::AngleBrackets) {
+    for (field, text) in [("title", &meta.title), ("description", &meta.description)] {
+      if text.contains(['<', '>']) {
+        out.push(warning(LintRule::AngleBrackets, field, "YouTube nie przyjmuje znaków < i >".into()));
+      }
+    }
+  }
+  out
+}
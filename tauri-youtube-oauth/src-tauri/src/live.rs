@@ -0,0 +1,298 @@
+use crate::{
+  api::{self, ApiCall, Privacy},
+  error::Error,
+  scopes,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{fmt, path::Path};
+
+pub(crate) const LIVE_SCOPES: &[&str] = &[scopes::YOUTUBE];
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct BroadcastOptions {
+  #[serde(default)]
+  pub description: Option<String>,
+  #[serde(default)]
+  pub scheduled_end: Option<String>,
+  #[serde(default)]
+  pub enable_auto_start: Option<bool>,
+  #[serde(default)]
+  pub enable_auto_stop: Option<bool>,
+  #[serde(default)]
+  pub enable_dvr: Option<bool>,
+  #[serde(default)]
+  pub record_from_start: Option<bool>,
+  /// `normal`, `low` or `ultraLow`.
+  #[serde(default)]
+  pub latency_preference: Option<String>,
+  #[serde(default)]
+  pub made_for_kids: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Broadcast {
+  pub id: String,
+  pub title: String,
+  pub scheduled_start: String,
+  pub privacy: String,
+  pub life_cycle_status: String,
+  pub bound_stream_id: Option<String>,
+}
+
+impl Broadcast {
+  fn from_resource(v: &Value) -> Self {
+    Broadcast {
+      id: str_at(v, "/id"),
+      title: str_at(v, "/snippet/title"),
+      scheduled_start: str_at(v, "/snippet/scheduledStartTime"),
+      privacy: str_at(v, "/status/privacyStatus"),
+      life_cycle_status: str_at(v, "/status/lifeCycleStatus"),
+      bound_stream_id: v.pointer("/contentDetails/boundStreamId").and_then(|s| s.as_str()).map(str::to_string),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamResolution {
+  #[serde(rename = "240p")]
+  P240,
+  #[serde(rename = "360p")]
+  P360,
+  #[serde(rename = "480p")]
+  P480,
+  #[serde(rename = "720p")]
+  P720,
+  #[serde(rename = "1080p")]
+  P1080,
+  #[serde(rename = "1440p")]
+  P1440,
+  #[serde(rename = "2160p")]
+  P2160,
+  #[serde(rename = "variable")]
+  Variable,
+}
+
+impl StreamResolution {
+  fn as_str(&self) -> &'static str {
+    match self {
+      StreamResolution::P240 => "240p",
+      StreamResolution::P360 => "360p",
+      StreamResolution::P480 => "480p",
+      StreamResolution::P720 => "720p",
+      StreamResolution::P1080 => "1080p",
+      StreamResolution::P1440 => "1440p",
+      StreamResolution::P2160 => "2160p",
+      StreamResolution::Variable => "variable",
+    }
+  }
+}
+
+/// A live stream with its RTMP ingestion details. `stream_key` is a secret:
+/// anyone holding it can broadcast to the channel, so `Debug` redacts it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LiveStream {
+  pub id: String,
+  pub title: String,
+  pub resolution: String,
+  pub frame_rate: String,
+  pub ingestion_address: String,
+  pub backup_ingestion_address: String,
+  pub stream_key: String,
+  pub stream_status: String,
+}
+
+impl LiveStream {
+  fn from_resource(v: &Value) -> Self {
+    LiveStream {
+      id: str_at(v, "/id"),
+      title: str_at(v, "/snippet/title"),
+      resolution: str_at(v, "/cdn/resolution"),
+      frame_rate: str_at(v, "/cdn/frameRate"),
+      ingestion_address: str_at(v, "/cdn/ingestionInfo/ingestionAddress"),
+      backup_ingestion_address: str_at(v, "/cdn/ingestionInfo/backupIngestionAddress"),
+      stream_key: str_at(v, "/cdn/ingestionInfo/streamName"),
+      stream_status: str_at(v, "/status/streamStatus"),
+    }
+  }
+}
+
+impl fmt::Debug for LiveStream {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LiveStream")
+      .field("id", &self.id)
+      .field("title", &self.title)
+      .field("resolution", &self.resolution)
+      .field("frame_rate", &self.frame_rate)
+      .field("ingestion_address", &self.ingestion_address)
+      .field("stream_key", &"<redacted>")
+      .field("stream_status", &self.stream_status)
+      .finish()
+  }
+}
+
+/// Target of `liveBroadcasts.transition`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BroadcastStatus {
+  Testing,
+  Live,
+  Complete,
+}
+
+impl BroadcastStatus {
+  fn as_str(&self) -> &'static str {
+    match self {
+      BroadcastStatus::Testing => "testing",
+      BroadcastStatus::Live => "live",
+      BroadcastStatus::Complete => "complete",
+    }
+  }
+
+  /// Life cycle states the API accepts this transition from.
+  fn allowed_from(&self) -> &'static [&'static str] {
+    match self {
+      BroadcastStatus::Testing => &["ready"],
+      BroadcastStatus::Live => &["ready", "testing"],
+      BroadcastStatus::Complete => &["testing", "live"],
+    }
+  }
+}
+
+fn str_at(v: &Value, pointer: &str) -> String {
+  v.pointer(pointer).and_then(|s| s.as_str()).unwrap_or_default().to_string()
+}
+
+fn normalize_time(field: &str, value: &str) -> Result<DateTime<Utc>, Error> {
+  DateTime::parse_from_rfc3339(value)
+    .map(|t| t.with_timezone(&Utc))
+    .map_err(|e| Error::Validation(format!("Nieprawidłowa data {} (oczekiwano RFC3339): {}", field, e)))
+}
+
+/// Checks a transition against the broadcast's current life cycle status so
+/// obviously invalid requests never reach the API.
+pub fn check_transition(broadcast: &Broadcast, target: BroadcastStatus) -> Result<(), Error> {
+  if target != BroadcastStatus::Complete && broadcast.bound_stream_id.is_none() {
+    return Err(Error::Validation("Transmisja nie ma podpiętego strumienia — użyj youtube_bind_stream".into()));
+  }
+  if !target.allowed_from().contains(&broadcast.life_cycle_status.as_str()) {
+    return Err(Error::Validation(format!(
+      "Niedozwolone przejście transmisji: {} → {} (dozwolone z: {})",
+      broadcast.life_cycle_status,
+      target.as_str(),
+      target.allowed_from().join(", ")
+    )));
+  }
+  Ok(())
+}
+
+pub async fn create_broadcast(
+  dir: &Path,
+  title: &str,
+  scheduled_start: &str,
+  privacy: Privacy,
+  options: &BroadcastOptions,
+) -> Result<Broadcast, Error> {
+  api::ensure_writable(dir)?;
+  if title.trim().is_empty() {
+    return Err(Error::Validation("Tytuł transmisji nie może być pusty".into()));
+  }
+  let start = normalize_time("scheduled_start", scheduled_start)?;
+  if start <= Utc::now() {
+    return Err(Error::Validation("scheduled_start musi być w przyszłości".into()));
+  }
+  let mut snippet = json!({
+    "title": title,
+    "scheduledStartTime": start.to_rfc3339_opts(SecondsFormat::Secs, true),
+  });
+  if let Some(end) = &options.scheduled_end {
+    let end = normalize_time("scheduled_end", end)?;
+    if end <= start {
+      return Err(Error::Validation("scheduled_end musi być po scheduled_start".into()));
+    }
+    snippet["scheduledEndTime"] = json!(end.to_rfc3339_opts(SecondsFormat::Secs, true));
+  }
+  if let Some(d) = &options.description {
+    snippet["description"] = json!(d);
+  }
+  let mut status = json!({ "privacyStatus": privacy.as_str() });
+  if let Some(kids) = options.made_for_kids {
+    status["selfDeclaredMadeForKids"] = json!(kids);
+  }
+  let mut content = serde_json::Map::new();
+  for (key, value) in [
+    ("enableAutoStart", options.enable_auto_start),
+    ("enableAutoStop", options.enable_auto_stop),
+    ("enableDvr", options.enable_dvr),
+    ("recordFromStart", options.record_from_start),
+  ] {
+    if let Some(v) = value {
+      content.insert(key.to_string(), json!(v));
+    }
+  }
+  if let Some(l) = &options.latency_preference {
+    content.insert("latencyPreference".into(), json!(l));
+  }
+  let call = ApiCall::post("/youtube/v3/liveBroadcasts")
+    .query("part", "snippet,status,contentDetails")
+    .json(json!({ "snippet": snippet, "status": status, "contentDetails": content }))
+    .scopes(LIVE_SCOPES);
+  Ok(Broadcast::from_resource(&api::execute(dir, call).await?))
+}
+
+pub async fn get_broadcast(dir: &Path, id: &str) -> Result<Broadcast, Error> {
+  let call = ApiCall::get("/youtube/v3/liveBroadcasts").query("part", "snippet,status,contentDetails").query("id", id);
+  let resp = api::execute(dir, call).await?;
+  resp["items"]
+    .get(0)
+    .map(Broadcast::from_resource)
+    .ok_or_else(|| Error::Validation(format!("Nie znaleziono transmisji {}", id)))
+}
+
+pub async fn bind_stream(dir: &Path, broadcast_id: &str, stream_id: &str) -> Result<Broadcast, Error> {
+  api::ensure_writable(dir)?;
+  let call = ApiCall::post("/youtube/v3/liveBroadcasts/bind")
+    .query("id", broadcast_id)
+    .query("streamId", stream_id)
+    .query("part", "id,snippet,contentDetails,status")
+    .scopes(LIVE_SCOPES);
+  Ok(Broadcast::from_resource(&api::execute(dir, call).await?))
+}
+
+pub async fn transition_broadcast(dir: &Path, id: &str, target: BroadcastStatus) -> Result<Broadcast, Error> {
+  api::ensure_writable(dir)?;
+  check_transition(&get_broadcast(dir, id).await?, target)?;
+  let call = ApiCall::post("/youtube/v3/liveBroadcasts/transition")
+    .query("broadcastStatus", target.as_str())
+    .query("id", id)
+    .query("part", "id,snippet,contentDetails,status")
+    .scopes(LIVE_SCOPES);
+  Ok(Broadcast::from_resource(&api::execute(dir, call).await?))
+}
+
+pub async fn list_streams(dir: &Path) -> Result<Vec<LiveStream>, Error> {
+  let call = ApiCall::get("/youtube/v3/liveStreams")
+    .query("part", "snippet,cdn,status")
+    .query("mine", "true")
+    .query("maxResults", "50");
+  let resp = api::execute(dir, call).await?;
+  Ok(resp["items"].as_array().map(|items| items.iter().map(LiveStream::from_resource).collect()).unwrap_or_default())
+}
+
+pub async fn create_stream(dir: &Path, title: &str, resolution: StreamResolution) -> Result<LiveStream, Error> {
+  api::ensure_writable(dir)?;
+  if title.trim().is_empty() {
+    return Err(Error::Validation("Tytuł strumienia nie może być pusty".into()));
+  }
+  // The API requires frameRate and resolution to be either both fixed or both variable.
+  let frame_rate = if resolution == StreamResolution::Variable { "variable" } else { "30fps" };
+  let call = ApiCall::post("/youtube/v3/liveStreams")
+    .query("part", "snippet,cdn,status")
+    .json(json!({
+      "snippet": { "title": title },
+      "cdn": { "ingestionType": "rtmp", "resolution": resolution.as_str(), "frameRate": frame_rate },
+    }))
+    .scopes(LIVE_SCOPES);
+  Ok(LiveStream::from_resource(&api::execute(dir, call).await?))
+}
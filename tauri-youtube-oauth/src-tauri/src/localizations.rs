@@ -0,0 +1,188 @@
+//! Titles and descriptions in other languages for playlists and the channel.
+//! `update` replaces the whole `localizations` part, so each change reads
+//! the resource first and sends back the languages it doesn't touch. The
+//! API refuses localizations on a resource without a default language; one
+//! that has none gets `default_metadata_language` (else the app language)
+//! in the same update. A playlist keeps it in `snippet`, the channel in
+//! `brandingSettings.channel`, since its `snippet` can't be written.
+
+use crate::{
+  api::{self, ApiCall},
+  channel,
+  error::Error,
+  format::{self, Language},
+  playlists, read_config_from_dir, scopes,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{collections::BTreeMap, path::Path};
+
+const READ_SCOPES: &[&str] = &[scopes::YOUTUBE_READONLY];
+const TITLE_MAX_CHARS: usize = 150;
+const DESCRIPTION_MAX_CHARS: usize = 5000;
+
+/// One language's version of the title and description.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Localization {
+  pub title: String,
+  #[serde(default)]
+  pub description: String,
+}
+
+/// A playlist's or the channel's localizations as they are now.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LocalizationSet {
+  pub id: String,
+  pub default_language: Option<String>,
+  pub localizations: BTreeMap<String, Localization>,
+  /// This change gave the resource its default language.
+  #[serde(default)]
+  pub default_language_added: bool,
+}
+
+/// A BCP-47 tag as YouTube lists them (`i18nLanguages`): a 2–3 letter
+/// language, then subtags of 2–8 letters or digits (`pt-BR`, `zh-Hant`,
+/// `es-419`).
+pub fn validate_language(code: &str) -> Result<(), Error> {
+  let mut subtags = code.split('-');
+  let language = subtags.next().unwrap_or_default();
+  let valid = (2..=3).contains(&language.len())
+    && language.chars().all(|c| c.is_ascii_alphabetic())
+    && subtags.all(|s| (2..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()));
+  if !valid {
+    return Err(Error::Validation(format!("Nieprawidłowy kod języka \"{}\" (oczekiwano np. en, pt-BR, zh-Hant)", code)));
+  }
+  Ok(())
+}
+
+fn validate(changes: &BTreeMap<String, Option<Localization>>) -> Result<(), Error> {
+  if changes.is_empty() {
+    return Err(Error::Validation("Brak lokalizacji do zmiany".into()));
+  }
+  for (language, localization) in changes {
+    validate_language(language)?;
+    let Some(l) = localization else { continue };
+    if l.title.trim().is_empty() {
+      return Err(Error::Validation(format!("Lokalizacja {} wymaga tytułu", language)));
+    }
+    if l.title.chars().count() > TITLE_MAX_CHARS || l.description.chars().count() > DESCRIPTION_MAX_CHARS {
+      return Err(Error::Validation(format!(
+        "Lokalizacja {}: tytuł może mieć najwyżej {} znaków, opis {}",
+        language, TITLE_MAX_CHARS, DESCRIPTION_MAX_CHARS
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// The `localizations` part with `changes` applied; `None` removes a language.
+fn merged(current: &Value, changes: &BTreeMap<String, Option<Localization>>) -> Value {
+  let mut out = current.as_object().cloned().unwrap_or_default();
+  for (language, localization) in changes {
+    match localization {
+      Some(l) => out.insert(language.clone(), json!(l)),
+      None => out.remove(language),
+    };
+  }
+  Value::Object(out)
+}
+
+/// What a resource without a default language gets.
+fn default_language(dir: &Path) -> String {
+  read_config_from_dir(dir).and_then(|c| c.default_metadata_language).unwrap_or_else(|| {
+    match format::language(dir) {
+      Language::Pl => "pl",
+      Language::En => "en",
+    }
+    .to_string()
+  })
+}
+
+fn parse(resource: &Value, default_language: &Value) -> LocalizationSet {
+  let localizations = resource["localizations"]
+    .as_object()
+    .map(|m| m.iter().filter_map(|(k, v)| Some((k.clone(), serde_json::from_value(v.clone()).ok()?))).collect())
+    .unwrap_or_default();
+  LocalizationSet {
+    id: resource["id"].as_str().unwrap_or_default().to_string(),
+    default_language: default_language.as_str().filter(|l| !l.is_empty()).map(str::to_string),
+    localizations,
+    default_language_added: false,
+  }
+}
+
+/// Fills in `slot` (a `defaultLanguage` field) when it is empty; true if it was.
+fn ensure_default(dir: &Path, slot: &mut Map<String, Value>) -> bool {
+  let missing = slot.get("defaultLanguage").and_then(Value::as_str).is_none_or(str::is_empty);
+  if missing {
+    slot.insert("defaultLanguage".into(), json!(default_language(dir)));
+  }
+  missing
+}
+
+async fn fetch_playlist(dir: &Path, playlist_id: &str) -> Result<Value, Error> {
+  let call = ApiCall::get("/youtube/v3/playlists").query("part", "localizations,snippet").query("id", playlist_id).scopes(READ_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  resp["items"].get(0).cloned().ok_or_else(|| Error::Api {
+    status: 404,
+    reason: "playlistNotFound".into(),
+    message: format!("Nie znaleziono playlisty {}", playlist_id),
+  })
+}
+
+pub async fn playlist_localizations(dir: &Path, playlist_id: &str) -> Result<LocalizationSet, Error> {
+  let resource = fetch_playlist(dir, playlist_id).await?;
+  Ok(parse(&resource, &resource["snippet"]["defaultLanguage"]))
+}
+
+/// Applies `changes` to the playlist's localizations, keeping the other
+/// languages; `null` removes one. Only the writable snippet fields go back.
+pub async fn set_playlist_localizations(
+  dir: &Path,
+  playlist_id: &str,
+  changes: &BTreeMap<String, Option<Localization>>,
+) -> Result<LocalizationSet, Error> {
+  api::ensure_writable(dir)?;
+  validate(changes)?;
+  let current = fetch_playlist(dir, playlist_id).await?;
+  let mut snippet = Map::new();
+  for key in ["title", "description", "tags", "defaultLanguage"] {
+    if let Some(v) = current["snippet"].get(key) {
+      snippet.insert(key.into(), v.clone());
+    }
+  }
+  let added = ensure_default(dir, &mut snippet);
+  let body = json!({ "id": playlist_id, "snippet": snippet, "localizations": merged(&current["localizations"], changes) });
+  let call = ApiCall::put("/youtube/v3/playlists").query("part", "localizations,snippet").json(body).scopes(playlists::PLAYLIST_WRITE_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  Ok(LocalizationSet { default_language_added: added, ..parse(&resp, &resp["snippet"]["defaultLanguage"]) })
+}
+
+async fn fetch_channel(dir: &Path) -> Result<Value, Error> {
+  Ok(channel::list_mine(dir, "brandingSettings,localizations").await?["items"][0].clone())
+}
+
+pub async fn channel_localizations(dir: &Path) -> Result<LocalizationSet, Error> {
+  let resource = fetch_channel(dir).await?;
+  Ok(parse(&resource, &resource["brandingSettings"]["channel"]["defaultLanguage"]))
+}
+
+/// [`set_playlist_localizations`] for the authenticated user's channel. The
+/// whole `brandingSettings` goes back with it, as `channels.update` replaces
+/// every part it's given.
+pub async fn set_channel_localizations(dir: &Path, changes: &BTreeMap<String, Option<Localization>>) -> Result<LocalizationSet, Error> {
+  api::ensure_writable(dir)?;
+  validate(changes)?;
+  let current = fetch_channel(dir).await?;
+  let mut branding = current["brandingSettings"].as_object().cloned().unwrap_or_default();
+  let section = branding.entry("channel").or_insert_with(|| json!({}));
+  if !section.is_object() {
+    *section = json!({});
+  }
+  let added = ensure_default(dir, section.as_object_mut().unwrap());
+  let body = json!({ "id": current["id"], "brandingSettings": branding, "localizations": merged(&current["localizations"], changes) });
+  let call =
+    ApiCall::put("/youtube/v3/channels").query("part", "brandingSettings,localizations").json(body).scopes(channel::BRANDING_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  Ok(LocalizationSet { default_language_added: added, ..parse(&resp, &resp["brandingSettings"]["channel"]["defaultLanguage"]) })
+}
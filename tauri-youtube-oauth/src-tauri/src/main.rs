@@ -3,16 +3,161 @@
     windows_subsystem = "windows"
 )]
 
-use tauri::{
-    Builder, Invoke,
-};
+use tauri::Builder;
+use tauri_youtube_oauth::*;
 
+// youtube_list_channels stays registered for older frontends.
+#[allow(deprecated)]
 fn main() {
-    Builder::default()
-        .invoke_handler(|invoke: Invoke| {
-            // Empty handler for minimal setup
-            invoke.resolver.respond(Ok(serde_json::Value::Null));
+    let config_dir = storage::override_from_env().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+    let mut builder = Builder::default();
+    if let Some(dir) = config_dir {
+        builder = builder.manage(storage::ConfigDirOverride(dir));
+    }
+    builder
+        .setup(|app| {
+            if let Err(e) = install_crash_reporter(&app.handle()) {
+                eprintln!("Raporty awarii wyłączone: {}", e);
+            }
+            recover_profiles(&app.handle());
+            start_storage_cleanup(&app.handle());
+            start_auth_health_check(&app.handle());
+            check_storage_migration(&app.handle());
+            check_oauth_client(&app.handle());
+            #[cfg(feature = "metrics-endpoint")]
+            start_metrics_endpoint(&app.handle());
+            Ok(())
         })
+        .on_window_event(on_window_event)
+        .invoke_handler(tauri::generate_handler![
+            start_oauth,
+            get_active_flow,
+            cancel_oauth,
+            set_redirect_port,
+            request_scopes,
+            reconsent,
+            exchange_code,
+            import_adc,
+            rotate_client_secret,
+            rotate_client_id,
+            refresh_tokens,
+            refresh_tokens_now,
+            youtube_list_channels,
+            youtube_get_channels,
+            youtube_list_my_videos,
+            youtube_create_broadcast,
+            youtube_bind_stream,
+            youtube_transition_broadcast,
+            youtube_list_streams,
+            youtube_create_stream,
+            youtube_set_channel_description,
+            youtube_list_playlist_localizations,
+            youtube_set_playlist_localizations,
+            youtube_list_channel_localizations,
+            youtube_set_channel_localizations,
+            youtube_raw_request,
+            youtube_upload_video,
+            lint_metadata,
+            resolve_video_links,
+            locate_video_for_file,
+            find_local_file_for_video,
+            hash_file,
+            reconcile_library,
+            load_video_metadata,
+            validate_sidecar,
+            resolve_publish_time,
+            preview_metadata,
+            youtube_set_chapters,
+            youtube_post_comment,
+            youtube_pin_comment,
+            youtube_heart_comment,
+            list_snapshots,
+            restore_snapshot,
+            youtube_set_thumbnail,
+            queue_add,
+            queue_remove,
+            queue_list,
+            set_queue_priority,
+            move_queue_entry,
+            start_queue,
+            revalidate_blocked,
+            retry_queue_entry,
+            retry_all_failed,
+            set_upload_schedule,
+            get_upload_plan,
+            test_hook,
+            youtube_upload_banner,
+            youtube_select_channel,
+            youtube_set_watermark,
+            youtube_unset_watermark,
+            youtube_search,
+            get_quota_usage,
+            youtube_list_channel_sections,
+            youtube_create_channel_section,
+            youtube_update_channel_section,
+            youtube_delete_channel_section,
+            youtube_list_playlist_items,
+            youtube_add_to_playlist,
+            deduplicate_playlist,
+            set_dry_run,
+            set_read_only_mode,
+            test_notification,
+            get_connectivity,
+            set_offline_mode,
+            set_http_capture,
+            get_http_capture,
+            export_http_capture,
+            get_metrics,
+            get_setup_state,
+            wait_until_authenticated,
+            get_auth_status,
+            check_auth_health,
+            get_capabilities,
+            get_token_age,
+            list_crash_reports,
+            cleanup_storage,
+            format_bytes,
+            format_duration,
+            format_eta,
+            format_timestamp,
+            run_diagnostics,
+            describe_oauth_client,
+            export_diagnostics,
+            reload_config,
+            open_config_dir,
+            get_storage_location,
+            migrate_storage,
+            query_upload_history,
+            list_overridden_uploads,
+            export_upload_history,
+            verify_remote_state,
+            export_channel_inventory,
+            archive_video,
+            archive_channel,
+            apply_metadata_plan,
+            bulk_set_privacy,
+            error_codes,
+            confirm_exit,
+            generate_env,
+            generate_env_for_profile,
+            generate_all_envs,
+            mint_ci_token,
+            write_ci_env,
+            check_env_file,
+            sync_env_file,
+            get_audit_log,
+            list_profiles,
+            get_active_profile,
+            switch_profile,
+            delete_profile,
+            get_config,
+            export_bundle,
+            set_app_passphrase,
+            clear_app_passphrase,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,280 @@
+//! Video metadata from a sidecar JSON (`video.mp4` → `video.json`) and, when
+//! the sidecar has no title, from the filename: a regex with named groups picks
+//! it apart and title/description templates put the pieces back together.
+
+use crate::{
+  api::Privacy,
+  channel,
+  chapters::{self, ChapterPlacement},
+  error::Error,
+  read_config_from_dir, runtime, sidecar,
+  upload::VideoMetadata,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+/// Placeholders available without a capture group of the same name.
+const BUILTINS: &[&str] = &["date", "channel_title", "file_stem"];
+const FILTERS: &[&str] = &["title", "upper", "lower"];
+
+/// `metadata_template` in the config, e.g. pattern
+/// `^(?P<date>\d{4}-\d{2}-\d{2})_(?P<topic>[a-z-]+)_ep(?P<ep>\d+)$` with title
+/// `{{topic|title}} — Episode {{ep}}`. The pattern is matched against the
+/// file stem.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetadataTemplate {
+  pub filename_pattern: String,
+  pub title: String,
+  #[serde(default)]
+  pub description: String,
+}
+
+/// Fields a sidecar may set; anything missing falls back to the template or
+/// the defaults.
+#[derive(Deserialize, Debug, Default)]
+struct Sidecar {
+  #[serde(default)]
+  title: Option<String>,
+  #[serde(default)]
+  description: Option<String>,
+  #[serde(default)]
+  tags: Vec<String>,
+  #[serde(default)]
+  category_id: Option<String>,
+  #[serde(default)]
+  privacy: Option<Privacy>,
+  #[serde(default)]
+  publish_at: Option<String>,
+  /// Relative to the video's directory.
+  #[serde(default)]
+  thumbnail: Option<PathBuf>,
+  #[serde(default)]
+  first_comment: Option<String>,
+}
+
+/// What `preview_metadata` shows for a filename.
+#[derive(Serialize, Debug, Clone)]
+pub struct MetadataPreview {
+  pub matched: bool,
+  pub groups: BTreeMap<String, String>,
+  pub title: Option<String>,
+  pub description: Option<String>,
+}
+
+fn compile(pattern: &str) -> Result<Regex, Error> {
+  Regex::new(pattern).map_err(|e| {
+    // regex only hands out a preformatted message; the parser knows where.
+    let position = match regex_syntax::Parser::new().parse(pattern) {
+      Err(regex_syntax::Error::Parse(e)) => Some(e.span().start.offset),
+      Err(regex_syntax::Error::Translate(e)) => Some(e.span().start.offset),
+      _ => None,
+    };
+    let message = e.to_string().lines().last().unwrap_or_default().trim().to_string();
+    match position {
+      Some(at) => Error::Config(format!("Nieprawidłowy filename_pattern (pozycja {}): {}", at, message)),
+      None => Error::Config(format!("Nieprawidłowy filename_pattern: {}", message)),
+    }
+  })
+}
+
+enum Segment<'a> {
+  Text(&'a str),
+  /// `{{name}}` or `{{name|filter}}`.
+  Field(&'a str, Option<&'a str>),
+}
+
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, Error> {
+  let mut segments = Vec::new();
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    segments.push(Segment::Text(&rest[..start]));
+    let after = &rest[start + 2..];
+    let end = after.find("}}").ok_or_else(|| Error::Config(format!("Niezamknięty znacznik {{{{ w szablonie: {}", template)))?;
+    let inner = after[..end].trim();
+    segments.push(match inner.split_once('|') {
+      Some((name, filter)) => Segment::Field(name.trim(), Some(filter.trim())),
+      None => Segment::Field(inner, None),
+    });
+    rest = &after[end + 2..];
+  }
+  segments.push(Segment::Text(rest));
+  Ok(segments)
+}
+
+fn fields(template: &str) -> Result<Vec<(&str, Option<&str>)>, Error> {
+  Ok(parse(template)?.into_iter().filter_map(|s| if let Segment::Field(n, f) = s { Some((n, f)) } else { None }).collect())
+}
+
+fn title_case(s: &str) -> String {
+  s.split(['-', '_', ' '])
+    .filter(|w| !w.is_empty())
+    .map(|w| {
+      let mut chars = w.chars();
+      chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn render(template: &str, vars: &BTreeMap<String, String>) -> Result<String, Error> {
+  let mut out = String::new();
+  for segment in parse(template)? {
+    match segment {
+      Segment::Text(text) => out.push_str(text),
+      Segment::Field(name, filter) => {
+        let value = vars.get(name).map(String::as_str).unwrap_or_default();
+        out.push_str(&match filter {
+          Some("title") => title_case(value),
+          Some("upper") => value.to_uppercase(),
+          Some("lower") => value.to_lowercase(),
+          _ => value.to_string(),
+        });
+      }
+    }
+  }
+  Ok(out)
+}
+
+/// Rejects a pattern that doesn't compile and templates referring to
+/// anything but the pattern's groups, the built-ins and known filters.
+pub fn validate(tpl: &MetadataTemplate) -> Result<(), Error> {
+  let re = compile(&tpl.filename_pattern)?;
+  let groups: Vec<&str> = re.capture_names().flatten().collect();
+  for (field, template) in [("title", &tpl.title), ("description", &tpl.description)] {
+    for (name, filter) in fields(template)? {
+      if !groups.contains(&name) && !BUILTINS.contains(&name) {
+        return Err(Error::Config(format!("Szablon {} używa nieznanego pola {{{{{}}}}}", field, name)));
+      }
+      if let Some(f) = filter.filter(|f| !FILTERS.contains(f)) {
+        return Err(Error::Config(format!("Szablon {} używa nieznanego filtra {} (dostępne: {})", field, f, FILTERS.join(", "))));
+      }
+    }
+  }
+  if tpl.title.trim().is_empty() {
+    return Err(Error::Config("metadata_template.title nie może być pusty".into()));
+  }
+  Ok(())
+}
+
+fn needs_channel_title(tpl: &MetadataTemplate) -> bool {
+  [&tpl.title, &tpl.description].iter().any(|t| fields(t).unwrap_or_default().iter().any(|(n, _)| *n == "channel_title"))
+}
+
+/// Title of the selected channel, or the account's first one.
+async fn channel_title(dir: &Path) -> Result<String, Error> {
+  let selected = read_config_from_dir(dir).and_then(|c| c.channel_id);
+  let channels = channel::list_channels(dir).await?;
+  let pick = channels.iter().find(|c| Some(&c.id) == selected.as_ref()).or(channels.first());
+  Ok(pick.map(|c| c.title.clone()).unwrap_or_default())
+}
+
+/// Applies the configured template to `file_name`; `None` when there is no
+/// template configured.
+async fn apply(dir: &Path, file_name: &str) -> Result<Option<MetadataPreview>, Error> {
+  let Some(tpl) = read_config_from_dir(dir).and_then(|c| c.metadata_template) else {
+    return Ok(None);
+  };
+  let re = compile(&tpl.filename_pattern)?;
+  let stem = Path::new(file_name).file_stem().and_then(|s| s.to_str()).unwrap_or(file_name);
+  let Some(caps) = re.captures(stem) else {
+    return Ok(Some(MetadataPreview { matched: false, groups: BTreeMap::new(), title: None, description: None }));
+  };
+  let groups: BTreeMap<String, String> = re
+    .capture_names()
+    .flatten()
+    .map(|n| (n.to_string(), caps.name(n).map(|m| m.as_str().to_string()).unwrap_or_default()))
+    .collect();
+  let mut vars = groups.clone();
+  vars.entry("date".into()).or_insert_with(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+  vars.entry("file_stem".into()).or_insert_with(|| stem.to_string());
+  if needs_channel_title(&tpl) && !vars.contains_key("channel_title") {
+    vars.insert("channel_title".into(), channel_title(dir).await?);
+  }
+  Ok(Some(MetadataPreview {
+    matched: true,
+    groups,
+    title: Some(render(&tpl.title, &vars)?),
+    description: Some(render(&tpl.description, &vars)?),
+  }))
+}
+
+/// What the template would produce for `file_name`; nothing is uploaded.
+pub async fn preview(dir: &Path, file_name: &str) -> Result<MetadataPreview, Error> {
+  apply(dir, file_name).await?.ok_or_else(|| Error::Config("Brak metadata_template w konfiguracji".into()))
+}
+
+fn with_chapters(dir: &Path, video: &Path, description: String, placement: ChapterPlacement) -> Result<String, Error> {
+  let Some(list) = chapters::read_sidecar(video)? else {
+    return Ok(description);
+  };
+  let block = chapters::format(&list)?;
+  for w in &block.warnings {
+    runtime::for_dir(dir).warn("chapters", format!("{}: {}", video.display(), w));
+  }
+  Ok(chapters::apply_to_description(&description, &block.text, placement))
+}
+
+pub fn sidecar_path(video: &Path) -> PathBuf {
+  video.with_extension("json")
+}
+
+/// Metadata for `video`: the sidecar's fields, with the title (and, if the
+/// sidecar has none, the description) generated from the filename when the
+/// sidecar doesn't set a title, and chapters added when `chapters_placement`
+/// is set. Privacy defaults to private.
+pub async fn load_video_metadata(dir: &Path, video: &Path) -> Result<VideoMetadata, Error> {
+  load_with_sidecar(dir, video, &sidecar_path(video)).await
+}
+
+/// [`load_video_metadata`] reading the sidecar from `path` instead of next
+/// to the video.
+pub async fn load_with_sidecar(dir: &Path, video: &Path, path: &Path) -> Result<VideoMetadata, Error> {
+  let sidecar: Sidecar = match sidecar::read_text(path)? {
+    Some(text) => {
+      sidecar::check(dir, path, &text)?;
+      serde_json::from_str(&text).map_err(|e| Error::Validation(format!("Nieprawidłowy plik {}: {}", path.display(), e)))?
+    }
+    None => Sidecar::default(),
+  };
+  let explicit = sidecar.title.filter(|t| !t.trim().is_empty());
+  let (title, description) = match explicit {
+    Some(title) => (title, sidecar.description.unwrap_or_default()),
+    None => {
+      // A name that isn't UTF-8 can't become a title without guessing.
+      let file_name = video
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| Error::Validation(format!("Nazwa pliku {} nie jest w UTF-8: podaj tytuł w {}", video.display(), path.display())))?;
+      match apply(dir, file_name).await? {
+        Some(MetadataPreview { matched: true, title: Some(title), description, .. }) => {
+          (title, sidecar.description.or(description).unwrap_or_default())
+        }
+        _ => {
+          return Err(Error::Validation(format!(
+            "Brak tytułu dla {}: dodaj go w {} albo ustaw pasujący metadata_template",
+            video.display(),
+            path.display()
+          )))
+        }
+      }
+    }
+  };
+  let description = match read_config_from_dir(dir).and_then(|c| c.chapters_placement) {
+    Some(placement) => with_chapters(dir, video, description, placement)?,
+    None => description,
+  };
+  Ok(VideoMetadata {
+    title,
+    description,
+    tags: sidecar.tags,
+    category_id: sidecar.category_id,
+    privacy: sidecar.privacy.unwrap_or(Privacy::Private),
+    publish_at: sidecar.publish_at,
+    thumbnail: sidecar.thumbnail.map(|t| video.parent().unwrap_or(Path::new("")).join(t)),
+    first_comment: sidecar.first_comment.filter(|c| !c.trim().is_empty()),
+  })
+}
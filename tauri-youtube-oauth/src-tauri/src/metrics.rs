@@ -0,0 +1,209 @@
+use crate::{
+  error::Error,
+  now_secs,
+  observe::Exchange,
+  runtime,
+  timeouts::OperationClass,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, VecDeque},
+  net::IpAddr,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+/// Latency percentiles cover this much recent traffic.
+const LATENCY_WINDOW: Duration = Duration::from_secs(15 * 60);
+/// Samples kept per class; older ones drop even inside the window.
+const MAX_SAMPLES: usize = 2000;
+
+#[derive(Default)]
+struct ClassCounters {
+  requests: u64,
+  errors: BTreeMap<String, u64>,
+  bytes_uploaded: u64,
+  chunk_bytes: Option<u64>,
+  /// When, how long, and the trace id.
+  latencies: VecDeque<(Instant, u64, String)>,
+}
+
+/// Counters since process start or the last reset.
+#[derive(Default)]
+pub(crate) struct Metrics {
+  since: Option<u64>,
+  auth: ClassCounters,
+  api: ClassCounters,
+  upload: ClassCounters,
+  outcomes: Outcomes,
+}
+
+/// Whole operations rather than requests.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Outcomes {
+  /// Uploads that ended with a video id; a shutdown that interrupts one
+  /// counts as neither.
+  pub uploads_succeeded: u64,
+  pub uploads_failed: u64,
+  pub token_refreshes: u64,
+  pub token_refresh_failures: u64,
+}
+
+/// `metrics_endpoint` in the config: where the `metrics-endpoint` build
+/// serves `/metrics`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MetricsEndpointConfig {
+  pub port: u16,
+  /// Loopback unless set; anything else exposes the counters to the network.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub address: Option<IpAddr>,
+  /// Keeps the settings but serves nothing.
+  #[serde(default)]
+  pub disabled: bool,
+}
+
+impl Metrics {
+  fn class(&mut self, class: OperationClass) -> &mut ClassCounters {
+    match class {
+      OperationClass::Auth => &mut self.auth,
+      OperationClass::Api => &mut self.api,
+      OperationClass::UploadChunk => &mut self.upload,
+    }
+  }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ClassMetrics {
+  pub requests: u64,
+  pub error_count: u64,
+  /// Failures by reason: the API's `reason` for HTTP errors, the error code
+  /// (`NETWORK_ERROR`, `TIMED_OUT`) for transport failures.
+  pub errors: BTreeMap<String, u64>,
+  pub p50_ms: Option<u64>,
+  pub p95_ms: Option<u64>,
+  pub bytes_uploaded: u64,
+  /// Chunk size the latest upload is using; uploads only.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub chunk_bytes: Option<u64>,
+  /// The slowest request of the latency window.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub slowest: Option<SlowRequest>,
+}
+
+/// A request worth finding in proxy logs by its `X-Client-Trace-Id`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SlowRequest {
+  pub trace_id: String,
+  pub latency_ms: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MetricsSnapshot {
+  /// Unix seconds the counters started at.
+  pub since: u64,
+  pub auth: ClassMetrics,
+  pub data_api: ClassMetrics,
+  pub upload: ClassMetrics,
+  #[serde(flatten)]
+  pub outcomes: Outcomes,
+}
+
+fn reason(e: &Exchange) -> Option<String> {
+  if let Some(err) = e.error {
+    return Some(match err {
+      Error::Api { reason, .. } | Error::Forbidden { reason, .. } if !reason.is_empty() => reason.clone(),
+      err => err.code().to_string(),
+    });
+  }
+  let status = e.status.filter(|s| *s >= 400)?;
+  let json: serde_json::Value = serde_json::from_str(e.body).unwrap_or_default();
+  // Data API: {"error": {"errors": [{"reason"}]}}; token endpoint: {"error": "invalid_grant"}.
+  let reason = json["error"]["errors"][0]["reason"].as_str().or_else(|| json["error"].as_str());
+  Some(reason.map(str::to_string).unwrap_or_else(|| format!("http_{}", status)))
+}
+
+pub(crate) fn record(dir: &Path, e: &Exchange) {
+  let reason = reason(e);
+  let latency_ms = e.started.elapsed().as_millis() as u64;
+  let rt = runtime::for_dir(dir);
+  let mut m = rt.metrics();
+  m.since.get_or_insert_with(now_secs);
+  let c = m.class(e.class);
+  c.requests += 1;
+  match reason {
+    Some(reason) => *c.errors.entry(reason).or_default() += 1,
+    None => c.bytes_uploaded += e.bytes_sent,
+  }
+  if c.latencies.len() == MAX_SAMPLES {
+    c.latencies.pop_front();
+  }
+  c.latencies.push_back((Instant::now(), latency_ms, e.trace_id.to_string()));
+}
+
+/// Counts a finished upload; `ok` when it got a video id.
+pub(crate) fn upload_finished(dir: &Path, ok: bool) {
+  let rt = runtime::for_dir(dir);
+  let mut m = rt.metrics();
+  m.since.get_or_insert_with(now_secs);
+  match ok {
+    true => m.outcomes.uploads_succeeded += 1,
+    false => m.outcomes.uploads_failed += 1,
+  }
+}
+
+/// Counts a token refresh; `ok` when new tokens were saved.
+pub(crate) fn refresh_finished(dir: &Path, ok: bool) {
+  let rt = runtime::for_dir(dir);
+  let mut m = rt.metrics();
+  m.since.get_or_insert_with(now_secs);
+  match ok {
+    true => m.outcomes.token_refreshes += 1,
+    false => m.outcomes.token_refresh_failures += 1,
+  }
+}
+
+pub(crate) fn set_chunk_size(dir: &Path, bytes: u64) {
+  runtime::for_dir(dir).metrics().upload.chunk_bytes = Some(bytes);
+}
+
+/// Nearest-rank percentile of sorted `values`.
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+  let rank = ((p * sorted.len() as f64).ceil() as usize).max(1);
+  sorted.get(rank - 1).copied()
+}
+
+fn summarize(c: &mut ClassCounters) -> ClassMetrics {
+  while c.latencies.front().is_some_and(|(at, ..)| at.elapsed() > LATENCY_WINDOW) {
+    c.latencies.pop_front();
+  }
+  let slowest = c.latencies.iter().max_by_key(|(_, ms, _)| *ms).map(|(_, ms, id)| SlowRequest { trace_id: id.clone(), latency_ms: *ms });
+  let mut sorted: Vec<u64> = c.latencies.iter().map(|(_, ms, _)| *ms).collect();
+  sorted.sort_unstable();
+  ClassMetrics {
+    requests: c.requests,
+    error_count: c.errors.values().sum(),
+    errors: c.errors.clone(),
+    p50_ms: percentile(&sorted, 0.5),
+    p95_ms: percentile(&sorted, 0.95),
+    bytes_uploaded: c.bytes_uploaded,
+    chunk_bytes: c.chunk_bytes,
+    slowest,
+  }
+}
+
+/// Current counters; with `reset` they start over from zero afterwards.
+pub fn snapshot(dir: &Path, reset: bool) -> MetricsSnapshot {
+  let rt = runtime::for_dir(dir);
+  let mut m = rt.metrics();
+  let snapshot = MetricsSnapshot {
+    since: *m.since.get_or_insert_with(now_secs),
+    auth: summarize(&mut m.auth),
+    data_api: summarize(&mut m.api),
+    upload: summarize(&mut m.upload),
+    outcomes: m.outcomes,
+  };
+  if reset {
+    *m = Metrics { since: Some(now_secs()), ..Default::default() };
+  }
+  snapshot
+}
@@ -0,0 +1,117 @@
+//! Prometheus text exposition of the counters `get_metrics` returns, plus
+//! gauges read at scrape time, for headless runs where nobody calls the
+//! command. [`start`] serves `GET /metrics` on `metrics_endpoint.port`,
+//! loopback unless `address` says otherwise; the config is read when it
+//! starts, so a change takes a restart.
+
+use crate::{
+  error::Error,
+  metrics::{self, ClassMetrics},
+  now_secs, queue, quota, read_config_from_dir, read_tokens_from_dir, runtime,
+};
+use std::{
+  fmt::Write,
+  net::{IpAddr, Ipv4Addr, SocketAddr},
+  path::{Path, PathBuf},
+};
+use tokio::sync::oneshot;
+use warp::{http::header, Filter, Reply};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The running listener.
+pub(crate) struct MetricsServer {
+  addr: SocketAddr,
+  shutdown: oneshot::Sender<()>,
+}
+
+fn label(value: &str) -> String {
+  value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+  let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+/// Everything `/metrics` answers with: the `get_metrics` counters, so the
+/// two agree, and the queue, quota and token gauges as they are now.
+pub fn render(dir: &Path) -> String {
+  let m = metrics::snapshot(dir, false);
+  let classes: [(&str, &ClassMetrics); 3] = [("auth", &m.auth), ("data_api", &m.data_api), ("upload", &m.upload)];
+  let mut out = String::new();
+
+  family(&mut out, "ytlite_uploads_total", "counter", "Uploads finished, by outcome.");
+  let _ = writeln!(out, "ytlite_uploads_total{{outcome=\"succeeded\"}} {}", m.outcomes.uploads_succeeded);
+  let _ = writeln!(out, "ytlite_uploads_total{{outcome=\"failed\"}} {}", m.outcomes.uploads_failed);
+  family(&mut out, "ytlite_token_refreshes_total", "counter", "Access token refreshes, by outcome.");
+  let _ = writeln!(out, "ytlite_token_refreshes_total{{outcome=\"succeeded\"}} {}", m.outcomes.token_refreshes);
+  let _ = writeln!(out, "ytlite_token_refreshes_total{{outcome=\"failed\"}} {}", m.outcomes.token_refresh_failures);
+  family(&mut out, "ytlite_requests_total", "counter", "HTTP requests sent, by class.");
+  for (class, c) in classes {
+    let _ = writeln!(out, "ytlite_requests_total{{class=\"{}\"}} {}", class, c.requests);
+  }
+  family(&mut out, "ytlite_api_errors_total", "counter", "Failed requests, by class and reason.");
+  for (class, c) in classes {
+    for (reason, n) in &c.errors {
+      let _ = writeln!(out, "ytlite_api_errors_total{{class=\"{}\",reason=\"{}\"}} {}", class, label(reason), n);
+    }
+  }
+  family(&mut out, "ytlite_uploaded_bytes_total", "counter", "Bytes of media sent.");
+  let _ = writeln!(out, "ytlite_uploaded_bytes_total {}", m.upload.bytes_uploaded);
+
+  family(&mut out, "ytlite_queue_depth", "gauge", "Queue entries pending or uploading.");
+  let _ = writeln!(out, "ytlite_queue_depth {}", queue::depth(dir));
+  let q = quota::status(dir);
+  family(&mut out, "ytlite_quota_used_units", "gauge", "Data API units spent today, as counted locally.");
+  let _ = writeln!(out, "ytlite_quota_used_units {}", q.used);
+  family(&mut out, "ytlite_quota_limit_units", "gauge", "Daily Data API allowance.");
+  let _ = writeln!(out, "ytlite_quota_limit_units {}", q.limit);
+  if let Some(t) = read_tokens_from_dir(dir).filter(|t| t.expires_in > 0) {
+    family(&mut out, "ytlite_token_expiry_seconds", "gauge", "Seconds until the access token expires; negative once it has.");
+    let _ = writeln!(out, "ytlite_token_expiry_seconds {}", (t.created_at + t.expires_in) as i64 - now_secs() as i64);
+  }
+  out
+}
+
+/// Starts serving `/metrics` as configured; returns the bound address, or
+/// `None` (and stops a running listener) when the config has no enabled
+/// `metrics_endpoint`.
+pub fn start(dir: &Path) -> Result<Option<SocketAddr>, Error> {
+  let rt = runtime::for_dir(dir);
+  let mut server = rt.metrics_server();
+  if let Some(old) = server.take() {
+    let _ = old.shutdown.send(());
+  }
+  let Some(cfg) = read_config_from_dir(dir).and_then(|c| c.metrics_endpoint).filter(|m| !m.disabled) else {
+    return Ok(None);
+  };
+  let addr = SocketAddr::new(cfg.address.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST)), cfg.port);
+  let dir: PathBuf = dir.to_path_buf();
+  let route = warp::path("metrics")
+    .and(warp::path::end())
+    .and(warp::get())
+    .map(move || warp::reply::with_header(render(&dir), header::CONTENT_TYPE, CONTENT_TYPE).into_response());
+  let (tx, rx) = oneshot::channel::<()>();
+  let (bound, serving) = warp::serve(route)
+    .try_bind_with_graceful_shutdown(addr, async {
+      let _ = rx.await;
+    })
+    .map_err(|e| Error::Network(format!("Nie można udostępnić metryk na {}: {}", addr, e)))?;
+  tokio::spawn(serving);
+  *server = Some(MetricsServer { addr: bound, shutdown: tx });
+  Ok(Some(bound))
+}
+
+/// Stops the listener; returns whether one was running.
+pub fn stop(dir: &Path) -> bool {
+  let Some(s) = runtime::for_dir(dir).metrics_server().take() else {
+    return false;
+  };
+  let _ = s.shutdown.send(());
+  true
+}
+
+/// Where the listener is bound, while it runs.
+pub fn address(dir: &Path) -> Option<SocketAddr> {
+  runtime::for_dir(dir).metrics_server().as_ref().map(|s| s.addr)
+}
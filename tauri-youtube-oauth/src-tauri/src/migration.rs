@@ -0,0 +1,211 @@
+//! Finding credentials left behind when the config dir moved: a renamed OS
+//! account (`C:\Users\old\…` → `C:\Users\new\…`), an older bundle
+//! identifier, or a dir named with `--migrate-from`. Startup only looks and
+//! offers them with `migration-available`; nothing is copied until
+//! `migrate_storage` is called for one of them.
+
+use crate::{auth, config, error::Error, now_secs, read_config_from_dir, runtime, setup, storage, Tokens};
+use serde::Serialize;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::UNIX_EPOCH,
+};
+
+/// `--migrate-from <path>`: a location to offer besides the probed ones.
+pub const MIGRATE_FROM_ARG: &str = "--migrate-from";
+/// Bundle identifiers earlier builds kept their config dir under.
+pub const LEGACY_IDENTIFIERS: &[&str] = &["tauri-youtube-oauth", "com.tauri.dev"];
+/// Overwritten files go to `<dir>/migration-backup-<unix seconds>/`.
+const BACKUP_PREFIX: &str = "migration-backup-";
+
+/// A location with credentials that could be copied in.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationSource {
+  pub path: String,
+  pub client_id: String,
+  pub has_tokens: bool,
+  /// Unix seconds tokens.json (without one, the config) was last written.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub modified_at: Option<u64>,
+}
+
+/// Payload of `migration-available`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationAvailable {
+  /// The config dir in use, which has no credentials.
+  pub target: String,
+  pub sources: Vec<MigrationSource>,
+}
+
+/// What `migrate_storage` did. Paths are relative to the config dir.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+  pub from: String,
+  pub copied: Vec<String>,
+  /// Files that were replaced; the old versions are under `backup_dir`.
+  pub backed_up: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub backup_dir: Option<String>,
+}
+
+/// The path after [`MIGRATE_FROM_ARG`] in `args` (program name excluded).
+pub fn from_args(args: &[String]) -> Option<PathBuf> {
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    if arg == MIGRATE_FROM_ARG {
+      return it.next().filter(|p| !p.is_empty()).map(PathBuf::from);
+    }
+    if let Some(v) = arg.strip_prefix("--migrate-from=").filter(|v| !v.is_empty()) {
+      return Some(PathBuf::from(v));
+    }
+  }
+  None
+}
+
+/// Where credentials for `dir` may have been left, `explicit` first: the dir
+/// under each legacy identifier next to it and, when `dir` is under `home`,
+/// the same places in the other accounts' homes beside it.
+pub fn candidates(dir: &Path, home: Option<&Path>, explicit: Option<&Path>) -> Vec<PathBuf> {
+  let mut out: Vec<PathBuf> = explicit.map(Path::to_path_buf).into_iter().collect();
+  let with_legacy = |dir: &Path| {
+    let mut all = vec![dir.to_path_buf()];
+    if let Some(parent) = dir.parent() {
+      all.extend(LEGACY_IDENTIFIERS.iter().map(|id| parent.join(id)));
+    }
+    all
+  };
+  out.extend(with_legacy(dir));
+  if let Some((home, relative)) = home.and_then(|h| Some((h, dir.strip_prefix(h).ok()?))) {
+    let mut others: Vec<PathBuf> = home
+      .parent()
+      .and_then(|p| fs::read_dir(p).ok())
+      .into_iter()
+      .flatten()
+      .filter_map(|e| e.ok().map(|e| e.path()))
+      .filter(|p| p.is_dir() && p != home)
+      .collect();
+    others.sort();
+    for other in others {
+      out.extend(with_legacy(&other.join(relative)));
+    }
+  }
+  let mut unique: Vec<PathBuf> = Vec::new();
+  for path in out {
+    if path != dir && !unique.contains(&path) {
+      unique.push(path);
+    }
+  }
+  unique
+}
+
+/// No config and no tokens: what a fresh install and a moved dir look like.
+pub fn is_empty(dir: &Path) -> bool {
+  !dir.join("oauth_config.json").exists() && !dir.join("tokens.json").exists()
+}
+
+fn modified(path: &Path) -> Option<u64> {
+  let at = fs::metadata(path).ok()?.modified().ok()?;
+  Some(at.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// `from` as a source: a valid config, and tokens.json readable if present.
+pub fn inspect(from: &Path) -> Result<MigrationSource, Error> {
+  if !from.join("oauth_config.json").is_file() {
+    return Err(Error::Validation(format!("{} nie zawiera oauth_config.json", from.display())));
+  }
+  let cfg = read_config_from_dir(from).ok_or_else(|| Error::Validation(format!("Nieczytelny oauth_config.json w {}", from.display())))?;
+  config::validate(&cfg).map_err(|e| e.context(&from.display().to_string()))?;
+  let tokens = from.join("tokens.json");
+  let has_tokens = tokens.is_file();
+  if has_tokens {
+    let text = fs::read_to_string(&tokens).map_err(|e| Error::Storage(format!("{}: {}", tokens.display(), e)))?;
+    serde_json::from_str::<Tokens>(&text).map_err(|e| Error::Validation(format!("Uszkodzony tokens.json w {}: {}", from.display(), e)))?;
+  }
+  Ok(MigrationSource {
+    path: from.display().to_string(),
+    client_id: cfg.client_id,
+    has_tokens,
+    modified_at: modified(&tokens).or_else(|| modified(&from.join("oauth_config.json"))),
+  })
+}
+
+/// When `dir` has no credentials, the `candidates` that have them, also
+/// emitted as `migration-available`. Copies nothing.
+pub fn probe(dir: &Path, candidates: &[PathBuf]) -> Option<MigrationAvailable> {
+  if !is_empty(dir) {
+    return None;
+  }
+  let sources: Vec<MigrationSource> = candidates.iter().filter_map(|c| inspect(c).ok()).collect();
+  if sources.is_empty() {
+    return None;
+  }
+  let available = MigrationAvailable { target: dir.display().to_string(), sources };
+  runtime::for_dir(dir).emit("migration-available", &available);
+  Some(available)
+}
+
+/// Files under `root` worth copying, relative to it: not the lock, leftover
+/// temp files or earlier migration backups.
+fn files(root: &Path, relative: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+  for entry in fs::read_dir(root.join(relative))? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().into_owned();
+    let path = relative.join(&name);
+    if name == storage::LOCK_FILE || name.ends_with(".tmp") || (relative.as_os_str().is_empty() && name.starts_with(BACKUP_PREFIX)) {
+      continue;
+    }
+    if entry.file_type()?.is_dir() {
+      files(root, &path, out)?;
+    } else {
+      out.push(path);
+    }
+  }
+  Ok(())
+}
+
+/// Copies everything in `from` into `dir` after checking it with
+/// [`inspect`]. Files `dir` already has are copied to a backup dir first;
+/// `from` is left as it is.
+pub fn migrate(dir: &Path, from: &Path) -> Result<MigrationReport, Error> {
+  let storage_err = |path: &Path, e: std::io::Error| Error::Storage(format!("{}: {}", path.display(), e));
+  let source = from.canonicalize().map_err(|e| Error::Validation(format!("Nie znaleziono {}: {}", from.display(), e)))?;
+  // Checked before `dir` is created, so a target inside `from` leaves no trace there.
+  let target = std::path::absolute(dir).map_err(|e| storage_err(dir, e))?;
+  let target = target.canonicalize().unwrap_or(target);
+  if source.starts_with(&target) || target.starts_with(&source) {
+    return Err(Error::Validation(format!("{} i {} to ten sam lub zagnieżdżony katalog", source.display(), target.display())));
+  }
+  inspect(&source)?;
+  fs::create_dir_all(dir).map_err(|e| storage_err(dir, e))?;
+  let mut relative = Vec::new();
+  files(&source, Path::new(""), &mut relative).map_err(|e| storage_err(&source, e))?;
+  relative.sort();
+
+  let lock = storage::lock(dir)?;
+  let backup = dir.join(format!("{}{}", BACKUP_PREFIX, now_secs()));
+  let mut report = MigrationReport { from: source.display().to_string(), copied: Vec::new(), backed_up: Vec::new(), backup_dir: None };
+  for rel in &relative {
+    let (src, dst) = (source.join(rel), dir.join(rel));
+    let data = fs::read(&src).map_err(|e| storage_err(&src, e))?;
+    if dst.is_file() {
+      let kept = backup.join(rel);
+      fs::create_dir_all(kept.parent().unwrap_or(&backup)).map_err(|e| storage_err(&kept, e))?;
+      fs::copy(&dst, &kept).map_err(|e| storage_err(&kept, e))?;
+      report.backed_up.push(rel.display().to_string());
+    }
+    fs::create_dir_all(dst.parent().unwrap_or(dir)).map_err(|e| storage_err(&dst, e))?;
+    storage::write_atomic(&dst, data).map_err(|e| storage_err(&dst, e))?;
+    report.copied.push(rel.display().to_string());
+  }
+  if !report.backed_up.is_empty() {
+    report.backup_dir = Some(backup.display().to_string());
+  }
+  drop(lock);
+  if let Err(e) = config::reload(dir) {
+    runtime::for_dir(dir).warn("migration", format!("Konfiguracja po migracji: {}", e));
+  }
+  auth::notify_changed(dir);
+  setup::notify(dir);
+  Ok(report)
+}
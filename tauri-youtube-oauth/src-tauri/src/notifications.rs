@@ -0,0 +1,178 @@
+//! Desktop notifications for uploads nobody is watching: a queue batch
+//! finished, its first entry failed, the login has to be renewed, or the
+//! daily quota paused the queue. Off unless `desktop_notifications` is set.
+//! The texts come from the catalog below in the app language and name files
+//! by basename only. The core builds them and hands them to the notifier the
+//! app attaches; a missing notifier or a denied permission drops them.
+
+use crate::{format, read_config_from_dir, runtime};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NoticeKind {
+  QueueFinished,
+  FirstFailure,
+  ReauthRequired,
+  QuotaPaused,
+  Test,
+}
+
+/// One notification as shown.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Notice {
+  pub kind: NoticeKind,
+  pub title: String,
+  pub body: String,
+}
+
+/// What `test_notification` reports.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NotificationTest {
+  /// `desktop_notifications` in the config; the test is sent either way.
+  pub enabled: bool,
+  /// The system took the notification.
+  pub delivered: bool,
+  pub error: Option<String>,
+}
+
+/// Counts of the queue batch under way; a batch ends once nothing is left
+/// pending.
+#[derive(Default)]
+pub(crate) struct Batch {
+  succeeded: usize,
+  failed: usize,
+  quota_paused: bool,
+  reauth_sent: bool,
+}
+
+/// Catalog texts are `[pl, en]`; `{name}` stands for a value.
+struct Entry {
+  title: [&'static str; 2],
+  body: [&'static str; 2],
+}
+
+const QUEUE_FINISHED: Entry = Entry {
+  title: ["Kolejka wysłana", "Upload queue finished"],
+  body: ["Wysłano: {succeeded}, nieudane: {failed}.", "Uploaded: {succeeded}, failed: {failed}."],
+};
+const FIRST_FAILURE: Entry = Entry {
+  title: ["Nie udało się wysłać filmu", "An upload failed"],
+  body: ["{file} ({code}). Pozostałe pliki są wysyłane dalej.", "{file} ({code}). The remaining files keep uploading."],
+};
+const REAUTH_REQUIRED: Entry = Entry {
+  title: ["Zaloguj się ponownie", "Sign in again"],
+  body: [
+    "YouTube nie przyjmuje już zapisanego logowania; wysyłanie czeka na ponowne zalogowanie.",
+    "YouTube no longer accepts the saved login; uploads wait until you sign in again.",
+  ],
+};
+const QUOTA_PAUSED: Entry = Entry {
+  title: ["Kolejka wstrzymana", "Upload queue paused"],
+  body: [
+    "Dzienny limit YouTube API się wyczerpał. Oczekujące pliki ({pending}) zostaną wysłane po odnowieniu limitu o północy czasu pacyficznego.",
+    "The daily YouTube API quota ran out. The pending files ({pending}) upload once it resets at midnight Pacific time.",
+  ],
+};
+const TEST: Entry = Entry {
+  title: ["Powiadomienia działają", "Notifications work"],
+  body: ["Tak będą wyglądać powiadomienia o kolejce wysyłania.", "This is how upload queue notifications will look."],
+};
+
+fn render(dir: &Path, kind: NoticeKind, entry: &Entry, values: &[(&str, String)]) -> Notice {
+  let i = match format::language(dir) {
+    format::Language::Pl => 0,
+    format::Language::En => 1,
+  };
+  let mut body = entry.body[i].to_string();
+  for (name, value) in values {
+    body = body.replace(&format!("{{{}}}", name), value);
+  }
+  Notice { kind, title: entry.title[i].to_string(), body }
+}
+
+/// Routes notices of `dir` to `show` unless a notifier is already attached.
+/// `show` fails when the system refused, e.g. without permission.
+pub fn attach(dir: &Path, show: impl Fn(&Notice) -> Result<(), String> + Send + Sync + 'static) {
+  runtime::for_dir(dir).attach_notifier(show);
+}
+
+pub fn enabled(dir: &Path) -> bool {
+  read_config_from_dir(dir).is_some_and(|c| c.desktop_notifications)
+}
+
+/// Shows `notice` when notifications are on. Whatever the system says, the
+/// caller carries on.
+fn send(dir: &Path, notice: Notice) {
+  if enabled(dir) {
+    let _ = runtime::for_dir(dir).deliver(&notice);
+  }
+}
+
+/// Sends a sample notice regardless of the toggle, to check the permission.
+pub fn test(dir: &Path) -> NotificationTest {
+  let result = runtime::for_dir(dir).deliver(&render(dir, NoticeKind::Test, &TEST, &[]));
+  NotificationTest { enabled: enabled(dir), delivered: result.is_ok(), error: result.err() }
+}
+
+fn basename(path: &str) -> String {
+  Path::new(path).file_name().map_or_else(|| path.to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+fn with_batch<T>(dir: &Path, f: impl FnOnce(&mut Batch) -> T) -> T {
+  f(&mut runtime::for_dir(dir).notice_batch())
+}
+
+/// A queue entry uploaded.
+pub(crate) fn entry_succeeded(dir: &Path) {
+  with_batch(dir, |b| {
+    b.succeeded += 1;
+    b.quota_paused = false;
+  });
+}
+
+/// A queue entry failed with error `code`; only the first of a batch is shown.
+pub(crate) fn entry_failed(dir: &Path, file_path: &str, code: &str) {
+  let first = with_batch(dir, |b| {
+    b.failed += 1;
+    b.failed == 1
+  });
+  if first {
+    send(dir, render(dir, NoticeKind::FirstFailure, &FIRST_FAILURE, &[("file", basename(file_path)), ("code", code.to_string())]));
+  }
+}
+
+/// The queue stopped on the quota with `pending` entries left; shown once
+/// per pause.
+pub(crate) fn quota_paused(dir: &Path, pending: usize) {
+  let already = with_batch(dir, |b| std::mem::replace(&mut b.quota_paused, true));
+  if !already {
+    send(dir, render(dir, NoticeKind::QuotaPaused, &QUOTA_PAUSED, &[("pending", pending.to_string())]));
+  }
+}
+
+/// Nothing is left pending: ends the batch, if it uploaded anything.
+pub(crate) fn queue_drained(dir: &Path) {
+  let (succeeded, failed) = with_batch(dir, |b| {
+    b.quota_paused = false;
+    (std::mem::take(&mut b.succeeded), std::mem::take(&mut b.failed))
+  });
+  if succeeded + failed > 0 {
+    let values = [("succeeded", succeeded.to_string()), ("failed", failed.to_string())];
+    send(dir, render(dir, NoticeKind::QueueFinished, &QUEUE_FINISHED, &values));
+  }
+}
+
+/// The saved login stopped working; shown once until tokens are written again.
+pub(crate) fn reauth_required(dir: &Path) {
+  let already = with_batch(dir, |b| std::mem::replace(&mut b.reauth_sent, true));
+  if !already {
+    send(dir, render(dir, NoticeKind::ReauthRequired, &REAUTH_REQUIRED, &[]));
+  }
+}
+
+/// Tokens were written: a later rejection is news again.
+pub(crate) fn auth_restored(dir: &Path) {
+  with_batch(dir, |b| b.reauth_sent = false);
+}
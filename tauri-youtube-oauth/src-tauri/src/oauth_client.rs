@@ -0,0 +1,240 @@
+//! Which Google Cloud project the OAuth client belongs to. A client_id
+//! starts with its project number (`123456789012-….apps.googleusercontent.com`),
+//! and each login records the client, project and account it was made with
+//! in `oauth_client.json`. A config or tokens.json imported from another
+//! profile, or edited by hand, then shows up as pieces from different
+//! projects instead of a puzzling login failure.
+//!
+//! Client ids are only ever shown masked: `…` and the last few characters.
+
+use crate::{
+  channel, consent_probe, error::Error, http_headers, now_secs, profiles, read_config_from_dir, read_tokens_from_dir, runtime, storage,
+  timeouts::{self, OperationClass},
+  tls,
+};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fs, path::Path};
+
+const CLIENT_FILE: &str = "oauth_client.json";
+const GOOGLE_SUFFIX: &str = ".apps.googleusercontent.com";
+/// Characters of the client_id kept by [`mask`].
+const VISIBLE_CHARS: usize = 6;
+
+/// The project number a Google client_id starts with.
+pub fn project_number(client_id: &str) -> Option<String> {
+  let (number, rest) = client_id.split_once('-')?;
+  (!number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) && !rest.is_empty()).then(|| number.to_string())
+}
+
+/// `…` and the last characters of the id's own part, without the project
+/// number and the googleusercontent suffix.
+pub fn mask(client_id: &str) -> String {
+  let own = client_id.strip_suffix(GOOGLE_SUFFIX).unwrap_or(client_id);
+  let own = match project_number(own) {
+    Some(number) => &own[number.len() + 1..],
+    None => own,
+  };
+  let chars: Vec<char> = own.chars().collect();
+  // Short ids would be shown nearly whole.
+  let keep = if chars.len() > 2 * VISIBLE_CHARS { VISIBLE_CHARS } else { 0 };
+  format!("…{}", chars[chars.len() - keep..].iter().collect::<String>())
+}
+
+/// A client as reported: masked, with its project.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClientRef {
+  pub client_id: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub project_number: Option<String>,
+}
+
+impl ClientRef {
+  fn of(client_id: &str) -> Self {
+    ClientRef { client_id: mask(client_id), project_number: project_number(client_id) }
+  }
+}
+
+/// What the last login was made with, kept per profile.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClientAssociation {
+  pub client_id: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub project_number: Option<String>,
+  /// From the id_token; absent without an email or openid scope.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub email: Option<String>,
+  pub recorded_at: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchKind {
+  /// The configured client is from another project than the login.
+  ProjectChanged,
+  /// Same project, but another client than the login.
+  ClientChanged,
+  /// tokens.json says it was issued to another client.
+  TokensForOtherClient,
+  /// tokeninfo says the access token belongs to another client.
+  TokenAudience,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClientMismatch {
+  pub kind: MismatchKind,
+  pub message: String,
+}
+
+/// What `describe_oauth_client` returns; also the payload of `oauth-client-mismatch`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct OAuthClientDescription {
+  pub profile: String,
+  /// The client in oauth_config.json; absent without a config.
+  pub configured: Option<ClientRef>,
+  /// The client of the last login.
+  pub login: Option<ClientRef>,
+  pub account_email: Option<String>,
+  pub login_at: Option<u64>,
+  /// The client tokens.json was issued to, when it says.
+  pub tokens: Option<ClientRef>,
+  /// The client tokeninfo names for the access token; only checked by
+  /// `describe_oauth_client`, and only while the token is valid.
+  pub token_audience: Option<ClientRef>,
+  /// Empty when everything belongs together. Only looked for while there
+  /// are tokens: the next login records the association anew.
+  pub mismatches: Vec<ClientMismatch>,
+}
+
+pub fn association(dir: &Path) -> Option<ClientAssociation> {
+  fs::read_to_string(dir.join(CLIENT_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// The claims of a JWT, unverified: the token came straight from Google's
+/// token endpoint, and they're only used for reporting.
+fn claims(jwt: &str) -> Option<Value> {
+  let payload = jwt.split('.').nth(1)?;
+  serde_json::from_slice(&consent_probe::base64_decode(payload)).ok()
+}
+
+/// Records the client a login was made with. `id_token`, when the grant
+/// had one, names the account and the client Google issued it to.
+pub(crate) fn record_login(dir: &Path, client_id: &str, id_token: Option<&str>) -> Result<(), Error> {
+  let claims = id_token.and_then(claims).unwrap_or_default();
+  let issued_to = claims["azp"].as_str().or_else(|| claims["aud"].as_str()).unwrap_or(client_id);
+  let association = ClientAssociation {
+    client_id: issued_to.to_string(),
+    project_number: project_number(issued_to),
+    email: claims["email"].as_str().map(str::to_string),
+    recorded_at: now_secs(),
+  };
+  let s = serde_json::to_string_pretty(&association).map_err(|e| Error::Storage(e.to_string()))?;
+  let _lock = storage::lock(dir)?;
+  storage::write_atomic(&dir.join(CLIENT_FILE), s).map_err(|e| Error::Storage(e.to_string()))
+}
+
+/// Another profile whose configured client is from `project`.
+fn profile_with_project(dir: &Path, project: &str) -> Option<String> {
+  let root = profiles::root_of(dir);
+  let own = profiles::name_of(dir);
+  profiles::list(&root).into_iter().filter(|name| *name != own).find(|name| {
+    let cfg = profiles::dir_for(&root, name).ok().and_then(|d| read_config_from_dir(&d));
+    cfg.is_some_and(|c| project_number(&c.client_id).as_deref() == Some(project))
+  })
+}
+
+fn mismatches(
+  dir: &Path,
+  configured: &str,
+  login: Option<&ClientAssociation>,
+  tokens: Option<&str>,
+  audience: Option<&str>,
+) -> Vec<ClientMismatch> {
+  let mut out = Vec::new();
+  let mut push = |kind, message: String| out.push(ClientMismatch { kind, message });
+  let (cfg_masked, cfg_project) = (mask(configured), project_number(configured));
+  if let Some(login) = login.filter(|l| l.client_id != configured) {
+    let who = login.email.as_ref().map(|e| format!(" (konto {})", e)).unwrap_or_default();
+    match (&login.project_number, &cfg_project) {
+      (Some(was), Some(is)) if was != is => {
+        let elsewhere = profile_with_project(dir, was).map(|p| format!("; klient z projektu {} jest w profilu {}", was, p)).unwrap_or_default();
+        push(
+          MismatchKind::ProjectChanged,
+          format!(
+            "Zalogowano{} przez klienta {} z projektu {}, a skonfigurowany klient {} należy do projektu {} — dane logowania z innego projektu lub profilu{}",
+            who,
+            mask(&login.client_id),
+            was,
+            cfg_masked,
+            is,
+            elsewhere
+          ),
+        )
+      }
+      _ => push(MismatchKind::ClientChanged, format!("Zalogowano{} przez klienta {}, a skonfigurowany jest {}", who, mask(&login.client_id), cfg_masked)),
+    }
+  }
+  if let Some(tokens) = tokens.filter(|t| *t != configured) {
+    push(MismatchKind::TokensForOtherClient, format!("tokens.json wydano dla klienta {}, a skonfigurowany jest {}", mask(tokens), cfg_masked));
+  }
+  if let Some(audience) = audience.filter(|a| *a != configured) {
+    push(MismatchKind::TokenAudience, format!("Według tokeninfo token należy do klienta {}, a skonfigurowany jest {}", mask(audience), cfg_masked));
+  }
+  out
+}
+
+fn build(dir: &Path, audience: Option<String>) -> OAuthClientDescription {
+  let cfg = read_config_from_dir(dir);
+  let login = association(dir);
+  let tokens = read_tokens_from_dir(dir);
+  let tokens_client = tokens.as_ref().and_then(|t| t.client_id.clone());
+  let found = match (&cfg, &tokens) {
+    (Some(cfg), Some(_)) => mismatches(dir, &cfg.client_id, login.as_ref(), tokens_client.as_deref(), audience.as_deref()),
+    _ => Vec::new(),
+  };
+  OAuthClientDescription {
+    profile: profiles::name_of(dir),
+    configured: cfg.as_ref().map(|c| ClientRef::of(&c.client_id)),
+    login: login.as_ref().map(|l| ClientRef::of(&l.client_id)),
+    account_email: login.as_ref().and_then(|l| l.email.clone()),
+    login_at: login.as_ref().map(|l| l.recorded_at),
+    tokens: tokens_client.as_deref().map(ClientRef::of),
+    token_audience: audience.as_deref().map(ClientRef::of),
+    mismatches: found,
+  }
+}
+
+/// The client tokeninfo names for the stored access token, if it's valid.
+async fn token_audience(dir: &Path) -> Option<String> {
+  let t = read_tokens_from_dir(dir).filter(|t| !t.access_token.is_empty())?;
+  let url = channel::tokeninfo_endpoint();
+  let req = tls::request(dir, Method::GET, &url, &http_headers::trace_id())
+    .ok()?
+    .query(&[("access_token", &t.access_token)])
+    .timeout(timeouts::for_class(dir, OperationClass::Auth));
+  let resp = req.send().await.ok()?;
+  if !resp.status().is_success() {
+    return None;
+  }
+  let json: Value = resp.json().await.ok()?;
+  json["azp"].as_str().or_else(|| json["aud"].as_str()).map(str::to_string)
+}
+
+/// The configured client, the last login's and the token's, with anything
+/// that doesn't belong together.
+pub async fn describe(dir: &Path) -> OAuthClientDescription {
+  let audience = token_audience(dir).await;
+  build(dir, audience)
+}
+
+/// Emits `oauth-client-mismatch` when the stored pieces no longer match;
+/// run after the config changes. Offline: tokeninfo isn't asked.
+pub(crate) fn check(dir: &Path) -> Option<OAuthClientDescription> {
+  let description = build(dir, None);
+  if description.mismatches.is_empty() {
+    return None;
+  }
+  runtime::for_dir(dir).emit("oauth-client-mismatch", &description);
+  Some(description)
+}
@@ -0,0 +1,27 @@
+//! The one hook every HTTP exchange reports to, so capture and metrics cover
+//! each call without the call sites knowing about either.
+
+use crate::{capture, error::Error, metrics, timeouts::OperationClass};
+use std::{path::Path, time::Instant};
+
+pub(crate) struct Exchange<'a> {
+  pub class: OperationClass,
+  pub method: &'a str,
+  pub url: &'a str,
+  pub started: Instant,
+  /// None when no response arrived.
+  pub status: Option<u16>,
+  pub body: &'a str,
+  pub error: Option<&'a Error>,
+  /// Request body size, counted as uploaded when the request succeeds.
+  pub bytes_sent: u64,
+  /// 1 for the first send, 2 for the replay after a forced token refresh.
+  pub attempt: u32,
+  /// The `X-Client-Trace-Id` the request carried.
+  pub trace_id: &'a str,
+}
+
+pub(crate) fn exchange(dir: &Path, e: Exchange) {
+  capture::record(dir, &e);
+  metrics::record(dir, &e);
+}
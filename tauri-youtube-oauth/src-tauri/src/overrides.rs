@@ -0,0 +1,62 @@
+//! Forcing an upload past the checks that would refuse it, one warning code
+//! at a time, and the record of each time it happened: on the queue entry,
+//! in the audit log and in the upload history.
+
+use crate::{audit, error::Error, lint::LintRule, now_secs};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+
+/// Code of the file age check (`upload_min_file_age_secs`).
+pub const RECENTLY_MODIFIED: &str = "recentlyModified";
+
+/// A check `force` lets an upload through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForceCode {
+  /// The file was modified within `upload_min_file_age_secs`.
+  RecentlyModified,
+  /// A metadata lint warning, in block mode.
+  Lint(LintRule),
+}
+
+impl ForceCode {
+  /// `recentlyModified`, or the rule's name as in `metadata_lint.disabled`.
+  pub fn code(self) -> String {
+    match self {
+      ForceCode::RecentlyModified => RECENTLY_MODIFIED.to_string(),
+      ForceCode::Lint(rule) => serde_json::to_value(rule).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default(),
+    }
+  }
+
+  fn parse(code: &str) -> Option<Self> {
+    if code == RECENTLY_MODIFIED {
+      return Some(ForceCode::RecentlyModified);
+    }
+    serde_json::from_value(json!(code)).ok().map(ForceCode::Lint)
+  }
+}
+
+/// `codes` as checks to force past. An unknown code is refused rather than
+/// forcing nothing, so a typo can't look like an override that worked.
+pub fn parse(codes: &[String]) -> Result<Vec<ForceCode>, Error> {
+  let unknown =
+    |c: &String| Error::Validation(format!("Nieznany kod ostrzeżenia '{}' w force (np. {}, missingDescription)", c, RECENTLY_MODIFIED));
+  codes.iter().map(|c| ForceCode::parse(c.trim()).ok_or_else(|| unknown(c))).collect()
+}
+
+/// One upload that went ahead despite warnings, because they were forced.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForcedOverride {
+  /// Unix seconds.
+  pub at: u64,
+  /// The warnings that would have refused the upload, by code.
+  pub codes: Vec<String>,
+}
+
+/// Notes in the audit log that `file_path` went up past `codes`.
+pub(crate) fn record(dir: &Path, file_path: &str, title: &str, codes: Vec<String>) -> ForcedOverride {
+  let forced = ForcedOverride { at: now_secs(), codes };
+  // Like history: a full disk shouldn't stop an upload the user forced.
+  let _ = audit::record(dir, "upload_forced", json!({ "file_path": file_path, "title": title, "codes": forced.codes }));
+  forced
+}
@@ -0,0 +1,77 @@
+//! Optional app passphrase: a speed bump on shared machines before anything
+//! reveals secrets. Only its argon2 hash is stored, in the config of the
+//! `default` profile: one passphrase guards every profile, whichever is
+//! active, so each function takes any profile's dir.
+
+use crate::{error::Error, profiles, read_config_from_dir, runtime, update_config_in_dir};
+use argon2::{
+  password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+  Argon2,
+};
+use std::{
+  path::Path,
+  time::{Duration, Instant},
+};
+
+/// Failed attempts allowed per minute; past that even the right passphrase
+/// is rejected until the window clears.
+const MAX_FAILURES_PER_MINUTE: usize = 5;
+const MIN_LEN: usize = 8;
+
+fn hash(passphrase: &str) -> Result<String, Error> {
+  let salt = SaltString::generate(&mut rand_core::OsRng);
+  Argon2::default()
+    .hash_password(passphrase.as_bytes(), &salt)
+    .map(|h| h.to_string())
+    .map_err(|e| Error::Storage(format!("Nie można zapisać hasła aplikacji: {}", e)))
+}
+
+/// Verified with the argon2 params stored in `stored`, and compared in
+/// constant time by the crate.
+fn matches(stored: &str, given: &str) -> bool {
+  PasswordHash::new(stored).is_ok_and(|parsed| Argon2::default().verify_password(given.as_bytes(), &parsed).is_ok())
+}
+
+pub fn is_set(dir: &Path) -> bool {
+  read_config_from_dir(&profiles::root_of(dir)).is_some_and(|c| c.passphrase_hash.is_some())
+}
+
+/// Succeeds when no passphrase is set or `given` matches it. Missing, wrong
+/// and rate-limited all fail the same way, so a caller without the
+/// passphrase can't tell whether one is set.
+pub fn require(dir: &Path, given: Option<&str>) -> Result<(), Error> {
+  let root = profiles::root_of(dir);
+  let Some(stored) = read_config_from_dir(&root).and_then(|c| c.passphrase_hash) else {
+    return Ok(());
+  };
+  let rt = runtime::for_dir(&root);
+  let mut failures = rt.passphrase_failures();
+  failures.retain(|at| at.elapsed() < Duration::from_secs(60));
+  if failures.len() >= MAX_FAILURES_PER_MINUTE {
+    return Err(Error::PassphraseRejected);
+  }
+  if given.is_some_and(|g| matches(&stored, g)) {
+    return Ok(());
+  }
+  failures.push(Instant::now());
+  Err(Error::PassphraseRejected)
+}
+
+/// Sets or replaces the passphrase; replacing needs the current one.
+pub fn set(dir: &Path, new: &str, current: Option<&str>) -> Result<(), Error> {
+  if new.chars().count() < MIN_LEN {
+    return Err(Error::Validation(format!("Hasło aplikacji musi mieć co najmniej {} znaków", MIN_LEN)));
+  }
+  require(dir, current)?;
+  let hash = hash(new)?;
+  update_config_in_dir(&profiles::root_of(dir), |cfg| cfg.passphrase_hash = Some(hash))
+}
+
+pub fn clear(dir: &Path, current: &str) -> Result<(), Error> {
+  require(dir, Some(current))?;
+  let root = profiles::root_of(dir);
+  if read_config_from_dir(&root).is_none() {
+    return Ok(());
+  }
+  update_config_in_dir(&root, |cfg| cfg.passphrase_hash = None)
+}
@@ -0,0 +1,218 @@
+use crate::{
+  api::{self, ApiCall},
+  cursor::PageRequest,
+  error::Error,
+  events, runtime, scopes,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+  collections::{BTreeMap, HashSet},
+  path::Path,
+  time::Duration,
+};
+
+const PLAYLIST_MAP_TTL: Duration = Duration::from_secs(10 * 60);
+const PLAYLIST_MAP_KEY: &str = "playlists:mine";
+/// Contents older than this are re-listed before an insert is skipped.
+const PLAYLIST_ITEMS_TTL: Duration = Duration::from_secs(5 * 60);
+pub(crate) const PLAYLIST_WRITE_SCOPES: &[&str] = &[scopes::YOUTUBE];
+/// Overall bound on walking every page of the playlist listing.
+const LIST_DEADLINE: Duration = Duration::from_secs(5 * 60);
+
+/// The user's playlists as id → title, cached for a few minutes.
+pub async fn playlist_map(dir: &Path, force_refresh: bool) -> Result<BTreeMap<String, String>, Error> {
+  let rt = runtime::for_dir(dir);
+  if !force_refresh {
+    if let Some(map) = rt.cache_get(PLAYLIST_MAP_KEY, PLAYLIST_MAP_TTL).and_then(|v| serde_json::from_value(v).ok()) {
+      return Ok(map);
+    }
+  }
+  let call = ApiCall::get("/youtube/v3/playlists").query("part", "id,snippet").query("mine", "true");
+  let map: BTreeMap<String, String> = api::paginate(dir, call, usize::MAX, LIST_DEADLINE)
+    .await?
+    .iter()
+    .filter_map(|p| Some((p["id"].as_str()?.to_string(), p["snippet"]["title"].as_str().unwrap_or_default().to_string())))
+    .collect();
+  rt.cache_put(PLAYLIST_MAP_KEY, serde_json::to_value(&map).unwrap_or_default());
+  Ok(map)
+}
+
+/// Ids from `ids` that don't resolve to an existing playlist. Own playlists
+/// are answered from the cached map; anything else (a stale cache, another
+/// channel's playlist) is looked up directly.
+pub async fn missing_playlists(dir: &Path, ids: &[String]) -> Result<Vec<String>, Error> {
+  let map = playlist_map(dir, false).await?;
+  let unknown: Vec<String> = ids.iter().filter(|id| !map.contains_key(*id)).cloned().collect();
+  if unknown.is_empty() {
+    return Ok(unknown);
+  }
+  let call = ApiCall::get("/youtube/v3/playlists").query("part", "id").query("id", unknown.join(","));
+  let found: Vec<String> = api::execute(dir, call).await?["items"]
+    .as_array()
+    .map(|items| items.iter().filter_map(|p| p["id"].as_str().map(str::to_string)).collect())
+    .unwrap_or_default();
+  Ok(unknown.into_iter().filter(|id| !found.contains(id)).collect())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistItem {
+  /// Id of the playlist entry, not of the video.
+  pub item_id: String,
+  pub video_id: String,
+  pub position: u32,
+}
+
+/// One stretch of a playlist from [`list_playlist_items`].
+#[derive(Serialize, Debug, Clone)]
+pub struct PlaylistItemsPage {
+  pub items: Vec<PlaylistItem>,
+  /// Pass back as `cursor` for the rest; absent at the end of the playlist.
+  pub next_cursor: Option<String>,
+  pub total: Option<u64>,
+}
+
+/// Outcome of `add_to_playlist`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "result", rename_all = "camelCase")]
+pub enum PlaylistInsert {
+  Inserted { item_id: String },
+  /// The video was already there; nothing was sent.
+  AlreadyInPlaylist { item_id: String },
+}
+
+/// Phase of a `playlist-progress` event.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupPhase {
+  Listing,
+  Removing,
+}
+
+/// Payload of `playlist-progress`; `total` is absent while the API hasn't said.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlaylistProgress {
+  pub playlist_id: String,
+  pub phase: DedupPhase,
+  pub done: usize,
+  pub total: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DedupReport {
+  pub playlist_id: String,
+  pub scanned: usize,
+  /// Extra copies, each a later position of a video listed earlier.
+  pub duplicates: Vec<PlaylistItem>,
+  /// How many of them were deleted; 0 on a dry run.
+  pub removed: usize,
+  pub dry_run: bool,
+}
+
+fn items_key(playlist_id: &str) -> String {
+  format!("playlist-items:{}", playlist_id)
+}
+
+fn item_from_resource(v: &serde_json::Value) -> Option<PlaylistItem> {
+  Some(PlaylistItem {
+    item_id: v["id"].as_str()?.to_string(),
+    video_id: v["snippet"]["resourceId"]["videoId"].as_str()?.to_string(),
+    position: v["snippet"]["position"].as_u64().unwrap_or_default() as u32,
+  })
+}
+
+fn store_items(dir: &Path, playlist_id: &str, items: &[PlaylistItem]) {
+  runtime::for_dir(dir).cache_put(&items_key(playlist_id), serde_json::to_value(items).unwrap_or_default());
+}
+
+/// Entries of a playlist by position, from a cache at most a few minutes old.
+pub async fn playlist_items(dir: &Path, playlist_id: &str, force_refresh: bool) -> Result<Vec<PlaylistItem>, Error> {
+  if !force_refresh {
+    let cached = runtime::for_dir(dir).cache_get(&items_key(playlist_id), PLAYLIST_ITEMS_TTL);
+    if let Some(items) = cached.and_then(|v| serde_json::from_value(v).ok()) {
+      return Ok(items);
+    }
+  }
+  let call = ApiCall::get("/youtube/v3/playlistItems").query("part", "id,snippet").query("playlistId", playlist_id);
+  let on_page = |done, total| {
+    events::progress(dir, playlist_id, "playlist-progress", PlaylistProgress { playlist_id: playlist_id.to_string(), phase: DedupPhase::Listing, done, total })
+  };
+  let mut items: Vec<PlaylistItem> =
+    api::paginate_with(dir, call, usize::MAX, LIST_DEADLINE, on_page).await?.iter().filter_map(item_from_resource).collect();
+  items.sort_by_key(|i| i.position);
+  store_items(dir, playlist_id, &items);
+  Ok(items)
+}
+
+/// Entries of a playlist in playlist order, `paging.page_budget` pages at a
+/// time. Not cached: a listing that stops halfway isn't the playlist.
+pub async fn list_playlist_items(dir: &Path, playlist_id: &str, paging: &PageRequest) -> Result<PlaylistItemsPage, Error> {
+  let call = ApiCall::get("/youtube/v3/playlistItems").query("part", "id,snippet").query("playlistId", playlist_id);
+  let on_page = |done, total| {
+    events::progress(dir, playlist_id, "playlist-progress", PlaylistProgress { playlist_id: playlist_id.to_string(), phase: DedupPhase::Listing, done, total })
+  };
+  let page = api::paginate_page(dir, call, paging, usize::MAX, LIST_DEADLINE, on_page).await?;
+  Ok(PlaylistItemsPage { items: page.items.iter().filter_map(item_from_resource).collect(), next_cursor: page.next_cursor, total: page.total })
+}
+
+/// Adds `video_id` at the end of the playlist unless it is already there,
+/// so re-running a failed batch doesn't add the same video twice.
+pub async fn add_to_playlist(dir: &Path, playlist_id: &str, video_id: &str) -> Result<PlaylistInsert, Error> {
+  api::ensure_writable(dir)?;
+  let mut items = playlist_items(dir, playlist_id, false).await?;
+  if let Some(existing) = items.iter().find(|i| i.video_id == video_id) {
+    return Ok(PlaylistInsert::AlreadyInPlaylist { item_id: existing.item_id.clone() });
+  }
+  let body = json!({
+    "snippet": { "playlistId": playlist_id, "resourceId": { "kind": "youtube#video", "videoId": video_id } }
+  });
+  let call = ApiCall::post("/youtube/v3/playlistItems").query("part", "snippet").json(body).scopes(PLAYLIST_WRITE_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  let item_id = resp["id"].as_str().unwrap_or_default().to_string();
+  let position = resp["snippet"]["position"].as_u64().map_or(items.len() as u32, |p| p as u32);
+  items.push(PlaylistItem { item_id: item_id.clone(), video_id: video_id.to_string(), position });
+  store_items(dir, playlist_id, &items);
+  Ok(PlaylistInsert::Inserted { item_id })
+}
+
+/// Every entry of a video after its first (lowest) position.
+pub fn find_duplicates(items: &[PlaylistItem]) -> Vec<PlaylistItem> {
+  let mut sorted = items.to_vec();
+  sorted.sort_by_key(|i| i.position);
+  let mut seen = HashSet::new();
+  sorted.into_iter().filter(|i| !seen.insert(i.video_id.clone())).collect()
+}
+
+/// Lists the whole playlist fresh and deletes the extra copies of each
+/// video, keeping the earliest. `dry_run` only reports what would go.
+pub async fn deduplicate_playlist(dir: &Path, playlist_id: &str, dry_run: bool) -> Result<DedupReport, Error> {
+  if !dry_run {
+    api::ensure_writable(dir)?;
+  }
+  let items = playlist_items(dir, playlist_id, true).await?;
+  let duplicates = find_duplicates(&items);
+  let mut report = DedupReport { playlist_id: playlist_id.to_string(), scanned: items.len(), duplicates, removed: 0, dry_run };
+  if dry_run || report.duplicates.is_empty() {
+    return Ok(report);
+  }
+  let total = Some(report.duplicates.len() as u64);
+  let mut removed = HashSet::new();
+  let result = async {
+    for item in &report.duplicates {
+      let call = ApiCall::delete("/youtube/v3/playlistItems").query("id", item.item_id.as_str()).scopes(PLAYLIST_WRITE_SCOPES);
+      api::execute(dir, call).await?;
+      removed.insert(item.item_id.clone());
+      let progress = PlaylistProgress { playlist_id: playlist_id.to_string(), phase: DedupPhase::Removing, done: removed.len(), total };
+      events::progress(dir, playlist_id, "playlist-progress", progress);
+    }
+    Ok::<_, Error>(())
+  }
+  .await;
+  // Even a partial run changed the playlist; later positions have shifted,
+  // but only membership matters to the insert check.
+  let kept: Vec<PlaylistItem> = items.into_iter().filter(|i| !removed.contains(&i.item_id)).collect();
+  store_items(dir, playlist_id, &kept);
+  result?;
+  report.removed = removed.len();
+  Ok(report)
+}
@@ -0,0 +1,144 @@
+//! Checks run right before a queued upload starts, so a file on a share that
+//! went away or a render still being written is caught up front instead of
+//! failing halfway through the transfer.
+
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::{
+  fs::{self, File},
+  io::{Read, Seek, SeekFrom},
+  path::Path,
+  time::Duration,
+};
+
+/// YouTube's limits for a single upload.
+pub const MAX_UPLOAD_BYTES: u64 = 256 * 1024 * 1024 * 1024;
+pub const MAX_DURATION_SECS: u64 = 12 * 60 * 60;
+/// Room the config dir needs for session and queue bookkeeping.
+const MIN_FREE_BYTES: u64 = 16 * 1024 * 1024;
+/// Gap between the two stats of the still-growing check.
+const GROWTH_PROBE: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PreflightReason {
+  FileMissing,
+  NotReadable,
+  Empty,
+  StillGrowing,
+  TooLarge,
+  TooLong,
+  LowDiskSpace,
+}
+
+fn fail(reason: PreflightReason, message: String) -> Error {
+  Error::PreflightFailed { reason, message }
+}
+
+/// Free bytes on the filesystem holding `dir`; `None` where unknown.
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+  use std::os::unix::ffi::OsStrExt;
+  let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+  let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+  // SAFETY: `path` is NUL-terminated and `stat` is a valid out-pointer.
+  if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+    return None;
+  }
+  Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> Option<u64> {
+  None
+}
+
+fn read_u32(f: &mut File) -> Option<u32> {
+  let mut b = [0; 4];
+  f.read_exact(&mut b).ok()?;
+  Some(u32::from_be_bytes(b))
+}
+
+fn read_u64(f: &mut File) -> Option<u64> {
+  let mut b = [0; 8];
+  f.read_exact(&mut b).ok()?;
+  Some(u64::from_be_bytes(b))
+}
+
+/// Finds the box `name` among those in `[start, end)`; returns its payload range.
+fn find_box(f: &mut File, start: u64, end: u64, name: &[u8; 4]) -> Option<(u64, u64)> {
+  let mut at = start;
+  while at + 8 <= end {
+    f.seek(SeekFrom::Start(at)).ok()?;
+    let size = read_u32(f)? as u64;
+    let mut kind = [0; 4];
+    f.read_exact(&mut kind).ok()?;
+    let (header, size) = match size {
+      1 => (16, read_u64(f)?),
+      0 => (8, end - at),
+      s => (8, s),
+    };
+    if size < header {
+      return None;
+    }
+    if &kind == name {
+      return Some((at + header, at + size));
+    }
+    at += size;
+  }
+  None
+}
+
+/// Duration from the `moov/mvhd` box of an MP4/MOV file; `None` for other
+/// containers, which YouTube checks after the upload instead.
+pub fn mp4_duration_secs(path: &Path) -> Option<u64> {
+  let mut f = File::open(path).ok()?;
+  let len = f.metadata().ok()?.len();
+  let (moov, moov_end) = find_box(&mut f, 0, len, b"moov")?;
+  let (mvhd, _) = find_box(&mut f, moov, moov_end, b"mvhd")?;
+  f.seek(SeekFrom::Start(mvhd)).ok()?;
+  let version = read_u32(&mut f)? >> 24;
+  let (timescale, duration) = if version == 1 {
+    f.seek(SeekFrom::Current(16)).ok()?;
+    (read_u32(&mut f)?, read_u64(&mut f)?)
+  } else {
+    f.seek(SeekFrom::Current(8)).ok()?;
+    (read_u32(&mut f)?, read_u32(&mut f)? as u64)
+  };
+  (timescale > 0).then(|| duration / timescale as u64)
+}
+
+/// Everything that must hold before `path` is uploaded from a config `dir`.
+/// Returns the file size.
+pub async fn check(dir: &Path, path: &Path) -> Result<u64, Error> {
+  let show = path.display();
+  let meta = match fs::metadata(path) {
+    Ok(m) if m.is_file() => m,
+    Ok(_) => return Err(fail(PreflightReason::FileMissing, format!("{} nie jest plikiem", show))),
+    Err(e) => return Err(fail(PreflightReason::FileMissing, format!("Plik {} jest niedostępny: {}", show, e))),
+  };
+  if let Err(e) = File::open(path).and_then(|mut f| f.read(&mut [0; 1])) {
+    return Err(fail(PreflightReason::NotReadable, format!("Nie można czytać {}: {}", show, e)));
+  }
+  if meta.len() == 0 {
+    return Err(fail(PreflightReason::Empty, format!("Plik {} jest pusty", show)));
+  }
+  if meta.len() > MAX_UPLOAD_BYTES {
+    return Err(fail(PreflightReason::TooLarge, format!("Plik {} przekracza limit YouTube 256 GB", show)));
+  }
+  if let Some(secs) = mp4_duration_secs(path).filter(|s| *s > MAX_DURATION_SECS) {
+    return Err(fail(PreflightReason::TooLong, format!("Film {} trwa {} s, YouTube przyjmuje najwyżej 12 godzin", show, secs)));
+  }
+  if let Some(free) = available_space(dir).filter(|f| *f < MIN_FREE_BYTES) {
+    return Err(fail(
+      PreflightReason::LowDiskSpace,
+      format!("W katalogu konfiguracyjnym zostało {} B wolnego miejsca (potrzeba {} B)", free, MIN_FREE_BYTES),
+    ));
+  }
+  tokio::time::sleep(GROWTH_PROBE).await;
+  let later = fs::metadata(path).map_err(|e| fail(PreflightReason::FileMissing, format!("Plik {} zniknął: {}", show, e)))?;
+  if later.len() != meta.len() || later.modified().ok() != meta.modified().ok() {
+    return Err(fail(PreflightReason::StillGrowing, format!("Plik {} jest jeszcze zapisywany ({} → {} B)", show, meta.len(), later.len())));
+  }
+  Ok(later.len())
+}
@@ -0,0 +1,239 @@
+//! Privacy changes for many videos at once, in two steps: the first call only
+//! reads the videos and returns what would change with a confirmation token,
+//! the second call with that token makes exactly those changes. Moving a
+//! scheduled video out of private cancels its `publishAt`, so that needs
+//! `cancel_schedules` on top.
+
+use crate::{
+  api::{self, Privacy},
+  bulk::{RowOutcome, RowStatus},
+  error::Error,
+  events, now_secs, runtime, videos,
+};
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+  collections::HashSet,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+/// How long a plan can be confirmed.
+const CONFIRM_TTL: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_MIN_INTERVAL_MS: u64 = 1000;
+/// One bulk change runs at a time, so its progress needs no finer key.
+const PROGRESS_KEY: &str = "bulk-privacy";
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PrivacyOptions {
+  /// Allow changes that cancel a scheduled publication.
+  #[serde(default)]
+  pub cancel_schedules: bool,
+  /// Pause between writes; 1000 ms when absent.
+  #[serde(default)]
+  pub min_interval_ms: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeAction {
+  Change,
+  /// Already has the target privacy.
+  Unchanged,
+  /// Scheduled with `publishAt`; the change would cancel that and needs `cancel_schedules`.
+  NeedsScheduleOverride,
+  /// Not returned by the API: deleted, or not on this channel.
+  NotFound,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PrivacyChange {
+  pub video_id: String,
+  pub title: Option<String>,
+  /// `privacyStatus` now; absent for videos that weren't found.
+  pub current: Option<Privacy>,
+  pub target: Privacy,
+  pub publish_at: Option<String>,
+  /// The change clears `publish_at`.
+  pub cancels_schedule: bool,
+  pub action: ChangeAction,
+}
+
+/// The first step: nothing was changed yet.
+#[derive(Serialize, Debug, Clone)]
+pub struct PrivacyPlan {
+  /// Pass to the second call, with the same videos, privacy and options.
+  pub confirm_token: String,
+  /// Unix seconds after which the token is refused.
+  pub expires_at: u64,
+  pub changes: Vec<PrivacyChange>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PrivacyReport {
+  /// `row` is the position in `video_ids`, 1-based.
+  pub rows: Vec<RowOutcome>,
+  pub succeeded: usize,
+  pub failed: usize,
+  pub unchanged: usize,
+  pub planned: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "result", rename_all = "camelCase")]
+pub enum BulkPrivacyResult {
+  Plan(PrivacyPlan),
+  Applied(PrivacyReport),
+}
+
+/// Payload of `privacy-progress`, sent after each video.
+#[derive(Serialize, Debug, Clone)]
+pub struct PrivacyProgress {
+  pub done: usize,
+  pub total: usize,
+  pub video_id: String,
+  pub status: RowStatus,
+}
+
+/// What a confirmation token was issued for.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PendingChange {
+  video_ids: Vec<String>,
+  privacy: Privacy,
+  cancel_schedules: bool,
+}
+
+fn privacy_of(video: &Value) -> Option<Privacy> {
+  serde_json::from_value(video["status"]["privacyStatus"].clone()).ok()
+}
+
+/// What changing `video` (a `snippet,status` resource, `None` when missing)
+/// to `target` would do.
+fn plan_change(video_id: &str, video: Option<&Value>, target: Privacy, cancel_schedules: bool) -> PrivacyChange {
+  let mut change = PrivacyChange {
+    video_id: video_id.to_string(),
+    title: None,
+    current: None,
+    target,
+    publish_at: None,
+    cancels_schedule: false,
+    action: ChangeAction::NotFound,
+  };
+  let Some(video) = video else {
+    return change;
+  };
+  change.title = video["snippet"]["title"].as_str().map(str::to_string);
+  change.current = privacy_of(video);
+  change.publish_at = video["status"]["publishAt"].as_str().map(str::to_string);
+  // publishAt only exists on private videos; leaving private drops it.
+  change.cancels_schedule = change.publish_at.is_some() && target != Privacy::Private;
+  change.action = if change.current == Some(target) {
+    ChangeAction::Unchanged
+  } else if change.cancels_schedule && !cancel_schedules {
+    ChangeAction::NeedsScheduleOverride
+  } else {
+    ChangeAction::Change
+  };
+  change
+}
+
+fn dedup(video_ids: &[String]) -> Vec<String> {
+  let mut seen = HashSet::new();
+  video_ids.iter().map(|id| id.trim().to_string()).filter(|id| !id.is_empty() && seen.insert(id.clone())).collect()
+}
+
+async fn plan(dir: &Path, request: PendingChange) -> Result<PrivacyPlan, Error> {
+  let videos = videos::fetch(dir, &request.video_ids, "snippet,status").await?;
+  let changes =
+    request.video_ids.iter().map(|id| plan_change(id, videos.get(id), request.privacy, request.cancel_schedules)).collect();
+  let confirm_token = format!("{:016x}{:016x}", rand_core::OsRng.next_u64(), rand_core::OsRng.next_u64());
+  let rt = runtime::for_dir(dir);
+  let mut pending = rt.privacy_confirmations();
+  pending.retain(|_, (issued, _)| issued.elapsed() < CONFIRM_TTL);
+  pending.insert(confirm_token.clone(), (Instant::now(), request));
+  Ok(PrivacyPlan { confirm_token, expires_at: now_secs() + CONFIRM_TTL.as_secs(), changes })
+}
+
+async fn apply_one(dir: &Path, change: &PrivacyChange, video: Option<&Value>) -> Result<RowStatus, Error> {
+  match (change.action, video) {
+    (ChangeAction::Unchanged, _) => Ok(RowStatus::Unchanged),
+    (ChangeAction::NotFound, _) | (_, None) => Err(Error::Validation(format!("Nie znaleziono wideo {}", change.video_id))),
+    (ChangeAction::NeedsScheduleOverride, _) => Err(Error::Validation(format!(
+      "Wideo ma zaplanowaną publikację ({}); zmiana ją anuluje — potwierdź z cancel_schedules",
+      change.publish_at.as_deref().unwrap_or_default()
+    ))),
+    (ChangeAction::Change, Some(video)) => {
+      let mut status = videos::status_for_update(&video["status"]);
+      status["privacyStatus"] = json!(change.target.as_str());
+      if change.cancels_schedule {
+        if let Some(s) = status.as_object_mut() {
+          s.remove("publishAt");
+        }
+      }
+      videos::update(dir, "status", json!({ "id": change.video_id, "status": status })).await?;
+      Ok(RowStatus::Succeeded)
+    }
+  }
+}
+
+async fn apply(dir: &Path, request: &PendingChange, options: &PrivacyOptions) -> Result<PrivacyReport, Error> {
+  // Read again: the plan may be minutes old.
+  let videos = videos::fetch(dir, &request.video_ids, "snippet,status").await?;
+  let interval = Duration::from_millis(options.min_interval_ms.unwrap_or(DEFAULT_MIN_INTERVAL_MS));
+  let total = request.video_ids.len();
+  let mut report = PrivacyReport::default();
+  for (i, id) in request.video_ids.iter().enumerate() {
+    let video = videos.get(id);
+    let change = plan_change(id, video, request.privacy, request.cancel_schedules);
+    let (status, error, plan) = match apply_one(dir, &change, video).await {
+      Ok(status) => (status, None, None),
+      Err(Error::DryRun(plan)) => (RowStatus::Planned, None, Some(plan)),
+      Err(e) => (RowStatus::Failed, Some(e.to_string()), None),
+    };
+    match status {
+      RowStatus::Succeeded => report.succeeded += 1,
+      RowStatus::Failed => report.failed += 1,
+      RowStatus::Unchanged => report.unchanged += 1,
+      RowStatus::Planned => report.planned += 1,
+    }
+    events::progress(dir, PROGRESS_KEY, "privacy-progress", PrivacyProgress { done: i + 1, total, video_id: id.clone(), status });
+    report.rows.push(RowOutcome { row: i + 1, video_id: id.clone(), status, error, plan, video_links: Vec::new() });
+    let wrote = change.action == ChangeAction::Change;
+    if wrote && i + 1 < total {
+      tokio::time::sleep(interval).await;
+    }
+  }
+  Ok(report)
+}
+
+/// Without `confirm_token` returns the plan and a token; with the token of
+/// an unexpired plan for the same request, applies it. Videos already in the
+/// target state are skipped, and a failed video doesn't stop the rest.
+pub async fn bulk_set_privacy(
+  dir: &Path,
+  video_ids: &[String],
+  privacy: Privacy,
+  confirm_token: Option<&str>,
+  options: &PrivacyOptions,
+) -> Result<BulkPrivacyResult, Error> {
+  let request = PendingChange { video_ids: dedup(video_ids), privacy, cancel_schedules: options.cancel_schedules };
+  if request.video_ids.is_empty() {
+    return Err(Error::Validation("Podaj co najmniej jedno wideo".into()));
+  }
+  let Some(token) = confirm_token else {
+    return Ok(BulkPrivacyResult::Plan(plan(dir, request).await?));
+  };
+  api::ensure_writable(dir)?;
+  // Single use, matched or not.
+  let pending = runtime::for_dir(dir).privacy_confirmations().remove(token);
+  match pending {
+    Some((issued, planned)) if issued.elapsed() < CONFIRM_TTL => {
+      if planned != request {
+        return Err(Error::Validation("Token potwierdzenia dotyczy innej zmiany — pobierz plan ponownie".into()));
+      }
+      Ok(BulkPrivacyResult::Applied(apply(dir, &request, options).await?))
+    }
+    _ => Err(Error::Validation("Token potwierdzenia jest nieznany lub wygasł — pobierz plan ponownie".into())),
+  }
+}
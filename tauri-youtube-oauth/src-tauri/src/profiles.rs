@@ -0,0 +1,193 @@
+//! Profiles: one config dir per channel setup. The root config dir is the
+//! `default` profile; others live in `profiles/<name>/` with their own
+//! oauth_config.json and tokens.json, so the dir-based core works unchanged.
+//!
+//! `active_profile.json` in the root names the profile the app works in.
+//! Switching and deleting change it first, synced to disk, and a deleted
+//! profile is renamed to `<name>.deleting` before it's removed, so a crash
+//! at any step leaves either the old state or the new one, never a profile
+//! that half exists; [`recover`] finishes the rest at the next start.
+
+use crate::{error::Error, shutdown, storage};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+const ACTIVE_FILE: &str = "active_profile.json";
+/// Suffix of a profile dir being removed; not a valid name, so never listed.
+pub const DELETING_SUFFIX: &str = ".deleting";
+
+#[derive(Serialize, Deserialize)]
+struct ActivePointer {
+  profile: String,
+}
+
+/// Payload of `profile-switched`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSwitched {
+  pub profile: String,
+  pub previous: String,
+}
+
+fn valid_name(name: &str) -> bool {
+  (1..=64).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn configured(dir: &Path) -> bool {
+  dir.join("oauth_config.json").is_file()
+}
+
+/// Config dir of `name`; fails for unknown profiles and names that could
+/// escape `profiles/`.
+pub fn dir_for(root: &Path, name: &str) -> Result<PathBuf, Error> {
+  if name == DEFAULT_PROFILE {
+    return Ok(root.to_path_buf());
+  }
+  if !valid_name(name) {
+    return Err(Error::Validation(format!("Nieprawidłowa nazwa profilu: {} (dozwolone litery, cyfry, - i _)", name)));
+  }
+  let dir = root.join(PROFILES_DIR).join(name);
+  if !configured(&dir) {
+    return Err(Error::Validation(format!("Nieznany profil: {}", name)));
+  }
+  Ok(dir)
+}
+
+/// Profile name of a config dir, the inverse of [`dir_for`].
+pub fn name_of(dir: &Path) -> String {
+  let in_profiles = dir.parent().and_then(|p| p.file_name()).is_some_and(|n| n == PROFILES_DIR);
+  match dir.file_name().and_then(|n| n.to_str()) {
+    Some(name) if in_profiles && valid_name(name) => name.to_string(),
+    _ => DEFAULT_PROFILE.to_string(),
+  }
+}
+
+/// The root config dir `dir` is a profile of, the inverse of [`dir_for`]'s `root`.
+pub(crate) fn root_of(dir: &Path) -> PathBuf {
+  match name_of(dir).as_str() {
+    DEFAULT_PROFILE => dir.to_path_buf(),
+    _ => dir.parent().and_then(Path::parent).unwrap_or(dir).to_path_buf(),
+  }
+}
+
+/// Configured profiles: `default` first when the root has a config, then
+/// the rest by name.
+pub fn list(root: &Path) -> Vec<String> {
+  let mut names: Vec<String> = fs::read_dir(root.join(PROFILES_DIR))
+    .into_iter()
+    .flatten()
+    .flatten()
+    .filter(|e| configured(&e.path()))
+    .filter_map(|e| e.file_name().into_string().ok())
+    .filter(|n| valid_name(n) && n != DEFAULT_PROFILE)
+    .collect();
+  names.sort();
+  if configured(root) {
+    names.insert(0, DEFAULT_PROFILE.to_string());
+  }
+  names
+}
+
+fn pointed(root: &Path) -> Option<String> {
+  let s = fs::read_to_string(root.join(ACTIVE_FILE)).ok()?;
+  serde_json::from_str::<ActivePointer>(&s).ok().map(|p| p.profile)
+}
+
+/// The profile the app works in; `default` without a pointer or when it
+/// names a profile that no longer exists.
+pub fn active(root: &Path) -> String {
+  pointed(root).filter(|name| dir_for(root, name).is_ok()).unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Config dir of the [`active`] profile.
+pub fn active_dir(root: &Path) -> PathBuf {
+  dir_for(root, &active(root)).unwrap_or_else(|_| root.to_path_buf())
+}
+
+fn point_to(root: &Path, name: &str) -> Result<(), Error> {
+  let s = serde_json::to_string(&ActivePointer { profile: name.to_string() }).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_durable(&root.join(ACTIVE_FILE), s).map_err(|e| Error::Storage(format!("{}: {}", ACTIVE_FILE, e)))
+}
+
+fn ensure_idle(dir: &Path, name: &str) -> Result<(), Error> {
+  match shutdown::pending_uploads(dir) {
+    0 => Ok(()),
+    n => Err(Error::Validation(format!("Profil {} ma trwające wysyłanie ({}) — poczekaj na jego koniec", name, n))),
+  }
+}
+
+/// Makes `name` the active profile: the pointer is synced to disk first,
+/// then the old profile's background tasks are ended. Starting the new
+/// profile's tasks and emitting `profile-switched` is the caller's part.
+/// `None` when `name` already is active. Refused while the old profile
+/// uploads.
+pub fn switch(root: &Path, name: &str) -> Result<Option<ProfileSwitched>, Error> {
+  dir_for(root, name)?;
+  let _lock = storage::lock(root)?;
+  let previous = active(root);
+  if previous == name {
+    return Ok(None);
+  }
+  let previous_dir = dir_for(root, &previous)?;
+  ensure_idle(&previous_dir, &previous)?;
+  point_to(root, name)?;
+  shutdown::end_tasks(&previous_dir);
+  Ok(Some(ProfileSwitched { profile: name.to_string(), previous }))
+}
+
+fn deleting_dir(root: &Path, name: &str) -> PathBuf {
+  root.join(PROFILES_DIR).join(format!("{}{}", name, DELETING_SUFFIX))
+}
+
+/// Deletes profile `name`. An active profile hands over to `default` first
+/// (the returned switch), then the dir is renamed to `<name>.deleting` and
+/// removed in the background. The default profile is the root config dir
+/// and can't be deleted; neither can a profile that uploads.
+pub fn delete(root: &Path, name: &str) -> Result<Option<ProfileSwitched>, Error> {
+  if name == DEFAULT_PROFILE {
+    return Err(Error::Validation("Profilu default nie można usunąć".into()));
+  }
+  let dir = dir_for(root, name)?;
+  let _lock = storage::lock(root)?;
+  ensure_idle(&dir, name)?;
+  let switched = (active(root) == name).then(|| ProfileSwitched { profile: DEFAULT_PROFILE.to_string(), previous: name.to_string() });
+  if switched.is_some() {
+    point_to(root, DEFAULT_PROFILE)?;
+  }
+  shutdown::end_tasks(&dir);
+  let doomed = deleting_dir(root, name);
+  // Left by an earlier deletion of the same name that didn't finish.
+  if doomed.exists() {
+    fs::remove_dir_all(&doomed).map_err(|e| Error::Storage(format!("{}: {}", doomed.display(), e)))?;
+  }
+  fs::rename(&dir, &doomed).map_err(|e| Error::Storage(format!("Nie można usunąć profilu {}: {}", name, e)))?;
+  std::thread::spawn(move || {
+    // Whatever is left is removed by `recover` at the next start.
+    let _ = fs::remove_dir_all(&doomed);
+  });
+  Ok(switched)
+}
+
+/// Finishes what a crash cut short: removes the `*.deleting` dirs and
+/// resets a pointer naming a profile that no longer exists. Called once at
+/// startup; returns the names of the removed profiles.
+pub fn recover(root: &Path) -> Vec<String> {
+  let mut removed: Vec<String> = fs::read_dir(root.join(PROFILES_DIR))
+    .into_iter()
+    .flatten()
+    .flatten()
+    .filter_map(|e| {
+      let name = e.file_name().into_string().ok()?.strip_suffix(DELETING_SUFFIX)?.to_string();
+      (e.path().is_dir() && fs::remove_dir_all(e.path()).is_ok()).then_some(name)
+    })
+    .collect();
+  removed.sort();
+  if pointed(root).is_some_and(|name| dir_for(root, &name).is_err()) {
+    let _ = point_to(root, DEFAULT_PROFILE);
+  }
+  removed
+}
@@ -0,0 +1,110 @@
+use crate::{format::ProgressLabels, publish_time::PublishTime};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Weight of the newest sample in the moving average.
+const ALPHA: f64 = 0.3;
+/// Samples needed after a (re)start before an ETA is reported.
+const STABLE_SAMPLES: u32 = 5;
+/// A gap this long between samples is treated as a pause.
+const PAUSE_GAP: Duration = Duration::from_secs(30);
+
+/// Payload of the `upload-progress` event.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadProgress {
+  pub bytes_sent: u64,
+  pub total_bytes: u64,
+  /// Speed over the last chunk only.
+  pub bytes_per_sec: f64,
+  pub smoothed_bytes_per_sec: Option<f64>,
+  pub eta_secs: Option<u64>,
+  /// Size of the chunks currently sent; 0 when not reported by the uploader.
+  #[serde(default)]
+  pub chunk_size: u64,
+  /// The same numbers worded in the app language.
+  #[serde(default)]
+  pub labels: Option<ProgressLabels>,
+  /// When the video goes public, if it is scheduled.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub publish_at: Option<PublishTime>,
+}
+
+/// Exponential moving average of upload speed, fed with cumulative byte
+/// counts at times measured from any fixed origin. Pure, so the uploader
+/// drives it from real clocks and tests from synthetic ones.
+#[derive(Debug, Clone, Default)]
+pub struct SpeedEstimator {
+  last: Option<(Duration, u64)>,
+  last_rate: f64,
+  smoothed: Option<f64>,
+  samples: u32,
+}
+
+impl SpeedEstimator {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Records that `bytes` in total were confirmed at time `at`.
+  pub fn sample(&mut self, at: Duration, bytes: u64) {
+    let Some((prev_at, prev_bytes)) = self.last else {
+      self.last = Some((at, bytes));
+      return;
+    };
+    // A retried chunk moves the offset back, and a long gap means the upload
+    // sat paused; neither interval says anything about link speed, so just
+    // rebase on this sample.
+    if bytes < prev_bytes || at <= prev_at || at - prev_at >= PAUSE_GAP {
+      self.last = Some((at, bytes));
+      return;
+    }
+    let rate = (bytes - prev_bytes) as f64 / (at - prev_at).as_secs_f64();
+    self.last = Some((at, bytes));
+    self.last_rate = rate;
+    self.smoothed = Some(match self.smoothed {
+      Some(s) => ALPHA * rate + (1.0 - ALPHA) * s,
+      None => rate,
+    });
+    self.samples += 1;
+  }
+
+  /// Forgets the baseline and restarts stabilization, e.g. on resume. The
+  /// last average is kept as a starting point since the link rarely changes
+  /// across a pause, but the ETA stays hidden until it is confirmed again.
+  pub fn reset(&mut self) {
+    self.last = None;
+    self.samples = 0;
+  }
+
+  /// Speed of the most recent interval.
+  pub fn raw_bytes_per_sec(&self) -> f64 {
+    self.last_rate
+  }
+
+  pub fn smoothed_bytes_per_sec(&self) -> Option<f64> {
+    self.smoothed
+  }
+
+  /// Seconds until `total` bytes are sent, once the estimate has stabilized.
+  pub fn eta_secs(&self, total: u64) -> Option<u64> {
+    let (_, sent) = self.last?;
+    let speed = self.smoothed.filter(|s| *s > 0.0)?;
+    if self.samples < STABLE_SAMPLES {
+      return None;
+    }
+    Some((total.saturating_sub(sent) as f64 / speed).ceil() as u64)
+  }
+
+  pub fn progress(&self, bytes_sent: u64, total_bytes: u64) -> UploadProgress {
+    UploadProgress {
+      bytes_sent,
+      total_bytes,
+      bytes_per_sec: self.last_rate,
+      smoothed_bytes_per_sec: self.smoothed,
+      eta_secs: self.eta_secs(total_bytes),
+      chunk_size: 0,
+      labels: None,
+      publish_at: None,
+    }
+  }
+}
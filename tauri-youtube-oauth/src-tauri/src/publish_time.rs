@@ -0,0 +1,105 @@
+//! `publish_at` as people write it, turned into the UTC instant the API
+//! wants. Besides RFC3339 with an offset, a datetime without one is local
+//! time in `publish_timezone` and a bare date means `default_publish_hour` on
+//! that day. A local time the clock skips or repeats at a DST change is
+//! refused with both instants it could mean instead of guessing.
+
+use crate::{error::Error, read_config_from_dir};
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, Offset, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Hour a date-only `publish_at` goes public at without `default_publish_hour`.
+pub const DEFAULT_PUBLISH_HOUR: u32 = 9;
+
+const NAIVE_FORMATS: [&str; 4] = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+
+/// One publish instant, as sent and as the user thinks of it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PublishTime {
+  /// RFC3339 in UTC, the form sent as `publishAt`.
+  pub utc: String,
+  /// The same instant as RFC3339 in `timezone`.
+  pub local: String,
+  /// IANA name of the zone `local` is in.
+  pub timezone: String,
+}
+
+/// `publish_timezone` from the config, UTC without one.
+pub fn timezone(dir: &Path) -> Result<Tz, Error> {
+  match read_config_from_dir(dir).and_then(|c| c.publish_timezone) {
+    Some(name) => name.parse().map_err(|_| Error::Config(format!("Nieznana strefa czasowa publish_timezone: {}", name))),
+    None => Ok(chrono_tz::UTC),
+  }
+}
+
+pub fn default_hour(dir: &Path) -> Result<u32, Error> {
+  match read_config_from_dir(dir).and_then(|c| c.default_publish_hour).unwrap_or(DEFAULT_PUBLISH_HOUR) {
+    hour @ 0..=23 => Ok(hour),
+    hour => Err(Error::Config(format!("default_publish_hour musi być godziną 0–23 (jest {})", hour))),
+  }
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+  at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// `at` in UTC and in `tz`.
+pub fn describe(tz: Tz, at: DateTime<Utc>) -> PublishTime {
+  PublishTime {
+    utc: rfc3339(at),
+    local: at.with_timezone(&tz).to_rfc3339_opts(SecondsFormat::Secs, false),
+    timezone: tz.name().to_string(),
+  }
+}
+
+/// The instant `input` means: RFC3339 as is, a naive datetime in `tz`, a
+/// date at `default_hour` in `tz`.
+pub fn parse(input: &str, tz: Tz, default_hour: u32) -> Result<DateTime<Utc>, Error> {
+  let input = input.trim();
+  if let Ok(at) = DateTime::parse_from_rfc3339(input) {
+    return Ok(at.with_timezone(&Utc));
+  }
+  let naive = NAIVE_FORMATS
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(input, f).ok())
+    .or_else(|| NaiveDate::parse_from_str(input, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(default_hour, 0, 0)))
+    .ok_or_else(|| {
+      Error::Validation(format!(
+        "publish_at {:?}: nieznany format — podaj RFC3339 (2026-03-29T18:00:00+02:00), datę z godziną (2026-03-29 18:00) albo samą datę",
+        input
+      ))
+    })?;
+  match tz.from_local_datetime(&naive) {
+    LocalResult::Single(at) => Ok(at.with_timezone(&Utc)),
+    LocalResult::Ambiguous(first, second) => Err(Error::Validation(format!(
+      "publish_at {} występuje w {} dwa razy (zmiana czasu): {} albo {} — podaj przesunięcie, np. {}",
+      input,
+      tz.name(),
+      rfc3339(first.with_timezone(&Utc)),
+      rfc3339(second.with_timezone(&Utc)),
+      first.to_rfc3339_opts(SecondsFormat::Secs, false)
+    ))),
+    LocalResult::None => {
+      // The clock jumps over it: read it with the offset before and after the jump.
+      let candidate = |around: NaiveDateTime| {
+        let offset = tz.offset_from_utc_datetime(&around).fix().local_minus_utc();
+        rfc3339(Utc.from_utc_datetime(&(naive - Duration::seconds(offset.into()))))
+      };
+      Err(Error::Validation(format!(
+        "publish_at {} nie istnieje w {} (przestawienie zegara): chodzi o {} albo {}? Podaj czas z przesunięciem",
+        input,
+        tz.name(),
+        candidate(naive - Duration::days(1)),
+        candidate(naive + Duration::days(1))
+      )))
+    }
+  }
+}
+
+/// `input` read with the timezone and default hour of `dir`.
+pub fn resolve(dir: &Path, input: &str) -> Result<PublishTime, Error> {
+  let tz = timezone(dir)?;
+  Ok(describe(tz, parse(input, tz, default_hour(dir)?)?))
+}
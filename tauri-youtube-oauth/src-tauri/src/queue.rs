@@ -0,0 +1,671 @@
+//! Upload queue: entries persisted in `upload_queue.json` together with the
+//! schedule options and the resulting plan, and a worker that uploads each
+//! entry once its slot comes.
+
+use crate::{
+  api::{self, DryRunPlan},
+  enrichment::{self, Enrichment},
+  error::Error,
+  events, format, library, metadata, notifications, now_secs,
+  overrides::{self, ForcedOverride},
+  preflight::{self, PreflightReason},
+  quota,
+  remote_state::Rejection,
+  runtime,
+  schedule::{self, ScheduleOptions, ScheduledEntry},
+  shutdown, sidecar, storage,
+  upload::{self, VideoMetadata},
+  video_links::LinkCache,
+  read_config_from_dir, Privacy,
+};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  sync::Mutex,
+  time::Duration,
+};
+
+const QUEUE_FILE: &str = "upload_queue.json";
+/// A scheduled video goes public this long after its upload slot, leaving
+/// YouTube time to process it.
+const PUBLISH_LEAD_SECS: i64 = 30 * 60;
+/// Longest the worker sleeps before re-checking the queue and shutdown.
+const IDLE_POLL: Duration = Duration::from_secs(30);
+/// Failed uploads before an entry gives up, unless `queue_max_attempts` says otherwise.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+  Pending,
+  Uploading,
+  Done,
+  Failed,
+  /// Failed the pre-flight checks; waits for `revalidate_blocked`.
+  Blocked,
+  /// Failed `queue_max_attempts` times; only `retry_queue_entry` brings it
+  /// back, `retry_all_failed` leaves it.
+  #[serde(rename = "gave_up")]
+  GaveUp,
+}
+
+/// One upload of an entry, as kept in its `attempt_history`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueueAttempt {
+  /// Unix seconds the attempt ended.
+  pub at: u64,
+  /// Error code it failed with; absent when the video went up.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub code: Option<String>,
+}
+
+/// Pending entries go by priority, then by their place in the queue.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryPriority {
+  Low,
+  #[default]
+  Normal,
+  High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueueEntry {
+  pub id: String,
+  pub file_path: String,
+  /// Explicit metadata; absent means the sidecar and `metadata_template`.
+  #[serde(default)]
+  pub metadata: Option<VideoMetadata>,
+  pub status: EntryStatus,
+  /// `normal` in queue files from older versions.
+  #[serde(default)]
+  pub priority: EntryPriority,
+  pub added_at: u64,
+  /// Planned upload time (Unix seconds); absent means as soon as possible.
+  #[serde(default)]
+  pub scheduled_at: Option<u64>,
+  #[serde(default)]
+  pub uploaded_at: Option<u64>,
+  #[serde(default)]
+  pub video_id: Option<String>,
+  #[serde(default)]
+  pub error: Option<String>,
+  /// Uploads tried since the entry was added or last brought back from
+  /// `gave_up`.
+  #[serde(default)]
+  pub attempts: u32,
+  /// Every attempt, oldest first; a retry keeps it.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub attempt_history: Vec<QueueAttempt>,
+  /// Warning codes the upload goes ahead despite (see [`overrides`]).
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub force: Vec<String>,
+  /// Each upload of the entry that `force` took past a warning.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub overrides: Vec<ForcedOverride>,
+  /// Details for the queue view, once computed (see [`enrichment`]); never
+  /// stored in the queue file.
+  #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+  pub enrichment: Option<Enrichment>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct QueueFile {
+  #[serde(default)]
+  entries: Vec<QueueEntry>,
+  #[serde(default)]
+  schedule: Option<ScheduleOptions>,
+  /// Day (schedule timezone) YouTube refused uploads with
+  /// `uploadLimitExceeded`; nothing is planned before the next one.
+  #[serde(default)]
+  limit_hit_on: Option<NaiveDate>,
+}
+
+/// What `get_upload_plan` shows.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadPlan {
+  pub options: Option<ScheduleOptions>,
+  pub entries: Vec<ScheduledEntry>,
+  pub limit_hit_on: Option<NaiveDate>,
+}
+
+/// Pending entries in the order they upload: higher priority first, queue
+/// order among equals. Blocked and failed entries aren't pending, so a stuck
+/// high-priority entry never holds up the rest.
+pub(crate) fn pending_in_order(entries: &[QueueEntry]) -> Vec<&QueueEntry> {
+  let mut pending: Vec<&QueueEntry> = entries.iter().filter(|e| e.status == EntryStatus::Pending).collect();
+  // Stable, so positions decide within a priority.
+  pending.sort_by_key(|e| std::cmp::Reverse(e.priority));
+  pending
+}
+
+fn read_queue(dir: &Path) -> QueueFile {
+  fs::read_to_string(dir.join(QUEUE_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+fn timezone(q: &QueueFile) -> chrono_tz::Tz {
+  q.schedule.as_ref().and_then(|o| schedule::timezone(o).ok()).unwrap_or(chrono_tz::UTC)
+}
+
+/// Recomputes the slots of pending entries.
+fn replan(q: &mut QueueFile, now: DateTime<Utc>) -> Result<Vec<ScheduledEntry>, Error> {
+  let tz = timezone(q);
+  let not_before = match q.limit_hit_on.map(|d| schedule::next_day_start(&tz, d)) {
+    Some(t) if t > now => t,
+    _ => {
+      q.limit_hit_on = None;
+      now
+    }
+  };
+  let plan = match &q.schedule {
+    Some(options) => schedule::plan_upload_schedule(&q.entries, options, not_before)?,
+    // Unscheduled entries run right away, unless the limit pushed them out.
+    None if not_before > now => pending_in_order(&q.entries)
+      .into_iter()
+      .map(|e| ScheduledEntry { id: e.id.clone(), start_at: not_before.timestamp() as u64, start_local: not_before.to_rfc3339(), start_label: None })
+      .collect(),
+    None => Vec::new(),
+  };
+  for e in q.entries.iter_mut().filter(|e| e.status == EntryStatus::Pending) {
+    e.scheduled_at = plan.iter().find(|p| p.id == e.id).map(|p| p.start_at);
+  }
+  Ok(plan)
+}
+
+/// Read-modify-write of the queue; the plan is recomputed before saving.
+fn update<T>(dir: &Path, f: impl FnOnce(&mut QueueFile) -> Result<T, Error>) -> Result<T, Error> {
+  let _lock = storage::lock(dir)?;
+  let _guard = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut q = read_queue(dir);
+  let out = f(&mut q)?;
+  replan(&mut q, Utc::now())?;
+  let s = serde_json::to_string_pretty(&q).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_atomic(&dir.join(QUEUE_FILE), s).map_err(|e| Error::Storage(e.to_string()))?;
+  enrichment::attach(dir, &mut q.entries);
+  events::broadcast(dir, "queue-changed", &q.entries);
+  Ok(out)
+}
+
+/// Entries still to go up: pending or uploading.
+pub fn depth(dir: &Path) -> usize {
+  read_queue(dir).entries.iter().filter(|e| matches!(e.status, EntryStatus::Pending | EntryStatus::Uploading)).count()
+}
+
+/// The status of every entry, in queue order.
+pub(crate) fn statuses(dir: &Path) -> Vec<EntryStatus> {
+  read_queue(dir).entries.iter().map(|e| e.status).collect()
+}
+
+/// The entries with whatever enrichment is ready; the rest arrives as
+/// `queue-entry-enriched`.
+pub fn list(dir: &Path) -> Vec<QueueEntry> {
+  let mut entries = read_queue(dir).entries;
+  enrichment::attach(dir, &mut entries);
+  entries
+}
+
+pub fn add(dir: &Path, file_path: &str, metadata: Option<VideoMetadata>) -> Result<QueueEntry, Error> {
+  add_forced(dir, file_path, metadata, &[])
+}
+
+/// [`add`] with warning codes to force the upload past; an unknown code is
+/// refused.
+pub fn add_forced(dir: &Path, file_path: &str, metadata: Option<VideoMetadata>, force: &[String]) -> Result<QueueEntry, Error> {
+  let force = overrides::parse(force)?.into_iter().map(|c| c.code()).collect();
+  if metadata.is_none() {
+    sidecar::check_file(dir, &metadata::sidecar_path(Path::new(file_path)))?;
+  }
+  let entry = QueueEntry {
+    id: format!("{:016x}", rand_core::OsRng.next_u64()),
+    file_path: file_path.to_string(),
+    metadata,
+    status: EntryStatus::Pending,
+    priority: EntryPriority::Normal,
+    added_at: now_secs(),
+    scheduled_at: None,
+    uploaded_at: None,
+    video_id: None,
+    error: None,
+    attempts: 0,
+    attempt_history: Vec::new(),
+    force,
+    overrides: Vec::new(),
+    enrichment: None,
+  };
+  let id = entry.id.clone();
+  update(dir, |q| {
+    q.entries.push(entry);
+    Ok(())
+  })?;
+  Ok(list(dir).into_iter().find(|e| e.id == id).expect("entry was just added"))
+}
+
+pub fn remove(dir: &Path, id: &str) -> Result<(), Error> {
+  update(dir, |q| {
+    let i = q.entries.iter().position(|e| e.id == id).ok_or_else(|| Error::Validation(format!("Brak wpisu kolejki {}", id)))?;
+    if q.entries[i].status == EntryStatus::Uploading {
+      return Err(Error::Validation("Nie można usunąć wpisu w trakcie wysyłania".into()));
+    }
+    q.entries.remove(i);
+    Ok(())
+  })
+}
+
+fn position(q: &QueueFile, id: &str) -> Result<usize, Error> {
+  q.entries.iter().position(|e| e.id == id).ok_or_else(|| Error::Validation(format!("Brak wpisu kolejki {}", id)))
+}
+
+pub fn set_priority(dir: &Path, id: &str, priority: EntryPriority) -> Result<QueueEntry, Error> {
+  update(dir, |q| {
+    let i = position(q, id)?;
+    q.entries[i].priority = priority;
+    Ok(())
+  })?;
+  list(dir).into_iter().find(|e| e.id == id).ok_or_else(|| Error::Validation(format!("Brak wpisu kolejki {}", id)))
+}
+
+/// Moves an entry to `new_index` of the list and returns the new order. The
+/// entry being uploaded stays where it is; others may move around it.
+pub fn move_entry(dir: &Path, id: &str, new_index: usize) -> Result<Vec<QueueEntry>, Error> {
+  update(dir, |q| {
+    let i = position(q, id)?;
+    if q.entries[i].status == EntryStatus::Uploading {
+      return Err(Error::Validation("Nie można przenieść wpisu w trakcie wysyłania".into()));
+    }
+    if new_index >= q.entries.len() {
+      return Err(Error::Validation(format!("Pozycja {} jest poza kolejką ({} wpisów)", new_index, q.entries.len())));
+    }
+    let entry = q.entries.remove(i);
+    q.entries.insert(new_index, entry);
+    Ok(())
+  })?;
+  Ok(list(dir))
+}
+
+/// Sets (or with `None` drops) the schedule and returns the new plan.
+pub fn set_schedule(dir: &Path, options: Option<ScheduleOptions>) -> Result<UploadPlan, Error> {
+  if let Some(o) = &options {
+    schedule::validate(o)?;
+  }
+  update(dir, |q| {
+    q.schedule = options;
+    Ok(())
+  })?;
+  plan(dir)
+}
+
+pub fn plan(dir: &Path) -> Result<UploadPlan, Error> {
+  let mut q = read_queue(dir);
+  let mut entries = replan(&mut q, Utc::now())?;
+  let lang = format::language(dir);
+  for e in &mut entries {
+    e.start_label = format::timestamp(&e.start_local, lang).ok();
+  }
+  Ok(UploadPlan { options: q.schedule, entries, limit_hit_on: q.limit_hit_on })
+}
+
+fn is_upload_limit(e: &Error) -> bool {
+  matches!(e, Error::Api { reason, .. } | Error::Forbidden { reason, .. } if reason == "uploadLimitExceeded")
+}
+
+/// Today's quota can't pay for another upload.
+fn quota_paused(dir: &Path) -> bool {
+  quota::check(dir, upload::INSERT_COST).is_err()
+}
+
+/// Held by the one worker allowed per config dir.
+struct WorkerGuard(PathBuf);
+
+impl WorkerGuard {
+  fn acquire(dir: &Path) -> Option<Self> {
+    let rt = runtime::for_dir(dir);
+    let mut busy = rt.queue_busy();
+    (!std::mem::replace(&mut *busy, true)).then(|| WorkerGuard(dir.to_path_buf()))
+  }
+}
+
+impl Drop for WorkerGuard {
+  fn drop(&mut self) {
+    *runtime::for_dir(&self.0).queue_busy() = false;
+  }
+}
+
+/// Payload of `queue-entry-blocked`.
+#[derive(Serialize, Debug, Clone)]
+pub struct EntryBlocked {
+  pub id: String,
+  pub file_path: String,
+  pub reason: PreflightReason,
+  pub message: String,
+}
+
+/// Payload of `queue-entry-failed` and `queue-entry-gave-up`.
+#[derive(Serialize, Debug, Clone)]
+pub struct EntryFailed {
+  pub id: String,
+  pub file_path: String,
+  pub error: String,
+  pub code: String,
+  pub attempts: u32,
+}
+
+/// Payload of `queue-entry-planned`.
+#[derive(Serialize, Debug, Clone)]
+pub struct EntryPlanned {
+  pub id: String,
+  pub file_path: String,
+  /// The call the upload would have started with.
+  pub plan: DryRunPlan,
+}
+
+/// Payload of `queue-entry-retried`.
+#[derive(Serialize, Debug, Clone)]
+pub struct EntryRetried {
+  pub id: String,
+  pub file_path: String,
+  /// What the entry was retried from: `failed` or `gave_up`.
+  pub from: EntryStatus,
+  pub attempts: u32,
+  /// The upload will continue the open resumable session instead of
+  /// sending the file from the start.
+  pub resumes: bool,
+}
+
+fn max_attempts(dir: &Path) -> u32 {
+  read_config_from_dir(dir).and_then(|c| c.queue_max_attempts).unwrap_or(DEFAULT_MAX_ATTEMPTS)
+}
+
+/// Takes the first due pending entry, by priority, that isn't in `skip`,
+/// and marks it as uploading.
+fn claim_due(dir: &Path, skip: &[String]) -> Result<Option<(QueueEntry, bool)>, Error> {
+  let now = now_secs();
+  update(dir, |q| {
+    let publish = q.schedule.as_ref().is_some_and(|o| o.schedule_publishing);
+    let due = pending_in_order(&q.entries)
+      .into_iter()
+      .find(|e| e.scheduled_at.is_none_or(|t| t <= now) && !skip.contains(&e.id))
+      .map(|e| e.id.clone());
+    let Some(e) = due.and_then(|id| q.entries.iter_mut().find(|e| e.id == id)) else {
+      return Ok(None);
+    };
+    e.status = EntryStatus::Uploading;
+    Ok(Some((e.clone(), publish)))
+  })
+}
+
+async fn upload_entry(
+  dir: &Path,
+  entry: &QueueEntry,
+  publish: bool,
+  links: &mut LinkCache,
+) -> Result<(String, Option<ForcedOverride>), Error> {
+  let path = PathBuf::from(&entry.file_path);
+  let force = overrides::parse(&entry.force)?;
+  preflight::check(dir, &path).await?;
+  let mut meta = match &entry.metadata {
+    Some(m) => m.clone(),
+    None => metadata::load_video_metadata(dir, &path).await?,
+  };
+  if publish && meta.publish_at.is_none() {
+    let slot = entry.scheduled_at.map_or(Utc::now().timestamp(), |t| t as i64).max(Utc::now().timestamp());
+    let at = DateTime::<Utc>::from_timestamp(slot + PUBLISH_LEAD_SECS, 0).unwrap_or_else(Utc::now);
+    meta.publish_at = Some(at.to_rfc3339_opts(SecondsFormat::Secs, true));
+    // publishAt only works on private videos.
+    meta.privacy = Privacy::Private;
+  }
+  upload::upload_video_with(dir, &path, &meta, &force, links).await.map(|v| (v.video_id, v.forced))
+}
+
+/// Uploads every entry whose slot has come, one at a time. Returns how many
+/// were attempted. `uploadLimitExceeded` puts the entry back and moves the
+/// rest of the plan to the next day. Paused while read-only mode is on and
+/// while the day's quota can't pay for an upload: entries stay pending until
+/// it's turned off or the quota resets. In dry-run mode each due entry is
+/// planned once and stays pending, with `queue-entry-planned`.
+pub async fn process_due(dir: &Path) -> Result<usize, Error> {
+  let Some(_busy) = WorkerGuard::acquire(dir) else {
+    return Ok(0);
+  };
+  if api::read_only_enabled(dir) {
+    return Ok(0);
+  }
+  // Only this worker uploads, so anything still marked uploading was cut off.
+  update(dir, |q| {
+    q.entries.iter_mut().filter(|e| e.status == EntryStatus::Uploading).for_each(|e| e.status = EntryStatus::Pending);
+    Ok(())
+  })?;
+  let mut attempted = 0;
+  // Entries dry-run planned this run; they stay pending, so they'd come up again.
+  let mut planned = Vec::new();
+  let mut links = LinkCache::default();
+  let generation = shutdown::task_generation(dir);
+  while !shutdown::tasks_ended(dir, generation) && !api::read_only_enabled(dir) {
+    if quota_paused(dir) {
+      let pending = read_queue(dir).entries.iter().filter(|e| e.status == EntryStatus::Pending).count();
+      if pending > 0 {
+        notifications::quota_paused(dir, pending);
+      }
+      break;
+    }
+    let Some((entry, publish)) = claim_due(dir, &planned)? else { break };
+    attempted += 1;
+    let result = upload_entry(dir, &entry, publish, &mut links).await;
+    let limit_hit = matches!(&result, Err(err) if is_upload_limit(err));
+    // Waits for the quota like for the upload limit, just without replanning.
+    let kept = limit_hit || matches!(result, Err(Error::QuotaExceeded { .. }));
+    let max_attempts = max_attempts(dir);
+    let mut gave_up = false;
+    let mut attempts = 0;
+    update(dir, |q| {
+      if limit_hit {
+        q.limit_hit_on = Some(Utc::now().with_timezone(&timezone(q)).date_naive());
+      }
+      let Some(e) = q.entries.iter_mut().find(|e| e.id == entry.id) else {
+        return Ok(());
+      };
+      match &result {
+        Ok((video_id, forced)) => {
+          e.status = EntryStatus::Done;
+          e.video_id = Some(video_id.clone());
+          e.overrides.extend(forced.clone());
+          e.uploaded_at = Some(now_secs());
+          e.error = None;
+          e.attempts += 1;
+          e.attempt_history.push(QueueAttempt { at: now_secs(), code: None });
+        }
+        Err(_) if kept || shutdown::stopping(dir) => e.status = EntryStatus::Pending,
+        // Only planned: not an attempt, and the entry waits for a real run.
+        Err(Error::DryRun(_)) => e.status = EntryStatus::Pending,
+        // Not an attempt: nothing was sent, and `revalidate_blocked` brings it back.
+        Err(err @ Error::PreflightFailed { .. }) => {
+          e.status = EntryStatus::Blocked;
+          e.error = Some(err.to_string());
+        }
+        Err(err) => {
+          e.attempts += 1;
+          e.attempt_history.push(QueueAttempt { at: now_secs(), code: Some(err.code().to_string()) });
+          gave_up = e.attempts >= max_attempts;
+          e.status = if gave_up { EntryStatus::GaveUp } else { EntryStatus::Failed };
+          e.error = Some(err.to_string());
+        }
+      }
+      attempts = e.attempts;
+      Ok(())
+    })?;
+    match &result {
+      Ok(_) => notifications::entry_succeeded(dir),
+      Err(_) if kept || shutdown::stopping(dir) => {}
+      Err(Error::DryRun(_)) => {}
+      Err(err) => notifications::entry_failed(dir, &entry.file_path, err.code()),
+    }
+    // Keyed like the upload's progress, so a failure can't be followed by a stale percentage.
+    let key = entry.file_path.clone();
+    match result {
+      Err(_) if kept || shutdown::stopping(dir) => {}
+      Err(Error::DryRun(plan)) => {
+        planned.push(entry.id.clone());
+        events::transition(dir, &key, "queue-entry-planned", EntryPlanned { id: entry.id, file_path: entry.file_path, plan })
+      }
+      Err(Error::PreflightFailed { reason, message }) => {
+        events::transition(dir, &key, "queue-entry-blocked", EntryBlocked { id: entry.id, file_path: entry.file_path, reason, message })
+      }
+      Err(err) => {
+        let failed = EntryFailed { id: entry.id, file_path: entry.file_path, error: err.to_string(), code: err.code().to_string(), attempts };
+        match gave_up {
+          true => events::transition(dir, &key, "queue-entry-gave-up", failed),
+          false => events::transition(dir, &key, "queue-entry-failed", failed),
+        }
+      }
+      Ok((video_id, _)) => {
+        // Processing is watched from the moment the upload ends; a rejection
+        // seen before the entry was done only reached the library.
+        if let Some(rejection) = library::find_local_file_for_video(dir, &video_id).and_then(|m| m.entry.rejection) {
+          mark_rejected(dir, &video_id, &rejection)?;
+        }
+      }
+    }
+  }
+  if !read_queue(dir).entries.iter().any(|e| matches!(e.status, EntryStatus::Pending | EntryStatus::Uploading)) {
+    notifications::queue_drained(dir);
+  }
+  Ok(attempted)
+}
+
+/// The done entry of `video_id`, once YouTube rejected, failed to process
+/// or removed the video: the attempt that uploaded it failed after all, so
+/// the entry fails (or gives up) with the reason. Returns whether there was
+/// one.
+pub(crate) fn mark_rejected(dir: &Path, video_id: &str, rejection: &Rejection) -> Result<bool, Error> {
+  let is_it = |e: &QueueEntry| e.status == EntryStatus::Done && e.video_id.as_deref() == Some(video_id);
+  if !read_queue(dir).entries.iter().any(is_it) {
+    return Ok(false);
+  }
+  let err = Error::VideoRejected { video_id: video_id.to_string(), rejection: rejection.clone() };
+  let max_attempts = max_attempts(dir);
+  let failed = update(dir, |q| {
+    let Some(e) = q.entries.iter_mut().find(|e| is_it(e)) else {
+      return Ok(None);
+    };
+    if let Some(last) = e.attempt_history.last_mut() {
+      last.code = Some(err.code().to_string());
+    }
+    let gave_up = e.attempts >= max_attempts;
+    e.status = if gave_up { EntryStatus::GaveUp } else { EntryStatus::Failed };
+    e.error = Some(err.to_string());
+    let failed = EntryFailed { id: e.id.clone(), file_path: e.file_path.clone(), error: err.to_string(), code: err.code().to_string(), attempts: e.attempts };
+    Ok(Some((failed, gave_up)))
+  })?;
+  let Some((failed, gave_up)) = failed else {
+    return Ok(false);
+  };
+  notifications::entry_failed(dir, &failed.file_path, err.code());
+  let key = failed.file_path.clone();
+  match gave_up {
+    true => events::transition(dir, &key, "queue-entry-gave-up", failed),
+    false => events::transition(dir, &key, "queue-entry-failed", failed),
+  }
+  Ok(true)
+}
+
+/// Runs the pre-flight checks again for blocked entries, e.g. after a share
+/// is remounted. Entries that pass go back to pending; returns them.
+pub async fn revalidate_blocked(dir: &Path) -> Result<Vec<QueueEntry>, Error> {
+  let blocked: Vec<QueueEntry> = read_queue(dir).entries.into_iter().filter(|e| e.status == EntryStatus::Blocked).collect();
+  let mut results = Vec::new();
+  for entry in blocked {
+    let result = preflight::check(dir, Path::new(&entry.file_path)).await;
+    results.push((entry.id, result.err().map(|e| e.to_string())));
+  }
+  update(dir, |q| {
+    let mut unblocked = Vec::new();
+    for (id, error) in &results {
+      // Removed or re-queued while being checked.
+      let Some(e) = q.entries.iter_mut().find(|e| &e.id == id && e.status == EntryStatus::Blocked) else {
+        continue;
+      };
+      match error {
+        Some(message) => e.error = Some(message.clone()),
+        None => {
+          e.status = EntryStatus::Pending;
+          e.error = None;
+          unblocked.push(e.id.clone());
+        }
+      }
+    }
+    Ok(unblocked)
+  })
+  .map(|ids| list(dir).into_iter().filter(|e| ids.contains(&e.id)).collect())
+}
+
+/// Puts a failed or given-up entry back in the queue, keeping its attempt
+/// history. A given-up entry starts counting attempts again. An open
+/// resumable session for the file is kept, so the upload continues it.
+pub fn retry(dir: &Path, id: &str) -> Result<QueueEntry, Error> {
+  retry_where(dir, |e| e.id == id && matches!(e.status, EntryStatus::Failed | EntryStatus::GaveUp))?
+    .pop()
+    .ok_or_else(|| match read_queue(dir).entries.iter().find(|e| e.id == id) {
+      Some(e) => Error::Validation(format!("Wpis kolejki {} nie jest nieudany ({:?})", id, e.status)),
+      None => Error::Validation(format!("Brak wpisu kolejki {}", id)),
+    })
+}
+
+/// Retries every failed entry, or with `codes` only those whose last
+/// attempt failed with one of them (e.g. `NETWORK_ERROR` but not
+/// `FORBIDDEN`). Given-up entries are left for [`retry`]. Returns the
+/// entries put back.
+pub fn retry_all_failed(dir: &Path, codes: Option<&[String]>) -> Result<Vec<QueueEntry>, Error> {
+  retry_where(dir, |e| {
+    let code = e.attempt_history.last().and_then(|a| a.code.as_ref());
+    e.status == EntryStatus::Failed && codes.is_none_or(|codes| code.is_some_and(|c| codes.contains(c)))
+  })
+}
+
+fn retry_where(dir: &Path, pick: impl Fn(&QueueEntry) -> bool) -> Result<Vec<QueueEntry>, Error> {
+  let retried = update(dir, |q| {
+    let mut retried = Vec::new();
+    for e in q.entries.iter_mut().filter(|e| pick(e)) {
+      let from = e.status;
+      if from == EntryStatus::GaveUp {
+        e.attempts = 0;
+      }
+      e.status = EntryStatus::Pending;
+      e.error = None;
+      let resumes = upload::has_resumable_session(dir, Path::new(&e.file_path));
+      retried.push(EntryRetried { id: e.id.clone(), file_path: e.file_path.clone(), from, attempts: e.attempts, resumes });
+    }
+    Ok(retried)
+  })?;
+  let ids: Vec<String> = retried.iter().map(|r| r.id.clone()).collect();
+  for r in retried {
+    let key = r.file_path.clone();
+    events::transition(dir, &key, "queue-entry-retried", r);
+  }
+  Ok(list(dir).into_iter().filter(|e| ids.contains(&e.id)).collect())
+}
+
+/// Worker loop: uploads due entries, then sleeps until the next slot. Ends
+/// when nothing is left pending, the app is shutting down or the profile
+/// was switched away from.
+pub async fn run(dir: &Path) -> Result<(), Error> {
+  let generation = shutdown::task_generation(dir);
+  while !shutdown::tasks_ended(dir, generation) {
+    process_due(dir).await?;
+    let pending: Vec<QueueEntry> = read_queue(dir).entries.into_iter().filter(|e| e.status == EntryStatus::Pending).collect();
+    if pending.is_empty() {
+      return Ok(());
+    }
+    let next = pending.iter().filter_map(|e| e.scheduled_at).min().unwrap_or_else(now_secs);
+    let wait = if api::read_only_enabled(dir) || quota_paused(dir) {
+      IDLE_POLL
+    } else {
+      Duration::from_secs(next.saturating_sub(now_secs())).clamp(Duration::from_secs(1), IDLE_POLL)
+    };
+    shutdown::idle(dir, generation, wait).await;
+  }
+  Ok(())
+}
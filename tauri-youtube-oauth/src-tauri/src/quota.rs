@@ -0,0 +1,89 @@
+use crate::{capabilities, error::Error, read_config_from_dir, storage};
+use chrono::Utc;
+use chrono_tz::America::Los_Angeles;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, sync::Mutex};
+
+/// Default Data API allowance for a Cloud project.
+pub const DEFAULT_DAILY_LIMIT: u32 = 10_000;
+
+/// Serializes read-modify-write of quota.json within the process.
+static QUOTA_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct QuotaFile {
+  day: String,
+  used: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuotaStatus {
+  /// Quota day (`YYYY-MM-DD`, Pacific time, when Google resets the counter).
+  pub day: String,
+  pub used: u32,
+  pub limit: u32,
+  pub remaining: u32,
+}
+
+fn quota_day() -> String {
+  Utc::now().with_timezone(&Los_Angeles).format("%Y-%m-%d").to_string()
+}
+
+fn limit(dir: &Path) -> u32 {
+  read_config_from_dir(dir).and_then(|c| c.daily_quota).unwrap_or(DEFAULT_DAILY_LIMIT)
+}
+
+fn read_used(dir: &Path, day: &str) -> u32 {
+  fs::read_to_string(dir.join("quota.json"))
+    .ok()
+    .and_then(|s| serde_json::from_str::<QuotaFile>(&s).ok())
+    .filter(|q| q.day == day)
+    .map(|q| q.used)
+    .unwrap_or(0)
+}
+
+/// Local estimate of today's usage. Google doesn't expose the real counter,
+/// so this only knows about calls made through this app.
+pub fn status(dir: &Path) -> QuotaStatus {
+  let day = quota_day();
+  let used = read_used(dir, &day);
+  let limit = limit(dir);
+  QuotaStatus { day, used, limit, remaining: limit.saturating_sub(used) }
+}
+
+/// Fails with `QuotaExceeded` when fewer than `cost` units are left today.
+pub fn check(dir: &Path, cost: u32) -> Result<(), Error> {
+  let s = status(dir);
+  if cost > s.remaining {
+    return Err(Error::QuotaExceeded { needed: cost, remaining: s.remaining });
+  }
+  Ok(())
+}
+
+/// Checks and records `cost` units against today's budget.
+pub(crate) fn charge(dir: &Path, cost: u32) -> Result<(), Error> {
+  if cost == 0 {
+    return Ok(());
+  }
+  let _lock = storage::lock(dir)?;
+  let _guard = QUOTA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  check(dir, cost)?;
+  let day = quota_day();
+  write(dir, QuotaFile { used: read_used(dir, &day) + cost, day })
+}
+
+/// Marks today's budget as spent: YouTube said so, whatever the estimate was.
+pub(crate) fn exhaust(dir: &Path) -> Result<(), Error> {
+  let _lock = storage::lock(dir)?;
+  let _guard = QUOTA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let day = quota_day();
+  write(dir, QuotaFile { used: read_used(dir, &day).max(limit(dir)), day })
+}
+
+fn write(dir: &Path, q: QuotaFile) -> Result<(), Error> {
+  fs::create_dir_all(dir).map_err(|e| Error::Storage(e.to_string()))?;
+  let s = serde_json::to_string_pretty(&q).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_atomic(&dir.join("quota.json"), s).map_err(|e| Error::Storage(e.to_string()))?;
+  capabilities::notify(dir);
+  Ok(())
+}
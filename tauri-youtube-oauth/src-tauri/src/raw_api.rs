@@ -0,0 +1,137 @@
+//! Requests the crate doesn't wrap, built by the frontend: any path of the
+//! Data API or another YouTube googleapis host, sent with the stored tokens
+//! and the same refresh, 401 retry, dry-run, quota and logging as the
+//! wrapped endpoints. Off unless `allow_raw_api` is set, since it lets the
+//! webview do whatever the tokens allow.
+
+use crate::{
+  api::{self, api_base, ApiCall, Reply},
+  connectivity,
+  error::Error,
+  read_config_from_dir, runtime,
+};
+use reqwest::Method;
+use serde::Serialize;
+use std::{
+  collections::BTreeMap,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+/// Hosts a raw request may go to besides the API base.
+const ALLOWED_HOSTS: &[&str] =
+  &["www.googleapis.com", "youtube.googleapis.com", "youtubeanalytics.googleapis.com", "youtubereporting.googleapis.com"];
+const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+const MAX_PER_MINUTE: usize = 60;
+/// Response headers handed back; the rest stay out of the webview.
+const HEADERS: &[&str] = &["content-type", "etag", "location", "retry-after"];
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RawResponse {
+  pub status: u16,
+  pub headers: BTreeMap<String, String>,
+  /// The JSON body; a body that isn't JSON comes as a string, none as null.
+  pub body: serde_json::Value,
+}
+
+/// The base and path `target` names: a path on the API base (`/youtube/v3/…`)
+/// or an https URL on one of [`ALLOWED_HOSTS`].
+fn resolve(target: &str) -> Result<(Option<String>, String), Error> {
+  let refused = |why: &str| Error::Validation(format!("Niedozwolony adres {}: {}", target, why));
+  let (base, path) = if target.starts_with('/') {
+    (None, target.to_string())
+  } else {
+    let url = url::Url::parse(target).map_err(|e| refused(&e.to_string()))?;
+    let host = url.host_str().unwrap_or_default();
+    if url.scheme() != "https" || !ALLOWED_HOSTS.contains(&host) || url.port().is_some() || !url.username().is_empty() {
+      return Err(refused(&format!("dozwolone są tylko https://{}", ALLOWED_HOSTS.join(", https://"))));
+    }
+    (Some(format!("https://{}", host)), url.path().to_string())
+  };
+  if target.contains(['?', '#']) {
+    return Err(refused("parametry podaj w query"));
+  }
+  if path.split('/').any(|seg| seg == ".." || seg == ".") {
+    return Err(refused("ścieżka nie może zawierać . ani .."));
+  }
+  Ok((base, path))
+}
+
+fn parse_method(method: &str) -> Result<Method, Error> {
+  let upper = method.to_ascii_uppercase();
+  if !METHODS.contains(&upper.as_str()) {
+    return Err(Error::Validation(format!("Nieobsługiwana metoda {} (dozwolone: {})", method, METHODS.join(", "))));
+  }
+  Method::from_bytes(upper.as_bytes()).map_err(|e| Error::Validation(e.to_string()))
+}
+
+/// At most [`MAX_PER_MINUTE`] raw requests a minute, so a runaway frontend
+/// loop can't burn through the quota.
+fn throttle(dir: &Path) -> Result<(), Error> {
+  let rt = runtime::for_dir(dir);
+  let mut sent = rt.raw_requests();
+  sent.retain(|at| at.elapsed() < Duration::from_secs(60));
+  if sent.len() >= MAX_PER_MINUTE {
+    return Err(Error::Api {
+      status: 429,
+      reason: "rateLimitExceeded".into(),
+      message: format!("Więcej niż {} surowych zapytań na minutę — odczekaj chwilę", MAX_PER_MINUTE),
+    });
+  }
+  sent.push(Instant::now());
+  Ok(())
+}
+
+fn response(reply: Reply) -> RawResponse {
+  let headers = HEADERS
+    .iter()
+    .filter_map(|name| reply.headers.get(*name).and_then(|v| v.to_str().ok()).map(|v| (name.to_string(), v.to_string())))
+    .collect();
+  let body = if reply.text.trim().is_empty() {
+    serde_json::Value::Null
+  } else {
+    serde_json::from_str(&reply.text).unwrap_or(serde_json::Value::String(reply.text))
+  };
+  RawResponse { status: reply.status.as_u16(), headers, body }
+}
+
+/// Sends `method path?query` with `body` as JSON and returns the answer
+/// whatever its status. Costs what `raw_api_costs` says for `path`, otherwise
+/// 1 unit to read and 50 to write; writes only plan while dry-run is on.
+pub async fn request(
+  dir: &Path,
+  method: &str,
+  path: &str,
+  query: &BTreeMap<String, String>,
+  body: Option<serde_json::Value>,
+) -> Result<RawResponse, Error> {
+  let cfg = read_config_from_dir(dir).unwrap_or_default();
+  if !cfg.allow_raw_api {
+    return Err(Error::Config("Surowe zapytania do API są wyłączone — włącz allow_raw_api w konfiguracji".into()));
+  }
+  let method = parse_method(method)?;
+  if method != Method::GET {
+    api::ensure_writable(dir)?;
+  }
+  let (base, path) = resolve(path)?;
+  let mut call = match &base {
+    Some(base) if *base != api_base() => ApiCall::on_host(method, base, &path),
+    _ => ApiCall::new(method, &path),
+  };
+  // The caller knows which scopes the endpoint wants; YouTube says so if they're missing.
+  call = call.scopes(&[]);
+  if let Some(&cost) = cfg.raw_api_costs.get(&path) {
+    call = call.cost(cost);
+  }
+  for (key, value) in query {
+    call = call.query(key, value.clone());
+  }
+  if let Some(body) = body {
+    call = call.json(body);
+  }
+  throttle(dir)?;
+  if !connectivity::online(dir).await {
+    return Err(Error::Offline);
+  }
+  Ok(response(api::send(dir, &call).await?))
+}
@@ -0,0 +1,99 @@
+//! Tokens keep the scopes they were granted with, while the config's `scopes`
+//! can change under them. [`check`] compares the two on every token use:
+//! when the config asks for more than the grant, `reconsent.json` records
+//! what is missing (so it survives a restart) and `reconsent-required` is
+//! emitted; [`start`] then asks for exactly those scopes. A config that asks
+//! for less needs nothing, since the extra grant is harmless, and only shows
+//! in `AuthStatus.unused_scopes`. The record goes once a check finds nothing
+//! missing, normally right after the consent.
+
+use crate::{
+  callback, capabilities, consent_probe, error::Error, now_secs, open_consent, read_config_from_dir, read_tokens_from_dir, runtime, scopes,
+  storage, AppConfig, Tokens,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+const FILE: &str = "reconsent.json";
+
+/// Contents of `reconsent.json` and payload of `reconsent-required`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReconsentRequired {
+  /// Configured scopes the stored tokens weren't granted.
+  pub missing: Vec<String>,
+  /// Unix seconds of the check that first found this set missing.
+  pub since: u64,
+}
+
+/// The recorded scope change, if consent for it is still outstanding.
+pub fn pending(dir: &Path) -> Option<ReconsentRequired> {
+  let s = fs::read_to_string(dir.join(FILE)).ok()?;
+  serde_json::from_str::<ReconsentRequired>(&s).ok().filter(|r| !r.missing.is_empty())
+}
+
+fn missing(cfg: &AppConfig, t: &Tokens) -> Vec<String> {
+  let configured = scopes::configured(cfg);
+  scopes::missing(t, &configured.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+/// Granted scopes the config no longer asks for, even through a broader one.
+pub fn unused(cfg: &AppConfig, t: &Tokens) -> Vec<String> {
+  let configured = scopes::configured(cfg);
+  scopes::granted(t).into_iter().filter(|g| !configured.iter().any(|c| scopes::covers(g, c))).collect()
+}
+
+/// Records or clears the pending reconsent for `t` against the current
+/// config; `reconsent-required` goes out when the missing set changes to a
+/// non-empty one.
+pub(crate) fn check(dir: &Path, t: &Tokens) {
+  let Some(cfg) = read_config_from_dir(dir) else {
+    return;
+  };
+  let missing = missing(&cfg, t);
+  let previous = pending(dir);
+  if previous.as_ref().map(|p| &p.missing) == Some(&missing) {
+    return;
+  }
+  if missing.is_empty() {
+    if previous.is_some() {
+      let _ = fs::remove_file(dir.join(FILE));
+      capabilities::notify(dir);
+    }
+    return;
+  }
+  let required = ReconsentRequired { missing, since: now_secs() };
+  // Best effort: the event and the error hint still carry it this time.
+  if let Ok(json) = serde_json::to_string_pretty(&required) {
+    let _ = storage::write_atomic(&dir.join(FILE), json);
+  }
+  runtime::for_dir(dir).emit("reconsent-required", &required);
+  capabilities::notify(dir);
+}
+
+/// Marks an `InsufficientScope` while a reconsent is pending, so its message
+/// points at `reconsent` rather than at a generic scope request: a 403 for
+/// a scope the grant seemed to cover most likely has the same cause.
+pub(crate) fn annotate(dir: &Path, err: Error) -> Error {
+  match err {
+    Error::InsufficientScope { missing, .. } => Error::InsufficientScope { missing, reconsent: pending(dir).is_some() },
+    e => e,
+  }
+}
+
+/// Incremental authorization for exactly the configured scopes the tokens
+/// lack: probes the consent screen, starts the callback server and opens
+/// consent for them. Returns what was asked for; empty (and nothing opened)
+/// when the grant already covers the config.
+pub(crate) async fn start(dir: &Path) -> Result<Vec<String>, Error> {
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta (Client ID/Secret)".into()))?;
+  let t = read_tokens_from_dir(dir).ok_or_else(|| Error::Auth("Brak tokenów — zaloguj się".into()))?;
+  check(dir, &t);
+  let missing = missing(&cfg, &t);
+  if !missing.is_empty() {
+    consent_probe::check(dir, &cfg, &missing).await?;
+    callback::start(dir)?;
+    // A browser that didn't open is reported through oauth-open-browser-failed.
+    open_consent(dir, &cfg, &missing, true);
+  }
+  Ok(missing)
+}
@@ -0,0 +1,82 @@
+//! Scrubbing of secrets from anything that may end up in logs, captures or
+//! bug reports. Everything leaving the process for diagnostics goes through here.
+
+use serde_json::Value;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Keys whose values are credentials, compared case-insensitively. Includes
+/// `streamName`, the RTMP stream key of live streams, and `secret` of
+/// event sink webhooks.
+const SECRET_KEYS: &[&str] =
+  &["access_token", "refresh_token", "client_secret", "id_token", "code", "key", "password", "authorization", "streamname", "secret"];
+
+pub fn is_secret_key(key: &str) -> bool {
+  SECRET_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key))
+}
+
+/// The URL with secret query values replaced.
+pub fn url(raw: &str) -> String {
+  let Ok(mut parsed) = reqwest::Url::parse(raw) else {
+    return text(raw);
+  };
+  if parsed.query().is_none() {
+    return raw.to_string();
+  }
+  let pairs: Vec<(String, String)> = parsed
+    .query_pairs()
+    .map(|(k, v)| {
+      let v = if is_secret_key(&k) { REDACTED.to_string() } else { v.into_owned() };
+      (k.into_owned(), v)
+    })
+    .collect();
+  parsed.query_pairs_mut().clear().extend_pairs(pairs);
+  parsed.to_string()
+}
+
+pub fn json(v: &mut Value) {
+  match v {
+    Value::Object(map) => {
+      for (k, v) in map.iter_mut() {
+        if is_secret_key(k) && !v.is_object() && !v.is_array() {
+          *v = Value::String(REDACTED.into());
+        } else {
+          json(v);
+        }
+      }
+    }
+    Value::Array(items) => items.iter_mut().for_each(json),
+    _ => {}
+  }
+}
+
+/// Redacts a body or message: as JSON when it parses, otherwise
+/// form-encoded `key=value` pairs and bearer tokens.
+pub fn text(s: &str) -> String {
+  if let Ok(mut v) = serde_json::from_str::<Value>(s) {
+    if v.is_object() || v.is_array() {
+      json(&mut v);
+      return v.to_string();
+    }
+  }
+  let mut out = String::with_capacity(s.len());
+  let mut rest = s;
+  while let Some(pos) = rest.find(['=', ' ']) {
+    let (head, tail) = rest.split_at(pos);
+    out.push_str(head);
+    let sep = &tail[..1];
+    out.push_str(sep);
+    let tail = &tail[1..];
+    let key = head.rsplit(|c: char| !(c.is_alphanumeric() || c == '_')).next().unwrap_or_default();
+    let secret = if sep == "=" { is_secret_key(key) } else { key.eq_ignore_ascii_case("bearer") };
+    let end = tail.find(['&', ' ', '\n', '"', ',']).unwrap_or(tail.len());
+    if secret && end > 0 {
+      out.push_str(REDACTED);
+      rest = &tail[end..];
+    } else {
+      rest = tail;
+    }
+  }
+  out.push_str(rest);
+  out
+}
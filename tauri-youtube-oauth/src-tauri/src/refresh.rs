@@ -0,0 +1,93 @@
+use crate::{error::Error, metrics, notifications, now_secs, refresh_and_persist, runtime, storage, Tokens};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+const STATE_FILE: &str = "refresh_state.json";
+/// Failures counted together when they fall within this window.
+const FAILURE_WINDOW_SECS: u64 = 5 * 60;
+/// Consecutive failures before attempts are skipped.
+const BACKOFF_AFTER: u32 = 3;
+const BACKOFF_BASE_SECS: u64 = 30;
+const BACKOFF_MAX_SECS: u64 = 60 * 60;
+
+/// Consecutive network failures of the token refresh, kept in the dir's
+/// runtime and mirrored to disk so a restart doesn't reset the backoff.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct RefreshState {
+  failures: u32,
+  first_failure_at: u64,
+  backoff_until: u64,
+}
+
+fn load(dir: &Path) -> RefreshState {
+  fs::read_to_string(dir.join(STATE_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+fn store(dir: &Path, state: &RefreshState) {
+  // Best effort: the in-memory copy still applies for this session.
+  if *state == RefreshState::default() {
+    let _ = fs::remove_file(dir.join(STATE_FILE));
+  } else if let Ok(s) = serde_json::to_string_pretty(state) {
+    let _ = storage::write_atomic(&dir.join(STATE_FILE), s);
+  }
+}
+
+fn update(dir: &Path, f: impl FnOnce(&mut RefreshState)) -> RefreshState {
+  let rt = runtime::for_dir(dir);
+  let mut slot = rt.refresh_state();
+  let state = slot.get_or_insert_with(|| load(dir));
+  let before = state.clone();
+  f(state);
+  if *state != before {
+    store(dir, state);
+  }
+  state.clone()
+}
+
+fn record_failure(state: &mut RefreshState, now: u64) {
+  // Scattered failures start over; once backing off, only a success resets.
+  let stale = state.failures < BACKOFF_AFTER && now.saturating_sub(state.first_failure_at) > FAILURE_WINDOW_SECS;
+  if state.failures == 0 || stale {
+    *state = RefreshState { failures: 0, first_failure_at: now, backoff_until: 0 };
+  }
+  state.failures += 1;
+  if state.failures >= BACKOFF_AFTER {
+    let exp = (state.failures - BACKOFF_AFTER).min(16);
+    state.backoff_until = now + (BACKOFF_BASE_SECS << exp).min(BACKOFF_MAX_SECS);
+  }
+}
+
+/// Clears the failure counter.
+pub fn reset(dir: &Path) {
+  update(dir, |s| *s = RefreshState::default());
+}
+
+/// Refreshes unless in backoff. Network failures count towards the backoff;
+/// a rejected refresh token doesn't (retrying can't help) and instead emits
+/// `reauth-required`.
+pub(crate) async fn refresh(dir: &Path) -> Result<Tokens, Error> {
+  let now = now_secs();
+  let state = update(dir, |_| {});
+  if now < state.backoff_until {
+    return Err(Error::RefreshBackoff { retry_at: state.backoff_until });
+  }
+  let result = refresh_and_persist(dir).await;
+  metrics::refresh_finished(dir, result.is_ok());
+  match result {
+    Ok(t) => {
+      reset(dir);
+      Ok(t)
+    }
+    Err(e @ (Error::Network(_) | Error::TimedOut { .. })) => {
+      update(dir, |s| record_failure(s, now));
+      Err(e)
+    }
+    Err(e @ (Error::Auth(_) | Error::GrantRevoked { .. } | Error::TokenEndpoint(_))) => {
+      reset(dir);
+      runtime::for_dir(dir).emit("reauth-required", e.to_string());
+      notifications::reauth_required(dir);
+      Err(e)
+    }
+    Err(e) => Err(e),
+  }
+}
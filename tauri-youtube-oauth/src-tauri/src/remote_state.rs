@@ -0,0 +1,186 @@
+//! What YouTube did with a video after it went up. An upload can finish and
+//! still be rejected while processing (a duplicate, a copyright match), fail
+//! to process, or disappear later; the history, the library and the queue
+//! would then go on calling it live. [`record`] marks such an end in all
+//! three, from processing or from [`verify`], which checks every recorded
+//! video against `videos.list`.
+
+use crate::{error::Error, history, library, now_secs, queue, runtime, scopes, videos};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, path::Path};
+
+const READ_SCOPES: &[&str] = &[scopes::YOUTUBE_READONLY];
+/// Parts [`rejection_of`] looks at.
+pub(crate) const PARTS: &str = "status,processingDetails";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionKind {
+  /// `uploadStatus: rejected`: a duplicate, copyright, terms of use…
+  Rejected,
+  /// `uploadStatus: failed`, or processing failed or was terminated.
+  Failed,
+  /// `uploadStatus: deleted`, or `videos.list` no longer knows the video.
+  Removed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+  pub kind: RejectionKind,
+  /// YouTube's reason, e.g. `duplicate`, `copyright`, `codec` or
+  /// `transcodeFailed`; absent when it gives none.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reason: Option<String>,
+  /// Unix seconds it was noticed.
+  pub detected_at: u64,
+}
+
+impl Rejection {
+  pub fn describe(&self, video_id: &str) -> String {
+    let reason = self.reason.as_ref().map(|r| format!(" ({})", r)).unwrap_or_default();
+    match self.kind {
+      RejectionKind::Rejected => format!("YouTube odrzucił wideo {}{}", video_id, reason),
+      RejectionKind::Failed => format!("YouTube nie przetworzył wideo {}{}", video_id, reason),
+      RejectionKind::Removed => format!("Wideo {} nie istnieje już na YouTube{}", video_id, reason),
+    }
+  }
+}
+
+/// The end a `videos.list` item with [`PARTS`] shows, if it isn't live.
+pub(crate) fn rejection_of(item: &Value) -> Option<Rejection> {
+  let (status, processing) = (&item["status"], &item["processingDetails"]);
+  let (kind, reason) = match status["uploadStatus"].as_str() {
+    Some("rejected") => (RejectionKind::Rejected, &status["rejectionReason"]),
+    Some("failed") => (RejectionKind::Failed, &status["failureReason"]),
+    Some("deleted") => (RejectionKind::Removed, &Value::Null),
+    _ => match processing["processingStatus"].as_str() {
+      Some("failed" | "terminated") => (RejectionKind::Failed, &processing["processingFailureReason"]),
+      _ => return None,
+    },
+  };
+  Some(Rejection { kind, reason: reason.as_str().map(str::to_string), detected_at: now_secs() })
+}
+
+/// Marks `video_id` as not live in the history, the library and the queue
+/// (where its entry fails with the reason). Returns how many records
+/// changed; none when they already said so.
+pub(crate) fn record(dir: &Path, video_id: &str, rejection: &Rejection) -> Result<usize, Error> {
+  let records = history::mark_rejected(dir, video_id, rejection)?;
+  let entries = library::mark_rejected(dir, video_id, rejection)?;
+  let queued = queue::mark_rejected(dir, video_id, rejection)?;
+  Ok(records + entries + usize::from(queued))
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct VerifyOptions {
+  /// Only videos uploaded from then on: RFC3339 or `YYYY-MM-DD` (UTC).
+  #[serde(default)]
+  pub since: Option<String>,
+  /// Report only; local records stay as they are.
+  #[serde(default)]
+  pub dry_run: bool,
+}
+
+/// A recorded video that isn't live.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RemoteVideo {
+  pub video_id: String,
+  /// The file it was uploaded from, when recorded.
+  pub file_path: Option<String>,
+  pub title: Option<String>,
+  /// Absent for private videos.
+  pub rejection: Option<Rejection>,
+  /// `publishAt` of a private video, when it's scheduled.
+  pub publish_at: Option<String>,
+}
+
+/// What `verify_remote_state` returns.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct VerifyReport {
+  /// Distinct video ids in the local records.
+  pub checked: usize,
+  /// Public or unlisted, and processed or processing.
+  pub live: usize,
+  /// Removed on YouTube or unknown to it.
+  pub missing: Vec<RemoteVideo>,
+  /// Private on YouTube, e.g. set so in Studio or scheduled.
+  pub private: Vec<RemoteVideo>,
+  /// Rejected or failed processing.
+  pub rejected: Vec<RemoteVideo>,
+  /// Local records changed; 0 with `dry_run`.
+  pub updated: usize,
+}
+
+/// Payload of `remote-state-verified`.
+#[derive(Serialize, Debug, Clone)]
+pub struct RemoteStateSummary {
+  pub checked: usize,
+  pub live: usize,
+  pub missing: usize,
+  pub private: usize,
+  pub rejected: usize,
+  pub updated: usize,
+}
+
+/// Every video id the history, the library and the queue know, with its
+/// file and title, uploaded at or after `since`.
+fn recorded(dir: &Path, since: u64) -> BTreeMap<String, (Option<String>, Option<String>)> {
+  let mut ids: BTreeMap<String, (Option<String>, Option<String>)> = BTreeMap::new();
+  for r in history::read_all(dir).into_iter().filter(|r| r.timestamp >= since) {
+    if let Some(id) = r.video_id {
+      ids.insert(id, (Some(r.file_path), Some(r.title)));
+    }
+  }
+  for e in library::entries(dir).into_iter().filter(|e| e.uploaded_at >= since) {
+    ids.entry(e.video_id).or_insert((Some(e.last_seen_path), None));
+  }
+  for e in queue::list(dir).into_iter().filter(|e| e.uploaded_at.is_some_and(|t| t >= since)) {
+    if let Some(id) = e.video_id {
+      ids.entry(id).or_insert((Some(e.file_path), None));
+    }
+  }
+  ids
+}
+
+/// Looks up every recorded video (50 per `videos.list` call) and sorts out
+/// the ones that are missing, private or rejected. Unless `dry_run`, the
+/// missing and rejected ones are recorded as such. Emits
+/// `remote-state-verified`.
+pub async fn verify(dir: &Path, options: &VerifyOptions) -> Result<VerifyReport, Error> {
+  let since = options.since.as_deref().map(|v| history::parse_bound(v, false)).transpose()?.unwrap_or(0);
+  let ids = recorded(dir, since);
+  let list: Vec<String> = ids.keys().cloned().collect();
+  let found = videos::fetch_as(dir, &list, PARTS, READ_SCOPES).await?;
+  let mut report = VerifyReport { checked: ids.len(), ..Default::default() };
+  for (video_id, (file_path, title)) in ids {
+    let item = found.get(&video_id);
+    let rejection = match item {
+      Some(item) => rejection_of(item),
+      None => Some(Rejection { kind: RejectionKind::Removed, reason: None, detected_at: now_secs() }),
+    };
+    let private = item.is_some_and(|i| i["status"]["privacyStatus"] == "private");
+    let publish_at = item.and_then(|i| i["status"]["publishAt"].as_str()).map(str::to_string);
+    if let (Some(rejection), false) = (&rejection, options.dry_run) {
+      report.updated += record(dir, &video_id, rejection)?;
+    }
+    let kind = rejection.as_ref().map(|r| r.kind);
+    let video = RemoteVideo { video_id, file_path, title, rejection, publish_at };
+    match kind {
+      Some(RejectionKind::Removed) => report.missing.push(video),
+      Some(_) => report.rejected.push(video),
+      None if private => report.private.push(video),
+      None => report.live += 1,
+    }
+  }
+  let summary = RemoteStateSummary {
+    checked: report.checked,
+    live: report.live,
+    missing: report.missing.len(),
+    private: report.private.len(),
+    rejected: report.rejected.len(),
+    updated: report.updated,
+  };
+  runtime::for_dir(dir).emit("remote-state-verified", &summary);
+  Ok(report)
+}
@@ -0,0 +1,76 @@
+//! Replacing the OAuth client's credentials. A new secret for the same
+//! client_id keeps the session: the refresh token belongs to the client, not
+//! to the secret, so it's checked with a refresh before it's saved. A new
+//! client_id is another client, whose login the stored tokens can't stand in
+//! for, so they go and the user logs in again. Both are audit-logged, without
+//! the secrets.
+
+use crate::{
+  audit,
+  auth::{self, AuthStatus},
+  error::Error,
+  read_config_from_dir, read_tokens_from_dir, refresh, refresh_with_secret, runtime, setup, storage, update_config_in_dir,
+};
+use serde_json::json;
+use std::{fs, path::Path};
+
+/// Checks `new_secret` with a refresh of the stored tokens and saves it only
+/// if Google accepts it. The old secret stays in memory for one more try,
+/// in case a later refresh reaches a server the new one hasn't reached yet.
+pub async fn rotate_client_secret(dir: &Path, new_secret: &str) -> Result<AuthStatus, Error> {
+  let new_secret = new_secret.trim();
+  if new_secret.is_empty() {
+    return Err(Error::Validation("Nowy client_secret jest pusty".into()));
+  }
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  if new_secret == cfg.client_secret {
+    return Ok(auth::status(dir));
+  }
+  if read_tokens_from_dir(dir).is_none_or(|t| t.refresh_token.is_empty()) {
+    return Err(Error::Auth(
+      "Bez refresh_token nie da się sprawdzić nowego sekretu — zaloguj się albo wpisz go w konfiguracji ręcznie".into(),
+    ));
+  }
+  refresh_with_secret(dir, Some(new_secret)).await.map_err(|e| e.context("Nowy client_secret nie został zapisany"))?;
+  let previous = update_config_in_dir(dir, |c| std::mem::replace(&mut c.client_secret, new_secret.to_string()))?;
+  *runtime::for_dir(dir).previous_secret() = Some(previous);
+  refresh::reset(dir);
+  audit::record(dir, "rotate_client_secret", json!({ "client_id": cfg.client_id }))?;
+  Ok(auth::status(dir))
+}
+
+/// Switches to another OAuth client. Stored tokens were issued for the old
+/// one and are deleted, which takes `confirm`; without it the error explains
+/// what would happen.
+pub fn rotate_client_id(dir: &Path, client_id: &str, client_secret: &str, confirm: bool) -> Result<AuthStatus, Error> {
+  let (client_id, client_secret) = (client_id.trim(), client_secret.trim());
+  if client_id.is_empty() || client_secret.is_empty() {
+    return Err(Error::Validation("Nowy klient wymaga client_id i client_secret".into()));
+  }
+  let cfg = read_config_from_dir(dir).ok_or_else(|| Error::Config("Brak konfiguracji klienta".into()))?;
+  if client_id == cfg.client_id {
+    return Err(Error::Validation("client_id się nie zmienia — sam sekret zmienia rotate_client_secret, bez ponownego logowania".into()));
+  }
+  let had_tokens = read_tokens_from_dir(dir).is_some();
+  if had_tokens && !confirm {
+    return Err(Error::Validation(format!(
+      "{} to inny klient OAuth niż {}: zapisane tokeny wydano dla poprzedniego i nie zadziałają z nowym, więc zostaną usunięte i trzeba będzie zalogować się ponownie. Potwierdź zmianę (confirm)",
+      client_id, cfg.client_id
+    )));
+  }
+  update_config_in_dir(dir, |c| {
+    c.client_id = client_id.to_string();
+    c.client_secret = client_secret.to_string();
+  })?;
+  if had_tokens {
+    let lock = storage::lock(dir)?;
+    fs::remove_file(dir.join("tokens.json")).map_err(|e| Error::Storage(format!("Nie można usunąć tokens.json: {}", e)))?;
+    drop(lock);
+    auth::notify_changed(dir);
+  }
+  *runtime::for_dir(dir).previous_secret() = None;
+  refresh::reset(dir);
+  audit::record(dir, "rotate_client_id", json!({ "previous_client_id": cfg.client_id, "client_id": client_id, "tokens_cleared": had_tokens }))?;
+  setup::notify(dir);
+  Ok(auth::status(dir))
+}
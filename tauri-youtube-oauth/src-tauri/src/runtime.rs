@@ -0,0 +1,361 @@
+use crate::{
+  callback::CallbackServer,
+  capabilities::Capabilities,
+  capture::CaptureBuffer,
+  enrichment::EnrichmentCache,
+  events::Dispatcher,
+  metrics::Metrics,
+  notifications::{Batch, Notice},
+  privacy::PendingChange,
+  refresh::RefreshState,
+  sinks::{self, SinkState},
+  status_file::{self, StatusState},
+  tls::ClientKey,
+};
+use serde::Serialize;
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex, MutexGuard, OnceLock},
+  time::{Duration, Instant},
+};
+
+/// An access token kept only in memory.
+#[derive(Debug, Clone)]
+pub(crate) struct HeldToken {
+  pub refresh_token: String,
+  pub access_token: String,
+  pub created_at: u64,
+  pub expires_in: u64,
+}
+
+/// Payload of `warning`: something went wrong where no caller waits for the
+/// answer — a sink, a background stage, a cleanup run. See [`Runtime::warn`].
+#[derive(Serialize, Debug, Clone)]
+pub struct Warning {
+  /// What reported it, e.g. `event_sinks` or `cleanup`.
+  pub source: String,
+  pub message: String,
+}
+
+type Emitter = Box<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+type Notifier = Box<dyn Fn(&Notice) -> Result<(), String> + Send + Sync>;
+
+/// In-memory state that lives as long as the process, one instance per config
+/// dir. Keying by dir keeps the core usable without a Tauri app and keeps two
+/// config dirs in one process (tests, multiple instances) apart.
+#[derive(Default)]
+pub(crate) struct Runtime {
+  /// The config dir this runtime belongs to.
+  dir: PathBuf,
+  cache: Mutex<HashMap<String, (Instant, serde_json::Value)>>,
+  emitter: Mutex<Option<Emitter>>,
+  /// Shows desktop notifications; attached by the app.
+  notifier: Mutex<Option<Notifier>>,
+  /// The queue batch the notifications summarize.
+  notice_batch: Mutex<Batch>,
+  /// Progress coalescing in front of the emitter.
+  events: Mutex<Dispatcher>,
+  /// Webhook deliveries of the configured event sinks.
+  sinks: Mutex<SinkState>,
+  /// What status.json shows besides the files on disk.
+  status_file: Mutex<StatusState>,
+  /// Set by `set_dry_run`; overrides the config flag until the app restarts.
+  dry_run: Mutex<Option<bool>>,
+  /// Set by `set_read_only_mode`; overrides the config flag the same way.
+  read_only: Mutex<Option<bool>>,
+  /// Last accepted config, as JSON, for `config-changed` diffs.
+  config_snapshot: Mutex<Option<serde_json::Value>>,
+  /// Token refresh failure tracking; loaded from disk on first use.
+  refresh_state: Mutex<Option<RefreshState>>,
+  /// Shared HTTP clients, see [`crate::tls::request`].
+  http_clients: Mutex<HashMap<ClientKey, reqwest::Client>>,
+  /// Last connectivity probe result.
+  connectivity: Mutex<Option<(Instant, bool)>>,
+  forced_offline: Mutex<bool>,
+  capture: Mutex<CaptureBuffer>,
+  metrics: Mutex<Metrics>,
+  /// Recent wrong app passphrase attempts.
+  passphrase_failures: Mutex<Vec<Instant>>,
+  /// Recent `youtube_raw_request` calls.
+  raw_requests: Mutex<Vec<Instant>>,
+  /// Signs listing cursors; new each run, so old cursors stop verifying.
+  cursor_key: OnceLock<[u8; 32]>,
+  /// The OAuth redirect listener while a login is pending.
+  callback_server: Mutex<Option<CallbackServer>>,
+  uploads_in_flight: Mutex<usize>,
+  /// Shutdown began; uploads stop at the next chunk.
+  stopping: Mutex<bool>,
+  /// `app-closing` was shown with uploads running; the next close proceeds.
+  exit_warned: Mutex<bool>,
+  /// Bumped when a profile switch ends this dir's background tasks.
+  task_generation: Mutex<u64>,
+  /// Notified alongside, so sleeping tasks end without waiting out the sleep.
+  tasks_ended: tokio::sync::Notify,
+  /// A queue worker is running.
+  queue_busy: Mutex<bool>,
+  /// Queue view details, computed in the background.
+  enrichment: Mutex<EnrichmentCache>,
+  /// `issued_at` of the tokens `token-aging-warning` was last sent for.
+  aging_warned: Mutex<Option<u64>>,
+  /// Bulk privacy plans awaiting confirmation, by token.
+  privacy_confirmations: Mutex<HashMap<String, (Instant, PendingChange)>>,
+  /// Last `capabilities-changed` payload sent.
+  capabilities: Mutex<Option<Capabilities>>,
+  /// The access token while `persist_access_token` is off, for the refresh
+  /// token it came with. Never written to disk.
+  held_token: Mutex<Option<HeldToken>>,
+  /// The client secret `rotate_client_secret` replaced, tried once more
+  /// while Google still propagates the new one. Never written to disk.
+  previous_secret: Mutex<Option<String>>,
+  /// Faults armed with `chaos::inject`.
+  #[cfg(feature = "chaos")]
+  chaos: Mutex<Vec<crate::chaos::Armed>>,
+  /// The `/metrics` listener while it runs.
+  #[cfg(feature = "metrics-endpoint")]
+  metrics_server: Mutex<Option<crate::metrics_endpoint::MetricsServer>>,
+  /// Notified whenever tokens are written.
+  auth_changed: tokio::sync::Notify,
+}
+
+pub(crate) fn for_dir(dir: &Path) -> Arc<Runtime> {
+  static RUNTIMES: OnceLock<Mutex<HashMap<PathBuf, Arc<Runtime>>>> = OnceLock::new();
+  let mut map = RUNTIMES.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+  map.entry(dir.to_path_buf()).or_insert_with(|| Arc::new(Runtime { dir: dir.to_path_buf(), ..Default::default() })).clone()
+}
+
+impl Runtime {
+  /// Routes events to `emit` unless an emitter is already attached.
+  pub fn attach_emitter(&self, emit: impl Fn(&str, serde_json::Value) + Send + Sync + 'static) {
+    let mut emitter = self.emitter.lock().unwrap_or_else(|e| e.into_inner());
+    if emitter.is_none() {
+      *emitter = Some(Box::new(emit));
+    }
+  }
+
+  /// Sends an event to the attached emitter, if any, to the configured
+  /// event sinks and to status.json. Every event the core sends ends here.
+  pub fn emit(&self, event: &str, payload: impl Serialize) {
+    let payload = serde_json::to_value(payload).unwrap_or_default();
+    if let Some(emit) = self.emitter.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+      emit(event, payload.clone());
+    }
+    let failures = sinks::forward(&self.dir, &mut self.sinks.lock().unwrap_or_else(|e| e.into_inner()), event, &payload);
+    for failure in failures {
+      self.warn(sinks::SOURCE, failure);
+    }
+    // A warning changes nothing status.json shows, and may come from inside it.
+    if event != "warning" {
+      status_file::observe(&self.dir, event, &payload);
+    }
+  }
+
+  /// Emits `warning` for a failure nobody is waiting on, and prints it to
+  /// stderr for headless runs and logs; the one place the core prints.
+  pub fn warn(&self, source: &str, message: impl std::fmt::Display) {
+    let warning = Warning { source: source.to_string(), message: message.to_string() };
+    eprintln!("{}: {}", warning.source, warning.message);
+    self.emit("warning", &warning);
+  }
+
+  /// Routes desktop notifications to `show` unless a notifier is already attached.
+  pub fn attach_notifier(&self, show: impl Fn(&Notice) -> Result<(), String> + Send + Sync + 'static) {
+    let mut notifier = self.notifier.lock().unwrap_or_else(|e| e.into_inner());
+    if notifier.is_none() {
+      *notifier = Some(Box::new(show));
+    }
+  }
+
+  /// Shows `notice`; fails when nothing is attached or the system refused.
+  pub fn deliver(&self, notice: &Notice) -> Result<(), String> {
+    match self.notifier.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+      Some(show) => show(notice),
+      None => Err("Brak obsługi powiadomień (tryb bez okna)".into()),
+    }
+  }
+
+  pub fn notice_batch(&self) -> MutexGuard<'_, Batch> {
+    self.notice_batch.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn events(&self) -> MutexGuard<'_, Dispatcher> {
+    self.events.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn http_clients(&self) -> MutexGuard<'_, HashMap<ClientKey, reqwest::Client>> {
+    self.http_clients.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn status_file(&self) -> MutexGuard<'_, StatusState> {
+    self.status_file.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn dry_run_override(&self) -> Option<bool> {
+    *self.dry_run.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn set_dry_run(&self, enabled: bool) {
+    *self.dry_run.lock().unwrap_or_else(|e| e.into_inner()) = Some(enabled);
+  }
+
+  pub fn read_only_override(&self) -> Option<bool> {
+    *self.read_only.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn set_read_only(&self, enabled: bool) {
+    *self.read_only.lock().unwrap_or_else(|e| e.into_inner()) = Some(enabled);
+  }
+
+  pub fn seed_config_snapshot(&self, read: impl FnOnce() -> Option<serde_json::Value>) {
+    let mut snapshot = self.config_snapshot.lock().unwrap_or_else(|e| e.into_inner());
+    if snapshot.is_none() {
+      *snapshot = read();
+    }
+  }
+
+  pub fn config_snapshot(&self) -> Option<serde_json::Value> {
+    self.config_snapshot.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+
+  /// Stores `new` as the accepted config and returns the previous one.
+  pub fn replace_config_snapshot(&self, new: serde_json::Value) -> Option<serde_json::Value> {
+    self.config_snapshot.lock().unwrap_or_else(|e| e.into_inner()).replace(new)
+  }
+
+  /// Stores `new` as the last sent capabilities and returns the previous ones.
+  pub fn replace_capabilities(&self, new: Capabilities) -> Option<Capabilities> {
+    self.capabilities.lock().unwrap_or_else(|e| e.into_inner()).replace(new)
+  }
+
+  pub fn refresh_state(&self) -> MutexGuard<'_, Option<RefreshState>> {
+    self.refresh_state.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Last known connectivity if observed less than `ttl` ago.
+  pub fn connectivity(&self, ttl: Duration) -> Option<bool> {
+    self.connectivity.lock().unwrap_or_else(|e| e.into_inner()).filter(|(at, _)| at.elapsed() < ttl).map(|(_, v)| v)
+  }
+
+  /// Stores the observed state; returns the previous one, however old.
+  pub fn set_connectivity(&self, online: bool) -> Option<bool> {
+    self.connectivity.lock().unwrap_or_else(|e| e.into_inner()).replace((Instant::now(), online)).map(|(_, v)| v)
+  }
+
+  pub fn clear_connectivity(&self) {
+    if let Some((at, _)) = self.connectivity.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+      // Keep the value for flip detection, but force the next check to probe.
+      *at = Instant::now() - Duration::from_secs(24 * 60 * 60);
+    }
+  }
+
+  pub fn forced_offline(&self) -> bool {
+    *self.forced_offline.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn set_forced_offline(&self, enabled: bool) {
+    *self.forced_offline.lock().unwrap_or_else(|e| e.into_inner()) = enabled;
+  }
+
+  pub fn capture(&self) -> MutexGuard<'_, CaptureBuffer> {
+    self.capture.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn enrichment(&self) -> MutexGuard<'_, EnrichmentCache> {
+    self.enrichment.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn metrics(&self) -> MutexGuard<'_, Metrics> {
+    self.metrics.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn passphrase_failures(&self) -> MutexGuard<'_, Vec<Instant>> {
+    self.passphrase_failures.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn raw_requests(&self) -> MutexGuard<'_, Vec<Instant>> {
+    self.raw_requests.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn cursor_key(&self) -> [u8; 32] {
+    *self.cursor_key.get_or_init(|| {
+      let mut key = [0u8; 32];
+      rand_core::RngCore::fill_bytes(&mut rand_core::OsRng, &mut key);
+      key
+    })
+  }
+
+  pub fn callback_server(&self) -> MutexGuard<'_, Option<CallbackServer>> {
+    self.callback_server.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  #[cfg(feature = "metrics-endpoint")]
+  pub fn metrics_server(&self) -> MutexGuard<'_, Option<crate::metrics_endpoint::MetricsServer>> {
+    self.metrics_server.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn uploads_in_flight(&self) -> MutexGuard<'_, usize> {
+    self.uploads_in_flight.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn stopping(&self) -> MutexGuard<'_, bool> {
+    self.stopping.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn exit_warned(&self) -> MutexGuard<'_, bool> {
+    self.exit_warned.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn task_generation(&self) -> MutexGuard<'_, u64> {
+    self.task_generation.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn tasks_ended(&self) -> &tokio::sync::Notify {
+    &self.tasks_ended
+  }
+
+  pub fn queue_busy(&self) -> MutexGuard<'_, bool> {
+    self.queue_busy.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn aging_warned(&self) -> MutexGuard<'_, Option<u64>> {
+    self.aging_warned.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn privacy_confirmations(&self) -> MutexGuard<'_, HashMap<String, (Instant, PendingChange)>> {
+    self.privacy_confirmations.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn held_token(&self) -> MutexGuard<'_, Option<HeldToken>> {
+    self.held_token.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn previous_secret(&self) -> MutexGuard<'_, Option<String>> {
+    self.previous_secret.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  #[cfg(feature = "chaos")]
+  pub fn chaos(&self) -> MutexGuard<'_, Vec<crate::chaos::Armed>> {
+    self.chaos.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn auth_changed(&self) -> &tokio::sync::Notify {
+    &self.auth_changed
+  }
+
+  /// Cached value for `key` if it was stored less than `ttl` ago.
+  pub fn cache_get(&self, key: &str, ttl: Duration) -> Option<serde_json::Value> {
+    let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+    match cache.get(key) {
+      Some((at, v)) if at.elapsed() < ttl => Some(v.clone()),
+      Some(_) => {
+        cache.remove(key);
+        None
+      }
+      None => None,
+    }
+  }
+
+  pub fn cache_put(&self, key: &str, value: serde_json::Value) {
+    self.cache.lock().unwrap_or_else(|e| e.into_inner()).insert(key.to_string(), (Instant::now(), value));
+  }
+}
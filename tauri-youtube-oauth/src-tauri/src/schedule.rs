@@ -0,0 +1,120 @@
+//! Spreads queued uploads over days so a batch stays under the channel's
+//! daily upload limit: each day gets at most `max_per_day` evenly spaced
+//! slots inside the allowed hours, in the configured timezone.
+
+use crate::{
+  error::Error,
+  queue::{self, EntryStatus, QueueEntry},
+};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScheduleOptions {
+  pub max_per_day: u32,
+  /// Allowed hours, `[window_start_hour, window_end_hour)` local time.
+  #[serde(default)]
+  pub window_start_hour: u32,
+  #[serde(default = "default_window_end")]
+  pub window_end_hour: u32,
+  /// IANA name, e.g. `Europe/Warsaw`.
+  #[serde(default = "default_timezone")]
+  pub timezone: String,
+  /// First day to use; earlier days (and past slots today) are skipped.
+  #[serde(default)]
+  pub start_date: Option<NaiveDate>,
+  /// Also set `publishAt` from the slot, so videos go public on the same
+  /// spread instead of right after processing.
+  #[serde(default)]
+  pub schedule_publishing: bool,
+}
+
+fn default_window_end() -> u32 {
+  24
+}
+
+fn default_timezone() -> String {
+  "UTC".into()
+}
+
+/// One planned upload.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScheduledEntry {
+  pub id: String,
+  /// Unix seconds.
+  pub start_at: u64,
+  /// The same moment as RFC3339 in the schedule's timezone.
+  pub start_local: String,
+  /// `start_local` worded in the app language; filled in by `queue::plan`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub start_label: Option<String>,
+}
+
+pub fn timezone(options: &ScheduleOptions) -> Result<Tz, Error> {
+  options.timezone.parse().map_err(|_| Error::Config(format!("Nieznana strefa czasowa: {}", options.timezone)))
+}
+
+pub fn validate(options: &ScheduleOptions) -> Result<(), Error> {
+  if options.max_per_day == 0 {
+    return Err(Error::Config("max_per_day musi być dodatnie".into()));
+  }
+  if options.window_start_hour >= options.window_end_hour || options.window_end_hour > 24 {
+    return Err(Error::Config(format!(
+      "Nieprawidłowe okno godzin {}–{} (wymagane 0 ≤ początek < koniec ≤ 24)",
+      options.window_start_hour, options.window_end_hour
+    )));
+  }
+  timezone(options).map(|_| ())
+}
+
+/// `secs` after local midnight of `day`. A time inside a DST gap doesn't
+/// exist locally and is taken as UTC instead.
+fn local_time(tz: &Tz, day: NaiveDate, secs: i64) -> DateTime<Tz> {
+  let naive = day.and_hms_opt(0, 0, 0).unwrap_or_default() + Duration::seconds(secs);
+  tz.from_local_datetime(&naive).earliest().unwrap_or_else(|| tz.from_utc_datetime(&naive))
+}
+
+/// Start of the day after `day` in `tz`.
+pub fn next_day_start(tz: &Tz, day: NaiveDate) -> DateTime<Utc> {
+  local_time(tz, day.succ_opt().unwrap_or(day), 0).with_timezone(&Utc)
+}
+
+/// Slots for the pending entries, by priority then queue order, none before `not_before`.
+/// Entries already uploaded count against their day's limit.
+pub fn plan_upload_schedule(entries: &[QueueEntry], options: &ScheduleOptions, not_before: DateTime<Utc>) -> Result<Vec<ScheduledEntry>, Error> {
+  validate(options)?;
+  let tz = timezone(options)?;
+  let mut used: HashMap<NaiveDate, u32> = HashMap::new();
+  for at in entries.iter().filter(|e| e.status == EntryStatus::Done).filter_map(|e| e.uploaded_at) {
+    if let Some(t) = DateTime::<Utc>::from_timestamp(at as i64, 0) {
+      *used.entry(t.with_timezone(&tz).date_naive()).or_default() += 1;
+    }
+  }
+  let window_start = i64::from(options.window_start_hour) * 3600;
+  let step = i64::from(options.window_end_hour - options.window_start_hour) * 3600 / i64::from(options.max_per_day);
+  let today = not_before.with_timezone(&tz).date_naive();
+  let mut day = options.start_date.map_or(today, |d| d.max(today));
+  let (mut slot, mut taken) = (0, used.get(&day).copied().unwrap_or(0));
+
+  let mut planned = Vec::new();
+  for entry in queue::pending_in_order(entries) {
+    let at = loop {
+      if slot >= options.max_per_day || taken >= options.max_per_day {
+        day = day.succ_opt().unwrap_or(day);
+        slot = 0;
+        taken = used.get(&day).copied().unwrap_or(0);
+        continue;
+      }
+      let t = local_time(&tz, day, window_start + step * i64::from(slot));
+      slot += 1;
+      if t.with_timezone(&Utc) >= not_before {
+        taken += 1;
+        break t;
+      }
+    };
+    planned.push(ScheduledEntry { id: entry.id.clone(), start_at: at.timestamp().max(0) as u64, start_local: at.to_rfc3339(), start_label: None });
+  }
+  Ok(planned)
+}
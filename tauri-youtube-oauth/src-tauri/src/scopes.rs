@@ -0,0 +1,58 @@
+use crate::{error::Error, AppConfig, Tokens};
+
+pub const YOUTUBE_READONLY: &str = "https://www.googleapis.com/auth/youtube.readonly";
+pub const YOUTUBE_UPLOAD: &str = "https://www.googleapis.com/auth/youtube.upload";
+pub const YOUTUBE: &str = "https://www.googleapis.com/auth/youtube";
+pub const YOUTUBE_FORCE_SSL: &str = "https://www.googleapis.com/auth/youtube.force-ssl";
+/// YouTube Analytics reports; not part of the Data API scopes.
+pub const YT_ANALYTICS_READONLY: &str = "https://www.googleapis.com/auth/yt-analytics.readonly";
+/// Needed for writes made on behalf of a CMS content owner.
+pub const YOUTUBE_PARTNER: &str = "https://www.googleapis.com/auth/youtubepartner";
+
+/// Scopes requested by a login flow: whatever the config lists, or read-only
+/// access when nothing was configured (the original behaviour). Content
+/// owner setups always add youtubepartner.
+pub fn configured(cfg: &AppConfig) -> Vec<String> {
+  let mut scopes = if cfg.scopes.is_empty() { vec![YOUTUBE_READONLY.to_string()] } else { cfg.scopes.clone() };
+  if cfg.content_owner_id.is_some() && !scopes.iter().any(|s| s == YOUTUBE_PARTNER) {
+    scopes.push(YOUTUBE_PARTNER.to_string());
+  }
+  scopes
+}
+
+/// Scopes recorded with the tokens. Tokens saved before scopes were tracked
+/// came from the read-only flow, so an empty field means exactly that.
+pub fn granted(t: &Tokens) -> Vec<String> {
+  if t.scope.trim().is_empty() {
+    return vec![YOUTUBE_READONLY.to_string()];
+  }
+  t.scope.split_whitespace().map(str::to_string).collect()
+}
+
+/// Whether `held` implies `required`: the full youtube scope covers the
+/// narrower read-only and upload scopes, and force-ssl covers all of them
+/// (comment endpoints accept force-ssl only, so the reverse doesn't hold).
+pub fn covers(held: &str, required: &str) -> bool {
+  if held == required {
+    return true;
+  }
+  match held {
+    YOUTUBE_FORCE_SSL => matches!(required, YOUTUBE | YOUTUBE_READONLY | YOUTUBE_UPLOAD),
+    YOUTUBE => matches!(required, YOUTUBE_READONLY | YOUTUBE_UPLOAD),
+    _ => false,
+  }
+}
+
+pub fn missing(t: &Tokens, required: &[&str]) -> Vec<String> {
+  let held = granted(t);
+  required
+    .iter()
+    .filter(|r| !held.iter().any(|h| covers(h, r)))
+    .map(|r| r.to_string())
+    .collect()
+}
+
+pub fn require(t: &Tokens, required: &[&str]) -> Result<(), Error> {
+  let missing = missing(t, required);
+  if missing.is_empty() { Ok(()) } else { Err(Error::InsufficientScope { missing, reconsent: false }) }
+}
@@ -0,0 +1,190 @@
+use crate::{
+  api::{self, ApiCall},
+  error::Error,
+  quota, runtime,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{path::Path, time::Duration};
+
+/// `search.list` is expensive, so every page is worth remembering briefly.
+const SEARCH_COST: u32 = 100;
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_MAX_RESULTS: u32 = 25;
+const MAX_RESULTS_CAP: u32 = 500;
+/// Overall bound on fetching all pages of one search.
+const SEARCH_DEADLINE: Duration = Duration::from_secs(3 * 60);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchType {
+  Video,
+  Channel,
+  Playlist,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchOrder {
+  Date,
+  Rating,
+  Relevance,
+  Title,
+  VideoCount,
+  ViewCount,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchOptions {
+  #[serde(default, rename = "type")]
+  pub search_type: Option<SearchType>,
+  #[serde(default)]
+  pub order: Option<SearchOrder>,
+  /// RFC3339 timestamp.
+  #[serde(default)]
+  pub published_after: Option<String>,
+  #[serde(default)]
+  pub channel_id: Option<String>,
+  /// Total results across pages (default 25, at most 500).
+  #[serde(default)]
+  pub max_results: Option<u32>,
+  /// ISO 3166-1 alpha-2 code results should be viewable in.
+  #[serde(default)]
+  pub region_code: Option<String>,
+}
+
+/// What a result points at; the API reports exactly one id per kind.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SearchId {
+  Video { video_id: String },
+  Channel { channel_id: String },
+  Playlist { playlist_id: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchResult {
+  pub id: SearchId,
+  pub title: String,
+  pub description: String,
+  pub channel_id: String,
+  pub channel_title: String,
+  pub published_at: String,
+  pub thumbnail_url: Option<String>,
+}
+
+/// The serde name of a unit enum variant, which is also its API value.
+fn wire_name<T: Serialize>(v: &T) -> String {
+  serde_json::to_value(v).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+fn str_at(v: &Value, pointer: &str) -> String {
+  v.pointer(pointer).and_then(|s| s.as_str()).unwrap_or_default().to_string()
+}
+
+fn parse_result(item: &Value) -> Option<SearchResult> {
+  let id = &item["id"];
+  let id = match id["kind"].as_str()? {
+    "youtube#video" => SearchId::Video { video_id: id["videoId"].as_str()?.to_string() },
+    "youtube#channel" => SearchId::Channel { channel_id: id["channelId"].as_str()?.to_string() },
+    "youtube#playlist" => SearchId::Playlist { playlist_id: id["playlistId"].as_str()?.to_string() },
+    _ => return None,
+  };
+  let snippet = &item["snippet"];
+  Some(SearchResult {
+    id,
+    title: str_at(snippet, "/title"),
+    description: str_at(snippet, "/description"),
+    channel_id: str_at(snippet, "/channelId"),
+    channel_title: str_at(snippet, "/channelTitle"),
+    published_at: str_at(snippet, "/publishedAt"),
+    thumbnail_url: ["high", "medium", "default"]
+      .iter()
+      .find_map(|size| snippet["thumbnails"][size]["url"].as_str())
+      .map(str::to_string),
+  })
+}
+
+/// Cache key: the query with case and whitespace folded, plus the options
+/// that change what the API returns.
+fn cache_key(query: &str, o: &SearchOptions, max_results: u32, published_after: &Option<String>) -> String {
+  let q = query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+  format!(
+    "search:{}|{:?}|{:?}|{}|{}|{}|{}",
+    q,
+    o.search_type,
+    o.order,
+    published_after.as_deref().unwrap_or(""),
+    o.channel_id.as_deref().unwrap_or(""),
+    o.region_code.as_deref().unwrap_or("").to_uppercase(),
+    max_results
+  )
+}
+
+fn region_error(e: Error, region: &Option<String>) -> Error {
+  match e {
+    Error::Api { ref reason, ref message, .. } | Error::Forbidden { ref reason, ref message }
+      if reason.to_lowercase().contains("region") || message.to_lowercase().contains("region") =>
+    {
+      Error::Validation(format!(
+        "Wyszukiwanie niedostępne dla regionu {}: {}",
+        region.as_deref().unwrap_or("(domyślny)"),
+        message
+      ))
+    }
+    e => e,
+  }
+}
+
+pub async fn search(dir: &Path, query: &str, o: &SearchOptions) -> Result<Vec<SearchResult>, Error> {
+  if query.trim().is_empty() && o.channel_id.is_none() {
+    return Err(Error::Validation("Podaj frazę wyszukiwania lub channel_id".into()));
+  }
+  let max_results = o.max_results.unwrap_or(DEFAULT_MAX_RESULTS).clamp(1, MAX_RESULTS_CAP);
+  let published_after = o
+    .published_after
+    .as_deref()
+    .map(|t| {
+      DateTime::parse_from_rfc3339(t)
+        .map(|t| t.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true))
+        .map_err(|e| Error::Validation(format!("Nieprawidłowa data published_after (oczekiwano RFC3339): {}", e)))
+    })
+    .transpose()?;
+
+  let key = cache_key(query, o, max_results, &published_after);
+  let rt = runtime::for_dir(dir);
+  if let Some(cached) = rt.cache_get(&key, CACHE_TTL) {
+    if let Ok(results) = serde_json::from_value(cached) {
+      return Ok(results);
+    }
+  }
+
+  // Each page costs 100 units; refuse up front rather than stopping halfway.
+  let pages = max_results.div_ceil(50);
+  quota::check(dir, pages * SEARCH_COST)?;
+
+  let mut call = ApiCall::get("/youtube/v3/search").query("part", "snippet").cost(SEARCH_COST);
+  if !query.trim().is_empty() {
+    call = call.query("q", query.trim());
+  }
+  if let Some(t) = o.search_type {
+    call = call.query("type", wire_name(&t));
+  }
+  if let Some(order) = o.order {
+    call = call.query("order", wire_name(&order));
+  }
+  if let Some(t) = &published_after {
+    call = call.query("publishedAfter", t.clone());
+  }
+  if let Some(c) = &o.channel_id {
+    call = call.query("channelId", c.clone());
+  }
+  if let Some(r) = &o.region_code {
+    call = call.query("regionCode", r.to_uppercase());
+  }
+  let items = api::paginate(dir, call, max_results as usize, SEARCH_DEADLINE).await.map_err(|e| region_error(e, &o.region_code))?;
+  let results: Vec<SearchResult> = items.iter().filter_map(parse_result).collect();
+  rt.cache_put(&key, serde_json::to_value(&results).unwrap_or_default());
+  Ok(results)
+}
@@ -0,0 +1,177 @@
+use crate::{
+  api::{self, ApiCall},
+  error::Error,
+  playlists, scopes,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+const SECTION_SCOPES: &[&str] = &[scopes::YOUTUBE];
+/// YouTube caps channel home pages at this many sections.
+const MAX_SECTIONS: usize = 12;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SectionType {
+  AllPlaylists,
+  CompletedEvents,
+  LiveEvents,
+  MultipleChannels,
+  MultiplePlaylists,
+  PopularUploads,
+  RecentUploads,
+  SinglePlaylist,
+  Subscriptions,
+  UpcomingEvents,
+}
+
+impl SectionType {
+  fn as_str(&self) -> &'static str {
+    match self {
+      SectionType::AllPlaylists => "allPlaylists",
+      SectionType::CompletedEvents => "completedEvents",
+      SectionType::LiveEvents => "liveEvents",
+      SectionType::MultipleChannels => "multipleChannels",
+      SectionType::MultiplePlaylists => "multiplePlaylists",
+      SectionType::PopularUploads => "popularUploads",
+      SectionType::RecentUploads => "recentUploads",
+      SectionType::SinglePlaylist => "singlePlaylist",
+      SectionType::Subscriptions => "subscriptions",
+      SectionType::UpcomingEvents => "upcomingEvents",
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelSection {
+  pub id: String,
+  /// Wire name of the type; kept as a string so types added by the API later
+  /// still list instead of failing the whole layout.
+  pub section_type: String,
+  pub title: Option<String>,
+  pub position: u32,
+  pub playlist_ids: Vec<String>,
+  pub channel_ids: Vec<String>,
+}
+
+impl ChannelSection {
+  fn from_resource(v: &Value) -> Self {
+    let ids = |pointer: &str| -> Vec<String> {
+      v.pointer(pointer)
+        .and_then(|a| a.as_array())
+        .map(|a| a.iter().filter_map(|s| s.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+    };
+    ChannelSection {
+      id: v["id"].as_str().unwrap_or_default().to_string(),
+      section_type: v["snippet"]["type"].as_str().unwrap_or_default().to_string(),
+      title: v["snippet"]["title"].as_str().map(str::to_string),
+      position: v["snippet"]["position"].as_u64().unwrap_or_default() as u32,
+      playlist_ids: ids("/contentDetails/playlists"),
+      channel_ids: ids("/contentDetails/channels"),
+    }
+  }
+}
+
+/// Desired state of a section, for both create and update.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SectionInput {
+  pub section_type: SectionType,
+  #[serde(default)]
+  pub title: Option<String>,
+  #[serde(default)]
+  pub playlist_ids: Vec<String>,
+  #[serde(default)]
+  pub position: Option<u32>,
+}
+
+async fn validate(dir: &Path, input: &SectionInput) -> Result<(), Error> {
+  let needs_title = matches!(input.section_type, SectionType::MultiplePlaylists | SectionType::MultipleChannels);
+  if needs_title && input.title.as_deref().map(str::trim).unwrap_or_default().is_empty() {
+    return Err(Error::Validation(format!("Sekcja typu {} wymaga tytułu", input.section_type.as_str())));
+  }
+  match input.section_type {
+    SectionType::SinglePlaylist if input.playlist_ids.len() != 1 => {
+      return Err(Error::Validation("Sekcja singlePlaylist wymaga dokładnie jednej playlisty".into()));
+    }
+    SectionType::MultiplePlaylists if input.playlist_ids.is_empty() => {
+      return Err(Error::Validation("Sekcja multiplePlaylists wymaga co najmniej jednej playlisty".into()));
+    }
+    SectionType::SinglePlaylist | SectionType::MultiplePlaylists => {}
+    _ if !input.playlist_ids.is_empty() => {
+      return Err(Error::Validation(format!("Sekcja typu {} nie przyjmuje playlist", input.section_type.as_str())));
+    }
+    _ => {}
+  }
+  if !input.playlist_ids.is_empty() {
+    let missing = playlists::missing_playlists(dir, &input.playlist_ids).await?;
+    if !missing.is_empty() {
+      return Err(Error::Validation(format!("Nie znaleziono playlist: {}", missing.join(", "))));
+    }
+  }
+  Ok(())
+}
+
+fn resource(input: &SectionInput, id: Option<&str>) -> Value {
+  let mut snippet = json!({ "type": input.section_type.as_str() });
+  if let Some(t) = &input.title {
+    snippet["title"] = json!(t);
+  }
+  if let Some(p) = input.position {
+    snippet["position"] = json!(p);
+  }
+  let mut body = json!({ "snippet": snippet });
+  if !input.playlist_ids.is_empty() {
+    body["contentDetails"] = json!({ "playlists": input.playlist_ids });
+  }
+  if let Some(id) = id {
+    body["id"] = json!(id);
+  }
+  body
+}
+
+/// Current layout ordered by position.
+pub async fn list_sections(dir: &Path) -> Result<Vec<ChannelSection>, Error> {
+  let call = ApiCall::get("/youtube/v3/channelSections").query("part", "snippet,contentDetails").query("mine", "true");
+  let resp = api::execute(dir, call).await?;
+  let mut sections: Vec<ChannelSection> =
+    resp["items"].as_array().map(|a| a.iter().map(ChannelSection::from_resource).collect()).unwrap_or_default();
+  sections.sort_by_key(|s| s.position);
+  Ok(sections)
+}
+
+// The API shifts other sections' positions on every insert, move and delete,
+// so mutations report the re-listed layout instead of their own response.
+
+pub async fn create_section(dir: &Path, input: &SectionInput) -> Result<Vec<ChannelSection>, Error> {
+  api::ensure_writable(dir)?;
+  validate(dir, input).await?;
+  if list_sections(dir).await?.len() >= MAX_SECTIONS {
+    return Err(Error::Validation(format!("Kanał ma już maksymalną liczbę sekcji ({})", MAX_SECTIONS)));
+  }
+  let call = ApiCall::post("/youtube/v3/channelSections")
+    .query("part", "snippet,contentDetails")
+    .json(resource(input, None))
+    .scopes(SECTION_SCOPES);
+  api::execute(dir, call).await?;
+  list_sections(dir).await
+}
+
+pub async fn update_section(dir: &Path, id: &str, input: &SectionInput) -> Result<Vec<ChannelSection>, Error> {
+  api::ensure_writable(dir)?;
+  validate(dir, input).await?;
+  let call = ApiCall::put("/youtube/v3/channelSections")
+    .query("part", "snippet,contentDetails")
+    .json(resource(input, Some(id)))
+    .scopes(SECTION_SCOPES);
+  api::execute(dir, call).await?;
+  list_sections(dir).await
+}
+
+pub async fn delete_section(dir: &Path, id: &str) -> Result<Vec<ChannelSection>, Error> {
+  api::ensure_writable(dir)?;
+  let call = ApiCall::delete("/youtube/v3/channelSections").query("id", id).scopes(SECTION_SCOPES);
+  api::execute(dir, call).await?;
+  list_sections(dir).await
+}
@@ -0,0 +1,114 @@
+//! First-run setup as a state machine computed from what's on disk, so the
+//! frontend can render a wizard without duplicating the rules.
+
+use crate::{capabilities, config, env_drift, runtime, scopes, AppConfig, Tokens};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::Path};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SetupStep {
+  NeedsClientCredentials,
+  NeedsConsent,
+  NeedsChannelSelection,
+  Ready,
+}
+
+/// A file that exists but can't be used as is.
+#[derive(Serialize, Debug, Clone)]
+pub struct SetupProblem {
+  pub file: String,
+  pub message: String,
+}
+
+/// Payload of `get_setup_state` and the `setup-state-changed` event.
+#[derive(Serialize, Debug, Clone)]
+pub struct SetupState {
+  pub step: SetupStep,
+  /// What the current step still needs: config fields, `tokens`,
+  /// `refresh_token`, scopes or `channel_id`.
+  pub missing: Vec<String>,
+  pub problem: Option<SetupProblem>,
+}
+
+enum Loaded<T> {
+  Absent,
+  Broken(SetupProblem),
+  Ok(T),
+}
+
+fn load<T: DeserializeOwned>(dir: &Path, file: &str) -> Loaded<T> {
+  let raw = match fs::read_to_string(dir.join(file)) {
+    Ok(raw) => raw,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Loaded::Absent,
+    Err(e) => return Loaded::Broken(SetupProblem { file: file.into(), message: e.to_string() }),
+  };
+  if raw.trim().is_empty() {
+    return Loaded::Broken(SetupProblem { file: file.into(), message: "Plik jest pusty".into() });
+  }
+  match serde_json::from_str(&raw) {
+    Ok(v) => Loaded::Ok(v),
+    Err(e) => Loaded::Broken(SetupProblem { file: file.into(), message: format!("Uszkodzony JSON: {}", e) }),
+  }
+}
+
+fn state(step: SetupStep, missing: Vec<String>, problem: Option<SetupProblem>) -> SetupState {
+  SetupState { step, missing, problem }
+}
+
+pub fn current(dir: &Path) -> SetupState {
+  let cfg: AppConfig = match load(dir, "oauth_config.json") {
+    Loaded::Absent => {
+      return state(SetupStep::NeedsClientCredentials, vec!["client_id".into(), "client_secret".into()], None)
+    }
+    Loaded::Broken(p) => return state(SetupStep::NeedsClientCredentials, Vec::new(), Some(p)),
+    Loaded::Ok(cfg) => cfg,
+  };
+  let blank: Vec<String> = [("client_id", &cfg.client_id), ("client_secret", &cfg.client_secret)]
+    .iter()
+    .filter(|(_, v)| v.trim().is_empty())
+    .map(|(k, _)| k.to_string())
+    .collect();
+  if !blank.is_empty() {
+    return state(SetupStep::NeedsClientCredentials, blank, None);
+  }
+  if let Err(e) = config::validate(&cfg) {
+    let problem = SetupProblem { file: "oauth_config.json".into(), message: e.to_string() };
+    return state(SetupStep::NeedsClientCredentials, Vec::new(), Some(problem));
+  }
+
+  let t: Tokens = match load(dir, "tokens.json") {
+    Loaded::Absent => return state(SetupStep::NeedsConsent, vec!["tokens".into()], None),
+    Loaded::Broken(p) => return state(SetupStep::NeedsConsent, Vec::new(), Some(p)),
+    Loaded::Ok(t) => t,
+  };
+  if t.client_id.as_deref().is_some_and(|id| id != cfg.client_id) {
+    let problem = SetupProblem {
+      file: "tokens.json".into(),
+      message: "Tokeny wydano dla innego client_id — zaloguj się ponownie".into(),
+    };
+    return state(SetupStep::NeedsConsent, Vec::new(), Some(problem));
+  }
+  if t.refresh_token.is_empty() {
+    return state(SetupStep::NeedsConsent, vec!["refresh_token".into()], None);
+  }
+  let configured = scopes::configured(&cfg);
+  let required: Vec<&str> = configured.iter().map(String::as_str).collect();
+  let missing_scopes = scopes::missing(&t, &required);
+  if !missing_scopes.is_empty() {
+    return state(SetupStep::NeedsConsent, missing_scopes, None);
+  }
+
+  if cfg.channel_id.as_deref().unwrap_or_default().is_empty() {
+    return state(SetupStep::NeedsChannelSelection, vec!["channel_id".into()], None);
+  }
+  state(SetupStep::Ready, Vec::new(), None)
+}
+
+/// Emits `setup-state-changed` with the state after a step-completing change,
+/// and `env-drift` when that left the watched `.env` behind.
+pub(crate) fn notify(dir: &Path) {
+  runtime::for_dir(dir).emit("setup-state-changed", current(dir));
+  capabilities::notify(dir);
+  env_drift::report(dir);
+}
@@ -0,0 +1,116 @@
+//! Clean exit: uploads stop at their next chunk boundary and persist their
+//! session, background listeners stop, and only then does the app exit.
+
+use crate::{callback, runtime};
+use serde::Serialize;
+use std::{
+  path::{Path, PathBuf},
+  time::{Duration, Instant},
+};
+
+/// How long exit waits for uploads to reach a chunk boundary.
+pub const GRACE: Duration = Duration::from_secs(10);
+const POLL: Duration = Duration::from_millis(50);
+
+/// Held by an upload while it runs. Uploads check [`stopping`] between
+/// chunks and, when set, persist their session before dropping the guard.
+pub struct UploadGuard {
+  dir: PathBuf,
+}
+
+impl Drop for UploadGuard {
+  fn drop(&mut self) {
+    let rt = runtime::for_dir(&self.dir);
+    let mut n = rt.uploads_in_flight();
+    *n = n.saturating_sub(1);
+  }
+}
+
+pub fn track_upload(dir: &Path) -> UploadGuard {
+  *runtime::for_dir(dir).uploads_in_flight() += 1;
+  UploadGuard { dir: dir.to_path_buf() }
+}
+
+pub fn pending_uploads(dir: &Path) -> usize {
+  *runtime::for_dir(dir).uploads_in_flight()
+}
+
+/// Set once shutdown began; uploads must not start another chunk.
+pub fn stopping(dir: &Path) -> bool {
+  *runtime::for_dir(dir).stopping()
+}
+
+/// Generation of the dir's background tasks; a task takes it when it starts
+/// and ends once [`tasks_ended`] says so.
+pub(crate) fn task_generation(dir: &Path) -> u64 {
+  *runtime::for_dir(dir).task_generation()
+}
+
+/// Whether a background task started in `generation` should end: shutdown
+/// began, or a profile switch has left the dir since.
+pub(crate) fn tasks_ended(dir: &Path, generation: u64) -> bool {
+  stopping(dir) || task_generation(dir) != generation
+}
+
+/// Ends the dir's background tasks: at their next check, and sleeping ones
+/// right away. Uploads already running still finish.
+pub(crate) fn end_tasks(dir: &Path) {
+  let rt = runtime::for_dir(dir);
+  *rt.task_generation() += 1;
+  rt.tasks_ended().notify_waiters();
+}
+
+/// Sleeps for `wait`, or until the tasks of `generation` are ended.
+pub(crate) async fn idle(dir: &Path, generation: u64, wait: Duration) {
+  let rt = runtime::for_dir(dir);
+  let ended = rt.tasks_ended().notified();
+  if tasks_ended(dir, generation) {
+    return;
+  }
+  let _ = tokio::time::timeout(wait, ended).await;
+}
+
+/// Payload of `app-closing`.
+#[derive(Serialize, Debug, Clone)]
+pub struct AppClosing {
+  pub pending_uploads: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ShutdownReport {
+  pub pending_at_start: usize,
+  /// Uploads still running when the grace period ran out.
+  pub abandoned: usize,
+}
+
+/// A close was requested: emits `app-closing` and says whether to go ahead.
+/// With uploads running the first request only warns, so the UI can ask;
+/// a second close (or `confirm_exit`) proceeds.
+pub fn request_exit(dir: &Path) -> bool {
+  let rt = runtime::for_dir(dir);
+  if *rt.stopping() {
+    return false;
+  }
+  let pending_uploads = pending_uploads(dir);
+  rt.emit("app-closing", AppClosing { pending_uploads });
+  let mut warned = rt.exit_warned();
+  if pending_uploads == 0 || *warned {
+    return true;
+  }
+  *warned = true;
+  false
+}
+
+/// Signals uploads to stop, waits up to `grace` for them, then stops the
+/// callback listener. State files are written atomically as they change, so
+/// nothing else needs flushing.
+pub async fn shutdown(dir: &Path, grace: Duration) -> ShutdownReport {
+  *runtime::for_dir(dir).stopping() = true;
+  let pending_at_start = pending_uploads(dir);
+  let deadline = Instant::now() + grace;
+  while pending_uploads(dir) > 0 && Instant::now() < deadline {
+    tokio::time::sleep(POLL).await;
+  }
+  callback::stop(dir);
+  ShutdownReport { pending_at_start, abandoned: pending_uploads(dir) }
+}
@@ -0,0 +1,231 @@
+//! Checks of sidecar JSON files against the fields of [`VideoMetadata`]. A
+//! typo used to be ignored (`privacyStauts`) or fail at the API much later;
+//! here an unknown key is a warning naming the closest known one, a value
+//! outside an enum lists the allowed ones, and a wrong type or broken JSON
+//! points at its line and column. Errors stop the upload and the queue;
+//! warnings only with `sidecar_warnings_block`.
+
+use crate::{api::Privacy, error::Error, publish_time, read_config_from_dir, upload::VideoMetadata};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+const PRIVACY: [Privacy; 3] = [Privacy::Public, Privacy::Unlisted, Privacy::Private];
+/// YouTube API names people copy into sidecars, with the key meant.
+const API_NAMES: &[(&str, &str)] = &[("privacyStatus", "privacy")];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SidecarDiagnostic {
+  pub severity: Severity,
+  /// The key concerned; absent for the file as a whole.
+  pub field: Option<String>,
+  pub message: String,
+  /// The known key an unknown one most likely meant.
+  pub suggestion: Option<String>,
+  /// 1-based position in the file, when known.
+  pub line: Option<usize>,
+  pub column: Option<usize>,
+}
+
+/// Every key of [`VideoMetadata`] with a value of its type, taken from the
+/// struct itself so a new field is known here as soon as it exists.
+fn schema() -> Map<String, Value> {
+  let sample = VideoMetadata {
+    title: String::new(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: Some(String::new()),
+    privacy: Privacy::Private,
+    publish_at: Some(String::new()),
+    thumbnail: Some(PathBuf::new()),
+    first_comment: Some(String::new()),
+  };
+  match serde_json::to_value(sample) {
+    Ok(Value::Object(fields)) => fields,
+    _ => Map::new(),
+  }
+}
+
+fn distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.chars().enumerate() {
+    let mut prev = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let next = (prev + usize::from(ca != *cb)).min(row[j] + 1).min(row[j + 1] + 1);
+      prev = row[j + 1];
+      row[j + 1] = next;
+    }
+  }
+  row[b.len()]
+}
+
+/// Compared without case and separators, so `categoryId` finds `category_id`.
+fn normalized(key: &str) -> String {
+  key.chars().filter(|c| *c != '_' && *c != '-').flat_map(char::to_lowercase).collect()
+}
+
+/// The closest known key, if close enough to be a typo of it.
+fn suggest(key: &str, known: &Map<String, Value>) -> Option<String> {
+  let key = normalized(key);
+  let candidates = known.keys().map(|k| (k.as_str(), k.as_str())).chain(API_NAMES.iter().copied());
+  let (d, meant) = candidates.map(|(name, meant)| (distance(&key, &normalized(name)), meant)).min_by_key(|(d, _)| *d)?;
+  (d <= (key.chars().count() / 3).max(2)).then(|| meant.to_string())
+}
+
+/// Line and column of `"key":` in `text`, 1-based.
+fn locate(text: &str, key: &str) -> (Option<usize>, Option<usize>) {
+  let quoted = format!("\"{}\"", key);
+  let found = text.match_indices(&quoted).map(|(i, _)| i).find(|&i| text[i + quoted.len()..].trim_start().starts_with(':'));
+  let Some(at) = found else {
+    return (None, None);
+  };
+  let before = &text[..at];
+  let line = before.matches('\n').count() + 1;
+  let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+  (Some(line), Some(column))
+}
+
+/// Diagnostics for the sidecar `text`, errors first.
+pub fn validate_text(text: &str) -> Vec<SidecarDiagnostic> {
+  let file = |message: String, line, column| SidecarDiagnostic {
+    severity: Severity::Error,
+    field: None,
+    message,
+    suggestion: None,
+    line: Some(line),
+    column: Some(column),
+  };
+  let root: Value = match serde_json::from_str(text) {
+    Ok(v) => v,
+    Err(e) => return vec![file(format!("Nieprawidłowy JSON: {}", e), e.line(), e.column())],
+  };
+  let Value::Object(fields) = root else {
+    return vec![file("Plik metadanych musi być obiektem JSON".into(), 1, 1)];
+  };
+  let known = schema();
+  let mut found = Vec::new();
+  for (key, value) in &fields {
+    let (line, column) = locate(text, key);
+    let diagnostic = |severity, message: String, suggestion: Option<String>| SidecarDiagnostic {
+      severity,
+      field: Some(key.clone()),
+      message,
+      suggestion,
+      line,
+      column,
+    };
+    let Some(sample) = known.get(key) else {
+      let suggestion = suggest(key, &known);
+      let message = match &suggestion {
+        Some(meant) => format!("Nieznane pole {} — czy chodziło o {}?", key, meant),
+        None => format!("Nieznane pole {} zostanie pominięte", key),
+      };
+      found.push(diagnostic(Severity::Warning, message, suggestion));
+      continue;
+    };
+    let wrong_type = match (sample, value) {
+      // Absent and null mean the same for everything but the list.
+      (Value::Array(_), Value::Array(items)) => !items.iter().all(Value::is_string),
+      (Value::Array(_), _) => true,
+      (_, Value::Null) => false,
+      (_, v) => !v.is_string(),
+    };
+    if wrong_type {
+      let expected = if sample.is_array() { "listą tekstów" } else { "tekstem" };
+      found.push(diagnostic(Severity::Error, format!("Pole {} musi być {}", key, expected), None));
+      continue;
+    }
+    let Some(s) = value.as_str() else { continue };
+    if key == "privacy" && !PRIVACY.iter().any(|p| p.as_str() == s) {
+      let allowed: Vec<&str> = PRIVACY.iter().map(Privacy::as_str).collect();
+      let message = format!("Nieprawidłowa wartość privacy: {} (dozwolone: {})", s, allowed.join(", "));
+      let suggestion = allowed.iter().min_by_key(|a| distance(&s.to_lowercase(), a)).map(|a| a.to_string());
+      found.push(diagnostic(Severity::Error, message, suggestion));
+    } else if key == "publish_at" {
+      // Only the form: the timezone, and with it DST, is checked on upload.
+      if let Err(e) = publish_time::parse(s, chrono_tz::UTC, 0) {
+        found.push(diagnostic(Severity::Error, e.to_string(), None));
+      }
+    }
+  }
+  found.sort_by_key(|d| (d.severity != Severity::Error, d.line));
+  found
+}
+
+fn invalid_encoding(path: &Path, at: usize) -> Error {
+  Error::Validation(format!("{}: nieprawidłowe kodowanie znaków od bajtu {} (oczekiwano UTF-8 albo UTF-16 z BOM)", path.display(), at))
+}
+
+fn from_utf16(path: &Path, bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Result<String, Error> {
+  const BOM_LEN: usize = 2;
+  if bytes.len() % 2 == 1 {
+    return Err(invalid_encoding(path, BOM_LEN + bytes.len() - 1));
+  }
+  let mut text = String::with_capacity(bytes.len());
+  let mut at = BOM_LEN;
+  for c in char::decode_utf16(bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]))) {
+    let c = c.map_err(|_| invalid_encoding(path, at))?;
+    at += 2 * c.len_utf16();
+    text.push(c);
+  }
+  Ok(text)
+}
+
+/// Text of a sidecar read from `path`: UTF-8, with or without a byte order
+/// mark, or UTF-16 with one (as Windows editors save it). Bytes that don't
+/// decode are refused with their offset rather than replaced, so a broken
+/// title never goes up with `�` in it.
+pub(crate) fn decode(path: &Path, bytes: Vec<u8>) -> Result<String, Error> {
+  match bytes.as_slice() {
+    [0xEF, 0xBB, 0xBF, rest @ ..] => std::str::from_utf8(rest).map(str::to_string).map_err(|e| invalid_encoding(path, 3 + e.valid_up_to())),
+    [0xFF, 0xFE, rest @ ..] => from_utf16(path, rest, u16::from_le_bytes),
+    [0xFE, 0xFF, rest @ ..] => from_utf16(path, rest, u16::from_be_bytes),
+    _ => String::from_utf8(bytes).map_err(|e| invalid_encoding(path, e.utf8_error().valid_up_to())),
+  }
+}
+
+/// The sidecar at `path` as text, see [`decode`]; `None` when there is none.
+pub(crate) fn read_text(path: &Path) -> Result<Option<String>, Error> {
+  match fs::read(path) {
+    Ok(bytes) => decode(path, bytes).map(Some),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(Error::Storage(format!("{}: {}", path.display(), e))),
+  }
+}
+
+/// Diagnostics for the sidecar at `path`.
+pub fn validate(path: &Path) -> Result<Vec<SidecarDiagnostic>, Error> {
+  let bytes = fs::read(path).map_err(|e| Error::Storage(format!("{}: {}", path.display(), e)))?;
+  Ok(validate_text(&decode(path, bytes)?))
+}
+
+/// Fails with `SidecarInvalid` when `text` read from `path` has errors, or
+/// warnings and `sidecar_warnings_block` is on.
+pub(crate) fn check(dir: &Path, path: &Path, text: &str) -> Result<(), Error> {
+  let diagnostics = validate_text(text);
+  let strict = read_config_from_dir(dir).is_some_and(|c| c.sidecar_warnings_block);
+  if diagnostics.iter().any(|d| d.severity == Severity::Error || strict) {
+    return Err(Error::SidecarInvalid { path: path.display().to_string(), diagnostics });
+  }
+  Ok(())
+}
+
+/// [`check`] for the file at `path`; a missing sidecar is fine.
+pub(crate) fn check_file(dir: &Path, path: &Path) -> Result<(), Error> {
+  match read_text(path)? {
+    Some(text) => check(dir, path, &text),
+    None => Ok(()),
+  }
+}
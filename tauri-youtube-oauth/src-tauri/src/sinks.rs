@@ -0,0 +1,173 @@
+//! Event sinks for runs nobody watches through the webview: every event the
+//! runtime emits (after coalescing) also goes to the `event_sinks` in the
+//! config — appended to a JSONL file or POSTed to a webhook — as
+//! `{"event", "at", "payload"}` with the same payload the UI gets. Sinks are
+//! read from the config per event, so an edit applies to the next one. A
+//! failing sink is reported as a `warning` (kept out of the sinks, so it
+//! can't feed on itself) and never holds up or fails what emitted it.
+
+use crate::{error::Error, now_secs, read_config_from_dir, runtime};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{fs::OpenOptions, io::Write, path::Path, time::Duration};
+use tokio::sync::mpsc;
+
+/// `source` of the warnings about sinks.
+pub(crate) const SOURCE: &str = "event_sinks";
+/// Header carrying a webhook sink's `secret`.
+pub const SECRET_HEADER: &str = "X-YTLite-Secret";
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+/// Tries per event before a webhook delivery is given up.
+pub const WEBHOOK_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum EventSink {
+  /// Appends one line per event to `path`, relative to the config dir
+  /// unless absolute.
+  File {
+    path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    events: Vec<String>,
+  },
+  /// POSTs each event to `url`, with `secret` in [`SECRET_HEADER`]. Network
+  /// errors, 429 and 5xx are retried; events go out in order.
+  Webhook {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    events: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout_secs: Option<u64>,
+  },
+}
+
+impl EventSink {
+  /// Event names, or prefixes ending in `*` (`upload-*`); empty means all.
+  fn events(&self) -> &[String] {
+    let (EventSink::File { events, .. } | EventSink::Webhook { events, .. }) = self;
+    events
+  }
+
+  pub fn accepts(&self, event: &str) -> bool {
+    let filter = self.events();
+    filter.is_empty() || filter.iter().any(|f| f.strip_suffix('*').map_or(f == event, |prefix| event.starts_with(prefix)))
+  }
+}
+
+pub fn validate(sink: &EventSink) -> Result<(), Error> {
+  match sink {
+    EventSink::File { path, .. } if path.trim().is_empty() => Err(Error::Config("Ujście typu file wymaga pola path".into())),
+    EventSink::File { .. } => Ok(()),
+    EventSink::Webhook { url, .. } => {
+      let parsed = url::Url::parse(url).map_err(|e| Error::Config(format!("Nieprawidłowy adres webhooka {}: {}", url, e)))?;
+      if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(Error::Config(format!("Webhook musi używać http(s): {}", url)));
+      }
+      Ok(())
+    }
+  }
+}
+
+struct Delivery {
+  url: String,
+  secret: Option<String>,
+  timeout: Duration,
+  body: Value,
+}
+
+/// The webhook delivery queue, drained by one task in order.
+#[derive(Default)]
+pub(crate) struct SinkState {
+  queue: Option<mpsc::UnboundedSender<Delivery>>,
+}
+
+/// Hands `event` to every configured sink that wants it; returns what
+/// failed, for the caller to warn about once `state` is released.
+pub(crate) fn forward(dir: &Path, state: &mut SinkState, event: &str, payload: &Value) -> Vec<String> {
+  let mut failures = Vec::new();
+  if event == "warning" && payload["source"] == SOURCE {
+    return failures;
+  }
+  let sinks = read_config_from_dir(dir).map(|c| c.event_sinks).unwrap_or_default();
+  let mut body = None;
+  for (i, sink) in sinks.iter().enumerate().filter(|(_, s)| s.accepts(event)) {
+    let body = body.get_or_insert_with(|| json!({ "event": event, "at": now_secs(), "payload": payload }));
+    match sink {
+      EventSink::File { path, .. } => {
+        if let Err(e) = append(&dir.join(path), body) {
+          failures.push(format!("[{}] (file) {}: {}", i, event, e));
+        }
+      }
+      EventSink::Webhook { url, secret, timeout_secs, .. } => {
+        let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+        let delivery = Delivery { url: url.clone(), secret: secret.clone(), timeout, body: body.clone() };
+        if let Err(e) = enqueue(dir, state, delivery) {
+          failures.push(e);
+        }
+      }
+    }
+  }
+  failures
+}
+
+fn append(path: &Path, body: &Value) -> std::io::Result<()> {
+  let mut line = body.to_string();
+  line.push('\n');
+  OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())
+}
+
+/// Queues `delivery`, starting the worker when there is none or the runtime
+/// it ran on has gone. Fails only without a runtime to start it on.
+fn enqueue(dir: &Path, state: &mut SinkState, delivery: Delivery) -> Result<(), String> {
+  let delivery = match &state.queue {
+    Some(queue) => match queue.send(delivery) {
+      Ok(()) => return Ok(()),
+      Err(mpsc::error::SendError(d)) => d,
+    },
+    None => delivery,
+  };
+  let Ok(handle) = tokio::runtime::Handle::try_current() else {
+    return Err(format!("webhook {} pominięty — brak środowiska asynchronicznego", delivery.url));
+  };
+  let (tx, mut rx) = mpsc::unbounded_channel();
+  let _ = tx.send(delivery);
+  state.queue = Some(tx);
+  let dir = dir.to_path_buf();
+  handle.spawn(async move {
+    let client = reqwest::Client::new();
+    while let Some(d) = rx.recv().await {
+      if let Err(e) = post(&client, &d).await {
+        runtime::for_dir(&dir).warn(SOURCE, format!("webhook {} ({}): {}", d.url, d.body["event"].as_str().unwrap_or_default(), e));
+      }
+    }
+  });
+  Ok(())
+}
+
+async fn post(client: &reqwest::Client, d: &Delivery) -> Result<(), Error> {
+  let mut attempt = 0;
+  loop {
+    attempt += 1;
+    let mut req = client.post(&d.url).json(&d.body).timeout(d.timeout);
+    if let Some(secret) = &d.secret {
+      req = req.header(SECRET_HEADER, secret);
+    }
+    let (err, retryable) = match req.send().await {
+      Ok(resp) if resp.status().is_success() => return Ok(()),
+      Ok(resp) => {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        let err = Error::Api { status: status.as_u16(), reason: "webhookFailed".into(), message: text.chars().take(200).collect() };
+        (err, status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+      }
+      Err(e) => (crate::timeouts::transport_error(e, "event webhook", d.timeout), true),
+    };
+    if !retryable || attempt >= WEBHOOK_ATTEMPTS {
+      return Err(err);
+    }
+    tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1)).await;
+  }
+}
@@ -0,0 +1,207 @@
+//! A video's metadata as it was before the app changed or deleted it, in
+//! `snapshots/<video_id>/<timestamp>.json`, so an edit can be undone and a
+//! deletion at least shows what was lost. The last [`KEEP_PER_VIDEO`] are
+//! kept per video. They hold titles and descriptions of unlisted and
+//! private videos, so exports leave them out unless asked for.
+
+use crate::{api, error::Error, now_secs, storage, videos};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fs, path::Path};
+
+pub(crate) const SNAPSHOT_DIR: &str = "snapshots";
+pub const KEEP_PER_VIDEO: usize = 5;
+/// Everything `restore` can write back.
+const PARTS: &str = "snippet,status,localizations,recordingDetails";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SnapshotReason {
+  Update,
+  Delete,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snapshot {
+  pub video_id: String,
+  /// Unix milliseconds; also the file name.
+  pub timestamp: u64,
+  pub reason: SnapshotReason,
+  /// The `videos.list` resource with [`PARTS`].
+  pub resource: Value,
+}
+
+/// One entry of [`list`], without the resource.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SnapshotInfo {
+  pub timestamp: u64,
+  pub reason: SnapshotReason,
+  pub title: Option<String>,
+  pub privacy: Option<String>,
+}
+
+fn now_millis() -> u64 {
+  std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(now_secs() * 1000, |d| d.as_millis() as u64)
+}
+
+/// Video ids are `[A-Za-z0-9_-]`; anything else used as a directory name
+/// would escape it.
+pub(crate) fn check_video_id(video_id: &str) -> Result<(), Error> {
+  if video_id.is_empty() || !video_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+    return Err(Error::Validation(format!("Nieprawidłowy identyfikator wideo {}", video_id)));
+  }
+  Ok(())
+}
+
+fn video_dir(dir: &Path, video_id: &str) -> Result<std::path::PathBuf, Error> {
+  check_video_id(video_id)?;
+  Ok(dir.join(SNAPSHOT_DIR).join(video_id))
+}
+
+/// Timestamps stored for `video_id`, oldest first.
+fn timestamps(dir: &Path, video_id: &str) -> Result<Vec<u64>, Error> {
+  let Ok(entries) = fs::read_dir(video_dir(dir, video_id)?) else {
+    return Ok(Vec::new());
+  };
+  let mut found: Vec<u64> = entries
+    .filter_map(|e| e.ok())
+    .filter_map(|e| e.file_name().to_str()?.strip_suffix(".json")?.parse().ok())
+    .collect();
+  found.sort_unstable();
+  Ok(found)
+}
+
+/// Fetches and stores `video_id` as it is now, before a `reason` change;
+/// the change shouldn't go ahead when this fails. Skipped in dry-run mode,
+/// where nothing is changed; a video the API doesn't return has nothing to
+/// keep.
+pub(crate) async fn take(dir: &Path, video_id: &str, reason: SnapshotReason) -> Result<(), Error> {
+  if api::dry_run_enabled(dir) {
+    return Ok(());
+  }
+  store(dir, video_id, reason).await.map_err(|e| e.context(&format!("Nie zapisano kopii metadanych {} przed zmianą", video_id)))
+}
+
+async fn store(dir: &Path, video_id: &str, reason: SnapshotReason) -> Result<(), Error> {
+  let path = video_dir(dir, video_id)?;
+  let Some(resource) = videos::fetch(dir, &[video_id.to_string()], PARTS).await?.remove(video_id) else {
+    return Ok(());
+  };
+  let mut timestamp = now_millis();
+  if let Some(&last) = timestamps(dir, video_id)?.last() {
+    timestamp = timestamp.max(last + 1);
+  }
+  let snapshot = Snapshot { video_id: video_id.to_string(), timestamp, reason, resource };
+  let s = serde_json::to_string_pretty(&snapshot).map_err(|e| Error::Storage(e.to_string()))?;
+  fs::create_dir_all(&path).map_err(|e| Error::Storage(format!("Nie można utworzyć {}: {}", path.display(), e)))?;
+  storage::write_atomic(&path.join(format!("{}.json", timestamp)), s).map_err(|e| Error::Storage(e.to_string()))?;
+  let stored = timestamps(dir, video_id)?;
+  for old in &stored[..stored.len().saturating_sub(KEEP_PER_VIDEO)] {
+    let _ = fs::remove_file(path.join(format!("{}.json", old)));
+  }
+  Ok(())
+}
+
+pub fn read(dir: &Path, video_id: &str, timestamp: u64) -> Result<Snapshot, Error> {
+  let path = video_dir(dir, video_id)?.join(format!("{}.json", timestamp));
+  let s = fs::read_to_string(&path).map_err(|_| Error::Validation(format!("Brak kopii {} z {}", video_id, timestamp)))?;
+  serde_json::from_str(&s).map_err(|e| Error::Storage(format!("Uszkodzona kopia {}: {}", path.display(), e)))
+}
+
+/// Stored snapshots of `video_id`, newest first.
+pub fn list(dir: &Path, video_id: &str) -> Result<Vec<SnapshotInfo>, Error> {
+  let mut infos = Vec::new();
+  for timestamp in timestamps(dir, video_id)?.into_iter().rev() {
+    let Ok(s) = read(dir, video_id, timestamp) else { continue };
+    let text = |v: &Value| v.as_str().map(str::to_string);
+    infos.push(SnapshotInfo {
+      timestamp,
+      reason: s.reason,
+      title: text(&s.resource["snippet"]["title"]),
+      privacy: text(&s.resource["status"]["privacyStatus"]),
+    });
+  }
+  Ok(infos)
+}
+
+/// The `videos.update` body and part list that put `resource` back.
+fn restore_body(video_id: &str, resource: &Value) -> (String, Value) {
+  let mut parts = vec!["snippet", "status"];
+  let mut body = json!({
+    "id": video_id,
+    "snippet": videos::snippet_for_update(&resource["snippet"]),
+    "status": videos::status_for_update(&resource["status"]),
+  });
+  if let Some(l) = resource.get("localizations").filter(|l| l.is_object()) {
+    parts.push("localizations");
+    body["localizations"] = l.clone();
+  }
+  if let Some(date) = resource["recordingDetails"].get("recordingDate").filter(|d| d.is_string()) {
+    parts.push("recordingDetails");
+    body["recordingDetails"] = json!({ "recordingDate": date });
+  }
+  (parts.join(","), body)
+}
+
+/// Writes the metadata of snapshot `timestamp` back to the video; that is
+/// itself an update, so the state it replaces is kept first. A deleted
+/// video stays deleted and the API answers `videoNotFound`.
+pub async fn restore(dir: &Path, video_id: &str, timestamp: u64) -> Result<Value, Error> {
+  let snapshot = read(dir, video_id, timestamp)?;
+  let (part, body) = restore_body(video_id, &snapshot.resource);
+  videos::update(dir, &part, body).await
+}
+
+/// Every stored snapshot by video id, for exports that ask for them.
+pub fn all(dir: &Path) -> BTreeMap<String, Vec<Snapshot>> {
+  let mut by_video = BTreeMap::new();
+  let Ok(entries) = fs::read_dir(dir.join(SNAPSHOT_DIR)) else {
+    return by_video;
+  };
+  for video_id in entries.filter_map(|e| e.ok()).filter_map(|e| e.file_name().into_string().ok()) {
+    let snapshots: Vec<Snapshot> =
+      timestamps(dir, &video_id).unwrap_or_default().into_iter().filter_map(|t| read(dir, &video_id, t).ok()).collect();
+    if !snapshots.is_empty() {
+      by_video.insert(video_id, snapshots);
+    }
+  }
+  by_video
+}
+
+/// Snapshot files taken before `before_millis` or beyond the last
+/// [`KEEP_PER_VIDEO`] of their video, relative to the config dir and with
+/// their sizes; removed unless `dry_run`. Emptied video dirs go too.
+pub(crate) fn prune(dir: &Path, before_millis: u64, dry_run: bool) -> Result<(Vec<String>, u64), Error> {
+  let (mut items, mut bytes) = (Vec::new(), 0);
+  let Ok(entries) = fs::read_dir(dir.join(SNAPSHOT_DIR)) else {
+    return Ok((items, bytes));
+  };
+  let mut videos: Vec<String> = entries.filter_map(|e| e.ok()).filter_map(|e| e.file_name().into_string().ok()).collect();
+  videos.sort();
+  for video_id in videos {
+    let Ok(stored) = timestamps(dir, &video_id) else { continue };
+    let excess = stored.len().saturating_sub(KEEP_PER_VIDEO);
+    let path = dir.join(SNAPSHOT_DIR).join(&video_id);
+    for (i, t) in stored.iter().enumerate() {
+      if i >= excess && *t >= before_millis {
+        continue;
+      }
+      let file = path.join(format!("{}.json", t));
+      let size = fs::metadata(&file).map_or(0, |m| m.len());
+      if !dry_run {
+        match fs::remove_file(&file) {
+          Ok(()) => {}
+          Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+          Err(e) => return Err(Error::Storage(format!("Nie można usunąć {}: {}", file.display(), e))),
+        }
+      }
+      items.push(format!("{}/{}/{}.json", SNAPSHOT_DIR, video_id, t));
+      bytes += size;
+    }
+    if !dry_run {
+      // Only succeeds once the dir is empty.
+      let _ = fs::remove_dir(&path);
+    }
+  }
+  Ok((items, bytes))
+}
@@ -0,0 +1,340 @@
+//! `status.json` for orchestrators that poll instead of listening to events
+//! (`status_file` in the config): the auth state, a queue summary, the
+//! uploads running now, today's quota and the last error, rewritten as
+//! events happen but at most once per [`MIN_INTERVAL`]. Every write is a
+//! temp file and a rename, so a reader never sees half a file, and nothing
+//! in it is a credential. Turning the feature off removes the file.
+
+use crate::{
+  auth::{self, AuthStatus},
+  error::Error,
+  now_secs, profiles,
+  progress::UploadProgress,
+  queue::{self, EntryStatus},
+  quota::{self, QuotaStatus},
+  read_config_from_dir, redact, runtime, storage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+  time::{Duration, Instant},
+};
+
+/// Shortest time between two writes of the file.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_PATH: &str = "status.json";
+
+/// JSON Schema of status.json as written by this version.
+pub const STATUS_SCHEMA_JSON: &str = r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "status.json",
+  "type": "object",
+  "required": ["version", "written_at", "profile", "auth", "queue", "uploads", "quota"],
+  "properties": {
+    "version": { "const": 1 },
+    "written_at": { "type": "integer", "minimum": 0, "description": "Unix seconds of this snapshot." },
+    "profile": { "type": "string" },
+    "auth": {
+      "type": "object",
+      "description": "As returned by get_auth_status; never a token.",
+      "required": ["authenticated", "refreshable"],
+      "properties": {
+        "authenticated": { "type": "boolean" },
+        "refreshable": { "type": "boolean" },
+        "expires_at": { "type": ["integer", "null"], "description": "Unix seconds the access token expires at." },
+        "scopes": { "type": "array", "items": { "type": "string" } },
+        "requested_scopes": { "type": "array", "items": { "type": "string" } },
+        "missing_scopes": { "type": "array", "items": { "type": "string" } },
+        "needs_reconsent": { "type": "boolean" },
+        "unused_scopes": { "type": "array", "items": { "type": "string" } },
+        "issued_at": { "type": ["integer", "null"], "description": "Unix seconds of the login behind the refresh token." },
+        "read_only_mode": { "type": "boolean" },
+        "health": {
+          "type": ["object", "null"],
+          "description": "The last background check of the login.",
+          "properties": {
+            "state": { "enum": ["healthy", "degraded", "dead"] },
+            "checked_at": { "type": "integer", "minimum": 0 },
+            "next_check_at": { "type": ["integer", "null"] },
+            "code": { "type": ["string", "null"] },
+            "error": { "type": ["string", "null"] },
+            "tooltip": { "type": "string" }
+          }
+        }
+      }
+    },
+    "queue": {
+      "type": "object",
+      "description": "Queue entries by status.",
+      "properties": {
+        "pending": { "type": "integer", "minimum": 0 },
+        "uploading": { "type": "integer", "minimum": 0 },
+        "done": { "type": "integer", "minimum": 0 },
+        "failed": { "type": "integer", "minimum": 0 },
+        "blocked": { "type": "integer", "minimum": 0 },
+        "gave_up": { "type": "integer", "minimum": 0 }
+      }
+    },
+    "uploads": {
+      "type": "array",
+      "description": "Uploads running now, by file path.",
+      "items": {
+        "type": "object",
+        "required": ["file_path", "bytes_sent", "total_bytes", "updated_at"],
+        "properties": {
+          "file_path": { "type": "string" },
+          "bytes_sent": { "type": "integer", "minimum": 0 },
+          "total_bytes": { "type": "integer", "minimum": 0 },
+          "bytes_per_sec": { "type": ["number", "null"], "description": "Smoothed speed, once known." },
+          "eta_secs": { "type": ["integer", "null"] },
+          "updated_at": { "type": "integer", "minimum": 0 }
+        }
+      }
+    },
+    "quota": {
+      "type": "object",
+      "required": ["day", "used", "limit", "remaining"],
+      "properties": {
+        "day": { "type": "string", "description": "Quota day, YYYY-MM-DD in Pacific time." },
+        "used": { "type": "integer", "minimum": 0 },
+        "limit": { "type": "integer", "minimum": 0 },
+        "remaining": { "type": "integer", "minimum": 0 }
+      }
+    },
+    "last_error": {
+      "type": ["object", "null"],
+      "required": ["source", "message", "at"],
+      "properties": {
+        "source": { "type": "string", "description": "Event it was reported with, or upload for a direct upload." },
+        "code": { "type": ["string", "null"], "description": "Error code as in error_codes, when known." },
+        "message": { "type": "string" },
+        "at": { "type": "integer", "minimum": 0 }
+      }
+    }
+  }
+}
+"#;
+
+/// `status_file` in the config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusFileConfig {
+  /// Relative to the config dir unless absolute; `status.json` when absent.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub path: Option<String>,
+  /// Keeps the settings but writes nothing (and removes the file).
+  #[serde(default)]
+  pub disabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueSummary {
+  pub pending: usize,
+  pub uploading: usize,
+  pub done: usize,
+  pub failed: usize,
+  pub blocked: usize,
+  pub gave_up: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActiveUpload {
+  pub file_path: String,
+  pub bytes_sent: u64,
+  pub total_bytes: u64,
+  pub bytes_per_sec: Option<f64>,
+  pub eta_secs: Option<u64>,
+  pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LastError {
+  pub source: String,
+  pub code: Option<String>,
+  pub message: String,
+  pub at: u64,
+}
+
+/// The contents of status.json; see [`STATUS_SCHEMA_JSON`].
+#[derive(Serialize, Debug, Clone)]
+pub struct StatusSnapshot {
+  pub version: u32,
+  pub written_at: u64,
+  pub profile: String,
+  pub auth: AuthStatus,
+  pub queue: QueueSummary,
+  pub uploads: Vec<ActiveUpload>,
+  pub quota: QuotaStatus,
+  pub last_error: Option<LastError>,
+}
+
+/// What the next snapshot is built from, besides the files on disk.
+#[derive(Default)]
+pub(crate) struct StatusState {
+  uploads: BTreeMap<String, ActiveUpload>,
+  last_error: Option<LastError>,
+  last_write: Option<Instant>,
+  flush_scheduled: bool,
+  /// Where the file was last written, removed when the feature goes off or
+  /// the path changes.
+  written: Option<PathBuf>,
+}
+
+/// Where status.json goes, or nothing when the feature is off.
+fn target(dir: &Path) -> Option<PathBuf> {
+  let cfg = read_config_from_dir(dir)?.status_file.filter(|s| !s.disabled)?;
+  Some(dir.join(cfg.path.as_deref().unwrap_or(DEFAULT_PATH)))
+}
+
+fn queue_summary(dir: &Path) -> QueueSummary {
+  let mut summary = QueueSummary::default();
+  for status in queue::statuses(dir) {
+    let n = match status {
+      EntryStatus::Pending => &mut summary.pending,
+      EntryStatus::Uploading => &mut summary.uploading,
+      EntryStatus::Done => &mut summary.done,
+      EntryStatus::Failed => &mut summary.failed,
+      EntryStatus::Blocked => &mut summary.blocked,
+      EntryStatus::GaveUp => &mut summary.gave_up,
+    };
+    *n += 1;
+  }
+  summary
+}
+
+fn snapshot(dir: &Path, state: &StatusState) -> StatusSnapshot {
+  StatusSnapshot {
+    version: 1,
+    written_at: now_secs(),
+    profile: profiles::name_of(dir),
+    auth: auth::status(dir),
+    queue: queue_summary(dir),
+    uploads: state.uploads.values().cloned().collect(),
+    quota: quota::status(dir),
+    last_error: state.last_error.clone(),
+  }
+}
+
+fn write(dir: &Path, path: &Path, state: &mut StatusState) -> Result<(), Error> {
+  let mut s = serde_json::to_string_pretty(&snapshot(dir, state)).map_err(|e| Error::Storage(e.to_string()))?;
+  s.push('\n');
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| Error::Storage(e.to_string()))?;
+  }
+  storage::write_atomic(path, s).map_err(|e| Error::Storage(format!("{}: {}", path.display(), e)))?;
+  state.last_write = Some(Instant::now());
+  state.written = Some(path.to_path_buf());
+  Ok(())
+}
+
+/// Writes the file now if the last write was long enough ago, else once
+/// its slot comes. Off, removes what was written.
+fn refresh(dir: &Path, state: &mut StatusState) {
+  let target = target(dir);
+  if let Some(old) = state.written.take_if(|w| Some(&*w) != target.as_ref()) {
+    let _ = fs::remove_file(old);
+  }
+  let Some(path) = target else {
+    return;
+  };
+  let due = state.last_write.map(|at| at + MIN_INTERVAL).filter(|due| *due > Instant::now());
+  match due {
+    Some(due) => schedule(dir, state, due),
+    None => {
+      if let Err(e) = write(dir, &path, state) {
+        runtime::for_dir(dir).warn("status_file", e);
+      }
+    }
+  }
+}
+
+fn schedule(dir: &Path, state: &mut StatusState, due: Instant) {
+  // Without a runtime the next event writes instead.
+  let Ok(handle) = tokio::runtime::Handle::try_current() else {
+    return;
+  };
+  if std::mem::replace(&mut state.flush_scheduled, true) {
+    return;
+  }
+  let dir = dir.to_path_buf();
+  handle.spawn(async move {
+    tokio::time::sleep_until(due.into()).await;
+    let rt = runtime::for_dir(&dir);
+    let mut state = rt.status_file();
+    state.flush_scheduled = false;
+    refresh(&dir, &mut state);
+  });
+}
+
+fn last_error(event: &str, payload: &Value) -> Option<LastError> {
+  let text = |key: &str| payload[key].as_str().map(str::to_string);
+  let (code, message) = match event {
+    "queue-entry-failed" | "queue-entry-gave-up" => (text("code"), text("error")?),
+    "queue-entry-blocked" => (Some("PREFLIGHT_FAILED".into()), text("message")?),
+    "reauth-required" => (Some("REAUTH_REQUIRED".into()), payload.as_str()?.to_string()),
+    "background-task-crashed" => (None, format!("{}: {}", text("task")?, text("message")?)),
+    _ => return None,
+  };
+  Some(LastError { source: event.to_string(), code, message: redact::text(&message), at: now_secs() })
+}
+
+/// Every emitted event: errors are remembered, and anything may have changed
+/// what the file shows.
+pub(crate) fn observe(dir: &Path, event: &str, payload: &Value) {
+  let rt = runtime::for_dir(dir);
+  let mut state = rt.status_file();
+  if let Some(error) = last_error(event, payload) {
+    state.last_error = Some(error);
+  }
+  refresh(dir, &mut state);
+}
+
+/// A failed upload that no event reports, like one started outside the queue.
+pub(crate) fn upload_failed(dir: &Path, e: &Error) {
+  let rt = runtime::for_dir(dir);
+  let mut state = rt.status_file();
+  state.last_error =
+    Some(LastError { source: "upload".into(), code: Some(e.code().to_string()), message: redact::text(&e.to_string()), at: now_secs() });
+  refresh(dir, &mut state);
+}
+
+/// Listed under `uploads` while held.
+pub(crate) struct ActiveGuard {
+  dir: PathBuf,
+  key: String,
+}
+
+impl Drop for ActiveGuard {
+  fn drop(&mut self) {
+    let rt = runtime::for_dir(&self.dir);
+    let mut state = rt.status_file();
+    state.uploads.remove(&self.key);
+    refresh(&self.dir, &mut state);
+  }
+}
+
+pub(crate) fn track_upload(dir: &Path, path: &Path, total_bytes: u64) -> ActiveGuard {
+  let key = path.to_string_lossy().into_owned();
+  let rt = runtime::for_dir(dir);
+  let mut state = rt.status_file();
+  let upload = ActiveUpload { file_path: key.clone(), bytes_sent: 0, total_bytes, bytes_per_sec: None, eta_secs: None, updated_at: now_secs() };
+  state.uploads.insert(key.clone(), upload);
+  refresh(dir, &mut state);
+  ActiveGuard { dir: dir.to_path_buf(), key }
+}
+
+pub(crate) fn upload_progress(dir: &Path, key: &str, p: &UploadProgress) {
+  let rt = runtime::for_dir(dir);
+  let mut state = rt.status_file();
+  let Some(upload) = state.uploads.get_mut(key) else {
+    return;
+  };
+  upload.bytes_sent = p.bytes_sent;
+  upload.total_bytes = p.total_bytes;
+  upload.bytes_per_sec = p.smoothed_bytes_per_sec;
+  upload.eta_secs = p.eta_secs;
+  upload.updated_at = now_secs();
+  refresh(dir, &mut state);
+}
@@ -0,0 +1,244 @@
+use crate::error::Error;
+use serde::Serialize;
+use std::{
+  cell::RefCell,
+  fs::{self, File, OpenOptions, TryLockError},
+  io::{self, Write},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    OnceLock,
+  },
+  time::{Duration, Instant},
+};
+
+const PORTABLE_FLAG: &str = "portable.flag";
+pub const CONFIG_DIR_ENV: &str = "YTLITE_OAUTH_CONFIG_DIR";
+const PORTABLE_DIR: &str = "ytlite-data";
+pub(crate) const LOCK_FILE: &str = ".lock";
+/// How long a writer waits for another process before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+const LOCK_RETRY: Duration = Duration::from_millis(5);
+
+thread_local! {
+  /// Dirs this thread holds the lock of, so nested updates don't wait on themselves.
+  static HELD: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageMode {
+  /// The platform app config dir (AppData, ~/.config, ~/Library).
+  Standard,
+  /// `<exe_dir>/ytlite-data`, travelling with the executable.
+  Portable,
+  /// Set with `--config-dir` or `YTLITE_OAUTH_CONFIG_DIR`.
+  Override,
+}
+
+/// Managed state holding the canonical `--config-dir` path.
+pub struct ConfigDirOverride(pub PathBuf);
+
+/// `--config-dir <path>` / `--config-dir=<path>` from `args` (program name
+/// excluded), else `env`. Relative paths are taken against `cwd`; the dir is
+/// created so it can be canonicalized.
+pub fn parse_override(args: &[String], env: Option<String>, cwd: &Path) -> Result<Option<PathBuf>, Error> {
+  let mut from_args = None;
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    if arg == "--config-dir" {
+      from_args = Some(it.next().cloned().ok_or_else(|| Error::Config("--config-dir wymaga ścieżki".into()))?);
+    } else if let Some(v) = arg.strip_prefix("--config-dir=") {
+      from_args = Some(v.to_string());
+    }
+  }
+  let Some(raw) = from_args.or(env).filter(|p| !p.is_empty()) else {
+    return Ok(None);
+  };
+  let dir = cwd.join(raw);
+  fs::create_dir_all(&dir).map_err(|e| Error::Storage(format!("Nie można utworzyć {}: {}", dir.display(), e)))?;
+  let dir = dir.canonicalize().map_err(|e| Error::Storage(format!("Nieprawidłowy katalog {}: {}", dir.display(), e)))?;
+  Ok(Some(dir))
+}
+
+/// [`parse_override`] for this process; called once at startup.
+pub fn override_from_env() -> Result<Option<PathBuf>, Error> {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let cwd = std::env::current_dir().map_err(|e| Error::Storage(e.to_string()))?;
+  parse_override(&args, std::env::var(CONFIG_DIR_ENV).ok(), &cwd)
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StorageLocation {
+  pub mode: StorageMode,
+  pub path: PathBuf,
+  /// Portable mode was requested but its dir isn't writable, so the
+  /// standard location is in use.
+  pub portable_unavailable: bool,
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+  static SEQ: AtomicU64 = AtomicU64::new(0);
+  let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+  path.with_file_name(format!(".{}.{}-{}.tmp", name, std::process::id(), SEQ.fetch_add(1, Ordering::Relaxed)))
+}
+
+/// The temp file that replaces `path`. It gets the permissions of the file
+/// it replaces, which the rename would otherwise drop (a 0600 `.env` turning
+/// world-readable); a new secret file is 0600 rather than the umask default.
+fn create_temp(path: &Path, tmp: &Path, secret: bool) -> io::Result<File> {
+  #[cfg(unix)]
+  use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+  let mut options = OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  if secret {
+    options.mode(0o600);
+  }
+  let file = options.open(tmp)?;
+  #[cfg(unix)]
+  match fs::metadata(path) {
+    Ok(existing) => file.set_permissions(existing.permissions())?,
+    Err(_) if secret => file.set_permissions(fs::Permissions::from_mode(0o600))?,
+    Err(_) => {}
+  }
+  // Elsewhere permissions are just the read-only flag, and replacing a
+  // read-only file fails as it did with `fs::write`.
+  #[cfg(not(unix))]
+  let _ = (path, secret);
+  Ok(file)
+}
+
+fn replace(path: &Path, contents: &[u8], secret: bool, sync: bool) -> io::Result<()> {
+  let tmp = temp_path(path);
+  let written = create_temp(path, &tmp, secret).and_then(|mut f| {
+    f.write_all(contents)?;
+    if sync {
+      f.sync_all()?;
+    }
+    Ok(())
+  });
+  written.and_then(|_| fs::rename(&tmp, path)).inspect_err(|_| {
+    let _ = fs::remove_file(&tmp);
+  })
+}
+
+/// Writes via a temp file and rename, so a crash or forced exit leaves either
+/// the old or the new contents, never a torn file. The file keeps its
+/// permissions.
+pub(crate) fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+  replace(path, contents.as_ref(), false, false)
+}
+
+/// [`write_atomic`] for files holding tokens or the client secret: created
+/// readable by the owner only.
+pub(crate) fn write_secret(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+  replace(path, contents.as_ref(), true, false)
+}
+
+/// [`write_atomic`] that is on disk when it returns: the file is synced
+/// before the rename and its dir after, so what comes next can't reach the
+/// disk first. For pointers that other changes are ordered after.
+pub(crate) fn write_durable(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+  replace(path, contents.as_ref(), false, true)?;
+  // Syncing the dir entry needs opening the dir, which only Unix allows.
+  #[cfg(unix)]
+  File::open(path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")))?.sync_all()?;
+  Ok(())
+}
+
+/// The lock of a config dir; released on drop.
+pub(crate) struct DirLock {
+  held: Option<(PathBuf, File)>,
+}
+
+impl Drop for DirLock {
+  fn drop(&mut self) {
+    if let Some((dir, _file)) = self.held.take() {
+      HELD.with(|h| h.borrow_mut().retain(|d| *d != dir));
+    }
+  }
+}
+
+/// Inside a multi-thread tokio runtime, where `block_in_place` works.
+fn on_worker_runtime() -> bool {
+  tokio::runtime::Handle::try_current().is_ok_and(|h| h.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread)
+}
+
+/// Advisory lock on `.lock` in `dir`, taken around each read-modify-write
+/// of its files so the GUI, the CLI and background tasks of either take
+/// turns. Held for the file work only, never across a request. A thread
+/// that already holds it gets it again; another process holding it past
+/// [`LOCK_TIMEOUT`] gives `StorageBusy`. Waiting for it doesn't hold up
+/// the other tasks of a tokio worker.
+pub(crate) fn lock(dir: &Path) -> Result<DirLock, Error> {
+  if HELD.with(|h| h.borrow().iter().any(|d| d == dir)) {
+    return Ok(DirLock { held: None });
+  }
+  let path = dir.join(LOCK_FILE);
+  fs::create_dir_all(dir).map_err(|e| Error::Storage(format!("Nie można utworzyć {}: {}", dir.display(), e)))?;
+  let file = OpenOptions::new()
+    .create(true)
+    .truncate(false)
+    .write(true)
+    .open(&path)
+    .map_err(|e| Error::Storage(format!("{}: {}", path.display(), e)))?;
+  let started = Instant::now();
+  let wait = || loop {
+    match file.try_lock() {
+      Ok(()) => return Ok(()),
+      Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => std::thread::sleep(LOCK_RETRY),
+      Err(TryLockError::WouldBlock) => {
+        return Err(Error::StorageBusy { path: path.display().to_string(), waited_ms: started.elapsed().as_millis() as u64 })
+      }
+      Err(TryLockError::Error(e)) => return Err(Error::Storage(format!("{}: {}", path.display(), e))),
+    }
+  };
+  match file.try_lock() {
+    Ok(()) => {}
+    // Most callers are async. On a tokio worker the wait hands the worker's
+    // other tasks to another thread first, so they keep running meanwhile;
+    // the thread stays the same, which `HELD` relies on.
+    Err(TryLockError::WouldBlock) if on_worker_runtime() => tokio::task::block_in_place(wait)?,
+    Err(_) => wait()?,
+  }
+  HELD.with(|h| h.borrow_mut().push(dir.to_path_buf()));
+  Ok(DirLock { held: Some((dir.to_path_buf(), file)) })
+}
+
+fn writable(dir: &Path) -> bool {
+  let probe = dir.join(".write-test");
+  fs::create_dir_all(dir).is_ok() && fs::write(&probe, b"").is_ok() && fs::remove_file(&probe).is_ok()
+}
+
+/// Picks the config dir. Portable mode is requested by `portable.flag` next
+/// to the executable or by `portable_arg` (`--portable`).
+pub fn resolve(exe_dir: Option<&Path>, portable_arg: bool, standard: Option<PathBuf>) -> Result<StorageLocation, Error> {
+  let requested = exe_dir.filter(|d| portable_arg || d.join(PORTABLE_FLAG).exists());
+  if let Some(exe_dir) = requested {
+    let dir = exe_dir.join(PORTABLE_DIR);
+    if writable(&dir) {
+      return Ok(StorageLocation { mode: StorageMode::Portable, path: dir, portable_unavailable: false });
+    }
+  }
+  let path = standard.ok_or_else(|| Error::Config("Nie można ustalić katalogu konfiguracyjnego aplikacji".into()))?;
+  Ok(StorageLocation { mode: StorageMode::Standard, path, portable_unavailable: requested.is_some() })
+}
+
+/// Location for this process: the override when given, otherwise resolved
+/// once so a flag file appearing or a dir turning read-only mid-session
+/// can't split state across two places.
+pub(crate) fn current(overridden: Option<&Path>, standard: impl FnOnce() -> Option<PathBuf>) -> Result<StorageLocation, Error> {
+  if let Some(path) = overridden {
+    return Ok(StorageLocation { mode: StorageMode::Override, path: path.to_path_buf(), portable_unavailable: false });
+  }
+  static CURRENT: OnceLock<StorageLocation> = OnceLock::new();
+  if let Some(loc) = CURRENT.get() {
+    return Ok(loc.clone());
+  }
+  let exe = std::env::current_exe().ok();
+  let exe_dir = exe.as_deref().and_then(Path::parent);
+  let portable_arg = std::env::args().skip(1).any(|a| a == "--portable");
+  let loc = resolve(exe_dir, portable_arg, standard())?;
+  Ok(CURRENT.get_or_init(|| loc).clone())
+}
@@ -0,0 +1,92 @@
+//! Custom video thumbnails (`thumbnails.set`). Right after an upload YouTube
+//! often refuses the thumbnail because it doesn't have the video yet; that
+//! answer looks permanent but clears within minutes, so it alone is retried.
+
+use crate::{
+  api::{self, ApiCall},
+  channel::{probe_image, Thumbnail},
+  error::Error,
+  events, read_config_from_dir, scopes,
+};
+use imagesize::ImageType;
+use serde::Serialize;
+use std::{
+  collections::BTreeMap,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+pub(crate) const THUMBNAIL_SCOPES: &[&str] = &[scopes::YOUTUBE_UPLOAD];
+const THUMBNAIL_MAX_BYTES: u64 = 2 * 1024 * 1024;
+/// How long a "not processed yet" answer is retried (`thumbnail_retry_window_secs`).
+const DEFAULT_RETRY_WINDOW_SECS: u64 = 10 * 60;
+
+/// Payload of `thumbnail-retry`, sent before each wait.
+#[derive(Serialize, Debug, Clone)]
+pub struct ThumbnailRetry {
+  pub video_id: String,
+  /// Attempts made so far.
+  pub attempt: u32,
+  pub retry_in_ms: u64,
+  pub reason: String,
+}
+
+/// Whether `e` is YouTube not having the video ready for a thumbnail yet,
+/// as opposed to a refusal that stays: a channel without custom thumbnails
+/// (403), a bad image (400 `invalidImage`) and the rest.
+pub fn is_processing_race(e: &Error) -> bool {
+  match e {
+    Error::Api { status: 404, reason, .. } => reason == "videoNotFound",
+    Error::Api { status: 400 | 409, reason, message } => {
+      reason == "failedPrecondition" || message.contains("processed") || message.contains("processing")
+    }
+    _ => false,
+  }
+}
+
+/// Waits before each retry: a fortieth of the window, doubling up to a fifth
+/// of it, so the default window tries at 15 s, 30 s, 1 min, 2 min, then every 2 min.
+fn backoff(window: Duration, attempt: u32) -> Duration {
+  let first = window / 40;
+  first.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))).min(window / 5)
+}
+
+async fn set_once(dir: &Path, video_id: &str, content_type: &str, data: &[u8]) -> Result<BTreeMap<String, Thumbnail>, Error> {
+  let call = ApiCall::post("/upload/youtube/v3/thumbnails/set")
+    .query("videoId", video_id)
+    .query("uploadType", "media")
+    .bytes(content_type, data.to_vec())
+    .scopes(THUMBNAIL_SCOPES);
+  let resp = api::execute(dir, call).await?;
+  serde_json::from_value(resp["items"][0].clone())
+    .map_err(|e| Error::Api { status: 200, reason: "invalidJson".into(), message: format!("thumbnails.set: {}", e) })
+}
+
+/// Sets the JPEG or PNG at `image_path` (at most 2 MB) as the thumbnail of
+/// `video_id` and returns the sizes YouTube made of it. A too-early attempt
+/// is retried with growing pauses, emitting `thumbnail-retry`, until
+/// `thumbnail_retry_window_secs` (10 min) runs out; then, like any other
+/// failure, the API's error is returned as is.
+pub async fn set_thumbnail(dir: &Path, video_id: &str, image_path: &Path) -> Result<BTreeMap<String, Thumbnail>, Error> {
+  api::ensure_writable(dir)?;
+  let img = probe_image(image_path, THUMBNAIL_MAX_BYTES, &[ImageType::Jpeg, ImageType::Png])?;
+  let cfg = read_config_from_dir(dir).unwrap_or_default();
+  let window = Duration::from_secs(cfg.thumbnail_retry_window_secs.unwrap_or(DEFAULT_RETRY_WINDOW_SECS));
+  let started = Instant::now();
+  let mut attempt = 0;
+  loop {
+    attempt += 1;
+    let err = match set_once(dir, video_id, img.content_type, &img.data).await {
+      Ok(sizes) => return Ok(sizes),
+      Err(e) if is_processing_race(&e) => e,
+      Err(e) => return Err(e),
+    };
+    let wait = backoff(window, attempt);
+    if started.elapsed() + wait > window {
+      return Err(err);
+    }
+    let retry = ThumbnailRetry { video_id: video_id.to_string(), attempt, retry_in_ms: wait.as_millis() as u64, reason: err.to_string() };
+    events::transition(dir, video_id, "thumbnail-retry", retry);
+    tokio::time::sleep(wait).await;
+  }
+}
@@ -0,0 +1,44 @@
+use crate::{error::Error, read_config_from_dir};
+use std::{future::Future, path::Path, time::Duration};
+
+const DEFAULT_AUTH_SECS: u64 = 30;
+const DEFAULT_API_SECS: u64 = 60;
+const DEFAULT_UPLOAD_CHUNK_SECS: u64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationClass {
+  /// Token endpoint calls.
+  Auth,
+  /// Regular Data API calls.
+  Api,
+  /// One request carrying media bytes.
+  UploadChunk,
+}
+
+/// Per-request timeout for `class`. Read from the config on every call, so
+/// edits apply to the next request without a restart.
+pub fn for_class(dir: &Path, class: OperationClass) -> Duration {
+  let cfg = read_config_from_dir(dir).unwrap_or_default();
+  let secs = match class {
+    OperationClass::Auth => cfg.auth_timeout_secs.unwrap_or(DEFAULT_AUTH_SECS),
+    OperationClass::Api => cfg.api_timeout_secs.unwrap_or(DEFAULT_API_SECS),
+    OperationClass::UploadChunk => cfg.upload_chunk_timeout_secs.unwrap_or(DEFAULT_UPLOAD_CHUNK_SECS),
+  };
+  Duration::from_secs(secs.max(1))
+}
+
+/// Maps a reqwest failure, telling timeouts apart from other transport errors.
+pub(crate) fn transport_error(e: reqwest::Error, operation: &str, after: Duration) -> Error {
+  if e.is_timeout() {
+    Error::TimedOut { operation: operation.to_string(), after_secs: after.as_secs() }
+  } else {
+    Error::Network(e.to_string())
+  }
+}
+
+/// Bounds a composite operation (several requests, polling) as a whole.
+pub async fn with_deadline<T>(operation: &str, deadline: Duration, fut: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+  tokio::time::timeout(deadline, fut)
+    .await
+    .unwrap_or_else(|_| Err(Error::TimedOut { operation: operation.to_string(), after_secs: deadline.as_secs() }))
+}
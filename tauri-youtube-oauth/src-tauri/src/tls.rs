@@ -0,0 +1,135 @@
+//! Opt-in TLS pinning for the requests that carry the client secret or a
+//! token (`pin_google_tls`). Pinned requests trust Google's root CAs from
+//! the bundled webpki-roots only, so a TLS-intercepting proxy whose CA is
+//! in the OS store can't read them. Also home of [`request`], which every
+//! request to Google is built with: shared clients, so connections are
+//! reused across calls, and the `custom_headers` and trace id on each.
+
+use crate::{
+  channel,
+  error::Error,
+  http_headers, read_config_from_dir, runtime,
+  timeouts::{self, OperationClass},
+  token_endpoint,
+};
+use reqwest::{Method, RequestBuilder};
+use std::{path::Path, sync::OnceLock, time::Duration};
+
+/// Hosts pinned requests go to in production.
+const GOOGLE_AUTH_HOSTS: &[&str] = &["accounts.google.com", "oauth2.googleapis.com"];
+
+/// Organizations of the roots Google's certificates chain to (pki.goog).
+const GOOGLE_ROOT_ORGS: &[&[u8]] = &[b"Google Trust Services", b"GlobalSign"];
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn enabled(dir: &Path) -> bool { read_config_from_dir(dir).is_some_and(|c| c.pin_google_tls) }
+
+fn origin(url: &str) -> Option<(String, String, Option<u16>)> {
+  let u = url::Url::parse(url).ok()?;
+  Some((u.scheme().to_string(), u.host_str()?.to_string(), u.port_or_known_default()))
+}
+
+/// Whether `url` is a Google login host or the (possibly overridden) token
+/// or tokeninfo endpoint.
+pub(crate) fn is_auth_host(url: &str) -> bool {
+  let Some(target) = origin(url) else {
+    return false;
+  };
+  GOOGLE_AUTH_HOSTS.contains(&target.1.as_str()) || [token_endpoint(), channel::tokeninfo_endpoint()].iter().any(|e| origin(e).as_ref() == Some(&target))
+}
+
+/// Whether requests to `url` are sent with [`pinned_builder`].
+pub(crate) fn pins(dir: &Path, url: &str) -> bool { enabled(dir) && is_auth_host(url) }
+
+fn google_roots() -> rustls::RootCertStore {
+  let mut roots = rustls::RootCertStore::empty();
+  let google = webpki_roots::TLS_SERVER_ROOTS
+    .iter()
+    .filter(|ta| GOOGLE_ROOT_ORGS.iter().any(|org| ta.subject.windows(org.len()).any(|w| w == *org)));
+  roots.add_trust_anchors(
+    google.map(|ta| rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)),
+  );
+  roots
+}
+
+/// A client builder trusting Google's roots and nothing else: neither the
+/// OS store nor certificates added to it.
+pub fn pinned_builder() -> reqwest::ClientBuilder {
+  static CONFIG: OnceLock<rustls::ClientConfig> = OnceLock::new();
+  let tls = CONFIG.get_or_init(|| {
+    rustls::ClientConfig::builder().with_safe_defaults().with_root_certificates(google_roots()).with_no_client_auth()
+  });
+  reqwest::Client::builder().use_preconfigured_tls(tls.clone())
+}
+
+/// Config keys a shared client is built from; [`crate::config::reload`]
+/// drops the clients when one changes. Request timeouts are set per request
+/// and need no new client.
+pub(crate) const CLIENT_KEYS: &[&str] = &["pin_google_tls", "auth_timeout_secs"];
+
+/// What a shared client is built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct ClientKey {
+  pinned: bool,
+  follow_redirects: bool,
+  /// Connecting never takes longer than a whole token request may.
+  connect_timeout: Duration,
+}
+
+/// The client with these settings, built once per [`ClientKey`] and kept, so
+/// its connection pool (and TLS sessions) serve every later request, upload
+/// chunks included.
+fn shared(dir: &Path, pinned: bool, follow_redirects: bool) -> Result<reqwest::Client, Error> {
+  let key = ClientKey { pinned, follow_redirects, connect_timeout: timeouts::for_class(dir, OperationClass::Auth) };
+  let rt = runtime::for_dir(dir);
+  let mut clients = rt.http_clients();
+  if let Some(client) = clients.get(&key) {
+    return Ok(client.clone());
+  }
+  let mut builder = if pinned { pinned_builder() } else { reqwest::Client::builder() };
+  if !follow_redirects {
+    builder = builder.redirect(reqwest::redirect::Policy::none());
+  }
+  let client = builder.connect_timeout(key.connect_timeout).build().map_err(|e| Error::Network(e.to_string()))?;
+  clients.insert(key, client.clone());
+  Ok(client)
+}
+
+/// A request to Google on the shared client for `url` (pinned when [`pins`]
+/// says so), carrying the `custom_headers` and `trace_id` of
+/// [`http_headers::apply`].
+pub(crate) fn request(dir: &Path, method: Method, url: &str, trace_id: &str) -> Result<RequestBuilder, Error> {
+  Ok(http_headers::apply(dir, shared(dir, pins(dir, url), true)?.request(method, url), trace_id))
+}
+
+/// [`request`] that hands a redirect back instead of following it.
+pub(crate) fn request_unredirected(dir: &Path, method: Method, url: &str, trace_id: &str) -> Result<RequestBuilder, Error> {
+  Ok(http_headers::apply(dir, shared(dir, pins(dir, url), false)?.request(method, url), trace_id))
+}
+
+/// A bodyless HEAD to `url`, never pinned, for probes that only ask whether
+/// anything answers.
+pub(crate) fn probe(dir: &Path, url: &str) -> Result<RequestBuilder, Error> {
+  Ok(http_headers::apply(dir, shared(dir, false, true)?.head(url), &http_headers::trace_id()))
+}
+
+/// Drops the shared clients and their pooled connections; the next request
+/// builds new ones from the current config.
+pub(crate) fn rebuild_clients(dir: &Path) { runtime::for_dir(dir).http_clients().clear(); }
+
+/// Maps a failed pinned request. A connection error is probed once without
+/// pinning (a bodyless HEAD, so nothing secret is sent): if that gets an
+/// answer, the certificate came from somewhere other than Google's CAs.
+pub(crate) async fn transport_error(dir: &Path, url: &str, e: reqwest::Error, operation: &str, after: Duration) -> Error {
+  if !e.is_connect() || !pins(dir, url) {
+    return timeouts::transport_error(e, operation, after);
+  }
+  let Ok(head) = probe(dir, url) else {
+    return timeouts::transport_error(e, operation, after);
+  };
+  match head.timeout(PROBE_TIMEOUT).send().await {
+    Ok(_) => Error::TlsInterceptionSuspected { host: origin(url).map(|o| o.1).unwrap_or_default() },
+    Err(_) => timeouts::transport_error(e, operation, after),
+  }
+}
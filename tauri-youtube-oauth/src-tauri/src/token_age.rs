@@ -0,0 +1,100 @@
+//! Refresh tokens of an OAuth app whose consent screen is still in "Testing"
+//! stop working 7 days after the login. The refreshes in between don't extend
+//! that, so the age counts from the first code exchange (`Tokens::issued_at`).
+
+use crate::{now_secs, read_config_from_dir, read_tokens_from_dir, runtime, Tokens};
+use serde::Serialize;
+use std::path::Path;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+pub const TESTING_LIFETIME_SECS: u64 = 7 * DAY_SECS;
+const WARN_AFTER_SECS: u64 = 6 * DAY_SECS;
+/// An `invalid_grant` this close to the 7-day mark is most likely the expiry.
+const HINT_WINDOW_SECS: u64 = 12 * 60 * 60;
+
+const TESTING_HINT: &str = "Aplikacja OAuth jest prawdopodobnie w trybie testowym (Testing) — Google unieważnia wtedy \
+  refresh token po 7 dniach. Zaloguj się ponownie albo opublikuj ekran zgody w Google Cloud Console \
+  i ustaw \"consent_screen_published\": true.";
+
+/// What is known about the OAuth app's publishing status
+/// (`consent_screen_published` in the config).
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsentStatus {
+  Unknown,
+  Testing,
+  Published,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TokenAge {
+  /// Unix seconds of the login that issued the refresh token; absent for
+  /// tokens saved by older versions.
+  pub issued_at: Option<u64>,
+  pub age_secs: Option<u64>,
+  pub consent_screen: ConsentStatus,
+  /// When the refresh token dies if the app is in Testing mode.
+  pub expires_if_testing_at: Option<u64>,
+  pub warning: Option<String>,
+}
+
+/// Payload of `token-aging-warning`.
+#[derive(Serialize, Debug, Clone)]
+pub struct TokenAgingWarning {
+  pub issued_at: u64,
+  pub age_secs: u64,
+  pub expires_if_testing_at: u64,
+  pub message: String,
+}
+
+fn consent_status(dir: &Path) -> ConsentStatus {
+  match read_config_from_dir(dir).and_then(|c| c.consent_screen_published) {
+    Some(true) => ConsentStatus::Published,
+    Some(false) => ConsentStatus::Testing,
+    None => ConsentStatus::Unknown,
+  }
+}
+
+fn of(dir: &Path, t: Option<&Tokens>, now: u64) -> TokenAge {
+  let consent_screen = consent_status(dir);
+  let issued_at = t.and_then(|t| t.issued_at);
+  let age_secs = issued_at.map(|at| now.saturating_sub(at));
+  let warning = match (consent_screen, age_secs) {
+    (ConsentStatus::Published, _) => None,
+    (_, Some(age)) if age >= WARN_AFTER_SECS => Some(format!(
+      "Token ma {} dni. Jeśli aplikacja OAuth jest w trybie testowym, przestanie działać po 7 dniach — \
+       zaloguj się ponownie albo opublikuj ekran zgody.",
+      age / DAY_SECS
+    )),
+    _ => None,
+  };
+  TokenAge { issued_at, age_secs, consent_screen, expires_if_testing_at: issued_at.map(|at| at + TESTING_LIFETIME_SECS), warning }
+}
+
+pub fn token_age(dir: &Path) -> TokenAge {
+  of(dir, read_tokens_from_dir(dir).as_ref(), now_secs())
+}
+
+/// Emits `token-aging-warning` once per login and process when the refresh
+/// token is six days old and the app may be in Testing mode.
+pub(crate) fn check(dir: &Path, t: &Tokens) {
+  let age = of(dir, Some(t), now_secs());
+  let (Some(message), Some(issued_at), Some(age_secs), Some(expires_if_testing_at)) =
+    (age.warning, age.issued_at, age.age_secs, age.expires_if_testing_at)
+  else {
+    return;
+  };
+  let rt = runtime::for_dir(dir);
+  if rt.aging_warned().replace(issued_at) == Some(issued_at) {
+    return;
+  }
+  rt.emit("token-aging-warning", TokenAgingWarning { issued_at, age_secs, expires_if_testing_at, message });
+}
+
+/// Hint for an `invalid_grant` on refresh: set when the token died close to
+/// its 7th day and the app isn't known to be published.
+pub(crate) fn invalid_grant_hint(dir: &Path, t: &Tokens) -> Option<String> {
+  let age = of(dir, Some(t), now_secs());
+  let near_expiry = age.age_secs.is_some_and(|a| a.abs_diff(TESTING_LIFETIME_SECS) <= HINT_WINDOW_SECS);
+  (near_expiry && age.consent_screen != ConsentStatus::Published).then(|| TESTING_HINT.to_string())
+}
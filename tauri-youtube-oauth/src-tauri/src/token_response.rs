@@ -0,0 +1,167 @@
+//! Strict reading of token endpoint replies, shared by the code exchange and
+//! the refresh. The status is checked first, an OAuth error object is read
+//! whatever the status (a 200 can carry one), and a body that isn't JSON —
+//! the HTML page of a proxy or a captive portal — is named as such instead
+//! of surfacing as a parse error.
+
+use crate::error::Error;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// Characters of a non-JSON body quoted in the message.
+const SNIPPET_CHARS: usize = 120;
+
+/// The token endpoint refused the request, or answered with neither an
+/// error nor a usable grant.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TokenEndpointError {
+  pub status: u16,
+  /// The OAuth `error` code; `None` when the reply had none.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error_description: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error_uri: Option<String>,
+  /// What was wrong with a reply that had no `error`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub problem: Option<String>,
+}
+
+impl fmt::Display for TokenEndpointError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.error {
+      Some(code) => write!(f, "Serwer tokenów odrzucił żądanie (HTTP {}, {})", self.status, code)?,
+      None => write!(f, "Nieprawidłowa odpowiedź serwera tokenów (HTTP {})", self.status)?,
+    }
+    for part in [&self.error_description, &self.problem].into_iter().flatten() {
+      write!(f, ": {}", part)?;
+    }
+    match &self.error_uri {
+      Some(uri) => write!(f, " ({})", uri),
+      None => Ok(()),
+    }
+  }
+}
+
+/// A reply with an access token. `expires_in` is 0 when it was missing or
+/// not positive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenGrant {
+  pub access_token: String,
+  pub refresh_token: Option<String>,
+  pub expires_in: u64,
+  pub scope: Option<String>,
+  /// Present when the scopes include openid or email.
+  pub id_token: Option<String>,
+  /// Oddities that didn't stop the grant from being used.
+  pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenReply {
+  Granted(TokenGrant),
+  Refused(TokenEndpointError),
+}
+
+impl TokenReply {
+  /// The OAuth `error` code of a refusal.
+  pub fn error_code(&self) -> Option<&str> {
+    match self {
+      TokenReply::Refused(e) => e.error.as_deref(),
+      TokenReply::Granted(_) => None,
+    }
+  }
+}
+
+/// What a body that isn't a JSON object looks like, for the message.
+fn describe(body: &str) -> String {
+  let trimmed = body.trim();
+  if trimmed.is_empty() {
+    return "pustą odpowiedź".into();
+  }
+  let snippet: String = trimmed.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(SNIPPET_CHARS).collect();
+  let lower = trimmed.to_ascii_lowercase();
+  if lower.starts_with('<') || lower.contains("<html") {
+    format!("stronę HTML — zapewne z proxy lub portalu logowania sieci, nie od Google (\"{}\")", snippet)
+  } else {
+    format!("odpowiedź, która nie jest obiektem JSON (\"{}\")", snippet)
+  }
+}
+
+fn text(json: &Value, key: &str) -> Option<String> {
+  json.get(key).and_then(Value::as_str).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// The OAuth error object of `json`. A Google API style
+/// `{"error": {"status", "message"}}` is read as well.
+fn refusal(status: u16, json: &Value) -> Option<TokenEndpointError> {
+  let (error, error_description) = match json.get("error")? {
+    Value::String(code) => (code.clone(), text(json, "error_description")),
+    Value::Object(_) => {
+      let inner = &json["error"];
+      (text(inner, "status").unwrap_or_else(|| "unknown_error".into()), text(inner, "message"))
+    }
+    _ => return None,
+  };
+  Some(TokenEndpointError { status, error: Some(error), error_description, error_uri: text(json, "error_uri"), problem: None })
+}
+
+/// Reads a token endpoint reply with HTTP `status` and `body`. A 5xx or a
+/// body that isn't a JSON object is an [`Error::Network`]: an outage or
+/// something in between, not a verdict on the request.
+pub fn parse(status: u16, body: &str) -> Result<TokenReply, Error> {
+  let json = serde_json::from_str::<Value>(body).ok().filter(Value::is_object);
+  if status >= 500 {
+    let detail = match &json {
+      Some(json) => refusal(status, json).map(|e| format!(": {}", e)).unwrap_or_default(),
+      None => format!(" ({})", describe(body)),
+    };
+    return Err(Error::Network(format!("Serwer tokenów odpowiedział HTTP {}{}", status, detail)));
+  }
+  let Some(json) = json else {
+    return Err(Error::Network(format!("Serwer tokenów zwrócił (HTTP {}) {} zamiast JSON", status, describe(body))));
+  };
+  if let Some(refused) = refusal(status, &json) {
+    return Ok(TokenReply::Refused(refused));
+  }
+  let problem = |p: String| Ok(TokenReply::Refused(TokenEndpointError { status, error: None, error_description: None, error_uri: None, problem: Some(p) }));
+  if !(200..300).contains(&status) {
+    return problem("odpowiedź bez kodu błędu OAuth".into());
+  }
+  let Some(access_token) = text(&json, "access_token") else {
+    return problem("brak access_token w odpowiedzi".into());
+  };
+  let mut warnings = Vec::new();
+  match json.get("token_type").and_then(Value::as_str) {
+    Some(t) if t.eq_ignore_ascii_case("bearer") => {}
+    Some(t) => warnings.push(format!("token_type {} zamiast Bearer", t)),
+    None => warnings.push("brak token_type (oczekiwano Bearer)".into()),
+  }
+  // Some proxies turn numbers into strings on the way.
+  let expires_in = match json.get("expires_in") {
+    Some(Value::String(s)) => s.trim().parse::<i64>().ok(),
+    Some(v) => v.as_i64(),
+    None => None,
+  };
+  let expires_in = match expires_in {
+    Some(secs) if secs > 0 => secs as u64,
+    Some(secs) => {
+      warnings.push(format!("expires_in {} nie jest dodatnie — czas ważności nieznany", secs));
+      0
+    }
+    None => {
+      warnings.push("brak poprawnego expires_in — czas ważności nieznany".into());
+      0
+    }
+  };
+  Ok(TokenReply::Granted(TokenGrant {
+    access_token,
+    refresh_token: text(&json, "refresh_token"),
+    expires_in,
+    scope: text(&json, "scope"),
+    id_token: text(&json, "id_token"),
+    warnings,
+  }))
+}
@@ -0,0 +1,53 @@
+//! The on-disk form of tokens.json, which tools outside the app read too
+//! (ytlite's uploader). Fields keep the struct order, and two RFC 3339 copies
+//! of the timestamps follow for readers that don't want epoch seconds. Reads
+//! ignore those copies: a hand-edit of one can't disagree with the numbers.
+
+use crate::Tokens;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+/// JSON Schema of tokens.json as written by this version.
+pub const TOKENS_SCHEMA_JSON: &str = r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "tokens.json",
+  "type": "object",
+  "required": ["access_token", "refresh_token"],
+  "properties": {
+    "access_token": { "type": "string", "description": "Empty with persist_access_token: false; the app keeps it in memory." },
+    "refresh_token": { "type": "string" },
+    "expires_in": { "type": "integer", "minimum": 0, "description": "Lifetime of access_token in seconds, from created_at; 0 when unknown." },
+    "created_at": { "type": "integer", "minimum": 0, "description": "Unix seconds access_token was issued at." },
+    "scope": { "type": "string", "description": "Space-separated scopes granted with these tokens." },
+    "client_id": { "type": "string", "description": "OAuth client the tokens were issued to." },
+    "issued_at": { "type": "integer", "minimum": 0, "description": "Unix seconds of the login behind refresh_token." },
+    "created_at_rfc3339": { "type": "string", "format": "date-time", "description": "created_at, for reading only." },
+    "expires_at_rfc3339": { "type": "string", "format": "date-time", "description": "created_at + expires_in, for reading only; absent when expires_in is 0." }
+  }
+}
+"#;
+
+#[derive(Serialize)]
+struct TokensFile<'a> {
+  #[serde(flatten)]
+  tokens: &'a Tokens,
+  created_at_rfc3339: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  expires_at_rfc3339: Option<String>,
+}
+
+fn rfc3339(secs: u64) -> String {
+  DateTime::<Utc>::from_timestamp(secs as i64, 0).unwrap_or_default().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Pretty JSON ending with a newline, the exact bytes of tokens.json.
+pub fn to_json(t: &Tokens) -> Result<String, serde_json::Error> {
+  let file = TokensFile {
+    tokens: t,
+    created_at_rfc3339: rfc3339(t.created_at),
+    expires_at_rfc3339: (t.expires_in > 0).then(|| rfc3339(t.created_at + t.expires_in)),
+  };
+  let mut s = serde_json::to_string_pretty(&file)?;
+  s.push('\n');
+  Ok(s)
+}
@@ -0,0 +1,781 @@
+//! Resumable video uploads (`uploadType=resumable`): a session is opened with
+//! the metadata, then the file goes up in chunks, each confirmed by the
+//! server. The session URL is kept on disk so an interrupted upload (exit,
+//! crash, lost network) continues where the server stopped counting.
+
+use crate::{
+  api::{self, ApiCall, Privacy, Reply},
+  comments, connectivity,
+  error::Error,
+  events, forbidden, format, hashing,
+  history::{self, UploadRecord, UploadStatus},
+  hooks::{self, HookContext},
+  http_headers,
+  library,
+  lint::{self, LintMode, LintRule, LintWarning, MetadataLint},
+  metrics, now_secs,
+  overrides::{self, ForceCode, ForcedOverride},
+  profiles,
+  progress::{SpeedEstimator, UploadProgress},
+  publish_time::{self, PublishTime},
+  read_config_from_dir,
+  remote_state::{self, Rejection, RejectionKind},
+  runtime, scopes, shutdown,
+  snapshots::{self, SnapshotReason},
+  status_file,
+  storage, thumbnails, timeouts,
+  video_links::{self, LinkAction, LinkCache, VideoLink},
+};
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+  collections::BTreeMap,
+  fs::{self, File},
+  io::{Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  sync::Mutex,
+  time::{Duration, Instant, SystemTime},
+};
+
+pub(crate) const UPLOAD_SCOPES: &[&str] = &[scopes::YOUTUBE_UPLOAD];
+const DELETE_SCOPES: &[&str] = &[scopes::YOUTUBE];
+/// Quota cost of `videos.insert`.
+pub(crate) const INSERT_COST: u32 = 1600;
+
+/// The protocol requires every chunk but the last to be a multiple of this.
+pub const CHUNK_GRANULARITY: u64 = 256 * 1024;
+pub const ADAPTIVE_START: u64 = 8 * 1024 * 1024;
+pub const ADAPTIVE_MAX: u64 = 256 * 1024 * 1024;
+/// A chunk confirmed faster than this doubles the next one in adaptive mode.
+const FAST_CHUNK: Duration = Duration::from_secs(15);
+/// Consecutive failed chunks before the upload gives up (the session stays).
+const MAX_CHUNK_RETRIES: u32 = 5;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+const DEFAULT_MIN_FILE_AGE_SECS: u64 = 30;
+const PROCESSING_POLL: Duration = Duration::from_secs(15);
+const PROCESSING_DEADLINE: Duration = Duration::from_secs(60 * 60);
+
+const SESSIONS_FILE: &str = "upload_sessions.json";
+/// Google keeps a resumable session open for about a week.
+pub(crate) const SESSION_VALIDITY_SECS: u64 = 7 * 24 * 60 * 60;
+static SESSIONS_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn validate_chunk_size(bytes: u64) -> Result<(), Error> {
+  if bytes == 0 || !bytes.is_multiple_of(CHUNK_GRANULARITY) {
+    return Err(Error::Config(format!(
+      "upload_chunk_bytes musi być dodatnią wielokrotnością {} B (256 KiB), podano {}",
+      CHUNK_GRANULARITY, bytes
+    )));
+  }
+  Ok(())
+}
+
+/// Picks the size of the next chunk. Fixed sizes never change; adaptive mode
+/// starts at [`ADAPTIVE_START`], doubles after each fast chunk up to
+/// [`ADAPTIVE_MAX`] and halves after a timeout or retry. Pure, so tests can
+/// drive it without a network.
+#[derive(Debug, Clone)]
+pub struct ChunkSizer {
+  size: u64,
+  adaptive: bool,
+}
+
+impl ChunkSizer {
+  pub fn fixed(bytes: u64) -> Self {
+    ChunkSizer { size: bytes, adaptive: false }
+  }
+
+  pub fn adaptive() -> Self {
+    ChunkSizer { size: ADAPTIVE_START, adaptive: true }
+  }
+
+  /// From `upload_chunk_bytes`, adaptive when it is absent.
+  pub fn for_dir(dir: &Path) -> Result<Self, Error> {
+    match read_config_from_dir(dir).and_then(|c| c.upload_chunk_bytes) {
+      Some(bytes) => validate_chunk_size(bytes).map(|_| Self::fixed(bytes)),
+      None => Ok(Self::adaptive()),
+    }
+  }
+
+  pub fn size(&self) -> u64 {
+    self.size
+  }
+
+  pub fn on_success(&mut self, elapsed: Duration) {
+    if self.adaptive && elapsed < FAST_CHUNK {
+      self.size = (self.size * 2).min(ADAPTIVE_MAX);
+    }
+  }
+
+  pub fn on_failure(&mut self) {
+    if self.adaptive {
+      self.size = (self.size / 2 / CHUNK_GRANULARITY).max(1) * CHUNK_GRANULARITY;
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VideoMetadata {
+  pub title: String,
+  #[serde(default)]
+  pub description: String,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  #[serde(default)]
+  pub category_id: Option<String>,
+  pub privacy: Privacy,
+  /// When the video goes public; it stays private until then. RFC3339, or
+  /// a local time or date as `publish_time` reads them.
+  #[serde(default)]
+  pub publish_at: Option<String>,
+  /// Image set as the thumbnail once the upload finished (see `thumbnails`).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub thumbnail: Option<PathBuf>,
+  /// Posted as the first comment once the upload finished, and taken as far
+  /// towards pinned as the API allows (see `comments`).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub first_comment: Option<String>,
+}
+
+impl VideoMetadata {
+  fn resource(&self) -> Value {
+    let mut status = json!({ "privacyStatus": self.privacy.as_str() });
+    if let Some(at) = &self.publish_at {
+      status["publishAt"] = json!(at);
+    }
+    let mut snippet = json!({ "title": self.title, "description": self.description, "tags": self.tags });
+    if let Some(category) = &self.category_id {
+      snippet["categoryId"] = json!(category);
+    }
+    json!({ "snippet": snippet, "status": status })
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadedVideo {
+  pub video_id: String,
+  pub url: String,
+  pub bytes: u64,
+  /// Hash of the file as it was when the upload started, in the configured
+  /// `hash_algorithm`.
+  pub file_hash: String,
+  /// Why the upload may not match the file (it changed while uploading).
+  #[serde(default)]
+  pub suspect: Option<String>,
+  /// The warnings `force` took it past.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub forced: Option<ForcedOverride>,
+}
+
+/// Payload of `upload-suspect`.
+#[derive(Serialize, Debug, Clone)]
+pub struct UploadSuspect {
+  pub file_path: String,
+  pub video_id: String,
+  pub reason: String,
+  /// `strict_integrity` removed the video from YouTube.
+  pub deleted: bool,
+}
+
+/// What must not change while a file uploads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint {
+  size: u64,
+  modified: Option<SystemTime>,
+}
+
+fn fingerprint(path: &Path) -> Result<Fingerprint, Error> {
+  let meta = fs::metadata(path).map_err(|e| Error::Validation(format!("Nie można odczytać {}: {}", path.display(), e)))?;
+  Ok(Fingerprint { size: meta.len(), modified: meta.modified().ok() })
+}
+
+/// An open upload session, keyed by file path in `upload_sessions.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Session {
+  session_url: String,
+  total_bytes: u64,
+  /// Resuming is only safe into a session for the same content.
+  #[serde(default)]
+  file_hash: String,
+  created_at: u64,
+}
+
+fn read_sessions(dir: &Path) -> BTreeMap<String, Session> {
+  fs::read_to_string(dir.join(SESSIONS_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+fn update_sessions(dir: &Path, f: impl FnOnce(&mut BTreeMap<String, Session>)) -> Result<(), Error> {
+  let _lock = storage::lock(dir)?;
+  let _guard = SESSIONS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut sessions = read_sessions(dir);
+  f(&mut sessions);
+  let s = serde_json::to_string_pretty(&sessions).map_err(|e| Error::Storage(e.to_string()))?;
+  storage::write_atomic(&dir.join(SESSIONS_FILE), s).map_err(|e| Error::Storage(e.to_string()))
+}
+
+/// Drops sessions created before `before` (Unix seconds); with `dry_run` only
+/// reports them. Returns their keys and the bytes the file shrinks by.
+pub(crate) fn prune_sessions(dir: &Path, before: u64, dry_run: bool) -> Result<(Vec<String>, u64), Error> {
+  // A dry run writes nothing, not even the lock file.
+  let _lock = if dry_run { None } else { Some(storage::lock(dir)?) };
+  let _guard = SESSIONS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut sessions = read_sessions(dir);
+  let stale: Vec<String> = sessions.iter().filter(|(_, s)| s.created_at < before).map(|(k, _)| k.clone()).collect();
+  if stale.is_empty() {
+    return Ok((stale, 0));
+  }
+  let size = |s: &BTreeMap<String, Session>| serde_json::to_string_pretty(s).map(|s| s.len() as u64).map_err(|e| Error::Storage(e.to_string()));
+  let old_size = size(&sessions)?;
+  sessions.retain(|k, _| !stale.contains(k));
+  let new_size = size(&sessions)?;
+  if !dry_run {
+    let s = serde_json::to_string_pretty(&sessions).map_err(|e| Error::Storage(e.to_string()))?;
+    storage::write_atomic(&dir.join(SESSIONS_FILE), s).map_err(|e| Error::Storage(e.to_string()))?;
+  }
+  Ok((stale, old_size.saturating_sub(new_size)))
+}
+
+fn session_key(path: &Path) -> String {
+  path.to_string_lossy().into_owned()
+}
+
+/// Whether an upload of `path` would resume an open session: one for a file
+/// of the same size, young enough that Google still keeps it. The content
+/// hash is compared when the upload starts.
+pub(crate) fn has_resumable_session(dir: &Path, path: &Path) -> bool {
+  let Ok(size) = fs::metadata(path).map(|m| m.len()) else { return false };
+  read_sessions(dir)
+    .get(&session_key(path))
+    .is_some_and(|s| s.total_bytes == size && s.created_at + SESSION_VALIDITY_SECS > now_secs())
+}
+
+/// Where the server stands with a session.
+enum Progress {
+  /// Bytes confirmed so far; the next chunk starts here.
+  Incomplete(u64),
+  /// The id of the created video.
+  Done(String),
+}
+
+/// Interprets a session reply: 308 carries `Range: bytes=0-N` (no header means
+/// nothing arrived yet), 200/201 the created video. A 2xx without the
+/// video's id fails rather than recording an upload nothing can find.
+fn progress_of(reply: &Reply) -> Result<Progress, Error> {
+  match reply.status.as_u16() {
+    308 => {
+      let confirmed = reply
+        .headers
+        .get(reqwest::header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|r| r.rsplit('-').next())
+        .and_then(|end| end.trim().parse::<u64>().ok())
+        .map_or(0, |end| end + 1);
+      Ok(Progress::Incomplete(confirmed))
+    }
+    status @ (200 | 201) => {
+      let video: Value =
+        serde_json::from_str(&reply.text).map_err(|e| Error::Api { status, reason: "invalidJson".into(), message: e.to_string() })?;
+      match video["id"].as_str().filter(|id| !id.is_empty()) {
+        Some(id) => Ok(Progress::Done(id.to_string())),
+        None => Err(Error::Api { status, reason: "invalidResponse".into(), message: "videos.insert: brak id filmu w odpowiedzi".into() }),
+      }
+    }
+    status => Err(api::error_from_response(status, &reply.text, UPLOAD_SCOPES, 0)),
+  }
+}
+
+async fn open_session(
+  dir: &Path,
+  path: &Path,
+  total: u64,
+  file_hash: &str,
+  meta: &VideoMetadata,
+  prepared: &Prepared,
+) -> Result<String, Error> {
+  let call = ApiCall::post("/upload/youtube/v3/videos")
+    .query("uploadType", "resumable")
+    .query("part", "snippet,status")
+    .header("X-Upload-Content-Length", total.to_string())
+    .header("X-Upload-Content-Type", "video/*")
+    .json(meta.resource())
+    .publish_at(prepared.publish.clone())
+    .video_links(prepared.links.clone())
+    .trace_id(&prepared.trace_id)
+    .cost(INSERT_COST)
+    .scopes(UPLOAD_SCOPES);
+  let reply = api::send(dir, &call).await?;
+  if !reply.status.is_success() {
+    let err = api::error_from_response(reply.status.as_u16(), &reply.text, UPLOAD_SCOPES, INSERT_COST);
+    let err = forbidden::record(dir, err);
+    return Err(api::content_owner_error(dir, err));
+  }
+  let url = reply
+    .headers
+    .get(reqwest::header::LOCATION)
+    .and_then(|v| v.to_str().ok())
+    .ok_or_else(|| Error::Api { status: reply.status.as_u16(), reason: "missingLocation".into(), message: "Brak adresu sesji wysyłania".into() })?
+    .to_string();
+  let session = Session { session_url: url.clone(), total_bytes: total, file_hash: file_hash.to_string(), created_at: now_secs() };
+  update_sessions(dir, |s| {
+    s.insert(session_key(path), session);
+  })?;
+  Ok(url)
+}
+
+/// Asks the server how much of the session it has (`Content-Range: bytes */total`).
+async fn query_session(dir: &Path, url: &str, total: u64, trace_id: &str) -> Result<Progress, Error> {
+  let call = ApiCall::upload_session(Method::PUT, url)
+    .header("Content-Range", format!("bytes */{}", total))
+    .bytes("video/*", Vec::new())
+    .trace_id(trace_id)
+    .scopes(UPLOAD_SCOPES);
+  progress_of(&api::send(dir, &call).await?)
+}
+
+fn read_chunk(path: &Path, offset: u64, len: u64) -> Result<Vec<u8>, Error> {
+  let mut f = File::open(path).map_err(|e| Error::Storage(format!("Nie można odczytać {}: {}", path.display(), e)))?;
+  f.seek(SeekFrom::Start(offset)).map_err(|e| Error::Storage(e.to_string()))?;
+  let mut data = vec![0; len as usize];
+  f.read_exact(&mut data).map_err(|e| Error::Storage(format!("Nie można odczytać {}: {}", path.display(), e)))?;
+  Ok(data)
+}
+
+async fn send_chunk(dir: &Path, url: &str, path: &Path, offset: u64, len: u64, total: u64, trace_id: &str) -> Result<Progress, Error> {
+  let data = read_chunk(path, offset, len)?;
+  let call = ApiCall::upload_session(Method::PUT, url)
+    .header("Content-Range", format!("bytes {}-{}/{}", offset, offset + len - 1, total))
+    .bytes("video/*", data)
+    .trace_id(trace_id)
+    .scopes(UPLOAD_SCOPES);
+  progress_of(&api::send(dir, &call).await?)
+}
+
+fn is_transient(e: &Error) -> bool {
+  matches!(e, Error::Network(_) | Error::TimedOut { .. }) || matches!(e, Error::Api { status, .. } if *status >= 500)
+}
+
+/// What `publish_at` and the description's video links resolved to before
+/// the upload, shown in progress events and dry-run plans, and the trace id
+/// every request of the upload carries.
+#[derive(Default)]
+struct Prepared {
+  publish: Option<PublishTime>,
+  links: Vec<VideoLink>,
+  trace_id: String,
+}
+
+/// The force codes of `warnings`, each once.
+fn codes_of<'a>(warnings: impl IntoIterator<Item = &'a LintWarning>) -> Vec<String> {
+  let mut codes = Vec::new();
+  for code in warnings.into_iter().map(|w| ForceCode::Lint(w.rule).code()) {
+    if !codes.contains(&code) {
+      codes.push(code);
+    }
+  }
+  codes
+}
+
+/// Lints `meta` and reports any warnings, with `links` left in that viewers
+/// can't open, as `metadata-lint`; in block mode they refuse the upload
+/// unless `force` has the code of each. Returns the codes that were forced.
+fn check_metadata(
+  dir: &Path,
+  path: &Path,
+  meta: &VideoMetadata,
+  cfg: &lint::LintConfig,
+  links: &[VideoLink],
+  force: &[ForceCode],
+) -> Result<Vec<String>, Error> {
+  let mut warnings = lint::lint_metadata(meta, cfg);
+  if !cfg.disabled.contains(&LintRule::UnavailableVideoLink) {
+    for link in links.iter().filter(|l| l.action == LinkAction::Warned) {
+      warnings.push(LintWarning { rule: LintRule::UnavailableVideoLink, field: "description".into(), message: link.problem() });
+    }
+  }
+  if warnings.is_empty() {
+    return Ok(Vec::new());
+  }
+  let (forced, unforced): (Vec<&LintWarning>, Vec<&LintWarning>) =
+    warnings.iter().partition(|w| force.contains(&ForceCode::Lint(w.rule)));
+  let blocked = cfg.mode == LintMode::Block && !unforced.is_empty();
+  let overridden = if cfg.mode == LintMode::Block { codes_of(forced) } else { Vec::new() };
+  let file_path = path.to_string_lossy().into_owned();
+  let listed = unforced.iter().map(|w| format!("{}: {}", w.field, w.message)).collect::<Vec<_>>().join("; ");
+  let hint = codes_of(unforced).join(", ");
+  events::transition(dir, &file_path, "metadata-lint", MetadataLint { file_path: file_path.clone(), warnings, blocked });
+  if blocked {
+    return Err(Error::Validation(format!(
+      "Metadane {} nie przeszły kontroli — {} — popraw je lub wymuś wysyłanie (force: {})",
+      path.display(),
+      listed,
+      hint
+    )));
+  }
+  Ok(overridden)
+}
+
+/// Uploads `path` as a new video, continuing an earlier session for the same
+/// file when the server still has it. Emits `upload-progress` after every
+/// confirmed chunk. When the app shuts down mid-upload the session is kept
+/// and the call fails; calling again resumes.
+///
+/// Files modified within `upload_min_file_age_secs` are refused unless
+/// `force` has `recentlyModified`. The file is hashed first and re-checked
+/// at the end: if its size or mtime changed, or fewer bytes went up than it
+/// had, the upload is marked suspect and `upload-suspect` emitted; with
+/// `strict_integrity` the video is also deleted and the call fails.
+///
+/// The metadata is linted first (`metadata_lint`); warnings are emitted as
+/// `metadata-lint`, and in block mode refuse the upload unless `force` has
+/// each one's rule. With `video_links` set, links in the description to
+/// videos viewers can't open yet are warned about, stripped or replaced
+/// before that. Whatever `force` took the upload past is in the audit log,
+/// the history record and `forced`.
+pub async fn upload_video(dir: &Path, path: &Path, meta: &VideoMetadata, force: &[ForceCode]) -> Result<UploadedVideo, Error> {
+  upload_video_with(dir, path, meta, force, &mut LinkCache::default()).await
+}
+
+/// [`upload_video`] sharing the video link lookups of a batch in `links`.
+pub(crate) async fn upload_video_with(
+  dir: &Path,
+  path: &Path,
+  meta: &VideoMetadata,
+  force: &[ForceCode],
+  links: &mut LinkCache,
+) -> Result<UploadedVideo, Error> {
+  api::ensure_writable(dir)?;
+  let _guard = shutdown::track_upload(dir);
+  let started = now_secs();
+  let before = fingerprint(path)?;
+  if before.size == 0 {
+    return Err(Error::Validation(format!("Plik {} jest pusty", path.display())));
+  }
+  let cfg = read_config_from_dir(dir).unwrap_or_default();
+  let age = before.modified.and_then(|m| m.elapsed().ok()).unwrap_or_default();
+  let min_age = Duration::from_secs(cfg.upload_min_file_age_secs.unwrap_or(DEFAULT_MIN_FILE_AGE_SECS));
+  let mut overridden = Vec::new();
+  if age < min_age {
+    if !force.contains(&ForceCode::RecentlyModified) {
+      return Err(Error::Validation(format!(
+        "Plik {} był zmieniony {} s temu i może być jeszcze zapisywany — odczekaj {} s lub wymuś wysyłanie (force: {})",
+        path.display(),
+        age.as_secs(),
+        min_age.as_secs(),
+        overrides::RECENTLY_MODIFIED
+      )));
+    }
+    overridden.push(ForceCode::RecentlyModified.code());
+  }
+  // Sent in UTC whatever form the sidecar used.
+  let publish = meta.publish_at.as_deref().map(|at| publish_time::resolve(dir, at)).transpose()?;
+  let mut meta = VideoMetadata { publish_at: publish.as_ref().map(|p| p.utc.clone()), ..meta.clone() };
+  let mut prepared = Prepared { publish, links: Vec::new(), trace_id: http_headers::trace_id() };
+  if let Some(link_cfg) = &cfg.video_links {
+    // A private video without a schedule has no date viewers see it from; now is the best guess.
+    let public_at = prepared.publish.as_ref().and_then(|p| DateTime::parse_from_rfc3339(&p.utc).ok()).map_or_else(Utc::now, |at| at.to_utc());
+    let report = video_links::resolve_with(dir, &meta.description, link_cfg, public_at, links).await?;
+    meta.description = report.description;
+    prepared.links = report.links;
+  }
+  let meta = &meta;
+  overridden.extend(check_metadata(dir, path, meta, &cfg.metadata_lint.clone().unwrap_or_default(), &prepared.links, force)?);
+  let mut sizer = ChunkSizer::for_dir(dir)?;
+  if !connectivity::online(dir).await {
+    return Err(Error::Offline);
+  }
+  let (hash_dir, hash_path) = (dir.to_path_buf(), path.to_path_buf());
+  let hashed = hashing::off_runtime(move || hashing::hash(&hash_dir, &hash_path)).await?;
+  let file_hash = hashed.hash.clone();
+  let mut record = UploadRecord {
+    timestamp: started,
+    profile: profiles::name_of(dir),
+    file_path: path.to_string_lossy().into_owned(),
+    file_hash: file_hash.clone(),
+    video_id: None,
+    title: meta.title.clone(),
+    privacy: meta.privacy.as_str().to_string(),
+    duration_secs: 0,
+    bytes: before.size,
+    status: UploadStatus::Failed,
+    error: None,
+    suspect: false,
+    rejection: None,
+    forced: None,
+  };
+  if !overridden.is_empty() && !api::dry_run_enabled(dir) {
+    record.forced = Some(overrides::record(dir, &record.file_path, &meta.title, overridden));
+  }
+  let active = status_file::track_upload(dir, path, before.size);
+  let result = match run(dir, path, before.size, &file_hash, meta, &prepared, &mut sizer).await {
+    Ok((video_id, sent)) => {
+      // Like history: the video is up, a busy or full disk only leaves a
+      // stale session behind (pruned later).
+      let _ = update_sessions(dir, |s| {
+        s.remove(&session_key(path));
+      });
+      let changed = fingerprint(path).ok() != Some(before.clone());
+      let suspect = if sent != before.size {
+        Some(format!("Wysłano {} B, a plik miał {} B", sent, before.size))
+      } else if changed {
+        Some("Plik zmienił się w trakcie wysyłania (rozmiar lub czas modyfikacji)".to_string())
+      } else {
+        None
+      };
+      record.video_id = Some(video_id.clone());
+      record.suspect = suspect.is_some();
+      let video = UploadedVideo {
+        url: format!("https://youtu.be/{}", video_id),
+        video_id,
+        bytes: sent,
+        file_hash,
+        suspect,
+        forced: record.forced.clone(),
+      };
+      match &video.suspect {
+        Some(reason) => flag_suspect(dir, path, &video, reason, cfg.strict_integrity).await,
+        None => Ok(video),
+      }
+    }
+    Err(e) => Err(e),
+  };
+  drop(active);
+  match &result {
+    Ok(video) => {
+      record.status = UploadStatus::Completed;
+      // Like history: a full disk shouldn't fail a finished upload.
+      let _ = library::record_upload(dir, &hashed, &video.video_id);
+      if let Some(image) = &meta.thumbnail {
+        tokio::spawn(set_thumbnail_after_upload(dir.to_path_buf(), video.video_id.clone(), image.clone()));
+      }
+      if let Some(text) = &meta.first_comment {
+        tokio::spawn(post_first_comment_after_upload(dir.to_path_buf(), video.video_id.clone(), text.clone()));
+      }
+      if !cfg.post_upload_hooks.is_empty() {
+        let ctx = HookContext {
+          video_id: video.video_id.clone(),
+          title: meta.title.clone(),
+          url: video.url.clone(),
+          profile: record.profile.clone(),
+        };
+        tokio::spawn(after_processing(dir.to_path_buf(), ctx));
+      }
+    }
+    // Interrupted by shutdown: not a failure, the session resumes later.
+    Err(_) if shutdown::stopping(dir) => return result,
+    // Only planned: nothing was sent, so there's nothing to record.
+    Err(Error::DryRun(_)) => return result,
+    Err(e) => {
+      record.error = Some(e.to_string());
+      status_file::upload_failed(dir, e);
+    }
+  }
+  metrics::upload_finished(dir, result.is_ok());
+  record.timestamp = now_secs();
+  record.duration_secs = record.timestamp.saturating_sub(started);
+  // History is a convenience; a full disk shouldn't turn a finished upload into an error.
+  let _ = history::append(dir, &record);
+  result
+}
+
+/// Payload of `upload-processed`.
+#[derive(Serialize, Debug, Clone)]
+pub struct UploadProcessed {
+  pub video_id: String,
+  /// `succeeded`, `failed`, `terminated`, `rejected`, `deleted`, or
+  /// `unknown` when polling failed.
+  pub status: String,
+  /// Why the video isn't live, unless it `succeeded`.
+  pub rejection: Option<Rejection>,
+}
+
+/// Payload of `processing-progress`, from `processingDetails.processingProgress`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ProcessingProgress {
+  pub video_id: String,
+  pub parts_processed: Option<u64>,
+  pub parts_total: Option<u64>,
+  pub time_left_ms: Option<u64>,
+}
+
+/// The API sends these counters as strings.
+fn counter(v: &Value) -> Option<u64> {
+  v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+}
+
+/// Polls `processingDetails` and `status` until YouTube is done with the
+/// video and returns how it ended. Emits `processing-progress` while
+/// YouTube reports how far it got. A rejection (a duplicate, copyright) or
+/// failed processing is recorded in the history, the library and the queue
+/// ([`remote_state::record`]).
+pub async fn wait_for_processing(dir: &Path, video_id: &str, poll: Duration, deadline: Duration) -> Result<UploadProcessed, Error> {
+  timeouts::with_deadline("processing", deadline, async {
+    loop {
+      let call = ApiCall::get("/youtube/v3/videos").query("part", remote_state::PARTS).query("id", video_id);
+      let resp = api::execute(dir, call).await?;
+      let item = &resp["items"][0];
+      let details = &item["processingDetails"];
+      if let Some(rejection) = remote_state::rejection_of(item) {
+        if let Err(e) = remote_state::record(dir, video_id, &rejection) {
+          runtime::for_dir(dir).warn("remote_state", format!("Nie udało się zapisać odrzucenia {}: {}", video_id, e));
+        }
+        let status = match (rejection.kind, details["processingStatus"].as_str()) {
+          (RejectionKind::Rejected, _) => "rejected",
+          (RejectionKind::Removed, _) => "deleted",
+          (RejectionKind::Failed, Some(status @ ("failed" | "terminated"))) => status,
+          (RejectionKind::Failed, _) => "failed",
+        };
+        return Ok(UploadProcessed { video_id: video_id.to_string(), status: status.to_string(), rejection: Some(rejection) });
+      }
+      match details["processingStatus"].as_str() {
+        Some("processing") | None => {
+          let p = &details["processingProgress"];
+          if p.is_object() {
+            let progress = ProcessingProgress {
+              video_id: video_id.to_string(),
+              parts_processed: counter(&p["partsProcessed"]),
+              parts_total: counter(&p["partsTotal"]),
+              time_left_ms: counter(&p["timeLeftMs"]),
+            };
+            events::progress(dir, video_id, "processing-progress", progress);
+          }
+          tokio::time::sleep(poll).await
+        }
+        Some(status) => return Ok(UploadProcessed { video_id: video_id.to_string(), status: status.to_string(), rejection: None }),
+      }
+    }
+  })
+  .await
+}
+
+/// Background stage after a successful upload: waits for processing, then
+/// runs the post-upload hooks. If the status can't be read (e.g. no read
+/// scope) the hooks still run; only a failed processing skips them.
+async fn after_processing(dir: std::path::PathBuf, ctx: HookContext) {
+  let processed = match wait_for_processing(&dir, &ctx.video_id, PROCESSING_POLL, PROCESSING_DEADLINE).await {
+    Ok(processed) => processed,
+    Err(e) => {
+      runtime::for_dir(&dir).warn("processing", format!("Nie udało się odczytać stanu przetwarzania {}: {}", ctx.video_id, e));
+      UploadProcessed { video_id: ctx.video_id.clone(), status: "unknown".to_string(), rejection: None }
+    }
+  };
+  let run_hooks = matches!(processed.status.as_str(), "succeeded" | "unknown");
+  events::transition(&dir, &ctx.video_id, "upload-processed", processed);
+  if run_hooks {
+    hooks::run_all(&dir, &ctx).await;
+  }
+}
+
+/// Background stage for `VideoMetadata::thumbnail`; a thumbnail that can't
+/// be set doesn't make the upload fail.
+async fn set_thumbnail_after_upload(dir: PathBuf, video_id: String, image: PathBuf) {
+  if let Err(e) = thumbnails::set_thumbnail(&dir, &video_id, &image).await {
+    runtime::for_dir(&dir).warn("thumbnail", format!("Nie udało się ustawić miniatury {}: {}", video_id, e));
+  }
+}
+
+/// Background stage for `VideoMetadata::first_comment`: emits
+/// `first-comment-posted` with what still has to be done by hand. A comment
+/// that can't be posted doesn't make the upload fail.
+async fn post_first_comment_after_upload(dir: PathBuf, video_id: String, text: String) {
+  match comments::post_first_comment(&dir, &video_id, &text).await {
+    Ok(posted) => events::transition(&dir, &video_id, "first-comment-posted", posted),
+    Err(e) => runtime::for_dir(&dir).warn("first_comment", format!("Nie udało się dodać pierwszego komentarza do {}: {}", video_id, e)),
+  }
+}
+
+/// Reports a suspect upload; in strict mode deletes the video and fails.
+async fn flag_suspect(dir: &Path, path: &Path, video: &UploadedVideo, reason: &str, strict: bool) -> Result<UploadedVideo, Error> {
+  let deleted = if strict {
+    snapshots::take(dir, &video.video_id, SnapshotReason::Delete).await?;
+    let call = ApiCall::delete("/youtube/v3/videos").query("id", video.video_id.clone()).scopes(DELETE_SCOPES);
+    api::execute(dir, call).await.map_err(|e| {
+      e.context(&format!("Wideo {} może być niekompletne ({}), a usunięcie go nie powiodło się", video.video_id, reason))
+    })?;
+    true
+  } else {
+    false
+  };
+  let file_path = path.to_string_lossy().into_owned();
+  let suspect = UploadSuspect { file_path: file_path.clone(), video_id: video.video_id.clone(), reason: reason.to_string(), deleted };
+  events::transition(dir, &file_path, "upload-suspect", suspect);
+  if deleted {
+    return Err(Error::Validation(format!("{} — wideo {} usunięto (strict_integrity)", reason, video.video_id)));
+  }
+  Ok(video.clone())
+}
+
+/// Sends the file; returns the video id and the bytes the last chunk ended at.
+async fn run(
+  dir: &Path,
+  path: &Path,
+  total: u64,
+  file_hash: &str,
+  meta: &VideoMetadata,
+  prepared: &Prepared,
+  sizer: &mut ChunkSizer,
+) -> Result<(String, u64), Error> {
+  let key = path.to_string_lossy();
+  let existing = read_sessions(dir).remove(&session_key(path)).filter(|s| s.total_bytes == total && s.file_hash == file_hash);
+  let (url, mut progress) = match existing {
+    Some(s) => match query_session(dir, &s.session_url, total, &prepared.trace_id).await {
+      Ok(p) => (s.session_url, p),
+      // Expired or unknown session (404/410): start over.
+      Err(Error::Api { status: 404 | 410, .. }) => (open_session(dir, path, total, file_hash, meta, prepared).await?, Progress::Incomplete(0)),
+      Err(e) => return Err(e),
+    },
+    None => (open_session(dir, path, total, file_hash, meta, prepared).await?, Progress::Incomplete(0)),
+  };
+  let clock = Instant::now();
+  let lang = format::language(dir);
+  let mut speed = SpeedEstimator::new();
+  let mut failures = 0;
+  // Already complete on resume means the earlier run sent everything.
+  let mut sent_through = total;
+  loop {
+    let offset = match progress {
+      Progress::Done(video_id) => return Ok((video_id, sent_through)),
+      Progress::Incomplete(offset) => offset,
+    };
+    speed.sample(clock.elapsed(), offset);
+    if shutdown::stopping(dir) {
+      return Err(Error::Network(format!(
+        "Wysyłanie wstrzymane przy zamykaniu aplikacji po {} z {} B — ponowienie je wznowi",
+        offset, total
+      )));
+    }
+    let len = sizer.size().min(total - offset);
+    metrics::set_chunk_size(dir, sizer.size());
+    let p = speed.progress(offset, total);
+    status_file::upload_progress(dir, &key, &p);
+    events::progress(dir, &key, "upload-progress", UploadProgress {
+      chunk_size: sizer.size(),
+      labels: Some(format::progress_labels(&p, lang)),
+      publish_at: prepared.publish.clone(),
+      ..p
+    });
+    sent_through = offset + len;
+    let chunk_started = Instant::now();
+    progress = match send_chunk(dir, &url, path, offset, len, total, &prepared.trace_id).await {
+      Ok(p) => {
+        failures = 0;
+        sizer.on_success(chunk_started.elapsed());
+        p
+      }
+      Err(e) if is_transient(&e) && failures < MAX_CHUNK_RETRIES => {
+        failures += 1;
+        sizer.on_failure();
+        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(failures - 1)).await;
+        // The server may have kept part of the chunk; continue from what it confirms.
+        match query_session(dir, &url, total, &prepared.trace_id).await {
+          Ok(p) => p,
+          Err(e) if is_transient(&e) => Progress::Incomplete(offset),
+          Err(e) => return Err(e),
+        }
+      }
+      Err(e) => return Err(e),
+    };
+  }
+}
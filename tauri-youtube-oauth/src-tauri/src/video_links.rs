@@ -0,0 +1,225 @@
+//! Links to other videos in a description, checked before it goes out. A
+//! template's "previous episode" link 404s for viewers while that video is
+//! still private or scheduled to go public after this one; `video_links` in
+//! the config says whether such a link is only warned about, stripped or
+//! replaced with a placeholder. Only URLs are recognized: a bare 11-character
+//! id is indistinguishable from an ordinary word.
+
+use crate::{error::Error, read_config_from_dir, scopes, videos};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, path::Path, sync::OnceLock};
+
+const LOOKUP_SCOPES: &[&str] = &[scopes::YOUTUBE_READONLY];
+/// What replaces an unavailable link without `placeholder`.
+pub const DEFAULT_PLACEHOLDER: &str = "(link pojawi się po publikacji)";
+
+/// What happens to a link viewers couldn't open.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+  /// Left in; uploads report it as a `metadata-lint` warning.
+  #[default]
+  Warn,
+  /// Removed, with its line when nothing else was on it.
+  Strip,
+  /// Replaced with `placeholder`.
+  Placeholder,
+}
+
+/// `video_links` in the config; absent means descriptions aren't checked.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct VideoLinksConfig {
+  #[serde(default)]
+  pub mode: LinkMode,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub placeholder: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStatus {
+  Public,
+  Unlisted,
+  /// Private with a `publishAt`.
+  Scheduled,
+  Private,
+  /// Deleted, rejected, or not visible to this account at all.
+  Missing,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkAction {
+  Kept,
+  Warned,
+  Stripped,
+  Replaced,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VideoLink {
+  pub video_id: String,
+  /// The link as written in the description.
+  pub url: String,
+  pub status: LinkStatus,
+  /// When a scheduled video goes public.
+  pub publish_at: Option<String>,
+  /// Whether viewers can open it once this description is public.
+  pub viewable: bool,
+  pub action: LinkAction,
+}
+
+/// Result of `resolve_video_links`: the description as it would be sent and
+/// every video link found in it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LinkReport {
+  pub description: String,
+  pub links: Vec<VideoLink>,
+}
+
+impl VideoLink {
+  /// Why viewers can't open it, for warnings.
+  pub fn problem(&self) -> String {
+    let why = match (self.status, &self.publish_at) {
+      (LinkStatus::Scheduled, Some(at)) => format!("publikacja zaplanowana na {}", at),
+      (LinkStatus::Missing, _) => "film nie istnieje lub jest niedostępny".to_string(),
+      _ => "film jest prywatny".to_string(),
+    };
+    format!("Link {} nie zadziała dla widzów: {}", self.url, why)
+  }
+}
+
+impl LinkReport {
+  /// The links left in that viewers can't open.
+  pub fn warnings(&self) -> impl Iterator<Item = &VideoLink> {
+    self.links.iter().filter(|l| l.action == LinkAction::Warned)
+  }
+}
+
+/// `status` parts looked up so far, by id (`None`: not returned). One per
+/// batch run (a queue pass, a metadata plan), so a link repeated in every
+/// description is paid for once.
+#[derive(Default)]
+pub(crate) struct LinkCache(HashMap<String, Option<Value>>);
+
+fn pattern() -> &'static Regex {
+  static PATTERN: OnceLock<Regex> = OnceLock::new();
+  PATTERN.get_or_init(|| {
+    Regex::new(
+      r"(?i)(?:https?://)?(?:www\.|m\.)?(?:youtube\.com/(?:watch\?(?:[^\s#]*&)?v=|shorts/|live/|embed/)|youtu\.be/)([a-z0-9_-]{11})(?:[?&#][\w=&%.-]*[\w=&%-])?",
+    )
+    .unwrap()
+  })
+}
+
+/// Each video URL in `text` with its byte range and id, in order.
+pub fn find(text: &str) -> Vec<(std::ops::Range<usize>, String)> {
+  pattern().captures_iter(text).filter_map(|c| Some((c.get(0)?.range(), c.get(1)?.as_str().to_string()))).collect()
+}
+
+fn status_of(resource: Option<&Value>) -> (LinkStatus, Option<String>) {
+  let Some(status) = resource.map(|r| &r["status"]) else {
+    return (LinkStatus::Missing, None);
+  };
+  if matches!(status["uploadStatus"].as_str(), Some("deleted" | "failed" | "rejected")) {
+    return (LinkStatus::Missing, None);
+  }
+  let publish_at = status["publishAt"].as_str().map(str::to_string);
+  match (status["privacyStatus"].as_str(), &publish_at) {
+    (Some("public"), _) => (LinkStatus::Public, None),
+    (Some("unlisted"), _) => (LinkStatus::Unlisted, None),
+    (Some("private"), Some(_)) => (LinkStatus::Scheduled, publish_at),
+    _ => (LinkStatus::Private, None),
+  }
+}
+
+/// A scheduled video counts as viewable when it goes public no later than
+/// the description does (`public_at`).
+fn viewable(status: LinkStatus, publish_at: Option<&str>, public_at: DateTime<Utc>) -> bool {
+  match status {
+    LinkStatus::Public | LinkStatus::Unlisted => true,
+    LinkStatus::Scheduled => publish_at.and_then(|at| DateTime::parse_from_rfc3339(at).ok()).is_some_and(|at| at <= public_at),
+    LinkStatus::Private | LinkStatus::Missing => false,
+  }
+}
+
+/// `description` with `cfg` applied to the links, given each video's
+/// `status` resource; pure, the lookups are [`resolve_with`]'s.
+pub(crate) fn apply(
+  description: &str,
+  resources: &HashMap<String, Option<Value>>,
+  cfg: &VideoLinksConfig,
+  public_at: DateTime<Utc>,
+) -> LinkReport {
+  let placeholder = cfg.placeholder.as_deref().unwrap_or(DEFAULT_PLACEHOLDER);
+  let mut out = String::new();
+  let mut links = Vec::new();
+  let mut last = 0;
+  for (range, video_id) in find(description) {
+    let (status, publish_at) = status_of(resources.get(&video_id).and_then(Option::as_ref));
+    let viewable = viewable(status, publish_at.as_deref(), public_at);
+    let action = match (viewable, cfg.mode) {
+      (true, _) => LinkAction::Kept,
+      (false, LinkMode::Warn) => LinkAction::Warned,
+      (false, LinkMode::Strip) => LinkAction::Stripped,
+      (false, LinkMode::Placeholder) => LinkAction::Replaced,
+    };
+    out.push_str(&description[last..range.start]);
+    match action {
+      LinkAction::Kept | LinkAction::Warned => out.push_str(&description[range.clone()]),
+      LinkAction::Replaced => out.push_str(placeholder),
+      LinkAction::Stripped => {}
+    }
+    last = range.end;
+    links.push(VideoLink { video_id, url: description[range].to_string(), status, publish_at, viewable, action });
+  }
+  out.push_str(&description[last..]);
+  if links.iter().any(|l| l.action == LinkAction::Stripped) {
+    // Lines that held nothing but a stripped link go with it.
+    let had: Vec<bool> = description.lines().map(|l| !l.trim().is_empty()).collect();
+    let kept: Vec<&str> = out.lines().collect();
+    if kept.len() == had.len() {
+      let trailing = if out.ends_with('\n') { "\n" } else { "" };
+      out = kept.iter().zip(had).filter(|(l, had)| !(*had && l.trim().is_empty())).map(|(l, _)| *l).collect::<Vec<_>>().join("\n");
+      out.push_str(trailing);
+    }
+  }
+  LinkReport { description: out, links }
+}
+
+/// [`apply`] after looking up the videos `description` links to that
+/// `cache` doesn't know yet, 50 per `videos.list`.
+pub(crate) async fn resolve_with(
+  dir: &Path,
+  description: &str,
+  cfg: &VideoLinksConfig,
+  public_at: DateTime<Utc>,
+  cache: &mut LinkCache,
+) -> Result<LinkReport, Error> {
+  let mut unknown: Vec<String> = find(description).into_iter().map(|(_, id)| id).filter(|id| !cache.0.contains_key(id)).collect();
+  unknown.sort();
+  unknown.dedup();
+  if !unknown.is_empty() {
+    let mut found = videos::fetch_as(dir, &unknown, "status", LOOKUP_SCOPES).await?;
+    for id in unknown {
+      let resource = found.remove(&id);
+      cache.0.insert(id, resource);
+    }
+  }
+  Ok(apply(description, &cache.0, cfg, public_at))
+}
+
+/// `video_links` from the config in `dir`, when the check is on.
+pub(crate) fn configured(dir: &Path) -> Option<VideoLinksConfig> {
+  read_config_from_dir(dir).and_then(|c| c.video_links)
+}
+
+/// What the configured handling (warn without one) does to the links in
+/// `description`, for a video going public now.
+pub async fn resolve_video_links(dir: &Path, description: &str) -> Result<LinkReport, Error> {
+  let cfg = configured(dir).unwrap_or_default();
+  resolve_with(dir, description, &cfg, Utc::now(), &mut LinkCache::default()).await
+}
@@ -0,0 +1,153 @@
+//! Reading and rewriting existing videos. `videos.update` replaces whole
+//! parts, so every write starts from the current resource and changes only
+//! the fields it means to.
+
+use crate::{
+  api::{self, ApiCall},
+  channel,
+  cursor::PageRequest,
+  error::Error,
+  scopes,
+  snapshots::{self, SnapshotReason},
+  video_links::VideoLink,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::HashMap, path::Path, time::Duration};
+
+pub(crate) const VIDEO_WRITE_SCOPES: &[&str] = &[scopes::YOUTUBE];
+pub const TITLE_MAX_CHARS: usize = 100;
+pub const DESCRIPTION_MAX_CHARS: usize = 5000;
+/// Counted the way YouTube does: commas between tags, quotes around tags
+/// with spaces.
+pub const TAGS_MAX_CHARS: usize = 500;
+/// `videos.list` takes at most this many ids per call.
+const FETCH_BATCH: usize = 50;
+/// Overall bound on one stretch of the uploads listing.
+const LIST_DEADLINE: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MyVideo {
+  pub video_id: String,
+  pub title: String,
+  pub published_at: Option<String>,
+}
+
+/// One stretch of the channel's uploads from [`list_my_videos`].
+#[derive(Serialize, Debug, Clone)]
+pub struct VideoPage {
+  pub videos: Vec<MyVideo>,
+  /// Pass back as `cursor` for the rest; absent after the oldest upload.
+  pub next_cursor: Option<String>,
+  pub total: Option<u64>,
+}
+
+/// Resources for `ids` by id; ids the API doesn't return are missing.
+pub(crate) async fn fetch(dir: &Path, ids: &[String], part: &str) -> Result<HashMap<String, Value>, Error> {
+  fetch_as(dir, ids, part, VIDEO_WRITE_SCOPES).await
+}
+
+/// [`fetch`] for reads that need only `scopes`, e.g. other creators' videos.
+pub(crate) async fn fetch_as(dir: &Path, ids: &[String], part: &str, scopes: &'static [&'static str]) -> Result<HashMap<String, Value>, Error> {
+  let mut found = HashMap::new();
+  for batch in ids.chunks(FETCH_BATCH) {
+    let call = ApiCall::get("/youtube/v3/videos").query("part", part).query("id", batch.join(",")).scopes(scopes);
+    let resp = api::execute(dir, call).await?;
+    for item in resp["items"].as_array().into_iter().flatten() {
+      if let Some(id) = item["id"].as_str() {
+        found.insert(id.to_string(), item.clone());
+      }
+    }
+  }
+  Ok(found)
+}
+
+/// The channel's uploads, newest first, `paging.page_budget` pages at a time.
+pub async fn list_my_videos(dir: &Path, paging: &PageRequest) -> Result<VideoPage, Error> {
+  let channel = channel::list_channels(dir).await?.into_iter().next().ok_or_else(|| Error::Validation("Brak kanału".into()))?;
+  let uploads = channel.uploads_playlist_id.ok_or_else(|| Error::Validation("Kanał nie ma playlisty przesłanych filmów".into()))?;
+  let call = ApiCall::get("/youtube/v3/playlistItems").query("part", "snippet,contentDetails").query("playlistId", uploads);
+  let page = api::paginate_page(dir, call, paging, usize::MAX, LIST_DEADLINE, |_, _| {}).await?;
+  let videos = page
+    .items
+    .iter()
+    .filter_map(|i| {
+      Some(MyVideo {
+        video_id: i["contentDetails"]["videoId"].as_str()?.to_string(),
+        title: i["snippet"]["title"].as_str().unwrap_or_default().to_string(),
+        published_at: i["contentDetails"]["videoPublishedAt"].as_str().map(str::to_string),
+      })
+    })
+    .collect();
+  Ok(VideoPage { videos, next_cursor: page.next_cursor, total: page.total })
+}
+
+/// The writable part of a fetched snippet; title and category are required
+/// by `videos.update`.
+pub(crate) fn snippet_for_update(snippet: &Value) -> Value {
+  let mut updated = json!({ "title": snippet["title"], "description": snippet["description"], "categoryId": snippet["categoryId"] });
+  for key in ["tags", "defaultLanguage", "defaultAudioLanguage"] {
+    if let Some(v) = snippet.get(key).filter(|v| !v.is_null()) {
+      updated[key] = v.clone();
+    }
+  }
+  updated
+}
+
+/// The writable part of a fetched status.
+pub(crate) fn status_for_update(status: &Value) -> Value {
+  let mut updated = json!({ "privacyStatus": status["privacyStatus"] });
+  for key in ["publishAt", "embeddable", "license", "publicStatsViewable", "selfDeclaredMadeForKids"] {
+    if let Some(v) = status.get(key).filter(|v| !v.is_null()) {
+      updated[key] = v.clone();
+    }
+  }
+  updated
+}
+
+/// Problems with a title, description and tags against YouTube's limits.
+pub fn text_problems(title: Option<&str>, description: Option<&str>, tags: Option<&[String]>) -> Vec<String> {
+  let mut problems = Vec::new();
+  if let Some(t) = title {
+    if t.trim().is_empty() {
+      problems.push("Tytuł nie może być pusty".to_string());
+    } else if t.chars().count() > TITLE_MAX_CHARS {
+      problems.push(format!("Tytuł ma więcej niż {} znaków", TITLE_MAX_CHARS));
+    }
+  }
+  if description.is_some_and(|d| d.chars().count() > DESCRIPTION_MAX_CHARS) {
+    problems.push(format!("Opis ma więcej niż {} znaków", DESCRIPTION_MAX_CHARS));
+  }
+  for (field, text) in [("Tytuł", title), ("Opis", description)] {
+    if text.is_some_and(|t| t.contains(['<', '>'])) {
+      problems.push(format!("{} nie może zawierać znaków < ani >", field));
+    }
+  }
+  if let Some(tags) = tags {
+    let len: usize = tags.iter().map(|t| t.chars().count() + if t.contains(' ') { 2 } else { 0 }).sum::<usize>() + tags.len().saturating_sub(1);
+    if len > TAGS_MAX_CHARS {
+      problems.push(format!("Tagi mają razem {} znaków (limit {})", len, TAGS_MAX_CHARS));
+    }
+    if tags.iter().any(|t| t.contains(['<', '>', ','])) {
+      problems.push("Tagi nie mogą zawierać znaków <, > ani przecinków".to_string());
+    }
+  }
+  problems
+}
+
+/// Writes `body` (with `id` and the parts named in `part`) back, keeping a
+/// snapshot of the video as it was first.
+pub(crate) async fn update(dir: &Path, part: &str, body: Value) -> Result<Value, Error> {
+  update_with_links(dir, part, body, Vec::new()).await
+}
+
+/// [`update`] of a description whose video `links` were checked, for the
+/// dry-run plan.
+pub(crate) async fn update_with_links(dir: &Path, part: &str, body: Value, links: Vec<VideoLink>) -> Result<Value, Error> {
+  api::ensure_writable(dir)?;
+  if let Some(id) = body["id"].as_str() {
+    snapshots::take(dir, id, SnapshotReason::Update).await?;
+  }
+  let call = ApiCall::put("/youtube/v3/videos").query("part", part).json(body).video_links(links).scopes(VIDEO_WRITE_SCOPES);
+  api::execute(dir, call).await
+}
@@ -0,0 +1,65 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{adc, read_tokens_from_dir, scopes};
+
+#[tokio::test]
+async fn adc_user_credentials_replace_the_consent_flow() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().join("app");
+  fs::create_dir_all(&dir).unwrap();
+  let gcloud = tmp.path().join("gcloud");
+  fs::create_dir_all(&gcloud).unwrap();
+  let adc_path = gcloud.join("application_default_credentials.json");
+  std::env::set_var("GOOGLE_APPLICATION_CREDENTIALS", &adc_path);
+  assert_eq!(adc::locate(), Some(adc_path.clone()));
+
+  let previous = r#"{"client_id":"old","client_secret":"old-secret","channel_id":"UC1"}"#;
+  fs::write(dir.join("oauth_config.json"), previous).unwrap();
+
+  // A service account can't act for a YouTube channel.
+  fs::write(&adc_path, json!({ "type": "service_account", "client_id": "1", "private_key": "k" }).to_string()).unwrap();
+  let err = adc::import(&dir, None).await.unwrap_err();
+  assert!(err.to_string().contains("authorized_user"), "{}", err);
+
+  // Plain `gcloud auth application-default login`: cloud-platform only.
+  let adc = json!({ "type": "authorized_user", "client_id": "gcloud-id", "client_secret": "gcloud-secret", "refresh_token": "1//adc" });
+  fs::write(&adc_path, adc.to_string()).unwrap();
+  let refresh = |scope: &str| {
+    json!({ "access_token": "ya29.fresh", "expires_in": 3599, "scope": scope, "token_type": "Bearer" }).to_string()
+  };
+  let cloud_only = server
+    .mock("POST", "/token")
+    .match_body(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("refresh_token".into(), "1//adc".into()),
+      Matcher::UrlEncoded("client_id".into(), "gcloud-id".into()),
+    ]))
+    .with_body(refresh("https://www.googleapis.com/auth/cloud-platform openid"))
+    .expect(1)
+    .create_async()
+    .await;
+  let err = adc::import(&dir, None).await.unwrap_err();
+  assert!(err.to_string().contains(scopes::YOUTUBE_READONLY), "{}", err);
+  cloud_only.assert_async().await;
+  // Refused: the earlier setup is back untouched.
+  assert_eq!(fs::read_to_string(dir.join("oauth_config.json")).unwrap(), previous);
+  assert!(read_tokens_from_dir(&dir).is_none());
+  cloud_only.remove_async().await;
+
+  server
+    .mock("POST", "/token")
+    .with_body(refresh(&format!("{} https://www.googleapis.com/auth/cloud-platform", scopes::YOUTUBE)))
+    .create_async()
+    .await;
+  let status = adc::import(&dir, Some(&adc_path)).await.unwrap();
+  assert!(status.authenticated && status.missing_scopes.is_empty());
+  let cfg: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("oauth_config.json")).unwrap()).unwrap();
+  assert_eq!((&cfg["client_id"], &cfg["client_secret"], &cfg["channel_id"]), (&json!("gcloud-id"), &json!("gcloud-secret"), &json!("UC1")));
+  let t = read_tokens_from_dir(&dir).unwrap();
+  assert_eq!((t.access_token.as_str(), t.refresh_token.as_str(), t.client_id.as_deref()), ("ya29.fresh", "1//adc", Some("gcloud-id")));
+
+  let missing = tmp.path().join("missing.json");
+  assert!(adc::import(&dir, Some(&missing)).await.unwrap_err().to_string().contains("application-default login"));
+}
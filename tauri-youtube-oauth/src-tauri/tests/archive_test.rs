@@ -0,0 +1,118 @@
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{archive, quota, scopes, ArchiveFilter};
+
+fn write_tokens(dir: &Path, scope: &str) {
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": scope });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+fn video(base: &str, id: &str, title: &str, published: &str) -> Value {
+  json!({
+    "id": id,
+    "etag": format!("etag-{}", title),
+    "snippet": {
+      "title": title,
+      "publishedAt": published,
+      "thumbnails": {
+        "default": { "url": format!("{}/vi/{}/default.jpg", base, id), "width": 120, "height": 90 },
+        "high": { "url": format!("{}/vi/{}/hqdefault.jpg", base, id), "width": 480, "height": 360 },
+      },
+    },
+    "status": { "privacyStatus": "unlisted" },
+    "statistics": { "viewCount": "7" },
+  })
+}
+
+async fn videos_mock(server: &mut mockito::ServerGuard, videos: &[Value]) -> mockito::Mock {
+  server.mock("GET", "/youtube/v3/videos").match_query(Matcher::Any).with_body(json!({ "items": videos }).to_string()).create_async().await
+}
+
+/// `captions.list` for `video`, expected once over the whole test.
+async fn tracks_mock(server: &mut mockito::ServerGuard, video: &str, items: Vec<Value>) -> mockito::Mock {
+  server
+    .mock("GET", "/youtube/v3/captions")
+    .match_query(Matcher::UrlEncoded("videoId".into(), video.into()))
+    .with_body(json!({ "items": items }).to_string())
+    .expect(1)
+    .create_async()
+    .await
+}
+
+#[tokio::test]
+async fn archives_matching_uploads_once_and_again_after_a_change() {
+  let mut server = mockito::Server::new_async().await;
+  let base = server.url();
+  std::env::set_var("YOUTUBE_API_URL", &base);
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", base));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let out = dir.join("archiwum");
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  write_tokens(dir, scopes::YOUTUBE_FORCE_SSL);
+
+  let channel = json!({ "items": [{ "id": "UC1", "snippet": { "title": "Kanał" }, "contentDetails": { "relatedPlaylists": { "uploads": "UU1" } } }] });
+  server.mock("GET", "/youtube/v3/channels").match_query(Matcher::Any).with_body(channel.to_string()).create_async().await;
+  let item = |id: &str, at: &str| json!({ "snippet": { "title": id }, "contentDetails": { "videoId": id, "videoPublishedAt": at }, "status": { "privacyStatus": "unlisted" } });
+  let items = [item("v1", "2020-05-01T10:00:00Z"), item("v2", "2024-05-01T10:00:00Z"), item("v3", "2021-12-31T20:00:00Z"), item("v1", "2020-05-01T10:00:00Z")];
+  server.mock("GET", "/youtube/v3/playlistItems").match_query(Matcher::Any).with_body(json!({ "items": items }).to_string()).create_async().await;
+  let old = [video(&base, "v1", "Pierwszy", "2020-05-01T10:00:00Z"), video(&base, "v3", "Trzeci", "2021-12-31T20:00:00Z")];
+  let listed = videos_mock(&mut server, &old).await;
+  for id in ["v1", "v3"] {
+    server.mock("GET", format!("/vi/{}/default.jpg", id).as_str()).with_body(format!("jpg-{}", id)).create_async().await;
+  }
+  server.mock("GET", "/vi/v1/hqdefault.jpg").with_body("hq").create_async().await;
+  server.mock("GET", "/vi/v3/hqdefault.jpg").with_status(404).create_async().await;
+  let track = |id: &str, lang: &str| json!({ "id": id, "snippet": { "language": lang, "name": "" } });
+  let v1_tracks = tracks_mock(&mut server, "v1", vec![track("c1", "pl")]).await;
+  let v3_tracks = tracks_mock(&mut server, "v3", vec![track("c2", "en-US"), track("c3", "de")]).await;
+  let srt = "1\n00:00:00,000 --> 00:00:01,000\nCześć\n";
+  for id in ["c1", "c2"] {
+    server.mock("GET", format!("/youtube/v3/captions/{}", id).as_str()).match_query(Matcher::Any).with_body(srt).create_async().await;
+  }
+  let refused = json!({ "error": { "code": 403, "message": "The permissions associated with the request are not sufficient to download the caption track.", "errors": [{ "reason": "forbidden" }] } });
+  server.mock("GET", "/youtube/v3/captions/c3").match_query(Matcher::Any).with_status(403).with_body(refused.to_string()).create_async().await;
+
+  let filter = ArchiveFilter { to: Some("2021-12-31".into()), ..Default::default() };
+  let first = archive::archive_channel(dir, &out, &filter).await.unwrap();
+  assert_eq!((first.matched, first.archived, first.skipped), (2, 2, 0));
+  v1_tracks.assert_async().await;
+  v3_tracks.assert_async().await;
+  // channels, playlistItems, videos; two captions.list at 50 and three downloads at 200.
+  assert_eq!(quota::status(dir).used, 3 + 2 * 50 + 3 * 200);
+
+  let metadata: Value = serde_json::from_str(&fs::read_to_string(out.join("v1/metadata.json")).unwrap()).unwrap();
+  assert_eq!(metadata["snippet"]["title"], "Pierwszy");
+  assert_eq!(fs::read_to_string(out.join("v1/thumbnails/default.jpg")).unwrap(), "jpg-v1");
+  assert_eq!(fs::read_to_string(out.join("v1/thumbnails/high.jpg")).unwrap(), "hq");
+  assert_eq!(fs::read_to_string(out.join("v1/captions/pl_c1.srt")).unwrap(), srt);
+  let index = archive::read_index(&out).unwrap();
+  assert_eq!(index.videos.keys().collect::<Vec<_>>(), ["v1", "v3"]);
+  let v3 = &index.videos["v3"];
+  assert_eq!(v3.thumbnails.keys().collect::<Vec<_>>(), ["default"]);
+  assert!(v3.problems[0].contains("high"), "{:?}", v3.problems);
+  assert_eq!(v3.captions[0].file.as_deref(), Some("captions/en-US_c2.srt"));
+  assert!(v3.captions[1].file.is_none() && v3.captions[1].error.as_deref().is_some_and(|e| e.contains("caption track")), "{:?}", v3.captions);
+
+  // Views changed, nothing else: skipped without downloading anything again.
+  listed.remove_async().await;
+  let mut viewed = old.clone();
+  viewed[0]["statistics"]["viewCount"] = json!("9000");
+  let listed = videos_mock(&mut server, &viewed).await;
+  let second = archive::archive_channel(dir, &out, &filter).await.unwrap();
+  assert_eq!((second.matched, second.archived, second.skipped), (2, 0, 2));
+  assert_eq!(quota::status(dir).used, 703 + 3);
+
+  // A new title is archived again; without force-ssl the captions are noted, not fetched.
+  listed.remove_async().await;
+  videos_mock(&mut server, &[video(&base, "v3", "Trzeci, poprawiony", "2021-12-31T20:00:00Z")]).await;
+  write_tokens(dir, scopes::YOUTUBE_READONLY);
+  let redone = archive::archive_video(dir, "v3", &out).await.unwrap();
+  assert!(!redone.skipped);
+  assert!(redone.video.captions.is_empty() && redone.video.captions_skipped.is_some());
+  assert_eq!(archive::read_index(&out).unwrap().videos["v3"].title, "Trzeci, poprawiony");
+  assert!(archive::archive_video(dir, "v3", &out).await.unwrap().skipped);
+  assert!(archive::archive_video(dir, "../v3", &out).await.is_err());
+}
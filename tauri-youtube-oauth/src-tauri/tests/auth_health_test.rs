@@ -0,0 +1,122 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{
+  fs,
+  path::Path,
+  sync::{Arc, Mutex},
+};
+use tauri_youtube_oauth::{auth, auth_health, notifications, HealthState, NoticeKind};
+
+fn now() -> u64 { std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() }
+
+fn seed(dir: &Path, access_token: &str, created_at: u64) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "language": "en", "desktop_notifications": true });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let tokens = json!({
+    "access_token": access_token,
+    "refresh_token": "1//refresh",
+    "expires_in": 3600,
+    "created_at": created_at,
+    "scope": "https://www.googleapis.com/auth/youtube.upload",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn turned_off_nothing_is_checked() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  seed(dir, "ya29.off", now());
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "auth_health_check": false });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  assert_eq!(auth_health::check_if_due(dir).await, None);
+  assert!(!dir.join("auth_health.json").exists());
+  assert_eq!(auth::status(dir).health, None);
+}
+
+#[tokio::test]
+async fn fresh_tokens_use_tokeninfo_expired_ones_refresh_and_failures_are_told_apart() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("OAUTH_TOKENINFO_URL", format!("{}/tokeninfo", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+
+  // A fresh access token is checked without spending a refresh.
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  seed(dir, "ya29.fresh", now());
+  let tokeninfo = server
+    .mock("GET", "/tokeninfo")
+    .match_query(Matcher::UrlEncoded("access_token".into(), "ya29.fresh".into()))
+    .with_body(r#"{"expires_in":"3500"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let health = auth_health::check_if_due(dir).await.unwrap();
+  assert_eq!(health.state, HealthState::Healthy);
+  assert_eq!(health.next_check_at, Some(health.checked_at + 24 * 60 * 60));
+  assert_eq!(health.tooltip, "ytlite: signed in");
+  // Persisted: not due again, also after a restart reads the file.
+  assert_eq!(auth_health::check_if_due(dir).await, None);
+  assert_eq!(auth::status(dir).health.unwrap().checked_at, health.checked_at);
+  tokeninfo.assert_async().await;
+  tokeninfo.remove_async().await;
+
+  // A day later it's due again; an expired access token is refreshed.
+  let stale = json!({ "state": "healthy", "checked_at": now() - 25 * 60 * 60 });
+  fs::write(dir.join("auth_health.json"), stale.to_string()).unwrap();
+  seed(dir, "ya29.expired", 1);
+  let refreshed = server
+    .mock("POST", "/token")
+    .match_body(Matcher::UrlEncoded("refresh_token".into(), "1//refresh".into()))
+    .with_body(r#"{"access_token":"ya29.new","expires_in":3600,"token_type":"Bearer"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let health = auth_health::check_if_due(dir).await.unwrap();
+  assert_eq!(health.state, HealthState::Healthy);
+  assert!(health.checked_at >= now() - 5);
+  assert!(fs::read_to_string(dir.join("tokens.json")).unwrap().contains("ya29.new"));
+  refreshed.assert_async().await;
+  refreshed.remove_async().await;
+
+  // Google refusing the refresh token: dead, notified, and not checked again.
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  seed(dir, "ya29.expired", 1);
+  let shown: Arc<Mutex<Vec<NoticeKind>>> = Arc::default();
+  let sink = shown.clone();
+  notifications::attach(dir, move |n| {
+    sink.lock().unwrap().push(n.kind);
+    Ok(())
+  });
+  let revoked = server.mock("POST", "/token").with_status(400).with_body(r#"{"error":"invalid_grant"}"#).expect(1).create_async().await;
+  let health = auth_health::check(dir).await.unwrap();
+  assert_eq!((health.state, health.code.as_deref(), health.next_check_at), (HealthState::Dead, Some("REAUTH_REQUIRED"), None));
+  assert_eq!(health.tooltip, "ytlite: sign in again");
+  assert_eq!(*shown.lock().unwrap(), [NoticeKind::ReauthRequired]);
+  assert_eq!(auth_health::check_if_due(dir).await, None);
+  assert_eq!(auth::status(dir).health.unwrap().state, HealthState::Dead);
+  revoked.assert_async().await;
+  revoked.remove_async().await;
+
+  // A refresh in backoff is skipped and leaves the check due.
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  seed(dir, "ya29.expired", 1);
+  let backoff = json!({ "failures": 3, "first_failure_at": now(), "backoff_until": now() + 600 });
+  fs::write(dir.join("refresh_state.json"), backoff.to_string()).unwrap();
+  let token = server.mock("POST", "/token").expect(0).create_async().await;
+  assert_eq!(auth_health::check_if_due(dir).await, None);
+  assert!(!dir.join("auth_health.json").exists());
+  token.assert_async().await;
+
+  // Tokeninfo answering 5xx says nothing about the login itself.
+  seed(dir, "ya29.fresh", now());
+  let down = server.mock("GET", "/tokeninfo").match_query(Matcher::Any).with_status(503).expect(1).create_async().await;
+  let health = auth_health::check_if_due(dir).await.unwrap();
+  assert_eq!(health.state, HealthState::Degraded);
+  assert_eq!(health.next_check_at, Some(health.checked_at + 60 * 60));
+  assert!(health.error.is_some());
+  down.assert_async().await;
+}
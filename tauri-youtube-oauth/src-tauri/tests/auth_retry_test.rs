@@ -0,0 +1,66 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{capture, channel, error::Error, read_tokens_from_dir};
+
+/// Tokens that look valid locally, so only the API's 401 can trigger a refresh.
+fn seed(dir: &std::path::Path, access_token: &str) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": access_token, "refresh_token": "r", "expires_in": 3600, "created_at": now });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+fn channels(server: &mut mockito::ServerGuard, token: &str) -> mockito::Mock {
+  server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::Any)
+    .match_header("authorization", format!("Bearer {}", token).as_str())
+}
+
+const UNAUTHORIZED: &str = r#"{"error":{"code":401,"message":"Invalid Credentials","errors":[{"reason":"authError"}]}}"#;
+
+#[tokio::test]
+async fn refreshes_once_on_401_and_gives_up_after_second() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let revoked = channels(&mut server, "revoked").with_status(401).with_body(UNAUTHORIZED).expect(1).create_async().await;
+  let ok = channels(&mut server, "fresh").with_body(include_str!("fixtures/channels_list_mine.json")).expect(1).create_async().await;
+  let refresh = server
+    .mock("POST", "/token")
+    .with_body(r#"{"access_token":"fresh","expires_in":3600}"#)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  capture::set_enabled(tmp.path(), true);
+  seed(tmp.path(), "revoked");
+  assert_eq!(channel::list_channels(tmp.path()).await.unwrap().len(), 2);
+  revoked.assert_async().await;
+  ok.assert_async().await;
+  refresh.assert_async().await;
+  assert_eq!(read_tokens_from_dir(tmp.path()).unwrap().access_token, "fresh");
+  let attempts: Vec<_> =
+    capture::entries(tmp.path()).into_iter().filter(|e| e.url.contains("/channels")).map(|e| (e.status, e.attempt)).collect();
+  assert_eq!(attempts, [(Some(401), 1), (Some(200), 2)]);
+
+  // The refreshed token is rejected too: one refresh, one replay, then stop.
+  let rejected = channels(&mut server, "dead").with_status(401).with_body(UNAUTHORIZED).expect(1).create_async().await;
+  let rejected_again = channels(&mut server, "also-dead").with_status(401).with_body(UNAUTHORIZED).expect(1).create_async().await;
+  refresh.remove_async().await;
+  let refresh = server
+    .mock("POST", "/token")
+    .with_body(r#"{"access_token":"also-dead","expires_in":3600}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path(), "dead");
+  let err = channel::list_channels(tmp.path()).await.unwrap_err();
+  assert!(matches!(err, Error::Auth(_)), "{err:?}");
+  assert_eq!(err.code(), "REAUTH_REQUIRED");
+  rejected.assert_async().await;
+  rejected_again.assert_async().await;
+  refresh.assert_async().await;
+}
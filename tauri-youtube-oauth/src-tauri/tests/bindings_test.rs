@@ -0,0 +1,41 @@
+#![cfg(feature = "bindings")]
+
+use std::{fs, path::Path};
+use tauri_youtube_oauth::bindings;
+
+fn generated() -> String {
+  bindings::generate(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src")).unwrap()
+}
+
+#[test]
+fn committed_bindings_are_up_to_date() {
+  let committed = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("../src/bindings.d.ts")).unwrap();
+  assert!(committed == generated(), "src/bindings.d.ts is stale; run `cargo run --features bindings --bin generate-bindings`");
+}
+
+#[test]
+fn every_registered_command_is_declared() {
+  let ts = generated();
+  let main = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/main.rs")).unwrap();
+  let handler = main.split("generate_handler![").nth(1).unwrap().split(']').next().unwrap();
+  let names: Vec<&str> = handler.split(',').map(|n| n.trim().rsplit("::").next().unwrap()).filter(|n| !n.is_empty()).collect();
+  assert!(names.len() > 50);
+  for name in names {
+    assert!(ts.contains(&format!("\n  {}: {{ args: ", name)), "{} missing from Commands", name);
+  }
+}
+
+#[test]
+fn serde_attributes_shape_the_declarations() {
+  let ts = generated();
+  let tokens = ts.split("export interface Tokens {").nth(1).unwrap().split("\n}").next().unwrap();
+  assert!(tokens.contains("  created_at: number;") && tokens.contains("  client_id?: string | null;"));
+  // Tauri takes arguments in camelCase; payloads keep the Rust field names.
+  assert!(ts.contains("  queue_add: { args: { filePath: string; metadata?: VideoMetadata | null }; result: QueueEntry };"));
+  assert!(ts.contains("export type Privacy =\n  | \"public\"\n  | \"unlisted\"\n  | \"private\";"));
+  assert!(ts.contains("  | { type: \"webhook\"; url: string;"));
+  assert!(ts.contains("  | { status: \"estimate\" } & InventoryEstimate\n"));
+  assert!(ts.contains("  \"queue-changed\": QueueEntry[];"));
+  assert!(ts.contains("export interface LoginResult extends Tokens {\n  missing_scopes: string[];\n}"));
+  assert!(ts.contains("  code: CommandErrorCode;") && ts.contains("  | \"CONSENT_FAILED\""));
+}
@@ -0,0 +1,81 @@
+use std::{fs, time::Duration};
+use tauri_youtube_oauth::{browser, AppConfig, Sandbox};
+
+#[cfg(unix)]
+#[test]
+fn preferred_browser_gets_the_url_as_one_argument_and_sandboxes_fall_back_to_the_link() {
+  use std::os::unix::fs::PermissionsExt;
+  std::env::remove_var("FLATPAK_ID");
+  std::env::remove_var("SNAP");
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let seen = dir.join("seen.txt");
+  let script = dir.join("browser.sh");
+  fs::write(&script, format!("#!/bin/sh\nprintf '%s\\n%s' \"$#\" \"$1\" > '{}'\n", seen.display())).unwrap();
+  fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+  let cfg = AppConfig { preferred_browser: Some(script.to_string_lossy().into_owned()), ..Default::default() };
+
+  // Shell metacharacters stay part of the URL.
+  let url = "https://accounts.google.com/o/oauth2/v2/auth?a=1&b=$(touch x);c d";
+  let launch = browser::open(dir, &cfg, url);
+  assert!(launch.opened && launch.error.is_none(), "{:?}", launch);
+  assert_eq!((launch.program.as_str(), launch.sandbox), (script.to_str().unwrap(), None));
+  let mut got = String::new();
+  for _ in 0..50 {
+    got = fs::read_to_string(&seen).unwrap_or_default();
+    if got.contains('\n') && got.ends_with('d') {
+      break;
+    }
+    std::thread::sleep(Duration::from_millis(50));
+  }
+  assert_eq!(got, format!("1\n{}", url));
+  assert!(!dir.join("x").exists());
+
+  let missing = AppConfig { preferred_browser: Some("/nonexistent/firefox".into()), ..Default::default() };
+  let launch = browser::open(dir, &missing, url);
+  assert!(!launch.opened && launch.error.as_deref().unwrap().contains("/nonexistent/firefox"));
+  assert_eq!(launch.url, url);
+
+  // A host browser can't be started from inside Flatpak: not even tried.
+  fs::remove_file(&seen).unwrap();
+  std::env::set_var("FLATPAK_ID", "com.example.ytlite");
+  assert_eq!(browser::sandbox(), Some(Sandbox::Flatpak));
+  let launch = browser::open(dir, &cfg, url);
+  assert!(!launch.opened && launch.error.is_some());
+  assert_eq!(launch.sandbox, Some(Sandbox::Flatpak));
+  std::thread::sleep(Duration::from_millis(200));
+  assert!(!seen.exists());
+  std::env::remove_var("FLATPAK_ID");
+}
+
+#[test]
+fn every_platform_gets_the_whole_consent_url_as_one_argument() {
+  use browser::Platform;
+  // `&` between the parameters and percent-escapes in them, as `consent_url` builds it.
+  let url = concat!(
+    "https://accounts.google.com/o/oauth2/v2/auth?client_id=id&response_type=code",
+    "&redirect_uri=http%3A%2F%2F127.0.0.1%3A8085%2Fcallback&access_type=offline&prompt=consent",
+    "&scope=https%3A%2F%2Fwww.googleapis.com%2Fauth%2Fyoutube.upload%20https%3A%2F%2Fwww.googleapis.com%2Fauth%2Fyoutube.readonly"
+  );
+  for (platform, program) in [(Platform::Windows, "explorer"), (Platform::Macos, "open"), (Platform::Linux, "xdg-open")] {
+    assert_eq!(browser::open_command(platform, None, url), (program.to_string(), vec![url.to_string()]));
+    let mut seen = None;
+    browser::open_url_with(url, None, platform, |p, args| {
+      seen = Some((p.to_string(), args.to_vec()));
+      Ok(())
+    })
+    .unwrap();
+    assert_eq!(seen, Some((program.to_string(), vec![url.to_string()])), "{:?}", platform);
+  }
+  assert_eq!(browser::open_command(Platform::Windows, Some("firefox"), url), ("firefox".to_string(), vec![url.to_string()]));
+
+  // The runner's failure comes back instead of vanishing.
+  let err = browser::open_url_with(url, Some("firefox"), Platform::Linux, |_, _| Err(std::io::ErrorKind::NotFound.into())).unwrap_err();
+  assert_eq!((err.code(), err.to_string().contains("firefox")), ("OPEN_FAILED", true));
+
+  // Nothing but https is started.
+  for bad in ["http://accounts.google.com/o/oauth2/v2/auth?a=1", "file:///etc/passwd", "javascript:alert(1)", "-n https://x", "calc.exe"] {
+    let err = browser::open_url_with(bad, None, Platform::Windows, |_, _| panic!("started for {}", bad)).unwrap_err();
+    assert_eq!(err.code(), "VALIDATION_FAILED", "{}", bad);
+  }
+}
@@ -0,0 +1,99 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{privacy, BulkPrivacyResult, ChangeAction, Privacy, PrivacyOptions, RowStatus};
+
+fn status_update(server: &mut mockito::Server, body: serde_json::Value) -> mockito::Mock {
+  server
+    .mock("PUT", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("part".into(), "status".into()))
+    .match_body(Matcher::Json(body))
+    .with_body(r#"{"id":"x"}"#)
+}
+
+#[tokio::test]
+async fn privacy_changes_need_a_matching_confirmation() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let video = |id: &str, status: serde_json::Value| json!({ "id": id, "snippet": { "title": format!("Film {}", id) }, "status": status });
+  server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .with_body(
+      json!({ "items": [
+        video("v1", json!({ "privacyStatus": "unlisted", "embeddable": true })),
+        video("v2", json!({ "privacyStatus": "public" })),
+        video("v3", json!({ "privacyStatus": "private", "publishAt": "2099-01-01T00:00:00Z" })),
+      ]})
+      .to_string(),
+    )
+    .create_async()
+    .await;
+  let v1 = status_update(&mut server, json!({ "id": "v1", "status": { "privacyStatus": "public", "embeddable": true } }))
+    .expect(2)
+    .create_async()
+    .await;
+  // The schedule goes with the change.
+  let v3 = status_update(&mut server, json!({ "id": "v3", "status": { "privacyStatus": "public" } })).expect(1).create_async().await;
+
+  let ids: Vec<String> = ["v1", "v2", "v3", "v4", "v1"].iter().map(|s| s.to_string()).collect();
+  let options = PrivacyOptions { cancel_schedules: false, min_interval_ms: Some(0) };
+  let plan = match privacy::bulk_set_privacy(dir, &ids, Privacy::Public, None, &options).await.unwrap() {
+    BulkPrivacyResult::Plan(plan) => plan,
+    other => panic!("expected a plan, got {:?}", other),
+  };
+  let actions: Vec<(&str, ChangeAction)> = plan.changes.iter().map(|c| (c.video_id.as_str(), c.action)).collect();
+  assert_eq!(
+    actions,
+    [("v1", ChangeAction::Change), ("v2", ChangeAction::Unchanged), ("v3", ChangeAction::NeedsScheduleOverride), ("v4", ChangeAction::NotFound)]
+  );
+  assert_eq!((plan.changes[0].current, plan.changes[0].title.as_deref()), (Some(Privacy::Unlisted), Some("Film v1")));
+  assert!(plan.changes[2].cancels_schedule && plan.expires_at > now);
+
+  // A token only confirms the request it was issued for, and only once.
+  let err = privacy::bulk_set_privacy(dir, &ids, Privacy::Unlisted, Some(&plan.confirm_token), &options).await.unwrap_err();
+  assert!(err.to_string().contains("innej zmiany"), "{}", err);
+  let err = privacy::bulk_set_privacy(dir, &ids, Privacy::Public, Some(&plan.confirm_token), &options).await.unwrap_err();
+  assert!(err.to_string().contains("nieznany"), "{}", err);
+
+  let BulkPrivacyResult::Plan(plan) = privacy::bulk_set_privacy(dir, &ids, Privacy::Public, None, &options).await.unwrap() else {
+    panic!("expected a plan")
+  };
+  let BulkPrivacyResult::Applied(report) =
+    privacy::bulk_set_privacy(dir, &ids, Privacy::Public, Some(&plan.confirm_token), &options).await.unwrap()
+  else {
+    panic!("expected a report")
+  };
+  let statuses: Vec<RowStatus> = report.rows.iter().map(|r| r.status).collect();
+  assert_eq!(statuses, [RowStatus::Succeeded, RowStatus::Unchanged, RowStatus::Failed, RowStatus::Failed]);
+  assert!(report.rows[2].error.as_deref().unwrap().contains("cancel_schedules"));
+  assert_eq!((report.succeeded, report.unchanged, report.failed), (1, 1, 2));
+
+  // With the extra flag the scheduled video goes public too.
+  let options = PrivacyOptions { cancel_schedules: true, ..options };
+  let BulkPrivacyResult::Plan(plan) = privacy::bulk_set_privacy(dir, &ids[..3], Privacy::Public, None, &options).await.unwrap() else {
+    panic!("expected a plan")
+  };
+  assert_eq!(plan.changes[2].action, ChangeAction::Change);
+  let BulkPrivacyResult::Applied(report) =
+    privacy::bulk_set_privacy(dir, &ids[..3], Privacy::Public, Some(&plan.confirm_token), &options).await.unwrap()
+  else {
+    panic!("expected a report")
+  };
+  assert_eq!((report.succeeded, report.unchanged, report.failed), (2, 1, 0));
+  v1.assert_async().await;
+  v3.assert_async().await;
+}
@@ -0,0 +1,60 @@
+use std::{fs, sync::Arc};
+use tauri_youtube_oauth::{
+  callback::{routes, CallbackState},
+  config, passphrase, AppConfig,
+};
+
+async fn callback(dir: &std::path::Path) -> warp::http::Response<warp::hyper::body::Bytes> {
+  let filter = routes(dir.to_path_buf(), Arc::new(CallbackState::new("st".into())));
+  warp::test::request().path("/callback?code=c&state=st").remote_addr("127.0.0.1:50000".parse().unwrap()).reply(&filter).await
+}
+
+#[test]
+fn redirect_must_be_http_or_https() {
+  let with = |target: &str| AppConfig {
+    client_id: "id".into(),
+    client_secret: "secret".into(),
+    callback_success_redirect: Some(target.into()),
+    ..Default::default()
+  };
+  assert!(config::validate(&with("https://example.com/done?from=app")).is_ok());
+  assert!(config::validate(&with("javascript:alert(1)")).is_err());
+  assert!(config::validate(&with("/done")).is_err());
+}
+
+#[test]
+fn a_config_with_a_bad_redirect_is_not_saved_back() {
+  let tmp = tempfile::tempdir().unwrap();
+  let cfg = r#"{"client_id":"id","client_secret":"secret","callback_success_redirect":"javascript:alert(1)"}"#;
+  fs::write(tmp.path().join("oauth_config.json"), cfg).unwrap();
+  assert!(passphrase::set(tmp.path(), "correct horse", None).is_err());
+  assert_eq!(fs::read_to_string(tmp.path().join("oauth_config.json")).unwrap(), cfg);
+}
+
+#[tokio::test]
+async fn successful_login_redirects_with_the_profile_and_failures_do_not() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().join("profiles").join("work");
+  fs::create_dir_all(&dir).unwrap();
+  let cfg = r#"{"client_id":"id","client_secret":"secret","callback_success_redirect":"https://example.com/done?from=app"}"#;
+  fs::write(dir.join("oauth_config.json"), cfg).unwrap();
+
+  let failing = server.mock("POST", "/token").with_status(400).with_body(r#"{"error":"invalid_grant"}"#).expect(1).create_async().await;
+  let resp = callback(&dir).await;
+  assert_eq!(resp.status().as_u16(), 502);
+  assert!(resp.headers().get("location").is_none());
+  failing.assert_async().await;
+  failing.remove_async().await;
+
+  server.mock("POST", "/token").with_body(r#"{"access_token":"a","refresh_token":"r","expires_in":3600}"#).create_async().await;
+  let resp = callback(&dir).await;
+  assert_eq!(resp.status().as_u16(), 302);
+  assert_eq!(resp.headers()["location"], "https://example.com/done?from=app&profile=work");
+  assert_eq!(resp.headers()["cache-control"], "no-store");
+
+  // Without the setting the built-in page is served.
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  assert_eq!(callback(&dir).await.status().as_u16(), 200);
+}
@@ -0,0 +1,83 @@
+use std::{fs, net::SocketAddr, sync::Arc};
+use tauri_youtube_oauth::callback::{routes, CallbackState};
+
+fn loopback() -> SocketAddr {
+  "127.0.0.1:50000".parse().unwrap()
+}
+
+async fn get<F>(filter: &F, method: &str, path: &str, peer: SocketAddr) -> warp::http::Response<warp::hyper::body::Bytes>
+where
+  F: warp::Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+  warp::test::request().method(method).path(path).remote_addr(peer).reply(filter).await
+}
+
+#[tokio::test]
+async fn callback_route_is_locked_down() {
+  let tmp = tempfile::tempdir().unwrap();
+  let filter = routes(tmp.path().to_path_buf(), Arc::new(CallbackState::new("st".into())));
+
+  let cases = [
+    ("POST", "/callback?code=x&state=st".to_string(), loopback(), 404),
+    ("GET", "/".to_string(), loopback(), 404),
+    ("GET", "/callback/extra?code=x".to_string(), loopback(), 404),
+    ("GET", "/callback?code=x&state=st".to_string(), "192.168.1.5:50000".parse().unwrap(), 403),
+    ("GET", format!("/callback?code={}&state=st", "a".repeat(5000)), loopback(), 414),
+    ("GET", "/callback".to_string(), loopback(), 400),
+    ("GET", "/callback?code=x".to_string(), loopback(), 400),
+    ("GET", "/callback?code=x&state=other".to_string(), loopback(), 400),
+    ("GET", "/callback?error=access_denied&state=".to_string(), loopback(), 400),
+  ];
+  for (method, path, peer, status) in cases {
+    let resp = get(&filter, method, &path, peer).await;
+    assert_eq!(resp.status().as_u16(), status, "{} {}", method, &path[..path.len().min(40)]);
+    assert_eq!(resp.headers()["cache-control"], "no-store");
+  }
+
+  let injected = "/callback?error=access_denied&error_description=%3Cscript%3Ealert(1)%3C%2Fscript%3E%22&state=st";
+  let resp = get(&filter, "GET", injected, loopback()).await;
+  assert_eq!(resp.status().as_u16(), 400);
+  let body = String::from_utf8(resp.body().to_vec()).unwrap();
+  assert!(!body.contains("<script>") && body.contains("&lt;script&gt;") && body.contains("&quot;"));
+}
+
+#[tokio::test]
+async fn only_first_valid_callback_is_handled() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let token = server
+    .mock("POST", "/token")
+    .match_body(mockito::Matcher::Regex("code=first".into()))
+    .with_body(r#"{"access_token":"a","refresh_token":"r","expires_in":3600}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let refused = server
+    .mock("POST", "/token")
+    .match_body(mockito::Matcher::Regex("code=refused".into()))
+    .with_status(400)
+    .with_body(r#"{"error":"invalid_grant","error_description":"Bad Request"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  fs::write(tmp.path().join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let filter = routes(tmp.path().to_path_buf(), Arc::new(CallbackState::new("st".into())));
+
+  assert_eq!(get(&filter, "GET", "/callback?code=first&state=st", loopback()).await.status().as_u16(), 200);
+  assert_eq!(get(&filter, "GET", "/callback?code=second&state=st", loopback()).await.status().as_u16(), 409);
+  token.assert_async().await;
+  assert!(tmp.path().join("tokens.json").exists());
+
+  // Reloading the tab after a failed exchange shows the failure again, not
+  // "already logged in", and doesn't spend the code twice.
+  let tmp = tempfile::tempdir().unwrap();
+  fs::write(tmp.path().join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let filter = routes(tmp.path().to_path_buf(), Arc::new(CallbackState::new("st".into())));
+  assert_eq!(get(&filter, "GET", "/callback?code=refused&state=st", loopback()).await.status().as_u16(), 502);
+  let reloaded = get(&filter, "GET", "/callback?code=refused&state=st", loopback()).await;
+  assert_eq!(reloaded.status().as_u16(), 502);
+  assert!(String::from_utf8(reloaded.body().to_vec()).unwrap().contains("Logowanie nieudane"));
+  refused.assert_async().await;
+  assert!(!tmp.path().join("tokens.json").exists());
+}
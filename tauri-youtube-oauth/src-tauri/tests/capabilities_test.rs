@@ -0,0 +1,98 @@
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{
+  capabilities::{self, CapabilityInputs},
+  channel, scopes, Capability, ReasonCode,
+};
+
+fn ready() -> CapabilityInputs {
+  CapabilityInputs {
+    authenticated: true,
+    granted_scopes: vec![scopes::YOUTUBE_FORCE_SSL.into(), scopes::YT_ANALYTICS_READONLY.into()],
+    channel_selected: true,
+    content_owner: false,
+    read_only_mode: false,
+    quota_remaining: 10_000,
+    needs_reconsent: false,
+  }
+}
+
+fn blocked(reason: ReasonCode) -> Capability {
+  Capability { available: false, reason_code: Some(reason), missing_scopes: Vec::new() }
+}
+
+#[test]
+fn each_input_disables_the_features_it_concerns() {
+  let all = capabilities::compute(&ready());
+  let every = |c: &capabilities::Capabilities| {
+    [&c.list_videos, &c.upload, &c.set_thumbnail, &c.manage_playlists, &c.comments, &c.live, &c.analytics].map(Clone::clone)
+  };
+  assert!(every(&all).iter().all(|c| c.available && c.reason_code.is_none()), "{:?}", all);
+
+  let signed_out = capabilities::compute(&CapabilityInputs { authenticated: false, ..ready() });
+  assert!(every(&signed_out).iter().all(|c| *c == blocked(ReasonCode::ReauthRequired)));
+
+  // The full youtube scope: no comments (force-ssl only) and no analytics.
+  let youtube = capabilities::compute(&CapabilityInputs { granted_scopes: vec![scopes::YOUTUBE.into()], ..ready() });
+  assert!(youtube.upload.available && youtube.manage_playlists.available && youtube.live.available);
+  assert_eq!(youtube.comments.reason_code, Some(ReasonCode::InsufficientScope));
+  assert_eq!(youtube.comments.missing_scopes, vec![scopes::YOUTUBE_FORCE_SSL.to_string()]);
+  assert_eq!(youtube.analytics.missing_scopes, vec![scopes::YT_ANALYTICS_READONLY.to_string()]);
+  // Upload-only tokens can upload and set thumbnails, nothing else.
+  let upload_only = capabilities::compute(&CapabilityInputs { granted_scopes: vec![scopes::YOUTUBE_UPLOAD.into()], ..ready() });
+  assert!(upload_only.upload.available && upload_only.set_thumbnail.available);
+  assert_eq!(upload_only.list_videos.missing_scopes, vec![scopes::YOUTUBE_READONLY.to_string()]);
+
+  // Content owners also need youtubepartner, but only to write.
+  let owner = capabilities::compute(&CapabilityInputs { content_owner: true, ..ready() });
+  assert!(owner.list_videos.available && owner.analytics.available);
+  assert_eq!(owner.upload.missing_scopes, vec![scopes::YOUTUBE_PARTNER.to_string()]);
+
+  let no_channel = capabilities::compute(&CapabilityInputs { channel_selected: false, ..ready() });
+  assert_eq!(no_channel.list_videos, blocked(ReasonCode::ChannelNotSelected));
+  assert!(no_channel.upload.available && no_channel.live.available);
+
+  let read_only = capabilities::compute(&CapabilityInputs { read_only_mode: true, ..ready() });
+  assert!(read_only.list_videos.available && read_only.analytics.available);
+  assert_eq!(read_only.upload, blocked(ReasonCode::ReadOnlyMode));
+  assert_eq!(read_only.comments, blocked(ReasonCode::ReadOnlyMode));
+
+  // An upload costs 1600 units; a list call still fits.
+  let low = capabilities::compute(&CapabilityInputs { quota_remaining: 100, ..ready() });
+  assert_eq!(low.upload, blocked(ReasonCode::QuotaExceeded));
+  assert!(low.set_thumbnail.available && low.list_videos.available);
+  let spent = capabilities::compute(&CapabilityInputs { quota_remaining: 0, ..ready() });
+  assert_eq!(spent.list_videos, blocked(ReasonCode::QuotaExceeded));
+  assert!(spent.analytics.available);
+
+  // The first obstacle wins: scopes before the channel before read-only.
+  let both = capabilities::compute(&CapabilityInputs { granted_scopes: Vec::new(), channel_selected: false, read_only_mode: true, ..ready() });
+  assert_eq!(both.manage_playlists.reason_code, Some(ReasonCode::InsufficientScope));
+  let channel_first = capabilities::compute(&CapabilityInputs { channel_selected: false, read_only_mode: true, ..ready() });
+  assert_eq!(channel_first.manage_playlists.reason_code, Some(ReasonCode::ChannelNotSelected));
+
+  let payload = serde_json::to_value(&read_only).unwrap();
+  assert_eq!(payload["upload"], json!({ "available": false, "reason_code": "READ_ONLY_MODE", "missing_scopes": [] }));
+}
+
+#[test]
+fn inputs_are_read_from_the_config_dir() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  assert_eq!(capabilities::current(dir).upload, blocked(ReasonCode::ReauthRequired));
+
+  fs::write(dir.join("oauth_config.json"), json!({ "client_id": "id", "client_secret": "s", "read_only_mode": true }).to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": scopes::YOUTUBE });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let inputs = capabilities::inputs(dir);
+  assert!(inputs.authenticated && inputs.read_only_mode && !inputs.channel_selected && !inputs.content_owner);
+  assert_eq!(inputs.granted_scopes, vec![scopes::YOUTUBE.to_string()]);
+  assert_eq!(inputs.quota_remaining, 10_000);
+  assert_eq!(capabilities::current(dir).list_videos, blocked(ReasonCode::ChannelNotSelected));
+
+  channel::select_channel(dir, "UC1").unwrap();
+  let caps = capabilities::current(dir);
+  assert!(caps.list_videos.available);
+  assert_eq!(caps.upload, blocked(ReasonCode::ReadOnlyMode));
+}
@@ -0,0 +1,53 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{capture, live};
+
+#[tokio::test]
+async fn capture_never_records_secrets() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  server
+    .mock("POST", "/token")
+    .with_body(r#"{"access_token":"fresh-access-value","expires_in":3600,"refresh_token":"rotated-refresh-value"}"#)
+    .create_async()
+    .await;
+  server
+    .mock("GET", "/youtube/v3/liveStreams")
+    .match_query(Matcher::Any)
+    .with_body(r#"{"items":[{"id":"s1","cdn":{"ingestionInfo":{"streamName":"stream-key-value"}}}]}"#)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  fs::write(tmp.path().join("oauth_config.json"), r#"{"client_id":"id","client_secret":"client-secret-value"}"#).unwrap();
+  let tokens = serde_json::json!({
+    "access_token": "old-access-value",
+    "refresh_token": "old-refresh-value",
+    "expires_in": 3600,
+    "created_at": 1,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(tmp.path().join("tokens.json"), tokens.to_string()).unwrap();
+
+  assert!(capture::set_enabled(tmp.path(), true).enabled);
+  live::list_streams(tmp.path()).await.unwrap();
+
+  let entries = capture::entries(tmp.path());
+  let methods: Vec<&str> = entries.iter().map(|e| e.method.as_str()).collect();
+  assert!(methods.contains(&"POST") && methods.contains(&"GET"), "{:?}", methods);
+  assert!(entries.iter().all(|e| e.status == Some(200)));
+
+  let out = tmp.path().join("capture.json");
+  assert_eq!(capture::export(tmp.path(), &out).unwrap(), entries.len());
+  let dump = fs::read_to_string(out).unwrap();
+  for secret in ["client-secret-value", "old-refresh-value", "rotated-refresh-value", "fresh-access-value", "old-access-value", "stream-key-value"] {
+    assert!(!dump.contains(secret), "{} leaked into {}", secret, dump);
+  }
+  assert!(dump.contains("[REDACTED]"));
+
+  // Off: nothing new is recorded, what was captured stays exportable.
+  assert!(!capture::set_enabled(tmp.path(), false).enabled);
+  live::list_streams(tmp.path()).await.unwrap();
+  assert_eq!(capture::entries(tmp.path()).len(), entries.len());
+}
@@ -0,0 +1,118 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{channel, error::Error};
+
+fn seed_dir(dir: &Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({
+    "access_token": "access",
+    "refresh_token": "refresh",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+/// A PNG header declaring `width`×`height`, padded to `len` bytes; the
+/// checks only look at the header, so no pixel data is needed.
+fn png(path: &Path, width: u32, height: u32, len: usize) {
+  let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+  data.extend_from_slice(&width.to_be_bytes());
+  data.extend_from_slice(&height.to_be_bytes());
+  data.extend_from_slice(&[8, 2, 0, 0, 0, 0, 0, 0, 0]);
+  data.resize(len.max(data.len()), 0);
+  fs::write(path, data).unwrap();
+}
+
+fn refusal(result: Result<impl std::fmt::Debug, Error>) -> String {
+  match result {
+    Err(Error::Validation(message)) => message,
+    other => panic!("expected a validation error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn branding_updates_keep_untouched_keys() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let branding = json!({
+    "channel": { "title": "Kanał", "keywords": "rust tauri", "unsubscribedTrailer": "vid1" },
+    "image": { "bannerExternalUrl": "https://yt3.example/old" },
+  });
+  let read = server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("part".into(), "brandingSettings".into()),
+      Matcher::UrlEncoded("mine".into(), "true".into()),
+    ]))
+    .with_body(json!({ "items": [{ "id": "UC1", "brandingSettings": branding }] }).to_string())
+    .expect(2)
+    .create_async()
+    .await;
+  let description = server
+    .mock("PUT", "/youtube/v3/channels")
+    .match_query(Matcher::UrlEncoded("part".into(), "brandingSettings".into()))
+    .match_body(Matcher::PartialJson(json!({
+      "id": "UC1",
+      "brandingSettings": {
+        "channel": { "title": "Kanał", "keywords": "rust tauri", "unsubscribedTrailer": "vid1", "description": "Nowy opis" },
+        "image": { "bannerExternalUrl": "https://yt3.example/old" },
+      },
+    })))
+    .with_body(r#"{"id":"UC1","brandingSettings":{"channel":{"description":"Nowy opis"}}}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let insert = server
+    .mock("POST", "/upload/youtube/v3/channelBanners/insert")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "media".into()))
+    .match_header("content-type", "image/png")
+    .with_body(r#"{"url":"https://yt3.example/new"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let banner = server
+    .mock("PUT", "/youtube/v3/channels")
+    .match_query(Matcher::UrlEncoded("part".into(), "brandingSettings".into()))
+    .match_body(Matcher::PartialJson(json!({
+      "brandingSettings": {
+        "channel": { "title": "Kanał", "keywords": "rust tauri", "unsubscribedTrailer": "vid1" },
+        "image": { "bannerExternalUrl": "https://yt3.example/new" },
+      },
+    })))
+    .with_body(r#"{"id":"UC1"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  seed_dir(dir);
+
+  // Refused before anything is read from the API.
+  assert!(refusal(channel::set_channel_description(dir, &"a".repeat(1001)).await).contains("1000"));
+  assert!(refusal(channel::set_channel_description(dir, "Nowy <b>opis</b>").await).contains('<'));
+  let small = dir.join("small.png");
+  png(&small, 1024, 576, 64);
+  assert!(refusal(channel::upload_banner(dir, &small).await).contains("1024×576"));
+  let heavy = dir.join("heavy.png");
+  png(&heavy, 2560, 1440, 6 * 1024 * 1024 + 1);
+  assert!(refusal(channel::upload_banner(dir, &heavy).await).contains("limit"));
+  let fake = dir.join("banner.png");
+  fs::write(&fake, "not an image").unwrap();
+  assert!(refusal(channel::upload_banner(dir, &fake).await).contains("rozpoznawalnym"));
+
+  channel::set_channel_description(dir, "Nowy opis").await.unwrap();
+  let ok = dir.join("ok.png");
+  png(&ok, 2048, 1152, 1024);
+  assert_eq!(channel::upload_banner(dir, &ok).await.unwrap(), "https://yt3.example/new");
+
+  read.assert_async().await;
+  description.assert_async().await;
+  insert.assert_async().await;
+  banner.assert_async().await;
+}
@@ -0,0 +1,68 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::channel;
+
+const FIXTURE: &str = include_str!("fixtures/channels_list_mine.json");
+
+#[test]
+fn parses_full_channel() {
+  let channels = channel::parse_channels(&serde_json::from_str(FIXTURE).unwrap()).unwrap();
+  assert_eq!(channels.len(), 2);
+  let c = &channels[0];
+  assert_eq!(c.id, "UCx9Jp0fGd3nQ1f6Fz2Vw_8A");
+  assert_eq!(c.title, "YTLite Demo");
+  assert_eq!(c.custom_url.as_deref(), Some("@ytlitedemo"));
+  assert_eq!(c.uploads_playlist_id.as_deref(), Some("UUx9Jp0fGd3nQ1f6Fz2Vw_8A"));
+  assert_eq!(c.thumbnails.len(), 3);
+  assert_eq!(c.thumbnails["high"].width, Some(800));
+  let stats = c.statistics.as_ref().unwrap();
+  assert_eq!((stats.view_count, stats.subscriber_count, stats.video_count), (Some(1283944), Some(15200), Some(412)));
+  assert!(!stats.hidden_subscriber_count);
+}
+
+#[test]
+fn parses_channel_without_custom_url_and_hidden_subscribers() {
+  let channels = channel::parse_channels(&serde_json::from_str(FIXTURE).unwrap()).unwrap();
+  let c = &channels[1];
+  assert_eq!(c.custom_url, None);
+  assert_eq!(c.thumbnails["default"].width, None);
+  let stats = c.statistics.as_ref().unwrap();
+  assert!(stats.hidden_subscriber_count);
+  assert_eq!(stats.subscriber_count, None);
+  assert_eq!(stats.video_count, Some(0));
+}
+
+#[test]
+fn tolerates_missing_parts() {
+  let resp = serde_json::json!({ "items": [{ "id": "UC1", "snippet": { "title": "Only id and title" } }] });
+  let c = &channel::parse_channels(&resp).unwrap()[0];
+  assert!(c.statistics.is_none() && c.uploads_playlist_id.is_none() && c.thumbnails.is_empty());
+}
+
+#[tokio::test]
+async fn requests_all_parts_in_one_call() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let list = server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("part".into(), "snippet,contentDetails,statistics".into()),
+      Matcher::UrlEncoded("mine".into(), "true".into()),
+    ]))
+    .with_body(FIXTURE)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "access", "refresh_token": "r", "expires_in": 3600, "created_at": now });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let channels = channel::list_channels(dir).await.unwrap();
+  assert_eq!(channels.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), ["UCx9Jp0fGd3nQ1f6Fz2Vw_8A", "UC0aB1cD2eF3gH4iJ5kL6mN7"]);
+  list.assert_async().await;
+}
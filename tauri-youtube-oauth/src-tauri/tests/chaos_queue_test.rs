@@ -0,0 +1,54 @@
+#![cfg(feature = "chaos")]
+
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{
+  chaos::{self, Fault},
+  queue::{self, EntryStatus},
+  quota,
+};
+
+#[tokio::test]
+async fn the_queue_pauses_on_quota_exceeded_and_resumes_with_the_next_quota() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "upload_min_file_age_secs": 0 });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube.upload" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  for name in ["one.mp4", "two.mp4"] {
+    fs::write(dir.join(name), b"not really a video").unwrap();
+    fs::write(dir.join(name).with_extension("json"), json!({ "title": name }).to_string()).unwrap();
+    queue::add(dir, &dir.join(name).to_string_lossy(), None).unwrap();
+  }
+
+  let session = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .with_header("location", &format!("{}/upload/session/q", server.url()))
+    .expect(2)
+    .create_async()
+    .await;
+  let media = server.mock("PUT", "/upload/session/q").with_status(201).with_body(r#"{"id":"vid"}"#).expect(2).create_async().await;
+
+  // Google says the day's quota is gone: the entry goes back and nothing else is tried.
+  let spent = json!({ "error": { "code": 403, "message": "Quota", "errors": [{ "reason": "quotaExceeded", "domain": "youtube.quota" }] } });
+  let fault = Fault::Status { status: 403, retry_after_secs: None, body: Some(spent.to_string()) };
+  chaos::inject(dir, "/upload/youtube/v3/videos", fault, 1).unwrap();
+  assert_eq!(queue::process_due(dir).await.unwrap(), 1);
+  assert_eq!(quota::status(dir).remaining, 0);
+  assert!(queue::list(dir).iter().all(|e| e.status == EntryStatus::Pending && e.error.is_none()));
+  assert_eq!(queue::process_due(dir).await.unwrap(), 0, "paused");
+
+  // The quota resets: both go up.
+  fs::remove_file(dir.join("quota.json")).unwrap();
+  assert_eq!(queue::process_due(dir).await.unwrap(), 2);
+  assert!(queue::list(dir).iter().all(|e| e.status == EntryStatus::Done));
+  session.assert_async().await;
+  media.assert_async().await;
+}
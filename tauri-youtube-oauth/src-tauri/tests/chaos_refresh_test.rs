@@ -0,0 +1,70 @@
+#![cfg(feature = "chaos")]
+
+use mockito::Matcher;
+use std::{fs, path::Path, time::Instant};
+use tauri_youtube_oauth::{
+  chaos::{self, Fault},
+  error::Error,
+  live, read_tokens_from_dir, update_tokens_in_dir,
+};
+
+fn seed_expired(dir: &Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let tokens = serde_json::json!({ "access_token": "old", "refresh_token": "r", "expires_in": 3600, "created_at": 1, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+fn expire(dir: &Path) {
+  update_tokens_in_dir(dir, |t| t.created_at = 1).unwrap();
+}
+
+#[tokio::test]
+async fn refresh_failures_are_retried_backed_off_and_forgotten_after_a_success() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  server.mock("POST", "/token").with_body(r#"{"access_token":"new","expires_in":3600}"#).create_async().await;
+  let streams = server.mock("GET", "/youtube/v3/liveStreams").match_query(Matcher::Any).with_body(r#"{"items":[]}"#).create_async().await;
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  seed_expired(dir);
+
+  // A dropped connection fails the call that needed the refresh; the next one refreshes.
+  chaos::inject(dir, "/token$", Fault::ConnectionReset, 1).unwrap();
+  assert!(matches!(live::list_streams(dir).await, Err(Error::Network(_))));
+  assert_eq!(chaos::pending(dir), 0);
+  live::list_streams(dir).await.unwrap();
+  assert_eq!(read_tokens_from_dir(dir).unwrap().access_token, "new");
+
+  // Two failures, a success, two more: the success reset the count, so no backoff.
+  for _ in 0..2 {
+    expire(dir);
+    chaos::inject(dir, "/token$", Fault::Timeout, 2).unwrap();
+    for _ in 0..2 {
+      assert!(matches!(live::list_streams(dir).await, Err(Error::TimedOut { .. })));
+    }
+    live::list_streams(dir).await.unwrap();
+  }
+  assert!(!dir.join("refresh_state.json").exists());
+
+  // Three in a row do back off: the fourth attempt isn't sent.
+  let other = tempfile::tempdir().unwrap();
+  seed_expired(other.path());
+  chaos::inject(other.path(), "/token$", Fault::Status { status: 503, retry_after_secs: None, body: None }, 3).unwrap();
+  for _ in 0..3 {
+    assert!(matches!(live::list_streams(other.path()).await, Err(Error::Network(_))));
+  }
+  assert!(matches!(live::list_streams(other.path()).await, Err(Error::RefreshBackoff { .. })));
+  chaos::clear(other.path());
+
+  // A 429 is waited out (Retry-After) and replayed.
+  streams.remove_async().await;
+  let streams = server.mock("GET", "/youtube/v3/liveStreams").match_query(Matcher::Any).with_body(r#"{"items":[]}"#).expect(1).create_async().await;
+  chaos::inject(dir, "liveStreams", Fault::Status { status: 429, retry_after_secs: Some(1), body: None }, 1).unwrap();
+  let started = Instant::now();
+  live::list_streams(dir).await.unwrap();
+  assert!(started.elapsed().as_millis() >= 1000);
+  streams.assert_async().await;
+
+  assert!(matches!(chaos::inject(dir, "(", Fault::Timeout, 1), Err(Error::Validation(_))));
+}
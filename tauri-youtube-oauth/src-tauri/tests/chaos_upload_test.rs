@@ -0,0 +1,58 @@
+#![cfg(feature = "chaos")]
+
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{
+  chaos::{self, Fault},
+  upload, ForceCode, Privacy, VideoMetadata,
+};
+
+const MIB: u64 = 1024 * 1024;
+
+#[tokio::test]
+async fn a_dropped_chunk_resumes_from_what_the_server_confirmed() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube.upload" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let total = 12 * MIB;
+  let file = dir.join("render.mp4");
+  fs::write(&file, vec![3u8; total as usize]).unwrap();
+
+  let location = format!("{}/upload/session/chaos", server.url());
+  let session = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .with_header("location", &location)
+    .expect(1)
+    .create_async()
+    .await;
+  let mut put = |range: String, status: usize| server.mock("PUT", "/upload/session/chaos").match_header("content-range", range.as_str()).with_status(status).expect(1);
+  // The first 8 MiB chunk never arrives; the server says it kept half of it.
+  let asked = put(format!("bytes */{}", total), 308).with_header("range", &format!("bytes=0-{}", 4 * MIB - 1)).create_async().await;
+  let resumed = put(format!("bytes {}-{}/{}", 4 * MIB, 8 * MIB - 1, total), 308).with_header("range", &format!("bytes=0-{}", 8 * MIB - 1)).create_async().await;
+  let last = put(format!("bytes {}-{}/{}", 8 * MIB, total - 1, total), 201).with_body(r#"{"id":"vid1"}"#).create_async().await;
+  chaos::inject(dir, "/upload/session/chaos", Fault::ConnectionReset, 1).unwrap();
+
+  let meta = VideoMetadata {
+    title: "Test".into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  };
+  let video = upload::upload_video(dir, &file, &meta, &[ForceCode::RecentlyModified]).await.unwrap();
+  assert_eq!((video.video_id.as_str(), video.bytes), ("vid1", total));
+  assert_eq!(chaos::pending(dir), 0);
+  for m in [&session, &asked, &resumed, &last] {
+    m.assert_async().await;
+  }
+}
@@ -0,0 +1,104 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{
+  chapters::{self, ChapterPlacement, MARKER},
+  metadata, Chapter,
+};
+
+fn ch(start_secs: u64, title: &str) -> Chapter {
+  Chapter { start_secs, title: title.into() }
+}
+
+#[test]
+fn formats_sorted_padded_timestamps() {
+  let block = chapters::format(&[ch(95, "Traity"), ch(3, "Wstęp"), ch(605, "Podsumowanie")]).unwrap();
+  assert_eq!(block.text, format!("{}\n00:00 Wstęp\n01:35 Traity\n10:05 Podsumowanie", MARKER));
+  assert_eq!(block.warnings.len(), 1, "{:?}", block.warnings);
+  assert!(block.warnings[0].contains("00:00"));
+
+  let long = chapters::format(&[ch(0, "A"), ch(1800, "B"), ch(3725, "C")]).unwrap();
+  assert!(long.text.ends_with("00:00:00 A\n00:30:00 B\n01:02:05 C"), "{}", long.text);
+  assert!(long.warnings.is_empty());
+
+  let short = chapters::format(&[ch(0, "A"), ch(5, "B")]).unwrap();
+  assert_eq!(short.warnings.len(), 2, "{:?}", short.warnings);
+  assert!(chapters::format(&[]).is_err());
+  assert!(chapters::format(&[ch(0, " ")]).is_err());
+}
+
+#[test]
+fn block_is_replaced_in_place_and_never_duplicated() {
+  let first = chapters::format(&[ch(0, "A"), ch(60, "B"), ch(120, "C")]).unwrap().text;
+  let second = chapters::format(&[ch(0, "A"), ch(90, "B2"), ch(180, "C")]).unwrap().text;
+  let once = chapters::apply_to_description("Opis filmu.\n\nLinki: example.com", &first, ChapterPlacement::Prepend);
+  assert!(once.starts_with(MARKER) && once.ends_with("Linki: example.com"));
+  assert_eq!(chapters::apply_to_description(&once, &first, ChapterPlacement::Prepend), once);
+
+  let appended = chapters::apply_to_description("Opis\n", &first, ChapterPlacement::Append);
+  let updated = chapters::apply_to_description(&format!("{}\n\n#rust", appended), &second, ChapterPlacement::Append);
+  assert_eq!(updated, format!("Opis\n\n{}\n\n#rust", second));
+  assert_eq!(chapters::apply_to_description("", &first, ChapterPlacement::Append), first);
+}
+
+#[tokio::test]
+async fn upload_metadata_and_set_chapters_keep_the_rest_of_the_description() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = serde_json::json!({ "client_id": "id", "client_secret": "secret", "chapters_placement": "append" });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let video = dir.join("film.mp4");
+  fs::write(dir.join("film.json"), r#"{"title": "Film", "description": "Opis"}"#).unwrap();
+  fs::write(dir.join("chapters.json"), r#"[{"start_secs": 0, "title": "A"}, {"start_secs": 60, "title": "B"}, {"start_secs": 120, "title": "C"}]"#)
+    .unwrap();
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  assert_eq!(meta.description, format!("Opis\n\n{}\n00:00 A\n01:00 B\n02:00 C", MARKER));
+
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "access",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let snippet = serde_json::json!({ "title": "Film", "categoryId": "28", "tags": ["rust"], "description": meta.description + "\n\n#rust" });
+  // The copy kept before the one real write.
+  let snapshot = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("part".into(), "snippet,status,localizations,recordingDetails".into()))
+    .with_body(serde_json::json!({ "items": [{ "id": "vid1", "snippet": snippet }] }).to_string())
+    .expect(1)
+    .create_async()
+    .await;
+  let list = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("id".into(), "vid1".into()))
+    .with_body(serde_json::json!({ "items": [{ "id": "vid1", "snippet": snippet }] }).to_string())
+    .expect(2)
+    .create_async()
+    .await;
+  let expected = format!("Opis\n\n{}\n00:00 A\n01:30 B\n03:00 C\n\n#rust", MARKER);
+  let update = server
+    .mock("PUT", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("part".into(), "snippet".into()))
+    .match_body(Matcher::Json(serde_json::json!({
+      "id": "vid1",
+      "snippet": { "title": "Film", "categoryId": "28", "tags": ["rust"], "description": expected },
+    })))
+    .with_body(r#"{"id": "vid1"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let block = chapters::set_chapters(dir, "vid1", &[ch(0, "A"), ch(90, "B"), ch(180, "C")]).await.unwrap();
+  assert!(block.warnings.is_empty());
+  // Same chapters as already in the description: nothing to write.
+  chapters::set_chapters(dir, "vid1", &[ch(0, "A"), ch(60, "B"), ch(120, "C")]).await.unwrap();
+  snapshot.assert_async().await;
+  list.assert_async().await;
+  update.assert_async().await;
+}
@@ -0,0 +1,55 @@
+use std::fs;
+use tauri_youtube_oauth::{audit, ci_token};
+
+#[tokio::test]
+async fn ci_tokens_carry_no_long_lived_secrets_and_are_audited() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"client-secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "still-valid",
+    "refresh_token": "refresh-secret",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube.upload",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let refresh = server
+    .mock("POST", "/token")
+    .with_body(r#"{"access_token":"fresh","expires_in":3599,"scope":"https://www.googleapis.com/auth/youtube.upload"}"#)
+    .expect(2)
+    .create_async()
+    .await;
+
+  // Refreshed even though the stored token was still valid.
+  let token = ci_token::mint(dir).await.unwrap();
+  assert_eq!(token.access_token, "fresh");
+  assert!(token.expires_at >= now + 3599 && token.expires_at <= now + 3599 + 5);
+  assert_eq!(token.scopes, ["https://www.googleapis.com/auth/youtube.upload"]);
+  let json = serde_json::to_string(&token).unwrap();
+  assert!(!json.contains("refresh-secret") && !json.contains("client-secret"));
+
+  let path = dir.join("ci.env");
+  fs::write(&path, "OLD=1\n").unwrap();
+  let written = ci_token::write_env(dir, &path).await.unwrap();
+  refresh.assert_async().await;
+  assert_eq!(
+    fs::read_to_string(&path).unwrap(),
+    format!("YOUTUBE_ACCESS_TOKEN=fresh\nYOUTUBE_TOKEN_EXPIRES_AT={}\n", written.expires_at)
+  );
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+  }
+
+  let log = audit::entries(dir);
+  let actions: Vec<&str> = log.iter().map(|e| e.action.as_str()).collect();
+  assert_eq!(actions, ["mint_ci_token", "mint_ci_token", "write_ci_env"]);
+  assert_eq!(log[0].profile, "default");
+  assert_eq!(log[0].details["expires_at"], token.expires_at);
+  assert!(!fs::read_to_string(dir.join("audit.jsonl")).unwrap().contains("fresh"));
+}
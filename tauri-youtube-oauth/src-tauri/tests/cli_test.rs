@@ -0,0 +1,83 @@
+use std::fs;
+use tauri_youtube_oauth::{cli, error::Error};
+
+async fn run(args: &[&str]) -> (u8, String, String) {
+  let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+  let (mut out, mut err) = (Vec::new(), Vec::new());
+  let code = cli::run(&args, &mut out, &mut err).await;
+  (code, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+}
+
+#[test]
+fn errors_map_to_distinct_exit_codes() {
+  assert_eq!(cli::exit_code(&Error::GrantRevoked { hint: None }), cli::EXIT_AUTH);
+  assert_eq!(cli::exit_code(&Error::Api { status: 401, reason: "authError".into(), message: String::new() }), cli::EXIT_AUTH);
+  assert_eq!(cli::exit_code(&Error::Offline), cli::EXIT_NETWORK);
+  assert_eq!(cli::exit_code(&Error::TimedOut { operation: "x".into(), after_secs: 1 }), cli::EXIT_NETWORK);
+  assert_eq!(cli::exit_code(&Error::Validation("x".into())), cli::EXIT_FAILURE);
+}
+
+#[tokio::test]
+async fn usage_errors_exit_with_64() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().to_str().unwrap();
+  assert_eq!(run(&[]).await.0, cli::EXIT_USAGE);
+  assert_eq!(run(&["--config-dir", dir, "frobnicate"]).await.0, cli::EXIT_USAGE);
+  assert_eq!(run(&["--config-dir", dir, "status", "--bogus"]).await.0, cli::EXIT_USAGE);
+  assert_eq!(run(&["--config-dir", dir, "upload"]).await.0, cli::EXIT_USAGE);
+  assert_eq!(run(&["--config-dir", dir, "env", "--format", "yaml", "--out", "x"]).await.0, cli::EXIT_USAGE);
+  let (code, out, _) = run(&["--help"]).await;
+  assert_eq!(code, cli::EXIT_OK);
+  assert!(out.contains("ytlite-oauth"));
+}
+
+#[tokio::test]
+async fn secrets_stay_off_the_terminal_unless_asked_for() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let d = dir.to_str().unwrap();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"s3cr3t","channel_id":"UC1"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "ya29.live", "refresh_token": "1//r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let (code, out, _) = run(&["--config-dir", d, "--json", "status"]).await;
+  assert_eq!(code, cli::EXIT_OK);
+  let status: serde_json::Value = serde_json::from_str(&out).unwrap();
+  assert_eq!(status["authenticated"], true);
+  assert!(!out.contains("ya29.live") && !out.contains("1//r"));
+  let (_, text, _) = run(&["--config-dir", d, "status"]).await;
+  assert!(text.starts_with("Zalogowano: tak"), "{}", text);
+
+  // The env text has the client secret: only into a file, or with the flag.
+  let (code, out, err) = run(&["--config-dir", d, "env"]).await;
+  assert_eq!(code, cli::EXIT_USAGE);
+  assert!(!out.contains("s3cr3t") && !err.contains("s3cr3t"));
+  let env_path = dir.join("out.env");
+  let (code, out, _) = run(&["--config-dir", d, "env", "--out", env_path.to_str().unwrap()]).await;
+  assert_eq!(code, cli::EXIT_OK);
+  assert!(!out.contains("s3cr3t"));
+  let written = fs::read_to_string(&env_path).unwrap();
+  assert!(written.contains("YOUTUBE_CLIENT_SECRET=s3cr3t") && written.contains("1//r"));
+  let (code, out, _) = run(&["--config-dir", d, "--include-secrets", "env", "--no-tokens", "--format", "json"]).await;
+  assert_eq!(code, cli::EXIT_OK);
+  assert!(out.contains("s3cr3t") && !out.contains("1//r"));
+}
+
+#[tokio::test]
+async fn refresh_failures_exit_by_kind() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let d = dir.to_str().unwrap();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let (code, _, err) = run(&["--config-dir", d, "refresh"]).await;
+  assert_eq!(code, cli::EXIT_AUTH, "{}", err);
+
+  std::env::set_var("OAUTH_TOKEN_URL", "http://127.0.0.1:9/token");
+  let tokens = serde_json::json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": 0 });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let (code, out, _) = run(&["--config-dir", d, "--json", "refresh"]).await;
+  assert_eq!(code, cli::EXIT_NETWORK);
+  let body: serde_json::Value = serde_json::from_str(&out).unwrap();
+  assert_eq!(body["error"]["code"], "NETWORK_ERROR");
+}
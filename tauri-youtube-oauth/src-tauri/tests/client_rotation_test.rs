@@ -0,0 +1,88 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{audit, error::Error, live, read_tokens_from_dir, rotation, update_tokens_in_dir};
+
+fn refresh_with(secret: &str) -> Matcher {
+  Matcher::AllOf(vec![
+    Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+    Matcher::UrlEncoded("client_secret".into(), secret.into()),
+  ])
+}
+
+#[tokio::test]
+async fn a_new_secret_is_checked_before_it_replaces_the_old_one() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  server.mock("GET", "/youtube/v3/liveStreams").match_query(Matcher::Any).with_body(r#"{"items":[]}"#).create_async().await;
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"old"}"#).unwrap();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": 1, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let config = || serde_json::from_str::<serde_json::Value>(&fs::read_to_string(dir.join("oauth_config.json")).unwrap()).unwrap();
+  let secret = || config()["client_secret"].clone();
+
+  // A mistyped secret is refused by Google and never saved.
+  let invalid = json!({ "error": "invalid_client", "error_description": "Unauthorized" }).to_string();
+  let mistyped = server.mock("POST", "/token").match_body(refresh_with("typo")).with_status(401).with_body(&invalid).create_async().await;
+  match rotation::rotate_client_secret(dir, "typo").await {
+    Err(Error::ConsentFailed(g)) => assert_eq!(g.code, "INVALID_CLIENT"),
+    other => panic!("expected INVALID_CLIENT, got {:?}", other),
+  }
+  mistyped.assert_async().await;
+  assert_eq!(secret(), "old");
+
+  let accepted = server
+    .mock("POST", "/token")
+    .match_body(refresh_with("new"))
+    .with_body(r#"{"access_token":"checked","expires_in":3600}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let status = rotation::rotate_client_secret(dir, " new ").await.unwrap();
+  accepted.assert_async().await;
+  assert!(status.authenticated);
+  assert_eq!(secret(), "new");
+  assert_eq!(read_tokens_from_dir(dir).unwrap().access_token, "checked", "the session is kept");
+
+  // Google lagging behind: the next refresh is refused for the new secret,
+  // so the old one is tried once.
+  accepted.remove_async().await;
+  let lagging = server.mock("POST", "/token").match_body(refresh_with("new")).with_status(401).with_body(&invalid).expect(2).create_async().await;
+  let old = server
+    .mock("POST", "/token")
+    .match_body(refresh_with("old"))
+    .with_body(r#"{"access_token":"via-old","expires_in":3600}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  update_tokens_in_dir(dir, |t| t.created_at = 1).unwrap();
+  live::list_streams(dir).await.unwrap();
+  assert_eq!(read_tokens_from_dir(dir).unwrap().access_token, "via-old");
+  update_tokens_in_dir(dir, |t| t.created_at = 1).unwrap();
+  assert!(live::list_streams(dir).await.is_err(), "only once");
+  lagging.assert_async().await;
+  old.assert_async().await;
+
+  // Another client_id: the tokens can't come along, so it takes a confirmation.
+  let refused = rotation::rotate_client_id(dir, "other-id", "other-secret", false).unwrap_err();
+  assert!(matches!(&refused, Error::Validation(m) if m.contains("zalogować się ponownie")), "{:?}", refused);
+  assert!(read_tokens_from_dir(dir).is_some());
+  assert!(matches!(rotation::rotate_client_id(dir, "id", "x", true), Err(Error::Validation(_))));
+  let status = rotation::rotate_client_id(dir, "other-id", "other-secret", true).unwrap();
+  assert!(!status.authenticated);
+  assert!(read_tokens_from_dir(dir).is_none());
+  assert_eq!((config()["client_id"].clone(), secret()), (json!("other-id"), json!("other-secret")));
+
+  let logged: Vec<_> = audit::entries(dir).into_iter().map(|e| (e.action, e.details)).collect();
+  assert_eq!(
+    logged,
+    [
+      ("rotate_client_secret".to_string(), json!({ "client_id": "id" })),
+      ("rotate_client_id".to_string(), json!({ "previous_client_id": "id", "client_id": "other-id", "tokens_cleared": true })),
+    ]
+  );
+  assert!(!fs::read_to_string(dir.join("audit.jsonl")).unwrap().contains("secret\":"), "no secrets in the log");
+}
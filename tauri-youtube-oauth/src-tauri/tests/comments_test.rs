@@ -0,0 +1,110 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{
+  fs,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tauri_youtube_oauth::{comments, error::Error, metadata, upload, ForceCode};
+
+type Calls = Arc<Mutex<Vec<&'static str>>>;
+
+fn logged(calls: &Calls, name: &'static str, body: String) -> impl Fn(&mockito::Request) -> Vec<u8> + Send + Sync + 'static {
+  let calls = calls.clone();
+  move |_| {
+    calls.lock().unwrap().push(name);
+    body.clone().into_bytes()
+  }
+}
+
+#[tokio::test]
+async fn the_first_comment_is_posted_then_published_and_pinning_is_left_to_studio() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube.force-ssl" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let calls: Calls = Arc::default();
+
+  let comment = "0:00 Wstęp\n1:30 Konfiguracja\n4:10 Podsumowanie";
+  let video = dir.join("odcinek.mp4");
+  fs::write(&video, vec![1u8; 4096]).unwrap();
+  fs::write(dir.join("odcinek.json"), json!({ "title": "Odcinek", "first_comment": comment }).to_string()).unwrap();
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  assert_eq!(meta.first_comment.as_deref(), Some(comment));
+
+  server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .with_header("location", &format!("{}/upload/session/c", server.url()))
+    .create_async()
+    .await;
+  server.mock("PUT", "/upload/session/c").with_status(201).with_body(r#"{"id":"vid1"}"#).create_async().await;
+  let thread = json!({
+    "id": "th1",
+    "snippet": { "videoId": "vid1", "topLevelComment": { "id": "c1", "snippet": { "textOriginal": comment, "moderationStatus": "published" } } }
+  });
+  let insert = server
+    .mock("POST", "/youtube/v3/commentThreads")
+    .match_query(Matcher::UrlEncoded("part".into(), "snippet".into()))
+    .match_body(Matcher::Json(json!({ "snippet": { "videoId": "vid1", "topLevelComment": { "snippet": { "textOriginal": comment } } } })))
+    .with_body_from_request(logged(&calls, "commentThreads.insert", thread.to_string()))
+    .expect(1)
+    .create_async()
+    .await;
+  let published = json!({ "items": [{ "id": "c1", "snippet": { "videoId": "vid1", "moderationStatus": "published" } }] });
+  let read = server
+    .mock("GET", "/youtube/v3/comments")
+    .match_query(Matcher::UrlEncoded("id".into(), "c1".into()))
+    .with_body_from_request(logged(&calls, "comments.list", published.to_string()))
+    .expect(1)
+    .create_async()
+    .await;
+  let moderate = server
+    .mock("POST", "/youtube/v3/comments/setModerationStatus")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("id".into(), "c2".into()),
+      Matcher::UrlEncoded("moderationStatus".into(), "published".into()),
+    ]))
+    .with_status(204)
+    .with_body_from_request(logged(&calls, "comments.setModerationStatus", String::new()))
+    .expect(1)
+    .create_async()
+    .await;
+
+  // After the upload, in the background: posted, read back, already published.
+  upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await.unwrap();
+  for _ in 0..100 {
+    if calls.lock().unwrap().len() == 2 {
+      break;
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+  assert_eq!(*calls.lock().unwrap(), ["commentThreads.insert", "comments.list"]);
+  insert.assert_async().await;
+  read.assert_async().await;
+
+  // A viewer's comment held for review is published first; the pin is refused, not faked.
+  calls.lock().unwrap().clear();
+  let held = json!({ "items": [{ "id": "c2", "snippet": { "videoId": "vid1", "moderationStatus": "heldForReview" } }] });
+  server
+    .mock("GET", "/youtube/v3/comments")
+    .match_query(Matcher::UrlEncoded("id".into(), "c2".into()))
+    .with_body_from_request(logged(&calls, "comments.list", held.to_string()))
+    .create_async()
+    .await;
+  let err = comments::pin_comment(dir, "c2").await.unwrap_err();
+  assert_eq!(err.code(), "NOT_SUPPORTED_BY_API");
+  assert!(err.to_string().contains("https://studio.youtube.com/video/vid1/comments"), "{}", err);
+  assert_eq!(*calls.lock().unwrap(), ["comments.list", "comments.setModerationStatus"]);
+  moderate.assert_async().await;
+
+  // Hearting has no endpoint: nothing is sent.
+  assert!(matches!(comments::heart_comment(dir, "c2").await, Err(Error::NotSupportedByApi { .. })));
+  assert!(matches!(comments::post_comment(dir, "vid1", "  ").await, Err(Error::Validation(_))));
+  assert_eq!(calls.lock().unwrap().len(), 2);
+}
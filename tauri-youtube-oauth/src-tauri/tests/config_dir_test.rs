@@ -0,0 +1,33 @@
+use tauri_youtube_oauth::{read_tokens_from_dir, storage, write_tokens_to_dir, Tokens};
+
+fn tokens(access: &str) -> Tokens {
+  Tokens { access_token: access.into(), refresh_token: format!("{}-refresh", access), ..Default::default() }
+}
+
+#[test]
+fn override_dirs_keep_tokens_apart() {
+  let cwd = tempfile::tempdir().unwrap();
+  let other = tempfile::tempdir().unwrap();
+
+  let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+  let a = storage::parse_override(&args(&["--config-dir", "instance-a"]), None, cwd.path()).unwrap().unwrap();
+  let b = storage::parse_override(&[], Some(other.path().join("b").display().to_string()), cwd.path()).unwrap().unwrap();
+  assert_eq!(a, cwd.path().canonicalize().unwrap().join("instance-a"));
+  assert!(b.is_absolute() && b.is_dir());
+
+  write_tokens_to_dir(&a, &tokens("token-a")).unwrap();
+  assert!(read_tokens_from_dir(&b).is_none());
+  write_tokens_to_dir(&b, &tokens("token-b")).unwrap();
+  assert_eq!(read_tokens_from_dir(&a).unwrap().access_token, "token-a");
+  assert_eq!(read_tokens_from_dir(&b).unwrap().access_token, "token-b");
+}
+
+#[test]
+fn cli_flag_wins_over_env() {
+  let cwd = tempfile::tempdir().unwrap();
+  let args = vec!["--config-dir=from-cli".to_string()];
+  let dir = storage::parse_override(&args, Some("from-env".into()), cwd.path()).unwrap().unwrap();
+  assert!(dir.ends_with("from-cli"));
+  assert!(storage::parse_override(&[], None, cwd.path()).unwrap().is_none());
+  assert!(storage::parse_override(&["--config-dir".to_string()], None, cwd.path()).is_err());
+}
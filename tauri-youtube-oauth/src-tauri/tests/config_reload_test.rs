@@ -0,0 +1,51 @@
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+use tauri_youtube_oauth::config;
+
+fn write_config(dir: &Path, client_id: &str, client_secret: &str, extra: Value) {
+  let mut cfg = json!({
+    "client_id": client_id,
+    "client_secret": client_secret,
+    "event_sinks": [{ "type": "file", "path": "events.jsonl", "events": ["config-changed"] }],
+  });
+  cfg.as_object_mut().unwrap().extend(extra.as_object().cloned().unwrap_or_default());
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+fn events(dir: &Path) -> Vec<Value> {
+  fs::read_to_string(dir.join("events.jsonl")).unwrap_or_default().lines().map(|l| serde_json::from_str(l).unwrap()).collect()
+}
+
+#[test]
+fn reload_reports_key_names_only_and_flags_tokens_from_another_client() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(dir, "old-id", "hunter2-old", json!({}));
+  let tokens = json!({ "access_token": "access", "refresh_token": "refresh", "expires_in": 3600, "created_at": 0, "client_id": "old-id" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let first = config::reload(dir).unwrap();
+  assert!(!first.client_id_mismatch);
+
+  // A new client: the secret rotates with it, and the stored tokens belong to the old one.
+  write_config(dir, "new-id", "hunter2-new", json!({ "daily_quota": 5000 }));
+  let change = config::reload(dir).unwrap();
+  assert_eq!(change.changed, ["client_id", "client_secret", "daily_quota"]);
+  assert!(change.client_id_mismatch);
+
+  let reported = serde_json::to_string(&change).unwrap();
+  let emitted = fs::read_to_string(dir.join("events.jsonl")).unwrap();
+  for text in [&reported, &emitted] {
+    assert!(!text.contains("hunter2"), "a secret leaked: {}", text);
+  }
+  let last = events(dir).pop().unwrap();
+  assert_eq!(last["event"], "config-changed");
+  assert_eq!(last["payload"], serde_json::to_value(&change).unwrap());
+
+  // Nothing changed since: no event, but the mismatch is still reported.
+  let seen = events(dir).len();
+  let again = config::reload(dir).unwrap();
+  assert!(again.changed.is_empty());
+  assert!(again.client_id_mismatch);
+  assert_eq!(events(dir).len(), seen);
+}
@@ -0,0 +1,74 @@
+use std::{fs, net::SocketAddr, sync::Arc};
+use tauri_youtube_oauth::{
+  callback::{routes, CallbackState},
+  error::{CommandError, Error},
+  format::Language,
+  guidance::{self, GuidanceSource},
+};
+
+#[test]
+fn known_errors_get_a_localized_entry() {
+  let g = guidance::for_callback("access_denied", None, None, Language::En);
+  assert_eq!((g.code.as_str(), g.known, g.source), ("ACCESS_DENIED", true, GuidanceSource::Callback));
+  assert!(g.action.unwrap().contains("test user"));
+  let pl = guidance::for_callback("access_denied", None, None, Language::Pl);
+  assert!(pl.action.unwrap().contains("użytkownika testowego"));
+
+  // The subtype is more specific than the error.
+  let g = guidance::for_callback("access_denied", Some("admin_policy_enforced"), None, Language::Pl);
+  assert_eq!(g.code, "ADMIN_POLICY_ENFORCED");
+  let g = guidance::for_callback("access_denied", None, Some("This app has not completed the Google verification process"), Language::En);
+  assert_eq!(g.code, "APP_NOT_VERIFIED");
+  let g = guidance::for_callback("redirect_uri_mismatch", None, None, Language::En);
+  assert!(g.action.unwrap().contains("http://127.0.0.1:14321/callback"));
+
+  let g = guidance::for_token_error("invalid_client", Some("The OAuth client was not found."), Language::Pl);
+  assert_eq!((g.code.as_str(), g.source), ("INVALID_CLIENT", GuidanceSource::Token));
+  assert_eq!(g.description.as_deref(), Some("The OAuth client was not found."));
+}
+
+#[test]
+fn unknown_errors_keep_the_raw_value() {
+  let g = guidance::for_callback("interaction_required", Some("odd"), Some("Try later"), Language::En);
+  assert_eq!((g.code.as_str(), g.known), ("UNKNOWN", false));
+  assert_eq!(g.explanation, "Google returned the error interaction_required (odd): Try later");
+  assert!(g.action.is_none());
+  assert!(g.help_url.starts_with("https://developers.google.com/identity/protocols/oauth2"));
+}
+
+#[tokio::test]
+async fn refused_logins_get_a_help_page_and_a_structured_error() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret","language":"en"}"#).unwrap();
+  let filter = routes(dir.to_path_buf(), Arc::new(CallbackState::new("st".into())));
+  let peer: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+
+  let resp = warp::test::request().path("/callback?error=admin_policy_enforced&state=st").remote_addr(peer).reply(&filter).await;
+  assert_eq!(resp.status().as_u16(), 400);
+  let body = String::from_utf8(resp.body().to_vec()).unwrap();
+  assert!(body.contains("<html lang=\"en\">") && body.contains("role=\"alert\""));
+  assert!(body.contains("Google Workspace") && body.contains("https://support.google.com/a/answer/7281227"));
+
+  let token = server
+    .mock("POST", "/token")
+    .with_status(401)
+    .with_body(r#"{"error":"invalid_client","error_description":"Unauthorized"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let resp = warp::test::request().path("/callback?code=abc&state=st").remote_addr(peer).reply(&filter).await;
+  token.assert_async().await;
+  assert_eq!(resp.status().as_u16(), 502);
+  let body = String::from_utf8(resp.body().to_vec()).unwrap();
+  assert!(body.contains("Invalid OAuth client") && body.contains("Credentials"));
+  assert!(!dir.join("tokens.json").exists());
+
+  let err = Error::ConsentFailed(Box::new(guidance::for_token_error("invalid_client", None, Language::En)));
+  let value = serde_json::to_value(CommandError::from(err)).unwrap();
+  assert_eq!(value["code"], "CONSENT_FAILED");
+  assert_eq!(value["details"]["code"], "INVALID_CLIENT");
+  assert_eq!(value["details"]["source"], "token");
+}
@@ -0,0 +1,70 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{consent_probe, error::Error, AppConfig, ConsentGuidance, GuidanceSource};
+
+fn client(id: &str) -> AppConfig {
+  serde_json::from_value(serde_json::json!({ "client_id": id, "client_secret": "secret" })).unwrap()
+}
+
+fn refusal(r: Result<(), Error>) -> ConsentGuidance {
+  match r {
+    Err(Error::ConsentFailed(g)) => {
+      assert_eq!(g.source, GuidanceSource::Preflight);
+      *g
+    }
+    other => panic!("expected CONSENT_FAILED, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn probe_catches_broken_clients_before_the_browser_opens() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_AUTH_URL", format!("{}/auth", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let scopes = vec!["https://www.googleapis.com/auth/youtube".to_string()];
+  let for_client = |id: &str| Matcher::AllOf(vec![Matcher::UrlEncoded("client_id".into(), id.into()), Matcher::UrlEncoded("prompt".into(), "none".into())]);
+
+  server
+    .mock("GET", "/auth")
+    .match_query(for_client("good"))
+    .with_status(302)
+    .with_header("location", "http://127.0.0.1:14321/callback?error=login_required")
+    .create_async()
+    .await;
+  consent_probe::check(dir, &client("good"), &scopes).await.unwrap();
+
+  server
+    .mock("GET", "/auth")
+    .match_query(for_client("no-redirect"))
+    .with_status(400)
+    .with_body("<html>Error 400: redirect_uri_mismatch</html>")
+    .create_async()
+    .await;
+  let g = refusal(consent_probe::check(dir, &client("no-redirect"), &scopes).await);
+  assert_eq!(g.code, "REDIRECT_URI_MISMATCH");
+  assert!(g.action.unwrap().contains("http://127.0.0.1:14321/callback"));
+
+  // Google's error page redirect carries the reason base64-encoded.
+  server
+    .mock("GET", "/auth")
+    .match_query(for_client("deleted"))
+    .with_status(302)
+    .with_header("location", "https://accounts.google.com/signin/oauth/error?authError=Cg5kZWxldGVkX2NsaWVudBIWVGhlIE9BdXRoIGNsaWVudCB3YXMgZGVsZXRlZC4%3D")
+    .create_async()
+    .await;
+  assert_eq!(refusal(consent_probe::check(dir, &client("deleted"), &scopes).await).code, "INVALID_CLIENT");
+
+  // The sign-in page says nothing either way; neither does no answer.
+  server
+    .mock("GET", "/auth")
+    .match_query(for_client("unknown"))
+    .with_status(302)
+    .with_header("location", "https://accounts.google.com/v3/signin/identifier")
+    .create_async()
+    .await;
+  consent_probe::check(dir, &client("unknown"), &scopes).await.unwrap();
+  std::env::set_var("OAUTH_AUTH_URL", "http://127.0.0.1:9/auth");
+  consent_probe::check(dir, &client("good"), &scopes).await.unwrap();
+}
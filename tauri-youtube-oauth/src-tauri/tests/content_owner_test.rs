@@ -0,0 +1,95 @@
+use mockito::Matcher;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{config, error::Error, playlists, scopes, AppConfig, PlaylistInsert};
+
+fn write_tokens(dir: &Path, scope: &str) {
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": scope });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[test]
+fn content_owner_needs_the_partner_scope() {
+  let cfg = AppConfig { client_id: "id".into(), client_secret: "secret".into(), ..Default::default() };
+  assert_eq!(scopes::configured(&cfg), [scopes::YOUTUBE_READONLY]);
+  let owner = AppConfig { content_owner_id: Some("CMS1".into()), scopes: vec![scopes::YOUTUBE.into()], ..cfg.clone() };
+  assert_eq!(scopes::configured(&owner), [scopes::YOUTUBE, scopes::YOUTUBE_PARTNER]);
+  assert!(config::validate(&owner).is_ok());
+
+  let orphan = AppConfig { content_owner_channel_id: Some("UCch".into()), ..cfg };
+  assert!(matches!(config::validate(&orphan), Err(Error::Config(m)) if m.contains("content_owner_id")));
+}
+
+#[tokio::test]
+async fn writes_carry_on_behalf_parameters_only_for_content_owners() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  write_tokens(dir, scopes::YOUTUBE);
+
+  // Reads never carry the parameters.
+  let list = server
+    .mock("GET", "/youtube/v3/playlistItems")
+    .match_query(Matcher::Regex("^part=id%2Csnippet&playlistId=PL[0-9]&maxResults=50$".into()))
+    .with_body(r#"{"items":[]}"#)
+    .expect(4)
+    .create_async()
+    .await;
+  let plain = server
+    .mock("POST", "/youtube/v3/playlistItems")
+    .match_query(Matcher::Exact("part=snippet".into()))
+    .with_body(r#"{"id":"item1"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  assert_eq!(playlists::add_to_playlist(dir, "PL1", "v1").await.unwrap(), PlaylistInsert::Inserted { item_id: "item1".into() });
+  plain.assert_async().await;
+
+  let owner_config = r#"{"client_id":"id","client_secret":"secret","content_owner_id":"CMS1","content_owner_channel_id":"UCch"}"#;
+  fs::write(dir.join("oauth_config.json"), owner_config).unwrap();
+  write_tokens(dir, &format!("{} {}", scopes::YOUTUBE, scopes::YOUTUBE_PARTNER));
+  let on_behalf = Matcher::AllOf(vec![
+    Matcher::UrlEncoded("part".into(), "snippet".into()),
+    Matcher::UrlEncoded("onBehalfOfContentOwner".into(), "CMS1".into()),
+    Matcher::UrlEncoded("onBehalfOfContentOwnerChannel".into(), "UCch".into()),
+  ]);
+  let partner = server
+    .mock("POST", "/youtube/v3/playlistItems")
+    .match_query(on_behalf.clone())
+    .match_body(Matcher::PartialJson(serde_json::json!({ "snippet": { "playlistId": "PL2" } })))
+    .with_body(r#"{"id":"item2"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  assert_eq!(playlists::add_to_playlist(dir, "PL2", "v1").await.unwrap(), PlaylistInsert::Inserted { item_id: "item2".into() });
+  partner.assert_async().await;
+
+  let unlinked = server
+    .mock("POST", "/youtube/v3/playlistItems")
+    .match_query(on_behalf)
+    .match_body(Matcher::PartialJson(serde_json::json!({ "snippet": { "playlistId": "PL3" } })))
+    .with_status(404)
+    .with_body(r#"{"error":{"message":"Channel not found.","errors":[{"reason":"channelNotFound"}]}}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  match playlists::add_to_playlist(dir, "PL3", "v1").await {
+    Err(Error::Api { status: 404, reason, message }) => {
+      assert_eq!(reason, "channelNotFound");
+      assert!(message.contains("UCch") && message.contains("content_owner_channel_id"), "{}", message);
+    }
+    other => panic!("expected channelNotFound, got {:?}", other),
+  }
+  unlinked.assert_async().await;
+
+  // Tokens from before the partner scope was configured: the write isn't sent.
+  write_tokens(dir, scopes::YOUTUBE);
+  match playlists::add_to_playlist(dir, "PL4", "v1").await {
+    Err(Error::InsufficientScope { missing, .. }) => assert_eq!(missing, [scopes::YOUTUBE_PARTNER]),
+    other => panic!("expected InsufficientScope, got {:?}", other),
+  }
+  list.assert_async().await;
+}
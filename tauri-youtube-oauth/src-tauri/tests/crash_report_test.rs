@@ -0,0 +1,38 @@
+use std::{
+  fs,
+  sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+  },
+};
+use tauri_youtube_oauth::crash;
+
+#[tokio::test]
+async fn panicking_task_is_reported_and_restarted() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  assert!(crash::list(dir).unwrap().is_empty());
+  crash::install(dir);
+
+  let runs = Arc::new(AtomicU32::new(0));
+  let counter = runs.clone();
+  crash::supervise(dir, "test", move || {
+    let counter = counter.clone();
+    async move {
+      if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+        panic!("refresh_token=1//secret-value went wrong");
+      }
+    }
+  })
+  .await;
+  assert_eq!(runs.load(Ordering::SeqCst), 2, "restarted once, then ended normally");
+
+  let reports = crash::list(dir).unwrap();
+  assert_eq!(reports.len(), 1);
+  assert!(reports[0].file_name.ends_with("Z.txt"), "{}", reports[0].file_name);
+  assert!(reports[0].summary.contains("went wrong"), "{}", reports[0].summary);
+  let text = fs::read_to_string(&reports[0].path).unwrap();
+  assert!(!text.contains("secret-value"), "{}", text);
+  assert!(text.contains(&format!("wersja: {}", env!("CARGO_PKG_VERSION"))));
+  assert!(text.contains("crash_report_test.rs"), "location and backtrace are included");
+}
@@ -0,0 +1,88 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{capture, channel, config, metadata, metrics, upload, ForceCode};
+
+fn write_config(dir: &Path, headers: serde_json::Value) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "custom_headers": headers });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+/// Both headers, as every request to Google must carry them.
+fn tagged(mock: mockito::Mock) -> mockito::Mock {
+  mock.match_header("x-request-source", "ytlite-ci").match_header("x-client-trace-id", Matcher::Regex("^[0-9a-f]{32}$".into()))
+}
+
+#[tokio::test]
+async fn auth_api_upload_and_probe_requests_carry_the_headers_and_one_trace_id_per_operation() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(dir, json!({ "X-Request-Source": "ytlite-ci" }));
+  // Expired: the first call refreshes.
+  let tokens = json!({ "access_token": "old", "refresh_token": "r", "expires_in": 3600, "created_at": 1, "scope": "https://www.googleapis.com/auth/youtube.upload https://www.googleapis.com/auth/youtube.readonly" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  capture::set_enabled(dir, true);
+  // The connectivity check before the first call goes to the token host too.
+  let probe = tagged(server.mock("HEAD", "/")).create_async().await;
+
+  let token = tagged(server.mock("POST", "/token"))
+    .with_body(r#"{"access_token":"a","expires_in":3600,"token_type":"Bearer"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let channels = tagged(server.mock("GET", "/youtube/v3/channels"))
+    .match_query(Matcher::Any)
+    .with_body(json!({ "items": [{ "id": "UC1" }] }).to_string())
+    .create_async()
+    .await;
+  channel::list_mine(dir, "snippet").await.unwrap();
+
+  let video = dir.join("odcinek.mp4");
+  fs::write(&video, vec![1u8; 2048]).unwrap();
+  fs::write(dir.join("odcinek.json"), json!({ "title": "Odcinek" }).to_string()).unwrap();
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  let location = format!("{}/upload/session/h", server.url());
+  let session = tagged(server.mock("POST", "/upload/youtube/v3/videos"))
+    .match_query(Matcher::Any)
+    .with_header("location", &location)
+    .create_async()
+    .await;
+  let chunk = tagged(server.mock("PUT", "/upload/session/h")).with_status(201).with_body(r#"{"id":"vid1"}"#).create_async().await;
+  upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await.unwrap();
+  for mock in [token, channels, session, chunk, probe] {
+    mock.assert_async().await;
+  }
+
+  let entries = capture::entries(dir);
+  let trace_of = |needle: &str| entries.iter().filter(|e| e.url.contains(needle)).map(|e| e.trace_id.clone()).collect::<Vec<_>>();
+  let (refresh, listed, opened, sent) = (trace_of("/token"), trace_of("/channels"), trace_of("/youtube/v3/videos"), trace_of("/session/h"));
+  assert_eq!((refresh.len(), listed.len(), opened.len(), sent.len()), (1, 1, 1, 1), "{:?}", entries);
+  // The upload's session and chunk are one operation; the listing another.
+  assert_eq!(opened, sent);
+  assert_ne!(listed, opened);
+  assert_ne!(refresh, listed);
+  let slowest = metrics::snapshot(dir, false).upload.slowest.unwrap();
+  assert_eq!(slowest.trace_id, sent[0]);
+}
+
+#[test]
+fn reserved_or_unprintable_headers_are_rejected_on_reload() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  for (headers, needle) in [
+    (json!({ "Authorization": "Bearer x" }), "Authorization"),
+    (json!({ "content-range": "bytes */1" }), "content-range"),
+    (json!({ "X-Request-Source": "zespół" }), "ASCII"),
+    (json!({ "X-Request-Source": "a\nb" }), "ASCII"),
+    (json!({ "X Bad": "a" }), "nazwa"),
+  ] {
+    write_config(dir, headers);
+    let err = config::reload(dir).unwrap_err().to_string();
+    assert!(err.contains("custom_headers") && err.contains(needle), "{}", err);
+  }
+  write_config(dir, json!({ "X-Request-Source": "ytlite ci/1.0" }));
+  assert!(config::reload(dir).unwrap().changed.contains(&"custom_headers".to_string()));
+}
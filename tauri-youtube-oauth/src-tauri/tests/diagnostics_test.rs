@@ -0,0 +1,50 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{diagnostics, CheckStatus};
+
+#[tokio::test]
+async fn report_covers_checklist_without_secrets() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("OAUTH_TOKENINFO_URL", format!("{}/tokeninfo", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  server.mock("HEAD", Matcher::Any).with_status(404).create_async().await;
+  server
+    .mock("GET", "/tokeninfo")
+    .match_query(Matcher::Any)
+    .with_body(r#"{"expires_in":"1800","scope":"https://www.googleapis.com/auth/youtube"}"#)
+    .create_async()
+    .await;
+  server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::Any)
+    .with_body(r#"{"pageInfo":{"totalResults":1},"items":[{"id":"UC1"}]}"#)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  fs::write(tmp.path().join("oauth_config.json"), r#"{"client_id":"id","client_secret":"client-secret-value"}"#).unwrap();
+  let tokens = serde_json::json!({
+    "access_token": "access-value",
+    "refresh_token": "refresh-value",
+    "expires_in": 3600,
+    "created_at": 4_102_444_800u64,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(tmp.path().join("tokens.json"), tokens.to_string()).unwrap();
+
+  let out = tmp.path().join("diagnostics.zip");
+  let report = diagnostics::export(tmp.path(), &out).await.unwrap();
+  let status = |id: &str| report.checks.iter().find(|c| c.id == id).map(|c| c.status);
+  for id in ["config", "config_dir_writable", "token_endpoint", "tokens", "token_valid", "hosted_domain", "channels_list", "quota"] {
+    assert_eq!(status(id), Some(CheckStatus::Pass), "{}: {:?}", id, report.checks);
+  }
+  for id in ["loopback_port", "dns", "clock_skew"] {
+    assert!(status(id).is_some(), "{} missing", id);
+  }
+  let json = serde_json::to_string(&report).unwrap();
+  for secret in ["client-secret-value", "access-value", "refresh-value"] {
+    assert!(!json.contains(secret));
+  }
+  assert!(fs::read(&out).unwrap().starts_with(b"PK"));
+}
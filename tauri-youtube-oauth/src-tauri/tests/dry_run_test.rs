@@ -0,0 +1,142 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{
+  channel,
+  error::Error,
+  history, live, metrics,
+  queue::{self, EntryStatus, QueueEntry},
+  sections,
+  status_file::MIN_INTERVAL,
+  upload, BroadcastOptions, ForceCode, Privacy, VideoMetadata,
+};
+
+fn seed_dir(dir: &std::path::Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret","dry_run":true}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "access",
+    "refresh_token": "refresh",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+fn meta(title: &str) -> VideoMetadata {
+  VideoMetadata {
+    title: title.into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  }
+}
+
+#[tokio::test]
+async fn dry_run_sends_no_mutating_requests() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let reads = server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::Any)
+    .with_body(r#"{"items":[{"id":"UC1","brandingSettings":{"channel":{"title":"Kanał","keywords":"rust"}}}]}"#)
+    .expect_at_least(1)
+    .create_async()
+    .await;
+  let mut writes = Vec::new();
+  for method in ["POST", "PUT", "DELETE"] {
+    writes.push(server.mock(method, Matcher::Any).expect(0).create_async().await);
+  }
+
+  let tmp = tempfile::tempdir().unwrap();
+  seed_dir(tmp.path());
+  let dir = tmp.path();
+
+  let start = "2999-01-01T18:00:00Z";
+  match live::create_broadcast(dir, "Premiera", start, Privacy::Unlisted, &BroadcastOptions::default()).await {
+    Err(Error::DryRun(plan)) => {
+      assert_eq!(plan.method, "POST");
+      assert!(plan.url.contains("/youtube/v3/liveBroadcasts"));
+      assert!(plan.body_summary.contains("Premiera"));
+      assert_eq!(plan.estimated_quota, 50);
+    }
+    other => panic!("expected dry-run plan, got {:?}", other),
+  }
+
+  // Read-modify-write: the read goes out, the write is only planned.
+  match channel::set_channel_description(dir, "Nowy opis").await {
+    Err(Error::DryRun(plan)) => {
+      assert_eq!(plan.method, "PUT");
+      assert!(plan.body_summary.contains("keywords"), "untouched branding keys are kept: {}", plan.body_summary);
+    }
+    other => panic!("expected dry-run plan, got {:?}", other),
+  }
+
+  assert!(matches!(sections::delete_section(dir, "sec1").await, Err(Error::DryRun(_))));
+  assert!(matches!(channel::unset_watermark(dir).await, Err(Error::DryRun(_))));
+
+  // A planned upload isn't a failed one: no history record, and nothing in
+  // /metrics or status.json.
+  let cfg = r#"{"client_id":"id","client_secret":"secret","dry_run":true,"status_file":{"path":"status.json"}}"#;
+  fs::write(dir.join("oauth_config.json"), cfg).unwrap();
+  let file = dir.join("film.mp4");
+  fs::write(&file, vec![1u8; 1024]).unwrap();
+  match upload::upload_video(dir, &file, &meta("Film"), &[ForceCode::RecentlyModified]).await {
+    Err(Error::DryRun(plan)) => {
+      assert_eq!(plan.method, "POST");
+      assert!(plan.url.contains("/upload/youtube/v3/videos"), "{}", plan.url);
+    }
+    other => panic!("expected dry-run plan, got {:?}", other),
+  }
+  assert!(history::read_all(dir).is_empty());
+  let outcomes = metrics::snapshot(dir, false).outcomes;
+  assert_eq!((outcomes.uploads_succeeded, outcomes.uploads_failed), (0, 0));
+  tokio::time::sleep(MIN_INTERVAL * 2).await;
+  let status: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("status.json")).unwrap()).unwrap();
+  assert!(status["last_error"].is_null(), "{}", status);
+
+  // A full queue run only plans: every entry stays pending without an
+  // attempt, and each plan comes as queue-entry-planned.
+  let sink = serde_json::json!({ "type": "file", "path": "events.jsonl", "events": ["queue-entry-planned"] });
+  let cfg = serde_json::json!({ "client_id": "id", "client_secret": "secret", "dry_run": true, "upload_min_file_age_secs": 0, "event_sinks": [sink] });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  for name in ["first.mp4", "second.mp4"] {
+    fs::write(dir.join(name), b"not really a video").unwrap();
+    queue::add(dir, &dir.join(name).to_string_lossy(), Some(meta(name))).unwrap();
+  }
+  let state = |entries: Vec<QueueEntry>| -> Vec<_> {
+    entries.into_iter().map(|e| (e.id, e.status, e.attempts, e.attempt_history, e.error)).collect()
+  };
+  let before = state(queue::list(dir));
+  assert_eq!(queue::process_due(dir).await.unwrap(), 2);
+  assert_eq!(state(queue::list(dir)), before);
+  assert!(before.iter().all(|(_, status, ..)| *status == EntryStatus::Pending));
+  let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+  let planned = loop {
+    let text = fs::read_to_string(dir.join("events.jsonl")).unwrap_or_default();
+    let lines: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    if lines.len() >= 2 || std::time::Instant::now() > deadline {
+      break lines;
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+  };
+  assert_eq!(planned.len(), 2, "{:?}", planned);
+  for (line, (id, ..)) in planned.iter().zip(&before) {
+    assert_eq!(line["event"], "queue-entry-planned");
+    assert_eq!(line["payload"]["id"], id.as_str());
+    assert_eq!(line["payload"]["plan"]["method"], "POST");
+  }
+  assert!(history::read_all(dir).is_empty());
+
+  reads.assert_async().await;
+  for w in writes {
+    w.assert_async().await;
+  }
+  // Planned calls don't spend quota.
+  assert!(tauri_youtube_oauth::quota::status(dir).used <= 2);
+}
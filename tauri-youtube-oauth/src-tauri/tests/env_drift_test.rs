@@ -0,0 +1,71 @@
+use std::fs;
+use tauri_youtube_oauth::{env_drift, env_file};
+
+fn seed(dir: &std::path::Path, secret: &str, refresh: Option<&str>) {
+  let cfg = serde_json::json!({ "client_id": "id", "client_secret": secret, "channel_id": "UC1" });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  match refresh {
+    Some(r) => fs::write(dir.join("tokens.json"), serde_json::json!({ "access_token": "ya29.new", "refresh_token": r, "expires_in": 3600, "created_at": 1 }).to_string()).unwrap(),
+    None => {
+      let _ = fs::remove_file(dir.join("tokens.json"));
+    }
+  }
+}
+
+#[test]
+fn merge_keeps_comments_order_and_unmanaged_lines() {
+  let text = "# deploy\nexport YOUTUBE_CLIENT_ID=old\nUPLOAD_PRIVACY=public\nYOUTUBE_ACCESS_TOKEN=x\n";
+  let merged = env_file::merge_dotenv(text, &[("YOUTUBE_CLIENT_ID", "new id".into()), ("YOUTUBE_CHANNEL_ID", "UC1".into())], &["YOUTUBE_ACCESS_TOKEN"]);
+  assert_eq!(merged, "# deploy\nexport YOUTUBE_CLIENT_ID='new id'\nUPLOAD_PRIVACY=public\nYOUTUBE_CHANNEL_ID=UC1\n");
+}
+
+#[test]
+fn rotated_secret_shows_as_stale_by_name_only_and_syncs() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let env = dir.join(".env");
+  fs::write(&env, "# ytlite\nYOUTUBE_CLIENT_ID=id\nYOUTUBE_CLIENT_SECRET=old-secret\nUPLOAD_PRIVACY=unlisted\nYOUTUBE_ACCESS_TOKEN=ya29.old\nYOUTUBE_REFRESH_TOKEN=1//old\n").unwrap();
+
+  seed(dir, "new-secret", Some("1//old"));
+  let drift = env_drift::check(dir, &env).unwrap();
+  assert_eq!(drift.stale, ["YOUTUBE_CLIENT_SECRET"]);
+  assert_eq!(drift.missing, ["YOUTUBE_CHANNEL_ID"]);
+  assert_eq!(drift.matching, ["YOUTUBE_CLIENT_ID", "YOUTUBE_ACCESS_TOKEN", "YOUTUBE_REFRESH_TOKEN"]);
+  assert!(!drift.in_sync);
+  let shown = serde_json::to_string(&drift).unwrap();
+  assert!(!shown.contains("new-secret") && !shown.contains("1//old"));
+
+  // A new login rotated the refresh token too: both tokens are rewritten.
+  seed(dir, "new-secret", Some("1//new"));
+  let result = env_drift::sync(dir, &env).unwrap();
+  assert_eq!(result.updated, ["YOUTUBE_CLIENT_SECRET", "YOUTUBE_REFRESH_TOKEN", "YOUTUBE_CHANNEL_ID", "YOUTUBE_ACCESS_TOKEN"]);
+  let text = fs::read_to_string(&env).unwrap();
+  assert!(text.starts_with("# ytlite\nYOUTUBE_CLIENT_ID=id\nYOUTUBE_CLIENT_SECRET=new-secret\nUPLOAD_PRIVACY=unlisted\n"), "{}", text);
+  assert!(text.contains("YOUTUBE_REFRESH_TOKEN=1//new") && text.contains("YOUTUBE_ACCESS_TOKEN=ya29.new"));
+  assert!(env_drift::check(dir, &env).unwrap().in_sync);
+
+  // Logged out: tokens left in the file are extra and go on sync.
+  seed(dir, "new-secret", None);
+  let drift = env_drift::check(dir, &env).unwrap();
+  assert_eq!(drift.extra, ["YOUTUBE_ACCESS_TOKEN", "YOUTUBE_REFRESH_TOKEN"]);
+  let result = env_drift::sync(dir, &env).unwrap();
+  assert_eq!(result.removed, drift.extra);
+  assert!(!fs::read_to_string(&env).unwrap().contains("TOKEN"));
+  assert!(env_drift::check(dir, &env).unwrap().in_sync);
+}
+
+#[cfg(unix)]
+#[test]
+fn sync_keeps_the_mode_of_the_env_file() {
+  use std::os::unix::fs::PermissionsExt;
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let env = dir.join(".env");
+  fs::write(&env, "YOUTUBE_CLIENT_ID=id\nYOUTUBE_CLIENT_SECRET=old-secret\n").unwrap();
+  fs::set_permissions(&env, fs::Permissions::from_mode(0o600)).unwrap();
+  seed(dir, "new-secret", None);
+
+  assert_eq!(env_drift::sync(dir, &env).unwrap().updated, ["YOUTUBE_CLIENT_SECRET", "YOUTUBE_CHANNEL_ID"]);
+  assert!(fs::read_to_string(&env).unwrap().contains("YOUTUBE_CLIENT_SECRET=new-secret"));
+  assert_eq!(fs::metadata(&env).unwrap().permissions().mode() & 0o777, 0o600);
+}
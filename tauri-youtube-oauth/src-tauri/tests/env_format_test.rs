@@ -0,0 +1,69 @@
+use tauri_youtube_oauth::{
+  env_file::{self, parse_dotenv},
+  AppConfig, EnvChannel, EnvFormat, Tokens,
+};
+
+const TRICKY: &[&str] = &[
+  "plain-Value_1",
+  "has space",
+  "hash#and$dollar",
+  "it's \"quoted\"",
+  "back\\slash`tick",
+  "multi\nline",
+  "",
+  "Zażółć gęślą jaźń 🎬",
+  "line\u{2028}separator\u{7}bell",
+];
+
+fn fixture(secret: &str) -> (AppConfig, Tokens) {
+  let cfg = AppConfig { client_id: "id.apps.googleusercontent.com".into(), client_secret: secret.into(), ..Default::default() };
+  let t = Tokens { access_token: format!("ya29.{}", secret), refresh_token: "1//r' $x".into(), ..Default::default() };
+  (cfg, t)
+}
+
+#[test]
+fn dotenv_round_trips_byte_for_byte() {
+  for secret in TRICKY {
+    let (cfg, t) = fixture(secret);
+    let channel = EnvChannel::Known("UC 1".into());
+    for format in [EnvFormat::DotEnv, EnvFormat::DotEnvNoComments] {
+      let text = env_file::render(&cfg, Some(&t), &channel, format);
+      let vars = parse_dotenv(&text);
+      let get = |k: &str| vars.iter().find(|(key, _)| key == k).map(|(_, v)| v.as_str());
+      assert_eq!(get("YOUTUBE_CLIENT_SECRET"), Some(*secret), "{:?}", text);
+      assert_eq!(get("YOUTUBE_CHANNEL_ID"), Some("UC 1"));
+      let refresh = get("YOUTUBE_REFRESH_TOKEN");
+      match format {
+        EnvFormat::DotEnv => assert_eq!(refresh, None),
+        _ => assert_eq!(refresh, Some("1//r' $x")),
+      }
+    }
+    let text = env_file::render(&cfg, Some(&t), &channel, EnvFormat::DotEnvNoComments);
+    assert!(text.lines().all(|l| !l.starts_with('#') && !l.is_empty()));
+  }
+}
+
+#[test]
+fn json_is_flat_object_of_variables() {
+  let (cfg, t) = fixture("s\"e#c");
+  let json: serde_json::Value =
+    serde_json::from_str(&env_file::render(&cfg, Some(&t), &EnvChannel::Unknown, EnvFormat::Json)).unwrap();
+  assert_eq!(json["YOUTUBE_CLIENT_SECRET"], "s\"e#c");
+  assert_eq!(json["AUTO_UPLOAD"], "false");
+  assert!(json.get("YOUTUBE_CHANNEL_ID").is_none());
+  assert!(json.as_object().unwrap().values().all(|v| v.is_string()));
+  let without = env_file::render(&cfg, None, &EnvChannel::Unknown, EnvFormat::Json);
+  assert!(!without.contains("YOUTUBE_REFRESH_TOKEN"));
+}
+
+#[cfg(unix)]
+#[test]
+fn shell_exports_survive_sourcing() {
+  for secret in TRICKY {
+    let (cfg, t) = fixture(secret);
+    let text = env_file::render(&cfg, Some(&t), &EnvChannel::Unknown, EnvFormat::ShellExports);
+    let script = format!("{}printf %s \"$YOUTUBE_CLIENT_SECRET\"", text);
+    let out = std::process::Command::new("sh").arg("-c").arg(script).output().unwrap();
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), *secret, "{}", text);
+  }
+}
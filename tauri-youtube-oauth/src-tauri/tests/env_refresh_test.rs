@@ -0,0 +1,63 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{env_file, read_tokens_from_dir, EnvFormat};
+
+fn seed(dir: &Path, refresh_token: &str, created_at: u64) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret","channel_id":"UC1"}"#).unwrap();
+  let tokens = json!({ "access_token": "ya29.old", "refresh_token": refresh_token, "expires_in": 3600, "created_at": created_at, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn stale_access_tokens_are_refreshed_before_going_into_the_env() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+
+  // Expired an hour ago: refreshed, and the new expiry is written next to it.
+  let ok = server
+    .mock("POST", "/token")
+    .match_body(Matcher::UrlEncoded("refresh_token".into(), "1//good".into()))
+    .with_body(json!({ "access_token": "ya29.new", "expires_in": 3600, "scope": "https://www.googleapis.com/auth/youtube" }).to_string())
+    .expect(1)
+    .create_async()
+    .await;
+  let fresh = tempfile::tempdir().unwrap();
+  seed(fresh.path(), "1//good", now - 7200);
+  let json = env_file::generate(fresh.path(), true, None, EnvFormat::Json).await.unwrap();
+  ok.assert_async().await;
+  let vars: serde_json::Value = serde_json::from_str(&json).unwrap();
+  assert_eq!(vars["YOUTUBE_ACCESS_TOKEN"], "ya29.new");
+  let stored = read_tokens_from_dir(fresh.path()).unwrap();
+  assert!(stored.created_at >= now);
+  let expires_at = chrono::DateTime::from_timestamp((stored.created_at + 3600) as i64, 0).unwrap();
+  assert_eq!(vars["YOUTUBE_TOKEN_EXPIRES_AT"], expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+  // Still good for most of an hour: nothing is refreshed.
+  seed(fresh.path(), "1//good", now);
+  let text = env_file::generate(fresh.path(), true, None, EnvFormat::ShellExports).await.unwrap();
+  assert!(text.contains("ya29.old"), "{}", text);
+  ok.assert_async().await;
+
+  // The refresh is refused: only the refresh token goes out, with the reason.
+  let refused = server
+    .mock("POST", "/token")
+    .match_body(Matcher::UrlEncoded("refresh_token".into(), "1//revoked".into()))
+    .with_status(400)
+    .with_body(json!({ "error": "invalid_grant", "error_description": "Token has been expired or revoked." }).to_string())
+    .expect(1)
+    .create_async()
+    .await;
+  let stale = tempfile::tempdir().unwrap();
+  seed(stale.path(), "1//revoked", now - 7200);
+  let text = env_file::generate(stale.path(), true, None, EnvFormat::DotEnv).await.unwrap();
+  refused.assert_async().await;
+  assert!(!text.contains("ya29.old") && !text.contains("YOUTUBE_TOKEN_EXPIRES_AT"), "{}", text);
+  assert!(text.contains("# YOUTUBE_ACCESS_TOKEN omitted: it had expired and refreshing failed"), "{}", text);
+  assert!(text.contains("YOUTUBE_REFRESH_TOKEN=1//revoked"), "{}", text);
+  let json = env_file::generate(stale.path(), true, None, EnvFormat::Json).await.unwrap();
+  let vars: serde_json::Value = serde_json::from_str(&json).unwrap();
+  assert_eq!(vars["YOUTUBE_REFRESH_TOKEN"], "1//revoked");
+  assert!(vars.get("YOUTUBE_ACCESS_TOKEN").is_none(), "{}", json);
+}
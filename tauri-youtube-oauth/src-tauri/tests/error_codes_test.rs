@@ -0,0 +1,90 @@
+use std::{collections::BTreeSet, fs, path::Path};
+use tauri_youtube_oauth::{
+  error::{error_codes, CommandError, Error},
+  format::Language,
+  guidance, DryRunPlan, PreflightReason, Rejection, RejectionKind, Severity, SidecarDiagnostic, TokenEndpointError, WorkspaceReason,
+};
+
+/// One instance of every `Error` variant; extend when adding a variant.
+fn every_variant() -> Vec<Error> {
+  vec![
+    Error::Config("Brak konfiguracji klienta".into()),
+    Error::Auth("Brak tokenów — zaloguj się".into()),
+    Error::GrantRevoked { hint: Some("Aplikacja OAuth jest prawdopodobnie w trybie testowym".into()) },
+    Error::InsufficientScope { missing: vec!["https://www.googleapis.com/auth/youtube".into()], reconsent: false },
+    Error::Network("connection refused".into()),
+    Error::Forbidden { reason: "liveStreamingNotEnabled".into(), message: "Transmisje na żywo nie są włączone".into() },
+    Error::Api { status: 503, reason: "backendError".into(), message: "Backend Error".into() },
+    Error::QuotaExceeded { needed: 100, remaining: 40 },
+    Error::Validation("Tytuł nie może być pusty".into()),
+    Error::Storage("permission denied".into()),
+    Error::DryRun(DryRunPlan {
+      method: "DELETE".into(),
+      url: "https://www.googleapis.com/youtube/v3/channelSections?id=s1".into(),
+      body_summary: String::new(),
+      estimated_quota: 50,
+      publish_at: None,
+      video_links: Vec::new(),
+    }),
+    Error::Offline,
+    Error::TimedOut { operation: "oauth_token_refresh".into(), after_secs: 30 },
+    Error::NoChannelForAccount { email: Some("user@example.com".into()) },
+    Error::RefreshBackoff { retry_at: 1_700_000_000 },
+    Error::Open { target: "/home/user/.config/ytlite".into(), message: "No such file or directory".into() },
+    Error::PassphraseRejected,
+    Error::PreflightFailed { reason: PreflightReason::StillGrowing, message: "Plik /renders/a.mp4 jest jeszcze zapisywany".into() },
+    Error::ConsentFailed(Box::new(guidance::for_callback("admin_policy_enforced", None, None, Language::Pl))),
+    Error::WorkspaceRestricted {
+      reason: WorkspaceReason::OrgInternal,
+      guidance: Box::new(guidance::for_token_error("org_internal", None, Language::Pl)),
+    },
+    Error::ReadOnlyMode,
+    Error::SidecarInvalid {
+      path: "/renders/a.json".into(),
+      diagnostics: vec![SidecarDiagnostic {
+        severity: Severity::Error,
+        field: Some("privacy".into()),
+        message: "Nieprawidłowa wartość privacy: publik (dozwolone: public, unlisted, private)".into(),
+        suggestion: Some("public".into()),
+        line: Some(3),
+        column: Some(3),
+      }],
+    },
+    Error::StorageBusy { path: "/home/user/.config/ytlite/.lock".into(), waited_ms: 2000 },
+    Error::NotSupportedByApi {
+      operation: "pin_comment".into(),
+      message: "YouTube API nie pozwala przypiąć komentarza — przypnij go w YouTube Studio: https://studio.youtube.com/video/vid1/comments".into(),
+    },
+    Error::TokenEndpoint(Box::new(TokenEndpointError {
+      status: 400,
+      error: Some("unauthorized_client".into()),
+      error_description: Some("Unauthorized".into()),
+      error_uri: None,
+      problem: None,
+    })),
+    Error::VideoRejected {
+      video_id: "vid1".into(),
+      rejection: Rejection { kind: RejectionKind::Rejected, reason: Some("duplicate".into()), detected_at: 1_700_000_000 },
+    },
+    Error::TlsInterceptionSuspected { host: "oauth2.googleapis.com".into() },
+  ]
+}
+
+#[test]
+fn command_errors_match_snapshot() {
+  let errors: Vec<CommandError> = every_variant().into_iter().map(CommandError::from).collect();
+  let actual = serde_json::to_value(&errors).unwrap();
+  let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/command_errors.json");
+  if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+    fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+  }
+  let expected: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+  assert_eq!(actual, expected, "CommandError JSON changed; update the frontend, then rerun with UPDATE_SNAPSHOTS=1");
+}
+
+#[test]
+fn every_code_is_documented() {
+  let documented: BTreeSet<&str> = error_codes().iter().map(|c| c.code).collect();
+  let used: BTreeSet<&str> = every_variant().iter().map(Error::code).collect();
+  assert_eq!(used, documented);
+}
@@ -0,0 +1,102 @@
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tauri_youtube_oauth::events::Dispatcher;
+
+type Sent = Vec<(String, Value)>;
+
+fn recorder(sent: &mut Sent) -> impl FnMut(&str, Value) + '_ {
+  |event, payload| sent.push((event.to_string(), payload))
+}
+
+fn ms(n: u64) -> Duration {
+  Duration::from_millis(n)
+}
+
+#[test]
+fn progress_is_coalesced_to_the_latest_payload() {
+  let start = Instant::now();
+  let mut d = Dispatcher::new(5);
+  let mut sent = Sent::new();
+  let mut due = None;
+  // 100 updates a second for one second.
+  for i in 0..100u64 {
+    due = due.or(d.progress(start + ms(i * 10), "a.mp4", "upload-progress", json!({ "bytes_sent": i }), &mut recorder(&mut sent)));
+    if let Some(at) = due.filter(|at| start + ms(i * 10) >= *at) {
+      due = d.flush_due(at, &mut recorder(&mut sent));
+    }
+  }
+  assert!(sent.len() <= 6, "{} events sent", sent.len());
+  assert_eq!(sent[0].1["bytes_sent"], 0);
+  // The last update is never dropped, only delayed.
+  let at = due.expect("the last update is held");
+  assert_eq!(d.flush_due(at, &mut recorder(&mut sent)), None);
+  assert_eq!(sent.last().unwrap().1["bytes_sent"], 99);
+  let sent_at: Vec<u64> = sent.iter().map(|(_, p)| p["bytes_sent"].as_u64().unwrap()).collect();
+  assert!(sent_at.windows(2).all(|w| w[0] < w[1]), "in order: {:?}", sent_at);
+
+  // Keys are limited separately.
+  let mut d = Dispatcher::new(5);
+  let mut sent = Sent::new();
+  d.progress(start, "a.mp4", "upload-progress", json!(1), &mut recorder(&mut sent));
+  d.progress(start, "b.mp4", "upload-progress", json!(2), &mut recorder(&mut sent));
+  assert_eq!(sent.len(), 2);
+}
+
+#[test]
+fn a_transition_is_never_seen_before_the_progress_it_supersedes() {
+  let start = Instant::now();
+  let mut d = Dispatcher::new(5);
+  let mut sent = Sent::new();
+  d.progress(start, "a.mp4", "upload-progress", json!({ "bytes_sent": 10 }), &mut recorder(&mut sent));
+  d.progress(start + ms(20), "b.mp4", "upload-progress", json!({ "bytes_sent": 1 }), &mut recorder(&mut sent));
+  d.progress(start + ms(50), "a.mp4", "upload-progress", json!({ "bytes_sent": 90 }), &mut recorder(&mut sent));
+  d.progress(start + ms(60), "b.mp4", "upload-progress", json!({ "bytes_sent": 2 }), &mut recorder(&mut sent));
+  d.progress(start + ms(80), "a.mp4", "upload-progress", json!({ "bytes_sent": 100 }), &mut recorder(&mut sent));
+  d.transition("a.mp4", "queue-entry-failed", json!({ "file_path": "a.mp4" }), &mut recorder(&mut sent));
+
+  let events: Vec<(&str, &Value)> = sent.iter().map(|(e, p)| (e.as_str(), p)).collect();
+  assert_eq!(
+    events,
+    [
+      ("upload-progress", &json!({ "bytes_sent": 10 })),
+      ("upload-progress", &json!({ "bytes_sent": 1 })),
+      ("upload-progress", &json!({ "bytes_sent": 100 })),
+      ("queue-entry-failed", &json!({ "file_path": "a.mp4" })),
+    ]
+  );
+  // Nothing of the finished key is left to arrive late; the other key still is.
+  let mut late = Sent::new();
+  d.flush_due(start + ms(1000), &mut recorder(&mut late));
+  assert_eq!(late, [("upload-progress".to_string(), json!({ "bytes_sent": 2 }))]);
+
+  // A new upload of the same file starts afresh.
+  let mut sent = Sent::new();
+  assert_eq!(d.progress(start + ms(90), "a.mp4", "upload-progress", json!(0), &mut recorder(&mut sent)), None);
+  assert_eq!(sent.len(), 1);
+}
+
+#[test]
+fn a_broadcast_follows_all_held_progress() {
+  let start = Instant::now();
+  let mut d = Dispatcher::new(5);
+  let mut sent = Sent::new();
+  for (i, key) in ["a.mp4", "b.mp4", "a.mp4", "b.mp4"].iter().enumerate() {
+    d.progress(start + ms(i as u64), key, "upload-progress", json!({ "file": key, "step": i }), &mut recorder(&mut sent));
+  }
+  d.broadcast("queue-changed", json!([]), &mut recorder(&mut sent));
+  let events: Vec<&str> = sent.iter().map(|(e, _)| e.as_str()).collect();
+  assert_eq!(events, ["upload-progress", "upload-progress", "upload-progress", "upload-progress", "queue-changed"]);
+  assert_eq!(sent[2].1["step"], 2);
+  assert_eq!(sent[3].1["step"], 3);
+  assert_eq!(d.flush_due(start + ms(1000), &mut recorder(&mut sent)), None);
+  assert_eq!(sent.len(), 5);
+
+  // A different progress event for a key isn't swallowed by the next one.
+  let mut d = Dispatcher::new(5);
+  let mut sent = Sent::new();
+  d.progress(start, "list", "inventory-progress", json!({ "phase": "listing", "done": 50 }), &mut recorder(&mut sent));
+  d.progress(start + ms(10), "list", "inventory-progress", json!({ "phase": "listing", "done": 100 }), &mut recorder(&mut sent));
+  d.progress(start + ms(300), "list", "other-progress", json!({}), &mut recorder(&mut sent));
+  let done: Vec<&Value> = sent.iter().map(|(_, p)| &p["done"]).collect();
+  assert_eq!(done, [&json!(50), &json!(100), &Value::Null]);
+}
@@ -0,0 +1,82 @@
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::{config, queue, sinks, EventSink};
+
+fn write_config(dir: &Path, sinks: Value) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "event_sinks": sinks });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+async fn until<F: std::future::Future<Output = bool>>(what: &str, done: impl Fn() -> F) {
+  let deadline = Instant::now() + Duration::from_secs(10);
+  while !done().await {
+    assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+}
+
+#[test]
+fn filters_match_names_and_prefixes() {
+  let sink = |events: &[&str]| EventSink::File { path: "e.jsonl".into(), events: events.iter().map(|e| e.to_string()).collect() };
+  assert!(sink(&[]).accepts("queue-changed"));
+  assert!(sink(&["upload-*", "queue-changed"]).accepts("upload-progress"));
+  assert!(sink(&["upload-*", "queue-changed"]).accepts("queue-changed"));
+  assert!(!sink(&["upload-*", "queue-changed"]).accepts("queue-entry-failed"));
+  assert!(sinks::validate(&EventSink::Webhook { url: "ftp://x".into(), secret: None, events: vec![], timeout_secs: None }).is_err());
+}
+
+#[tokio::test]
+async fn events_reach_the_file_and_the_webhook_and_failures_stay_local() {
+  let mut server = mockito::Server::new_async().await;
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let video = dir.join("film.mp4");
+  fs::write(&video, b"x").unwrap();
+  let webhook = |url: String| json!({ "type": "webhook", "url": url, "secret": "s3cret", "events": ["queue-changed"] });
+  write_config(dir, json!([{ "type": "file", "path": "events.jsonl", "events": ["queue-changed", "config-*"] }, webhook(format!("{}/hook", server.url()))]));
+
+  // Retried, then given up; the queue doesn't notice.
+  let failing = server.mock("POST", "/hook").with_status(503).expect(sinks::WEBHOOK_ATTEMPTS as usize).create_async().await;
+  queue::add(dir, &video.to_string_lossy(), None).unwrap();
+  until("the retries", || async { failing.matched_async().await }).await;
+  failing.remove_async().await;
+
+  let delivered = server
+    .mock("POST", "/hook")
+    .match_header(sinks::SECRET_HEADER, "s3cret")
+    .match_body(Matcher::PartialJson(json!({ "event": "queue-changed" })))
+    .with_status(204)
+    .expect(1)
+    .create_async()
+    .await;
+  let id = queue::list(dir)[0].id.clone();
+  queue::remove(dir, &id).unwrap();
+  until("the delivery", || async { delivered.matched_async().await }).await;
+
+  // A config reload is not a queue event: only the file gets it. The sink
+  // that can't write is warned about, but not to the sinks again.
+  let broken = json!({ "type": "file", "path": "missing/dir/x.jsonl" });
+  let warnings = json!({ "type": "file", "path": "warnings.jsonl", "events": ["warning"] });
+  write_config(dir, json!([{ "type": "file", "path": "events.jsonl", "events": ["queue-changed", "config-*"] }, webhook(format!("{}/hook", server.url())), broken, warnings]));
+  config::reload(dir).unwrap();
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  delivered.assert_async().await;
+  assert!(!dir.join("warnings.jsonl").exists());
+
+  let lines: Vec<Value> = fs::read_to_string(dir.join("events.jsonl")).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+  let names: Vec<&str> = lines.iter().map(|l| l["event"].as_str().unwrap()).collect();
+  assert!(names.starts_with(&["queue-changed", "queue-changed"]), "{:?}", names);
+  assert!(names.contains(&"config-changed"), "{:?}", names);
+  assert_eq!(lines[0]["payload"][0]["file_path"], video.to_string_lossy().as_ref());
+  assert!(lines[1]["payload"].as_array().unwrap().is_empty());
+  assert!(lines.iter().all(|l| l["at"].as_u64().is_some()));
+
+  // The UI sees the sinks without the webhook secret.
+  let visible = config::visible(dir, false).unwrap();
+  assert!(matches!(&visible.event_sinks[1], EventSink::Webhook { secret: None, .. }));
+}
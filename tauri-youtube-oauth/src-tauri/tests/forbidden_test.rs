@@ -0,0 +1,68 @@
+use mockito::Matcher;
+use std::{fs, path::Path, time::Instant};
+use tauri_youtube_oauth::{
+  error::{CommandError, Error},
+  forbidden::{self, ForbiddenKind},
+  live, quota,
+};
+
+fn fixture(name: &str) -> String {
+  fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)).unwrap()
+}
+
+#[test]
+fn the_reason_decides_what_a_403_means() {
+  let required = ["https://www.googleapis.com/auth/youtube.force-ssl"];
+  // (captured body, kind, code, retryable)
+  let cases = [
+    ("forbidden_quota_exceeded.json", ForbiddenKind::Quota, "QUOTA_EXCEEDED", false),
+    ("forbidden_rate_limit.json", ForbiddenKind::RateLimit, "API_ERROR", true),
+    ("forbidden_insufficient_permissions.json", ForbiddenKind::Scope, "INSUFFICIENT_SCOPE", false),
+    ("thumbnails_forbidden.json", ForbiddenKind::Ineligible, "FORBIDDEN", false),
+  ];
+  for (body, kind, code, retryable) in cases {
+    let refusal = forbidden::parse(&fixture(body));
+    assert_eq!(refusal.kind, kind, "{}", body);
+    let err = CommandError::from(refusal.into_error(403, &required, 50));
+    assert_eq!((err.code, err.retryable), (code, retryable), "{}", body);
+  }
+
+  let scope = forbidden::parse(&fixture("forbidden_insufficient_permissions.json")).into_error(403, &required, 1);
+  assert!(matches!(scope, Error::InsufficientScope { ref missing, reconsent: false } if missing == &required));
+  match forbidden::parse(&fixture("thumbnails_forbidden.json")).into_error(403, &required, 50) {
+    Error::Forbidden { reason, message } => assert!(reason == "forbidden" && message.contains("miniatur"), "{}", message),
+    other => panic!("{:?}", other),
+  }
+  // Without a body there's nothing to retry or reauthorize for.
+  assert_eq!(forbidden::parse("").kind, ForbiddenKind::Ineligible);
+}
+
+#[tokio::test]
+async fn rate_limits_back_off_and_quota_stops_later_calls() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let streams = |server: &mut mockito::ServerGuard| server.mock("GET", "/youtube/v3/liveStreams").match_query(Matcher::Any);
+
+  let limited = streams(&mut server).with_status(403).with_body(fixture("forbidden_rate_limit.json")).expect(1).create_async().await;
+  let ok = streams(&mut server).with_body(r#"{"items":[]}"#).expect(1).create_async().await;
+  let started = Instant::now();
+  assert!(live::list_streams(dir).await.unwrap().is_empty());
+  assert!(started.elapsed().as_millis() >= 500);
+  limited.assert_async().await;
+  ok.assert_async().await;
+
+  let spent = streams(&mut server).with_status(403).with_body(fixture("forbidden_quota_exceeded.json")).expect(1).create_async().await;
+  let err = live::list_streams(dir).await.unwrap_err();
+  assert!(matches!(err, Error::QuotaExceeded { remaining: 0, .. }), "{:?}", err);
+  assert_eq!(quota::status(dir).remaining, 0);
+  // Known to be spent: the next call fails without asking.
+  assert!(matches!(live::list_streams(dir).await, Err(Error::QuotaExceeded { .. })));
+  spent.assert_async().await;
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use tauri_youtube_oauth::{
+  format::{self, Language},
+  progress::SpeedEstimator,
+  queue,
+  schedule::ScheduleOptions,
+};
+
+#[test]
+fn bytes_use_binary_units_and_the_language_decimal_separator() {
+  assert_eq!(format::bytes(0, Language::Pl), "0 B");
+  assert_eq!(format::bytes(1023, Language::En), "1023 B");
+  assert_eq!(format::bytes(1024, Language::En), "1.0 KiB");
+  assert_eq!(format::bytes(1_572_864, Language::Pl), "1,5 MiB");
+  assert_eq!(format::bytes(1_572_864, Language::En), "1.5 MiB");
+  // Just under a MiB rounds up into the next unit instead of "1024.0 KiB".
+  assert_eq!(format::bytes(1024 * 1024 - 10, Language::En), "1.0 MiB");
+  assert_eq!(format::bytes(u64::MAX, Language::En), "16.0 EiB");
+  assert_eq!(format::speed(2.5 * 1024.0 * 1024.0, Language::Pl), "2,5 MiB/s");
+  assert_eq!(format::speed(f64::NAN, Language::En), "0 B/s");
+}
+
+#[test]
+fn durations_show_the_two_largest_units() {
+  assert_eq!(format::duration(0.0, Language::En), "0 ms");
+  assert_eq!(format::duration(0.4504, Language::Pl), "450 ms");
+  assert_eq!(format::duration(0.9996, Language::En), "999 ms");
+  assert_eq!(format::duration(-3.0, Language::En), "0 ms");
+  assert_eq!(format::duration(5.0, Language::En), "5 s");
+  assert_eq!(format::duration(65.0, Language::En), "1 min 5 s");
+  assert_eq!(format::duration(3600.0, Language::En), "1 h");
+  assert_eq!(format::duration(3725.0, Language::Pl), "1 godz. 2 min");
+  assert_eq!(format::duration(90_061.0, Language::En), "1 d 1 h");
+  assert_eq!(format::duration(86_400.0 + 59.0, Language::En), "1 d");
+}
+
+#[test]
+fn eta_covers_unknown_zero_and_multi_day_estimates() {
+  assert_eq!(format::eta(None, Language::Pl), "obliczanie…");
+  assert_eq!(format::eta(None, Language::En), "estimating…");
+  assert_eq!(format::eta(Some(0), Language::Pl), "za chwilę");
+  assert_eq!(format::eta(Some(65), Language::Pl), "jeszcze 1 min 5 s");
+  assert_eq!(format::eta(Some(30 * 3600), Language::En), "1 d 6 h left");
+  assert_eq!(format::eta(Some(30 * 3600), Language::Pl), "jeszcze 1 d 6 godz.");
+}
+
+#[test]
+fn timestamps_keep_their_own_offset() {
+  assert_eq!(format::timestamp("2026-10-14T18:05:00+02:00", Language::Pl).unwrap(), "14 października 2026, 18:05");
+  assert_eq!(format::timestamp("2026-01-02T03:04:05Z", Language::En).unwrap(), "2 January 2026, 03:04");
+  assert!(format::timestamp("14.10.2026", Language::En).is_err());
+}
+
+#[test]
+fn payloads_carry_labels_next_to_raw_numbers() {
+  let mut speed = SpeedEstimator::new();
+  speed.sample(std::time::Duration::ZERO, 0);
+  speed.sample(std::time::Duration::from_secs(1), 1024 * 1024);
+  let p = speed.progress(1024 * 1024, 4 * 1024 * 1024);
+  let labels = format::progress_labels(&p, Language::En);
+  assert_eq!((labels.sent.as_str(), labels.total.as_str(), labels.speed.as_str()), ("1.0 MiB", "4.0 MiB", "1.0 MiB/s"));
+  assert_eq!(labels.eta, "estimating…", "the estimate is not stable after one interval");
+
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"s","language":"en"}"#).unwrap();
+  assert_eq!(format::language(dir), Language::En);
+  queue::add(dir, "/renders/a.mp4", None).unwrap();
+  let options = ScheduleOptions {
+    max_per_day: 1,
+    window_start_hour: 10,
+    window_end_hour: 12,
+    timezone: "UTC".into(),
+    start_date: chrono::NaiveDate::from_ymd_opt(2099, 3, 1),
+    schedule_publishing: false,
+  };
+  let plan = queue::set_schedule(dir, Some(options)).unwrap();
+  assert_eq!(plan.entries[0].start_local, "2099-03-01T10:00:00+00:00");
+  assert_eq!(plan.entries[0].start_label.as_deref(), Some("1 March 2099, 10:00"));
+}
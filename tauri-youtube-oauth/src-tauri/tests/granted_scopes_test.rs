@@ -0,0 +1,41 @@
+use std::{fs, sync::Arc};
+use tauri_youtube_oauth::{
+  auth,
+  callback::{routes, CallbackState},
+  scopes,
+};
+
+#[tokio::test]
+async fn login_granting_a_subset_reports_the_rest() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  // The upload box was unchecked on the consent screen.
+  server
+    .mock("POST", "/token")
+    .with_body(format!(r#"{{"access_token":"a","refresh_token":"r","expires_in":3600,"scope":"{}"}}"#, scopes::YOUTUBE_READONLY))
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = serde_json::json!({ "client_id": "id", "client_secret": "secret", "scopes": [scopes::YOUTUBE_READONLY, scopes::YOUTUBE_UPLOAD] });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let status = auth::status(dir);
+  assert_eq!(status.requested_scopes, [scopes::YOUTUBE_READONLY, scopes::YOUTUBE_UPLOAD]);
+  assert!(status.scopes.is_empty() && status.missing_scopes.is_empty());
+
+  let filter = routes(dir.to_path_buf(), Arc::new(CallbackState::new("st".into())));
+  let resp = warp::test::request().path("/callback?code=c&state=st").remote_addr("127.0.0.1:50000".parse().unwrap()).reply(&filter).await;
+  assert_eq!(resp.status().as_u16(), 200);
+
+  let status = auth::status(dir);
+  assert!(status.authenticated);
+  assert_eq!(status.scopes, [scopes::YOUTUBE_READONLY]);
+  assert_eq!(status.requested_scopes, [scopes::YOUTUBE_READONLY, scopes::YOUTUBE_UPLOAD]);
+  assert_eq!(status.missing_scopes, [scopes::YOUTUBE_UPLOAD]);
+
+  // A broader grant covers the narrower scopes.
+  let mut t = tauri_youtube_oauth::read_tokens_from_dir(dir).unwrap();
+  t.scope = scopes::YOUTUBE.into();
+  tauri_youtube_oauth::write_tokens_to_dir(dir, &t).unwrap();
+  assert!(auth::status(dir).missing_scopes.is_empty());
+}
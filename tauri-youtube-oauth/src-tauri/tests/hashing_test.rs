@@ -0,0 +1,121 @@
+use serde_json::{json, Value};
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+use blake3::hazmat::HasherExt;
+use tauri_youtube_oauth::{hashing, FileHash, HashAlgorithm};
+
+/// The byte pattern of the official BLAKE3 test vectors.
+fn pattern(len: usize) -> Vec<u8> {
+  (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn hash(dir: &Path, path: &Path, algorithm: HashAlgorithm) -> FileHash {
+  hashing::hash_with(dir, path, algorithm).unwrap()
+}
+
+#[test]
+fn both_algorithms_match_their_reference_digests() {
+  let tmp = tempfile::tempdir().unwrap();
+  let file = tmp.path().join("v.bin");
+  let blake3 = [
+    (0, "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"),
+    (1024, "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7"),
+    (1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"),
+    (5000, "ee78d92070de3df1c57c37002abf0a6b1a6589acdeef4d8ffac7cf3d9e8f2836"),
+    (1048583, "89541f1047f7a56806fe16efda4c2cdc45f141c838e413019f0124189fa55232"),
+  ];
+  for (len, expected) in blake3 {
+    fs::write(&file, pattern(len)).unwrap();
+    assert_eq!(hash(tmp.path(), &file, HashAlgorithm::Blake3).hash, expected, "{} bytes", len);
+  }
+  fs::write(&file, pattern(1048583)).unwrap();
+  assert_eq!(hash(tmp.path(), &file, HashAlgorithm::Sha256).hash, "9e037498ddbb955fba0752812031c14ba299a4875cb400e8b8c1d77b3962c90e");
+  fs::write(&file, b"abc").unwrap();
+  assert_eq!(hash(tmp.path(), &file, HashAlgorithm::Sha256).hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+  // The config picks the algorithm; blake3 without one.
+  assert_eq!(hashing::hash(tmp.path(), &file).unwrap().algorithm, HashAlgorithm::Blake3);
+  fs::write(tmp.path().join("oauth_config.json"), json!({ "client_id": "id", "client_secret": "s", "hash_algorithm": "sha256" }).to_string()).unwrap();
+  assert_eq!(hashing::hash(tmp.path(), &file).unwrap().algorithm, HashAlgorithm::Sha256);
+}
+
+#[test]
+fn an_interrupted_blake3_hash_resumes_from_its_checkpoint() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let sink = json!({ "type": "file", "path": "events.jsonl", "events": ["hash-progress"] });
+  fs::write(dir.join("oauth_config.json"), json!({ "client_id": "id", "client_secret": "s", "event_sinks": [sink] }).to_string()).unwrap();
+  let subtree = hashing::CHECKPOINT_BYTES as usize;
+  let suffix = b"the rest of the render".as_slice();
+  let file = dir.join("render.mp4");
+  fs::write(&file, [pattern(subtree).as_slice(), suffix].concat()).unwrap();
+  // Read in one go, the merged subtrees give the plain BLAKE3 hash.
+  let full = hash(dir, &file, HashAlgorithm::Blake3);
+  assert_eq!(full.hash, blake3::hash(&fs::read(&file).unwrap()).to_hex().as_str());
+  assert!(!dir.join("hash_progress.json").exists(), "a finished hash drops its checkpoint");
+  let (size, modified_ns) = (full.size, full.modified_ns.unwrap());
+
+  // A checkpoint after the first subtree, holding the chaining value of
+  // zeros: the result is then the hash of zeros and the rest, which shows
+  // the first subtree wasn't read.
+  let zeros = vec![0u8; subtree];
+  let mut zeros_hasher = blake3::Hasher::new();
+  zeros_hasher.update(&zeros);
+  let cv = blake3::Hash::from(zeros_hasher.finalize_non_root()).to_hex().to_string();
+  let checkpoint = |modified_ns: u64| {
+    let partial = json!({ "size": size, "modified_ns": modified_ns, "offset": subtree, "chaining_values": [cv], "updated_at": 1 });
+    fs::write(dir.join("hash_progress.json"), json!({ file.to_string_lossy(): partial }).to_string()).unwrap();
+  };
+  checkpoint(modified_ns);
+  let resumed = hash(dir, &file, HashAlgorithm::Blake3);
+  assert_eq!((resumed.resumed_from, resumed.from_library), (subtree as u64, false));
+  assert_eq!(resumed.hash, blake3::hash(&[zeros.as_slice(), suffix].concat()).to_hex().as_str());
+  assert!(!dir.join("hash_progress.json").exists(), "a finished hash drops its checkpoint");
+
+  // One for another mtime is for a different file; SHA-256 always starts over.
+  checkpoint(modified_ns + 1);
+  assert_eq!(hash(dir, &file, HashAlgorithm::Blake3).resumed_from, 0);
+  checkpoint(modified_ns);
+  assert_eq!(hash(dir, &file, HashAlgorithm::Sha256).resumed_from, 0);
+
+  let deadline = Instant::now() + Duration::from_secs(10);
+  let progress = loop {
+    let text = fs::read_to_string(dir.join("events.jsonl")).unwrap_or_default();
+    let lines: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    let finished = |l: &Value| l["payload"]["path"] == file.to_string_lossy().as_ref() && l["payload"]["hashed_bytes"] == size;
+    if let Some(line) = lines.into_iter().find(finished) {
+      break line;
+    }
+    assert!(Instant::now() < deadline, "no hash-progress");
+    std::thread::sleep(Duration::from_millis(20));
+  };
+  assert_eq!(progress["event"], "hash-progress");
+  assert_eq!(progress["payload"]["total_bytes"], size);
+}
+
+#[test]
+fn an_unchanged_file_is_taken_from_the_library() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let file = dir.join("render.mp4");
+  fs::write(&file, b"episode one").unwrap();
+  let fresh = hash(dir, &file, HashAlgorithm::Blake3);
+  assert!(!fresh.from_library);
+  // The stored key stands for the content: a file that wasn't read gets it back as is.
+  let entry = json!({
+    "video_id": "vid1", "original_path": fresh.path, "last_seen_path": fresh.path, "uploaded_at": 1,
+    "algorithm": "blake3", "size": fresh.size, "modified_ns": fresh.modified_ns,
+  });
+  fs::write(dir.join("library.json"), json!({ "00ff": entry }).to_string()).unwrap();
+  let known = hash(dir, &file, HashAlgorithm::Blake3);
+  assert_eq!((known.hash.as_str(), known.from_library), ("00ff", true));
+  // Only for the algorithm the entry was made with.
+  assert!(!hash(dir, &file, HashAlgorithm::Sha256).from_library);
+
+  fs::write(&file, b"episode one, recut").unwrap();
+  let changed = hash(dir, &file, HashAlgorithm::Blake3);
+  assert_eq!((changed.from_library, changed.hash.len()), (false, 64));
+}
@@ -0,0 +1,116 @@
+use std::{fs, sync::Arc, thread};
+use tauri_youtube_oauth::{
+  error::Error,
+  history::{self, ExportFormat, HistoryFilter, UploadRecord, UploadStatus},
+};
+
+/// 2024-03-01T00:00:00Z.
+const MARCH_1: u64 = 1_709_251_200;
+const DAY: u64 = 24 * 60 * 60;
+
+fn record(timestamp: u64, title: &str, status: UploadStatus) -> UploadRecord {
+  UploadRecord {
+    timestamp,
+    profile: "default".into(),
+    file_path: format!("/renders/{}.mp4", title),
+    file_hash: format!("hash-{}", title),
+    video_id: (status != UploadStatus::Failed).then(|| format!("vid-{}", title)),
+    title: title.into(),
+    privacy: "private".into(),
+    duration_secs: 42,
+    bytes: 1024,
+    status,
+    error: (status == UploadStatus::Failed).then(|| "Przekroczono limit".into()),
+    suspect: false,
+    rejection: None,
+    forced: None,
+  }
+}
+
+fn titles(filter: &HistoryFilter, dir: &std::path::Path) -> Vec<String> {
+  history::query(dir, filter).unwrap().records.into_iter().map(|r| r.title).collect()
+}
+
+#[test]
+fn concurrent_appends_keep_every_line_whole() {
+  let tmp = Arc::new(tempfile::tempdir().unwrap());
+  let workers: Vec<_> = (0..8)
+    .map(|w| {
+      let tmp = Arc::clone(&tmp);
+      thread::spawn(move || {
+        for i in 0..25 {
+          // Long titles make a torn write show up as an unreadable line.
+          let title = format!("{}-{}-{}", w, i, "x".repeat(4096));
+          history::append(tmp.path(), &record(MARCH_1 + i, &title, UploadStatus::Completed)).unwrap();
+        }
+      })
+    })
+    .collect();
+  for w in workers {
+    w.join().unwrap();
+  }
+  let text = fs::read_to_string(tmp.path().join("upload_history.jsonl")).unwrap();
+  assert_eq!(text.lines().count(), 200);
+  assert_eq!(history::read_all(tmp.path()).len(), 200);
+}
+
+#[test]
+fn query_filters_by_date_status_and_text_newest_first() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  history::append(dir, &record(MARCH_1 - 1, "Luty", UploadStatus::Completed)).unwrap();
+  history::append(dir, &record(MARCH_1, "Wiosna część 1", UploadStatus::Completed)).unwrap();
+  history::append(dir, &record(MARCH_1 + DAY / 2, "Wiosna część 2", UploadStatus::Failed)).unwrap();
+  history::append(dir, &record(MARCH_1 + DAY - 1, "Podsumowanie", UploadStatus::Completed)).unwrap();
+  history::append(dir, &record(MARCH_1 + DAY, "Drugi marca", UploadStatus::Rejected)).unwrap();
+
+  let all = history::query(dir, &HistoryFilter::default()).unwrap();
+  assert_eq!(all.total, 5);
+  assert_eq!(all.records[0].title, "Drugi marca");
+
+  // A bare date covers that whole UTC day; RFC3339 bounds are exact.
+  let march_1 = HistoryFilter { from: Some("2024-03-01".into()), to: Some("2024-03-01".into()), ..Default::default() };
+  assert_eq!(titles(&march_1, dir), ["Podsumowanie", "Wiosna część 2", "Wiosna część 1"]);
+  let exact =
+    HistoryFilter { from: Some("2024-03-01T12:00:00+00:00".into()), to: Some("2024-03-02T00:00:00Z".into()), ..Default::default() };
+  assert_eq!(titles(&exact, dir), ["Drugi marca", "Podsumowanie", "Wiosna część 2"]);
+
+  let failed = HistoryFilter { status: Some(UploadStatus::Failed), ..Default::default() };
+  assert_eq!(titles(&failed, dir), ["Wiosna część 2"]);
+  let text = HistoryFilter { text: Some("WIOSNA".into()), status: Some(UploadStatus::Completed), ..Default::default() };
+  assert_eq!(titles(&text, dir), ["Wiosna część 1"]);
+
+  let page = history::query(dir, &HistoryFilter { offset: 1, limit: Some(2), ..Default::default() }).unwrap();
+  assert_eq!((page.total, page.offset), (5, 1));
+  assert_eq!(page.records.iter().map(|r| r.title.as_str()).collect::<Vec<_>>(), ["Podsumowanie", "Wiosna część 2"]);
+
+  let bad = HistoryFilter { from: Some("1 marca".into()), ..Default::default() };
+  assert!(matches!(history::query(dir, &bad), Err(Error::Validation(m)) if m.contains("1 marca")));
+}
+
+#[test]
+fn export_writes_csv_and_json() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  history::append(dir, &record(MARCH_1, "Rust, \"od zera\"", UploadStatus::Completed)).unwrap();
+  history::append(dir, &record(MARCH_1 + 60, "Tauri", UploadStatus::Failed)).unwrap();
+
+  let csv_path = dir.join("history.csv");
+  assert_eq!(history::export(dir, &csv_path, ExportFormat::Csv).unwrap(), 2);
+  let csv = fs::read_to_string(&csv_path).unwrap();
+  let lines: Vec<&str> = csv.split("\r\n").collect();
+  assert_eq!(lines[0], "timestamp,profile,file_path,file_hash,video_id,title,privacy,duration_secs,bytes,status,error");
+  assert!(lines[1].starts_with("2024-03-01T00:00:00+00:00,default,"), "{}", lines[1]);
+  assert!(lines[1].contains(",\"Rust, \"\"od zera\"\"\",private,42,1024,completed,"), "{}", lines[1]);
+  assert!(lines[2].ends_with(",Tauri,private,42,1024,failed,Przekroczono limit"), "{}", lines[2]);
+  assert_eq!(lines.len(), 4, "a CRLF after every record");
+
+  let json_path = dir.join("history.json");
+  assert_eq!(history::export(dir, &json_path, ExportFormat::Json).unwrap(), 2);
+  let exported: Vec<UploadRecord> = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+  assert_eq!(
+    exported.iter().map(|r| (r.title.as_str(), r.status)).collect::<Vec<_>>(),
+    [("Rust, \"od zera\"", UploadStatus::Completed), ("Tauri", UploadStatus::Failed)]
+  );
+  assert_eq!(exported[1].error.as_deref(), Some("Przekroczono limit"));
+}
@@ -0,0 +1,82 @@
+use mockito::Matcher;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{config, error::Error, hooks, AppConfig, HookContext};
+
+fn write_hooks(dir: &Path, hooks: serde_json::Value) {
+  let cfg = serde_json::json!({ "client_id": "id", "client_secret": "secret", "post_upload_hooks": hooks });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+fn ctx() -> HookContext {
+  HookContext {
+    video_id: "vid1".into(),
+    title: r#"Odcinek "1" — start"#.into(),
+    url: "https://youtu.be/vid1".into(),
+    profile: "default".into(),
+  }
+}
+
+#[tokio::test]
+async fn webhooks_render_templates_and_failures_are_reported() {
+  let mut server = mockito::Server::new_async().await;
+  let discord = server
+    .mock("POST", "/discord")
+    .match_body(Matcher::Json(serde_json::json!({ "content": r#"Nowy film: Odcinek "1" — start https://youtu.be/vid1"# })))
+    .expect(1)
+    .create_async()
+    .await;
+  let plain = server
+    .mock("POST", "/plain")
+    .match_body(Matcher::PartialJson(serde_json::json!({ "video_id": "vid1", "profile": "default" })))
+    .with_status(500)
+    .expect(1)
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  write_hooks(
+    tmp.path(),
+    serde_json::json!([
+      { "type": "webhook", "url": format!("{}/discord", server.url()), "template": r#"{"content": "Nowy film: {{title}} {{url}}"}"# },
+      { "type": "webhook", "url": format!("{}/plain", server.url()) },
+    ]),
+  );
+  let outcomes = hooks::run_all(tmp.path(), &ctx()).await;
+  discord.assert_async().await;
+  plain.assert_async().await;
+  assert!(outcomes[0].ok, "{:?}", outcomes[0].error);
+  assert!(!outcomes[1].ok && outcomes[1].error.is_some());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn commands_get_metadata_but_not_the_environment_and_time_out() {
+  std::env::set_var("YTLITE_TEST_SECRET", "do-not-leak");
+  let tmp = tempfile::tempdir().unwrap();
+  write_hooks(
+    tmp.path(),
+    serde_json::json!([
+      { "type": "command", "program": "sh", "args": ["-c", r#"test "$YTLITE_VIDEO_ID" = vid1 && test -z "$YTLITE_TEST_SECRET""#] },
+      { "type": "command", "program": "sleep", "args": ["5"], "timeout_secs": 1 },
+      { "type": "command", "program": "/nonexistent/hook" },
+    ]),
+  );
+  let outcomes = hooks::run_all(tmp.path(), &ctx()).await;
+  assert!(outcomes[0].ok, "{:?}", outcomes[0].error);
+  assert!(!outcomes[1].ok && outcomes[1].duration_ms < 4000);
+  assert!(!outcomes[2].ok);
+
+  let sample = hooks::test(tmp.path(), 0).await.unwrap();
+  assert_eq!(sample.video_id, "TEST_VIDEO_ID");
+  assert!(matches!(hooks::test(tmp.path(), 3).await, Err(Error::Validation(_))));
+}
+
+#[test]
+fn invalid_hooks_fail_validation() {
+  let with_hooks = |hooks: serde_json::Value| -> AppConfig {
+    serde_json::from_value(serde_json::json!({ "client_id": "id", "client_secret": "secret", "post_upload_hooks": hooks })).unwrap()
+  };
+  assert!(config::validate(&with_hooks(serde_json::json!([{ "type": "webhook", "url": "https://example.com/hook" }]))).is_ok());
+  assert!(config::validate(&with_hooks(serde_json::json!([{ "type": "webhook", "url": "ftp://example.com/x" }]))).is_err());
+  assert!(config::validate(&with_hooks(serde_json::json!([{ "type": "webhook", "url": "https://example.com", "template": "{{title}" }]))).is_err());
+  assert!(config::validate(&with_hooks(serde_json::json!([{ "type": "command", "program": " " }]))).is_err());
+}
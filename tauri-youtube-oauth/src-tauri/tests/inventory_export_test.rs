@@ -0,0 +1,123 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{
+  inventory::{self, InventoryRow},
+  ExportFormat, InventoryResult, PageRequest,
+};
+
+#[test]
+fn parses_iso_durations() {
+  assert_eq!(inventory::iso_duration_secs("PT4M13S"), Some(253));
+  assert_eq!(inventory::iso_duration_secs("PT1H"), Some(3600));
+  assert_eq!(inventory::iso_duration_secs("P1DT2S"), Some(86_402));
+  assert_eq!(inventory::iso_duration_secs("P0D"), Some(0));
+  assert_eq!(inventory::iso_duration_secs("4M13S"), None);
+  assert_eq!(inventory::iso_duration_secs("PT4X"), None);
+}
+
+#[test]
+fn csv_joins_tags_and_leaves_missing_counts_empty() {
+  let row = InventoryRow {
+    video_id: "v1".into(),
+    title: "Tytuł, z przecinkiem".into(),
+    description: "Opis\nw dwóch liniach".into(),
+    tags: vec!["rust".into(), "tauri".into()],
+    category_id: Some("28".into()),
+    privacy: "public".into(),
+    publish_at: None,
+    published_at: Some("2024-03-01T10:00:00Z".into()),
+    duration_secs: Some(253),
+    views: Some(10),
+    likes: None,
+    comments: None,
+  };
+  let csv = inventory::render(&[row], ExportFormat::Csv).unwrap();
+  let mut lines = csv.split("\r\n");
+  assert!(lines.next().unwrap().starts_with("video_id,title,description,tags,"));
+  assert_eq!(
+    lines.next().unwrap(),
+    "v1,\"Tytuł, z przecinkiem\",\"Opis\nw dwóch liniach\",\"rust,tauri\",28,public,,2024-03-01T10:00:00Z,253,10,,"
+  );
+}
+
+fn video(id: &str) -> serde_json::Value {
+  serde_json::json!({
+    "id": id,
+    "snippet": { "title": format!("Film {}", id), "description": "", "publishedAt": "2024-03-01T10:00:00Z" },
+    "status": { "privacyStatus": "public" },
+    "statistics": { "viewCount": "7" },
+    "contentDetails": { "duration": "PT1M" },
+  })
+}
+
+#[tokio::test]
+async fn interrupted_export_resumes_from_the_partial_file() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let channel = serde_json::json!({ "items": [{
+    "id": "UC1",
+    "snippet": { "title": "Kanał" },
+    "contentDetails": { "relatedPlaylists": { "uploads": "UU1" } },
+    "statistics": { "videoCount": "60" },
+  }] });
+  server.mock("GET", "/youtube/v3/channels").match_query(Matcher::Any).with_body(channel.to_string()).create_async().await;
+  let ids: Vec<String> = (0..60).map(|i| format!("v{:02}", i)).collect();
+  let item = |id: &String| serde_json::json!({ "contentDetails": { "videoId": id } });
+  let first: Vec<_> = ids[..50].iter().map(item).collect();
+  let second: Vec<_> = ids[50..].iter().map(item).collect();
+  server
+    .mock("GET", "/youtube/v3/playlistItems")
+    .match_query(Matcher::Regex("playlistId=UU1&maxResults=50$".into()))
+    .with_body(serde_json::json!({ "items": first, "nextPageToken": "p2", "pageInfo": { "totalResults": 60 } }).to_string())
+    .create_async()
+    .await;
+  server
+    .mock("GET", "/youtube/v3/playlistItems")
+    .match_query(Matcher::Regex("pageToken=p2".into()))
+    .with_body(serde_json::json!({ "items": second }).to_string())
+    .create_async()
+    .await;
+  let batch1 = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::Regex("id=v00%2Cv01".into()))
+    .with_body(serde_json::json!({ "items": ids[..50].iter().map(|id| video(id)).collect::<Vec<_>>() }).to_string())
+    .expect(1)
+    .create_async()
+    .await;
+
+  let out = dir.join("inventory.json");
+  let InventoryResult::Estimate(estimate) = inventory::export_channel_inventory(dir, &out, ExportFormat::Json, false, &PageRequest::default()).await.unwrap() else {
+    panic!("expected an estimate");
+  };
+  assert_eq!((estimate.video_count, estimate.quota_units, estimate.already_exported), (60, 5, 0));
+
+  // The second batch isn't mocked: the run dies after writing the first one.
+  assert!(inventory::export_channel_inventory(dir, &out, ExportFormat::Json, true, &PageRequest::default()).await.is_err());
+  assert!(!out.exists());
+  assert_eq!(fs::read_to_string(dir.join("inventory.json.partial.jsonl")).unwrap().lines().count(), 50);
+
+  // Deleted videos (v59) are simply missing from the response.
+  let batch2 = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::Regex("id=v50%2C".into()))
+    .with_body(serde_json::json!({ "items": ids[50..59].iter().map(|id| video(id)).collect::<Vec<_>>() }).to_string())
+    .expect(1)
+    .create_async()
+    .await;
+  let result = inventory::export_channel_inventory(dir, &out, ExportFormat::Json, true, &PageRequest::default()).await.unwrap();
+  assert!(matches!(result, InventoryResult::Exported { rows: 59, resumed_rows: 50, .. }), "{:?}", result);
+  batch1.assert_async().await;
+  batch2.assert_async().await;
+  let rows: Vec<InventoryRow> = serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+  assert_eq!(rows.first().map(|r| r.video_id.as_str()), Some("v00"));
+  assert_eq!(rows.last().map(|r| (r.video_id.as_str(), r.views, r.duration_secs)), Some(("v58", Some(7), Some(60))));
+  assert!(!dir.join("inventory.json.partial.jsonl").exists());
+}
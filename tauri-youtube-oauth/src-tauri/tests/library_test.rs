@@ -0,0 +1,61 @@
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{hashing, library, HashAlgorithm};
+
+#[test]
+fn renamed_and_moved_files_keep_their_video() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().join("app");
+  let renders = tmp.path().join("renders");
+  fs::create_dir_all(&dir).unwrap();
+  fs::create_dir_all(renders.join("2024")).unwrap();
+  let first = renders.join("ep1.mp4");
+  let gone = renders.join("ep2.mp4");
+  fs::write(&first, b"episode one").unwrap();
+  fs::write(&gone, b"episode two").unwrap();
+  // Entries from before the algorithm was recorded: SHA-256, while new hashes are BLAKE3.
+  let sha256 = |path| hashing::hash_with(&dir, path, HashAlgorithm::Sha256).unwrap().hash;
+  let (h1, h2) = (sha256(&first), sha256(&gone));
+  let entry = |video: &str, path: &std::path::Path| {
+    let p = path.to_string_lossy();
+    json!({ "video_id": video, "original_path": p, "last_seen_path": p, "uploaded_at": 1700000000 })
+  };
+  fs::write(dir.join("library.json"), json!({ &h1: entry("vid1", &first), &h2: entry("vid2", &gone) }).to_string()).unwrap();
+  fs::remove_file(&gone).unwrap();
+
+  // Reorganized: same content, new name in a subdirectory.
+  let moved = renders.join("2024").join("Episode 1 (final).MP4");
+  fs::rename(&first, &moved).unwrap();
+  let found = library::locate_video_for_file(&dir, &moved).unwrap().unwrap();
+  assert_eq!((found.entry.video_id.as_str(), found.file_hash.as_str(), found.present), ("vid1", h1.as_str(), true));
+  assert_eq!(found.entry.algorithm, HashAlgorithm::Sha256);
+  assert_eq!(found.entry.original_path, first.to_string_lossy());
+  assert_eq!(found.entry.last_seen_path, moved.to_string_lossy());
+  let local = library::find_local_file_for_video(&dir, "vid1").unwrap();
+  assert_eq!(local.entry.last_seen_path, moved.to_string_lossy());
+  assert!(!library::find_local_file_for_video(&dir, "vid2").unwrap().present);
+  assert!(library::find_local_file_for_video(&dir, "other").is_none());
+
+  let fresh = renders.join("ep3.webm");
+  fs::write(&fresh, b"episode three").unwrap();
+  fs::write(renders.join("ep3.json"), b"{}").unwrap();
+  assert!(library::locate_video_for_file(&dir, &fresh).unwrap().is_none());
+
+  // Moved back by hand: reconcile notices it.
+  fs::rename(&moved, &first).unwrap();
+  let report = library::reconcile(&dir, &renders).unwrap();
+  assert_eq!(report.matched.len(), 1);
+  let m = &report.matched[0];
+  assert_eq!((m.video_id.as_str(), m.path.as_str()), ("vid1", &*first.to_string_lossy()));
+  assert_eq!(m.moved_from.as_deref(), Some(&*moved.to_string_lossy()));
+  // Only videos are hashed, not the sidecar.
+  assert_eq!(report.new, [fresh.to_string_lossy()]);
+  assert_eq!(report.missing_locally.iter().map(|m| m.entry.video_id.as_str()).collect::<Vec<_>>(), ["vid2"]);
+
+  // Nothing moved since: a second pass changes nothing.
+  let again = library::reconcile(&dir, &renders).unwrap();
+  assert!(again.matched[0].moved_from.is_none());
+  // A directory the missing file never lived in doesn't report it.
+  assert!(library::reconcile(&dir, &renders.join("2024")).unwrap().missing_locally.is_empty());
+  assert!(library::reconcile(&dir, &renders.join("nope")).is_err());
+}
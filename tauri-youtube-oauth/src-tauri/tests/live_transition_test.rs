@@ -0,0 +1,52 @@
+use tauri_youtube_oauth::{
+  error::Error,
+  live::{self, Broadcast, BroadcastStatus},
+};
+
+fn broadcast(status: &str, stream: Option<&str>) -> Broadcast {
+  Broadcast {
+    id: "b1".into(),
+    title: "Premiera".into(),
+    scheduled_start: "2999-01-01T18:00:00Z".into(),
+    privacy: "unlisted".into(),
+    life_cycle_status: status.into(),
+    bound_stream_id: stream.map(str::to_string),
+  }
+}
+
+#[test]
+fn transitions_follow_the_life_cycle() {
+  use BroadcastStatus::{Complete, Live, Testing};
+  let cases = [
+    ("ready", Testing, true),
+    ("ready", Live, true),
+    ("ready", Complete, false),
+    ("testing", Testing, false),
+    ("testing", Live, true),
+    ("testing", Complete, true),
+    ("live", Testing, false),
+    ("live", Live, false),
+    ("live", Complete, true),
+    ("complete", Live, false),
+    ("complete", Complete, false),
+    ("created", Testing, false),
+  ];
+  for (status, target, allowed) in cases {
+    let result = live::check_transition(&broadcast(status, Some("s1")), target);
+    assert_eq!(result.is_ok(), allowed, "{} → {:?}: {:?}", status, target, result);
+    if let Err(Error::Validation(message)) = result {
+      assert!(message.contains(status) && message.contains("dozwolone z"), "{}", message);
+    }
+  }
+}
+
+#[test]
+fn an_unbound_broadcast_can_only_be_completed() {
+  for target in [BroadcastStatus::Testing, BroadcastStatus::Live] {
+    match live::check_transition(&broadcast("ready", None), target) {
+      Err(Error::Validation(message)) => assert!(message.contains("youtube_bind_stream"), "{}", message),
+      other => panic!("expected a refusal for {:?}, got {:?}", target, other),
+    }
+  }
+  assert!(live::check_transition(&broadcast("live", None), BroadcastStatus::Complete).is_ok());
+}
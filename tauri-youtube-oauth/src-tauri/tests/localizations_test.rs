@@ -0,0 +1,102 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{collections::BTreeMap, fs};
+use tauri_youtube_oauth::{error::Error, localizations, Localization};
+
+fn loc(title: &str) -> Option<Localization> {
+  Some(Localization { title: title.into(), description: format!("{} opis", title) })
+}
+
+#[test]
+fn language_codes_follow_youtube_tags() {
+  for ok in ["en", "pt-BR", "zh-Hant", "es-419", "fil"] {
+    assert!(localizations::validate_language(ok).is_ok(), "{}", ok);
+  }
+  for bad in ["", "e", "english", "en_US", "en-", "pl-PL-x!", "12"] {
+    assert!(matches!(localizations::validate_language(bad), Err(Error::Validation(_))), "{}", bad);
+  }
+}
+
+#[tokio::test]
+async fn localizations_are_merged_into_what_is_there() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  // A playlist without a default language: it gets the app's (pl), the
+  // untouched language stays and the removed one goes.
+  let playlist = json!({
+    "id": "PL1",
+    "snippet": { "title": "Odcinki", "description": "Wszystkie", "channelId": "UC1", "publishedAt": "2024-01-01T00:00:00Z" },
+    "localizations": { "de": { "title": "Folgen", "description": "" }, "fr": { "title": "Épisodes", "description": "" } }
+  });
+  server
+    .mock("GET", "/youtube/v3/playlists")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("part".into(), "localizations,snippet".into()),
+      Matcher::UrlEncoded("id".into(), "PL1".into()),
+    ]))
+    .with_body(json!({ "items": [playlist] }).to_string())
+    .create_async()
+    .await;
+  let sent = json!({
+    "id": "PL1",
+    "snippet": { "title": "Odcinki", "description": "Wszystkie", "defaultLanguage": "pl" },
+    "localizations": { "de": { "title": "Folgen", "description": "" }, "en": { "title": "Episodes", "description": "Episodes opis" } }
+  });
+  let update = server
+    .mock("PUT", "/youtube/v3/playlists")
+    .match_query(Matcher::UrlEncoded("part".into(), "localizations,snippet".into()))
+    .match_body(Matcher::Json(sent.clone()))
+    .with_body(sent.to_string())
+    .expect(1)
+    .create_async()
+    .await;
+  let changes = BTreeMap::from([("en".to_string(), loc("Episodes")), ("fr".to_string(), None)]);
+  let set = localizations::set_playlist_localizations(dir, "PL1", &changes).await.unwrap();
+  update.assert_async().await;
+  assert!(set.default_language_added);
+  assert_eq!(set.default_language.as_deref(), Some("pl"));
+  assert_eq!(set.localizations.keys().collect::<Vec<_>>(), ["de", "en"]);
+  let current = localizations::playlist_localizations(dir, "PL1").await.unwrap();
+  assert_eq!((current.default_language, current.localizations.len()), (None, 2));
+
+  // The channel already has one; the rest of brandingSettings goes back as it was.
+  let branding = json!({ "channel": { "title": "Kanał", "keywords": "a b", "defaultLanguage": "en" }, "image": { "bannerExternalUrl": "https://b" } });
+  server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("part".into(), "brandingSettings,localizations".into()),
+      Matcher::UrlEncoded("mine".into(), "true".into()),
+    ]))
+    .with_body(json!({ "items": [{ "id": "UC1", "brandingSettings": branding }] }).to_string())
+    .create_async()
+    .await;
+  let sent = json!({ "id": "UC1", "brandingSettings": branding, "localizations": { "pl": { "title": "Kanał PL", "description": "Kanał PL opis" } } });
+  let update = server
+    .mock("PUT", "/youtube/v3/channels")
+    .match_query(Matcher::UrlEncoded("part".into(), "brandingSettings,localizations".into()))
+    .match_body(Matcher::Json(sent.clone()))
+    .with_body(sent.to_string())
+    .expect(1)
+    .create_async()
+    .await;
+  let set = localizations::set_channel_localizations(dir, &BTreeMap::from([("pl".to_string(), loc("Kanał PL"))])).await.unwrap();
+  update.assert_async().await;
+  assert!(!set.default_language_added);
+  assert_eq!((set.id.as_str(), set.default_language.as_deref()), ("UC1", Some("en")));
+  assert!(localizations::channel_localizations(dir).await.unwrap().localizations.is_empty());
+
+  // Checked before anything is read.
+  let bad = BTreeMap::from([("polski".to_string(), loc("x"))]);
+  assert!(matches!(localizations::set_channel_localizations(dir, &bad).await, Err(Error::Validation(_))));
+  let untitled = BTreeMap::from([("de".to_string(), loc(" "))]);
+  assert!(matches!(localizations::set_playlist_localizations(dir, "PL1", &untitled).await, Err(Error::Validation(_))));
+  update.assert_async().await;
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use tauri_youtube_oauth::{
+  error::Error,
+  lint::{self, LintConfig, LintMode, LintRule},
+  upload, ForceCode, Privacy, VideoMetadata,
+};
+
+fn meta(title: &str, description: &str) -> VideoMetadata {
+  VideoMetadata {
+    title: title.into(),
+    description: description.into(),
+    tags: vec!["rust".into()],
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  }
+}
+
+fn rules(m: &VideoMetadata, cfg: &LintConfig) -> Vec<(LintRule, String)> {
+  lint::lint_metadata(m, cfg).into_iter().map(|w| (w.rule, w.field)).collect()
+}
+
+#[test]
+fn clean_metadata_passes() {
+  assert!(lint::lint_metadata(&meta("Rust traits, part 2", "Generics and trait objects."), &LintConfig::default()).is_empty());
+}
+
+#[test]
+fn shouting_titles_are_flagged() {
+  let cfg = LintConfig::default();
+  assert_eq!(rules(&meta("THE BEST RUST TRAITS EVER", "d"), &cfg), [(LintRule::ExcessiveCaps, "title".into())]);
+  // Too short to judge, and acronyms in an ordinary title.
+  assert!(rules(&meta("NASA DIY", "d"), &cfg).is_empty());
+  assert!(rules(&meta("Intro to HTTP and TCP with Rust", "d"), &cfg).is_empty());
+}
+
+#[test]
+fn external_links_are_counted() {
+  let cfg = LintConfig::default();
+  let own = "https://youtu.be/abc https://www.youtube.com/@me https://m.youtube.com/watch?v=x";
+  assert!(rules(&meta("Title", &format!("{} https://example.com www.github.com/me", own)), &cfg).is_empty());
+  let many = "https://a.example https://b.example\nwww.c.example";
+  assert_eq!(rules(&meta("Title", many), &cfg), [(LintRule::TooManyLinks, "description".into())]);
+  assert!(rules(&meta("Title", many), &LintConfig { max_links: Some(3), ..Default::default() }).is_empty());
+}
+
+#[test]
+fn banned_words_match_whole_words_ignoring_case() {
+  let cfg = LintConfig { banned_words: vec!["giveaway".into(), "darmowe".into()], ..Default::default() };
+  let mut m = meta("Big GIVEAWAY today", "Darmowe naklejki");
+  m.tags.push("giveaway".into());
+  assert_eq!(
+    rules(&m, &cfg),
+    [(LintRule::BannedWord, "title".into()), (LintRule::BannedWord, "description".into()), (LintRule::BannedWord, "tags".into())]
+  );
+  assert!(rules(&meta("Giveaways explained", "niedarmowe"), &cfg).is_empty());
+}
+
+#[test]
+fn missing_description_is_flagged() {
+  assert_eq!(rules(&meta("Title", "  \n"), &LintConfig::default()), [(LintRule::MissingDescription, "description".into())]);
+}
+
+#[test]
+fn tags_near_the_limit_are_flagged() {
+  let mut m = meta("Title", "d");
+  // 45 tags of 9 letters and 44 commas: 449.
+  m.tags = (0..45).map(|i| format!("tag{:06}", i)).collect();
+  assert_eq!(lint::tags_length(&m.tags), 449);
+  assert!(rules(&m, &LintConfig::default()).is_empty());
+  // A space costs two quotes.
+  m.tags[0] = "tag 00000".into();
+  assert_eq!(lint::tags_length(&m.tags), 451);
+  assert_eq!(rules(&m, &LintConfig::default()), [(LintRule::TagsNearLimit, "tags".into())]);
+  m.tags.extend((0..6).map(|i| format!("more{:05}", i)));
+  let warnings = lint::lint_metadata(&m, &LintConfig::default());
+  assert!(warnings[0].message.contains("limit"), "{:?}", warnings);
+}
+
+#[test]
+fn angle_brackets_are_flagged() {
+  assert_eq!(
+    rules(&meta("Vec<T> explained", "a -> b"), &LintConfig::default()),
+    [(LintRule::AngleBrackets, "title".into()), (LintRule::AngleBrackets, "description".into())]
+  );
+}
+
+#[test]
+fn rules_can_be_disabled() {
+  let m = meta("VEC<T> IS THE BEST TYPE", "");
+  assert_eq!(rules(&m, &LintConfig::default()).len(), 3);
+  let cfg: LintConfig = serde_json::from_str(r#"{"disabled":["excessiveCaps","missingDescription"]}"#).unwrap();
+  assert_eq!(cfg.mode, LintMode::Warn);
+  assert_eq!(rules(&m, &cfg), [(LintRule::AngleBrackets, "title".into())]);
+}
+
+#[tokio::test]
+async fn block_mode_refuses_the_upload_until_forced() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = r#"{"client_id":"id","client_secret":"secret","upload_min_file_age_secs":0,"metadata_lint":{"mode":"block"}}"#;
+  fs::write(dir.join("oauth_config.json"), cfg).unwrap();
+  let video = dir.join("film.mp4");
+  fs::write(&video, b"not really a video").unwrap();
+  match upload::upload_video(dir, &video, &meta("Film", ""), &[]).await {
+    Err(Error::Validation(message)) => assert!(message.contains("Brak opisu") && message.contains("force"), "{}", message),
+    other => panic!("{:?}", other),
+  }
+  // Forced, it gets past the lint (and fails later, with no API to talk to).
+  let err = upload::upload_video(dir, &video, &meta("Film", ""), &[ForceCode::Lint(LintRule::MissingDescription)]).await.unwrap_err();
+  assert!(!err.to_string().contains("Brak opisu"), "{}", err);
+}
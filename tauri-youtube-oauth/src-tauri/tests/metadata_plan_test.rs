@@ -0,0 +1,110 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{bulk, PlanOptions, PlanRow, RowStatus};
+
+#[test]
+fn csv_plan_leaves_empty_cells_alone() {
+  let tmp = tempfile::tempdir().unwrap();
+  let path = tmp.path().join("plan.csv");
+  let csv = "\u{feff}video_id,title,description,tags,privacy,publish_at\r\n\
+             v1,Nowy tytuł,\"Linia 1\nLinia \"\"2\"\"\",\"rust, tauri\",,\r\n\
+             v2,,,,private,2099-01-01T00:00:00Z\r\n";
+  fs::write(&path, csv).unwrap();
+  let rows = bulk::read_plan(&path).unwrap();
+  assert_eq!(
+    rows,
+    [
+      PlanRow {
+        video_id: "v1".into(),
+        title: Some("Nowy tytuł".into()),
+        description: Some("Linia 1\nLinia \"2\"".into()),
+        tags: Some(vec!["rust".into(), "tauri".into()]),
+        ..Default::default()
+      },
+      PlanRow {
+        video_id: "v2".into(),
+        privacy: Some("private".into()),
+        publish_at: Some("2099-01-01T00:00:00Z".into()),
+        ..Default::default()
+      },
+    ]
+  );
+  fs::write(&path, "title\r\nA\r\n").unwrap();
+  assert!(bulk::read_plan(&path).is_err(), "video_id column is required");
+}
+
+#[tokio::test]
+async fn plan_is_validated_in_full_before_any_write() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let video = |id: &str, title: &str| {
+    serde_json::json!({
+      "id": id,
+      "snippet": { "title": title, "description": "Opis", "categoryId": "28", "tags": ["rust"] },
+      "status": { "privacyStatus": "public", "embeddable": true },
+    })
+  };
+  server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .with_body(serde_json::json!({ "items": [video("v1", "Stary"), video("v2", "Bez zmian")] }).to_string())
+    .create_async()
+    .await;
+  let update = server
+    .mock("PUT", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("part".into(), "snippet,status".into()))
+    .match_body(Matcher::Json(serde_json::json!({
+      "id": "v1",
+      "snippet": { "title": "Nowy", "description": "Opis", "categoryId": "28", "tags": ["rust"] },
+      "status": { "privacyStatus": "private", "embeddable": true, "publishAt": "2099-01-01T00:00:00Z" },
+    })))
+    .with_body(r#"{"id":"v1"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let plan = dir.join("plan.json");
+  let bad = serde_json::json!([
+    { "video_id": "v1", "title": "x".repeat(101) },
+    { "video_id": "v2", "publish_at": "jutro" },
+    { "video_id": "v2", "publish_at": "2099-01-01T00:00:00Z" },
+    { "video_id": "gone", "title": "A" },
+  ]);
+  fs::write(&plan, bad.to_string()).unwrap();
+  let report = bulk::apply_metadata_plan(dir, &plan, &PlanOptions::default()).await.unwrap();
+  let found: Vec<(usize, &str)> = report.problems.iter().map(|p| (p.row, p.video_id.as_str())).collect();
+  // Row 3 is both a repeated id and a publish_at on a public video.
+  assert_eq!(found, [(1, "v1"), (2, "v2"), (3, "v2"), (3, "v2"), (4, "gone")], "{:?}", report.problems);
+  assert!(report.rows.is_empty());
+
+  let good = serde_json::json!([
+    { "video_id": "v1", "title": "Nowy", "privacy": "private", "publish_at": "2099-01-01T00:00:00Z" },
+    { "video_id": "v2", "title": "Bez zmian", "tags": ["rust"] },
+  ]);
+  fs::write(&plan, good.to_string()).unwrap();
+  let options = PlanOptions { only_diff: true, min_interval_ms: Some(0) };
+  let report = bulk::apply_metadata_plan(dir, &plan, &options).await.unwrap();
+  assert!(report.problems.is_empty(), "{:?}", report.problems);
+  assert_eq!(report.rows.iter().map(|r| r.status).collect::<Vec<_>>(), [RowStatus::Succeeded, RowStatus::Unchanged]);
+  assert_eq!((report.succeeded, report.unchanged, report.failed), (1, 1, 0));
+
+  // Crate-wide dry-run: the same write is only planned.
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret","dry_run":true}"#).unwrap();
+  let report = bulk::apply_metadata_plan(dir, &plan, &options).await.unwrap();
+  assert_eq!(report.planned, 1);
+  assert_eq!(report.rows[0].plan.as_ref().map(|p| p.method.as_str()), Some("PUT"));
+  update.assert_async().await;
+}
@@ -0,0 +1,83 @@
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{config, error::Error, metadata, Privacy};
+
+const PATTERN: &str = r"^(?P<date>\d{4}-\d{2}-\d{2})_(?P<topic>[a-z-]+)_ep(?P<ep>\d+)$";
+
+fn write_config(dir: &Path, template: serde_json::Value) {
+  let cfg = serde_json::json!({
+    "client_id": "id",
+    "client_secret": "secret",
+    "channel_id": "UC0aB1cD2eF3gH4iJ5kL6mN7",
+    "metadata_template": template,
+  });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn filename_fills_in_title_and_description() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(
+    dir,
+    serde_json::json!({ "filename_pattern": PATTERN, "title": "{{topic|title}} — Episode {{ep}}", "description": "{{date}} / {{file_stem}}" }),
+  );
+
+  let preview = metadata::preview(dir, "2024-06-01_rust-traits_ep12.mp4").await.unwrap();
+  assert!(preview.matched);
+  assert_eq!(preview.groups["topic"], "rust-traits");
+  assert_eq!(preview.title.as_deref(), Some("Rust Traits — Episode 12"));
+  assert_eq!(preview.description.as_deref(), Some("2024-06-01 / 2024-06-01_rust-traits_ep12"));
+  assert!(!metadata::preview(dir, "holiday.mp4").await.unwrap().matched);
+
+  // Sidecar title wins; sidecar without one keeps its other fields.
+  let video = dir.join("2024-06-01_rust-traits_ep12.mp4");
+  fs::write(metadata::sidecar_path(&video), r#"{"title": "Własny tytuł", "privacy": "unlisted"}"#).unwrap();
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  assert_eq!((meta.title.as_str(), meta.privacy), ("Własny tytuł", Privacy::Unlisted));
+  fs::write(metadata::sidecar_path(&video), r#"{"tags": ["rust"], "description": "Z pliku"}"#).unwrap();
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  assert_eq!(meta.title, "Rust Traits — Episode 12");
+  assert_eq!((meta.description.as_str(), meta.tags.as_slice(), meta.privacy), ("Z pliku", &["rust".to_string()][..], Privacy::Private));
+
+  let err = metadata::load_video_metadata(dir, &dir.join("holiday.mp4")).await.unwrap_err();
+  assert!(matches!(err, Error::Validation(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn channel_title_comes_from_the_selected_channel() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let list = server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(mockito::Matcher::Any)
+    .with_body(include_str!("fixtures/channels_list_mine.json"))
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(dir, serde_json::json!({ "filename_pattern": PATTERN, "title": "{{channel_title}}: odcinek {{ep}}" }));
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "access", "refresh_token": "r", "expires_in": 3600, "created_at": now });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let preview = metadata::preview(dir, "2024-06-01_rust-traits_ep12.mp4").await.unwrap();
+  assert_eq!(preview.title.as_deref(), Some("Kanał testowy: odcinek 12"));
+  list.assert_async().await;
+}
+
+#[test]
+fn bad_templates_are_rejected_on_reload() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(dir, serde_json::json!({ "filename_pattern": "^(?P<topic>[a-z]+_ep", "title": "{{topic}}" }));
+  let err = config::reload(dir).unwrap_err().to_string();
+  assert!(err.contains("pozycja"), "{}", err);
+
+  write_config(dir, serde_json::json!({ "filename_pattern": PATTERN, "title": "{{episode}}" }));
+  assert!(config::reload(dir).unwrap_err().to_string().contains("episode"));
+  write_config(dir, serde_json::json!({ "filename_pattern": PATTERN, "title": "{{topic|shout}}" }));
+  assert!(config::reload(dir).is_err());
+  write_config(dir, serde_json::json!({ "filename_pattern": PATTERN, "title": "{{topic}} #{{ep}}" }));
+  assert!(config::reload(dir).is_ok());
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "metrics-endpoint")]
+
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, net::TcpListener, path::Path};
+use tauri_youtube_oauth::{metadata, metrics, metrics_endpoint, queue, upload, ForceCode};
+
+fn free_port() -> u16 {
+  TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn write_config(dir: &Path, endpoint: serde_json::Value) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "metrics_endpoint": endpoint });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+async fn session(server: &mut mockito::ServerGuard, status: usize, body: String) -> mockito::Mock {
+  let location = format!("{}/upload/session/m", server.url());
+  server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .with_status(status)
+    .with_header("location", &location)
+    .with_body(body)
+    .create_async()
+    .await
+}
+
+/// The value of `series` (name and labels as exposed), if present.
+fn sample(text: &str, series: &str) -> Option<i64> {
+  text.lines().find_map(|l| l.strip_prefix(series)?.strip_prefix(' ')?.parse().ok())
+}
+
+#[tokio::test]
+async fn scraped_counters_follow_an_upload_run_and_agree_with_get_metrics() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let port = free_port();
+  write_config(dir, json!({ "port": port }));
+  // Expired: the first call refreshes.
+  let tokens = json!({ "access_token": "old", "refresh_token": "r", "expires_in": 3600, "created_at": 1, "scope": "https://www.googleapis.com/auth/youtube.upload" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  server
+    .mock("POST", "/token")
+    .with_body(r#"{"access_token":"a","expires_in":3600,"scope":"https://www.googleapis.com/auth/youtube.upload"}"#)
+    .create_async()
+    .await;
+
+  let addr = metrics_endpoint::start(dir).unwrap().unwrap();
+  assert!(addr.ip().is_loopback() && addr.port() == port);
+  assert_eq!(metrics_endpoint::address(dir), Some(addr));
+  let scrape = || async move {
+    let resp = reqwest::get(format!("http://{}/metrics", addr)).await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert!(resp.headers()["content-type"].to_str().unwrap().starts_with("text/plain; version=0.0.4"));
+    resp.text().await.unwrap()
+  };
+  let before = scrape().await;
+  assert_eq!(sample(&before, "ytlite_uploads_total{outcome=\"succeeded\"}"), Some(0));
+  assert!(before.contains("# TYPE ytlite_queue_depth gauge"), "{}", before);
+  assert_eq!(reqwest::get(format!("http://{}/other", addr)).await.unwrap().status().as_u16(), 404);
+
+  let video = dir.join("odcinek.mp4");
+  fs::write(&video, vec![1u8; 2048]).unwrap();
+  fs::write(dir.join("odcinek.json"), json!({ "title": "Odcinek" }).to_string()).unwrap();
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  let opened = session(&mut server, 200, String::new()).await;
+  server.mock("PUT", "/upload/session/m").with_status(201).with_body(r#"{"id":"vid1"}"#).create_async().await;
+  upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await.unwrap();
+  opened.remove_async().await;
+  let gone = json!({ "error": { "code": 403, "message": "Quota", "errors": [{ "reason": "quotaExceeded", "domain": "youtube.quota" }] } });
+  session(&mut server, 403, gone.to_string()).await;
+  upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await.unwrap_err();
+  queue::add(dir, &video.to_string_lossy(), None).unwrap();
+
+  let text = scrape().await;
+  assert_eq!(sample(&text, "ytlite_uploads_total{outcome=\"succeeded\"}"), Some(1));
+  assert_eq!(sample(&text, "ytlite_uploads_total{outcome=\"failed\"}"), Some(1));
+  assert_eq!(sample(&text, "ytlite_token_refreshes_total{outcome=\"succeeded\"}"), Some(1));
+  assert_eq!(sample(&text, "ytlite_api_errors_total{class=\"upload\",reason=\"quotaExceeded\"}"), Some(1), "{}", text);
+  assert_eq!(sample(&text, "ytlite_queue_depth"), Some(1));
+  assert!(sample(&text, "ytlite_token_expiry_seconds").is_some_and(|s| s > 3500 && s <= 3600), "{}", text);
+
+  // The same registry as get_metrics.
+  let snapshot = metrics::snapshot(dir, false);
+  assert_eq!(sample(&text, "ytlite_quota_used_units"), Some(tauri_youtube_oauth::quota::status(dir).used as i64));
+  assert_eq!(sample(&text, "ytlite_requests_total{class=\"upload\"}"), Some(snapshot.upload.requests as i64));
+  assert_eq!(sample(&text, "ytlite_requests_total{class=\"auth\"}"), Some(snapshot.auth.requests as i64));
+  assert_eq!(snapshot.outcomes.uploads_succeeded, 1);
+
+  // Disabled: the listener goes away.
+  write_config(dir, json!({ "port": port, "disabled": true }));
+  assert_eq!(metrics_endpoint::start(dir).unwrap(), None);
+  assert!(reqwest::get(format!("http://{}/metrics", addr)).await.is_err());
+  assert!(!metrics_endpoint::stop(dir));
+}
@@ -0,0 +1,49 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{live, metrics};
+
+#[tokio::test]
+async fn counts_requests_errors_and_resets_on_read() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  let tmp = tempfile::tempdir().unwrap();
+  fs::write(tmp.path().join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": 4_102_444_800u64,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(tmp.path().join("tokens.json"), tokens.to_string()).unwrap();
+
+  let ok = server
+    .mock("GET", "/youtube/v3/liveStreams")
+    .match_query(Matcher::Any)
+    .with_body(r#"{"items":[]}"#)
+    .create_async()
+    .await;
+  live::list_streams(tmp.path()).await.unwrap();
+  live::list_streams(tmp.path()).await.unwrap();
+  ok.remove_async().await;
+  server
+    .mock("GET", "/youtube/v3/liveStreams")
+    .match_query(Matcher::Any)
+    .with_status(403)
+    .with_body(r#"{"error":{"message":"no","errors":[{"reason":"liveStreamingNotEnabled"}]}}"#)
+    .create_async()
+    .await;
+  assert!(live::list_streams(tmp.path()).await.is_err());
+
+  let m = metrics::snapshot(tmp.path(), true);
+  assert_eq!(m.data_api.requests, 3);
+  assert_eq!(m.data_api.error_count, 1);
+  assert_eq!(m.data_api.errors.get("liveStreamingNotEnabled"), Some(&1));
+  assert!(m.data_api.p50_ms.is_some() && m.data_api.p95_ms >= m.data_api.p50_ms);
+  assert_eq!(m.auth.requests, 0);
+
+  let after = metrics::snapshot(tmp.path(), false);
+  assert_eq!(after.data_api.requests, 0);
+  assert_eq!(after.data_api.p50_ms, None);
+}
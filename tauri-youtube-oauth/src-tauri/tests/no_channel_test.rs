@@ -0,0 +1,38 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{channel, error::Error};
+
+#[tokio::test]
+async fn empty_channel_list_is_no_channel_error() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("OAUTH_TOKENINFO_URL", format!("{}/tokeninfo", server.url()));
+  server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::Any)
+    .with_body(include_str!("fixtures/channels_list_empty.json"))
+    .create_async()
+    .await;
+  server
+    .mock("GET", "/tokeninfo")
+    .match_query(Matcher::UrlEncoded("access_token".into(), "access".into()))
+    .with_body(r#"{"email":"new.user@example.com","expires_in":"3500"}"#)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "access", "refresh_token": "r", "expires_in": 3600, "created_at": now });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  match channel::selected_channel_id(dir).await {
+    Err(Error::NoChannelForAccount { email }) => assert_eq!(email.as_deref(), Some("new.user@example.com")),
+    other => panic!("expected NoChannelForAccount, got {:?}", other),
+  }
+  // Nothing was persisted as the selected channel.
+  let cfg: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("oauth_config.json")).unwrap()).unwrap();
+  assert!(cfg.get("channel_id").is_none());
+}
@@ -0,0 +1,102 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{error::Error, history, metadata, sidecar, upload};
+
+const TITLE: &str = "Wakacje nad morzem 🏖️ — Zażółć gęślą jaźń";
+const DESCRIPTION: &str = "Łódź, Kraków i Gdańsk 🎬\nmuzyka: «Żółty słoń» ✓";
+
+fn utf16(text: &str, unit: fn(u16) -> [u8; 2], bom: [u8; 2]) -> Vec<u8> {
+  bom.into_iter().chain(text.encode_utf16().flat_map(unit)).collect()
+}
+
+#[tokio::test]
+async fn sidecars_in_utf8_and_utf16_read_the_same_and_broken_bytes_are_refused() {
+  let tmp = tempfile::tempdir().unwrap();
+  let folder = tmp.path().join("Zażółć gęślą jaźń");
+  fs::create_dir_all(&folder).unwrap();
+  let video = folder.join("odcinek ♫.mp4");
+  let text = json!({ "title": TITLE, "description": DESCRIPTION, "tags": ["żeglarstwo", "🌊"] }).to_string();
+  let encodings = [
+    text.clone().into_bytes(),
+    [b"\xEF\xBB\xBF".as_slice(), text.as_bytes()].concat(),
+    utf16(&text, u16::to_le_bytes, [0xFF, 0xFE]),
+    utf16(&text, u16::to_be_bytes, [0xFE, 0xFF]),
+  ];
+  for bytes in encodings {
+    fs::write(metadata::sidecar_path(&video), bytes).unwrap();
+    let meta = metadata::load_video_metadata(tmp.path(), &video).await.unwrap();
+    assert_eq!((meta.title.as_str(), meta.description.as_str()), (TITLE, DESCRIPTION));
+    assert_eq!(meta.tags, ["żeglarstwo", "🌊"]);
+    assert!(sidecar::validate(&metadata::sidecar_path(&video)).unwrap().is_empty());
+  }
+
+  // Latin-1 from an old editor, and UTF-16 with a lone surrogate: refused
+  // where they break, never replaced with '�'.
+  let broken: [(&[u8], &str); 3] = [
+    (b"{\"title\":\"Caf\xe9\"}", "bajtu 13"),
+    (b"\xEF\xBB\xBF{\"title\":\"\xc5\"}", "bajtu 13"),
+    (b"\xFF\xFE{\x00\x00\xD8}\x00", "bajtu 4"),
+  ];
+  for (bytes, at) in broken {
+    fs::write(metadata::sidecar_path(&video), bytes).unwrap();
+    match metadata::load_video_metadata(tmp.path(), &video).await {
+      Err(Error::Validation(message)) => assert!(message.contains(at), "{}", message),
+      other => panic!("{:?}", other),
+    }
+  }
+}
+
+#[tokio::test]
+async fn a_polish_path_and_an_emoji_title_arrive_byte_exact() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "upload_min_file_age_secs": 0 });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube.upload" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let folder = dir.join("Filmy").join("Zażółć gęślą jaźń");
+  fs::create_dir_all(&folder).unwrap();
+  let video = folder.join("wakacje 🏖️ część 1.mp4");
+  fs::write(&video, "klatki filmu").unwrap();
+  let sidecar = json!({ "title": TITLE, "description": DESCRIPTION }).to_string();
+  fs::write(metadata::sidecar_path(&video), utf16(&sidecar, u16::to_le_bytes, [0xFF, 0xFE])).unwrap();
+
+  // The title and description go as raw UTF-8 in the body, not as `\u`
+  // escapes and not in a header.
+  let session = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .match_header("content-type", "application/json; charset=UTF-8")
+    .match_body(Matcher::AllOf(vec![
+      Matcher::PartialJson(json!({ "snippet": { "title": TITLE, "description": DESCRIPTION }, "status": { "privacyStatus": "private" } })),
+      Matcher::Regex(regex::escape(TITLE)),
+      Matcher::Regex(regex::escape("Łódź, Kraków i Gdańsk 🎬")),
+    ]))
+    .with_header("location", &format!("{}/upload/session/pl", server.url()))
+    .expect(1)
+    .create_async()
+    .await;
+  let media = server
+    .mock("PUT", "/upload/session/pl")
+    .match_body("klatki filmu")
+    .with_status(201)
+    .with_body(r#"{"id":"vid-pl"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  let uploaded = upload::upload_video(dir, &video, &meta, &[]).await.unwrap();
+  assert_eq!(uploaded.video_id, "vid-pl");
+  session.assert_async().await;
+  media.assert_async().await;
+
+  let records = history::read_all(dir);
+  assert_eq!(records.len(), 1);
+  assert_eq!((records[0].title.as_str(), records[0].file_path.as_str()), (TITLE, video.to_str().unwrap()));
+}
@@ -0,0 +1,74 @@
+use serde_json::json;
+use std::{
+  fs,
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+};
+use tauri_youtube_oauth::{notifications, queue, quota, Notice, NoticeKind};
+
+fn write_config(dir: &Path, enabled: bool) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "language": "en", "desktop_notifications": enabled });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn a_batch_reports_its_first_failure_the_summary_and_a_quota_pause() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(dir, true);
+  let shown: Arc<Mutex<Vec<Notice>>> = Arc::default();
+  let denied = Arc::new(AtomicBool::new(false));
+  let (sink, refuse) = (shown.clone(), denied.clone());
+  notifications::attach(dir, move |n| {
+    if refuse.load(Ordering::SeqCst) {
+      return Err("permission denied".into());
+    }
+    sink.lock().unwrap().push(n.clone());
+    Ok(())
+  });
+  let kinds = || shown.lock().unwrap().iter().map(|n| n.kind).collect::<Vec<_>>();
+
+  // Neither file exists, so both entries get blocked: one failure notice, then the summary.
+  queue::add(dir, "/nonexistent/renders/first.mp4", None).unwrap();
+  queue::add(dir, "/nonexistent/renders/second.mp4", None).unwrap();
+  assert_eq!(queue::process_due(dir).await.unwrap(), 2);
+  assert_eq!(kinds(), [NoticeKind::FirstFailure, NoticeKind::QueueFinished]);
+  {
+    let shown = shown.lock().unwrap();
+    assert_eq!((shown[0].title.as_str(), shown[0].body.as_str()), ("An upload failed", "first.mp4 (PREFLIGHT_FAILED). The remaining files keep uploading."));
+    assert_eq!(shown[1].body, "Uploaded: 0, failed: 2.");
+    assert!(shown.iter().all(|n| !n.body.contains("/nonexistent")));
+  }
+
+  // A spent quota holds the entry back and says so once.
+  let day = quota::status(dir).day;
+  fs::write(dir.join("quota.json"), json!({ "day": day, "used": 10_000 }).to_string()).unwrap();
+  let held = queue::add(dir, "/nonexistent/renders/third.mp4", None).unwrap();
+  assert_eq!(queue::process_due(dir).await.unwrap(), 0);
+  assert_eq!(queue::process_due(dir).await.unwrap(), 0);
+  assert_eq!(kinds()[2..], [NoticeKind::QuotaPaused]);
+  assert!(shown.lock().unwrap()[2].body.contains("(1)"));
+  let entry = queue::list(dir).into_iter().find(|e| e.id == held.id).unwrap();
+  assert_eq!(entry.status, queue::EntryStatus::Pending);
+
+  // A refused permission is reported by the test and changes nothing else.
+  denied.store(true, Ordering::SeqCst);
+  let test = notifications::test(dir);
+  assert!(test.enabled && !test.delivered);
+  assert_eq!(test.error.as_deref(), Some("permission denied"));
+  fs::remove_file(dir.join("quota.json")).unwrap();
+  assert_eq!(queue::process_due(dir).await.unwrap(), 1);
+  denied.store(false, Ordering::SeqCst);
+
+  // Turned off: the queue stays quiet, the test still goes out.
+  write_config(dir, false);
+  queue::add(dir, "/nonexistent/renders/fourth.mp4", None).unwrap();
+  assert_eq!(queue::process_due(dir).await.unwrap(), 1);
+  assert_eq!(kinds().len(), 3);
+  let test = notifications::test(dir);
+  assert!(!test.enabled && test.delivered);
+  assert_eq!(shown.lock().unwrap().last().unwrap().title, "Notifications work");
+}
@@ -0,0 +1,95 @@
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::{
+  fs,
+  net::TcpListener,
+  path::Path,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::{callback, config, oauth_client, MismatchKind};
+
+const MAIN: &str = "123456789012-abcdefghijklmnop.apps.googleusercontent.com";
+const OTHER: &str = "987654321098-zyxwvutsrqponmlk.apps.googleusercontent.com";
+
+fn write_config(dir: &Path, client_id: &str, port: u16) {
+  fs::create_dir_all(dir).unwrap();
+  let sink = json!({ "type": "file", "path": "events.jsonl", "events": ["oauth-client-mismatch"] });
+  let cfg = json!({ "client_id": client_id, "client_secret": "secret", "redirect_port": port, "event_sinks": [sink] });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+/// An unsigned JWT; only its claims are read.
+fn id_token(claims: Value) -> String {
+  let mut payload = String::new();
+  let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+  for chunk in claims.to_string().as_bytes().chunks(3) {
+    let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+    for i in 0..=chunk.len() {
+      payload.push(alphabet[(n >> (18 - 6 * i) & 63) as usize] as char);
+    }
+  }
+  format!("eyJhbGciOiJSUzI1NiJ9.{}.sig", payload)
+}
+
+#[test]
+fn project_numbers_and_masks() {
+  assert_eq!(oauth_client::project_number(MAIN).as_deref(), Some("123456789012"));
+  assert_eq!(oauth_client::project_number("local-client"), None);
+  assert_eq!(oauth_client::project_number("id"), None);
+  assert_eq!(oauth_client::mask(MAIN), "…klmnop");
+  assert_eq!(oauth_client::mask("123-short.apps.googleusercontent.com"), "…");
+}
+
+#[tokio::test]
+async fn a_config_from_another_profile_is_flagged_against_the_login() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("OAUTH_TOKENINFO_URL", format!("{}/tokeninfo", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+  write_config(dir, MAIN, port);
+  write_config(&dir.join("profiles").join("klient"), OTHER, port);
+
+  // The login records its client, project and account.
+  let reply = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "token_type": "Bearer",
+    "id_token": id_token(json!({ "azp": MAIN, "aud": MAIN, "email": "kanal@example.com" })) });
+  server.mock("POST", "/token").with_body(reply.to_string()).create_async().await;
+  callback::start(dir).unwrap();
+  let state = callback::flow_state(dir).unwrap();
+  assert_eq!(reqwest::get(format!("http://127.0.0.1:{}/callback?code=c&state={}", port, state)).await.unwrap().status().as_u16(), 200);
+  let stored = oauth_client::association(dir).unwrap();
+  assert_eq!((stored.project_number.as_deref(), stored.email.as_deref()), (Some("123456789012"), Some("kanal@example.com")));
+
+  server.mock("GET", "/tokeninfo").match_query(Matcher::Any).with_body(json!({ "azp": MAIN, "aud": MAIN }).to_string()).create_async().await;
+  let fine = oauth_client::describe(dir).await;
+  assert!(fine.mismatches.is_empty(), "{:?}", fine.mismatches);
+  assert_eq!(fine.configured.unwrap().project_number.as_deref(), Some("123456789012"));
+  assert_eq!(fine.token_audience.unwrap().client_id, "…klmnop");
+
+  // The other profile's config copied over this one.
+  fs::copy(dir.join("profiles/klient/oauth_config.json"), dir.join("oauth_config.json")).unwrap();
+  config::reload(dir).unwrap();
+  let mixed = oauth_client::describe(dir).await;
+  let kinds: Vec<MismatchKind> = mixed.mismatches.iter().map(|m| m.kind).collect();
+  assert_eq!(kinds, [MismatchKind::ProjectChanged, MismatchKind::TokensForOtherClient, MismatchKind::TokenAudience]);
+  let project = &mixed.mismatches[0].message;
+  assert!(project.contains("123456789012") && project.contains("987654321098") && project.contains("klient"), "{}", project);
+  assert!(project.contains("kanal@example.com"), "{}", project);
+  let shown = serde_json::to_string(&mixed).unwrap();
+  assert!(!shown.contains(MAIN) && !shown.contains(OTHER) && !shown.contains("abcdefghij"), "{}", shown);
+
+  // The reload warned on its own; the startup check would too.
+  let deadline = Instant::now() + Duration::from_secs(10);
+  let warned = loop {
+    let text = fs::read_to_string(dir.join("events.jsonl")).unwrap_or_default();
+    if let Some(line) = text.lines().next() {
+      break serde_json::from_str::<Value>(line).unwrap();
+    }
+    assert!(Instant::now() < deadline, "no oauth-client-mismatch");
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  };
+  assert_eq!(warned["event"], "oauth-client-mismatch");
+  assert_eq!(warned["payload"]["mismatches"][0]["kind"], "project_changed");
+  assert!(warned["payload"]["token_audience"].is_null());
+}
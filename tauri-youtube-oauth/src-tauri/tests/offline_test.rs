@@ -0,0 +1,54 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{channel, connectivity, error::Error, sections};
+
+#[tokio::test]
+async fn offline_reads_come_from_etag_cache_and_writes_fail_fast() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let fresh = server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::Any)
+    .match_header("if-none-match", Matcher::Missing)
+    .with_header("etag", "\"v1\"")
+    .with_body(r#"{"pageInfo":{"totalResults":1},"items":[{"id":"UC1"}]}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let revalidated = server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::Any)
+    .match_header("if-none-match", "\"v1\"")
+    .with_status(304)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "access", "refresh_token": "r", "expires_in": 3600, "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let first = channel::list_mine(dir, "snippet").await.unwrap();
+  assert!(first.get("from_cache").is_none());
+  let second = channel::list_mine(dir, "snippet").await.unwrap();
+  assert_eq!(second["items"][0]["id"], "UC1");
+  fresh.assert_async().await;
+  revalidated.assert_async().await;
+
+  let status = connectivity::set_offline_mode(dir, true).await;
+  assert!(!status.online && status.forced_offline);
+  let cached = channel::list_mine(dir, "snippet").await.unwrap();
+  assert_eq!(cached["from_cache"], true);
+  assert!(cached["fetched_at"].as_u64().unwrap() >= now);
+  assert_eq!(cached["items"][0]["id"], "UC1");
+  assert!(matches!(sections::delete_section(dir, "s1").await, Err(Error::Offline)));
+
+  assert!(connectivity::set_offline_mode(dir, false).await.online);
+}
@@ -0,0 +1,76 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{error::Error, playlists, videos, PageRequest};
+
+fn item(i: usize) -> serde_json::Value {
+  json!({
+    "id": format!("it{}", i),
+    "snippet": { "title": format!("Film {}", i), "position": i, "resourceId": { "videoId": format!("v{}", i) } },
+    "contentDetails": { "videoId": format!("v{}", i), "videoPublishedAt": "2024-03-01T10:00:00Z" },
+  })
+}
+
+fn page(range: std::ops::Range<usize>, next: Option<&str>) -> String {
+  let mut body = json!({ "items": range.map(item).collect::<Vec<_>>(), "pageInfo": { "totalResults": 6 } });
+  if let Some(next) = next {
+    body["nextPageToken"] = json!(next);
+  }
+  body.to_string()
+}
+
+#[tokio::test]
+async fn budgeted_listings_continue_from_a_signed_cursor() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let channel = json!({ "items": [{ "id": "UC1", "snippet": { "title": "Kanał" }, "contentDetails": { "relatedPlaylists": { "uploads": "UU1" } } }] });
+  server.mock("GET", "/youtube/v3/channels").match_query(Matcher::Any).with_body(channel.to_string()).create_async().await;
+  // Page tokens may hold the cursor's separator.
+  for playlist in ["PL1", "UU1"] {
+    let first = Matcher::Regex(format!("playlistId={}&maxResults=50$", playlist));
+    server.mock("GET", "/youtube/v3/playlistItems").match_query(first).with_body(page(0..2, Some("t.2"))).create_async().await;
+    let second = Matcher::AllOf(vec![Matcher::Regex(format!("playlistId={}", playlist)), Matcher::UrlEncoded("pageToken".into(), "t.2".into())]);
+    server.mock("GET", "/youtube/v3/playlistItems").match_query(second).with_body(page(2..4, Some("t3"))).create_async().await;
+    let third = Matcher::AllOf(vec![Matcher::Regex(format!("playlistId={}", playlist)), Matcher::UrlEncoded("pageToken".into(), "t3".into())]);
+    server.mock("GET", "/youtube/v3/playlistItems").match_query(third).with_body(page(4..6, None)).create_async().await;
+  }
+
+  let budget = |cursor: Option<String>| PageRequest { cursor, page_budget: Some(1) };
+  let first = playlists::list_playlist_items(dir, "PL1", &budget(None)).await.unwrap();
+  assert_eq!(first.items.iter().map(|i| i.video_id.as_str()).collect::<Vec<_>>(), ["v0", "v1"]);
+  assert_eq!(first.total, Some(6));
+  let cursor = first.next_cursor.expect("more pages");
+
+  // The rest in one go, with no budget.
+  let rest = playlists::list_playlist_items(dir, "PL1", &PageRequest { cursor: Some(cursor.clone()), page_budget: None }).await.unwrap();
+  assert_eq!(rest.items.iter().map(|i| i.position).collect::<Vec<_>>(), [2, 3, 4, 5]);
+  assert!(rest.next_cursor.is_none());
+
+  // A cursor is bound to its listing, and can't be edited or made up.
+  let refused = |r: Result<_, Error>| matches!(r, Err(Error::Validation(m)) if m.contains("kursor"));
+  assert!(refused(videos::list_my_videos(dir, &budget(Some(cursor.clone()))).await.map(|_| ())));
+  assert!(refused(playlists::list_playlist_items(dir, "PL2", &budget(Some(cursor.clone()))).await.map(|_| ())));
+  let tampered = cursor.replacen("2.", "0.", 1);
+  assert!(refused(playlists::list_playlist_items(dir, "PL1", &budget(Some(tampered))).await.map(|_| ())));
+  assert!(refused(playlists::list_playlist_items(dir, "PL1", &budget(Some("garbage".into()))).await.map(|_| ())));
+
+  let mut seen = Vec::new();
+  let mut cursor = None;
+  loop {
+    let page = videos::list_my_videos(dir, &budget(cursor)).await.unwrap();
+    seen.extend(page.videos.into_iter().map(|v| v.video_id));
+    match page.next_cursor {
+      Some(next) => cursor = Some(next),
+      None => break,
+    }
+  }
+  assert_eq!(seen, ["v0", "v1", "v2", "v3", "v4", "v5"]);
+}
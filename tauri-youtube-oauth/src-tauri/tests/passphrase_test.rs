@@ -0,0 +1,66 @@
+use std::fs;
+use tauri_youtube_oauth::{config, error::Error, passphrase, profiles};
+
+fn seed(dir: &std::path::Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+}
+
+#[test]
+fn passphrase_guards_and_limits_attempts() {
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path());
+  assert!(passphrase::require(tmp.path(), None).is_ok());
+
+  passphrase::set(tmp.path(), "correct horse", None).unwrap();
+  let stored = fs::read_to_string(tmp.path().join("oauth_config.json")).unwrap();
+  assert!(stored.contains("$argon2") && !stored.contains("correct horse"));
+  assert!(passphrase::require(tmp.path(), Some("correct horse")).is_ok());
+  assert!(matches!(passphrase::require(tmp.path(), None), Err(Error::PassphraseRejected)));
+  // Replacing needs the current one.
+  assert!(passphrase::set(tmp.path(), "another one", Some("wrong")).is_err());
+
+  for _ in 0..5 {
+    let _ = passphrase::require(tmp.path(), Some("wrong"));
+  }
+  assert!(matches!(passphrase::require(tmp.path(), Some("correct horse")), Err(Error::PassphraseRejected)));
+}
+
+#[test]
+fn bundle_never_contains_the_hash() {
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path());
+  passphrase::set(tmp.path(), "correct horse", None).unwrap();
+  let out = tmp.path().join("bundle.json");
+  config::export_bundle(tmp.path(), &out, false).unwrap();
+  let bundle = fs::read_to_string(out).unwrap();
+  assert!(bundle.contains("secret") && !bundle.contains("argon2") && !bundle.contains("passphrase_hash"));
+  assert!(config::visible(tmp.path(), false).unwrap().client_secret.is_empty());
+
+  passphrase::clear(tmp.path(), "correct horse").unwrap();
+  assert!(!passphrase::is_set(tmp.path()));
+}
+
+#[test]
+fn one_passphrase_guards_every_profile_whichever_was_active() {
+  let root = tempfile::tempdir().unwrap();
+  seed(root.path());
+  let music = root.path().join("profiles/music");
+  fs::create_dir_all(&music).unwrap();
+  seed(&music);
+  profiles::switch(root.path(), "music").unwrap();
+  let active = profiles::active_dir(root.path());
+  assert_eq!(active, music);
+
+  // Set from the active profile, kept with the default one.
+  passphrase::set(&active, "correct horse", None).unwrap();
+  assert!(fs::read_to_string(root.path().join("oauth_config.json")).unwrap().contains("$argon2"));
+  assert!(!fs::read_to_string(music.join("oauth_config.json")).unwrap().contains("passphrase_hash"));
+  for dir in [root.path(), music.as_path()] {
+    assert!(passphrase::is_set(dir));
+    assert!(matches!(passphrase::require(dir, None), Err(Error::PassphraseRejected)));
+    assert!(passphrase::require(dir, Some("correct horse")).is_ok());
+  }
+
+  passphrase::clear(root.path(), "correct horse").unwrap();
+  assert!(!passphrase::is_set(&music) && passphrase::require(&music, None).is_ok());
+}
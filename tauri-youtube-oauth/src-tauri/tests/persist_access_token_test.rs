@@ -0,0 +1,85 @@
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{auth, channel, config, env_file, read_tokens_from_dir, write_tokens_to_dir, EnvFormat, Tokens};
+
+fn write_config(dir: &Path, persist: bool) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "channel_id": "UC1", "persist_access_token": persist });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+fn on_disk(dir: &Path) -> Value {
+  serde_json::from_str(&fs::read_to_string(dir.join("tokens.json")).unwrap()).unwrap()
+}
+
+/// The same files in a dir this process hasn't seen: what the next start finds.
+fn restart(from: &Path, to: &Path) {
+  fs::create_dir_all(to).unwrap();
+  for name in ["oauth_config.json", "tokens.json"] {
+    fs::copy(from.join(name), to.join(name)).unwrap();
+  }
+}
+
+#[tokio::test]
+async fn the_access_token_stays_in_memory_and_is_refreshed_after_a_restart() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().join("first");
+  fs::create_dir_all(&dir).unwrap();
+  write_config(&dir, false);
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let scope = "https://www.googleapis.com/auth/youtube.upload https://www.googleapis.com/auth/youtube.readonly";
+  let tokens = Tokens { access_token: "ya29.mem".into(), refresh_token: "1//r".into(), expires_in: 3600, created_at: now, scope: scope.into(), ..Default::default() };
+  write_tokens_to_dir(&dir, &tokens).unwrap();
+
+  // Only the refresh token and metadata reach the file; this process still has the rest.
+  let file = on_disk(&dir);
+  assert_eq!((file["access_token"].as_str(), file["refresh_token"].as_str(), file["expires_in"].as_u64()), (Some(""), Some("1//r"), Some(0)));
+  assert_eq!(file["scope"], scope);
+  let held = read_tokens_from_dir(&dir).unwrap();
+  assert_eq!((held.access_token.as_str(), held.expires_in), ("ya29.mem", 3600));
+
+  // After a restart the login is still there, just without an access token.
+  let second = tmp.path().join("second");
+  restart(&dir, &second);
+  assert!(read_tokens_from_dir(&second).unwrap().access_token.is_empty());
+  let status = auth::status(&second);
+  assert!(status.authenticated && status.refreshable, "{:?}", status);
+  assert_eq!(status.expires_at, None);
+
+  let refresh = server
+    .mock("POST", "/token")
+    .match_body(Matcher::Regex("grant_type=refresh_token".into()))
+    .with_body(r#"{"access_token":"ya29.fresh","expires_in":3600,"token_type":"Bearer"}"#)
+    .expect(2)
+    .create_async()
+    .await;
+  let channels = server
+    .mock("GET", "/youtube/v3/channels")
+    .match_query(Matcher::Any)
+    .match_header("authorization", "Bearer ya29.fresh")
+    .with_body(json!({ "items": [{ "id": "UC1" }] }).to_string())
+    .expect(2)
+    .create_async()
+    .await;
+  // The first call refreshes; the second uses the token now held.
+  channel::list_mine(&second, "id").await.unwrap();
+  channel::list_mine(&second, "id").await.unwrap();
+  channels.assert_async().await;
+  assert_eq!(on_disk(&second)["access_token"], "");
+
+  // An env file with tokens needs something to emit: one more refresh.
+  let third = tmp.path().join("third");
+  restart(&second, &third);
+  let env = env_file::generate(&third, true, None, EnvFormat::default()).await.unwrap();
+  assert!(env.contains("YOUTUBE_ACCESS_TOKEN=ya29.fresh") && env.contains("YOUTUBE_REFRESH_TOKEN=1//r"), "{}", env);
+  refresh.assert_async().await;
+
+  // Turning the flag back on puts the token on disk at the next reload.
+  write_config(&third, true);
+  config::reload(&third).unwrap();
+  assert_eq!(on_disk(&third)["access_token"], "ya29.fresh");
+  assert_eq!(on_disk(&third)["expires_in"], 3600);
+}
@@ -0,0 +1,84 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{playlists, PlaylistInsert, PlaylistItem};
+
+fn item(item_id: &str, video_id: &str, position: u32) -> PlaylistItem {
+  PlaylistItem { item_id: item_id.into(), video_id: video_id.into(), position }
+}
+
+fn resource(item_id: &str, video_id: &str, position: u32) -> serde_json::Value {
+  serde_json::json!({ "id": item_id, "snippet": { "position": position, "resourceId": { "kind": "youtube#video", "videoId": video_id } } })
+}
+
+#[test]
+fn duplicates_keep_the_earliest_position() {
+  let items = [item("c", "v1", 4), item("a", "v1", 0), item("b", "v2", 1), item("d", "v2", 2), item("e", "v3", 3)];
+  assert_eq!(playlists::find_duplicates(&items), [item("d", "v2", 2), item("c", "v1", 4)]);
+  assert!(playlists::find_duplicates(&[]).is_empty());
+}
+
+#[tokio::test]
+async fn inserts_are_skipped_for_videos_already_in_the_playlist() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  let first_page = serde_json::json!({
+    "items": [resource("i0", "v1", 0), resource("i1", "v2", 1)],
+    "nextPageToken": "p2",
+    "pageInfo": { "totalResults": 3 },
+  });
+  let page1 = server
+    .mock("GET", "/youtube/v3/playlistItems")
+    .match_query(Matcher::Regex("playlistId=PL1&maxResults=50$".into()))
+    .with_body(first_page.to_string())
+    .expect(3)
+    .create_async()
+    .await;
+  let page2 = server
+    .mock("GET", "/youtube/v3/playlistItems")
+    .match_query(Matcher::Regex("pageToken=p2".into()))
+    .with_body(serde_json::json!({ "items": [resource("i2", "v1", 2)], "pageInfo": { "totalResults": 3 } }).to_string())
+    .expect(3)
+    .create_async()
+    .await;
+  let insert = server
+    .mock("POST", "/youtube/v3/playlistItems")
+    .match_query(Matcher::Any)
+    .match_body(Matcher::PartialJson(serde_json::json!({ "snippet": { "playlistId": "PL1", "resourceId": { "videoId": "v3" } } })))
+    .with_body(resource("i3", "v3", 3).to_string())
+    .expect(1)
+    .create_async()
+    .await;
+
+  let skipped = playlists::add_to_playlist(dir, "PL1", "v1").await.unwrap();
+  assert_eq!(skipped, PlaylistInsert::AlreadyInPlaylist { item_id: "i0".into() });
+  assert_eq!(playlists::add_to_playlist(dir, "PL1", "v3").await.unwrap(), PlaylistInsert::Inserted { item_id: "i3".into() });
+  // The insert updated the cached contents: no re-list, no second insert.
+  assert_eq!(playlists::add_to_playlist(dir, "PL1", "v3").await.unwrap(), PlaylistInsert::AlreadyInPlaylist { item_id: "i3".into() });
+  insert.assert_async().await;
+
+  // Deduplication always lists fresh: once per run.
+  let delete =
+    server.mock("DELETE", "/youtube/v3/playlistItems").match_query(Matcher::UrlEncoded("id".into(), "i2".into())).expect(1).create_async().await;
+  let report = playlists::deduplicate_playlist(dir, "PL1", true).await.unwrap();
+  assert_eq!((report.scanned, report.removed), (3, 0));
+  assert_eq!(report.duplicates, [item("i2", "v1", 2)]);
+  let report = playlists::deduplicate_playlist(dir, "PL1", false).await.unwrap();
+  assert_eq!(report.removed, 1);
+  page1.assert_async().await;
+  page2.assert_async().await;
+  delete.assert_async().await;
+}
@@ -0,0 +1,50 @@
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{
+  env_file::{self, parse_dotenv},
+  profiles,
+};
+
+fn seed(dir: &Path, channel: &str, tokens: bool) {
+  fs::create_dir_all(dir).unwrap();
+  let cfg = serde_json::json!({ "client_id": "id", "client_secret": "secret", "channel_id": channel });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  if tokens {
+    let t = serde_json::json!({ "access_token": "a", "refresh_token": format!("r-{}", channel), "client_id": "id" });
+    fs::write(dir.join("tokens.json"), t.to_string()).unwrap();
+  }
+}
+
+#[tokio::test]
+async fn writes_one_env_per_profile_without_aborting_on_missing_tokens() {
+  let root = tempfile::tempdir().unwrap();
+  seed(root.path(), "UC-default", true);
+  seed(&root.path().join("profiles/music"), "UC-music", true);
+  seed(&root.path().join("profiles/gaming"), "UC-gaming", false);
+  fs::create_dir_all(root.path().join("profiles/not-configured")).unwrap();
+  assert_eq!(profiles::list(root.path()), ["default", "gaming", "music"]);
+  assert!(profiles::dir_for(root.path(), "../escape").is_err());
+
+  let out = root.path().join("envs");
+  let results = env_file::generate_all(root.path(), &out).await.unwrap();
+  assert_eq!(results.len(), 3);
+  for r in &results {
+    let text = fs::read_to_string(&r.path).unwrap();
+    // They hold refresh tokens and the client secret.
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      assert_eq!(fs::metadata(&r.path).unwrap().permissions().mode() & 0o777, 0o600, "{}", r.path);
+    }
+    assert!(text.contains(&format!("# Profile: {}", r.profile)) && text.contains("# Generated at: "));
+    let vars = parse_dotenv(&text);
+    let channel = vars.iter().find(|(k, _)| k == "YOUTUBE_CHANNEL_ID").map(|(_, v)| v.clone());
+    assert_eq!(channel.as_deref(), Some(format!("UC-{}", r.profile).as_str()));
+    if r.profile == "gaming" {
+      assert!(r.warning.is_some());
+      assert!(text.contains("# WARNING: no valid tokens") && text.contains("# YOUTUBE_REFRESH_TOKEN=\n"));
+    } else {
+      assert!(r.warning.is_none());
+      assert!(text.contains(&format!("# YOUTUBE_REFRESH_TOKEN=r-UC-{}", r.profile)));
+    }
+  }
+}
@@ -0,0 +1,149 @@
+use serde_json::{json, Value};
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::{profiles, queue, shutdown, ProfileSwitched};
+
+fn seed(dir: &Path, channel: &str) {
+  fs::create_dir_all(dir).unwrap();
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "channel_id": channel });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  fs::write(dir.join("tokens.json"), json!({ "access_token": "a", "refresh_token": format!("r-{}", channel) }).to_string()).unwrap();
+  fs::write(dir.join("history.jsonl"), "").unwrap();
+}
+
+fn pointer(root: &Path) -> Value { serde_json::from_str(&fs::read_to_string(root.join("active_profile.json")).unwrap()).unwrap() }
+
+fn switched(profile: &str, previous: &str) -> Option<ProfileSwitched> {
+  Some(ProfileSwitched { profile: profile.into(), previous: previous.into() })
+}
+
+fn wait_gone(path: &Path) {
+  let deadline = Instant::now() + Duration::from_secs(10);
+  while path.exists() {
+    assert!(Instant::now() < deadline, "{} still there", path.display());
+    std::thread::sleep(Duration::from_millis(20));
+  }
+}
+
+#[test]
+fn switching_and_deleting_move_the_pointer_before_anything_else() {
+  let tmp = tempfile::tempdir().unwrap();
+  let root = tmp.path();
+  seed(root, "UC-default");
+  seed(&root.join("profiles/music"), "UC-music");
+  seed(&root.join("profiles/gaming"), "UC-gaming");
+  assert_eq!(profiles::active(root), "default");
+
+  assert_eq!(profiles::switch(root, "music").unwrap(), switched("music", "default"));
+  assert_eq!(pointer(root), json!({ "profile": "music" }));
+  assert_eq!(profiles::active_dir(root), root.join("profiles/music"));
+  assert_eq!(profiles::switch(root, "music").unwrap(), None);
+  assert!(profiles::switch(root, "missing").is_err());
+  assert_eq!(profiles::active(root), "music");
+
+  // An upload running in the active profile holds the switch back.
+  let upload = shutdown::track_upload(&root.join("profiles/music"));
+  assert!(profiles::switch(root, "gaming").is_err());
+  assert!(profiles::delete(root, "music").is_err());
+  drop(upload);
+  assert_eq!(profiles::switch(root, "gaming").unwrap(), switched("gaming", "music"));
+
+  assert!(profiles::delete(root, "default").is_err());
+  // Deleting another profile leaves the pointer alone.
+  assert_eq!(profiles::delete(root, "music").unwrap(), None);
+  assert_eq!(profiles::active(root), "gaming");
+  // Deleting the active one hands over to default.
+  assert_eq!(profiles::delete(root, "gaming").unwrap(), switched("default", "gaming"));
+  assert_eq!(pointer(root), json!({ "profile": "default" }));
+  assert_eq!(profiles::list(root), ["default"]);
+  wait_gone(&root.join("profiles/music.deleting"));
+  wait_gone(&root.join("profiles/gaming.deleting"));
+  assert!(root.join("tokens.json").exists(), "the default profile is untouched");
+}
+
+#[test]
+fn a_crash_between_the_steps_is_recovered_at_the_next_start() {
+  // Crashed after the pointer moved, before the rename: the profile is
+  // still whole, just no longer active.
+  let tmp = tempfile::tempdir().unwrap();
+  let root = tmp.path();
+  seed(root, "UC-default");
+  seed(&root.join("profiles/music"), "UC-music");
+  fs::write(root.join("active_profile.json"), json!({ "profile": "default" }).to_string()).unwrap();
+  assert!(profiles::recover(root).is_empty());
+  assert_eq!(profiles::list(root), ["default", "music"]);
+  assert_eq!(profiles::active(root), "default");
+
+  // Crashed after the rename, halfway through the removal, with a pointer
+  // still naming the deleted profile.
+  let tmp = tempfile::tempdir().unwrap();
+  let root = tmp.path();
+  seed(root, "UC-default");
+  seed(&root.join("profiles/gaming"), "UC-gaming");
+  let doomed = root.join("profiles/music.deleting");
+  seed(&doomed, "UC-music");
+  fs::remove_file(doomed.join("tokens.json")).unwrap();
+  fs::create_dir_all(doomed.join("cache")).unwrap();
+  fs::write(root.join("active_profile.json"), json!({ "profile": "music" }).to_string()).unwrap();
+  // Never listed nor active, even before recovery.
+  assert_eq!(profiles::list(root), ["default", "gaming"]);
+  assert_eq!(profiles::active(root), "default");
+  assert!(profiles::dir_for(root, "music.deleting").is_err());
+  assert_eq!(profiles::recover(root), ["music"]);
+  assert!(!doomed.exists());
+  assert_eq!(pointer(root), json!({ "profile": "default" }));
+  assert_eq!(profiles::list(root), ["default", "gaming"]);
+
+  // Crashed while writing the pointer: the temp file is ignored.
+  let tmp = tempfile::tempdir().unwrap();
+  let root = tmp.path();
+  seed(root, "UC-default");
+  seed(&root.join("profiles/music"), "UC-music");
+  fs::write(root.join("active_profile.json"), json!({ "profile": "music" }).to_string()).unwrap();
+  fs::write(root.join(".active_profile.json.1-0.tmp"), r#"{"prof"#).unwrap();
+  assert!(profiles::recover(root).is_empty());
+  assert_eq!(profiles::active(root), "music");
+
+  // A leftover of an earlier deletion doesn't stop deleting a new profile
+  // of the same name.
+  let doomed = root.join("profiles/music.deleting");
+  seed(&doomed, "UC-old-music");
+  assert_eq!(profiles::delete(root, "music").unwrap(), switched("default", "music"));
+  assert!(!root.join("profiles/music").exists());
+  wait_gone(&doomed);
+}
+
+#[tokio::test]
+async fn switching_away_ends_the_old_profiles_queue_worker() {
+  let tmp = tempfile::tempdir().unwrap();
+  let root = tmp.path().to_path_buf();
+  seed(&root, "UC-default");
+  seed(&root.join("profiles/music"), "UC-music");
+  // Read-only mode keeps the entry pending, so the worker idles.
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "read_only_mode": true });
+  fs::write(root.join("oauth_config.json"), cfg.to_string()).unwrap();
+  queue::add(&root, "/renders/film.mp4", None).unwrap();
+  let worker = tokio::spawn({
+    let root = root.clone();
+    async move { queue::run(&root).await }
+  });
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  assert!(!worker.is_finished());
+
+  profiles::switch(&root, "music").unwrap();
+  tokio::time::timeout(Duration::from_secs(5), worker).await.expect("the worker ended").unwrap().unwrap();
+  assert_eq!(queue::list(&root)[0].status, queue::EntryStatus::Pending);
+
+  // Back again: a new worker runs rather than ending at once.
+  profiles::switch(&root, "default").unwrap();
+  let worker = tokio::spawn({
+    let root = root.clone();
+    async move { queue::run(&root).await }
+  });
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  assert!(!worker.is_finished());
+  worker.abort();
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+use tauri_youtube_oauth::progress::SpeedEstimator;
+
+fn secs(s: u64) -> Duration {
+  Duration::from_secs(s)
+}
+
+#[test]
+fn eta_appears_after_stable_samples() {
+  let mut est = SpeedEstimator::new();
+  est.sample(secs(0), 0);
+  for i in 1..=4 {
+    est.sample(secs(i), i * 1000);
+    assert_eq!(est.eta_secs(100_000), None, "ETA shown after {} samples", i);
+  }
+  est.sample(secs(5), 5000);
+  assert_eq!(est.smoothed_bytes_per_sec(), Some(1000.0));
+  assert_eq!(est.eta_secs(100_000), Some(95));
+}
+
+#[test]
+fn smoothing_damps_spikes() {
+  let mut est = SpeedEstimator::new();
+  est.sample(secs(0), 0);
+  let mut sent = 0;
+  for (i, chunk) in [1000, 1000, 9000, 1000, 1000].into_iter().enumerate() {
+    sent += chunk;
+    est.sample(secs(i as u64 + 1), sent);
+  }
+  let smoothed = est.smoothed_bytes_per_sec().unwrap();
+  assert_eq!(est.raw_bytes_per_sec(), 1000.0);
+  assert!(smoothed > 1000.0 && smoothed < 3000.0, "smoothed {}", smoothed);
+}
+
+#[test]
+fn retried_chunk_does_not_skew_speed() {
+  let mut est = SpeedEstimator::new();
+  est.sample(secs(0), 0);
+  for i in 1..=5 {
+    est.sample(secs(i), i * 1000);
+  }
+  // Server only kept 3000 bytes; the chunk is resent from there.
+  est.sample(secs(6), 3000);
+  est.sample(secs(7), 4000);
+  assert_eq!(est.smoothed_bytes_per_sec(), Some(1000.0));
+  assert_eq!(est.eta_secs(10_000), Some(6));
+}
+
+#[test]
+fn pause_restarts_stabilization() {
+  let mut est = SpeedEstimator::new();
+  est.sample(secs(0), 0);
+  for i in 1..=5 {
+    est.sample(secs(i), i * 1000);
+  }
+  assert!(est.eta_secs(10_000).is_some());
+
+  est.reset();
+  est.sample(secs(300), 5000);
+  assert_eq!(est.eta_secs(10_000), None);
+  est.sample(secs(301), 6000);
+  assert_eq!(est.smoothed_bytes_per_sec(), Some(1000.0));
+
+  // An unannounced long gap is also not counted as a slow interval.
+  let mut est = SpeedEstimator::new();
+  est.sample(secs(0), 0);
+  est.sample(secs(1), 1000);
+  est.sample(secs(120), 2000);
+  assert_eq!(est.smoothed_bytes_per_sec(), Some(1000.0));
+}
@@ -0,0 +1,77 @@
+use chrono_tz::Europe::Warsaw;
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{error::Error, publish_time, upload, ForceCode, Privacy, PublishTime, VideoMetadata};
+
+fn utc(input: &str, hour: u32) -> Result<String, String> {
+  publish_time::parse(input, Warsaw, hour).map(|t| t.to_rfc3339()).map_err(|e| e.to_string())
+}
+
+#[test]
+fn local_times_around_the_dst_changes_are_read_or_refused_with_both_instants() {
+  // 2026-03-29: 02:00 CET jumps to 03:00 CEST.
+  assert_eq!(utc("2026-03-29 01:30", 9).unwrap(), "2026-03-29T00:30:00+00:00");
+  assert_eq!(utc("2026-03-29T03:30", 9).unwrap(), "2026-03-29T01:30:00+00:00");
+  let skipped = utc("2026-03-29 02:30", 9).unwrap_err();
+  assert!(skipped.contains("nie istnieje"), "{}", skipped);
+  assert!(skipped.contains("2026-03-29T01:30:00Z") && skipped.contains("2026-03-29T00:30:00Z"), "{}", skipped);
+
+  // 2026-10-25: 03:00 CEST falls back to 02:00 CET, so 02:30 happens twice.
+  let repeated = utc("2026-10-25 02:30:00", 9).unwrap_err();
+  assert!(repeated.contains("dwa razy"), "{}", repeated);
+  assert!(repeated.contains("2026-10-25T00:30:00Z") && repeated.contains("2026-10-25T01:30:00Z"), "{}", repeated);
+  // With the offset spelled out there is nothing to guess.
+  assert_eq!(utc("2026-10-25T02:30:00+01:00", 9).unwrap(), "2026-10-25T01:30:00+00:00");
+
+  // A date alone goes out at the default hour, on either side of the change.
+  assert_eq!(utc("2026-10-24", 9).unwrap(), "2026-10-24T07:00:00+00:00");
+  assert_eq!(utc("2026-10-25", 9).unwrap(), "2026-10-25T08:00:00+00:00");
+  assert!(matches!(publish_time::parse("jutro o 18", Warsaw, 9), Err(Error::Validation(_))));
+
+  let at = publish_time::parse("2026-10-25 18:00", Warsaw, 9).unwrap();
+  assert_eq!(
+    publish_time::describe(Warsaw, at),
+    PublishTime { utc: "2026-10-25T17:00:00Z".into(), local: "2026-10-25T18:00:00+01:00".into(), timezone: "Europe/Warsaw".into() }
+  );
+}
+
+#[tokio::test]
+async fn uploads_send_publish_at_in_utc_and_plan_it_in_both_forms() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let writes = server.mock("POST", Matcher::Any).expect(0).create_async().await;
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "dry_run": true, "publish_timezone": "Europe/Warsaw", "default_publish_hour": 18 });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let video = dir.join("clip.mp4");
+  fs::write(&video, b"not really a video").unwrap();
+  let meta = |publish_at: &str| VideoMetadata {
+    title: "Klip".into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: Some(publish_at.into()),
+    thumbnail: None,
+    first_comment: None,
+  };
+
+  match upload::upload_video(dir, &video, &meta("2030-06-01"), &[ForceCode::RecentlyModified]).await {
+    Err(Error::DryRun(plan)) => {
+      assert!(plan.body_summary.contains(r#""publishAt":"2030-06-01T16:00:00Z""#), "{}", plan.body_summary);
+      let at = plan.publish_at.unwrap();
+      assert_eq!((at.utc.as_str(), at.local.as_str()), ("2030-06-01T16:00:00Z", "2030-06-01T18:00:00+02:00"));
+    }
+    other => panic!("expected dry-run plan, got {:?}", other),
+  }
+  // Refused before anything is planned.
+  let err = upload::upload_video(dir, &video, &meta("2026-10-25 02:30"), &[ForceCode::RecentlyModified]).await.unwrap_err();
+  assert!(matches!(err, Error::Validation(_)), "{:?}", err);
+  writes.assert_async().await;
+}
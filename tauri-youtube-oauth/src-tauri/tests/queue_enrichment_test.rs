@@ -0,0 +1,86 @@
+use serde_json::json;
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::{hashing, queue, HashAlgorithm, QueueEntry};
+
+const ENTRIES: usize = 100;
+
+/// Lists the queue until `done` holds for every entry, or fails after 10 s.
+async fn settled(dir: &Path, done: impl Fn(&QueueEntry) -> bool) -> Vec<QueueEntry> {
+  let deadline = Instant::now() + Duration::from_secs(10);
+  loop {
+    let entries = queue::list(dir);
+    if entries.iter().all(&done) {
+      return entries;
+    }
+    assert!(Instant::now() < deadline, "not enriched in time: {:?}", entries.iter().filter(|e| !done(e)).map(|e| &e.file_path).collect::<Vec<_>>());
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_returns_at_once_and_enrichment_follows_in_the_background() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().to_path_buf();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let file = |i: usize| dir.join(format!("clip-{:03}.mp4", i));
+  for i in 0..ENTRIES {
+    fs::write(file(i), vec![i as u8; 256 * 1024]).unwrap();
+    let sidecar = match i {
+      // No title and no template: the upload would refuse it.
+      0 => None,
+      1 => Some(json!({ "title": "Z miniaturą", "thumbnail": "brak.png" })),
+      _ => Some(json!({ "title": format!("Odcinek {}", i) })),
+    };
+    if let Some(sidecar) = sidecar {
+      fs::write(file(i).with_extension("json"), sidecar.to_string()).unwrap();
+    }
+  }
+  // Written before the algorithm was recorded: SHA-256.
+  let uploaded = hashing::hash_with(&dir, &file(2), HashAlgorithm::Sha256).unwrap().hash;
+  let entry = json!({ "video_id": "vidDup", "original_path": "/old/clip.mp4", "last_seen_path": "/old/clip.mp4", "uploaded_at": 1 });
+  fs::write(dir.join("library.json"), json!({ uploaded: entry }).to_string()).unwrap();
+  // Queued without a runtime, so nothing is enriched yet.
+  let queued = dir.clone();
+  std::thread::spawn(move || {
+    for i in 0..ENTRIES {
+      queue::add(&queued, &queued.join(format!("clip-{:03}.mp4", i)).to_string_lossy(), None).unwrap();
+    }
+  })
+  .join()
+  .unwrap();
+
+  let started = Instant::now();
+  let cold = queue::list(&dir);
+  let elapsed = started.elapsed();
+  assert_eq!(cold.len(), ENTRIES);
+  assert!(elapsed < Duration::from_millis(50), "list took {:?}", elapsed);
+
+  let entries = settled(&dir, |e| e.enrichment.is_some()).await;
+  let of = |i: usize| entries[i].enrichment.clone().unwrap();
+  assert!(of(0).title.is_none() && of(0).metadata_error.as_deref().is_some_and(|m| m.contains("Brak tytułu")), "{:?}", of(0));
+  assert_eq!((of(1).title.as_deref(), of(1).thumbnail_exists), (Some("Z miniaturą"), Some(false)));
+  assert_eq!(of(2).uploaded_as.as_deref(), Some("vidDup"));
+  assert_eq!((of(3).title.as_deref(), of(3).uploaded_as.as_deref(), of(3).thumbnail_exists), (Some("Odcinek 3"), None, None));
+  assert!(entries.iter().all(|e| e.enrichment.as_ref().unwrap().file_error.is_none()));
+  // Never written to the queue file.
+  assert!(!fs::read_to_string(dir.join("upload_queue.json")).unwrap().contains("enrichment"));
+
+  // An edited sidecar is picked up again; a deleted video is reported.
+  fs::write(file(3).with_extension("json"), json!({ "title": "Odcinek trzeci, poprawiony" }).to_string()).unwrap();
+  fs::remove_file(file(4)).unwrap();
+  let entries = settled(&dir, |e| {
+    let title = e.enrichment.as_ref().and_then(|x| x.title.as_deref());
+    let missing = e.enrichment.as_ref().is_some_and(|x| x.file_error.is_some());
+    match e.file_path.rsplit('/').next() {
+      Some("clip-003.mp4") => title == Some("Odcinek trzeci, poprawiony"),
+      Some("clip-004.mp4") => missing,
+      _ => e.enrichment.is_some(),
+    }
+  })
+  .await;
+  assert_eq!(entries.iter().filter(|e| e.enrichment.as_ref().unwrap().file_error.is_some()).count(), 1);
+}
@@ -0,0 +1,119 @@
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::queue::{self, EntryStatus, QueueEntry};
+
+fn entry(dir: &Path, name: &str) -> QueueEntry {
+  queue::list(dir).into_iter().find(|e| e.file_path.ends_with(name)).unwrap()
+}
+
+fn codes(e: &QueueEntry) -> Vec<Option<&str>> {
+  e.attempt_history.iter().map(|a| a.code.as_deref()).collect()
+}
+
+/// `queue-entry-*` events written so far, once there are `count` of them.
+async fn events(dir: &Path, count: usize) -> Vec<(String, Value)> {
+  let deadline = Instant::now() + Duration::from_secs(10);
+  loop {
+    let text = fs::read_to_string(dir.join("events.jsonl")).unwrap_or_default();
+    let lines: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    if lines.len() >= count {
+      return lines.into_iter().map(|l| (l["event"].as_str().unwrap().to_string(), l["payload"].clone())).collect();
+    }
+    assert!(Instant::now() < deadline, "{} of {} events: {:?}", lines.len(), count, lines);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+}
+
+#[tokio::test]
+async fn failed_entries_are_retried_on_request_and_give_up_after_the_max() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let sink = json!({ "type": "file", "path": "events.jsonl", "events": ["queue-entry-failed", "queue-entry-gave-up", "queue-entry-retried"] });
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "upload_min_file_age_secs": 0, "queue_max_attempts": 2, "event_sinks": [sink] });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube.upload" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  for name in ["flaky.mp4", "refused.mp4"] {
+    fs::write(dir.join(name), b"not really a video").unwrap();
+    fs::write(dir.join(name).with_extension("json"), json!({ "title": name }).to_string()).unwrap();
+    queue::add(dir, &dir.join(name).to_string_lossy(), None).unwrap();
+  }
+
+  let flaky = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .match_body(Matcher::Regex("flaky".into()))
+    .with_status(400)
+    .with_body(json!({ "error": { "code": 400, "message": "Bad", "errors": [{ "reason": "badRequest" }] } }).to_string())
+    .expect(2)
+    .create_async()
+    .await;
+  let refused = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .match_body(Matcher::Regex("refused".into()))
+    .with_status(403)
+    .with_body(json!({ "error": { "code": 403, "message": "No", "errors": [{ "reason": "forbidden" }] } }).to_string())
+    .expect(1)
+    .create_async()
+    .await;
+
+  // Both fail once and wait: the worker doesn't retry by itself.
+  assert_eq!(queue::process_due(dir).await.unwrap(), 2);
+  assert_eq!(queue::process_due(dir).await.unwrap(), 0);
+  let (one, two) = (entry(dir, "flaky.mp4"), entry(dir, "refused.mp4"));
+  assert_eq!((one.status, one.attempts, codes(&one)), (EntryStatus::Failed, 1, vec![Some("API_ERROR")]));
+  assert_eq!((two.status, two.attempts, codes(&two)), (EntryStatus::Failed, 1, vec![Some("FORBIDDEN")]));
+  let failed = events(dir, 2).await;
+  assert!(failed.iter().all(|(name, p)| name == "queue-entry-failed" && p["attempts"] == 1), "{:?}", failed);
+
+  // Only what failed with a listed code comes back; the refusal stays failed.
+  let retried = queue::retry_all_failed(dir, Some(&["API_ERROR".to_string(), "NETWORK_ERROR".to_string()])).unwrap();
+  assert_eq!(retried.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), [one.id.as_str()]);
+  assert_eq!((retried[0].status, retried[0].error.as_deref()), (EntryStatus::Pending, None));
+  assert_eq!(entry(dir, "refused.mp4").status, EntryStatus::Failed);
+
+  // The second failure is the last one allowed.
+  queue::process_due(dir).await.unwrap();
+  let one = entry(dir, "flaky.mp4");
+  assert_eq!((one.status, one.attempts, codes(&one).len()), (EntryStatus::GaveUp, 2, 2));
+  let serialized = serde_json::to_value(&one).unwrap();
+  assert_eq!(serialized["status"], "gave_up");
+  assert_eq!(serialized["attempt_history"][1]["code"], "API_ERROR");
+  flaky.assert_async().await;
+  refused.assert_async().await;
+
+  // Without a filter every failed entry comes back, but not one that gave up.
+  let retried = queue::retry_all_failed(dir, None).unwrap();
+  assert_eq!(retried.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), [two.id.as_str()]);
+  queue::remove(dir, &two.id).unwrap();
+
+  // An explicit retry does, with a fresh count and the history kept.
+  server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .with_header("location", &format!("{}/upload/session/r", server.url()))
+    .create_async()
+    .await;
+  server.mock("PUT", "/upload/session/r").with_status(201).with_body(r#"{"id":"vid"}"#).create_async().await;
+  let back = queue::retry(dir, &one.id).unwrap();
+  assert_eq!((back.status, back.attempts), (EntryStatus::Pending, 0));
+  assert_eq!(queue::process_due(dir).await.unwrap(), 1);
+  let done = entry(dir, "flaky.mp4");
+  assert_eq!((done.status, done.attempts, codes(&done)), (EntryStatus::Done, 1, vec![Some("API_ERROR"), Some("API_ERROR"), None]));
+  assert!(queue::retry(dir, &one.id).is_err(), "a done entry isn't retried");
+  assert!(queue::retry(dir, "missing").is_err());
+
+  let names: Vec<(String, Value)> = events(dir, 6).await;
+  let names: Vec<(&str, &str)> = names.iter().map(|(n, p)| (n.as_str(), p["from"].as_str().unwrap_or(""))).collect();
+  assert_eq!(names[2..], [("queue-entry-retried", "failed"), ("queue-entry-gave-up", ""), ("queue-entry-retried", "failed"), ("queue-entry-retried", "gave_up")]);
+}
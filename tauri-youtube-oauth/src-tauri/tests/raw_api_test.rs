@@ -0,0 +1,73 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{collections::BTreeMap, fs, path::Path};
+use tauri_youtube_oauth::{error::Error, quota, raw_api};
+
+fn write_config(dir: &Path, extra: serde_json::Value) {
+  let mut cfg = json!({ "client_id": "id", "client_secret": "secret" });
+  cfg.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn raw_requests_are_gated_charged_and_sent_with_the_tokens() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let none = BTreeMap::new();
+
+  write_config(dir, json!({}));
+  let err = raw_api::request(dir, "GET", "/youtube/v3/captions", &none, None).await.unwrap_err();
+  assert!(matches!(err, Error::Config(ref m) if m.contains("allow_raw_api")), "{:?}", err);
+
+  write_config(dir, json!({ "allow_raw_api": true, "raw_api_costs": { "/youtube/v3/captions": 50 } }));
+  for bad in ["https://oauth2.googleapis.com/token", "http://youtube.googleapis.com/youtube/v3/videos", "https://evil.example/x", "/youtube/v3/videos?part=id", "/youtube/../token"] {
+    let err = raw_api::request(dir, "GET", bad, &none, None).await.unwrap_err();
+    assert!(matches!(err, Error::Validation(_)), "{}: {:?}", bad, err);
+  }
+  assert!(matches!(raw_api::request(dir, "TRACE", "/youtube/v3/videos", &none, None).await, Err(Error::Validation(_))));
+  assert_eq!(quota::status(dir).used, 0);
+
+  let list = server
+    .mock("GET", "/youtube/v3/captions")
+    .match_header("authorization", "Bearer a")
+    .match_query(Matcher::AllOf(vec![Matcher::UrlEncoded("part".into(), "snippet".into()), Matcher::UrlEncoded("videoId".into(), "v1".into())]))
+    .with_header("etag", "\"abc\"")
+    .with_header("x-internal", "hidden")
+    .with_body(r#"{"items":[{"id":"c1"}]}"#)
+    .create_async()
+    .await;
+  let query = BTreeMap::from([("part".to_string(), "snippet".to_string()), ("videoId".to_string(), "v1".to_string())]);
+  let resp = raw_api::request(dir, "get", "/youtube/v3/captions", &query, None).await.unwrap();
+  assert_eq!((resp.status, &resp.body["items"][0]["id"]), (200, &json!("c1")));
+  assert_eq!(resp.headers.get("etag").map(String::as_str), Some("\"abc\""));
+  assert!(!resp.headers.contains_key("x-internal"));
+  list.assert_async().await;
+  // The cost override applies to reads as well.
+  assert_eq!(quota::status(dir).used, 50);
+
+  // Errors are the caller's to read, and still cost quota.
+  server.mock("DELETE", "/youtube/v3/comments").match_query(Matcher::Any).with_status(404).with_body("not json").create_async().await;
+  let query = BTreeMap::from([("id".to_string(), "gone".to_string())]);
+  let resp = raw_api::request(dir, "DELETE", "/youtube/v3/comments", &query, None).await.unwrap();
+  assert_eq!((resp.status, resp.body), (404, json!("not json")));
+  assert_eq!(quota::status(dir).used, 100);
+
+  // A revoked access token gets the usual refresh and replay.
+  server.mock("POST", "/token").with_body(r#"{"access_token":"b","expires_in":3600}"#).create_async().await;
+  server.mock("GET", "/youtube/v3/i18nLanguages").match_header("authorization", "Bearer a").with_status(401).create_async().await;
+  server.mock("GET", "/youtube/v3/i18nLanguages").match_header("authorization", "Bearer b").with_body("{}").create_async().await;
+  assert_eq!(raw_api::request(dir, "GET", "/youtube/v3/i18nLanguages", &none, None).await.unwrap().status, 200);
+
+  write_config(dir, json!({ "allow_raw_api": true, "dry_run": true }));
+  let body = json!({ "snippet": { "title": "x" } });
+  match raw_api::request(dir, "POST", "/youtube/v3/playlists", &none, Some(body)).await {
+    Err(Error::DryRun(plan)) => assert_eq!((plan.method.as_str(), plan.estimated_quota), ("POST", 50)),
+    other => panic!("{:?}", other),
+  }
+}
@@ -0,0 +1,88 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{collections::BTreeMap, fs, path::Path};
+use tauri_youtube_oauth::{
+  auth, channel,
+  chapters::{self, Chapter},
+  error::Error,
+  live, playlists, queue, raw_api, sections, thumbnails, upload, ForceCode, Privacy, VideoMetadata,
+};
+
+fn write_config(dir: &Path, read_only: bool) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "channel_id": "UC1", "allow_raw_api": true, "read_only_mode": read_only });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+fn refused<T: std::fmt::Debug>(r: Result<T, Error>) {
+  assert!(matches!(r, Err(Error::ReadOnlyMode)), "{:?}", r);
+}
+
+#[tokio::test]
+async fn read_only_mode_keeps_every_write_off_the_wire() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  write_config(dir, true);
+
+  let mut writes = Vec::new();
+  for method in ["POST", "PUT", "DELETE", "PATCH"] {
+    writes.push(server.mock(method, Matcher::Any).expect(0).create_async().await);
+  }
+  // Writes that read first don't get to read either.
+  let videos = server.mock("GET", "/youtube/v3/videos").expect(0).create_async().await;
+
+  let video = dir.join("clip.mp4");
+  fs::write(&video, b"not really a video").unwrap();
+  let image = dir.join("thumb.png");
+  fs::write(&image, b"\x89PNG").unwrap();
+  let meta = VideoMetadata {
+    title: "Klip".into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  };
+  refused(upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await);
+  refused(channel::set_channel_description(dir, "Nowy opis").await);
+  refused(channel::unset_watermark(dir).await);
+  refused(playlists::add_to_playlist(dir, "PL1", "v1").await);
+  refused(playlists::deduplicate_playlist(dir, "PL1", false).await);
+  refused(sections::delete_section(dir, "s1").await);
+  refused(live::bind_stream(dir, "b1", "s1").await);
+  refused(thumbnails::set_thumbnail(dir, "v1", &image).await);
+  refused(chapters::set_chapters(dir, "v1", &[Chapter { start_secs: 0, title: "Wstęp".into() }]).await);
+  let none = BTreeMap::new();
+  refused(raw_api::request(dir, "POST", "/youtube/v3/captions", &none, Some(json!({}))).await);
+
+  // The queue keeps its entry pending instead of uploading it.
+  let entry = queue::add(dir, video.to_str().unwrap(), Some(meta)).unwrap();
+  assert_eq!(queue::process_due(dir).await.unwrap(), 0);
+  let pending = &queue::list(dir)[0];
+  assert_eq!((&pending.id, pending.status), (&entry.id, queue::EntryStatus::Pending));
+  assert!(auth::status(dir).read_only_mode);
+
+  // Reads still go out.
+  let captions = server.mock("GET", "/youtube/v3/captions").with_body(r#"{"items":[]}"#).expect(1).create_async().await;
+  assert_eq!(raw_api::request(dir, "GET", "/youtube/v3/captions", &none, None).await.unwrap().status, 200);
+  captions.assert_async().await;
+  for mock in &writes {
+    mock.assert_async().await;
+  }
+  videos.assert_async().await;
+
+  // Off again: the same raw write reaches the server.
+  write_config(dir, false);
+  assert!(!auth::status(dir).read_only_mode);
+  server.reset();
+  let post = server.mock("POST", "/youtube/v3/captions").with_body("{}").expect(1).create_async().await;
+  raw_api::request(dir, "POST", "/youtube/v3/captions", &none, Some(json!({}))).await.unwrap();
+  post.assert_async().await;
+}
@@ -0,0 +1,78 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{auth, capabilities, comments, error::Error, metadata, reconsent, scopes, upload, write_tokens_to_dir, ForceCode, Tokens};
+
+fn write_config(dir: &Path, scopes: &[&str]) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "channel_id": "UC1", "scopes": scopes });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+fn tokens(scope: &str) -> Tokens {
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  Tokens { access_token: "a".into(), refresh_token: "r".into(), expires_in: 3600, created_at: now, scope: scope.into(), ..Default::default() }
+}
+
+#[tokio::test]
+async fn grown_scopes_are_flagged_until_consent_and_shrunk_ones_are_only_noted() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(dir, &[scopes::YOUTUBE_UPLOAD, scopes::YOUTUBE_READONLY]);
+  write_tokens_to_dir(dir, &tokens(&format!("{} {}", scopes::YOUTUBE_UPLOAD, scopes::YOUTUBE_READONLY))).unwrap();
+  let video = dir.join("odcinek.mp4");
+  fs::write(&video, vec![1u8; 1024]).unwrap();
+  fs::write(dir.join("odcinek.json"), json!({ "title": "Odcinek" }).to_string()).unwrap();
+  let meta = metadata::load_video_metadata(dir, &video).await.unwrap();
+  let refused = fs::read_to_string("tests/fixtures/forbidden_insufficient_permissions.json").unwrap();
+  let session = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .with_status(403)
+    .with_body(&refused)
+    .expect(2)
+    .create_async()
+    .await;
+
+  // The grant matches the config: a refused scope is a plain scope request.
+  match upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await {
+    Err(Error::InsufficientScope { reconsent: false, .. }) => {}
+    other => panic!("expected InsufficientScope, got {:?}", other),
+  }
+  assert!(reconsent::pending(dir).is_none());
+  assert!(!auth::status(dir).needs_reconsent);
+
+  // The config gains force-ssl: the next token use records it, and the 403 says why.
+  write_config(dir, &[scopes::YOUTUBE_UPLOAD, scopes::YOUTUBE_READONLY, scopes::YOUTUBE_FORCE_SSL]);
+  let err = upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await.unwrap_err();
+  assert!(matches!(err, Error::InsufficientScope { reconsent: true, .. }), "{:?}", err);
+  assert!(err.to_string().contains("reconsent"), "{}", err);
+  session.assert_async().await;
+  assert_eq!(reconsent::pending(dir).unwrap().missing, [scopes::YOUTUBE_FORCE_SSL]);
+  let status = auth::status(dir);
+  assert!(status.needs_reconsent);
+  assert_eq!(status.missing_scopes, [scopes::YOUTUBE_FORCE_SSL]);
+  let caps = capabilities::current(dir);
+  assert!(caps.needs_reconsent);
+  assert_eq!(caps.comments.missing_scopes, [scopes::YOUTUBE_FORCE_SSL]);
+
+  // Refused before sending, with the same hint.
+  match comments::post_comment(dir, "vid1", "Pierwszy").await {
+    Err(Error::InsufficientScope { missing, reconsent: true }) => assert_eq!(missing, [scopes::YOUTUBE_FORCE_SSL]),
+    other => panic!("expected InsufficientScope, got {:?}", other),
+  }
+
+  // The consent's tokens clear the flag.
+  let all = format!("{} {} {}", scopes::YOUTUBE_UPLOAD, scopes::YOUTUBE_READONLY, scopes::YOUTUBE_FORCE_SSL);
+  write_tokens_to_dir(dir, &tokens(&all)).unwrap();
+  assert!(reconsent::pending(dir).is_none());
+  assert!(!capabilities::current(dir).needs_reconsent);
+
+  // Fewer scopes configured: nothing to do, the leftover is only listed.
+  write_config(dir, &[scopes::YOUTUBE_UPLOAD]);
+  let status = auth::status(dir);
+  assert!(!status.needs_reconsent && status.missing_scopes.is_empty());
+  assert_eq!(status.unused_scopes, [scopes::YOUTUBE_READONLY]);
+}
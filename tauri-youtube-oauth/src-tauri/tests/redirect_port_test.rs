@@ -0,0 +1,90 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, net::TcpListener, path::Path};
+use tauri_youtube_oauth::{callback, config, error::Error, FlowState};
+
+fn free_port() -> u16 {
+  TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn write_config(dir: &Path, port: u16) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "redirect_port": port });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+fn saved_port(dir: &Path) -> serde_json::Value {
+  serde_json::from_str::<serde_json::Value>(&fs::read_to_string(dir.join("oauth_config.json")).unwrap()).unwrap()["redirect_port"].clone()
+}
+
+#[tokio::test]
+async fn a_flow_keeps_its_port_and_a_busy_port_is_refused_on_save() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let (first, second) = (free_port(), free_port());
+  write_config(dir, first);
+  assert!(callback::active_flow(dir).is_none());
+
+  callback::start(dir).unwrap();
+  let flow = callback::active_flow(dir).unwrap();
+  assert_eq!(flow.redirect_uri, format!("http://127.0.0.1:{}/callback", first));
+  assert_eq!((flow.port, flow.state), (first, FlowState::Waiting));
+
+  // Saved while the flow waits: only the next flow moves.
+  assert_eq!(callback::set_port(dir, Some(second)).unwrap(), second);
+  assert_eq!(saved_port(dir), second);
+  assert_eq!(callback::active_flow(dir).unwrap().port, first);
+  callback::check_port(dir, first).unwrap();
+
+  // Something else holds the port: refused by the setter and by a reload.
+  let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+  let busy = taken.local_addr().unwrap().port();
+  assert!(matches!(callback::set_port(dir, Some(busy)), Err(Error::Validation(_))));
+  assert_eq!(saved_port(dir), second);
+  write_config(dir, busy);
+  assert!(matches!(config::reload(dir), Err(Error::Validation(_))));
+  assert!(matches!(callback::set_port(dir, Some(0)), Err(Error::Validation(_))));
+  write_config(dir, second);
+  config::reload(dir).unwrap();
+
+  // The code is exchanged with the URI the flow started with, then it's over.
+  let redirect = format!("redirect_uri=http%3A%2F%2F127.0.0.1%3A{}%2Fcallback", first);
+  let exchange = server
+    .mock("POST", "/token")
+    .match_body(Matcher::Regex(redirect))
+    .with_body(r#"{"access_token":"a","refresh_token":"r","expires_in":3600}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let state = callback::flow_state(dir).unwrap();
+  let resp = reqwest::get(format!("http://127.0.0.1:{}/callback?code=c&state={}", first, state)).await.unwrap();
+  assert_eq!(resp.status().as_u16(), 200);
+  exchange.assert_async().await;
+  assert!(callback::active_flow(dir).is_none());
+
+  // The next flow listens on the new port; a refusal shows as failed.
+  callback::start(dir).unwrap();
+  assert_eq!(callback::active_flow(dir).unwrap().port, second);
+  // A page sending the browser here without the flow's state fails nothing.
+  for forged in ["error=access_denied", "error=access_denied&state=forged", "code=attacker", "code=attacker&state="] {
+    let resp = reqwest::get(format!("http://127.0.0.1:{}/callback?{}", second, forged)).await.unwrap();
+    assert_eq!(resp.status().as_u16(), 400, "{}", forged);
+    assert!(resp.text().await.unwrap().contains("state"), "{}", forged);
+    assert_eq!(callback::active_flow(dir).unwrap().state, FlowState::Waiting, "{}", forged);
+  }
+  let state = callback::flow_state(dir).unwrap();
+  let resp = reqwest::get(format!("http://127.0.0.1:{}/callback?error=access_denied&state={}", second, state)).await.unwrap();
+  assert_eq!(resp.status().as_u16(), 400);
+  assert_eq!(callback::active_flow(dir).unwrap().state, FlowState::Failed);
+  // Re-armed with a new state: the old one no longer passes.
+  callback::start(dir).unwrap();
+  assert_eq!(callback::active_flow(dir).unwrap().state, FlowState::Waiting);
+  assert_ne!(callback::flow_state(dir).unwrap(), state);
+  let resp = reqwest::get(format!("http://127.0.0.1:{}/callback?error=access_denied&state={}", second, state)).await.unwrap();
+  assert_eq!(resp.status().as_u16(), 400);
+  assert_eq!(callback::active_flow(dir).unwrap().state, FlowState::Waiting);
+  assert!(callback::stop(dir));
+  assert!(callback::active_flow(dir).is_none());
+  assert!(!callback::stop(dir));
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use tauri_youtube_oauth::{error::Error, live};
+
+fn seed_expired(dir: &std::path::Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let tokens = serde_json::json!({
+    "access_token": "old",
+    "refresh_token": "refresh",
+    "expires_in": 3600,
+    "created_at": 1,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn network_failures_back_off_and_invalid_grant_does_not() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+
+  let down = server.mock("POST", "/token").with_status(503).expect(3).create_async().await;
+  let tmp = tempfile::tempdir().unwrap();
+  seed_expired(tmp.path());
+  for _ in 0..3 {
+    assert!(matches!(live::list_streams(tmp.path()).await, Err(Error::Network(_))));
+  }
+  match live::list_streams(tmp.path()).await {
+    Err(Error::RefreshBackoff { retry_at }) => assert!(retry_at > 0),
+    other => panic!("expected backoff, got {:?}", other.map(|_| ())),
+  }
+  down.assert_async().await;
+  assert!(tmp.path().join("refresh_state.json").exists());
+
+  let rejected = server
+    .mock("POST", "/token")
+    .with_status(400)
+    .with_body(r#"{"error":"invalid_grant"}"#)
+    .expect(3)
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  seed_expired(tmp.path());
+  for _ in 0..3 {
+    assert!(matches!(live::list_streams(tmp.path()).await, Err(Error::GrantRevoked { hint: None })));
+  }
+  rejected.assert_async().await;
+  assert!(!tmp.path().join("refresh_state.json").exists());
+}
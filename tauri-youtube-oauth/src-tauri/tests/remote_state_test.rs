@@ -0,0 +1,145 @@
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::{
+  history::{self, UploadRecord, UploadStatus},
+  library,
+  queue::{self, EntryStatus},
+  remote_state::{self, RejectionKind, VerifyOptions},
+  upload,
+};
+
+fn fixture(name: &str) -> String {
+  fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)).unwrap()
+}
+
+fn completed(video_id: &str, title: &str) -> UploadRecord {
+  UploadRecord {
+    timestamp: 1_700_000_000,
+    profile: "default".into(),
+    file_path: format!("/renders/{}.mp4", title),
+    file_hash: format!("hash-{}", title),
+    video_id: Some(video_id.into()),
+    title: title.into(),
+    privacy: "public".into(),
+    duration_secs: 60,
+    bytes: 1024,
+    status: UploadStatus::Completed,
+    error: None,
+    suspect: false,
+    rejection: None,
+    forced: None,
+  }
+}
+
+fn statuses(dir: &Path) -> Vec<(Option<String>, UploadStatus)> {
+  history::read_all(dir).into_iter().map(|r| (r.video_id, r.status)).collect()
+}
+
+/// Events written so far, once there are `count` of them.
+async fn events(dir: &Path, count: usize) -> Vec<Value> {
+  let deadline = Instant::now() + Duration::from_secs(10);
+  loop {
+    let text = fs::read_to_string(dir.join("events.jsonl")).unwrap_or_default();
+    let lines: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    if lines.len() >= count {
+      return lines;
+    }
+    assert!(Instant::now() < deadline, "{} of {} events: {:?}", lines.len(), count, lines);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+}
+
+#[tokio::test]
+async fn rejected_uploads_fail_the_queue_entry_and_verify_sorts_out_the_rest() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let sink = json!({ "type": "file", "path": "events.jsonl", "events": ["queue-entry-failed", "remote-state-verified"] });
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "upload_min_file_age_secs": 0, "event_sinks": [sink] });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let scope = "https://www.googleapis.com/auth/youtube.upload https://www.googleapis.com/auth/youtube.readonly";
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": scope });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let file = dir.join("dup.mp4");
+  fs::write(&file, b"seen that before").unwrap();
+  fs::write(file.with_extension("json"), json!({ "title": "Duplikat" }).to_string()).unwrap();
+  queue::add(dir, &file.to_string_lossy(), None).unwrap();
+
+  let location = format!("{}/upload/session/dup", server.url());
+  server.mock("POST", "/upload/youtube/v3/videos").match_query(Matcher::Any).with_header("location", &location).create_async().await;
+  server.mock("PUT", "/upload/session/dup").with_status(201).with_body(r#"{"id":"vidDup"}"#).create_async().await;
+  server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("id".into(), "vidDup".into()))
+    .with_body(fixture("videos_processing_rejected_duplicate.json"))
+    .create_async()
+    .await;
+
+  // The upload went through, but YouTube turned it down as a duplicate.
+  assert_eq!(queue::process_due(dir).await.unwrap(), 1);
+  let processed = upload::wait_for_processing(dir, "vidDup", Duration::from_millis(10), Duration::from_secs(5)).await.unwrap();
+  assert_eq!(processed.status, "rejected");
+  let rejection = processed.rejection.unwrap();
+  assert_eq!((rejection.kind, rejection.reason.as_deref()), (RejectionKind::Rejected, Some("duplicate")));
+
+  let entry = queue::list(dir).into_iter().next().unwrap();
+  assert_eq!(entry.status, EntryStatus::Failed);
+  assert_eq!(entry.attempt_history.last().unwrap().code.as_deref(), Some("VIDEO_REJECTED"));
+  assert!(entry.error.as_deref().unwrap().contains("duplicate"), "{:?}", entry.error);
+  let failed = events(dir, 1).await;
+  assert_eq!((failed[0]["event"].as_str(), failed[0]["payload"]["code"].as_str()), (Some("queue-entry-failed"), Some("VIDEO_REJECTED")));
+  let record = history::read_all(dir).pop().unwrap();
+  assert_eq!((record.status, record.rejection.map(|r| r.kind)), (UploadStatus::Rejected, Some(RejectionKind::Rejected)));
+  let known = library::find_local_file_for_video(dir, "vidDup").unwrap();
+  assert_eq!(known.entry.rejection.and_then(|r| r.reason).as_deref(), Some("duplicate"));
+
+  // A failed processing ends the wait the same way.
+  server.mock("GET", "/youtube/v3/videos").match_query(Matcher::UrlEncoded("id".into(), "vidBroken".into())).with_body(fixture("videos_processing_failed.json")).create_async().await;
+  let broken = upload::wait_for_processing(dir, "vidBroken", Duration::from_millis(10), Duration::from_secs(5)).await.unwrap();
+  assert_eq!(broken.status, "failed");
+  assert_eq!(broken.rejection.map(|r| (r.kind, r.reason)), Some((RejectionKind::Failed, Some("codec".into()))));
+
+  // Earlier uploads, one of them gone from YouTube altogether.
+  for (id, title) in [("vidLive", "live"), ("vidLater", "later"), ("vidClaimed", "claimed"), ("vidGone", "gone")] {
+    history::append(dir, &completed(id, title)).unwrap();
+  }
+  let listed = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::AllOf(vec![Matcher::Regex("vidLive".into()), Matcher::UrlEncoded("part".into(), "status,processingDetails".into())]))
+    .with_body(fixture("videos_list_remote_state.json"))
+    .expect(2)
+    .create_async()
+    .await;
+
+  let before = statuses(dir);
+  let dry = remote_state::verify(dir, &VerifyOptions { since: None, dry_run: true }).await.unwrap();
+  assert_eq!((dry.checked, dry.live, dry.updated), (5, 1, 0));
+  assert_eq!(statuses(dir), before);
+
+  let report = remote_state::verify(dir, &VerifyOptions::default()).await.unwrap();
+  listed.assert_async().await;
+  let ids = |videos: &[remote_state::RemoteVideo]| videos.iter().map(|v| v.video_id.clone()).collect::<Vec<_>>();
+  assert_eq!(ids(&report.missing), ["vidGone"]);
+  assert_eq!(ids(&report.private), ["vidLater"]);
+  assert_eq!(ids(&report.rejected), ["vidClaimed", "vidDup"]);
+  assert_eq!(report.private[0].publish_at.as_deref(), Some("2030-01-01T10:00:00Z"));
+  assert_eq!(report.missing[0].file_path.as_deref(), Some("/renders/gone.mp4"));
+  // The duplicate was already recorded; the claim and the removal are new.
+  assert_eq!(report.updated, 2);
+  let after: Vec<UploadStatus> = statuses(dir).into_iter().map(|(_, s)| s).collect();
+  let expected = [UploadStatus::Rejected, UploadStatus::Completed, UploadStatus::Completed, UploadStatus::Rejected, UploadStatus::Rejected];
+  assert_eq!(after, expected);
+
+  let verified = events(dir, 3).await;
+  assert!(verified[1..].iter().all(|e| e["event"] == "remote-state-verified"), "{:?}", verified);
+  assert_eq!(verified[1]["payload"]["updated"], 0);
+  assert_eq!(verified[2]["payload"], json!({ "checked": 5, "live": 1, "missing": 1, "private": 1, "rejected": 2, "updated": 2 }));
+}
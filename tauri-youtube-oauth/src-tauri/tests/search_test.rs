@@ -0,0 +1,61 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{
+  error::Error,
+  quota,
+  search::{self, SearchId, SearchOptions},
+};
+
+#[tokio::test]
+async fn search_refuses_without_quota_and_caches_the_normalized_query() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let list = server
+    .mock("GET", "/youtube/v3/search")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("part".into(), "snippet".into()),
+      Matcher::UrlEncoded("q".into(), "Rust Tauri".into()),
+    ]))
+    .with_body(
+      json!({
+        "items": [
+          { "id": { "kind": "youtube#video", "videoId": "v1" }, "snippet": { "title": "Pierwszy", "channelId": "UC1" } },
+          { "id": { "kind": "youtube#playlist", "playlistId": "PL1" }, "snippet": { "title": "Lista" } },
+        ],
+      })
+      .to_string(),
+    )
+    .expect(1)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret","daily_quota":250}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "access", "refresh_token": "r", "expires_in": 3600, "created_at": now });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  // Three pages would cost 300 units: refused before the first one, not halfway.
+  let wide = SearchOptions { max_results: Some(150), ..Default::default() };
+  match search::search(dir, "Rust Tauri", &wide).await {
+    Err(Error::QuotaExceeded { needed, remaining }) => assert_eq!((needed, remaining), (300, 250)),
+    other => panic!("expected QuotaExceeded, got {:?}", other),
+  }
+  assert_eq!(quota::status(dir).used, 0);
+
+  let narrow = SearchOptions { max_results: Some(50), ..Default::default() };
+  let results = search::search(dir, "Rust Tauri", &narrow).await.unwrap();
+  assert_eq!(results.len(), 2);
+  assert_eq!(results[0].id, SearchId::Video { video_id: "v1".into() });
+  assert_eq!(results[1].id, SearchId::Playlist { playlist_id: "PL1".into() });
+  assert_eq!(quota::status(dir).used, 100);
+
+  // Case and whitespace don't matter: served from the cache, no second call or charge.
+  let again = search::search(dir, "  rust   TAURI ", &narrow).await.unwrap();
+  assert_eq!(again.len(), 2);
+  assert_eq!(quota::status(dir).used, 100);
+  list.assert_async().await;
+}
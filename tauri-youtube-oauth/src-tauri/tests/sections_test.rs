@@ -0,0 +1,100 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{
+  error::Error,
+  sections::{self, SectionInput, SectionType},
+};
+
+fn single(playlist_id: &str) -> SectionInput {
+  SectionInput { section_type: SectionType::SinglePlaylist, title: None, playlist_ids: vec![playlist_id.into()], position: Some(0) }
+}
+
+#[tokio::test]
+async fn mutations_check_playlists_and_report_the_relisted_layout() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let own = server
+    .mock("GET", "/youtube/v3/playlists")
+    .match_query(Matcher::UrlEncoded("mine".into(), "true".into()))
+    .with_body(r#"{"items":[{"id":"PL1","snippet":{"title":"Moja"}}]}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let lookup = server
+    .mock("GET", "/youtube/v3/playlists")
+    .match_query(Matcher::UrlEncoded("id".into(), "PLX".into()))
+    .with_body(r#"{"items":[]}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  // The layout as the API reports it after the insert, with positions shifted.
+  let list = server
+    .mock("GET", "/youtube/v3/channelSections")
+    .match_query(Matcher::UrlEncoded("mine".into(), "true".into()))
+    .with_body(
+      json!({
+        "items": [
+          { "id": "sec1", "snippet": { "type": "recentUploads", "position": 1 } },
+          { "id": "new", "snippet": { "type": "singlePlaylist", "position": 0 }, "contentDetails": { "playlists": ["PL1"] } },
+        ],
+      })
+      .to_string(),
+    )
+    .expect(4)
+    .create_async()
+    .await;
+  let insert = server
+    .mock("POST", "/youtube/v3/channelSections")
+    .match_body(Matcher::PartialJson(json!({ "snippet": { "type": "singlePlaylist" }, "contentDetails": { "playlists": ["PL1"] } })))
+    .with_body(r#"{"id":"new","snippet":{"type":"singlePlaylist","position":0}}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let delete = server
+    .mock("DELETE", "/youtube/v3/channelSections")
+    .match_query(Matcher::UrlEncoded("id".into(), "sec1".into()))
+    .expect(1)
+    .create_async()
+    .await;
+
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({
+    "access_token": "access",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  // Neither among the channel's own playlists nor found by id: refused before anything is created.
+  match sections::create_section(dir, &single("PLX")).await {
+    Err(Error::Validation(message)) => assert!(message.contains("PLX"), "{}", message),
+    other => panic!("expected a refusal for the missing playlist, got {:?}", other),
+  }
+  let untitled = SectionInput {
+    section_type: SectionType::MultiplePlaylists,
+    title: Some(" ".into()),
+    playlist_ids: vec!["PL1".into()],
+    position: None,
+  };
+  assert!(matches!(sections::create_section(dir, &untitled).await, Err(Error::Validation(m)) if m.contains("wymaga tytułu")));
+
+  // The result is the re-listed layout in position order, not the insert's own response.
+  let layout = sections::create_section(dir, &single("PL1")).await.unwrap();
+  assert_eq!(layout.iter().map(|s| (s.id.as_str(), s.position)).collect::<Vec<_>>(), [("new", 0), ("sec1", 1)]);
+  assert_eq!(layout[0].playlist_ids, ["PL1"]);
+  assert_eq!(sections::delete_section(dir, "sec1").await.unwrap().len(), 2);
+  assert_eq!(sections::list_sections(dir).await.unwrap()[1].section_type, "recentUploads");
+
+  own.assert_async().await;
+  lookup.assert_async().await;
+  list.assert_async().await;
+  insert.assert_async().await;
+  delete.assert_async().await;
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use tauri_youtube_oauth::{channel, setup, SetupStep};
+
+#[test]
+fn walks_setup_steps_from_disk() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let s = setup::current(dir);
+  assert_eq!(s.step, SetupStep::NeedsClientCredentials);
+  assert_eq!(s.missing, ["client_id", "client_secret"]);
+
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","#).unwrap();
+  let s = setup::current(dir);
+  assert_eq!(s.step, SetupStep::NeedsClientCredentials);
+  assert_eq!(s.problem.unwrap().file, "oauth_config.json");
+
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":""}"#).unwrap();
+  assert_eq!(setup::current(dir).missing, ["client_secret"]);
+
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let s = setup::current(dir);
+  assert_eq!((s.step, s.missing), (SetupStep::NeedsConsent, vec!["tokens".to_string()]));
+
+  fs::write(dir.join("tokens.json"), "").unwrap();
+  assert_eq!(setup::current(dir).problem.unwrap().file, "tokens.json");
+
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "scope": "https://www.googleapis.com/auth/youtube.readonly",
+    "client_id": "other",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  assert_eq!(setup::current(dir).step, SetupStep::NeedsConsent);
+
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "scope": "https://www.googleapis.com/auth/youtube.readonly",
+    "client_id": "id",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let s = setup::current(dir);
+  assert_eq!((s.step, s.missing), (SetupStep::NeedsChannelSelection, vec!["channel_id".to_string()]));
+
+  channel::select_channel(dir, "UC123").unwrap();
+  assert_eq!(setup::current(dir).step, SetupStep::Ready);
+}
@@ -0,0 +1,49 @@
+use std::{fs, path::Path, time::Duration};
+use tauri_youtube_oauth::shutdown;
+
+/// Stand-in for a resumable upload: "sends" chunks and records its session
+/// after each one, stopping at a chunk boundary once shutdown begins.
+async fn fake_upload(dir: &Path) -> usize {
+  let _guard = shutdown::track_upload(dir);
+  let session = dir.join("upload_session.json");
+  let mut offset = 0;
+  while !shutdown::stopping(dir) {
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    offset += 256 * 1024;
+    fs::write(&session, serde_json::json!({ "offset": offset, "state": "uploading" }).to_string()).unwrap();
+  }
+  fs::write(&session, serde_json::json!({ "offset": offset, "state": "paused" }).to_string()).unwrap();
+  offset
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_upload_to_persist() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().to_path_buf();
+  let worker = tokio::spawn({
+    let dir = dir.clone();
+    async move { fake_upload(&dir).await }
+  });
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  assert_eq!(shutdown::pending_uploads(&dir), 1);
+
+  // First close only warns while an upload runs; the second goes through.
+  assert!(!shutdown::request_exit(&dir));
+  assert!(shutdown::request_exit(&dir));
+
+  let report = shutdown::shutdown(&dir, shutdown::GRACE).await;
+  assert_eq!((report.pending_at_start, report.abandoned), (1, 0));
+  let offset = worker.await.unwrap();
+  let session: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("upload_session.json")).unwrap()).unwrap();
+  assert_eq!(session["state"], "paused");
+  assert_eq!(session["offset"], offset);
+  assert!(!shutdown::request_exit(&dir), "no second shutdown once stopping");
+}
+
+#[tokio::test]
+async fn grace_period_bounds_the_wait() {
+  let tmp = tempfile::tempdir().unwrap();
+  let _stuck = shutdown::track_upload(tmp.path());
+  let report = shutdown::shutdown(tmp.path(), Duration::from_millis(200)).await;
+  assert_eq!(report.abandoned, 1);
+}
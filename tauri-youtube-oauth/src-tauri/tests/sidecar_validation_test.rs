@@ -0,0 +1,68 @@
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{error::Error, metadata, queue, sidecar, Severity};
+
+#[test]
+fn diagnostics_name_the_closest_key_the_allowed_values_and_the_position() {
+  let text = "{\n  \"title\": \"Odcinek 1\",\n  \"privacyStauts\": \"public\",\n  \"categoryId\": \"27\",\n  \"privacy\": \"publik\",\n  \"tags\": \"rust\",\n  \"publish_at\": \"jutro\",\n  \"colour\": \"red\"\n}";
+  let found = sidecar::validate_text(text);
+  let summary: Vec<_> =
+    found.iter().map(|d| (d.severity, d.field.as_deref().unwrap(), d.suggestion.as_deref(), d.line, d.column)).collect();
+  assert_eq!(
+    summary,
+    [
+      (Severity::Error, "privacy", Some("public"), Some(5), Some(3)),
+      (Severity::Error, "tags", None, Some(6), Some(3)),
+      (Severity::Error, "publish_at", None, Some(7), Some(3)),
+      (Severity::Warning, "privacyStauts", Some("privacy"), Some(3), Some(3)),
+      (Severity::Warning, "categoryId", Some("category_id"), Some(4), Some(3)),
+      (Severity::Warning, "colour", None, Some(8), Some(3)),
+    ]
+  );
+  assert!(found[0].message.contains("public, unlisted, private"), "{}", found[0].message);
+
+  let broken = sidecar::validate_text("{\n  \"title\": \"a\",\n  \"tags\": [\"x\",]\n}");
+  assert_eq!(broken.len(), 1);
+  assert_eq!((broken[0].severity, broken[0].field.as_deref(), broken[0].line), (Severity::Error, None, Some(3)));
+
+  let valid = json!({ "title": "t", "description": null, "tags": ["a"], "privacy": "unlisted", "publish_at": "2026-01-31T18:00:00Z" });
+  assert!(sidecar::validate_text(&valid.to_string()).is_empty());
+}
+
+#[tokio::test]
+async fn errors_keep_a_file_out_of_the_queue_and_warnings_only_when_configured() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let write_config = |strict: bool| {
+    let cfg = json!({ "client_id": "id", "client_secret": "secret", "sidecar_warnings_block": strict });
+    fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  };
+  write_config(false);
+  let video = dir.join("film.mp4");
+  let video_path = video.to_str().unwrap();
+  let sidecar_path = metadata::sidecar_path(&video);
+
+  fs::write(&sidecar_path, json!({ "title": "Film", "privacy": "hidden" }).to_string()).unwrap();
+  match queue::add(dir, video_path, None) {
+    Err(Error::SidecarInvalid { path, diagnostics }) => {
+      assert_eq!(path, sidecar_path.display().to_string());
+      assert_eq!(diagnostics[0].field.as_deref(), Some("privacy"));
+    }
+    other => panic!("{:?}", other),
+  }
+  assert!(queue::list(dir).is_empty());
+
+  // An unknown key alone is let through and ignored, unless warnings block.
+  fs::write(&sidecar_path, json!({ "title": "Film", "privacy": "private", "thumbnial": "t.png" }).to_string()).unwrap();
+  let loaded = metadata::load_video_metadata(dir, &video).await.unwrap();
+  assert_eq!(loaded.title, "Film");
+  queue::add(dir, video_path, None).unwrap();
+  write_config(true);
+  let err = metadata::load_video_metadata(dir, &video).await.unwrap_err();
+  assert_eq!(err.code(), "SIDECAR_INVALID");
+  assert!(err.to_string().contains("thumbnail"), "{}", err);
+  assert!(queue::add(dir, video_path, None).is_err());
+  // Metadata given with the entry doesn't read the sidecar.
+  queue::add(dir, video_path, Some(loaded)).unwrap();
+  assert_eq!(queue::list(dir).len(), 2);
+}
@@ -0,0 +1,97 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{config, error::Error, snapshots, Snapshot, SnapshotReason};
+
+fn write_config(dir: &Path, dry_run: bool) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "dry_run": dry_run });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn restore_puts_the_old_metadata_back_and_keeps_the_last_five() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_config(dir, false);
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  // Taken before an edit that went wrong.
+  let old = Snapshot {
+    video_id: "vid1".into(),
+    timestamp: 1000,
+    reason: SnapshotReason::Update,
+    resource: json!({
+      "id": "vid1",
+      "snippet": { "title": "Stary", "description": "Opis", "categoryId": "22", "tags": ["rust"], "channelTitle": "Kanał" },
+      "status": { "privacyStatus": "unlisted", "uploadStatus": "processed" },
+      "localizations": { "en": { "title": "Old", "description": "Description" } },
+      "recordingDetails": { "recordingDate": "2026-01-02T00:00:00Z", "location": {} },
+    }),
+  };
+  fs::create_dir_all(dir.join("snapshots/vid1")).unwrap();
+  fs::write(dir.join("snapshots/vid1/1000.json"), serde_json::to_string(&old).unwrap()).unwrap();
+
+  let current = json!({ "items": [{ "id": "vid1", "snippet": { "title": "Nowy", "categoryId": "22" }, "status": { "privacyStatus": "private" } }] });
+  let fetch = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("id".into(), "vid1".into()),
+      Matcher::UrlEncoded("part".into(), "snippet,status,localizations,recordingDetails".into()),
+    ]))
+    .with_body(current.to_string())
+    .expect(5)
+    .create_async()
+    .await;
+  let put = server
+    .mock("PUT", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("part".into(), "snippet,status,localizations,recordingDetails".into()))
+    .match_body(Matcher::Json(json!({
+      "id": "vid1",
+      "snippet": { "title": "Stary", "description": "Opis", "categoryId": "22", "tags": ["rust"] },
+      "status": { "privacyStatus": "unlisted" },
+      "localizations": { "en": { "title": "Old", "description": "Description" } },
+      "recordingDetails": { "recordingDate": "2026-01-02T00:00:00Z" },
+    })))
+    .with_body(r#"{"id":"vid1"}"#)
+    .expect(5)
+    .create_async()
+    .await;
+
+  snapshots::restore(dir, "vid1", 1000).await.unwrap();
+  // The restore was an edit too: what it replaced comes first.
+  let listed = snapshots::list(dir, "vid1").unwrap();
+  assert_eq!(listed.len(), 2);
+  assert_eq!((listed[0].title.as_deref(), listed[0].privacy.as_deref()), (Some("Nowy"), Some("private")));
+  assert_eq!((listed[1].timestamp, listed[1].title.as_deref(), listed[1].reason), (1000, Some("Stary"), SnapshotReason::Update));
+
+  for _ in 0..4 {
+    snapshots::restore(dir, "vid1", 1000).await.unwrap();
+  }
+  let listed = snapshots::list(dir, "vid1").unwrap();
+  assert_eq!(listed.len(), snapshots::KEEP_PER_VIDEO);
+  assert!(listed.windows(2).all(|w| w[0].timestamp > w[1].timestamp));
+  assert!(matches!(snapshots::restore(dir, "vid1", 1000).await, Err(Error::Validation(_))), "the oldest was dropped");
+  fetch.assert_async().await;
+  put.assert_async().await;
+
+  // A dry run changes nothing, so there's nothing to keep.
+  write_config(dir, true);
+  assert!(matches!(snapshots::restore(dir, "vid1", listed[0].timestamp).await, Err(Error::DryRun(_))));
+  assert_eq!(snapshots::list(dir, "vid1").unwrap(), listed);
+
+  // Exports leave them out unless asked.
+  let bundle = dir.join("bundle.json");
+  config::export_bundle(dir, &bundle, false).unwrap();
+  let exported: serde_json::Value = serde_json::from_str(&fs::read_to_string(&bundle).unwrap()).unwrap();
+  assert!(exported.get("snapshots").is_none());
+  config::export_bundle(dir, &bundle, true).unwrap();
+  let exported: serde_json::Value = serde_json::from_str(&fs::read_to_string(&bundle).unwrap()).unwrap();
+  assert_eq!(exported["snapshots"]["vid1"].as_array().unwrap().len(), snapshots::KEEP_PER_VIDEO);
+
+  assert!(matches!(snapshots::list(dir, "../tokens"), Err(Error::Validation(_))));
+}
@@ -0,0 +1,159 @@
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::{
+  collections::BTreeSet,
+  fs,
+  io::Write,
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+use tauri_youtube_oauth::{config, status_file::MIN_INTERVAL, upload, ForceCode, Privacy, VideoMetadata, STATUS_SCHEMA_JSON};
+
+const CHUNK: u64 = 256 * 1024;
+
+fn seed(dir: &Path, status_file: Value) {
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "upload_chunk_bytes": CHUNK, "status_file": status_file });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({
+    "access_token": "ya29.status",
+    "refresh_token": "1//status",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube.upload",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+fn meta(title: &str) -> VideoMetadata {
+  VideoMetadata {
+    title: title.into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  }
+}
+
+/// A two-chunk upload for `session`, the second chunk answered slowly so
+/// the uploads overlap.
+async fn mock_upload(server: &mut mockito::ServerGuard, session: &str, file: &str) -> Vec<mockito::Mock> {
+  let total = 2 * CHUNK;
+  let open = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .match_body(Matcher::Regex(file.into()))
+    .with_header("location", &format!("{}/upload/session/{}", server.url(), session))
+    .expect(1)
+    .create_async()
+    .await;
+  let first = server
+    .mock("PUT", format!("/upload/session/{}", session).as_str())
+    .match_header("content-range", format!("bytes 0-{}/{}", CHUNK - 1, total).as_str())
+    .with_status(308)
+    .with_header("range", &format!("bytes=0-{}", CHUNK - 1))
+    .expect(1)
+    .create_async()
+    .await;
+  let video = format!(r#"{{"id":"vid-{}","kind":"youtube#video"}}"#, session);
+  let last = server
+    .mock("PUT", format!("/upload/session/{}", session).as_str())
+    .match_header("content-range", format!("bytes {}-{}/{}", CHUNK, total - 1, total).as_str())
+    .with_status(200)
+    .with_chunked_body(move |w| {
+      std::thread::sleep(Duration::from_millis(1500));
+      w.write_all(video.as_bytes())
+    })
+    .expect(1)
+    .create_async()
+    .await;
+  vec![open, first, last]
+}
+
+#[tokio::test]
+async fn concurrent_uploads_give_whole_consistent_snapshots_and_turning_off_removes_the_file() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().to_path_buf();
+  seed(&dir, json!({ "path": "run/status.json" }));
+  let status = dir.join("run/status.json");
+  let files: Vec<_> = ["first", "second"].iter().map(|n| dir.join(format!("{}.mp4", n))).collect();
+  for f in &files {
+    fs::write(f, vec![1u8; 2 * CHUNK as usize]).unwrap();
+  }
+  let mut mocks = mock_upload(&mut server, "a", "first").await;
+  mocks.extend(mock_upload(&mut server, "b", "second").await);
+
+  // Polls the file the whole time: every read must be a complete snapshot.
+  let done = Arc::new(AtomicBool::new(false));
+  let reader = {
+    let (status, done) = (status.clone(), done.clone());
+    std::thread::spawn(move || {
+      let mut seen = Vec::new();
+      while !done.load(Ordering::Relaxed) {
+        if let Ok(raw) = fs::read_to_string(&status) {
+          let snapshot: Value = serde_json::from_str(&raw).unwrap_or_else(|e| panic!("torn status.json ({}): {}", e, raw));
+          seen.push((raw, snapshot));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+      }
+      seen
+    })
+  };
+  let (a, b) = tokio::join!(
+    upload::upload_video(&dir, &files[0], &meta("first"), &[ForceCode::RecentlyModified]),
+    upload::upload_video(&dir, &files[1], &meta("second"), &[ForceCode::RecentlyModified])
+  );
+  assert_eq!((a.unwrap().video_id, b.unwrap().video_id), ("vid-a".to_string(), "vid-b".to_string()));
+  // The writes held back by the interval land after the uploads finish.
+  tokio::time::sleep(MIN_INTERVAL * 2).await;
+  done.store(true, Ordering::Relaxed);
+  let seen = reader.join().unwrap();
+  for m in &mocks {
+    m.assert_async().await;
+  }
+
+  let paths: BTreeSet<String> = files.iter().map(|f| f.to_string_lossy().into_owned()).collect();
+  let mut listed = BTreeSet::new();
+  for (raw, snapshot) in &seen {
+    assert!(!raw.contains("ya29.status") && !raw.contains("1//status") && !raw.contains("\"secret\""), "{}", raw);
+    assert_eq!(snapshot["version"], 1);
+    assert_eq!(snapshot["auth"]["authenticated"], true);
+    for u in snapshot["uploads"].as_array().unwrap() {
+      let file_path = u["file_path"].as_str().unwrap().to_string();
+      assert!(paths.contains(&file_path), "{}", file_path);
+      assert!(u["bytes_sent"].as_u64().unwrap() <= u["total_bytes"].as_u64().unwrap());
+      listed.insert(file_path);
+    }
+  }
+  assert_eq!(listed, paths, "both uploads were shown while they ran");
+  let last: Value = serde_json::from_str(&fs::read_to_string(&status).unwrap()).unwrap();
+  assert_eq!(last["uploads"], json!([]));
+  assert_eq!(last["last_error"], Value::Null);
+  assert!(last["quota"]["used"].as_u64().unwrap() > 0);
+
+  // Every written key is in the schema.
+  let schema: Value = serde_json::from_str(STATUS_SCHEMA_JSON).unwrap();
+  for key in last.as_object().unwrap().keys() {
+    assert!(schema["properties"].get(key).is_some(), "{} missing from the schema", key);
+  }
+  for key in last["auth"].as_object().unwrap().keys() {
+    assert!(schema["properties"]["auth"]["properties"].get(key).is_some(), "auth.{} missing from the schema", key);
+  }
+
+  // Off: the file goes, and held back writes don't bring it back.
+  seed(&dir, json!({ "path": "run/status.json", "disabled": true }));
+  config::reload(&dir).unwrap();
+  assert!(!status.exists());
+  tokio::time::sleep(MIN_INTERVAL * 2).await;
+  assert!(!status.exists());
+}
@@ -0,0 +1,128 @@
+use serde_json::json;
+use std::{
+  collections::BTreeSet,
+  fs,
+  path::Path,
+  time::{Duration, SystemTime},
+};
+use tauri_youtube_oauth::{cleanup, CleanupCategory, CleanupOptions, CleanupReport};
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn now() -> u64 {
+  SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn write_aged(path: &Path, contents: &str, age_days: u64) {
+  fs::create_dir_all(path.parent().unwrap()).unwrap();
+  fs::write(path, contents).unwrap();
+  let modified = SystemTime::now() - Duration::from_secs(age_days * DAY);
+  fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+}
+
+fn files(dir: &Path) -> BTreeSet<String> {
+  let mut found = BTreeSet::new();
+  let mut stack = vec![dir.to_path_buf()];
+  while let Some(d) = stack.pop() {
+    for e in fs::read_dir(d).unwrap().flatten() {
+      if e.file_type().unwrap().is_dir() {
+        stack.push(e.path());
+      } else {
+        found.insert(e.path().strip_prefix(dir).unwrap().to_string_lossy().replace('\\', "/"));
+      }
+    }
+  }
+  found
+}
+
+fn items(report: &CleanupReport, category: CleanupCategory) -> Vec<String> {
+  report.categories.iter().find(|c| c.category == category).unwrap().items.clone()
+}
+
+fn bytes(report: &CleanupReport, category: CleanupCategory) -> u64 {
+  report.categories.iter().find(|c| c.category == category).unwrap().bytes
+}
+
+/// A config dir after months of use: every kept file is old enough that its
+/// age alone would qualify it, so only the rules keep it. Returns the
+/// snapshot old enough to go.
+fn seed(dir: &Path) -> String {
+  for keep in ["tokens.json", "oauth_config.json", "upload_history.jsonl", "profiles/music/tokens.json", "profiles/music/.x.tmp"] {
+    write_aged(&dir.join(keep), "{}", 400);
+  }
+  write_aged(&dir.join("audit.jsonl"), "", 400);
+  write_aged(&dir.join("crashes/20240101T000000.000Z.txt"), "panic: old", 40);
+  write_aged(&dir.join("crashes/20261010T000000.000Z.txt"), "panic: recent", 4);
+  write_aged(&dir.join("sync.log"), "old log", 45);
+  write_aged(&dir.join("sync.log.1"), "rotated", 60);
+  write_aged(&dir.join("today.log"), "fresh log", 0);
+  write_aged(&dir.join(".tokens.json.123-0.tmp"), "{}", 2);
+  write_aged(&dir.join(".quota.json.456-1.tmp"), "{}", 0);
+  // Snapshots age by the timestamp they're named after.
+  let snapshot = |age_days: u64| format!("snapshots/vid1/{}.json", (now() - age_days * DAY) * 1000);
+  write_aged(&dir.join(snapshot(1)), "{}", 0);
+  let old_snapshot = snapshot(100);
+  write_aged(&dir.join(&old_snapshot), "{}", 0);
+  let cache = json!({
+    "/youtube/v3/channels?part=snippet": { "etag": null, "fetched_at": now() - 60 * DAY, "body": { "items": [] } },
+    "/youtube/v3/videos?id=a": { "etag": "e", "fetched_at": now() - DAY, "body": { "items": [] } },
+  });
+  fs::write(dir.join("http_cache.json"), cache.to_string()).unwrap();
+  let sessions = json!({
+    "/renders/old.mp4": { "session_url": "https://u/1", "total_bytes": 10, "file_hash": "h", "created_at": now() - 8 * DAY },
+    "/renders/new.mp4": { "session_url": "https://u/2", "total_bytes": 10, "file_hash": "h", "created_at": now() - DAY },
+  });
+  fs::write(dir.join("upload_sessions.json"), sessions.to_string()).unwrap();
+  old_snapshot
+}
+
+#[test]
+fn removes_exactly_the_stale_artifacts() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let old_snapshot = seed(dir);
+  let before = files(dir);
+
+  let planned = cleanup::cleanup(dir, &CleanupOptions { dry_run: true, ..Default::default() }).unwrap();
+  assert!(planned.dry_run);
+  assert_eq!(files(dir), before, "a dry run deletes nothing");
+  assert_eq!(items(&planned, CleanupCategory::Cache), ["/youtube/v3/channels?part=snippet"]);
+  assert_eq!(items(&planned, CleanupCategory::UploadSessions), ["/renders/old.mp4"]);
+  assert_eq!(items(&planned, CleanupCategory::Logs), ["crashes/20240101T000000.000Z.txt", "sync.log", "sync.log.1"]);
+  assert_eq!(items(&planned, CleanupCategory::Snapshots), [old_snapshot.as_str()]);
+  assert_eq!(items(&planned, CleanupCategory::TempFiles), [".tokens.json.123-0.tmp"]);
+  let logs_bytes = ("panic: old".len() + "old log".len() + "rotated".len()) as u64;
+  assert_eq!(bytes(&planned, CleanupCategory::Logs), logs_bytes);
+  assert!(planned.categories.iter().all(|c| c.bytes > 0));
+  assert_eq!(planned.total_bytes, planned.categories.iter().map(|c| c.bytes).sum::<u64>());
+
+  let cache_size = fs::metadata(dir.join("http_cache.json")).unwrap().len();
+  let done = cleanup::cleanup(dir, &CleanupOptions::default()).unwrap();
+  assert_eq!(done.total_bytes, planned.total_bytes);
+  let gone: BTreeSet<String> = before.difference(&files(dir)).cloned().collect();
+  let expected: BTreeSet<String> =
+    ["crashes/20240101T000000.000Z.txt", "sync.log", "sync.log.1", &old_snapshot, ".tokens.json.123-0.tmp"].iter().map(|s| s.to_string()).collect();
+  assert_eq!(gone, expected);
+  assert_eq!(
+    fs::metadata(dir.join("http_cache.json")).unwrap().len(),
+    cache_size - bytes(&done, CleanupCategory::Cache),
+    "reported bytes match the file shrinking"
+  );
+  let cache: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("http_cache.json")).unwrap()).unwrap();
+  assert_eq!(cache.as_object().unwrap().keys().collect::<Vec<_>>(), ["/youtube/v3/videos?id=a"]);
+  let sessions: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("upload_sessions.json")).unwrap()).unwrap();
+  assert_eq!(sessions.as_object().unwrap().keys().collect::<Vec<_>>(), ["/renders/new.mp4"]);
+
+  // A shorter retention reaches the recent crash report too; nothing else is left to take.
+  let again = cleanup::cleanup(dir, &CleanupOptions { log_retention_days: Some(1), cache_max_age_secs: Some(7 * DAY), dry_run: false }).unwrap();
+  assert_eq!(items(&again, CleanupCategory::Logs), ["crashes/20261010T000000.000Z.txt"]);
+  assert!(items(&again, CleanupCategory::Cache).is_empty() && items(&again, CleanupCategory::TempFiles).is_empty());
+  assert!(items(&again, CleanupCategory::Snapshots).is_empty());
+
+  // The weekly run happens once, then waits a week.
+  assert!(cleanup::run_if_due(dir).unwrap().is_some());
+  assert!(cleanup::run_if_due(dir).unwrap().is_none());
+  for keep in ["tokens.json", "oauth_config.json", "upload_history.jsonl", "audit.jsonl", "profiles/music/.x.tmp", "today.log"] {
+    assert!(dir.join(keep).exists(), "{} must survive", keep);
+  }
+}
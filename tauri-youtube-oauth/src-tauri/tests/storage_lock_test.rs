@@ -0,0 +1,105 @@
+use std::{
+  fs::{self, OpenOptions},
+  path::Path,
+  process::Command,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::{error::Error, read_tokens_from_dir, update_tokens_in_dir, write_tokens_to_dir, Tokens};
+
+const CHILD_DIR: &str = "STORAGE_LOCK_TEST_DIR";
+const ROUNDS: usize = 60;
+
+/// Adds `ROUNDS` words tagged `tag` to the stored scope, one read-modify-write each.
+fn hammer(dir: &Path, tag: &str) {
+  for i in 0..ROUNDS {
+    update_tokens_in_dir(dir, |t| t.scope = format!("{} {}-{}", t.scope, tag, i).trim().to_string()).unwrap();
+  }
+}
+
+#[test]
+fn token_updates_from_two_processes_and_threads_are_all_kept() {
+  if let Ok(dir) = std::env::var(CHILD_DIR) {
+    hammer(Path::new(&dir), "child");
+    return;
+  }
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  write_tokens_to_dir(dir, &Tokens { refresh_token: "r".into(), ..Tokens::default() }).unwrap();
+
+  let mut child = Command::new(std::env::current_exe().unwrap())
+    .args(["--exact", "token_updates_from_two_processes_and_threads_are_all_kept", "--test-threads=1"])
+    .env(CHILD_DIR, dir)
+    .spawn()
+    .unwrap();
+  std::thread::scope(|s| {
+    s.spawn(|| hammer(dir, "a"));
+    s.spawn(|| hammer(dir, "b"));
+  });
+  assert!(child.wait().unwrap().success());
+
+  let scope = read_tokens_from_dir(dir).expect("tokens.json stays valid JSON").scope;
+  let words: Vec<&str> = scope.split(' ').collect();
+  assert_eq!(words.len(), 3 * ROUNDS, "{}", scope);
+  for tag in ["a", "b", "child"] {
+    let mine: Vec<&str> = words.iter().copied().filter(|w| w.rsplit_once('-').unwrap().0 == tag).collect();
+    let expected: Vec<String> = (0..ROUNDS).map(|i| format!("{}-{}", tag, i)).collect();
+    assert_eq!(mine, expected);
+  }
+  assert_eq!(read_tokens_from_dir(dir).unwrap().refresh_token, "r");
+}
+
+#[test]
+fn a_lock_held_elsewhere_gives_storage_busy_then_clears() {
+  if std::env::var(CHILD_DIR).is_ok() {
+    return;
+  }
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let tokens = Tokens { refresh_token: "r".into(), ..Tokens::default() };
+  write_tokens_to_dir(dir, &tokens).unwrap();
+
+  let other = OpenOptions::new().write(true).open(dir.join(".lock")).unwrap();
+  other.lock().unwrap();
+  let started = Instant::now();
+  match write_tokens_to_dir(dir, &Tokens { refresh_token: "lost".into(), ..Tokens::default() }) {
+    Err(e @ Error::StorageBusy { .. }) => {
+      assert_eq!(e.code(), "STORAGE_BUSY");
+      assert!(e.retryable());
+    }
+    other => panic!("expected StorageBusy, got {:?}", other),
+  }
+  assert!(started.elapsed().as_millis() >= 1000);
+  assert_eq!(read_tokens_from_dir(dir).unwrap().refresh_token, "r");
+
+  other.unlock().unwrap();
+  update_tokens_in_dir(dir, |t| t.refresh_token = "next".into()).unwrap();
+  assert_eq!(read_tokens_from_dir(dir).unwrap().refresh_token, "next");
+  let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("tokens.json")).unwrap()).unwrap();
+  assert_eq!(json["refresh_token"], "next");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn waiting_for_the_lock_leaves_the_worker_to_other_tasks() {
+  if std::env::var(CHILD_DIR).is_ok() {
+    return;
+  }
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().to_path_buf();
+  write_tokens_to_dir(&dir, &Tokens { refresh_token: "r".into(), ..Tokens::default() }).unwrap();
+
+  let other = OpenOptions::new().write(true).open(dir.join(".lock")).unwrap();
+  other.lock().unwrap();
+  let writer = {
+    let dir = dir.clone();
+    tokio::spawn(async move { write_tokens_to_dir(&dir, &Tokens { refresh_token: "next".into(), ..Tokens::default() }) })
+  };
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  // The only worker is the writer's; this task still runs while it waits.
+  let other_task = tokio::spawn(tokio::time::sleep(Duration::from_millis(50)));
+  tokio::time::timeout(Duration::from_millis(1000), other_task).await.expect("the worker was held up").unwrap();
+  assert!(!writer.is_finished());
+
+  other.unlock().unwrap();
+  writer.await.unwrap().unwrap();
+  assert_eq!(read_tokens_from_dir(&dir).unwrap().refresh_token, "next");
+}
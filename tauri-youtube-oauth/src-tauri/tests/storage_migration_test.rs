@@ -0,0 +1,90 @@
+use serde_json::json;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+use tauri_youtube_oauth::{error::Error, migration, read_tokens_from_dir};
+
+const ID: &str = "com.ytlite.oauth";
+
+fn roaming(users: &Path, user: &str) -> PathBuf {
+  users.join(user).join("AppData").join("Roaming")
+}
+
+fn write_credentials(dir: &Path, client_id: &str) {
+  fs::create_dir_all(dir).unwrap();
+  fs::write(dir.join("oauth_config.json"), json!({ "client_id": client_id, "client_secret": "s" }).to_string()).unwrap();
+  let tokens = json!({ "access_token": "a", "refresh_token": format!("r-{}", client_id), "expires_in": 3600, "created_at": 1, "scope": "" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[test]
+fn renamed_account_is_offered_and_copied_only_on_request() {
+  let tmp = tempfile::tempdir().unwrap();
+  let users = tmp.path().join("Users");
+  let new_home = users.join("jan.kowalski");
+  let dir = roaming(&users, "jan.kowalski").join(ID);
+  fs::create_dir_all(&dir).unwrap();
+  let old = roaming(&users, "jan").join(ID);
+  write_credentials(&old, "old-id");
+  fs::create_dir_all(old.join("history")).unwrap();
+  fs::write(old.join("history/2024.jsonl"), "{}\n").unwrap();
+  fs::write(old.join(".lock"), "").unwrap();
+  fs::write(old.join(".tokens.json.1-0.tmp"), "torn").unwrap();
+  // A pre-rename identifier of another account, with a config that doesn't validate.
+  let broken = roaming(&users, "gosc").join(migration::LEGACY_IDENTIFIERS[0]);
+  fs::create_dir_all(&broken).unwrap();
+  fs::write(broken.join("oauth_config.json"), r#"{"client_id":"","client_secret":""}"#).unwrap();
+  fs::create_dir_all(users.join("Public")).unwrap();
+  let explicit = tmp.path().join("kopia");
+
+  let candidates = migration::candidates(&dir, Some(&new_home), Some(&explicit));
+  assert_eq!(candidates[0], explicit);
+  assert!(candidates.contains(&roaming(&users, "jan.kowalski").join(migration::LEGACY_IDENTIFIERS[1])));
+  assert!(candidates.contains(&old) && candidates.contains(&broken) && !candidates.contains(&dir), "{:?}", candidates);
+
+  // Only the valid source is offered, and nothing is copied by looking.
+  let offered = migration::probe(&dir, &candidates).unwrap();
+  assert_eq!(offered.sources.len(), 1);
+  assert_eq!((offered.sources[0].client_id.as_str(), offered.sources[0].has_tokens), ("old-id", true));
+  assert!(migration::is_empty(&dir));
+
+  // Something written in the new dir since is backed up before being replaced.
+  fs::write(dir.join("oauth_config.json"), json!({ "client_id": "new-id", "client_secret": "s" }).to_string()).unwrap();
+  assert_eq!(migration::probe(&dir, &candidates), None);
+  let report = migration::migrate(&dir, Path::new(&offered.sources[0].path)).unwrap();
+  let mut copied = report.copied.clone();
+  copied.sort();
+  let history = Path::new("history").join("2024.jsonl").display().to_string();
+  assert_eq!(copied, [history.as_str(), "oauth_config.json", "tokens.json"]);
+  assert_eq!(report.backed_up, ["oauth_config.json"]);
+  let backup = PathBuf::from(report.backup_dir.unwrap());
+  assert!(fs::read_to_string(backup.join("oauth_config.json")).unwrap().contains("new-id"));
+  assert_eq!(read_tokens_from_dir(&dir).unwrap().refresh_token, "r-old-id");
+  assert!(!dir.join(".tokens.json.1-0.tmp").exists());
+  // The source stays as it was.
+  assert!(old.join("tokens.json").exists() && old.join("history/2024.jsonl").exists());
+}
+
+#[test]
+fn unusable_sources_are_refused() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path().join("nowy");
+  let from = tmp.path().join("stary");
+  write_credentials(&from, "id");
+  fs::write(from.join("tokens.json"), "{ nie json").unwrap();
+  let err = migration::migrate(&dir, &from).unwrap_err();
+  assert!(matches!(err, Error::Validation(_)) && err.to_string().contains("tokens.json"), "{}", err);
+  assert!(migration::is_empty(&dir));
+
+  assert!(migration::migrate(&dir, &tmp.path().join("brak")).is_err());
+  write_credentials(&from, "id");
+  assert!(migration::migrate(&from, &from).is_err());
+  assert!(migration::migrate(&from.join("zagniezdzony"), &from).is_err());
+  assert!(!from.join("zagniezdzony").exists());
+
+  let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+  assert_eq!(migration::from_args(&args(&["--portable", "--migrate-from", "/stary"])), Some(PathBuf::from("/stary")));
+  assert_eq!(migration::from_args(&args(&["--migrate-from=/kopia"])), Some(PathBuf::from("/kopia")));
+  assert_eq!(migration::from_args(&args(&["--migrate-from"])), None);
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use tauri_youtube_oauth::{storage, StorageMode};
+
+#[test]
+fn portable_flag_moves_storage_next_to_exe() {
+  let exe_dir = tempfile::tempdir().unwrap();
+  let standard = tempfile::tempdir().unwrap();
+
+  let loc = storage::resolve(Some(exe_dir.path()), false, Some(standard.path().to_path_buf())).unwrap();
+  assert_eq!(loc.mode, StorageMode::Standard);
+  assert_eq!(loc.path, standard.path());
+
+  fs::write(exe_dir.path().join("portable.flag"), "").unwrap();
+  let loc = storage::resolve(Some(exe_dir.path()), false, Some(standard.path().to_path_buf())).unwrap();
+  assert_eq!(loc.mode, StorageMode::Portable);
+  assert_eq!(loc.path, exe_dir.path().join("ytlite-data"));
+  assert!(loc.path.is_dir());
+}
+
+#[test]
+fn unwritable_portable_dir_falls_back_to_standard() {
+  let exe_dir = tempfile::tempdir().unwrap();
+  let standard = tempfile::tempdir().unwrap();
+  // A file where the data dir should be can't be created or written into.
+  fs::write(exe_dir.path().join("ytlite-data"), "").unwrap();
+
+  let loc = storage::resolve(Some(exe_dir.path()), true, Some(standard.path().to_path_buf())).unwrap();
+  assert_eq!(loc.mode, StorageMode::Standard);
+  assert!(loc.portable_unavailable);
+}
+
+#[cfg(unix)]
+#[test]
+fn rewritten_files_keep_their_mode_and_new_token_files_are_owner_only() {
+  use std::os::unix::fs::PermissionsExt;
+  use tauri_youtube_oauth::{passphrase, write_tokens_to_dir, Tokens};
+  let mode = |p: &std::path::Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+  let tmp = tempfile::tempdir().unwrap();
+  let config = tmp.path().join("oauth_config.json");
+  fs::write(&config, r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  fs::set_permissions(&config, fs::Permissions::from_mode(0o640)).unwrap();
+  passphrase::set(tmp.path(), "correct horse", None).unwrap();
+  assert!(fs::read_to_string(&config).unwrap().contains("$argon2"));
+  assert_eq!(mode(&config), 0o640);
+
+  let tokens = Tokens { access_token: "a".into(), refresh_token: "r".into(), ..Default::default() };
+  write_tokens_to_dir(tmp.path(), &tokens).unwrap();
+  assert_eq!(mode(&tmp.path().join("tokens.json")), 0o600);
+}
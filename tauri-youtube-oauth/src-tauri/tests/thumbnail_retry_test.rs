@@ -0,0 +1,90 @@
+use mockito::Matcher;
+use std::{
+  fs,
+  path::Path,
+  time::{Duration, Instant},
+};
+use tauri_youtube_oauth::{error::Error, thumbnails};
+
+/// A PNG header with a 1280×720 IHDR, all `imagesize` reads.
+fn write_png(path: &Path) {
+  let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+  png.extend_from_slice(&1280u32.to_be_bytes());
+  png.extend_from_slice(&720u32.to_be_bytes());
+  png.extend_from_slice(b"\x08\x02\x00\x00\x00\x00\x00\x00\x00");
+  fs::write(path, png).unwrap();
+}
+
+fn fixture(name: &str) -> String {
+  fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)).unwrap()
+}
+
+#[tokio::test]
+async fn only_the_not_ready_answer_is_retried() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = r#"{"client_id":"id","client_secret":"secret","thumbnail_retry_window_secs":1}"#;
+  fs::write(dir.join("oauth_config.json"), cfg).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube.upload",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let image = dir.join("thumb.png");
+  write_png(&image);
+
+  // (video, status, captured body, retried)
+  let cases = [
+    ("new1", 404, "thumbnails_video_not_found.json", true),
+    ("new2", 400, "thumbnails_not_processed.json", true),
+    ("inel", 403, "thumbnails_forbidden.json", false),
+    ("img", 400, "thumbnails_invalid_image.json", false),
+  ];
+  for (video, status, body, retried) in cases {
+    let body = fixture(body);
+    let mock = server
+      .mock("POST", "/upload/youtube/v3/thumbnails/set")
+      .match_query(Matcher::UrlEncoded("videoId".into(), video.into()))
+      .with_status(status)
+      .with_body(&body);
+    let mock = if retried { mock.expect_at_least(3) } else { mock.expect(1) }.create_async().await;
+    let started = Instant::now();
+    let err = thumbnails::set_thumbnail(dir, video, &image).await.unwrap_err();
+    assert_eq!(thumbnails::is_processing_race(&err), retried, "{}: {:?}", video, err);
+    // Given up with the API's own error once the window ran out.
+    let reason = serde_json::from_str::<serde_json::Value>(&body).unwrap()["error"]["errors"][0]["reason"].as_str().unwrap().to_string();
+    match err {
+      Error::Api { reason: r, .. } | Error::Forbidden { reason: r, .. } => assert_eq!(r, reason),
+      other => panic!("{}: {:?}", video, other),
+    }
+    assert!(started.elapsed() < Duration::from_secs(if retried { 2 } else { 1 }), "{}", video);
+    mock.assert_async().await;
+  }
+
+  let ok = r#"{"kind":"youtube#thumbnailSetResponse","items":[{"default":{"url":"https://i.ytimg.com/vi/done/default.jpg","width":120,"height":90},"high":{"url":"https://i.ytimg.com/vi/done/hqdefault.jpg","width":480,"height":360}}]}"#;
+  let mock = server
+    .mock("POST", "/upload/youtube/v3/thumbnails/set")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("videoId".into(), "done".into()),
+      Matcher::UrlEncoded("uploadType".into(), "media".into()),
+    ]))
+    .match_header("content-type", "image/png")
+    .with_body(ok)
+    .create_async()
+    .await;
+  let sizes = thumbnails::set_thumbnail(dir, "done", &image).await.unwrap();
+  assert_eq!(sizes.keys().collect::<Vec<_>>(), ["default", "high"]);
+  assert_eq!(sizes["high"].width, Some(480));
+  mock.assert_async().await;
+
+  // Checked locally, before any quota is spent.
+  fs::write(dir.join("thumb.gif"), b"GIF89a\x01\x00\x01\x00\x00\x00\x00").unwrap();
+  assert!(matches!(thumbnails::set_thumbnail(dir, "done", &dir.join("thumb.gif")).await, Err(Error::Validation(_))));
+}
@@ -0,0 +1,98 @@
+use std::{
+  fs,
+  io::{BufRead, BufReader, Write},
+  net::TcpListener,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+};
+use tauri_youtube_oauth::{error::Error, live, tls};
+
+fn fixture(name: &str) -> PathBuf { Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls").join(name) }
+
+/// An HTTPS server on localhost with a certificate from the test CA,
+/// standing in for an intercepting proxy. Records each request line.
+fn intercepting_server(reply: &'static str) -> (u16, Arc<Mutex<Vec<String>>>) {
+  let certs = rustls_pemfile::certs(&mut BufReader::new(fs::File::open(fixture("localhost.pem")).unwrap())).unwrap();
+  let key = rustls_pemfile::pkcs8_private_keys(&mut BufReader::new(fs::File::open(fixture("localhost.key")).unwrap())).unwrap().remove(0);
+  let config = rustls::ServerConfig::builder()
+    .with_safe_defaults()
+    .with_no_client_auth()
+    .with_single_cert(certs.into_iter().map(rustls::Certificate).collect(), rustls::PrivateKey(key))
+    .unwrap();
+  let config = Arc::new(config);
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let port = listener.local_addr().unwrap().port();
+  let seen = Arc::new(Mutex::new(Vec::new()));
+  let log = seen.clone();
+  std::thread::spawn(move || {
+    for tcp in listener.incoming().flatten() {
+      let conn = rustls::ServerConnection::new(config.clone()).unwrap();
+      let mut tls = BufReader::new(rustls::StreamOwned::new(conn, tcp));
+      let mut line = String::new();
+      // A client refusing the certificate ends the handshake here.
+      if tls.read_line(&mut line).is_err() {
+        continue;
+      }
+      log.lock().unwrap().push(line.trim_end().to_string());
+      let mut header = String::new();
+      while tls.read_line(&mut header).is_ok_and(|n| n > 2) {
+        header.clear();
+      }
+      let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", reply.len(), reply);
+      let _ = tls.get_mut().write_all(response.as_bytes());
+      let _ = tls.get_mut().flush();
+    }
+  });
+  (port, seen)
+}
+
+#[tokio::test]
+async fn the_pinned_client_rejects_a_certificate_the_default_client_accepts() {
+  let (port, seen) = intercepting_server("{}");
+  let url = format!("https://localhost:{}/token", port);
+  let ca = reqwest::Certificate::from_pem(&fs::read(fixture("ca.pem")).unwrap()).unwrap();
+  let default = reqwest::Client::builder().add_root_certificate(ca.clone()).build().unwrap();
+  assert!(default.get(&url).send().await.unwrap().status().is_success());
+
+  // Adding the CA to a pinned builder doesn't get it trusted either.
+  let pinned = tls::pinned_builder().add_root_certificate(ca).build().unwrap();
+  let err = pinned.get(&url).send().await.unwrap_err();
+  assert!(err.is_connect(), "{:?}", err);
+  assert_eq!(seen.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn a_pinned_refresh_through_an_interceptor_sends_nothing_and_says_why() {
+  let (port, seen) = intercepting_server(r#"{"access_token":"ya29.fresh","expires_in":3600,"token_type":"Bearer"}"#);
+  // The interceptor's CA is "installed": trusted by the OS-store client.
+  std::env::set_var("SSL_CERT_FILE", fixture("ca.pem"));
+  std::env::set_var("OAUTH_TOKEN_URL", format!("https://localhost:{}/token", port));
+  std::env::set_var("YOUTUBE_API_URL", "http://127.0.0.1:9");
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let write_config = |pin: bool| {
+    let cfg = serde_json::json!({ "client_id": "id", "client_secret": "secret", "pin_google_tls": pin });
+    fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  };
+  write_config(true);
+  let tokens = serde_json::json!({
+    "access_token": "old",
+    "refresh_token": "refresh",
+    "expires_in": 3600,
+    "created_at": 1,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+
+  match live::list_streams(dir).await {
+    Err(Error::TlsInterceptionSuspected { host }) => assert_eq!(host, "localhost"),
+    other => panic!("expected TlsInterceptionSuspected, got {:?}", other.map(|_| ())),
+  }
+  // Only the bodyless probe got through; the refresh with the secret never did.
+  assert_eq!(*seen.lock().unwrap(), vec![String::from("HEAD /token HTTP/1.1")]);
+
+  // Off (the default), the same proxy is trusted and the refresh goes through it.
+  write_config(false);
+  let _ = live::list_streams(dir).await;
+  assert_eq!(seen.lock().unwrap().get(1).map(String::as_str), Some("POST /token HTTP/1.1"));
+}
@@ -0,0 +1,82 @@
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{auth, error::Error, live, token_age, ConsentStatus};
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn now() -> u64 {
+  std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn seed(dir: &Path, issued_at: Option<u64>, published: Option<bool>) {
+  let mut cfg = serde_json::json!({ "client_id": "id", "client_secret": "secret" });
+  if let Some(p) = published {
+    cfg["consent_screen_published"] = p.into();
+  }
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let mut tokens = serde_json::json!({
+    "access_token": "old",
+    "refresh_token": "refresh",
+    "expires_in": 3600,
+    "created_at": 1,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  if let Some(at) = issued_at {
+    tokens["issued_at"] = at.into();
+  }
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[test]
+fn warns_on_day_six_unless_published() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let issued = now() - 6 * DAY - 3600;
+  seed(dir, Some(issued), None);
+  let age = token_age::token_age(dir);
+  assert_eq!(age.consent_screen, ConsentStatus::Unknown);
+  assert_eq!(age.expires_if_testing_at, Some(issued + 7 * DAY));
+  assert!(age.warning.is_some());
+  assert_eq!(auth::status(dir).issued_at, Some(issued));
+
+  seed(dir, Some(issued), Some(true));
+  assert!(token_age::token_age(dir).warning.is_none());
+  seed(dir, Some(now() - 2 * DAY), Some(false));
+  let age = token_age::token_age(dir);
+  assert_eq!((age.consent_screen, age.warning), (ConsentStatus::Testing, None));
+  seed(dir, None, None);
+  assert!(token_age::token_age(dir).age_secs.is_none());
+}
+
+#[tokio::test]
+async fn refresh_keeps_issuance_and_invalid_grant_near_day_seven_hints_testing_mode() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  server.mock("GET", "/youtube/v3/liveStreams").match_query(mockito::Matcher::Any).with_body(r#"{"items":[]}"#).create_async().await;
+
+  let refreshed = server
+    .mock("POST", "/token")
+    .with_body(r#"{"access_token":"new","expires_in":3600}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  let issued = now() - 3 * DAY;
+  seed(tmp.path(), Some(issued), None);
+  live::list_streams(tmp.path()).await.unwrap();
+  refreshed.assert_async().await;
+  let stored: serde_json::Value = serde_json::from_str(&fs::read_to_string(tmp.path().join("tokens.json")).unwrap()).unwrap();
+  assert_eq!((stored["access_token"].as_str(), stored["issued_at"].as_u64()), (Some("new"), Some(issued)));
+  refreshed.remove_async().await;
+
+  server.mock("POST", "/token").with_status(400).with_body(r#"{"error":"invalid_grant"}"#).create_async().await;
+  let near = now() - 7 * DAY + 2 * 3600;
+  for (issued_at, published, hinted) in [(Some(near), None, true), (Some(near), Some(true), false), (Some(now() - 2 * DAY), None, false), (None, None, false)] {
+    let tmp = tempfile::tempdir().unwrap();
+    seed(tmp.path(), issued_at, published);
+    let err = live::list_streams(tmp.path()).await.map(|_| ()).unwrap_err();
+    let Error::GrantRevoked { hint } = &err else { panic!("expected GrantRevoked, got {:?}", err) };
+    assert_eq!(hint.is_some(), hinted, "{:?} {:?}", issued_at, published);
+    assert_eq!(err.code(), "REAUTH_REQUIRED");
+  }
+}
@@ -0,0 +1,78 @@
+use tauri_youtube_oauth::{error::Error, token_response, TokenReply};
+
+/// What a reply should be read as.
+#[derive(Debug)]
+enum Expect {
+  /// A grant with this `expires_in` and this many warnings.
+  Granted(u64, usize),
+  /// A refusal with this OAuth code and a message containing the text.
+  Refused(Option<&'static str>, &'static str),
+  /// A network error whose message contains the text.
+  Network(&'static str),
+}
+
+const GOOGLE_OK: &str = r#"{"access_token":"ya29.a0","expires_in":3599,"refresh_token":"1//0g","scope":"https://www.googleapis.com/auth/youtube.upload","token_type":"Bearer"}"#;
+const PROXY_PAGE: &str = "<!DOCTYPE html>\n<html><head><title>502 Bad Gateway</title></head><body><h1>Bad Gateway</h1></body></html>";
+const PORTAL_PAGE: &str = "<html><body>Zaloguj się do sieci Wi-Fi hotelu</body></html>";
+
+fn cases() -> Vec<(&'static str, u16, &'static str, Expect)> {
+  use Expect::*;
+  vec![
+    ("google grant", 200, GOOGLE_OK, Granted(3599, 0)),
+    ("refresh without a new refresh token", 200, r#"{"access_token":"a","expires_in":3600,"token_type":"bearer"}"#, Granted(3600, 0)),
+    ("expires_in as a string", 200, r#"{"access_token":"a","expires_in":"3600","token_type":"Bearer"}"#, Granted(3600, 0)),
+    ("expires_in zero", 200, r#"{"access_token":"a","expires_in":0,"token_type":"Bearer"}"#, Granted(0, 1)),
+    ("expires_in negative, no token_type", 200, r#"{"access_token":"a","expires_in":-5}"#, Granted(0, 2)),
+    ("mac token_type", 200, r#"{"access_token":"a","expires_in":60,"token_type":"mac"}"#, Granted(60, 1)),
+    ("error in a 200", 200, r#"{"error":"invalid_request","error_description":"Missing code"}"#, Refused(Some("invalid_request"), "Missing code")),
+    ("revoked grant", 400, r#"{"error":"invalid_grant","error_description":"Token has been expired or revoked."}"#, Refused(Some("invalid_grant"), "revoked")),
+    ("wrong secret", 401, r#"{"error":"invalid_client","error_description":"Unauthorized"}"#, Refused(Some("invalid_client"), "HTTP 401")),
+    ("error_uri", 400, r#"{"error":"unsupported_grant_type","error_uri":"https://example.com/grants"}"#, Refused(Some("unsupported_grant_type"), "https://example.com/grants")),
+    ("google api style error", 400, r#"{"error":{"code":400,"message":"Bad client","status":"INVALID_ARGUMENT"}}"#, Refused(Some("INVALID_ARGUMENT"), "Bad client")),
+    ("4xx without a code", 403, r#"{"message":"nope"}"#, Refused(None, "bez kodu błędu")),
+    ("200 without access_token", 200, r#"{"expires_in":3600,"token_type":"Bearer"}"#, Refused(None, "brak access_token")),
+    ("proxy 502 page", 502, PROXY_PAGE, Network("HTML")),
+    ("empty 503", 503, "", Network("pustą")),
+    ("5xx with an OAuth error", 503, r#"{"error":"temporarily_unavailable"}"#, Network("temporarily_unavailable")),
+    ("captive portal 200", 200, PORTAL_PAGE, Network("proxy lub portalu")),
+    ("plain text 400", 400, "Bad Request", Network("nie jest obiektem JSON")),
+    ("json array", 200, "[]", Network("nie jest obiektem JSON")),
+  ]
+}
+
+#[test]
+fn reads_every_captured_reply_shape() {
+  for (name, status, body, expect) in cases() {
+    let got = token_response::parse(status, body);
+    match (&expect, &got) {
+      (Expect::Granted(expires_in, warnings), Ok(TokenReply::Granted(g))) => {
+        assert_eq!((g.expires_in, g.warnings.len()), (*expires_in, *warnings), "{}: {:?}", name, g.warnings);
+        assert!(!g.access_token.is_empty(), "{}", name);
+      }
+      (Expect::Refused(code, text), Ok(reply @ TokenReply::Refused(e))) => {
+        assert_eq!(reply.error_code(), *code, "{}", name);
+        assert_eq!(e.status, status, "{}", name);
+        let message = Error::TokenEndpoint(Box::new(e.clone())).to_string();
+        assert!(message.contains(text), "{}: {}", name, message);
+      }
+      (Expect::Network(text), Err(e @ Error::Network(message))) => {
+        assert!(message.contains(text), "{}: {}", name, message);
+        assert!(e.retryable(), "{}", name);
+      }
+      _ => panic!("{}: expected {:?}, got {:?}", name, expect, got),
+    }
+  }
+}
+
+#[test]
+fn grant_fields_are_kept() {
+  let Ok(TokenReply::Granted(g)) = token_response::parse(200, GOOGLE_OK) else { panic!("not a grant") };
+  assert_eq!(g.access_token, "ya29.a0");
+  assert_eq!(g.refresh_token.as_deref(), Some("1//0g"));
+  assert_eq!(g.scope.as_deref(), Some("https://www.googleapis.com/auth/youtube.upload"));
+  let Ok(TokenReply::Refused(e)) = token_response::parse(400, r#"{"error":"invalid_scope","error_uri":"https://e.test/s"}"#) else {
+    panic!("not a refusal")
+  };
+  let details = tauri_youtube_oauth::error::CommandError::from(Error::TokenEndpoint(Box::new(e))).details.unwrap();
+  assert_eq!(details, serde_json::json!({ "status": 400, "error": "invalid_scope", "error_uri": "https://e.test/s" }));
+}
@@ -0,0 +1,57 @@
+use mockito::Matcher;
+use std::fs;
+use tauri_youtube_oauth::{live, read_tokens_from_dir};
+
+fn seed_expired(dir: &std::path::Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let tokens = serde_json::json!({
+    "access_token": "old",
+    "refresh_token": "refresh-1",
+    "expires_in": 3600,
+    "created_at": 1,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn rotated_refresh_token_is_persisted() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  server
+    .mock("GET", "/youtube/v3/liveStreams")
+    .match_query(Matcher::Any)
+    .with_body(r#"{"items":[]}"#)
+    .create_async()
+    .await;
+
+  let rotating = server
+    .mock("POST", "/token")
+    .match_body(Matcher::UrlEncoded("refresh_token".into(), "refresh-1".into()))
+    .with_body(r#"{"access_token":"new","expires_in":3600,"refresh_token":"refresh-2"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let tmp = tempfile::tempdir().unwrap();
+  seed_expired(tmp.path());
+  live::list_streams(tmp.path()).await.unwrap();
+  rotating.assert_async().await;
+  let t = read_tokens_from_dir(tmp.path()).unwrap();
+  assert_eq!((t.access_token.as_str(), t.refresh_token.as_str()), ("new", "refresh-2"));
+
+  // Without the field the old refresh token stays.
+  let plain = server
+    .mock("POST", "/token")
+    .match_body(Matcher::UrlEncoded("refresh_token".into(), "refresh-1".into()))
+    .with_body(r#"{"access_token":"newer","expires_in":3600}"#)
+    .create_async()
+    .await;
+  rotating.remove_async().await;
+  let tmp = tempfile::tempdir().unwrap();
+  seed_expired(tmp.path());
+  live::list_streams(tmp.path()).await.unwrap();
+  plain.assert_async().await;
+  let t = read_tokens_from_dir(tmp.path()).unwrap();
+  assert_eq!((t.access_token.as_str(), t.refresh_token.as_str()), ("newer", "refresh-1"));
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use tauri_youtube_oauth::{read_tokens_from_dir, write_tokens_to_dir, Tokens, TOKENS_SCHEMA_JSON};
+
+fn sample() -> Tokens {
+  Tokens {
+    access_token: "ya29.a".into(),
+    refresh_token: "1//r".into(),
+    expires_in: 3600,
+    created_at: 1640995200,
+    scope: "https://www.googleapis.com/auth/youtube".into(),
+    client_id: Some("id".into()),
+    issued_at: Some(1640995000),
+  }
+}
+
+#[test]
+fn tokens_file_has_a_stable_layout() {
+  let tmp = tempfile::tempdir().unwrap();
+  write_tokens_to_dir(tmp.path(), &sample()).unwrap();
+  let written = fs::read_to_string(tmp.path().join("tokens.json")).unwrap();
+  let expected = r#"{
+  "access_token": "ya29.a",
+  "refresh_token": "1//r",
+  "expires_in": 3600,
+  "created_at": 1640995200,
+  "scope": "https://www.googleapis.com/auth/youtube",
+  "client_id": "id",
+  "issued_at": 1640995000,
+  "created_at_rfc3339": "2022-01-01T00:00:00Z",
+  "expires_at_rfc3339": "2022-01-01T01:00:00Z"
+}
+"#;
+  assert_eq!(written, expected);
+
+  // Unknown lifetime: no expiry to show.
+  write_tokens_to_dir(tmp.path(), &Tokens { expires_in: 0, ..sample() }).unwrap();
+  let written = fs::read_to_string(tmp.path().join("tokens.json")).unwrap();
+  assert!(written.contains("\"created_at_rfc3339\"") && !written.contains("expires_at_rfc3339"));
+}
+
+#[test]
+fn derived_fields_are_ignored_on_read() {
+  let tmp = tempfile::tempdir().unwrap();
+  write_tokens_to_dir(tmp.path(), &sample()).unwrap();
+  let path = tmp.path().join("tokens.json");
+  let edited = fs::read_to_string(&path).unwrap().replace("2022-01-01T01:00:00Z", "2030-01-01T00:00:00Z");
+  fs::write(&path, edited).unwrap();
+  let t = read_tokens_from_dir(tmp.path()).unwrap();
+  assert_eq!((t.created_at, t.expires_in), (1640995200, 3600));
+  write_tokens_to_dir(tmp.path(), &t).unwrap();
+  assert!(fs::read_to_string(&path).unwrap().contains("\"expires_at_rfc3339\": \"2022-01-01T01:00:00Z\""));
+}
+
+#[test]
+fn schema_describes_every_written_field() {
+  let schema: serde_json::Value = serde_json::from_str(TOKENS_SCHEMA_JSON).unwrap();
+  let tmp = tempfile::tempdir().unwrap();
+  write_tokens_to_dir(tmp.path(), &sample()).unwrap();
+  let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(tmp.path().join("tokens.json")).unwrap()).unwrap();
+  let keys = |v: &serde_json::Value| v.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+  assert_eq!(keys(&schema["properties"]), keys(&written));
+  // Only the fields without a serde default are required.
+  let minimal: Tokens = serde_json::from_str(r#"{"access_token":"a","refresh_token":"r"}"#).unwrap();
+  assert_eq!(minimal.expires_in, 0);
+  assert_eq!(schema["required"], serde_json::json!(["access_token", "refresh_token"]));
+}
@@ -0,0 +1,146 @@
+use mockito::Matcher;
+use std::{fs, path::Path, time::Duration};
+use tauri_youtube_oauth::{
+  history, metrics,
+  upload::{self, ChunkSizer, ADAPTIVE_MAX, ADAPTIVE_START, CHUNK_GRANULARITY},
+  ForceCode, Privacy, VideoMetadata,
+};
+
+const MIB: u64 = 1024 * 1024;
+
+#[test]
+fn adaptive_sizer_grows_caps_and_halves() {
+  let mut s = ChunkSizer::adaptive();
+  assert_eq!(s.size(), ADAPTIVE_START);
+  s.on_success(Duration::from_secs(1));
+  assert_eq!(s.size(), 16 * MIB);
+  s.on_success(Duration::from_secs(60));
+  assert_eq!(s.size(), 16 * MIB, "slow chunk must not grow");
+  for _ in 0..10 {
+    s.on_success(Duration::from_secs(1));
+  }
+  assert_eq!(s.size(), ADAPTIVE_MAX);
+  for _ in 0..20 {
+    s.on_failure();
+  }
+  assert_eq!(s.size(), CHUNK_GRANULARITY);
+
+  let mut fixed = ChunkSizer::fixed(4 * MIB);
+  fixed.on_success(Duration::ZERO);
+  fixed.on_failure();
+  assert_eq!(fixed.size(), 4 * MIB);
+}
+
+#[test]
+fn chunk_size_must_be_multiple_of_256_kib() {
+  assert!(upload::validate_chunk_size(CHUNK_GRANULARITY * 3).is_ok());
+  assert!(upload::validate_chunk_size(0).is_err());
+  assert!(upload::validate_chunk_size(1_000_000).is_err());
+}
+
+fn seed(dir: &Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube.upload",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+fn meta() -> VideoMetadata {
+  VideoMetadata {
+    title: "Test".into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  }
+}
+
+async fn open_session(server: &mut mockito::ServerGuard, total: u64, session: &str) -> mockito::Mock {
+  let location = format!("{}/upload/session/{}", server.url(), session);
+  server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .match_header("x-upload-content-length", total.to_string().as_str())
+    .with_header("location", &location)
+    .expect(1)
+    .create_async()
+    .await
+}
+
+async fn chunk(server: &mut mockito::ServerGuard, session: &str, range: &str, status: usize, confirmed: Option<u64>) -> mockito::Mock {
+  let mut m = server
+    .mock("PUT", format!("/upload/session/{}", session).as_str())
+    .match_header("content-range", range)
+    .with_status(status);
+  if let Some(end) = confirmed {
+    m = m.with_header("range", &format!("bytes=0-{}", end - 1));
+  }
+  m.expect(1).create_async().await
+}
+
+#[tokio::test]
+async fn content_range_follows_changing_chunk_sizes() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+
+  // Fast chunks: 8 MiB, then 16 MiB, then the 1 MiB remainder.
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path());
+  let total = 25 * MIB;
+  let file = tmp.path().join("render.mp4");
+  fs::write(&file, vec![7u8; total as usize]).unwrap();
+  let mut mocks = vec![open_session(&mut server, total, "grow").await];
+  mocks.push(chunk(&mut server, "grow", &format!("bytes 0-{}/{}", 8 * MIB - 1, total), 308, Some(8 * MIB)).await);
+  mocks.push(chunk(&mut server, "grow", &format!("bytes {}-{}/{}", 8 * MIB, 24 * MIB - 1, total), 308, Some(24 * MIB)).await);
+  let last = server
+    .mock("PUT", "/upload/session/grow")
+    .match_header("content-range", format!("bytes {}-{}/{}", 24 * MIB, total - 1, total).as_str())
+    .with_status(200)
+    .with_body(r#"{"id":"vid1","kind":"youtube#video"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let video = upload::upload_video(tmp.path(), &file, &meta(), &[ForceCode::RecentlyModified]).await.unwrap();
+  assert_eq!((video.video_id.as_str(), video.bytes), ("vid1", total));
+  for m in mocks.iter().chain([&last]) {
+    m.assert_async().await;
+  }
+  assert_eq!(metrics::snapshot(tmp.path(), false).upload.chunk_bytes, Some(32 * MIB));
+  assert!(!fs::read_to_string(tmp.path().join("upload_sessions.json")).unwrap().contains("render.mp4"));
+  assert_eq!(history::read_all(tmp.path())[0].video_id.as_deref(), Some("vid1"));
+
+  // A failed chunk halves the size and the upload continues from what the
+  // server confirmed, then grows again.
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path());
+  let total = 12 * MIB;
+  let file = tmp.path().join("flaky.mp4");
+  fs::write(&file, vec![1u8; total as usize]).unwrap();
+  let mut mocks = vec![open_session(&mut server, total, "flaky").await];
+  mocks.push(chunk(&mut server, "flaky", &format!("bytes 0-{}/{}", 8 * MIB - 1, total), 503, None).await);
+  mocks.push(chunk(&mut server, "flaky", &format!("bytes */{}", total), 308, Some(4 * MIB)).await);
+  mocks.push(chunk(&mut server, "flaky", &format!("bytes {}-{}/{}", 4 * MIB, 8 * MIB - 1, total), 308, Some(8 * MIB)).await);
+  let last = server
+    .mock("PUT", "/upload/session/flaky")
+    .match_header("content-range", format!("bytes {}-{}/{}", 8 * MIB, total - 1, total).as_str())
+    .with_status(201)
+    .with_body(r#"{"id":"vid2"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let video = upload::upload_video(tmp.path(), &file, &meta(), &[ForceCode::RecentlyModified]).await.unwrap();
+  assert_eq!(video.video_id, "vid2");
+  for m in mocks.iter().chain([&last]) {
+    m.assert_async().await;
+  }
+}
@@ -0,0 +1,139 @@
+use mockito::Matcher;
+use std::{fs, io::Write, path::Path};
+use tauri_youtube_oauth::{error::Error, hashing, history, snapshots, upload, ForceCode, Privacy, SnapshotReason, VideoMetadata};
+
+const CHUNK: u64 = 256 * 1024;
+
+fn seed(dir: &Path, extra: &str) {
+  let cfg = format!(r#"{{"client_id":"id","client_secret":"secret","upload_chunk_bytes":{}{}}}"#, CHUNK, extra);
+  fs::write(dir.join("oauth_config.json"), cfg).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+fn meta() -> VideoMetadata {
+  VideoMetadata {
+    title: "Render".into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  }
+}
+
+/// Two-chunk session whose first chunk appends to the file, like a renderer
+/// still writing it.
+async fn growing_upload(server: &mut mockito::ServerGuard, file: &Path, session: &str, video_id: &str) -> Vec<mockito::Mock> {
+  let total = 2 * CHUNK;
+  let location = format!("{}/upload/session/{}", server.url(), session);
+  let open = server.mock("POST", "/upload/youtube/v3/videos").match_query(Matcher::Any).with_header("location", &location).create_async().await;
+  let path = file.to_path_buf();
+  let first = server
+    .mock("PUT", format!("/upload/session/{}", session).as_str())
+    .match_header("content-range", format!("bytes 0-{}/{}", CHUNK - 1, total).as_str())
+    .with_status(308)
+    .with_header("range", &format!("bytes=0-{}", CHUNK - 1))
+    .with_body_from_request(move |_| {
+      fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"late frame").unwrap();
+      Vec::new()
+    })
+    .expect(1)
+    .create_async()
+    .await;
+  let last = server
+    .mock("PUT", format!("/upload/session/{}", session).as_str())
+    .match_header("content-range", format!("bytes {}-{}/{}", CHUNK, total - 1, total).as_str())
+    .with_status(200)
+    .with_body(format!(r#"{{"id":"{}"}}"#, video_id))
+    .expect(1)
+    .create_async()
+    .await;
+  vec![open, first, last]
+}
+
+#[tokio::test]
+async fn changed_file_marks_upload_suspect_and_strict_mode_deletes_it() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+
+  // A file written just now is refused unless forced, before any request.
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path(), r#","upload_min_file_age_secs":3600"#);
+  let file = tmp.path().join("render.mp4");
+  fs::write(&file, vec![3u8; 2 * CHUNK as usize]).unwrap();
+  let err = upload::upload_video(tmp.path(), &file, &meta(), &[]).await.unwrap_err();
+  assert!(matches!(err, Error::Validation(_)), "{err:?}");
+
+  let expected_hash = hashing::hash(tmp.path(), &file).unwrap().hash;
+  let mocks = growing_upload(&mut server, &file, "grows", "vid1").await;
+  let video = upload::upload_video(tmp.path(), &file, &meta(), &[ForceCode::RecentlyModified]).await.unwrap();
+  for m in &mocks {
+    m.assert_async().await;
+  }
+  assert_eq!(video.video_id, "vid1");
+  assert_eq!(video.file_hash, expected_hash);
+  assert!(video.suspect.is_some());
+  let rec = history::read_all(tmp.path()).pop().unwrap();
+  assert!(rec.suspect);
+  assert_eq!(rec.file_hash, expected_hash);
+
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path(), r#","strict_integrity":true"#);
+  let file = tmp.path().join("render.mp4");
+  fs::write(&file, vec![4u8; 2 * CHUNK as usize]).unwrap();
+  let mocks = growing_upload(&mut server, &file, "strict", "vid2").await;
+  let snapshot = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("id".into(), "vid2".into()))
+    .with_body(r#"{"items":[{"id":"vid2","snippet":{"title":"Render"},"status":{"privacyStatus":"private"}}]}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let delete = server
+    .mock("DELETE", "/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("id".into(), "vid2".into()))
+    .with_status(204)
+    .expect(1)
+    .create_async()
+    .await;
+  let err = upload::upload_video(tmp.path(), &file, &meta(), &[ForceCode::RecentlyModified]).await.unwrap_err();
+  assert!(err.to_string().contains("vid2"), "{err}");
+  for m in mocks.iter().chain([&snapshot, &delete]) {
+    m.assert_async().await;
+  }
+  // What was deleted is kept.
+  let kept = snapshots::list(tmp.path(), "vid2").unwrap();
+  assert_eq!((kept.len(), kept[0].reason, kept[0].title.as_deref()), (1, SnapshotReason::Delete, Some("Render")));
+  assert_eq!(history::read_all(tmp.path()).pop().unwrap().status, history::UploadStatus::Failed);
+
+  // A finished session answering without the video's id is no upload.
+  drop(mocks);
+  let tmp = tempfile::tempdir().unwrap();
+  seed(tmp.path(), "");
+  let file = tmp.path().join("render.mp4");
+  fs::write(&file, vec![5u8; CHUNK as usize]).unwrap();
+  let location = format!("{}/upload/session/noid", server.url());
+  let open =
+    server.mock("POST", "/upload/youtube/v3/videos").match_query(Matcher::Any).with_header("location", &location).create_async().await;
+  let done =
+    server.mock("PUT", "/upload/session/noid").with_status(201).with_body(r#"{"kind":"youtube#video"}"#).expect(1).create_async().await;
+  match upload::upload_video(tmp.path(), &file, &meta(), &[ForceCode::RecentlyModified]).await {
+    Err(Error::Api { status: 201, reason, .. }) => assert_eq!(reason, "invalidResponse"),
+    other => panic!("{:?}", other),
+  }
+  open.assert_async().await;
+  done.assert_async().await;
+  let rec = history::read_all(tmp.path()).pop().unwrap();
+  assert_eq!((rec.status, rec.video_id), (history::UploadStatus::Failed, None));
+}
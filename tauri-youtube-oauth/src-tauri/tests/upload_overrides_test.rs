@@ -0,0 +1,124 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::fs;
+use tauri_youtube_oauth::{
+  audit,
+  error::Error,
+  history, overrides,
+  queue::{self, EntryStatus},
+  upload, ForceCode, LintRule, Privacy, VideoMetadata,
+};
+
+fn meta(title: &str) -> VideoMetadata {
+  VideoMetadata {
+    title: title.into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  }
+}
+
+#[test]
+fn unknown_codes_are_refused() {
+  let codes = |c: &[&str]| c.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+  assert_eq!(
+    overrides::parse(&codes(&["recentlyModified", "missingDescription"])).unwrap(),
+    [ForceCode::RecentlyModified, ForceCode::Lint(LintRule::MissingDescription)]
+  );
+  match overrides::parse(&codes(&["missingDescription", "everything"])) {
+    Err(Error::Validation(message)) => assert!(message.contains("'everything'"), "{}", message),
+    other => panic!("{:?}", other),
+  }
+
+  let tmp = tempfile::tempdir().unwrap();
+  assert!(queue::add_forced(tmp.path(), "/renders/a.mp4", Some(meta("A")), &codes(&["true"])).is_err());
+  assert!(queue::list(tmp.path()).is_empty());
+}
+
+#[tokio::test]
+async fn only_the_listed_warnings_are_forced() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "metadata_lint": { "mode": "block" } });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let video = dir.join("film.mp4");
+  fs::write(&video, b"not really a video").unwrap();
+
+  // Just written: the file age check wants its own code.
+  let lint_only = [ForceCode::Lint(LintRule::MissingDescription)];
+  match upload::upload_video(dir, &video, &meta("Film"), &lint_only).await {
+    Err(Error::Validation(message)) => assert!(message.contains("force: recentlyModified"), "{}", message),
+    other => panic!("{:?}", other),
+  }
+  // The shouting title isn't covered by the description's code.
+  let force = [ForceCode::RecentlyModified, ForceCode::Lint(LintRule::MissingDescription)];
+  match upload::upload_video(dir, &video, &meta("THE BEST FILM EVER MADE"), &force).await {
+    Err(Error::Validation(message)) => {
+      assert!(message.contains("force: excessiveCaps") && !message.contains("Brak opisu"), "{}", message)
+    }
+    other => panic!("{:?}", other),
+  }
+  assert!(audit::entries(dir).is_empty(), "nothing was forced through");
+}
+
+#[tokio::test]
+async fn a_forced_upload_is_recorded_on_the_entry_in_the_audit_log_and_in_history() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let cfg = json!({ "client_id": "id", "client_secret": "secret", "upload_min_file_age_secs": 0, "metadata_lint": { "mode": "block" } });
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube.upload" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let add = |name: &str, force: &[&str]| {
+    let file = dir.join(name);
+    fs::write(&file, b"not really a video").unwrap();
+    let force: Vec<String> = force.iter().map(|c| c.to_string()).collect();
+    queue::add_forced(dir, &file.to_string_lossy(), Some(meta(name)), &force).unwrap()
+  };
+  let forced = add("forced.mp4", &["missingDescription", "excessiveCaps"]);
+  let held = add("held.mp4", &[]);
+  assert_eq!(forced.force, ["missingDescription", "excessiveCaps"]);
+
+  let session = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::UrlEncoded("uploadType".into(), "resumable".into()))
+    .with_header("location", &format!("{}/upload/session/o", server.url()))
+    .expect(1)
+    .create_async()
+    .await;
+  let media = server.mock("PUT", "/upload/session/o").with_status(201).with_body(r#"{"id":"vid-forced"}"#).expect(1).create_async().await;
+  assert_eq!(queue::process_due(dir).await.unwrap(), 2);
+  session.assert_async().await;
+  media.assert_async().await;
+
+  // Only the warning that was there counts as overridden.
+  let entries = queue::list(dir);
+  let entry = entries.iter().find(|e| e.id == forced.id).unwrap();
+  assert_eq!((entry.status, entry.video_id.as_deref()), (EntryStatus::Done, Some("vid-forced")));
+  assert_eq!(entry.overrides.len(), 1);
+  assert_eq!(entry.overrides[0].codes, ["missingDescription"]);
+  let entry = entries.iter().find(|e| e.id == held.id).unwrap();
+  assert_eq!(entry.status, EntryStatus::Failed);
+  assert!(entry.overrides.is_empty() && entry.error.as_deref().unwrap_or_default().contains("force: missingDescription"));
+
+  let audit = audit::entries(dir);
+  assert_eq!(audit.len(), 1);
+  assert_eq!(audit[0].action, "upload_forced");
+  assert_eq!(audit[0].details["codes"], json!(["missingDescription"]));
+  assert!(audit[0].details["file_path"].as_str().unwrap().ends_with("forced.mp4"));
+
+  let overridden = history::overridden(dir, None).unwrap();
+  assert_eq!(overridden.len(), 1);
+  assert_eq!(overridden[0].video_id.as_deref(), Some("vid-forced"));
+  assert_eq!(overridden[0].forced.as_ref().unwrap(), &entries[0].overrides[0]);
+  assert!(history::overridden(dir, Some("2999-01-01")).unwrap().is_empty());
+  assert!(history::overridden(dir, Some("yesterday")).is_err());
+}
@@ -0,0 +1,89 @@
+use std::{fs, io::Write, path::Path, time::Duration};
+use tauri_youtube_oauth::{error::Error, preflight, queue, EntryStatus, PreflightReason};
+
+fn reason(result: Result<u64, Error>) -> PreflightReason {
+  match result {
+    Err(Error::PreflightFailed { reason, .. }) => reason,
+    other => panic!("expected a pre-flight failure, got {:?}", other),
+  }
+}
+
+/// A minimal MP4: `ftyp`, then `moov` holding a version 0 `mvhd`.
+fn mp4(timescale: u32, duration: u32) -> Vec<u8> {
+  let mut mvhd = Vec::new();
+  mvhd.extend(108u32.to_be_bytes());
+  mvhd.extend(b"mvhd");
+  mvhd.extend([0; 4]); // version + flags
+  mvhd.extend([0; 8]); // creation + modification time
+  mvhd.extend(timescale.to_be_bytes());
+  mvhd.extend(duration.to_be_bytes());
+  mvhd.resize(108, 0);
+  let mut file = Vec::new();
+  file.extend(16u32.to_be_bytes());
+  file.extend(b"ftypisom\0\0\0\0");
+  file.extend((8 + mvhd.len() as u32).to_be_bytes());
+  file.extend(b"moov");
+  file.extend(mvhd);
+  file
+}
+
+#[tokio::test]
+async fn checks_each_file_before_upload() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+
+  assert_eq!(reason(preflight::check(dir, &dir.join("gone.mp4")).await), PreflightReason::FileMissing);
+  assert_eq!(reason(preflight::check(dir, dir).await), PreflightReason::FileMissing);
+  fs::write(dir.join("empty.mp4"), b"").unwrap();
+  assert_eq!(reason(preflight::check(dir, &dir.join("empty.mp4")).await), PreflightReason::Empty);
+
+  let long = dir.join("long.mp4");
+  fs::write(&long, mp4(1000, 13 * 3600 * 1000)).unwrap();
+  assert_eq!(preflight::mp4_duration_secs(&long), Some(13 * 3600));
+  assert_eq!(reason(preflight::check(dir, &long).await), PreflightReason::TooLong);
+
+  let short = dir.join("short.mp4");
+  fs::write(&short, mp4(600, 90 * 600)).unwrap();
+  assert_eq!(preflight::mp4_duration_secs(&short), Some(90));
+  assert_eq!(preflight::check(dir, &short).await.unwrap(), fs::metadata(&short).unwrap().len());
+
+  let growing = dir.join("growing.mp4");
+  fs::write(&growing, b"first frames").unwrap();
+  let writer = {
+    let growing = growing.clone();
+    std::thread::spawn(move || {
+      std::thread::sleep(Duration::from_millis(300));
+      fs::OpenOptions::new().append(true).open(growing).unwrap().write_all(b" more frames").unwrap();
+    })
+  };
+  assert_eq!(reason(preflight::check(dir, &growing).await), PreflightReason::StillGrowing);
+  writer.join().unwrap();
+}
+
+fn status(dir: &Path, id: &str) -> EntryStatus {
+  queue::list(dir).into_iter().find(|e| e.id == id).unwrap().status
+}
+
+#[tokio::test]
+async fn failing_entries_are_blocked_until_revalidated() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let share = dir.join("share");
+  let entry = queue::add(dir, &share.join("ep12.mp4").to_string_lossy(), None).unwrap();
+
+  // The share is not mounted: nothing reaches the API, the entry waits.
+  assert_eq!(queue::process_due(dir).await.unwrap(), 1);
+  let blocked = queue::list(dir).remove(0);
+  assert_eq!(blocked.status, EntryStatus::Blocked);
+  assert!(blocked.error.unwrap().contains("ep12.mp4"));
+  assert_eq!(queue::process_due(dir).await.unwrap(), 0, "blocked entries are not retried on their own");
+  assert!(queue::revalidate_blocked(dir).await.unwrap().is_empty());
+  assert_eq!(status(dir, &entry.id), EntryStatus::Blocked);
+
+  fs::create_dir(&share).unwrap();
+  fs::write(share.join("ep12.mp4"), b"frames").unwrap();
+  let unblocked = queue::revalidate_blocked(dir).await.unwrap();
+  assert_eq!(unblocked.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), [entry.id.as_str()]);
+  assert_eq!(status(dir, &entry.id), EntryStatus::Pending);
+  assert!(queue::list(dir)[0].error.is_none());
+}
@@ -0,0 +1,220 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use mockito::Matcher;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{
+  queue,
+  schedule::{self, ScheduleOptions},
+  EntryPriority, EntryStatus, Privacy, QueueEntry, VideoMetadata,
+};
+
+fn entry(id: &str, status: EntryStatus, uploaded_at: Option<u64>) -> QueueEntry {
+  QueueEntry {
+    id: id.into(),
+    file_path: format!("/renders/{}.mp4", id),
+    metadata: None,
+    status,
+    priority: EntryPriority::Normal,
+    added_at: 0,
+    scheduled_at: None,
+    uploaded_at,
+    video_id: None,
+    error: None,
+    attempts: 0,
+    attempt_history: Vec::new(),
+    force: Vec::new(),
+    overrides: Vec::new(),
+    enrichment: None,
+  }
+}
+
+fn options() -> ScheduleOptions {
+  ScheduleOptions {
+    max_per_day: 2,
+    window_start_hour: 10,
+    window_end_hour: 18,
+    timezone: "Europe/Warsaw".into(),
+    start_date: NaiveDate::from_ymd_opt(2099, 6, 1),
+    schedule_publishing: false,
+  }
+}
+
+#[test]
+fn spreads_entries_over_days_inside_the_window() {
+  let entries: Vec<QueueEntry> = ["a", "b", "c", "d", "e"].iter().map(|id| entry(id, EntryStatus::Pending, None)).collect();
+  let plan = schedule::plan_upload_schedule(&entries, &options(), Utc::now()).unwrap();
+  let starts: Vec<&str> = plan.iter().map(|p| p.start_local.as_str()).collect();
+  assert_eq!(
+    starts,
+    [
+      "2099-06-01T10:00:00+02:00",
+      "2099-06-01T14:00:00+02:00",
+      "2099-06-02T10:00:00+02:00",
+      "2099-06-02T14:00:00+02:00",
+      "2099-06-03T10:00:00+02:00",
+    ]
+  );
+  assert_eq!(plan[0].start_at, Utc.with_ymd_and_hms(2099, 6, 1, 8, 0, 0).unwrap().timestamp() as u64);
+}
+
+#[test]
+fn uploaded_entries_and_past_slots_use_up_the_day() {
+  let done_at = Utc.with_ymd_and_hms(2099, 6, 1, 8, 5, 0).unwrap();
+  let entries = vec![
+    entry("done", EntryStatus::Done, Some(done_at.timestamp() as u64)),
+    entry("failed", EntryStatus::Failed, None),
+    entry("a", EntryStatus::Pending, None),
+    entry("b", EntryStatus::Pending, None),
+  ];
+  // Starting half-way through the first slot: only the 14:00 slot is left.
+  let plan = schedule::plan_upload_schedule(&entries, &options(), done_at).unwrap();
+  assert_eq!(plan.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+  assert_eq!(plan[0].start_local, "2099-06-01T14:00:00+02:00");
+  assert_eq!(plan[1].start_local, "2099-06-02T10:00:00+02:00");
+
+  // The first day's slots are both behind `not_before`: nothing lands there.
+  let late = Utc.with_ymd_and_hms(2099, 6, 1, 13, 0, 0).unwrap();
+  let plan = schedule::plan_upload_schedule(&entries, &options(), late).unwrap();
+  assert_eq!(plan[0].start_local, "2099-06-02T10:00:00+02:00");
+}
+
+#[test]
+fn priority_comes_before_queue_order() {
+  let with = |id: &str, status, priority| QueueEntry { priority, ..entry(id, status, None) };
+  let entries = vec![
+    with("low", EntryStatus::Pending, EntryPriority::Low),
+    with("a", EntryStatus::Pending, EntryPriority::Normal),
+    with("stuck", EntryStatus::Blocked, EntryPriority::High),
+    with("b", EntryStatus::Pending, EntryPriority::Normal),
+    with("urgent", EntryStatus::Pending, EntryPriority::High),
+  ];
+  let plan = schedule::plan_upload_schedule(&entries, &options(), Utc::now()).unwrap();
+  // The blocked entry gets no slot and holds nothing up.
+  assert_eq!(plan.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), ["urgent", "a", "b", "low"]);
+  assert_eq!(plan[0].start_local, "2099-06-01T10:00:00+02:00");
+}
+
+#[test]
+fn entries_are_reprioritized_and_moved_in_place() {
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let uploading = entry("busy", EntryStatus::Uploading, None);
+  let queued: Vec<QueueEntry> = ["a", "b", "c"].iter().map(|id| entry(id, EntryStatus::Pending, None)).collect();
+  let file = serde_json::json!({ "entries": [&uploading, &queued[0], &queued[1], &queued[2]] });
+  fs::write(dir.join("upload_queue.json"), file.to_string()).unwrap();
+  let ids = |entries: &[QueueEntry]| entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+
+  assert_eq!(queue::set_priority(dir, "c", EntryPriority::High).unwrap().priority, EntryPriority::High);
+  assert_eq!(ids(&queue::move_entry(dir, "a", 3).unwrap()), ["busy", "b", "c", "a"]);
+  assert_eq!(ids(&queue::move_entry(dir, "a", 0).unwrap()), ["a", "busy", "b", "c"]);
+  assert!(queue::move_entry(dir, "busy", 3).is_err(), "the upload in progress stays put");
+  assert!(queue::move_entry(dir, "a", 4).is_err());
+  assert!(queue::set_priority(dir, "nope", EntryPriority::Low).is_err());
+
+  let entries = queue::list(dir);
+  assert_eq!(ids(&entries), ["a", "busy", "b", "c"]);
+  assert_eq!(entries[1].status, EntryStatus::Uploading);
+  assert_eq!(entries[3].priority, EntryPriority::High);
+  let stored = fs::read_to_string(dir.join("upload_queue.json")).unwrap();
+  assert!(stored.contains("\"priority\": \"high\""));
+}
+
+#[test]
+fn rejects_invalid_options() {
+  for bad in [
+    ScheduleOptions { max_per_day: 0, ..options() },
+    ScheduleOptions { window_start_hour: 18, window_end_hour: 10, ..options() },
+    ScheduleOptions { window_end_hour: 25, ..options() },
+    ScheduleOptions { timezone: "Mars/Olympus".into(), ..options() },
+  ] {
+    assert!(schedule::validate(&bad).is_err(), "{:?}", bad);
+  }
+}
+
+fn meta(title: &str) -> VideoMetadata {
+  VideoMetadata {
+    title: title.into(),
+    description: String::new(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: None,
+    thumbnail: None,
+    first_comment: None,
+  }
+}
+
+fn seed(dir: &Path) {
+  let cfg = r#"{"client_id":"id","client_secret":"secret","upload_min_file_age_secs":0}"#;
+  fs::write(dir.join("oauth_config.json"), cfg).unwrap();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({
+    "access_token": "a",
+    "refresh_token": "r",
+    "expires_in": 3600,
+    "created_at": now,
+    "scope": "https://www.googleapis.com/auth/youtube",
+  });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn upload_limit_pushes_the_rest_of_the_queue_to_the_next_day() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  seed(dir);
+  for name in ["a", "b", "c"] {
+    fs::write(dir.join(format!("{}.mp4", name)), b"frames").unwrap();
+  }
+  let location = format!("{}/upload/session/a", server.url());
+  let open_a = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .match_body(Matcher::PartialJson(serde_json::json!({ "snippet": { "title": "A" } })))
+    .with_header("location", &location)
+    .expect(1)
+    .create_async()
+    .await;
+  let put_a = server.mock("PUT", "/upload/session/a").with_status(200).with_body(r#"{"id":"vidA"}"#).expect(1).create_async().await;
+  let limit = server
+    .mock("POST", "/upload/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .match_body(Matcher::PartialJson(serde_json::json!({ "snippet": { "title": "B" } })))
+    .with_status(400)
+    .with_body(r#"{"error":{"message":"limit","errors":[{"reason":"uploadLimitExceeded"}]}}"#)
+    .expect(1)
+    .create_async()
+    .await;
+
+  let a = queue::add(dir, &dir.join("a.mp4").to_string_lossy(), Some(meta("A"))).unwrap();
+  let b = queue::add(dir, &dir.join("b.mp4").to_string_lossy(), Some(meta("B"))).unwrap();
+  let c = queue::add(dir, &dir.join("c.mp4").to_string_lossy(), Some(meta("C"))).unwrap();
+  assert!(queue::plan(dir).unwrap().entries.is_empty(), "unscheduled entries run right away");
+
+  assert_eq!(queue::process_due(dir).await.unwrap(), 2);
+  open_a.assert_async().await;
+  put_a.assert_async().await;
+  limit.assert_async().await;
+
+  let entries = queue::list(dir);
+  assert_eq!(entries[0].status, EntryStatus::Done);
+  assert_eq!(entries[0].video_id.as_deref(), Some("vidA"));
+  assert!(entries[1..].iter().all(|e| e.status == EntryStatus::Pending));
+  let plan = queue::plan(dir).unwrap();
+  let tomorrow = schedule::next_day_start(&chrono_tz::UTC, Utc::now().date_naive()).timestamp() as u64;
+  assert_eq!(plan.limit_hit_on, Some(Utc::now().date_naive()));
+  assert_eq!(plan.entries.iter().map(|p| (p.id.clone(), p.start_at)).collect::<Vec<_>>(), [(b.id, tomorrow), (c.id.clone(), tomorrow)]);
+  assert_eq!(queue::process_due(dir).await.unwrap(), 0, "nothing is due before tomorrow");
+
+  // The plan is kept with the queue and follows edits.
+  let planned = queue::set_schedule(dir, Some(ScheduleOptions { start_date: None, timezone: "UTC".into(), ..options() })).unwrap();
+  assert!(planned.entries.iter().all(|p| p.start_at >= tomorrow + 10 * 3600));
+  queue::remove(dir, &c.id).unwrap();
+  assert_eq!(queue::plan(dir).unwrap().entries.len(), 1);
+  assert!(queue::remove(dir, &a.id).is_ok());
+  let stored: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("upload_queue.json")).unwrap()).unwrap();
+  assert_eq!(stored["schedule"]["max_per_day"], 2);
+  assert!(stored["entries"][0]["scheduled_at"].as_u64().unwrap() >= tomorrow);
+}
@@ -0,0 +1,153 @@
+use mockito::Matcher;
+use serde_json::json;
+use std::{fs, path::Path};
+use tauri_youtube_oauth::{
+  bulk, error::Error, upload, video_links, ForceCode, LinkAction, LinkStatus, PlanOptions, Privacy, RowStatus, VideoMetadata,
+  DEFAULT_PLACEHOLDER,
+};
+
+const DESCRIPTION: &str = "Poprzedni odcinek:\nhttps://youtu.be/privDDDDDDD\n\
+  Zobacz też https://www.youtube.com/watch?v=pubAAAAAAAA&t=30s, https://youtube.com/shorts/lateCCCCCCC i https://youtu.be/soonBBBBBBB.\n\
+  https://youtu.be/goneEEEEEEE?si=x\n\
+  Kanał: youtu.be/pubAAAAAAAA\n";
+
+fn config(dir: &Path, extra: serde_json::Value) {
+  let mut cfg = json!({ "client_id": "id", "client_secret": "secret" });
+  cfg.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+  fs::write(dir.join("oauth_config.json"), cfg.to_string()).unwrap();
+}
+
+fn status(id: &str, privacy: &str, publish_at: Option<&str>) -> serde_json::Value {
+  json!({ "id": id, "status": { "privacyStatus": privacy, "publishAt": publish_at, "uploadStatus": "processed" } })
+}
+
+#[tokio::test]
+async fn links_to_videos_viewers_cannot_open_are_warned_stripped_or_replaced() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let items = [
+    status("pubAAAAAAAA", "public", None),
+    status("soonBBBBBBB", "private", Some("2030-01-01T00:00:00Z")),
+    status("lateCCCCCCC", "private", Some("2032-01-01T00:00:00Z")),
+    status("privDDDDDDD", "private", None),
+  ];
+  // Each id once, whatever the number of times it's linked.
+  let lookups = server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("part".into(), "status".into()),
+      Matcher::UrlEncoded("id".into(), "goneEEEEEEE,lateCCCCCCC,privDDDDDDD,pubAAAAAAAA,soonBBBBBBB".into()),
+    ]))
+    .with_body(json!({ "items": items }).to_string())
+    .expect(4)
+    .create_async()
+    .await;
+
+  // Warn (the default): nothing changes, every link is reported.
+  config(dir, json!({}));
+  let report = video_links::resolve_video_links(dir, DESCRIPTION).await.unwrap();
+  assert_eq!(report.description, DESCRIPTION);
+  let found: Vec<(&str, LinkStatus, bool, LinkAction)> =
+    report.links.iter().map(|l| (l.video_id.as_str(), l.status, l.viewable, l.action)).collect();
+  assert_eq!(
+    found,
+    [
+      ("privDDDDDDD", LinkStatus::Private, false, LinkAction::Warned),
+      ("pubAAAAAAAA", LinkStatus::Public, true, LinkAction::Kept),
+      ("lateCCCCCCC", LinkStatus::Scheduled, false, LinkAction::Warned),
+      // Still scheduled now; only a video going public after it may link to it.
+      ("soonBBBBBBB", LinkStatus::Scheduled, false, LinkAction::Warned),
+      ("goneEEEEEEE", LinkStatus::Missing, false, LinkAction::Warned),
+      ("pubAAAAAAAA", LinkStatus::Public, true, LinkAction::Kept),
+    ]
+  );
+  assert_eq!(report.links[1].url, "https://www.youtube.com/watch?v=pubAAAAAAAA&t=30s");
+  assert_eq!(report.links[4].url, "https://youtu.be/goneEEEEEEE?si=x");
+  assert_eq!(report.links[2].publish_at.as_deref(), Some("2032-01-01T00:00:00Z"));
+
+  let video = dir.join("ep2.mp4");
+  fs::write(&video, b"not really a video").unwrap();
+  let meta = VideoMetadata {
+    title: "Odcinek 2".into(),
+    description: DESCRIPTION.into(),
+    tags: Vec::new(),
+    category_id: None,
+    privacy: Privacy::Private,
+    publish_at: Some("2031-01-01T00:00:00Z".into()),
+    thumbnail: None,
+    first_comment: None,
+  };
+  // Warnings go through the metadata lint, so its block mode stops the upload.
+  let lint = json!({ "mode": "block", "disabled": ["tooManyLinks"] });
+  config(dir, json!({ "dry_run": true, "upload_min_file_age_secs": 0, "video_links": { "mode": "warn" }, "metadata_lint": lint }));
+  match upload::upload_video(dir, &video, &meta, &[]).await {
+    Err(Error::Validation(message)) => {
+      assert!(message.contains("https://youtu.be/privDDDDDDD nie zadziała") && message.contains("2032-01-01T00:00:00Z"), "{}", message);
+      assert!(!message.contains("soonBBBBBBB"), "goes public before this one: {}", message);
+    }
+    other => panic!("expected the lint to block, got {:?}", other),
+  }
+
+  // Strip: gone from what would be sent, with the lines they were alone on.
+  config(dir, json!({ "dry_run": true, "video_links": { "mode": "strip" } }));
+  match upload::upload_video(dir, &video, &meta, &[ForceCode::RecentlyModified]).await {
+    Err(Error::DryRun(plan)) => {
+      let body = &plan.body_summary;
+      assert!(!body.contains("privDDDDDDD") && !body.contains("lateCCCCCCC") && !body.contains("goneEEEEEEE"), "{}", body);
+      assert!(body.contains(r"Poprzedni odcinek:\nZobacz też https://www.youtube.com/watch?v=pubAAAAAAAA&t=30s,  i https://youtu.be/soonBBBBBBB.\nKanał"), "{}", body);
+      let actions: Vec<LinkAction> = plan.video_links.iter().map(|l| l.action).collect();
+      use LinkAction::*;
+      assert_eq!(actions, [Stripped, Kept, Stripped, Kept, Stripped, Kept]);
+    }
+    other => panic!("expected dry-run plan, got {:?}", other),
+  }
+
+  // Placeholder, in a metadata plan: the lookups are shared by its rows.
+  config(dir, json!({ "video_links": { "mode": "placeholder" } }));
+  let resource = |id: &str| {
+    json!({ "id": id, "snippet": { "title": "T", "description": "", "categoryId": "28" }, "status": { "privacyStatus": "public" } })
+  };
+  server
+    .mock("GET", "/youtube/v3/videos")
+    .match_query(Matcher::AllOf(vec![
+      Matcher::UrlEncoded("part".into(), "status".into()),
+      Matcher::UrlEncoded("id".into(), "ep1AAAAAAAA,ep3AAAAAAAA".into()),
+    ]))
+    .with_body(json!({ "items": [resource("ep1AAAAAAAA"), resource("ep3AAAAAAAA")] }).to_string())
+    .create_async()
+    .await;
+  server
+    .mock("GET", "/youtube/v3/videos")
+    // The rows' current state and their snapshots.
+    .match_query(Matcher::Regex("part=snippet".into()))
+    .with_body(json!({ "items": [resource("ep1AAAAAAAA"), resource("ep3AAAAAAAA")] }).to_string())
+    .create_async()
+    .await;
+  let p = DEFAULT_PLACEHOLDER;
+  let expected = format!(
+    "Poprzedni odcinek:\n{p}\nZobacz też https://www.youtube.com/watch?v=pubAAAAAAAA&t=30s, {p} i {p}.\n{p}\nKanał: youtu.be/pubAAAAAAAA\n"
+  );
+  let writes = server
+    .mock("PUT", "/youtube/v3/videos")
+    .match_query(Matcher::Any)
+    .match_body(Matcher::PartialJson(json!({ "snippet": { "description": expected } })))
+    .with_body("{}")
+    .expect(2)
+    .create_async()
+    .await;
+  let plan = dir.join("plan.json");
+  fs::write(&plan, json!([{ "video_id": "ep1AAAAAAAA", "description": DESCRIPTION }, { "video_id": "ep3AAAAAAAA", "description": DESCRIPTION }]).to_string()).unwrap();
+  let report = bulk::apply_metadata_plan(dir, &plan, &PlanOptions { min_interval_ms: Some(0), ..Default::default() }).await.unwrap();
+  assert!(report.problems.is_empty(), "{:?}", report.problems);
+  assert_eq!(report.succeeded, 2, "{:?}", report.rows);
+  assert!(report.rows.iter().all(|r| r.status == RowStatus::Succeeded && r.video_links.len() == 6));
+  assert_eq!(report.rows[0].video_links[3].action, LinkAction::Replaced);
+  writes.assert_async().await;
+  lookups.assert_async().await;
+}
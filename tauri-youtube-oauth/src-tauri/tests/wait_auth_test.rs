@@ -0,0 +1,69 @@
+use std::{
+  fs,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tauri_youtube_oauth::{auth, error::Error, write_tokens_to_dir, Tokens};
+
+fn write_config(dir: &std::path::Path) {
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret"}"#).unwrap();
+}
+
+fn tokens(refresh_token: &str, created_at: u64) -> Tokens {
+  Tokens {
+    access_token: "a".into(),
+    refresh_token: refresh_token.into(),
+    expires_in: 3600,
+    created_at,
+    scope: "https://www.googleapis.com/auth/youtube.readonly".into(),
+    client_id: Some("id".into()),
+    issued_at: None,
+  }
+}
+
+fn now() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[tokio::test]
+async fn fails_at_once_without_client_credentials() {
+  let tmp = tempfile::tempdir().unwrap();
+  let started = std::time::Instant::now();
+  let err = auth::wait_until_authenticated(tmp.path(), Duration::from_secs(30)).await.unwrap_err();
+  assert!(matches!(err, Error::Config(_)), "{err:?}");
+  assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn times_out_while_no_login_happens() {
+  let tmp = tempfile::tempdir().unwrap();
+  write_config(tmp.path());
+  // Expired and not refreshable: not usable.
+  write_tokens_to_dir(tmp.path(), &tokens("", now() - 7200)).unwrap();
+  let err = auth::wait_until_authenticated(tmp.path(), Duration::from_millis(200)).await.unwrap_err();
+  assert!(matches!(err, Error::TimedOut { ref operation, .. } if operation == "wait_until_authenticated"), "{err:?}");
+}
+
+#[tokio::test]
+async fn resolves_when_tokens_are_written() {
+  let tmp = tempfile::tempdir().unwrap();
+  write_config(tmp.path());
+  let dir = tmp.path().to_path_buf();
+  let writer = tokio::spawn(async move {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    write_tokens_to_dir(&dir, &tokens("r", now())).unwrap();
+  });
+  let status = auth::wait_until_authenticated(tmp.path(), Duration::from_secs(5)).await.unwrap();
+  writer.await.unwrap();
+  assert!(status.authenticated && status.refreshable);
+  assert_eq!(status.scopes, ["https://www.googleapis.com/auth/youtube.readonly"]);
+}
+
+#[tokio::test]
+async fn expired_but_refreshable_tokens_count() {
+  let tmp = tempfile::tempdir().unwrap();
+  write_config(tmp.path());
+  write_tokens_to_dir(tmp.path(), &tokens("r", now() - 7200)).unwrap();
+  let status = auth::wait_until_authenticated(tmp.path(), Duration::from_secs(1)).await.unwrap();
+  assert!(status.authenticated);
+  assert!(status.expires_at.unwrap() < now());
+}
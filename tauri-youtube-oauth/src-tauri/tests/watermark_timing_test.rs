@@ -0,0 +1,34 @@
+use tauri_youtube_oauth::{channel, error::Error, OffsetType, WatermarkTiming};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+#[tokio::test]
+async fn timing_is_checked_before_the_image_is_read() {
+  use OffsetType::{OffsetFromEnd, OffsetFromStart};
+  let cases = [
+    (OffsetFromStart, 0, None, None),
+    (OffsetFromStart, 15_000, Some(5_000), None),
+    (OffsetFromStart, 12 * HOUR_MS, Some(12 * HOUR_MS), None),
+    (OffsetFromStart, 12 * HOUR_MS + 1, None, Some("przekracza maksymalną długość")),
+    (OffsetFromStart, 0, Some(0), Some("musi być dodatni")),
+    (OffsetFromStart, 0, Some(12 * HOUR_MS + 1), Some("przekracza 12 h")),
+    (OffsetFromEnd, 30_000, Some(30_000), None),
+    (OffsetFromEnd, 30_000, None, None),
+    (OffsetFromEnd, 30_000, Some(30_001), Some("nie może być dłuższy")),
+    (OffsetFromEnd, 0, None, Some("przesunięcie musi być dodatnie")),
+  ];
+  let tmp = tempfile::tempdir().unwrap();
+  // A missing image: timings that pass validation stop at reading it, before any request.
+  let image = tmp.path().join("missing.png");
+  for (offset_type, offset_ms, duration_ms, refusal) in cases {
+    let timing = WatermarkTiming { offset_type, offset_ms, duration_ms };
+    let message = match channel::set_watermark(tmp.path(), &image, &timing).await {
+      Err(Error::Validation(message)) => message,
+      other => panic!("{:?}: expected a validation error, got {:?}", timing, other),
+    };
+    match refusal {
+      Some(expected) => assert!(message.contains(expected), "{:?}: {}", timing, message),
+      None => assert!(message.contains("missing.png"), "{:?} should be accepted: {}", timing, message),
+    }
+  }
+}
@@ -0,0 +1,80 @@
+use mockito::Matcher;
+use std::{fs, net::SocketAddr, sync::Arc};
+use tauri_youtube_oauth::{
+  callback::{routes, CallbackState},
+  diagnostics,
+  error::{CommandError, Error},
+  format::Language,
+  guidance::{self, WorkspaceReason},
+  CheckStatus,
+};
+
+#[test]
+fn workspace_policies_are_told_apart() {
+  let admin = guidance::for_callback("access_denied", Some("admin_policy_enforced"), None, Language::En);
+  assert_eq!(guidance::workspace_reason(&admin), Some(WorkspaceReason::AdminPolicyEnforced));
+  assert!(admin.action.as_deref().unwrap().contains("admin"));
+  let internal = guidance::for_token_error("org_internal", None, Language::En);
+  assert_eq!(guidance::workspace_reason(&internal), Some(WorkspaceReason::OrgInternal));
+  assert_eq!(internal.title, "This OAuth client is restricted to another organization");
+  // Only the description says so.
+  let described = guidance::for_callback("access_denied", None, Some("This app is restricted to users within its organization."), Language::En);
+  assert_eq!(guidance::workspace_reason(&described), Some(WorkspaceReason::OrgInternal));
+  assert_eq!(guidance::workspace_reason(&guidance::for_callback("access_denied", None, None, Language::En)), None);
+
+  let err = Error::WorkspaceRestricted { reason: WorkspaceReason::OrgInternal, guidance: Box::new(internal) };
+  assert!(!err.retryable());
+  let value = serde_json::to_value(CommandError::from(err)).unwrap();
+  assert_eq!((&value["code"], &value["details"]["reason"], &value["details"]["code"]), (&"WORKSPACE_RESTRICTED".into(), &"org_internal".into(), &"ORG_INTERNAL".into()));
+}
+
+#[tokio::test]
+async fn restricted_logins_leave_no_token_state_and_show_in_diagnostics() {
+  let mut server = mockito::Server::new_async().await;
+  std::env::set_var("OAUTH_TOKEN_URL", format!("{}/token", server.url()));
+  std::env::set_var("OAUTH_TOKENINFO_URL", format!("{}/tokeninfo", server.url()));
+  std::env::set_var("YOUTUBE_API_URL", server.url());
+  let tmp = tempfile::tempdir().unwrap();
+  let dir = tmp.path();
+  fs::write(dir.join("oauth_config.json"), r#"{"client_id":"id","client_secret":"secret","language":"en","hosted_domain":"firma.pl"}"#).unwrap();
+  let filter = routes(dir.to_path_buf(), Arc::new(CallbackState::new("st".into())));
+  let peer: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+
+  let resp = warp::test::request()
+    .path("/callback?error=access_denied&error_subtype=admin_policy_enforced&state=st")
+    .remote_addr(peer)
+    .reply(&filter)
+    .await;
+  assert_eq!(resp.status().as_u16(), 400);
+  assert!(String::from_utf8(resp.body().to_vec()).unwrap().contains("Google Workspace"));
+
+  let token = server
+    .mock("POST", "/token")
+    .with_status(400)
+    .with_body(r#"{"error":"org_internal","error_description":"Access blocked"}"#)
+    .expect(1)
+    .create_async()
+    .await;
+  let resp = warp::test::request().path("/callback?code=abc&state=st").remote_addr(peer).reply(&filter).await;
+  token.assert_async().await;
+  assert_eq!(resp.status().as_u16(), 502);
+  assert!(String::from_utf8(resp.body().to_vec()).unwrap().contains("another organization"));
+  // No tokens yet: nothing written, cleared or backed off.
+  assert!(!dir.join("tokens.json").exists());
+  assert!(!dir.join("refresh_state.json").exists());
+
+  server.mock("HEAD", Matcher::Any).with_status(404).create_async().await;
+  server.mock("GET", "/tokeninfo").match_query(Matcher::Any).with_body(r#"{"expires_in":"1800","email":"me@gmail.com"}"#).create_async().await;
+  server.mock("GET", "/youtube/v3/channels").match_query(Matcher::Any).with_body(r#"{"pageInfo":{"totalResults":1},"items":[{"id":"UC1"}]}"#).create_async().await;
+  let report = diagnostics::run(dir).await;
+  let hd = |report: &diagnostics::DiagnosticsReport| report.checks.iter().find(|c| c.id == "hosted_domain").cloned().unwrap();
+  assert_eq!((hd(&report).status, hd(&report).code.as_str()), (CheckStatus::Warn, "SKIPPED"));
+
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+  let tokens = serde_json::json!({ "access_token": "a", "refresh_token": "r", "expires_in": 3600, "created_at": now, "scope": "https://www.googleapis.com/auth/youtube.readonly" });
+  fs::write(dir.join("tokens.json"), tokens.to_string()).unwrap();
+  let report = diagnostics::run(dir).await;
+  let check = hd(&report);
+  assert_eq!((check.status, check.code.as_str()), (CheckStatus::Fail, "HD_MISMATCH"));
+  assert!(check.message.contains("gmail.com") && check.message.contains("firma.pl"), "{}", check.message);
+}
@@ -0,0 +1,2095 @@
+// Generated by `cargo run --features bindings --bin generate-bindings` from the Rust sources; do not edit.
+
+/** Values of `CommandError.code`. */
+export type CommandErrorCode =
+  | "CONFIG_MISSING"
+  | "REAUTH_REQUIRED"
+  | "INSUFFICIENT_SCOPE"
+  | "NETWORK_ERROR"
+  | "FORBIDDEN"
+  | "API_ERROR"
+  | "QUOTA_EXCEEDED"
+  | "VALIDATION_FAILED"
+  | "STORAGE_ERROR"
+  | "DRY_RUN"
+  | "OFFLINE"
+  | "TIMED_OUT"
+  | "NO_CHANNEL"
+  | "REFRESH_BACKOFF"
+  | "OPEN_FAILED"
+  | "PASSPHRASE_REJECTED"
+  | "PREFLIGHT_FAILED"
+  | "CONSENT_FAILED"
+  | "WORKSPACE_RESTRICTED"
+  | "READ_ONLY_MODE"
+  | "SIDECAR_INVALID"
+  | "STORAGE_BUSY"
+  | "NOT_SUPPORTED_BY_API"
+  | "TOKEN_ENDPOINT_ERROR"
+  | "VIDEO_REJECTED"
+  | "TLS_INTERCEPTION_SUSPECTED";
+
+/** The login in progress, from `get_active_flow`. */
+export interface ActiveFlow {
+  redirect_uri: string;
+  port: number;
+  age_secs: number;
+  state: FlowState;
+}
+
+/** Payload of `app-closing`. */
+export interface AppClosing {
+  pending_uploads: number;
+}
+
+export interface AppConfig {
+  client_id: string;
+  client_secret: string;
+  /** Scopes requested on login; empty means read-only access. */
+  scopes?: string[];
+  /**
+   * Google Workspace domain sent as `hd` on login, so the consent screen
+   * offers that domain's accounts.
+   */
+  hosted_domain?: string | null;
+  /** Channel the channel-level commands act on; filled from `mine=true` on first use. */
+  channel_id?: string | null;
+  /** Daily Data API quota of the Cloud project, if raised above the default 10 000. */
+  daily_quota?: number | null;
+  /** Plan mutating API calls instead of sending them (see `set_dry_run`). */
+  dry_run?: boolean;
+  /** Refuse every call that would change the channel (see `set_read_only_mode`). */
+  read_only_mode?: boolean;
+  /**
+   * Lets the frontend send its own requests to Google APIs with the stored
+   * tokens (`youtube_raw_request`).
+   */
+  allow_raw_api?: boolean;
+  /**
+   * Quota units of raw requests by path, e.g. `"/youtube/v3/captions": 400`;
+   * other paths cost 1 to read and 50 to write.
+   */
+  raw_api_costs?: Record<string, number>;
+  /** Token endpoint request timeout (default 30 s). */
+  auth_timeout_secs?: number | null;
+  /** Data API request timeout (default 60 s). */
+  api_timeout_secs?: number | null;
+  /** Timeout of a single media upload request (default 600 s). */
+  upload_chunk_timeout_secs?: number | null;
+  /**
+   * Fixed upload chunk size in bytes, a multiple of 256 KiB; absent means
+   * adaptive (starts at 8 MiB, grows on fast chunks, shrinks on failures).
+   */
+  upload_chunk_bytes?: number | null;
+  /**
+   * Files modified more recently than this are refused unless forced
+   * (default 30 s), so an upload doesn't race the renderer.
+   */
+  upload_min_file_age_secs?: number | null;
+  /** How long a thumbnail YouTube isn't ready for yet is retried (default 600 s). */
+  thumbnail_retry_window_secs?: number | null;
+  /** Delete the uploaded video when the file changed during the upload. */
+  strict_integrity?: boolean;
+  /** Webhooks and local commands run after each upload finished processing. */
+  post_upload_hooks?: Hook[];
+  /** Title/description generated from the filename when a sidecar has no title. */
+  metadata_template?: MetadataTemplate | null;
+  /** Title/description checks run before each upload; absent means all rules, warning only. */
+  metadata_lint?: LintConfig | null;
+  /**
+   * Where chapters from a `chapters.json` sidecar go in the description;
+   * absent means the sidecar is ignored on upload.
+   */
+  chapters_placement?: ChapterPlacement | null;
+  /**
+   * Whether the OAuth consent screen is published; absent means unknown,
+   * which enables the 7-day Testing-mode warnings.
+   */
+  consent_screen_published?: boolean | null;
+  /** Argon2 hash of the app passphrase guarding secret-revealing commands. */
+  passphrase_hash?: string | null;
+  /** Language of the display strings from `format` (`pl` when absent). */
+  language?: Language | null;
+  /**
+   * Page the browser is sent to (302) after a successful login instead of
+   * the built-in one; gets `?profile=<name>` appended.
+   */
+  callback_success_redirect?: string | null;
+  /**
+   * CMS content owner the channel is managed by; when set every write is
+   * sent with `onBehalfOfContentOwner` and needs the youtubepartner scope.
+   */
+  content_owner_id?: string | null;
+  /** Channel of that owner the writes act on (`onBehalfOfContentOwnerChannel`). */
+  content_owner_channel_id?: string | null;
+  /**
+   * A deployed `.env` compared with this config after every setup change;
+   * `env-drift` reports when it is out of date.
+   */
+  watched_env_file?: string | null;
+  /**
+   * Program name or path the consent screen is opened with instead of the
+   * platform opener, e.g. the browser signed in to the channel's account.
+   */
+  preferred_browser?: string | null;
+  /**
+   * What happens to links to videos viewers can't open yet (private,
+   * scheduled later) in descriptions sent; absent means they aren't checked.
+   */
+  video_links?: VideoLinksConfig | null;
+  /** Desktop notifications about the upload queue (see `notifications`). */
+  desktop_notifications?: boolean;
+  /** Sidecar warnings (unknown keys) stop the upload like errors do. */
+  sidecar_warnings_block?: boolean;
+  /**
+   * IANA zone a `publish_at` without an offset is read in, e.g.
+   * `Europe/Warsaw`; UTC without it.
+   */
+  publish_timezone?: string | null;
+  /** Local hour a date-only `publish_at` goes public at (default 9). */
+  default_publish_hour?: number | null;
+  /**
+   * `defaultLanguage` given to a playlist or the channel that has none
+   * when localizations are added, e.g. `pl`; the app `language` when absent.
+   */
+  default_metadata_language?: string | null;
+  /**
+   * Loopback port of the OAuth redirect, 14321 when absent. Must be in the
+   * client's Authorized redirect URIs; a login in progress keeps its port.
+   */
+  redirect_port?: number | null;
+  /**
+   * Where a build with the `metrics-endpoint` feature serves Prometheus
+   * `/metrics`; nothing is served when absent.
+   */
+  metrics_endpoint?: MetricsEndpointConfig | null;
+  /**
+   * Where every emitted event is also sent (JSONL file, webhook), for runs
+   * without a window.
+   */
+  event_sinks?: EventSink[];
+  /**
+   * Headers added to every request to Google, e.g. `X-Request-Source` for
+   * an egress proxy; see [`http_headers::RESERVED`] for what they can't set.
+   */
+  custom_headers?: Record<string, string>;
+  /**
+   * Failed uploads before a queue entry stops and waits for an explicit
+   * retry (default 3).
+   */
+  queue_max_attempts?: number | null;
+  /**
+   * `false` keeps the access token in memory only: tokens.json holds the
+   * refresh token, scopes and timestamps, and each start refreshes first.
+   * Absent means `true`.
+   */
+  persist_access_token?: boolean | null;
+  /** What the library and upload sessions hash files with (default blake3). */
+  hash_algorithm?: HashAlgorithm | null;
+  /**
+   * Token endpoint and tokeninfo requests trust Google's root CAs from the
+   * bundled webpki-roots only, never the OS store, so they fail rather
+   * than go through a TLS-intercepting proxy.
+   */
+  pin_google_tls?: boolean;
+  /**
+   * A status.json snapshot of auth, queue, uploads, quota and the last
+   * error for orchestrators that poll; nothing is written when absent.
+   */
+  status_file?: StatusFileConfig | null;
+  /**
+   * A daily background check that the saved login still works, shown as
+   * `health` in the auth status. Absent means `true`.
+   */
+  auth_health_check?: boolean | null;
+}
+
+/** Which uploads [`archive_channel`] keeps; empty means all of them. */
+export interface ArchiveFilter {
+  /** Published at or after: RFC3339 timestamp or `YYYY-MM-DD` (UTC). */
+  from?: string | null;
+  /** Published at or before: RFC3339 timestamp or `YYYY-MM-DD` (end of that day, UTC). */
+  to?: string | null;
+  privacy?: Privacy | null;
+  /** Case-insensitive substring of the title. */
+  text?: string | null;
+}
+
+export type ArchivePhase =
+  | "listing"
+  | "archiving";
+
+/** Payload of `archive-progress`. */
+export interface ArchiveProgress {
+  phase: ArchivePhase;
+  done: number;
+  total?: number | null;
+}
+
+export interface ArchivedCaption {
+  track_id: string;
+  language: string;
+  /** The track's name; empty for most. */
+  name: string;
+  /** SubRip file, relative to the video's folder; absent when refused. */
+  file?: string | null;
+  /** Why YouTube refused the download (e.g. a track the channel doesn't own). */
+  error?: string | null;
+}
+
+/** One entry of `index.json`. */
+export interface ArchivedVideo {
+  video_id: string;
+  title: string;
+  published_at?: string | null;
+  privacy?: string | null;
+  /**
+   * SHA-256 of the resource without `statistics` and `etag`, which change
+   * on their own; the same checksum means there is nothing new to keep.
+   */
+  checksum: string;
+  archived_at: number;
+  /**
+   * Files by thumbnail size (`default`, `high`, `maxres`…), relative to the
+   * video's folder.
+   */
+  thumbnails: Record<string, string>;
+  captions: ArchivedCaption[];
+  /** Why no captions were fetched at all, e.g. a missing scope. */
+  captions_skipped?: string | null;
+  /** Thumbnails that couldn't be downloaded. */
+  problems?: string[];
+}
+
+export interface AuditEntry {
+  /** Unix seconds. */
+  timestamp: number;
+  profile: string;
+  action: string;
+  details: unknown;
+}
+
+/**
+ * The login's health as of the last check, in `get_auth_status` and the
+ * payload of `auth-health-changed`.
+ */
+export interface AuthHealth {
+  state: HealthState;
+  /** Unix seconds of the last check; a token refresh counts as one. */
+  checked_at: number;
+  /** When the background check runs next; absent while dead or turned off. */
+  next_check_at?: number | null;
+  /** Error code of a degraded or dead check. */
+  code?: string | null;
+  error?: string | null;
+  /** One line for a tray tooltip, in the app language. */
+  tooltip: string;
+}
+
+export interface AuthStatus {
+  /** Tokens exist and are either unexpired or refreshable. */
+  authenticated: boolean;
+  /** Unix seconds the access token expires at, when known. */
+  expires_at?: number | null;
+  refreshable: boolean;
+  /** Granted with the stored tokens. */
+  scopes: string[];
+  /** What a login asks for with the current config. */
+  requested_scopes: string[];
+  /**
+   * Requested but not covered by the granted scopes; non-empty means consent
+   * should be redone. Empty without tokens.
+   */
+  missing_scopes: string[];
+  /**
+   * The config gained scopes after these tokens were issued
+   * (`reconsent-required`); `reconsent` asks for them.
+   */
+  needs_reconsent: boolean;
+  /** Granted but no longer configured; nothing to do about them. */
+  unused_scopes: string[];
+  /** Unix seconds of the login behind the refresh token, when known. */
+  issued_at?: number | null;
+  /** Mutating calls are refused (`set_read_only_mode`). */
+  read_only_mode: boolean;
+  /** The last background check of the login; absent before the first one. */
+  health?: AuthHealth | null;
+}
+
+export interface Broadcast {
+  id: string;
+  title: string;
+  scheduled_start: string;
+  privacy: string;
+  life_cycle_status: string;
+  bound_stream_id?: string | null;
+}
+
+export interface BroadcastOptions {
+  description?: string | null;
+  scheduled_end?: string | null;
+  enable_auto_start?: boolean | null;
+  enable_auto_stop?: boolean | null;
+  enable_dvr?: boolean | null;
+  record_from_start?: boolean | null;
+  /** `normal`, `low` or `ultraLow`. */
+  latency_preference?: string | null;
+  made_for_kids?: boolean | null;
+}
+
+/** Target of `liveBroadcasts.transition`. */
+export type BroadcastStatus =
+  | "testing"
+  | "live"
+  | "complete";
+
+/**
+ * How the consent screen was opened; what `start_oauth` returns and the
+ * payload of `oauth-open-browser-failed`.
+ */
+export interface BrowserLaunch {
+  url: string;
+  /** The program started, or the one that would have been. */
+  program: string;
+  opened: boolean;
+  /** Why the browser didn't open. */
+  error?: string | null;
+  sandbox?: Sandbox | null;
+}
+
+export type BulkPrivacyResult =
+  | { result: "plan" } & PrivacyPlan
+  | { result: "applied" } & PrivacyReport;
+
+/** Payload of the `oauth-callback` event. */
+export interface CallbackOutcome {
+  ok: boolean;
+  error?: string | null;
+  /** What to tell the user when Google refused the login. */
+  guidance?: ConsentGuidance | null;
+}
+
+/** Payload of `get_capabilities` and the `capabilities-changed` event. */
+export interface Capabilities {
+  list_videos: Capability;
+  upload: Capability;
+  set_thumbnail: Capability;
+  manage_playlists: Capability;
+  comments: Capability;
+  live: Capability;
+  analytics: Capability;
+  /**
+   * `AuthStatus.needs_reconsent`: the missing scopes above come from a
+   * config change and `reconsent` restores them.
+   */
+  needs_reconsent: boolean;
+}
+
+export interface Capability {
+  available: boolean;
+  /** The first thing in the way, checked in declaration order of [`ReasonCode`]. */
+  reason_code?: ReasonCode | null;
+  /** Scopes to ask for with `request_scopes`; set with `INSUFFICIENT_SCOPE`. */
+  missing_scopes: string[];
+}
+
+export interface CaptureStatus {
+  enabled: boolean;
+  /** Seconds until capture turns itself off. */
+  expires_in_secs?: number | null;
+  entries: number;
+}
+
+export interface CategoryReport {
+  category: CleanupCategory;
+  /** Cache keys, session file paths or file paths relative to the config dir. */
+  items: string[];
+  bytes: number;
+}
+
+export type ChangeAction =
+  | "change"
+  | "unchanged"
+  | "needsScheduleOverride"
+  | "notFound";
+
+export interface Channel {
+  id: string;
+  title: string;
+  description: string;
+  custom_url?: string | null;
+  /** Keyed by size name: `default`, `medium`, `high`. */
+  thumbnails: Record<string, Thumbnail>;
+  statistics?: ChannelStatistics | null;
+  uploads_playlist_id?: string | null;
+}
+
+export interface ChannelArchive {
+  index_path: string;
+  /** Uploads the filter matched. */
+  matched: number;
+  archived: number;
+  /** Matched uploads already archived with the same metadata. */
+  skipped: number;
+}
+
+export interface ChannelSection {
+  id: string;
+  /**
+   * Wire name of the type; kept as a string so types added by the API later
+   * still list instead of failing the whole layout.
+   */
+  section_type: string;
+  title?: string | null;
+  position: number;
+  playlist_ids: string[];
+  channel_ids: string[];
+}
+
+export interface ChannelStatistics {
+  view_count?: number | null;
+  /** Absent when the owner hides it (`hidden_subscriber_count`). */
+  subscriber_count?: number | null;
+  hidden_subscriber_count: boolean;
+  video_count?: number | null;
+}
+
+export interface Chapter {
+  start_secs: number;
+  title: string;
+}
+
+/**
+ * The formatted block and what YouTube would object to; warnings don't stop
+ * the block from being written.
+ */
+export interface ChapterBlock {
+  text: string;
+  warnings: string[];
+}
+
+export type ChapterPlacement =
+  | "append"
+  | "prepend";
+
+export interface Check {
+  id: string;
+  status: CheckStatus;
+  /** `OK`, an error code from `error_codes`, or a check-specific code. */
+  code: string;
+  message: string;
+}
+
+export type CheckStatus =
+  | "pass"
+  | "warn"
+  | "fail";
+
+export interface CiToken {
+  access_token: string;
+  /** Unix seconds after which the token stops working. */
+  expires_at: number;
+  scopes: string[];
+}
+
+export interface ClassMetrics {
+  requests: number;
+  error_count: number;
+  /**
+   * Failures by reason: the API's `reason` for HTTP errors, the error code
+   * (`NETWORK_ERROR`, `TIMED_OUT`) for transport failures.
+   */
+  errors: Record<string, number>;
+  p50_ms?: number | null;
+  p95_ms?: number | null;
+  bytes_uploaded: number;
+  /** Chunk size the latest upload is using; uploads only. */
+  chunk_bytes?: number | null;
+  /** The slowest request of the latency window. */
+  slowest?: SlowRequest | null;
+}
+
+export type CleanupCategory =
+  | "cache"
+  | "uploadSessions"
+  | "logs"
+  | "snapshots"
+  | "tempFiles";
+
+export interface CleanupOptions {
+  /** List candidates without deleting anything. */
+  dry_run?: boolean;
+  /** Cache entries fetched longer ago go; 30 days when absent. */
+  cache_max_age_secs?: number | null;
+  /** Crash reports and logs older than this go; 30 days when absent. */
+  log_retention_days?: number | null;
+}
+
+export interface CleanupReport {
+  dry_run: boolean;
+  categories: CategoryReport[];
+  total_bytes: number;
+}
+
+export interface ClientMismatch {
+  kind: MismatchKind;
+  message: string;
+}
+
+/** A client as reported: masked, with its project. */
+export interface ClientRef {
+  client_id: string;
+  project_number?: string | null;
+}
+
+/**
+ * What commands reject with on the JS side: the `code` is the stable
+ * contract, `message` is for display only.
+ */
+export interface CommandError {
+  code: CommandErrorCode;
+  message: string;
+  details?: unknown | null;
+  retryable: boolean;
+}
+
+/** Payload of the `config-changed` event and result of `reload_config`. */
+export interface ConfigChange {
+  /**
+   * Top-level keys that were added, removed or changed. Names only, so
+   * secrets never leave the backend.
+   */
+  changed: string[];
+  /**
+   * Stored tokens were issued for a different client_id; the UI should
+   * suggest logging in again.
+   */
+  client_id_mismatch: boolean;
+}
+
+export interface Connectivity {
+  online: boolean;
+  /** Offline mode was switched on by hand (`set_offline_mode`). */
+  forced_offline: boolean;
+}
+
+export interface ConsentGuidance {
+  /** Stable id of the entry, `UNKNOWN` when the error isn't in the catalog. */
+  code: string;
+  source: GuidanceSource;
+  /** The values as Google sent them. */
+  error: string;
+  error_subtype?: string | null;
+  description?: string | null;
+  known: boolean;
+  title: string;
+  explanation: string;
+  action?: string | null;
+  /** Page to open for the fix, or Google's error reference for unknown codes. */
+  help_url: string;
+}
+
+/**
+ * What is known about the OAuth app's publishing status
+ * (`consent_screen_published` in the config).
+ */
+export type ConsentStatus =
+  | "unknown"
+  | "testing"
+  | "published";
+
+export interface CrashReport {
+  file_name: string;
+  path: string;
+  size_bytes: number;
+  /** Unix seconds, from the file's modification time. */
+  created_at: number;
+  /** First line of the panic message. */
+  summary: string;
+}
+
+/** Phase of a `playlist-progress` event. */
+export type DedupPhase =
+  | "listing"
+  | "removing";
+
+export interface DedupReport {
+  playlist_id: string;
+  scanned: number;
+  /** Extra copies, each a later position of a video listed earlier. */
+  duplicates: PlaylistItem[];
+  /** How many of them were deleted; 0 on a dry run. */
+  removed: number;
+  dry_run: boolean;
+}
+
+export interface DiagnosticsReport {
+  generated_at: number;
+  app_version: string;
+  checks: Check[];
+}
+
+/**
+ * What a mutating call would have done, returned instead of sending it while
+ * dry-run mode is on.
+ */
+export interface DryRunPlan {
+  method: string;
+  url: string;
+  body_summary: string;
+  estimated_quota: number;
+  /** When the video would go public, for calls that set `publishAt`. */
+  publish_at?: PublishTime | null;
+  /** Links to other videos in the description and what was done with them. */
+  video_links?: VideoLink[];
+}
+
+export interface Enrichment {
+  /** From the entry's metadata, or the sidecar and `metadata_template`. */
+  title?: string | null;
+  /** Why the metadata couldn't be loaded; the upload would fail the same way. */
+  metadata_error?: string | null;
+  lint: LintWarning[];
+  /** The video this exact content was uploaded as before, from the library. */
+  uploaded_as?: string | null;
+  /** Whether the metadata's thumbnail exists; absent without one. */
+  thumbnail_exists?: boolean | null;
+  /** The video file can't be read (moved or deleted since it was queued). */
+  file_error?: string | null;
+}
+
+/** Payload of `queue-entry-blocked`. */
+export interface EntryBlocked {
+  id: string;
+  file_path: string;
+  reason: PreflightReason;
+  message: string;
+}
+
+/** Payload of `queue-entry-enriched`. */
+export interface EntryEnriched {
+  id: string;
+  enrichment: Enrichment;
+}
+
+/** Payload of `queue-entry-failed` and `queue-entry-gave-up`. */
+export interface EntryFailed {
+  id: string;
+  file_path: string;
+  error: string;
+  code: string;
+  attempts: number;
+}
+
+/** Payload of `queue-entry-planned`. */
+export interface EntryPlanned {
+  id: string;
+  file_path: string;
+  /** The call the upload would have started with. */
+  plan: DryRunPlan;
+}
+
+/** Pending entries go by priority, then by their place in the queue. */
+export type EntryPriority =
+  | "low"
+  | "normal"
+  | "high";
+
+/** Payload of `queue-entry-retried`. */
+export interface EntryRetried {
+  id: string;
+  file_path: string;
+  /** What the entry was retried from: `failed` or `gave_up`. */
+  from: EntryStatus;
+  attempts: number;
+  /**
+   * The upload will continue the open resumable session instead of
+   * sending the file from the start.
+   */
+  resumes: boolean;
+}
+
+export type EntryStatus =
+  | "pending"
+  | "uploading"
+  | "done"
+  | "failed"
+  | "blocked"
+  | "gave_up";
+
+/** Key names only; no value leaves the app this way. */
+export interface EnvDrift {
+  path: string;
+  matching: string[];
+  /** In the file with another value than the app has. */
+  stale: string[];
+  /** Known to the app but not in the file. */
+  missing: string[];
+  /** Tokens in the file while the app has none. */
+  extra: string[];
+  in_sync: boolean;
+}
+
+/** One file written by [`generate_all`]. */
+export interface EnvFileResult {
+  profile: string;
+  path: string;
+  warning?: string | null;
+}
+
+export type EnvFormat =
+  | "dotEnv"
+  | "dotEnvNoComments"
+  | "json"
+  | "shellExports";
+
+/** What [`sync`] changed in the file. */
+export interface EnvSyncResult {
+  path: string;
+  /** Written with the app's value. */
+  updated: string[];
+  removed: string[];
+}
+
+export interface ErrorCode {
+  code: string;
+  description: string;
+  /** Default for the code; `CommandError::retryable` is authoritative per error. */
+  retryable: boolean;
+}
+
+export type EventSink =
+  | { type: "file"; path: string; events?: string[] }
+  | { type: "webhook"; url: string; secret?: string | null; events?: string[]; timeout_secs?: number | null };
+
+export type ExportFormat =
+  | "csv"
+  | "json";
+
+/** What `hash_file` returns. */
+export interface FileHash {
+  path: string;
+  algorithm: HashAlgorithm;
+  /** Lowercase hex. */
+  hash: string;
+  size: number;
+  /** Unix nanoseconds; absent where the filesystem has no mtime. */
+  modified_ns?: number | null;
+  /** Taken from the library entry without reading the file. */
+  from_library: boolean;
+  /** Where an interrupted hash was picked up; 0 for a fresh one. */
+  resumed_from: number;
+}
+
+/**
+ * Payload of `first-comment-posted`, after the sidecar's `first_comment`
+ * went up.
+ */
+export interface FirstCommentPosted {
+  video_id: string;
+  comment_id: string;
+  /** Always false while the API can't pin; `pin_hint` says what to do instead. */
+  pinned: boolean;
+  pin_hint?: string | null;
+}
+
+export type FlowState =
+  | "waiting"
+  | "exchanging"
+  | "failed";
+
+/** One upload that went ahead despite warnings, because they were forced. */
+export interface ForcedOverride {
+  /** Unix seconds. */
+  at: number;
+  /** The warnings that would have refused the upload, by code. */
+  codes: string[];
+}
+
+export type GuidanceSource =
+  | "callback"
+  | "token"
+  | "preflight";
+
+export type HashAlgorithm =
+  | "blake3"
+  | "sha256";
+
+/** Payload of `hash-progress`. */
+export interface HashProgress {
+  path: string;
+  algorithm: HashAlgorithm;
+  hashed_bytes: number;
+  total_bytes: number;
+}
+
+export type HealthState =
+  | "healthy"
+  | "degraded"
+  | "dead";
+
+export interface HistoryFilter {
+  /** RFC3339 timestamp or `YYYY-MM-DD` (start of that day, UTC). */
+  from?: string | null;
+  /** RFC3339 timestamp or `YYYY-MM-DD` (end of that day, UTC). */
+  to?: string | null;
+  status?: UploadStatus | null;
+  /** Case-insensitive substring of the title. */
+  text?: string | null;
+  offset: number;
+  limit?: number | null;
+}
+
+export interface HistoryPage {
+  /** Records matching the filter, before pagination. */
+  total: number;
+  offset: number;
+  /** Newest first. */
+  records: UploadRecord[];
+}
+
+export type Hook =
+  | { type: "webhook"; url: string; template?: string | null; timeout_secs?: number | null }
+  | { type: "command"; program: string; args: string[]; timeout_secs?: number | null };
+
+/** Payload of `post-upload-hook`, one per hook run. */
+export interface HookOutcome {
+  index: number;
+  kind: string;
+  video_id: string;
+  ok: boolean;
+  error?: string | null;
+  duration_ms: number;
+}
+
+/**
+ * One request/response pair. Request headers and bodies are never kept:
+ * they carry the bearer token, client_secret and refresh tokens.
+ */
+export interface HttpExchange {
+  at: number;
+  method: string;
+  url: string;
+  status?: number | null;
+  latency_ms: number;
+  response_body: string;
+  error?: string | null;
+  /** Above 1 when the request was replayed after a 401 and a token refresh. */
+  attempt: number;
+  /** The `X-Client-Trace-Id` sent, to find the request in proxy logs. */
+  trace_id?: string;
+}
+
+/** What a run will cost, returned instead of exporting without `confirm`. */
+export interface InventoryEstimate {
+  /** From the channel statistics; may lag behind recent uploads. */
+  video_count: number;
+  /** Rows already in the partial file from an interrupted run. */
+  already_exported: number;
+  quota_units: number;
+  quota_remaining: number;
+}
+
+export type InventoryPhase =
+  | "listing"
+  | "details";
+
+/** Payload of `inventory-progress`. */
+export interface InventoryProgress {
+  phase: InventoryPhase;
+  done: number;
+  total?: number | null;
+}
+
+export type InventoryResult =
+  | { status: "estimate" } & InventoryEstimate
+  | { status: "exported"; path: string; rows: number; resumed_rows: number }
+  | { status: "partial"; path: string; rows: number; next_cursor: string };
+
+export type Language =
+  | "pl"
+  | "en";
+
+export interface LibraryEntry {
+  video_id: string;
+  /** Where the file was uploaded from. */
+  original_path: string;
+  last_seen_path: string;
+  /** Unix seconds. */
+  uploaded_at: number;
+  /** What produced the key. */
+  algorithm: HashAlgorithm;
+  /**
+   * Size and mtime (unix nanoseconds) of the file at `last_seen_path` when
+   * it was last hashed; while they match, it isn't hashed again.
+   */
+  size?: number | null;
+  modified_ns?: number | null;
+  /**
+   * YouTube rejected, failed to process or removed the video: the file
+   * was uploaded, but isn't live.
+   */
+  rejection?: Rejection | null;
+}
+
+/** A library entry with its key, as the lookups return it. */
+export interface LibraryMatch extends LibraryEntry {
+  file_hash: string;
+  /** Whether a file is still at `last_seen_path`. */
+  present: boolean;
+}
+
+/** Payload of `library-progress`, after each file [`reconcile`] hashed. */
+export interface LibraryProgress {
+  dir: string;
+  done: number;
+  total: number;
+}
+
+export type LinkAction =
+  | "kept"
+  | "warned"
+  | "stripped"
+  | "replaced";
+
+/** What happens to a link viewers couldn't open. */
+export type LinkMode =
+  | "warn"
+  | "strip"
+  | "placeholder";
+
+/**
+ * Result of `resolve_video_links`: the description as it would be sent and
+ * every video link found in it.
+ */
+export interface LinkReport {
+  description: string;
+  links: VideoLink[];
+}
+
+export type LinkStatus =
+  | "public"
+  | "unlisted"
+  | "scheduled"
+  | "private"
+  | "missing";
+
+/** `metadata_lint` in the config. */
+export interface LintConfig {
+  mode: LintMode;
+  /** Rules not checked. */
+  disabled?: LintRule[];
+  /** Matched as whole words, ignoring case. */
+  banned_words?: string[];
+  /** External links allowed in the description (default 2). */
+  max_links?: number | null;
+}
+
+/** What a failed check means for the upload. */
+export type LintMode =
+  | "warn"
+  | "block";
+
+export type LintRule =
+  | "excessiveCaps"
+  | "tooManyLinks"
+  | "bannedWord"
+  | "missingDescription"
+  | "tagsNearLimit"
+  | "angleBrackets"
+  | "unavailableVideoLink";
+
+export interface LintWarning {
+  rule: LintRule;
+  /** `title`, `description` or `tags`. */
+  field: string;
+  message: string;
+}
+
+/**
+ * A live stream with its RTMP ingestion details. `stream_key` is a secret:
+ * anyone holding it can broadcast to the channel, so `Debug` redacts it.
+ */
+export interface LiveStream {
+  id: string;
+  title: string;
+  resolution: string;
+  frame_rate: string;
+  ingestion_address: string;
+  backup_ingestion_address: string;
+  stream_key: string;
+  stream_status: string;
+}
+
+/** One language's version of the title and description. */
+export interface Localization {
+  title: string;
+  description: string;
+}
+
+/** A playlist's or the channel's localizations as they are now. */
+export interface LocalizationSet {
+  id: string;
+  default_language?: string | null;
+  localizations: Record<string, Localization>;
+  /** This change gave the resource its default language. */
+  default_language_added: boolean;
+}
+
+/**
+ * What `exchange_code` returns: the saved tokens, plus the requested scopes
+ * the user unchecked on the consent screen.
+ */
+export interface LoginResult extends Tokens {
+  missing_scopes: string[];
+}
+
+/** Payload of `metadata-lint`, sent when an upload starts with warnings. */
+export interface MetadataLint {
+  file_path: string;
+  warnings: LintWarning[];
+  /** Whether the upload was refused over them. */
+  blocked: boolean;
+}
+
+/** What `preview_metadata` shows for a filename. */
+export interface MetadataPreview {
+  matched: boolean;
+  groups: Record<string, string>;
+  title?: string | null;
+  description?: string | null;
+}
+
+/**
+ * `metadata_template` in the config, e.g. pattern
+ * `^(?P<date>\d{4}-\d{2}-\d{2})_(?P<topic>[a-z-]+)_ep(?P<ep>\d+)$` with title
+ * `{{topic|title}} — Episode {{ep}}`. The pattern is matched against the
+ * file stem.
+ */
+export interface MetadataTemplate {
+  filename_pattern: string;
+  title: string;
+  description: string;
+}
+
+/**
+ * `metrics_endpoint` in the config: where the `metrics-endpoint` build
+ * serves `/metrics`.
+ */
+export interface MetricsEndpointConfig {
+  port: number;
+  /** Loopback unless set; anything else exposes the counters to the network. */
+  address?: string | null;
+  /** Keeps the settings but serves nothing. */
+  disabled: boolean;
+}
+
+export interface MetricsSnapshot extends Outcomes {
+  /** Unix seconds the counters started at. */
+  since: number;
+  auth: ClassMetrics;
+  data_api: ClassMetrics;
+  upload: ClassMetrics;
+}
+
+/** Payload of `migration-available`. */
+export interface MigrationAvailable {
+  /** The config dir in use, which has no credentials. */
+  target: string;
+  sources: MigrationSource[];
+}
+
+/** What `migrate_storage` did. Paths are relative to the config dir. */
+export interface MigrationReport {
+  from: string;
+  copied: string[];
+  /** Files that were replaced; the old versions are under `backup_dir`. */
+  backed_up: string[];
+  backup_dir?: string | null;
+}
+
+/** A location with credentials that could be copied in. */
+export interface MigrationSource {
+  path: string;
+  client_id: string;
+  has_tokens: boolean;
+  /** Unix seconds tokens.json (without one, the config) was last written. */
+  modified_at?: number | null;
+}
+
+export type MismatchKind =
+  | "project_changed"
+  | "client_changed"
+  | "tokens_for_other_client"
+  | "token_audience";
+
+export interface MyVideo {
+  video_id: string;
+  title: string;
+  published_at?: string | null;
+}
+
+/** What `test_notification` reports. */
+export interface NotificationTest {
+  /** `desktop_notifications` in the config; the test is sent either way. */
+  enabled: boolean;
+  /** The system took the notification. */
+  delivered: boolean;
+  error?: string | null;
+}
+
+/** What `describe_oauth_client` returns; also the payload of `oauth-client-mismatch`. */
+export interface OAuthClientDescription {
+  profile: string;
+  /** The client in oauth_config.json; absent without a config. */
+  configured?: ClientRef | null;
+  /** The client of the last login. */
+  login?: ClientRef | null;
+  account_email?: string | null;
+  login_at?: number | null;
+  /** The client tokens.json was issued to, when it says. */
+  tokens?: ClientRef | null;
+  /**
+   * The client tokeninfo names for the access token; only checked by
+   * `describe_oauth_client`, and only while the token is valid.
+   */
+  token_audience?: ClientRef | null;
+  /**
+   * Empty when everything belongs together. Only looked for while there
+   * are tokens: the next login records the association anew.
+   */
+  mismatches: ClientMismatch[];
+}
+
+export type OffsetType =
+  | "offsetFromStart"
+  | "offsetFromEnd";
+
+/** Whole operations rather than requests. */
+export interface Outcomes {
+  /**
+   * Uploads that ended with a video id; a shutdown that interrupts one
+   * counts as neither.
+   */
+  uploads_succeeded: number;
+  uploads_failed: number;
+  token_refreshes: number;
+  token_refresh_failures: number;
+}
+
+export interface PlanOptions {
+  /** Skip rows whose values already match YouTube, saving the 50-unit write. */
+  only_diff?: boolean;
+  /** Pause between writes; 1000 ms when absent. */
+  min_interval_ms?: number | null;
+}
+
+export interface PlanProblem {
+  /** 1-based position of the row in the plan. */
+  row: number;
+  video_id: string;
+  message: string;
+}
+
+/** Payload of `metadata-plan-progress`, sent after each row. */
+export interface PlanProgress {
+  row: number;
+  total: number;
+  video_id: string;
+  status: RowStatus;
+}
+
+/** With `problems` set nothing was applied. */
+export interface PlanReport {
+  problems: PlanProblem[];
+  rows: RowOutcome[];
+  succeeded: number;
+  failed: number;
+  unchanged: number;
+  planned: number;
+}
+
+/** Outcome of `add_to_playlist`. */
+export type PlaylistInsert =
+  | { result: "inserted"; item_id: string }
+  | { result: "alreadyInPlaylist"; item_id: string };
+
+export interface PlaylistItem {
+  /** Id of the playlist entry, not of the video. */
+  item_id: string;
+  video_id: string;
+  position: number;
+}
+
+/** One stretch of a playlist from [`list_playlist_items`]. */
+export interface PlaylistItemsPage {
+  items: PlaylistItem[];
+  /** Pass back as `cursor` for the rest; absent at the end of the playlist. */
+  next_cursor?: string | null;
+  total?: number | null;
+}
+
+/** Payload of `playlist-progress`; `total` is absent while the API hasn't said. */
+export interface PlaylistProgress {
+  playlist_id: string;
+  phase: DedupPhase;
+  done: number;
+  total?: number | null;
+}
+
+export interface PostedComment {
+  /** Id of the comment itself, as `pin_comment` takes it. */
+  comment_id: string;
+  thread_id: string;
+  video_id: string;
+  /**
+   * `published` for the owner's comments; `heldForReview` when the
+   * channel holds every comment.
+   */
+  moderation_status?: string | null;
+}
+
+export type PreflightReason =
+  | "fileMissing"
+  | "notReadable"
+  | "empty"
+  | "stillGrowing"
+  | "tooLarge"
+  | "tooLong"
+  | "lowDiskSpace";
+
+export type Privacy =
+  | "public"
+  | "unlisted"
+  | "private";
+
+export interface PrivacyChange {
+  video_id: string;
+  title?: string | null;
+  /** `privacyStatus` now; absent for videos that weren't found. */
+  current?: Privacy | null;
+  target: Privacy;
+  publish_at?: string | null;
+  /** The change clears `publish_at`. */
+  cancels_schedule: boolean;
+  action: ChangeAction;
+}
+
+export interface PrivacyOptions {
+  /** Allow changes that cancel a scheduled publication. */
+  cancel_schedules?: boolean;
+  /** Pause between writes; 1000 ms when absent. */
+  min_interval_ms?: number | null;
+}
+
+/** The first step: nothing was changed yet. */
+export interface PrivacyPlan {
+  /** Pass to the second call, with the same videos, privacy and options. */
+  confirm_token: string;
+  /** Unix seconds after which the token is refused. */
+  expires_at: number;
+  changes: PrivacyChange[];
+}
+
+/** Payload of `privacy-progress`, sent after each video. */
+export interface PrivacyProgress {
+  done: number;
+  total: number;
+  video_id: string;
+  status: RowStatus;
+}
+
+export interface PrivacyReport {
+  /** `row` is the position in `video_ids`, 1-based. */
+  rows: RowOutcome[];
+  succeeded: number;
+  failed: number;
+  unchanged: number;
+  planned: number;
+}
+
+/** Payload of `processing-progress`, from `processingDetails.processingProgress`. */
+export interface ProcessingProgress {
+  video_id: string;
+  parts_processed?: number | null;
+  parts_total?: number | null;
+  time_left_ms?: number | null;
+}
+
+/** Payload of `profile-switched`. */
+export interface ProfileSwitched {
+  profile: string;
+  previous: string;
+}
+
+/** Human-readable companions of an `upload-progress` event. */
+export interface ProgressLabels {
+  sent: string;
+  total: string;
+  speed: string;
+  eta: string;
+}
+
+/** One publish instant, as sent and as the user thinks of it. */
+export interface PublishTime {
+  /** RFC3339 in UTC, the form sent as `publishAt`. */
+  utc: string;
+  /** The same instant as RFC3339 in `timezone`. */
+  local: string;
+  /** IANA name of the zone `local` is in. */
+  timezone: string;
+}
+
+/** One upload of an entry, as kept in its `attempt_history`. */
+export interface QueueAttempt {
+  /** Unix seconds the attempt ended. */
+  at: number;
+  /** Error code it failed with; absent when the video went up. */
+  code?: string | null;
+}
+
+export interface QueueEntry {
+  id: string;
+  file_path: string;
+  /** Explicit metadata; absent means the sidecar and `metadata_template`. */
+  metadata?: VideoMetadata | null;
+  status: EntryStatus;
+  /** `normal` in queue files from older versions. */
+  priority: EntryPriority;
+  added_at: number;
+  /** Planned upload time (Unix seconds); absent means as soon as possible. */
+  scheduled_at?: number | null;
+  uploaded_at?: number | null;
+  video_id?: string | null;
+  error?: string | null;
+  /**
+   * Uploads tried since the entry was added or last brought back from
+   * `gave_up`.
+   */
+  attempts: number;
+  /** Every attempt, oldest first; a retry keeps it. */
+  attempt_history?: QueueAttempt[];
+  /** Warning codes the upload goes ahead despite (see [`overrides`]). */
+  force?: string[];
+  /** Each upload of the entry that `force` took past a warning. */
+  overrides?: ForcedOverride[];
+  /**
+   * Details for the queue view, once computed (see [`enrichment`]); never
+   * stored in the queue file.
+   */
+  enrichment?: Enrichment | null;
+}
+
+export interface QuotaStatus {
+  /** Quota day (`YYYY-MM-DD`, Pacific time, when Google resets the counter). */
+  day: string;
+  used: number;
+  limit: number;
+  remaining: number;
+}
+
+export interface RawResponse {
+  status: number;
+  headers: Record<string, string>;
+  /** The JSON body; a body that isn't JSON comes as a string, none as null. */
+  body: unknown;
+}
+
+/**
+ * Why a feature isn't available; the values match `CommandError.code`
+ * where the failing call would report the same.
+ */
+export type ReasonCode =
+  | "REAUTH_REQUIRED"
+  | "INSUFFICIENT_SCOPE"
+  | "CHANNEL_NOT_SELECTED"
+  | "READ_ONLY_MODE"
+  | "QUOTA_EXCEEDED";
+
+export interface ReconcileReport {
+  matched: ReconciledFile[];
+  /** Videos in the directory that were never uploaded. */
+  new: string[];
+  /** Uploads last seen under the directory that weren't found in it. */
+  missing_locally: LibraryMatch[];
+}
+
+/** A file [`reconcile`] found that was uploaded before. */
+export interface ReconciledFile {
+  path: string;
+  video_id: string;
+  file_hash: string;
+  /** The earlier `last_seen_path`, when the file was found elsewhere. */
+  moved_from?: string | null;
+}
+
+/** Contents of `reconsent.json` and payload of `reconsent-required`. */
+export interface ReconsentRequired {
+  /** Configured scopes the stored tokens weren't granted. */
+  missing: string[];
+  /** Unix seconds of the check that first found this set missing. */
+  since: number;
+}
+
+export interface Rejection {
+  kind: RejectionKind;
+  /**
+   * YouTube's reason, e.g. `duplicate`, `copyright`, `codec` or
+   * `transcodeFailed`; absent when it gives none.
+   */
+  reason?: string | null;
+  /** Unix seconds it was noticed. */
+  detected_at: number;
+}
+
+export type RejectionKind =
+  | "rejected"
+  | "failed"
+  | "removed";
+
+/** Payload of `remote-state-verified`. */
+export interface RemoteStateSummary {
+  checked: number;
+  live: number;
+  missing: number;
+  private: number;
+  rejected: number;
+  updated: number;
+}
+
+/** A recorded video that isn't live. */
+export interface RemoteVideo {
+  video_id: string;
+  /** The file it was uploaded from, when recorded. */
+  file_path?: string | null;
+  title?: string | null;
+  /** Absent for private videos. */
+  rejection?: Rejection | null;
+  /** `publishAt` of a private video, when it's scheduled. */
+  publish_at?: string | null;
+}
+
+export interface RowOutcome {
+  row: number;
+  video_id: string;
+  status: RowStatus;
+  error?: string | null;
+  plan?: DryRunPlan | null;
+  /** Links to other videos in the description written, with `video_links` on. */
+  video_links?: VideoLink[];
+}
+
+export type RowStatus =
+  | "succeeded"
+  | "failed"
+  | "unchanged"
+  | "planned";
+
+export type Sandbox =
+  | "flatpak"
+  | "snap";
+
+export interface ScheduleOptions {
+  max_per_day: number;
+  /** Allowed hours, `[window_start_hour, window_end_hour)` local time. */
+  window_start_hour: number;
+  window_end_hour: number;
+  /** IANA name, e.g. `Europe/Warsaw`. */
+  timezone: string;
+  /** First day to use; earlier days (and past slots today) are skipped. */
+  start_date?: string | null;
+  /**
+   * Also set `publishAt` from the slot, so videos go public on the same
+   * spread instead of right after processing.
+   */
+  schedule_publishing: boolean;
+}
+
+/** One planned upload. */
+export interface ScheduledEntry {
+  id: string;
+  /** Unix seconds. */
+  start_at: number;
+  /** The same moment as RFC3339 in the schedule's timezone. */
+  start_local: string;
+  /** `start_local` worded in the app language; filled in by `queue::plan`. */
+  start_label?: string | null;
+}
+
+/** Payload of `scopes-not-granted`. */
+export interface ScopesNotGranted {
+  missing: string[];
+}
+
+/** What a result points at; the API reports exactly one id per kind. */
+export type SearchId =
+  | { kind: "video"; video_id: string }
+  | { kind: "channel"; channel_id: string }
+  | { kind: "playlist"; playlist_id: string };
+
+export interface SearchOptions {
+  type?: SearchType | null;
+  order?: SearchOrder | null;
+  /** RFC3339 timestamp. */
+  published_after?: string | null;
+  channel_id?: string | null;
+  /** Total results across pages (default 25, at most 500). */
+  max_results?: number | null;
+  /** ISO 3166-1 alpha-2 code results should be viewable in. */
+  region_code?: string | null;
+}
+
+export type SearchOrder =
+  | "date"
+  | "rating"
+  | "relevance"
+  | "title"
+  | "videoCount"
+  | "viewCount";
+
+export interface SearchResult {
+  id: SearchId;
+  title: string;
+  description: string;
+  channel_id: string;
+  channel_title: string;
+  published_at: string;
+  thumbnail_url?: string | null;
+}
+
+export type SearchType =
+  | "video"
+  | "channel"
+  | "playlist";
+
+export type SectionType =
+  | "allPlaylists"
+  | "completedEvents"
+  | "liveEvents"
+  | "multipleChannels"
+  | "multiplePlaylists"
+  | "popularUploads"
+  | "recentUploads"
+  | "singlePlaylist"
+  | "subscriptions"
+  | "upcomingEvents";
+
+/** A file that exists but can't be used as is. */
+export interface SetupProblem {
+  file: string;
+  message: string;
+}
+
+/** Payload of `get_setup_state` and the `setup-state-changed` event. */
+export interface SetupState {
+  step: SetupStep;
+  /**
+   * What the current step still needs: config fields, `tokens`,
+   * `refresh_token`, scopes or `channel_id`.
+   */
+  missing: string[];
+  problem?: SetupProblem | null;
+}
+
+export type SetupStep =
+  | "needsClientCredentials"
+  | "needsConsent"
+  | "needsChannelSelection"
+  | "ready";
+
+export type Severity =
+  | "error"
+  | "warning";
+
+export interface SidecarDiagnostic {
+  severity: Severity;
+  /** The key concerned; absent for the file as a whole. */
+  field?: string | null;
+  message: string;
+  /** The known key an unknown one most likely meant. */
+  suggestion?: string | null;
+  /** 1-based position in the file, when known. */
+  line?: number | null;
+  column?: number | null;
+}
+
+/** A request worth finding in proxy logs by its `X-Client-Trace-Id`. */
+export interface SlowRequest {
+  trace_id: string;
+  latency_ms: number;
+}
+
+/** One entry of [`list`], without the resource. */
+export interface SnapshotInfo {
+  timestamp: number;
+  reason: SnapshotReason;
+  title?: string | null;
+  privacy?: string | null;
+}
+
+export type SnapshotReason =
+  | "update"
+  | "delete";
+
+/** `status_file` in the config. */
+export interface StatusFileConfig {
+  /** Relative to the config dir unless absolute; `status.json` when absent. */
+  path?: string | null;
+  /** Keeps the settings but writes nothing (and removes the file). */
+  disabled?: boolean;
+}
+
+export interface StorageLocation {
+  mode: StorageMode;
+  path: string;
+  /**
+   * Portable mode was requested but its dir isn't writable, so the
+   * standard location is in use.
+   */
+  portable_unavailable: boolean;
+}
+
+export type StorageMode =
+  | "standard"
+  | "portable"
+  | "override";
+
+export type StreamResolution =
+  | "240p"
+  | "360p"
+  | "480p"
+  | "720p"
+  | "1080p"
+  | "1440p"
+  | "2160p"
+  | "variable";
+
+/** Payload of `background-task-crashed`. */
+export interface TaskCrashed {
+  task: string;
+  message: string;
+  report?: string | null;
+  restarts: number;
+  /** Absent when the task was given up on. */
+  restart_in_ms?: number | null;
+}
+
+export interface Thumbnail {
+  url: string;
+  width?: number | null;
+  height?: number | null;
+}
+
+/** Payload of `thumbnail-retry`, sent before each wait. */
+export interface ThumbnailRetry {
+  video_id: string;
+  /** Attempts made so far. */
+  attempt: number;
+  retry_in_ms: number;
+  reason: string;
+}
+
+export interface TokenAge {
+  /**
+   * Unix seconds of the login that issued the refresh token; absent for
+   * tokens saved by older versions.
+   */
+  issued_at?: number | null;
+  age_secs?: number | null;
+  consent_screen: ConsentStatus;
+  /** When the refresh token dies if the app is in Testing mode. */
+  expires_if_testing_at?: number | null;
+  warning?: string | null;
+}
+
+/** Payload of `token-aging-warning`. */
+export interface TokenAgingWarning {
+  issued_at: number;
+  age_secs: number;
+  expires_if_testing_at: number;
+  message: string;
+}
+
+export interface Tokens {
+  access_token: string;
+  refresh_token: string;
+  expires_in: number;
+  created_at: number;
+  /** Space-separated scopes granted with these tokens, as returned by the token endpoint. */
+  scope: string;
+  /** OAuth client the tokens were issued to; absent in files from older versions. */
+  client_id?: string | null;
+  /**
+   * Unix seconds of the code exchange that issued the refresh token; kept
+   * across refreshes. Absent in files from older versions.
+   */
+  issued_at?: number | null;
+}
+
+/** What `get_upload_plan` shows. */
+export interface UploadPlan {
+  options?: ScheduleOptions | null;
+  entries: ScheduledEntry[];
+  limit_hit_on?: string | null;
+}
+
+/** Payload of `upload-processed`. */
+export interface UploadProcessed {
+  video_id: string;
+  /**
+   * `succeeded`, `failed`, `terminated`, `rejected`, `deleted`, or
+   * `unknown` when polling failed.
+   */
+  status: string;
+  /** Why the video isn't live, unless it `succeeded`. */
+  rejection?: Rejection | null;
+}
+
+/** Payload of the `upload-progress` event. */
+export interface UploadProgress {
+  bytes_sent: number;
+  total_bytes: number;
+  /** Speed over the last chunk only. */
+  bytes_per_sec: number;
+  smoothed_bytes_per_sec?: number | null;
+  eta_secs?: number | null;
+  /** Size of the chunks currently sent; 0 when not reported by the uploader. */
+  chunk_size: number;
+  /** The same numbers worded in the app language. */
+  labels?: ProgressLabels | null;
+  /** When the video goes public, if it is scheduled. */
+  publish_at?: PublishTime | null;
+}
+
+export interface UploadRecord {
+  /** Unix seconds when the upload finished. */
+  timestamp: number;
+  profile: string;
+  file_path: string;
+  file_hash: string;
+  video_id?: string | null;
+  title: string;
+  privacy: string;
+  duration_secs: number;
+  bytes: number;
+  status: UploadStatus;
+  error?: string | null;
+  /** The file changed while it was uploading; the video may be truncated. */
+  suspect?: boolean;
+  rejection?: Rejection | null;
+  /** Warnings the upload was forced past. */
+  forced?: ForcedOverride | null;
+}
+
+export type UploadStatus =
+  | "completed"
+  | "failed"
+  | "rejected";
+
+/** Payload of `upload-suspect`. */
+export interface UploadSuspect {
+  file_path: string;
+  video_id: string;
+  reason: string;
+  /** `strict_integrity` removed the video from YouTube. */
+  deleted: boolean;
+}
+
+export interface UploadedVideo {
+  video_id: string;
+  url: string;
+  bytes: number;
+  /**
+   * Hash of the file as it was when the upload started, in the configured
+   * `hash_algorithm`.
+   */
+  file_hash: string;
+  /** Why the upload may not match the file (it changed while uploading). */
+  suspect?: string | null;
+  /** The warnings `force` took it past. */
+  forced?: ForcedOverride | null;
+}
+
+export interface VerifyOptions {
+  /** Only videos uploaded from then on: RFC3339 or `YYYY-MM-DD` (UTC). */
+  since?: string | null;
+  /** Report only; local records stay as they are. */
+  dry_run?: boolean;
+}
+
+/** What `verify_remote_state` returns. */
+export interface VerifyReport {
+  /** Distinct video ids in the local records. */
+  checked: number;
+  /** Public or unlisted, and processed or processing. */
+  live: number;
+  /** Removed on YouTube or unknown to it. */
+  missing: RemoteVideo[];
+  /** Private on YouTube, e.g. set so in Studio or scheduled. */
+  private: RemoteVideo[];
+  /** Rejected or failed processing. */
+  rejected: RemoteVideo[];
+  /** Local records changed; 0 with `dry_run`. */
+  updated: number;
+}
+
+export interface VideoArchive extends ArchivedVideo {
+  /** Already archived with the same metadata; nothing was downloaded. */
+  skipped: boolean;
+  /** The video's folder. */
+  path: string;
+}
+
+export interface VideoLink {
+  video_id: string;
+  /** The link as written in the description. */
+  url: string;
+  status: LinkStatus;
+  /** When a scheduled video goes public. */
+  publish_at?: string | null;
+  /** Whether viewers can open it once this description is public. */
+  viewable: boolean;
+  action: LinkAction;
+}
+
+/** `video_links` in the config; absent means descriptions aren't checked. */
+export interface VideoLinksConfig {
+  mode: LinkMode;
+  placeholder?: string | null;
+}
+
+export interface VideoMetadata {
+  title: string;
+  description: string;
+  tags: string[];
+  category_id?: string | null;
+  privacy: Privacy;
+  /**
+   * When the video goes public; it stays private until then. RFC3339, or
+   * a local time or date as `publish_time` reads them.
+   */
+  publish_at?: string | null;
+  /** Image set as the thumbnail once the upload finished (see `thumbnails`). */
+  thumbnail?: string | null;
+  /**
+   * Posted as the first comment once the upload finished, and taken as far
+   * towards pinned as the API allows (see `comments`).
+   */
+  first_comment?: string | null;
+}
+
+/** One stretch of the channel's uploads from [`list_my_videos`]. */
+export interface VideoPage {
+  videos: MyVideo[];
+  /** Pass back as `cursor` for the rest; absent after the oldest upload. */
+  next_cursor?: string | null;
+  total?: number | null;
+}
+
+/**
+ * Payload of `warning`: something went wrong where no caller waits for the
+ * answer — a sink, a background stage, a cleanup run. See [`Runtime::warn`].
+ */
+export interface Warning {
+  /** What reported it, e.g. `event_sinks` or `cleanup`. */
+  source: string;
+  message: string;
+}
+
+/**
+ * When the watermark shows: `offset_ms` from the start or end of each video,
+ * for `duration_ms` (until the end of the video when absent).
+ */
+export interface WatermarkTiming {
+  offset_type: OffsetType;
+  offset_ms: number;
+  duration_ms?: number | null;
+}
+
+/** Which Workspace policy refused the login. */
+export type WorkspaceReason =
+  | "admin_policy_enforced"
+  | "org_internal";
+
+/** Payload of `workspace-restricted`. */
+export interface WorkspaceRestriction {
+  reason: WorkspaceReason;
+  guidance: ConsentGuidance;
+}
+
+/** Arguments and result of every command, by name. */
+export interface Commands {
+  apply_metadata_plan: { args: { path: string; options?: PlanOptions | null }; result: PlanReport };
+  archive_channel: { args: { dir: string; filter?: ArchiveFilter | null }; result: ChannelArchive };
+  archive_video: { args: { videoId: string; dir: string }; result: VideoArchive };
+  bulk_set_privacy: { args: { videoIds: string[]; privacy: Privacy; confirmToken?: string | null; options?: PrivacyOptions | null }; result: BulkPrivacyResult };
+  cancel_oauth: { args: {}; result: boolean };
+  check_auth_health: { args: {}; result: AuthHealth | null };
+  check_env_file: { args: { path?: string | null }; result: EnvDrift };
+  cleanup_storage: { args: { options?: CleanupOptions | null }; result: CleanupReport };
+  clear_app_passphrase: { args: { current: string }; result: null };
+  confirm_exit: { args: {}; result: null };
+  deduplicate_playlist: { args: { playlistId: string; dryRun?: boolean | null }; result: DedupReport };
+  delete_profile: { args: { name: string }; result: string[] };
+  describe_oauth_client: { args: {}; result: OAuthClientDescription };
+  error_codes: { args: {}; result: ErrorCode[] };
+  exchange_code: { args: { code: string }; result: LoginResult };
+  export_bundle: { args: { path: string; passphrase?: string | null; includeSnapshots?: boolean | null }; result: null };
+  export_channel_inventory: { args: { path: string; format: ExportFormat; confirm?: boolean | null; cursor?: string | null; pageBudget?: number | null }; result: InventoryResult };
+  export_diagnostics: { args: { path: string }; result: DiagnosticsReport };
+  export_http_capture: { args: { path: string }; result: number };
+  export_upload_history: { args: { path: string; format: ExportFormat }; result: number };
+  find_local_file_for_video: { args: { videoId: string }; result: LibraryMatch | null };
+  format_bytes: { args: { bytes: number }; result: string };
+  format_duration: { args: { secs: number }; result: string };
+  format_eta: { args: { secs?: number | null }; result: string };
+  format_timestamp: { args: { value: string }; result: string };
+  generate_all_envs: { args: { dir: string; passphrase?: string | null }; result: EnvFileResult[] };
+  generate_env: { args: { includeTokens?: boolean | null; passphrase?: string | null; format?: EnvFormat | null }; result: string };
+  generate_env_for_profile: { args: { profile: string; includeTokens?: boolean | null; passphrase?: string | null; format?: EnvFormat | null }; result: string };
+  get_active_flow: { args: {}; result: ActiveFlow | null };
+  get_active_profile: { args: {}; result: string };
+  get_audit_log: { args: {}; result: AuditEntry[] };
+  get_auth_status: { args: {}; result: AuthStatus };
+  get_capabilities: { args: {}; result: Capabilities };
+  get_config: { args: { includeSecret?: boolean | null; passphrase?: string | null }; result: AppConfig };
+  get_connectivity: { args: {}; result: Connectivity };
+  get_http_capture: { args: {}; result: HttpExchange[] };
+  get_metrics: { args: { reset?: boolean | null }; result: MetricsSnapshot };
+  get_quota_usage: { args: {}; result: QuotaStatus };
+  get_setup_state: { args: {}; result: SetupState };
+  get_storage_location: { args: {}; result: StorageLocation };
+  get_token_age: { args: {}; result: TokenAge };
+  get_upload_plan: { args: {}; result: UploadPlan };
+  hash_file: { args: { path: string }; result: FileHash };
+  import_adc: { args: { path?: string | null }; result: AuthStatus };
+  lint_metadata: { args: { metadata: VideoMetadata }; result: LintWarning[] };
+  list_crash_reports: { args: {}; result: CrashReport[] };
+  list_overridden_uploads: { args: { since?: string | null }; result: UploadRecord[] };
+  list_profiles: { args: {}; result: string[] };
+  list_snapshots: { args: { videoId: string }; result: SnapshotInfo[] };
+  load_video_metadata: { args: { filePath: string }; result: VideoMetadata };
+  locate_video_for_file: { args: { path: string }; result: LibraryMatch | null };
+  migrate_storage: { args: { fromPath: string }; result: MigrationReport };
+  mint_ci_token: { args: { passphrase?: string | null }; result: CiToken };
+  move_queue_entry: { args: { id: string; newIndex: number }; result: QueueEntry[] };
+  open_config_dir: { args: {}; result: string };
+  preview_metadata: { args: { filename: string }; result: MetadataPreview };
+  query_upload_history: { args: { filter: HistoryFilter }; result: HistoryPage };
+  queue_add: { args: { filePath: string; metadata?: VideoMetadata | null; force?: string[] | null }; result: QueueEntry };
+  queue_list: { args: {}; result: QueueEntry[] };
+  queue_remove: { args: { id: string }; result: null };
+  reconcile_library: { args: { dir: string }; result: ReconcileReport };
+  reconsent: { args: {}; result: string[] };
+  refresh_tokens: { args: {}; result: Tokens };
+  refresh_tokens_now: { args: {}; result: Tokens };
+  reload_config: { args: {}; result: ConfigChange };
+  request_scopes: { args: { extra: string[] }; result: null };
+  resolve_publish_time: { args: { value: string }; result: PublishTime };
+  resolve_video_links: { args: { description: string }; result: LinkReport };
+  restore_snapshot: { args: { videoId: string; timestamp: number }; result: unknown };
+  retry_all_failed: { args: { codes?: string[] | null }; result: QueueEntry[] };
+  retry_queue_entry: { args: { id: string }; result: QueueEntry };
+  revalidate_blocked: { args: {}; result: QueueEntry[] };
+  rotate_client_id: { args: { clientId: string; clientSecret: string; confirm?: boolean | null }; result: AuthStatus };
+  rotate_client_secret: { args: { newSecret: string }; result: AuthStatus };
+  run_diagnostics: { args: {}; result: DiagnosticsReport };
+  set_app_passphrase: { args: { passphrase: string; current?: string | null }; result: null };
+  set_dry_run: { args: { enabled: boolean }; result: boolean };
+  set_http_capture: { args: { enabled: boolean }; result: CaptureStatus };
+  set_offline_mode: { args: { enabled: boolean }; result: Connectivity };
+  set_queue_priority: { args: { id: string; priority: EntryPriority }; result: QueueEntry };
+  set_read_only_mode: { args: { enabled: boolean }; result: boolean };
+  set_redirect_port: { args: { port?: number | null }; result: number };
+  set_upload_schedule: { args: { options?: ScheduleOptions | null }; result: UploadPlan };
+  start_oauth: { args: {}; result: BrowserLaunch };
+  start_queue: { args: {}; result: null };
+  switch_profile: { args: { name: string }; result: string };
+  sync_env_file: { args: { path?: string | null; passphrase?: string | null }; result: EnvSyncResult };
+  test_hook: { args: { index: number }; result: HookOutcome };
+  test_notification: { args: {}; result: NotificationTest };
+  validate_sidecar: { args: { path: string }; result: SidecarDiagnostic[] };
+  verify_remote_state: { args: { options: VerifyOptions }; result: VerifyReport };
+  wait_until_authenticated: { args: { timeoutSecs: number }; result: AuthStatus };
+  write_ci_env: { args: { path: string; passphrase?: string | null }; result: CiToken };
+  youtube_add_to_playlist: { args: { playlistId: string; videoId: string }; result: PlaylistInsert };
+  youtube_bind_stream: { args: { broadcastId: string; streamId: string }; result: Broadcast };
+  youtube_create_broadcast: { args: { title: string; scheduledStart: string; privacy: Privacy; options: BroadcastOptions }; result: Broadcast };
+  youtube_create_channel_section: { args: { sectionType: SectionType; title?: string | null; playlistIds: string[]; position?: number | null }; result: ChannelSection[] };
+  youtube_create_stream: { args: { title: string; resolution: StreamResolution }; result: LiveStream };
+  youtube_delete_channel_section: { args: { id: string }; result: ChannelSection[] };
+  youtube_get_channels: { args: {}; result: Channel[] };
+  youtube_heart_comment: { args: { commentId: string }; result: null };
+  youtube_list_channel_localizations: { args: {}; result: LocalizationSet };
+  youtube_list_channel_sections: { args: {}; result: ChannelSection[] };
+  youtube_list_channels: { args: {}; result: unknown };
+  youtube_list_my_videos: { args: { cursor?: string | null; pageBudget?: number | null }; result: VideoPage };
+  youtube_list_playlist_items: { args: { playlistId: string; cursor?: string | null; pageBudget?: number | null }; result: PlaylistItemsPage };
+  youtube_list_playlist_localizations: { args: { playlistId: string }; result: LocalizationSet };
+  youtube_list_streams: { args: {}; result: LiveStream[] };
+  youtube_pin_comment: { args: { commentId: string }; result: null };
+  youtube_post_comment: { args: { videoId: string; text: string }; result: PostedComment };
+  youtube_raw_request: { args: { method: string; path: string; query?: Record<string, string> | null; body?: unknown | null }; result: RawResponse };
+  youtube_search: { args: { query: string; options: SearchOptions }; result: SearchResult[] };
+  youtube_select_channel: { args: { channelId: string }; result: null };
+  youtube_set_channel_description: { args: { text: string }; result: unknown };
+  youtube_set_channel_localizations: { args: { changes: Record<string, Localization | null> }; result: LocalizationSet };
+  youtube_set_chapters: { args: { videoId: string; chapters: Chapter[] }; result: ChapterBlock };
+  youtube_set_playlist_localizations: { args: { playlistId: string; changes: Record<string, Localization | null> }; result: LocalizationSet };
+  youtube_set_thumbnail: { args: { videoId: string; imagePath: string }; result: Record<string, Thumbnail> };
+  youtube_set_watermark: { args: { imagePath: string; timing: WatermarkTiming }; result: null };
+  youtube_transition_broadcast: { args: { id: string; status: BroadcastStatus }; result: Broadcast };
+  youtube_unset_watermark: { args: {}; result: null };
+  youtube_update_channel_section: { args: { id: string; sectionType: SectionType; title?: string | null; playlistIds: string[]; position?: number | null }; result: ChannelSection[] };
+  youtube_upload_banner: { args: { imagePath: string }; result: string };
+  youtube_upload_video: { args: { filePath: string; metadata?: VideoMetadata | null; force?: string[] | null }; result: UploadedVideo };
+}
+
+/** Payload of every event, by name. */
+export interface Events {
+  "app-closing": AppClosing;
+  "archive-progress": ArchiveProgress;
+  "auth-health-changed": AuthHealth;
+  "background-task-crashed": TaskCrashed;
+  "capabilities-changed": Capabilities;
+  "config-changed": ConfigChange;
+  "connectivity-changed": Connectivity;
+  "dry-run-plan": DryRunPlan;
+  "env-drift": EnvDrift;
+  "first-comment-posted": FirstCommentPosted;
+  "hash-progress": HashProgress;
+  "inventory-progress": InventoryProgress;
+  "library-progress": LibraryProgress;
+  "metadata-lint": MetadataLint;
+  "metadata-plan-progress": PlanProgress;
+  "migration-available": MigrationAvailable;
+  "oauth-callback": CallbackOutcome;
+  "oauth-client-mismatch": OAuthClientDescription;
+  "oauth-open-browser-failed": BrowserLaunch;
+  "playlist-progress": PlaylistProgress;
+  "post-upload-hook": HookOutcome;
+  "privacy-progress": PrivacyProgress;
+  "processing-progress": ProcessingProgress;
+  "profile-switched": ProfileSwitched;
+  "queue-changed": QueueEntry[];
+  "queue-entry-blocked": EntryBlocked;
+  "queue-entry-enriched": EntryEnriched;
+  "queue-entry-failed": EntryFailed;
+  "queue-entry-gave-up": EntryFailed;
+  "queue-entry-planned": EntryPlanned;
+  "queue-entry-retried": EntryRetried;
+  "read-only-mode-changed": boolean;
+  "reauth-required": string;
+  "reconsent-required": ReconsentRequired;
+  "remote-state-verified": RemoteStateSummary;
+  "scopes-not-granted": ScopesNotGranted;
+  "setup-state-changed": SetupState;
+  "storage-cleaned": CleanupReport;
+  "thumbnail-retry": ThumbnailRetry;
+  "token-aging-warning": TokenAgingWarning;
+  "upload-processed": UploadProcessed;
+  "upload-progress": UploadProgress;
+  "upload-suspect": UploadSuspect;
+  "warning": Warning;
+  "workspace-restricted": WorkspaceRestriction;
+}